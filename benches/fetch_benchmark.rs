@@ -2,8 +2,9 @@
 //! 
 //! Run with: cargo bench
 
-use criterion::{black_box, criterion_group, criterion_main, Criterion, BenchmarkId};
+use criterion::{criterion_group, criterion_main, Criterion, BenchmarkId};
 use oanda_connector::{OandaClient, OandaConfig, Granularity};
+use std::hint::black_box;
 use tokio::runtime::Runtime;
 
 fn create_client() -> OandaClient {
@@ -69,7 +70,7 @@ fn benchmark_rate_limiter(c: &mut Criterion) {
     let rt = Runtime::new().unwrap();
     
     c.bench_function("rate_limiter_acquire", |b| {
-        let limiter = RateLimiter::new(100);
+        let limiter = RateLimiter::new(100, 100);
         
         b.to_async(&rt).iter(|| async {
             let permit = limiter.acquire().await;
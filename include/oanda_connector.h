@@ -0,0 +1,60 @@
+/*
+ * C interface to oanda-connector, built with the `ffi` feature:
+ *
+ *     cargo rustc --release --features ffi --lib --crate-type cdylib
+ *
+ * Strings passed in are NUL-terminated UTF-8 and only borrowed for the
+ * call. Strings returned are JSON owned by the caller; release them with
+ * oanda_string_free. A NULL result means the call failed, and
+ * oanda_last_error describes why.
+ */
+
+#ifndef OANDA_CONNECTOR_H
+#define OANDA_CONNECTOR_H
+
+#include <stdbool.h>
+#include <stddef.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct OandaClient OandaClient;
+
+/* Client for an account; practice selects the demo environment */
+OandaClient *oanda_client_new(const char *api_key, const char *account_id, bool practice);
+
+/* Client configured from OANDA_* environment variables */
+OandaClient *oanda_client_from_env(void);
+
+void oanda_client_free(OandaClient *client);
+
+void oanda_string_free(char *value);
+
+/* Latest failure on this thread, or NULL; valid until the next call */
+const char *oanda_last_error(void);
+
+char *oanda_get_price(const OandaClient *client, const char *instrument);
+
+/* granularity such as "M5" or "H1" */
+char *oanda_get_candles(const OandaClient *client, const char *instrument,
+                        const char *granularity, size_t count);
+
+char *oanda_get_account_summary(const OandaClient *client);
+
+char *oanda_get_open_positions(const OandaClient *client);
+
+char *oanda_get_pending_orders(const OandaClient *client);
+
+/* order_json uses the API's field names, e.g.
+ * {"type":"MARKET","instrument":"EUR_USD","units":"1000"} */
+char *oanda_place_order(const OandaClient *client, const char *order_json);
+
+/* order is an OANDA order ID, or a client ID prefixed with '@' */
+char *oanda_cancel_order(const OandaClient *client, const char *order);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* OANDA_CONNECTOR_H */
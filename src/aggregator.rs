@@ -0,0 +1,171 @@
+//! Candles built from live ticks
+//!
+//! [`CandleAggregator`] turns a tick stream into mid-price candles at any
+//! [`Granularity`], aligned the way OANDA aligns its own candles. A candle
+//! is emitted, marked complete, once a tick arrives for a later period or
+//! [`flush`](CandleAggregator::flush) is called after the period has
+//! ended. Volume counts ticks, like OANDA's tick volume.
+//!
+//! ```
+//! use oanda_connector::aggregator::CandleAggregator;
+//! use oanda_connector::Granularity;
+//! # use oanda_connector::Tick;
+//! # fn run(ticks: Vec<Tick>) {
+//! let mut m5 = CandleAggregator::new(Granularity::M5);
+//! for tick in &ticks {
+//!     if let Some(candle) = m5.on_tick(tick) {
+//!         println!("{} closed at {}", candle.timestamp, candle.close);
+//!     }
+//! }
+//! # }
+//! ```
+
+use crate::models::{Candle, Granularity, Tick};
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+
+/// Builds candles per instrument from ticks
+#[derive(Debug, Clone)]
+pub struct CandleAggregator {
+    granularity: Granularity,
+    current: HashMap<String, Candle>,
+}
+
+impl CandleAggregator {
+    pub fn new(granularity: Granularity) -> Self {
+        Self {
+            granularity,
+            current: HashMap::new(),
+        }
+    }
+
+    pub fn granularity(&self) -> Granularity {
+        self.granularity
+    }
+
+    /// Add a tick; returns the instrument's previous candle if it closed
+    ///
+    /// Non-tradeable ticks and ticks older than the current candle are
+    /// ignored.
+    pub fn on_tick(&mut self, tick: &Tick) -> Option<Candle> {
+        if !tick.tradeable {
+            return None;
+        }
+        let start = self.granularity.align_down(tick.timestamp);
+        let price = tick.mid();
+        match self.current.get_mut(&tick.instrument) {
+            Some(candle) if candle.timestamp == start => {
+                candle.high = candle.high.max(price);
+                candle.low = candle.low.min(price);
+                candle.close = price;
+                candle.volume += 1;
+                None
+            }
+            Some(candle) if candle.timestamp > start => None,
+            _ => {
+                let candle = Candle {
+                    instrument: tick.instrument.clone(),
+                    timestamp: start,
+                    open: price,
+                    high: price,
+                    low: price,
+                    close: price,
+                    volume: 1,
+                    complete: false,
+                };
+                self.current
+                    .insert(tick.instrument.clone(), candle)
+                    .map(|mut closed| {
+                        closed.complete = true;
+                        closed
+                    })
+            }
+        }
+    }
+
+    /// Close and return every candle whose period ended by `now`
+    pub fn flush(&mut self, now: DateTime<Utc>) -> Vec<Candle> {
+        let granularity = self.granularity;
+        let ended: Vec<String> = self
+            .current
+            .iter()
+            .filter(|(_, candle)| granularity.next_boundary(candle.timestamp) <= now)
+            .map(|(instrument, _)| instrument.clone())
+            .collect();
+        let mut closed: Vec<Candle> = ended
+            .iter()
+            .filter_map(|instrument| self.current.remove(instrument))
+            .map(|mut candle| {
+                candle.complete = true;
+                candle
+            })
+            .collect();
+        closed.sort_by(|a, b| a.instrument.cmp(&b.instrument));
+        closed
+    }
+
+    /// Candle still being built for an instrument
+    pub fn current(&self, instrument: &str) -> Option<&Candle> {
+        self.current.get(instrument)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::PriceStatus;
+    use rust_decimal::Decimal;
+    use rust_decimal_macros::dec;
+
+    fn tick(seconds: i64, mid: Decimal) -> Tick {
+        Tick {
+            instrument: "EUR_USD".to_string(),
+            // 2023-11-14 22:13:20 UTC
+            timestamp: DateTime::from_timestamp(1_700_000_000 + seconds, 0).unwrap(),
+            bid: mid - dec!(0.0001),
+            ask: mid + dec!(0.0001),
+            tradeable: true,
+            status: PriceStatus::Tradeable,
+            closeout_bid: None,
+            closeout_ask: None,
+        }
+    }
+
+    #[test]
+    fn test_builds_and_closes_candles() {
+        let mut m1 = CandleAggregator::new(Granularity::M1);
+        assert!(m1.on_tick(&tick(0, dec!(1.1000))).is_none());
+        assert!(m1.on_tick(&tick(10, dec!(1.1010))).is_none());
+        assert!(m1.on_tick(&tick(20, dec!(1.0990))).is_none());
+        assert!(m1.on_tick(&tick(30, dec!(1.1005))).is_none());
+
+        let closed = m1.on_tick(&tick(40, dec!(1.1020))).unwrap();
+        assert_eq!(
+            closed.timestamp,
+            DateTime::from_timestamp(1_699_999_980, 0).unwrap()
+        );
+        assert_eq!(
+            (closed.open, closed.high, closed.low, closed.close),
+            (dec!(1.1000), dec!(1.1010), dec!(1.0990), dec!(1.1005))
+        );
+        assert_eq!(closed.volume, 4);
+        assert!(closed.complete);
+
+        // Late ticks don't reopen a closed candle
+        assert!(m1.on_tick(&tick(30, dec!(1.2000))).is_none());
+        assert_eq!(m1.current("EUR_USD").unwrap().high, dec!(1.1020));
+    }
+
+    #[test]
+    fn test_flush_closes_ended_periods() {
+        let mut m1 = CandleAggregator::new(Granularity::M1);
+        m1.on_tick(&tick(40, dec!(1.1000)));
+        let start = m1.current("EUR_USD").unwrap().timestamp;
+
+        assert!(m1.flush(start + chrono::Duration::seconds(59)).is_empty());
+        let closed = m1.flush(start + chrono::Duration::seconds(60));
+        assert_eq!(closed.len(), 1);
+        assert!(closed[0].complete);
+        assert!(m1.current("EUR_USD").is_none());
+    }
+}
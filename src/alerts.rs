@@ -0,0 +1,550 @@
+//! Price alerts
+//!
+//! [`AlertEngine`] checks registered [`AlertCondition`]s against every tick
+//! it sees and publishes an [`AlertEvent`] on a broadcast channel when one
+//! is met. Alerts are edge-triggered: a crossing fires once per cross, a
+//! spread alert once each time the spread widens past its limit, and a
+//! move alert once per move, after which it measures from the new price.
+//!
+//! ```no_run
+//! use oanda_connector::alerts::{AlertCondition, AlertEngine, AlertEvent};
+//! use oanda_connector::{OandaClient, OandaConfig};
+//! use rust_decimal_macros::dec;
+//! use std::time::Duration;
+//!
+//! # async fn run() -> oanda_connector::Result<()> {
+//! let client = OandaClient::new(OandaConfig::from_env()?)?;
+//! let instruments = client.get_instruments().await?;
+//! let eur_usd = instruments.iter().find(|i| i.name == "EUR_USD").unwrap();
+//!
+//! let engine = AlertEngine::new();
+//! engine.add(AlertCondition::crosses_above("EUR_USD", dec!(1.1000)));
+//! engine.add(AlertCondition::spread_above_pips(eur_usd, dec!(3)));
+//! engine.add(AlertCondition::moves("EUR_USD", dec!(0.5), Duration::from_secs(15 * 60)));
+//!
+//! let mut alerts = engine.subscribe();
+//! let _feed = engine.spawn_streaming(client, vec!["EUR_USD".to_string()], Duration::from_secs(5));
+//! while let Ok(alert) = alerts.recv().await {
+//!     if let AlertEvent::Moved { percent, .. } = alert {
+//!         println!("EUR/USD moved {}%", percent);
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::client::OandaClient;
+use crate::error::Result;
+use crate::models::{Instrument, Tick};
+use crate::shutdown::{ShutdownHandle, EVENT_CAPACITY};
+use chrono::{DateTime, Utc};
+use futures::{Stream, StreamExt};
+use rust_decimal::Decimal;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::broadcast;
+
+/// Handle for removing an alert
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct AlertId(pub u64);
+
+/// What to watch for; prices are compared at the mid
+#[derive(Debug, Clone, PartialEq)]
+pub enum AlertCondition {
+    CrossesAbove {
+        instrument: String,
+        level: Decimal,
+    },
+    CrossesBelow {
+        instrument: String,
+        level: Decimal,
+    },
+    SpreadAbove {
+        instrument: String,
+        max_pips: Decimal,
+        pip_size: Decimal,
+    },
+    /// Mid moves by at least `percent` either way within `window`
+    Moves {
+        instrument: String,
+        percent: Decimal,
+        window: Duration,
+    },
+}
+
+impl AlertCondition {
+    pub fn crosses_above(instrument: &str, level: Decimal) -> Self {
+        AlertCondition::CrossesAbove {
+            instrument: instrument.to_string(),
+            level,
+        }
+    }
+
+    pub fn crosses_below(instrument: &str, level: Decimal) -> Self {
+        AlertCondition::CrossesBelow {
+            instrument: instrument.to_string(),
+            level,
+        }
+    }
+
+    /// Spread wider than `max_pips` of the instrument
+    pub fn spread_above_pips(instrument: &Instrument, max_pips: Decimal) -> Self {
+        AlertCondition::SpreadAbove {
+            instrument: instrument.name.clone(),
+            max_pips,
+            pip_size: instrument.pip_size(),
+        }
+    }
+
+    pub fn moves(instrument: &str, percent: Decimal, window: Duration) -> Self {
+        AlertCondition::Moves {
+            instrument: instrument.to_string(),
+            percent,
+            window,
+        }
+    }
+
+    pub fn instrument(&self) -> &str {
+        match self {
+            AlertCondition::CrossesAbove { instrument, .. }
+            | AlertCondition::CrossesBelow { instrument, .. }
+            | AlertCondition::SpreadAbove { instrument, .. }
+            | AlertCondition::Moves { instrument, .. } => instrument,
+        }
+    }
+}
+
+/// Direction of a level cross
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CrossDirection {
+    Above,
+    Below,
+}
+
+/// A condition that was met
+#[derive(Debug, Clone, PartialEq)]
+pub enum AlertEvent {
+    Crossed {
+        id: AlertId,
+        instrument: String,
+        direction: CrossDirection,
+        level: Decimal,
+        price: Decimal,
+        time: DateTime<Utc>,
+    },
+    SpreadExceeded {
+        id: AlertId,
+        instrument: String,
+        spread_pips: Decimal,
+        max_pips: Decimal,
+        time: DateTime<Utc>,
+    },
+    Moved {
+        id: AlertId,
+        instrument: String,
+        /// Signed change from `from_price`
+        percent: Decimal,
+        from_price: Decimal,
+        price: Decimal,
+        time: DateTime<Utc>,
+    },
+}
+
+impl AlertEvent {
+    pub fn id(&self) -> AlertId {
+        match self {
+            AlertEvent::Crossed { id, .. }
+            | AlertEvent::SpreadExceeded { id, .. }
+            | AlertEvent::Moved { id, .. } => *id,
+        }
+    }
+
+    pub fn instrument(&self) -> &str {
+        match self {
+            AlertEvent::Crossed { instrument, .. }
+            | AlertEvent::SpreadExceeded { instrument, .. }
+            | AlertEvent::Moved { instrument, .. } => instrument,
+        }
+    }
+}
+
+/// A registered condition and what it has seen so far
+struct Alert {
+    id: AlertId,
+    condition: AlertCondition,
+    /// Mid of the previous tick, for crosses
+    last_mid: Option<Decimal>,
+    /// Whether the spread was already too wide
+    wide: bool,
+    /// Samples in the window with rising mids; the front is the low
+    lows: VecDeque<(DateTime<Utc>, Decimal)>,
+    /// Samples in the window with falling mids; the front is the high
+    highs: VecDeque<(DateTime<Utc>, Decimal)>,
+}
+
+impl Alert {
+    fn check(&mut self, tick: &Tick) -> Option<AlertEvent> {
+        let mid = tick.mid();
+        match &self.condition {
+            AlertCondition::CrossesAbove { instrument, level }
+            | AlertCondition::CrossesBelow { instrument, level } => {
+                let above = matches!(self.condition, AlertCondition::CrossesAbove { .. });
+                let crossed = self.last_mid.replace(mid).is_some_and(|last| {
+                    if above {
+                        last < *level && mid >= *level
+                    } else {
+                        last > *level && mid <= *level
+                    }
+                });
+                crossed.then(|| AlertEvent::Crossed {
+                    id: self.id,
+                    instrument: instrument.clone(),
+                    direction: if above {
+                        CrossDirection::Above
+                    } else {
+                        CrossDirection::Below
+                    },
+                    level: *level,
+                    price: mid,
+                    time: tick.timestamp,
+                })
+            }
+            AlertCondition::SpreadAbove {
+                instrument,
+                max_pips,
+                pip_size,
+            } => {
+                let spread_pips = tick.spread().checked_div(*pip_size)?;
+                let was_wide = std::mem::replace(&mut self.wide, spread_pips > *max_pips);
+                (self.wide && !was_wide).then(|| AlertEvent::SpreadExceeded {
+                    id: self.id,
+                    instrument: instrument.clone(),
+                    spread_pips,
+                    max_pips: *max_pips,
+                    time: tick.timestamp,
+                })
+            }
+            AlertCondition::Moves {
+                instrument,
+                percent,
+                window,
+            } => {
+                let window = chrono::Duration::from_std(*window).unwrap_or(chrono::Duration::MAX);
+                for extremes in [&mut self.lows, &mut self.highs] {
+                    while extremes
+                        .front()
+                        .is_some_and(|(time, _)| tick.timestamp - *time > window)
+                    {
+                        extremes.pop_front();
+                    }
+                }
+                // A sample is dropped once a later one is at least as extreme
+                while self.lows.back().is_some_and(|(_, low)| *low >= mid) {
+                    self.lows.pop_back();
+                }
+                self.lows.push_back((tick.timestamp, mid));
+                while self.highs.back().is_some_and(|(_, high)| *high <= mid) {
+                    self.highs.pop_back();
+                }
+                self.highs.push_back((tick.timestamp, mid));
+
+                let change_from = |from: Decimal| {
+                    (mid - from)
+                        .checked_div(from)
+                        .map(|change| (change * Decimal::ONE_HUNDRED, from))
+                };
+                let (_, low) = *self.lows.front()?;
+                let (_, high) = *self.highs.front()?;
+                let (change, from_price) = [change_from(low), change_from(high)]
+                    .into_iter()
+                    .flatten()
+                    .max_by_key(|(change, _)| change.abs())?;
+                if change.abs() < *percent {
+                    return None;
+                }
+                // Measure the next move from here
+                self.lows.clear();
+                self.lows.push_back((tick.timestamp, mid));
+                self.highs.clear();
+                self.highs.push_back((tick.timestamp, mid));
+                Some(AlertEvent::Moved {
+                    id: self.id,
+                    instrument: instrument.clone(),
+                    percent: change,
+                    from_price,
+                    price: mid,
+                    time: tick.timestamp,
+                })
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+struct State {
+    next_id: u64,
+    alerts: Vec<Alert>,
+}
+
+/// Checks alert conditions against prices
+///
+/// Cloning is cheap and clones share the same alerts and channel.
+#[derive(Clone)]
+pub struct AlertEngine {
+    state: Arc<Mutex<State>>,
+    events: broadcast::Sender<AlertEvent>,
+}
+
+impl Default for AlertEngine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AlertEngine {
+    pub fn new() -> Self {
+        let (events, _) = broadcast::channel(EVENT_CAPACITY);
+        Self {
+            state: Arc::new(Mutex::new(State::default())),
+            events,
+        }
+    }
+
+    /// Start watching for a condition
+    pub fn add(&self, condition: AlertCondition) -> AlertId {
+        let mut state = self.state.lock().unwrap();
+        state.next_id += 1;
+        let id = AlertId(state.next_id);
+        state.alerts.push(Alert {
+            id,
+            condition,
+            last_mid: None,
+            wide: false,
+            lows: VecDeque::new(),
+            highs: VecDeque::new(),
+        });
+        id
+    }
+
+    /// Stop watching; returns the condition if it was registered
+    pub fn remove(&self, id: AlertId) -> Option<AlertCondition> {
+        let mut state = self.state.lock().unwrap();
+        let index = state.alerts.iter().position(|alert| alert.id == id)?;
+        Some(state.alerts.remove(index).condition)
+    }
+
+    /// Registered conditions
+    pub fn alerts(&self) -> Vec<(AlertId, AlertCondition)> {
+        let state = self.state.lock().unwrap();
+        state
+            .alerts
+            .iter()
+            .map(|alert| (alert.id, alert.condition.clone()))
+            .collect()
+    }
+
+    /// Instruments the registered conditions watch, without duplicates
+    pub fn instruments(&self) -> Vec<String> {
+        let state = self.state.lock().unwrap();
+        let mut instruments: Vec<String> = state
+            .alerts
+            .iter()
+            .map(|alert| alert.condition.instrument().to_string())
+            .collect();
+        instruments.sort();
+        instruments.dedup();
+        instruments
+    }
+
+    /// Receiver for alerts fired from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<AlertEvent> {
+        self.events.subscribe()
+    }
+
+    /// Check a tick against every condition and publish what fires
+    ///
+    /// Non-tradeable prices are skipped. Returns the alerts fired.
+    pub fn on_tick(&self, tick: &Tick) -> Vec<AlertEvent> {
+        if !tick.tradeable {
+            return Vec::new();
+        }
+        let fired: Vec<AlertEvent> = {
+            let mut state = self.state.lock().unwrap();
+            state
+                .alerts
+                .iter_mut()
+                .filter(|alert| alert.condition.instrument() == tick.instrument)
+                .filter_map(|alert| alert.check(tick))
+                .collect()
+        };
+        for event in &fired {
+            let _ = self.events.send(event.clone());
+        }
+        fired
+    }
+
+    /// Check every tick from a price stream until it ends
+    ///
+    /// Errors in the stream are returned immediately.
+    pub async fn consume<S>(&self, ticks: S) -> Result<()>
+    where
+        S: Stream<Item = Result<Tick>>,
+    {
+        futures::pin_mut!(ticks);
+        while let Some(tick) = ticks.next().await {
+            self.on_tick(&tick?);
+        }
+        Ok(())
+    }
+
+    /// Follow the pricing stream in the background
+    ///
+    /// Reopens the stream `reconnect_delay` after it drops. The task runs
+    /// until shut down through the returned handle, or until the handle is
+    /// dropped.
+    pub fn spawn_streaming(
+        &self,
+        client: OandaClient,
+        instruments: Vec<String>,
+        reconnect_delay: Duration,
+    ) -> ShutdownHandle {
+        let engine = self.clone();
+        ShutdownHandle::spawn(|mut signal| async move {
+            loop {
+                tokio::select! {
+                    result = async {
+                        let ticks = client.stream_prices(&instruments).await?;
+                        engine.consume(ticks).await
+                    } => {
+                        if let Err(e) = result {
+                            log::warn!("alert price stream failed: {}", e);
+                        }
+                    }
+                    _ = signal.requested() => break,
+                }
+                tokio::select! {
+                    _ = tokio::time::sleep(reconnect_delay) => {}
+                    _ = signal.requested() => break,
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::PriceStatus;
+    use rust_decimal_macros::dec;
+
+    fn tick(seconds: i64, bid: Decimal, ask: Decimal) -> Tick {
+        Tick {
+            instrument: "EUR_USD".to_string(),
+            timestamp: DateTime::from_timestamp(1_700_000_000 + seconds, 0).unwrap(),
+            bid,
+            ask,
+            tradeable: true,
+            status: PriceStatus::Tradeable,
+            closeout_bid: None,
+            closeout_ask: None,
+        }
+    }
+
+    #[test]
+    fn test_crosses_fire_once_per_cross() {
+        let engine = AlertEngine::new();
+        let up = engine.add(AlertCondition::crosses_above("EUR_USD", dec!(1.1000)));
+        let down = engine.add(AlertCondition::crosses_below("EUR_USD", dec!(1.1000)));
+        let mut events = engine.subscribe();
+
+        let mids = [dec!(1.0990), dec!(1.1001), dec!(1.1005), dec!(1.0999)];
+        let fired: Vec<Vec<AlertId>> = mids
+            .iter()
+            .enumerate()
+            .map(|(i, mid)| {
+                engine
+                    .on_tick(&tick(i as i64, *mid, *mid))
+                    .iter()
+                    .map(AlertEvent::id)
+                    .collect()
+            })
+            .collect();
+        assert_eq!(fired, vec![vec![], vec![up], vec![], vec![down]]);
+        assert!(matches!(
+            events.try_recv().unwrap(),
+            AlertEvent::Crossed { direction: CrossDirection::Above, price, .. } if price == dec!(1.1001)
+        ));
+
+        engine.remove(up);
+        assert!(engine
+            .on_tick(&tick(4, dec!(1.1002), dec!(1.1002)))
+            .is_empty());
+    }
+
+    #[test]
+    fn test_spread_and_move_alerts() {
+        let engine = AlertEngine::new();
+        engine.add(AlertCondition::SpreadAbove {
+            instrument: "EUR_USD".to_string(),
+            max_pips: dec!(2),
+            pip_size: dec!(0.0001),
+        });
+        engine.add(AlertCondition::moves(
+            "EUR_USD",
+            dec!(1),
+            Duration::from_secs(60),
+        ));
+
+        assert!(engine
+            .on_tick(&tick(0, dec!(1.0000), dec!(1.0001)))
+            .is_empty());
+        let fired = engine.on_tick(&tick(10, dec!(1.0000), dec!(1.0003)));
+        assert!(matches!(
+            fired.as_slice(),
+            [AlertEvent::SpreadExceeded { spread_pips, .. }] if *spread_pips == dec!(3)
+        ));
+        // Still wide: no repeat
+        assert!(engine
+            .on_tick(&tick(20, dec!(1.0000), dec!(1.0004)))
+            .is_empty());
+
+        // 1.00005 -> 1.01015 inside the window
+        let fired = engine.on_tick(&tick(50, dec!(1.0101), dec!(1.0102)));
+        assert!(matches!(
+            fired.as_slice(),
+            [AlertEvent::Moved { from_price, .. }] if *from_price == dec!(1.00005)
+        ));
+        // Too slow: the 2% drop is spread over more than a minute
+        assert!(engine
+            .on_tick(&tick(100, dec!(1.0000), dec!(1.0001)))
+            .is_empty());
+        assert!(engine
+            .on_tick(&tick(170, dec!(0.9900), dec!(0.9901)))
+            .is_empty());
+    }
+
+    #[test]
+    fn test_move_measured_from_window_extremes() {
+        let engine = AlertEngine::new();
+        engine.add(AlertCondition::moves(
+            "EUR_USD",
+            dec!(1),
+            Duration::from_secs(60),
+        ));
+
+        let mids = [dec!(1.0000), dec!(1.0090), dec!(1.0050), dec!(0.9980)];
+        let fired: Vec<Vec<AlertEvent>> = mids
+            .iter()
+            .enumerate()
+            .map(|(i, mid)| engine.on_tick(&tick(i as i64 * 10, *mid, *mid)))
+            .collect();
+        assert!(fired[..3].iter().all(Vec::is_empty));
+        // Only 0.2% below the first tick, but over 1% off the high in between
+        assert!(matches!(
+            fired[3].as_slice(),
+            [AlertEvent::Moved { from_price, percent, .. }]
+                if *from_price == dec!(1.0090) && *percent < dec!(-1)
+        ));
+    }
+}
@@ -0,0 +1,286 @@
+//! Apache Arrow output (`arrow` feature)
+//!
+//! Turns candles and ticks into Arrow [`RecordBatch`]es, ready for
+//! DataFusion, Polars or Python through pyarrow without going through CSV
+//! or JSON. Prices are `Decimal128` so they stay exact; times are UTC
+//! nanosecond timestamps.
+//!
+//! Candles ([`candle_schema`]):
+//!
+//! | column       | type                           | nullable |
+//! |--------------|--------------------------------|----------|
+//! | `instrument` | `Utf8`                         | no       |
+//! | `time`       | `Timestamp(Nanosecond, "UTC")` | no       |
+//! | `open`       | `Decimal128(38, 10)`           | no       |
+//! | `high`       | `Decimal128(38, 10)`           | no       |
+//! | `low`        | `Decimal128(38, 10)`           | no       |
+//! | `close`      | `Decimal128(38, 10)`           | no       |
+//! | `volume`     | `Int64`                        | no       |
+//! | `complete`   | `Boolean`                      | no       |
+//!
+//! Ticks ([`tick_schema`]):
+//!
+//! | column         | type                           | nullable |
+//! |----------------|--------------------------------|----------|
+//! | `instrument`   | `Utf8`                         | no       |
+//! | `time`         | `Timestamp(Nanosecond, "UTC")` | no       |
+//! | `bid`          | `Decimal128(38, 10)`           | no       |
+//! | `ask`          | `Decimal128(38, 10)`           | no       |
+//! | `tradeable`    | `Boolean`                      | no       |
+//! | `status`       | `Utf8`                         | no       |
+//! | `closeout_bid` | `Decimal128(38, 10)`           | yes      |
+//! | `closeout_ask` | `Decimal128(38, 10)`           | yes      |
+//!
+//! `status` is `tradeable`, `non-tradeable` or `invalid`, as in the API.
+//! Prices with more than [`PRICE_SCALE`] decimal places are rounded to it;
+//! OANDA quotes at most five.
+//!
+//! ```
+//! # fn run(candles: &[oanda_connector::Candle]) -> oanda_connector::Result<()> {
+//! use oanda_connector::arrow::candles_to_record_batch;
+//!
+//! let batch = candles_to_record_batch(candles)?;
+//! assert_eq!(batch.num_rows(), candles.len());
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::error::{Error, Result};
+use crate::models::{Candle, PriceStatus, Tick};
+use arrow_array::{
+    ArrayRef, BooleanArray, Decimal128Array, Int64Array, StringArray, TimestampNanosecondArray,
+};
+use arrow_schema::{DataType, Field, Schema, SchemaRef, TimeUnit};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use std::sync::Arc;
+
+pub use arrow_array::RecordBatch;
+
+/// Precision of price columns
+pub const PRICE_PRECISION: u8 = 38;
+
+/// Decimal places kept in price columns
+pub const PRICE_SCALE: i8 = 10;
+
+fn price_type() -> DataType {
+    DataType::Decimal128(PRICE_PRECISION, PRICE_SCALE)
+}
+
+fn time_type() -> DataType {
+    DataType::Timestamp(TimeUnit::Nanosecond, Some("UTC".into()))
+}
+
+/// Schema of [`candles_to_record_batch`] output
+pub fn candle_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new("instrument", DataType::Utf8, false),
+        Field::new("time", time_type(), false),
+        Field::new("open", price_type(), false),
+        Field::new("high", price_type(), false),
+        Field::new("low", price_type(), false),
+        Field::new("close", price_type(), false),
+        Field::new("volume", DataType::Int64, false),
+        Field::new("complete", DataType::Boolean, false),
+    ]))
+}
+
+/// Schema of [`ticks_to_record_batch`] output
+pub fn tick_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new("instrument", DataType::Utf8, false),
+        Field::new("time", time_type(), false),
+        Field::new("bid", price_type(), false),
+        Field::new("ask", price_type(), false),
+        Field::new("tradeable", DataType::Boolean, false),
+        Field::new("status", DataType::Utf8, false),
+        Field::new("closeout_bid", price_type(), true),
+        Field::new("closeout_ask", price_type(), true),
+    ]))
+}
+
+fn arrow_error(e: arrow_schema::ArrowError) -> Error {
+    Error::EncodingError(e.to_string())
+}
+
+fn nanos(time: &DateTime<Utc>) -> Result<i64> {
+    time.timestamp_nanos_opt()
+        .ok_or_else(|| Error::EncodingError(format!("{} is out of timestamp range", time)))
+}
+
+/// Mantissa of a price at `PRICE_SCALE`
+fn scaled(price: Decimal) -> Result<i128> {
+    let mut value = price.round_dp(PRICE_SCALE as u32);
+    value.rescale(PRICE_SCALE as u32);
+    if value.scale() != PRICE_SCALE as u32 {
+        return Err(Error::EncodingError(format!(
+            "{} does not fit Decimal128({}, {})",
+            price, PRICE_PRECISION, PRICE_SCALE
+        )));
+    }
+    Ok(value.mantissa())
+}
+
+fn price_column(values: Vec<Option<i128>>) -> Result<ArrayRef> {
+    let array = Decimal128Array::from(values)
+        .with_precision_and_scale(PRICE_PRECISION, PRICE_SCALE)
+        .map_err(arrow_error)?;
+    Ok(Arc::new(array))
+}
+
+fn prices<T>(rows: &[T], price: impl Fn(&T) -> Option<Decimal>) -> Result<ArrayRef> {
+    let values = rows
+        .iter()
+        .map(|row| price(row).map(scaled).transpose())
+        .collect::<Result<Vec<_>>>()?;
+    price_column(values)
+}
+
+fn times<T>(rows: &[T], time: impl Fn(&T) -> &DateTime<Utc>) -> Result<ArrayRef> {
+    let values = rows
+        .iter()
+        .map(|row| nanos(time(row)))
+        .collect::<Result<Vec<_>>>()?;
+    Ok(Arc::new(
+        TimestampNanosecondArray::from(values).with_timezone("UTC"),
+    ))
+}
+
+fn status_name(status: PriceStatus) -> &'static str {
+    match status {
+        PriceStatus::Tradeable => "tradeable",
+        PriceStatus::NonTradeable => "non-tradeable",
+        PriceStatus::Invalid => "invalid",
+    }
+}
+
+/// One row per candle, in [`candle_schema`]
+pub fn candles_to_record_batch(candles: &[Candle]) -> Result<RecordBatch> {
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(StringArray::from_iter_values(
+            candles.iter().map(|c| c.instrument.as_str()),
+        )),
+        times(candles, |c| &c.timestamp)?,
+        prices(candles, |c| Some(c.open))?,
+        prices(candles, |c| Some(c.high))?,
+        prices(candles, |c| Some(c.low))?,
+        prices(candles, |c| Some(c.close))?,
+        Arc::new(Int64Array::from_iter_values(
+            candles.iter().map(|c| c.volume),
+        )),
+        Arc::new(BooleanArray::from_iter(
+            candles.iter().map(|c| Some(c.complete)),
+        )),
+    ];
+    RecordBatch::try_new(candle_schema(), columns).map_err(arrow_error)
+}
+
+/// One row per tick, in [`tick_schema`]
+pub fn ticks_to_record_batch(ticks: &[Tick]) -> Result<RecordBatch> {
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(StringArray::from_iter_values(
+            ticks.iter().map(|t| t.instrument.as_str()),
+        )),
+        times(ticks, |t| &t.timestamp)?,
+        prices(ticks, |t| Some(t.bid))?,
+        prices(ticks, |t| Some(t.ask))?,
+        Arc::new(BooleanArray::from_iter(
+            ticks.iter().map(|t| Some(t.tradeable)),
+        )),
+        Arc::new(StringArray::from_iter_values(
+            ticks.iter().map(|t| status_name(t.status)),
+        )),
+        prices(ticks, |t| t.closeout_bid)?,
+        prices(ticks, |t| t.closeout_ask)?,
+    ];
+    RecordBatch::try_new(tick_schema(), columns).map_err(arrow_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_array::Array;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_candle_batch() {
+        let timestamp = DateTime::from_timestamp(1_700_000_000, 500).unwrap();
+        let candles = vec![
+            Candle::builder()
+                .timestamp(timestamp)
+                .ohlc(dec!(1.10000), dec!(1.10250), dec!(1.09875), dec!(1.10125))
+                .volume(42)
+                .build(),
+            Candle::builder()
+                .instrument("USD_JPY")
+                .complete(false)
+                .build(),
+        ];
+        let batch = candles_to_record_batch(&candles).unwrap();
+        assert_eq!(batch.schema(), candle_schema());
+        assert_eq!(batch.num_rows(), 2);
+
+        let time = batch
+            .column(1)
+            .as_any()
+            .downcast_ref::<TimestampNanosecondArray>()
+            .unwrap();
+        assert_eq!(time.value(0), 1_700_000_000_000_000_500);
+
+        let high = batch
+            .column(3)
+            .as_any()
+            .downcast_ref::<Decimal128Array>()
+            .unwrap();
+        assert_eq!(high.value_as_string(0), "1.1025000000");
+        assert_eq!(high.value(0), 11_025_000_000);
+
+        let instrument = batch
+            .column(0)
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap();
+        assert_eq!(instrument.value(1), "USD_JPY");
+
+        let empty = candles_to_record_batch(&[]).unwrap();
+        assert_eq!(empty.num_rows(), 0);
+    }
+
+    #[test]
+    fn test_tick_batch() {
+        let ticks = vec![
+            Tick::builder()
+                .bid(dec!(1.10000))
+                .ask(dec!(1.10020))
+                .build(),
+            Tick::builder()
+                .closeout(dec!(1.09990), dec!(1.10030))
+                .tradeable(false)
+                .build(),
+        ];
+        let batch = ticks_to_record_batch(&ticks).unwrap();
+        assert_eq!(batch.schema(), tick_schema());
+
+        let closeout_bid = batch
+            .column(6)
+            .as_any()
+            .downcast_ref::<Decimal128Array>()
+            .unwrap();
+        assert!(closeout_bid.is_null(0));
+        assert_eq!(closeout_bid.value_as_string(1), "1.0999000000");
+
+        let status = batch
+            .column(5)
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap();
+        assert_eq!(status.value(1), status_name(ticks[1].status));
+    }
+
+    #[test]
+    fn test_prices_rounded_to_scale() {
+        assert_eq!(scaled(dec!(1.123456789012)).unwrap(), 11_234_567_890);
+        assert_eq!(scaled(dec!(-150.5)).unwrap(), -1_505_000_000_000);
+        assert!(matches!(scaled(Decimal::MAX), Err(Error::EncodingError(_))));
+    }
+}
@@ -0,0 +1,411 @@
+//! Backtesting against historical prices
+//!
+//! [`Backtest`] replays candles or ticks through a [`PaperTrader`], calls a
+//! strategy after each one and collects the resulting equity curve, P/L and
+//! financing into a [`BacktestReport`]. Fetch the data with the client as
+//! usual; the simulation itself makes no requests.
+//!
+//! ```no_run
+//! use oanda_connector::backtest::{Backtest, BacktestConfig, MarketEvent};
+//! use oanda_connector::{Granularity, OandaClient, OandaConfig, OrderRequest};
+//! use rust_decimal_macros::dec;
+//!
+//! # async fn run() -> oanda_connector::Result<()> {
+//! let client = OandaClient::new(OandaConfig::from_env()?)?;
+//! let candles = client.get_candles("EUR_USD", Granularity::H1, 500).await?;
+//!
+//! let backtest = Backtest::new(BacktestConfig {
+//!     candle_spread: dec!(0.00012),
+//!     ..BacktestConfig::default()
+//! });
+//! let report = backtest
+//!     .run(candles.into_iter().map(MarketEvent::from), |event, account| {
+//!         let MarketEvent::Candle(candle) = event else { return Vec::new() };
+//!         match account.positions().first() {
+//!             None if candle.close > candle.open => vec![OrderRequest::market("EUR_USD", dec!(1000))],
+//!             Some(position) if candle.close < candle.open => {
+//!                 vec![OrderRequest::market("EUR_USD", -position.units)]
+//!             }
+//!             _ => Vec::new(),
+//!         }
+//!     })
+//!     .await?;
+//! println!("return {}, max drawdown {}", report.total_return, report.max_drawdown);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Candles are treated as mid prices: each is replayed as four ticks
+//! (open, the nearer extreme, the other extreme, close) with
+//! `candle_spread` around them, so pending orders can trigger inside the
+//! bar. Orders fill at the first replayed price that triggers them, which
+//! can be better than the order's price when a bar moves through it. The
+//! strategy runs once the bar has closed, and its market orders fill at the
+//! close.
+
+use crate::equity::DrawdownStats;
+use crate::error::Result;
+use crate::models::{Candle, InstrumentFinancing, PriceStatus, Tick};
+use crate::orders::OrderRequest;
+use crate::paper::{PaperConfig, PaperTrader};
+use crate::reports::PnlReport;
+use crate::trading::Trading;
+use crate::transactions::Transaction;
+use chrono::{DateTime, Duration, Utc};
+use futures::{Stream, StreamExt};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// One step of historical data
+#[derive(Debug, Clone)]
+pub enum MarketEvent {
+    Candle(Candle),
+    Tick(Tick),
+}
+
+impl MarketEvent {
+    pub fn instrument(&self) -> &str {
+        match self {
+            MarketEvent::Candle(candle) => &candle.instrument,
+            MarketEvent::Tick(tick) => &tick.instrument,
+        }
+    }
+
+    pub fn timestamp(&self) -> DateTime<Utc> {
+        match self {
+            MarketEvent::Candle(candle) => candle.timestamp,
+            MarketEvent::Tick(tick) => tick.timestamp,
+        }
+    }
+}
+
+impl From<Candle> for MarketEvent {
+    fn from(candle: Candle) -> Self {
+        MarketEvent::Candle(candle)
+    }
+}
+
+impl From<Tick> for MarketEvent {
+    fn from(tick: Tick) -> Self {
+        MarketEvent::Tick(tick)
+    }
+}
+
+/// Simulation settings
+#[derive(Debug, Clone)]
+pub struct BacktestConfig {
+    /// Account, spread model and slippage used for fills
+    pub paper: PaperConfig,
+    /// Bid/ask spread assumed around candle prices
+    pub candle_spread: Decimal,
+    /// Financing rates per instrument, e.g. from `Instrument::financing`;
+    /// instruments missing here are not charged
+    pub financing: HashMap<String, InstrumentFinancing>,
+    /// UTC hour of the daily rollover that financing is charged at
+    pub rollover_hour: u32,
+}
+
+impl Default for BacktestConfig {
+    fn default() -> Self {
+        Self {
+            paper: PaperConfig::default(),
+            candle_spread: Decimal::ZERO,
+            financing: HashMap::new(),
+            // 17:00 New York during daylight saving time
+            rollover_hour: 21,
+        }
+    }
+}
+
+/// Account value after one event
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct EquityPoint {
+    pub timestamp: DateTime<Utc>,
+    pub balance: Decimal,
+    pub nav: Decimal,
+}
+
+/// Outcome of a backtest
+#[derive(Debug, Clone)]
+pub struct BacktestReport {
+    pub initial_balance: Decimal,
+    pub final_balance: Decimal,
+    /// Balance plus unrealized P/L of positions still open at the end
+    pub final_nav: Decimal,
+    pub realized_pl: Decimal,
+    /// Net financing paid (negative) or earned
+    pub financing: Decimal,
+    /// `final_nav / initial_balance - 1`
+    pub total_return: Decimal,
+    /// Largest fall in NAV from a previous peak, as a fraction of the peak
+    pub max_drawdown: Decimal,
+    pub fill_count: usize,
+    /// Orders the simulated account refused, e.g. for insufficient margin
+    pub rejected_orders: usize,
+    pub equity_curve: Vec<EquityPoint>,
+    /// Realized P/L per instrument and per day
+    pub pnl: PnlReport,
+    pub transactions: Vec<Transaction>,
+}
+
+/// Replays historical data through a strategy
+pub struct Backtest {
+    config: BacktestConfig,
+    account: PaperTrader,
+}
+
+impl Backtest {
+    pub fn new(config: BacktestConfig) -> Self {
+        Self {
+            account: PaperTrader::new(config.paper.clone()),
+            config,
+        }
+    }
+
+    /// Run `strategy` over events in chronological order
+    ///
+    /// The strategy sees each event and the simulated account, and returns
+    /// the orders to place; pending orders can be cancelled directly on the
+    /// account.
+    pub async fn run<I, F>(self, events: I, strategy: F) -> Result<BacktestReport>
+    where
+        I: IntoIterator<Item = MarketEvent>,
+        F: FnMut(&MarketEvent, &PaperTrader) -> Vec<OrderRequest>,
+    {
+        self.run_stream(futures::stream::iter(events.into_iter().map(Ok)), strategy)
+            .await
+    }
+
+    /// Like `run`, for data read lazily, e.g. from paginated candle requests
+    ///
+    /// Stops at the first error from the stream.
+    pub async fn run_stream<S, F>(self, events: S, mut strategy: F) -> Result<BacktestReport>
+    where
+        S: Stream<Item = Result<MarketEvent>>,
+        F: FnMut(&MarketEvent, &PaperTrader) -> Vec<OrderRequest>,
+    {
+        futures::pin_mut!(events);
+        let mut next_rollover: Option<DateTime<Utc>> = None;
+        let mut financing = Decimal::ZERO;
+        let mut rejected_orders = 0;
+        let mut equity_curve = Vec::new();
+
+        while let Some(event) = events.next().await {
+            let event = event?;
+            if let MarketEvent::Candle(candle) = &event {
+                if !candle.complete {
+                    continue;
+                }
+            }
+
+            let time = event.timestamp();
+            let rollover = *next_rollover.get_or_insert_with(|| self.rollover_after(time));
+            if time >= rollover {
+                // Positions held over several rollovers (e.g. a data gap) pay each
+                let mut rollover = rollover;
+                while time >= rollover {
+                    financing += self
+                        .account
+                        .apply_financing(&self.config.financing, rollover);
+                    rollover += Duration::days(1);
+                }
+                next_rollover = Some(rollover);
+            }
+
+            match &event {
+                MarketEvent::Candle(candle) => {
+                    for tick in self.candle_ticks(candle) {
+                        self.account.on_tick(&tick);
+                    }
+                }
+                MarketEvent::Tick(tick) => {
+                    self.account.on_tick(tick);
+                }
+            }
+
+            for order in strategy(&event, &self.account) {
+                if self.account.place_order(&order).await.is_err() {
+                    rejected_orders += 1;
+                }
+            }
+
+            let summary = self.account.account_summary();
+            equity_curve.push(EquityPoint {
+                timestamp: time,
+                balance: summary.balance,
+                nav: summary.nav,
+            });
+        }
+
+        let summary = self.account.account_summary();
+        let transactions = self.account.transactions();
+        let initial_balance = self.config.paper.initial_balance;
+        Ok(BacktestReport {
+            initial_balance,
+            final_balance: summary.balance,
+            final_nav: summary.nav,
+            realized_pl: summary.realized_pl,
+            financing,
+            total_return: if initial_balance.is_zero() {
+                Decimal::ZERO
+            } else {
+                summary.nav / initial_balance - Decimal::ONE
+            },
+            max_drawdown: DrawdownStats::from_curve(&equity_curve).max_drawdown,
+            fill_count: transactions
+                .iter()
+                .filter(|t| matches!(t, Transaction::OrderFill(_)))
+                .count(),
+            rejected_orders,
+            equity_curve,
+            pnl: PnlReport::from_transactions(&transactions),
+            transactions,
+        })
+    }
+
+    /// First rollover strictly after `time`
+    fn rollover_after(&self, time: DateTime<Utc>) -> DateTime<Utc> {
+        let rollover = time
+            .date_naive()
+            .and_hms_opt(self.config.rollover_hour, 0, 0)
+            .map(|naive| naive.and_utc())
+            .unwrap_or(time);
+        if rollover > time {
+            rollover
+        } else {
+            rollover + Duration::days(1)
+        }
+    }
+
+    /// Open, nearer extreme, other extreme, close
+    fn candle_ticks(&self, candle: &Candle) -> Vec<Tick> {
+        let (first, second) = if candle.close >= candle.open {
+            (candle.low, candle.high)
+        } else {
+            (candle.high, candle.low)
+        };
+        let half_spread = self.config.candle_spread / Decimal::TWO;
+        [candle.open, first, second, candle.close]
+            .into_iter()
+            .map(|mid| Tick {
+                instrument: candle.instrument.clone(),
+                timestamp: candle.timestamp,
+                bid: mid - half_spread,
+                ask: mid + half_spread,
+                tradeable: true,
+                status: PriceStatus::Tradeable,
+                closeout_bid: None,
+                closeout_ask: None,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::FinancingDayOfWeek;
+    use chrono::{TimeZone, Timelike};
+    use rust_decimal_macros::dec;
+
+    fn candle(
+        hour: u32,
+        open: Decimal,
+        high: Decimal,
+        low: Decimal,
+        close: Decimal,
+    ) -> MarketEvent {
+        MarketEvent::Candle(Candle {
+            instrument: "EUR_USD".to_string(),
+            timestamp: Utc.with_ymd_and_hms(2025, 1, 6, hour, 0, 0).unwrap(),
+            open,
+            high,
+            low,
+            close,
+            volume: 100,
+            complete: true,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_round_trip_report() {
+        let events = vec![
+            candle(1, dec!(1.1000), dec!(1.1010), dec!(1.0990), dec!(1.1000)),
+            candle(2, dec!(1.1000), dec!(1.1000), dec!(1.0900), dec!(1.0950)),
+            candle(3, dec!(1.0950), dec!(1.1100), dec!(1.0950), dec!(1.1100)),
+        ];
+        let mut step = 0;
+        let report = Backtest::new(BacktestConfig::default())
+            .run(events, |_, _| {
+                step += 1;
+                match step {
+                    1 => vec![OrderRequest::market("EUR_USD", dec!(10000))],
+                    3 => vec![OrderRequest::market("EUR_USD", dec!(-10000))],
+                    _ => Vec::new(),
+                }
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(report.fill_count, 2);
+        assert_eq!(report.realized_pl, dec!(100));
+        assert_eq!(report.final_nav, dec!(100100));
+        assert_eq!(report.total_return, dec!(0.001));
+        assert_eq!(report.equity_curve.len(), 3);
+        // Trough at the second close: down 50 from 100000
+        assert_eq!(report.max_drawdown, dec!(0.0005));
+        assert_eq!(report.pnl.total.realized_pl, dec!(100.0));
+    }
+
+    #[tokio::test]
+    async fn test_limit_fills_inside_bar() {
+        let events = vec![
+            candle(1, dec!(1.1000), dec!(1.1000), dec!(1.1000), dec!(1.1000)),
+            candle(2, dec!(1.1000), dec!(1.1020), dec!(1.0940), dec!(1.0990)),
+        ];
+        let report = Backtest::new(BacktestConfig::default())
+            .run(events, |event, _| match event.timestamp().hour() {
+                1 => vec![OrderRequest::limit("EUR_USD", dec!(1000), dec!(1.0950))],
+                _ => Vec::new(),
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(report.fill_count, 1);
+        // Bought at the low, the first replayed price through the limit
+        assert_eq!(report.final_nav, dec!(100005));
+    }
+
+    #[tokio::test]
+    async fn test_financing_at_rollover() {
+        let financing = InstrumentFinancing {
+            long_rate: dec!(-0.0365),
+            short_rate: dec!(0.01),
+            financing_days_of_week: vec![FinancingDayOfWeek {
+                day_of_week: "MONDAY".to_string(),
+                days_charged: 1,
+            }],
+        };
+        let config = BacktestConfig {
+            financing: HashMap::from([("EUR_USD".to_string(), financing)]),
+            ..BacktestConfig::default()
+        };
+        let events = vec![
+            candle(20, dec!(1), dec!(1), dec!(1), dec!(1)),
+            candle(22, dec!(1), dec!(1), dec!(1), dec!(1)),
+        ];
+        let report = Backtest::new(config)
+            .run(events, |event, _| match event.timestamp().hour() {
+                20 => vec![OrderRequest::market("EUR_USD", dec!(10000))],
+                _ => Vec::new(),
+            })
+            .await
+            .unwrap();
+
+        // 10000 * 1.0 * -3.65% / 365 for one Monday
+        assert_eq!(report.financing, dec!(-1));
+        assert_eq!(report.final_balance, dec!(99999));
+        assert_eq!(report.pnl.total.financing, dec!(-1.0));
+    }
+}
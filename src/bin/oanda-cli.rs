@@ -0,0 +1,289 @@
+//! Command-line access to an OANDA account
+//!
+//! Credentials come from `--profile`, `--config` or the `OANDA_*`
+//! environment variables, in that order of preference.
+
+use clap::{Args, Parser, Subcommand};
+use futures::StreamExt;
+use oanda_connector::{
+    Error, Granularity, OandaClient, OandaConfig, OrderId, OrderRequest, OrderSpecifier, Result,
+};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use std::path::PathBuf;
+
+#[derive(Parser)]
+#[command(
+    name = "oanda-cli",
+    version,
+    about = "Inspect and trade an OANDA account"
+)]
+struct Cli {
+    /// Profile from the default config file
+    #[arg(long, global = true, conflicts_with = "config")]
+    profile: Option<String>,
+    /// TOML or YAML config file
+    #[arg(long, global = true)]
+    config: Option<PathBuf>,
+    /// Print JSON instead of text
+    #[arg(long, global = true)]
+    json: bool,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Current bid and ask
+    Price {
+        #[arg(required = true)]
+        instruments: Vec<String>,
+    },
+    /// Latest candles
+    Candles {
+        instrument: String,
+        #[arg(short, long, default_value = "H1")]
+        granularity: Granularity,
+        #[arg(short = 'n', long, default_value_t = 100)]
+        count: usize,
+        /// Print CSV with a header row
+        #[arg(long, conflicts_with = "json")]
+        csv: bool,
+    },
+    /// Balance, NAV and margin
+    Account,
+    /// Tradeable instruments
+    Instruments,
+    /// Follow prices until interrupted
+    Stream {
+        #[arg(required = true)]
+        instruments: Vec<String>,
+    },
+    /// Place or cancel orders
+    #[command(subcommand)]
+    Order(OrderCommand),
+    /// List or close positions
+    #[command(subcommand)]
+    Positions(PositionsCommand),
+}
+
+#[derive(Subcommand)]
+enum OrderCommand {
+    /// Market order, or limit/stop order with a price
+    Place(PlaceArgs),
+    /// Cancel by OANDA order ID, or by client ID prefixed with `@`
+    Cancel { order: String },
+}
+
+#[derive(Args)]
+struct PlaceArgs {
+    instrument: String,
+    /// Signed units: positive buys, negative sells
+    #[arg(allow_hyphen_values = true)]
+    units: Decimal,
+    #[arg(long, conflicts_with = "stop")]
+    limit: Option<Decimal>,
+    #[arg(long)]
+    stop: Option<Decimal>,
+    #[arg(long)]
+    stop_loss: Option<Decimal>,
+    #[arg(long)]
+    take_profit: Option<Decimal>,
+}
+
+#[derive(Subcommand)]
+enum PositionsCommand {
+    /// Open positions
+    List,
+    /// Close every unit of a position at market
+    Close { instrument: String },
+}
+
+fn config(cli: &Cli) -> Result<OandaConfig> {
+    match (&cli.profile, &cli.config) {
+        (Some(profile), _) => OandaConfig::from_profile(profile),
+        (None, Some(path)) => OandaConfig::from_file(path),
+        (None, None) => OandaConfig::from_env(),
+    }
+}
+
+fn print_json<T: Serialize>(value: &T) -> Result<()> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
+
+async fn run(cli: Cli) -> Result<()> {
+    let client = OandaClient::new(config(&cli)?)?;
+    let json = cli.json;
+
+    match cli.command {
+        Command::Price { instruments } => {
+            let ticks = client.get_current_prices(&instruments).await?;
+            if json {
+                return print_json(&ticks);
+            }
+            for tick in ticks {
+                println!(
+                    "{:<12} bid {:<12} ask {:<12} spread {}",
+                    tick.instrument,
+                    tick.bid,
+                    tick.ask,
+                    tick.spread()
+                );
+            }
+        }
+        Command::Candles {
+            instrument,
+            granularity,
+            count,
+            csv,
+        } => {
+            let candles = client.get_candles(&instrument, granularity, count).await?;
+            if json {
+                return print_json(&candles);
+            }
+            if csv {
+                println!("time,open,high,low,close,volume,complete");
+            }
+            for c in candles {
+                let time = c.timestamp.to_rfc3339();
+                if csv {
+                    println!(
+                        "{},{},{},{},{},{},{}",
+                        time, c.open, c.high, c.low, c.close, c.volume, c.complete
+                    );
+                } else {
+                    println!(
+                        "{}  O {}  H {}  L {}  C {}  V {}",
+                        time, c.open, c.high, c.low, c.close, c.volume
+                    );
+                }
+            }
+        }
+        Command::Account => {
+            let summary = client.get_account_summary().await?;
+            if json {
+                return print_json(&summary);
+            }
+            println!("account          {}", summary.id);
+            println!("balance          {} {}", summary.balance, summary.currency);
+            println!("NAV              {}", summary.nav);
+            println!("unrealized P/L   {}", summary.unrealized_pl);
+            println!("margin used      {}", summary.margin_used);
+            println!("margin available {}", summary.margin_available);
+            println!("open trades      {}", summary.open_trade_count);
+            println!("open positions   {}", summary.open_position_count);
+        }
+        Command::Instruments => {
+            let instruments = client.get_instruments().await?;
+            if json {
+                return print_json(&instruments);
+            }
+            for instrument in instruments {
+                println!("{:<16} {}", instrument.name, instrument.display_name);
+            }
+        }
+        Command::Stream { instruments } => {
+            let ticks = client.stream_prices(&instruments).await?;
+            futures::pin_mut!(ticks);
+            loop {
+                let tick = tokio::select! {
+                    tick = ticks.next() => tick,
+                    _ = tokio::signal::ctrl_c() => break,
+                };
+                let Some(tick) = tick.transpose()? else {
+                    break;
+                };
+                if json {
+                    println!("{}", serde_json::to_string(&tick)?);
+                } else {
+                    println!(
+                        "{} {:<12} {} / {}",
+                        tick.timestamp.to_rfc3339(),
+                        tick.instrument,
+                        tick.bid,
+                        tick.ask
+                    );
+                }
+            }
+        }
+        Command::Order(OrderCommand::Place(args)) => {
+            let mut order = match (args.limit, args.stop) {
+                (Some(price), _) => OrderRequest::limit(&args.instrument, args.units, price),
+                (None, Some(price)) => OrderRequest::stop(&args.instrument, args.units, price),
+                (None, None) => OrderRequest::market(&args.instrument, args.units),
+            };
+            if let Some(price) = args.stop_loss {
+                order = order.stop_loss(price);
+            }
+            if let Some(price) = args.take_profit {
+                order = order.take_profit(price);
+            }
+            let response = client.place_order(&order).await?;
+            if json {
+                return print_json(&response);
+            }
+            match (
+                &response.order_fill_transaction,
+                &response.order_cancel_transaction,
+            ) {
+                (Some(_), _) => println!("filled"),
+                (None, Some(_)) => println!("cancelled by OANDA"),
+                (None, None) => println!("pending"),
+            }
+            if let Some(id) = response.last_transaction_id {
+                println!("last transaction {}", id);
+            }
+        }
+        Command::Order(OrderCommand::Cancel { order }) => {
+            let specifier = match order.strip_prefix('@') {
+                Some(client_id) => OrderSpecifier::client_id(client_id),
+                None => OrderSpecifier::Id(order.parse::<OrderId>()?),
+            };
+            let response = client.cancel_order(specifier).await?;
+            if json {
+                return print_json(&response);
+            }
+            println!("cancelled {}", order);
+        }
+        Command::Positions(PositionsCommand::List) => {
+            let positions = client.get_open_positions().await?;
+            if json {
+                return print_json(&positions);
+            }
+            for position in positions {
+                println!(
+                    "{:<12} long {:<10} short {:<10} unrealized {}",
+                    position.instrument,
+                    position.long.units,
+                    position.short.units,
+                    position.unrealized_pl.unwrap_or_default()
+                );
+            }
+        }
+        Command::Positions(PositionsCommand::Close { instrument }) => {
+            let position = client
+                .get_open_positions()
+                .await?
+                .into_iter()
+                .find(|p| p.instrument == instrument)
+                .ok_or_else(|| {
+                    Error::InvalidInstrument(format!("no open position in {}", instrument))
+                })?;
+            let response = client.close_position(&position).await?;
+            if json {
+                return print_json(&response);
+            }
+            println!("closed {} ({} units)", instrument, position.net_units());
+        }
+    }
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() {
+    if let Err(e) = run(Cli::parse()).await {
+        eprintln!("error: {}", e);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,23 @@
+//! Local HTTP gateway in front of OANDA
+//!
+//! Reads the OANDA credentials from `OANDA_*` and the gateway settings from
+//! `OANDA_GATEWAY_*` environment variables, then serves until Ctrl-C.
+
+use oanda_connector::gateway::{Gateway, GatewayConfig};
+use oanda_connector::{OandaClient, OandaConfig};
+
+#[tokio::main]
+async fn main() -> oanda_connector::Result<()> {
+    let client = OandaClient::new(OandaConfig::from_env()?)?;
+    let config = GatewayConfig::from_env()?;
+    if config.tokens.is_empty() {
+        eprintln!("warning: OANDA_GATEWAY_TOKENS not set, serving without authentication");
+    }
+    eprintln!("oanda-gateway listening on {}", config.bind);
+
+    Gateway::new(client, config)
+        .serve(async {
+            tokio::signal::ctrl_c().await.ok();
+        })
+        .await
+}
@@ -0,0 +1,266 @@
+//! Kotlin and Swift bindings (`uniffi` feature)
+//!
+//! Exposes the market data side of the crate through
+//! [UniFFI](https://mozilla.github.io/uniffi-rs/), so Android and iOS apps
+//! get the same rate limiting, retries and models as Rust callers.
+//! [`MarketDataClient`] wraps an [`OandaClient`]; its methods are async and
+//! become `suspend` functions in Kotlin and `async` ones in Swift.
+//!
+//! Prices and amounts cross the boundary as decimal strings, since neither
+//! language has an exact decimal type UniFFI can map to. Times are
+//! timestamps (`java.time.Instant`, `Date`).
+//!
+//! Build the shared library with the `uniffi` feature, then generate the
+//! bindings from it:
+//!
+//! ```text
+//! cargo rustc --release --features uniffi --lib --crate-type cdylib
+//! cargo run --features uniffi-cli --bin uniffi-bindgen -- generate \
+//!     --library target/release/liboanda_connector.so --language kotlin --out-dir out
+//! ```
+
+use crate::client::OandaClient;
+use crate::config::OandaConfig;
+use crate::error::{Error, ErrorKind};
+use crate::models::{AccountSummary, Candle, Granularity, Instrument, Tick};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+/// Error seen by binding callers, grouped by what to do about it
+#[derive(Debug, thiserror::Error, uniffi::Error)]
+pub enum OandaError {
+    /// Network trouble or timeout; retry with backoff
+    #[error("{message}")]
+    Network { message: String },
+    /// Rate limit hit; retry later
+    #[error("{message}")]
+    RateLimited { message: String },
+    /// Credentials rejected
+    #[error("{message}")]
+    Auth { message: String },
+    /// Bad argument or configuration
+    #[error("{message}")]
+    InvalidRequest { message: String },
+    /// OANDA failed; retry with backoff
+    #[error("{message}")]
+    Server { message: String },
+    /// Response could not be understood
+    #[error("{message}")]
+    Data { message: String },
+}
+
+impl From<Error> for OandaError {
+    fn from(error: Error) -> Self {
+        let message = error.to_string();
+        match error.kind() {
+            ErrorKind::Transient => OandaError::Network { message },
+            ErrorKind::RateLimited => OandaError::RateLimited { message },
+            ErrorKind::Auth => OandaError::Auth { message },
+            ErrorKind::ClientBug => OandaError::InvalidRequest { message },
+            ErrorKind::ServerError => OandaError::Server { message },
+            ErrorKind::Data => OandaError::Data { message },
+        }
+    }
+}
+
+/// Latest price of an instrument
+#[derive(Debug, Clone, PartialEq, uniffi::Record)]
+pub struct PriceQuote {
+    pub instrument: String,
+    pub time: SystemTime,
+    pub bid: String,
+    pub ask: String,
+    pub mid: String,
+    pub spread: String,
+    pub tradeable: bool,
+}
+
+impl From<Tick> for PriceQuote {
+    fn from(tick: Tick) -> Self {
+        Self {
+            time: tick.timestamp.into(),
+            bid: tick.bid.to_string(),
+            ask: tick.ask.to_string(),
+            mid: tick.mid().to_string(),
+            spread: tick.spread().to_string(),
+            tradeable: tick.tradeable,
+            instrument: tick.instrument,
+        }
+    }
+}
+
+/// Mid-price candle
+#[derive(Debug, Clone, PartialEq, uniffi::Record)]
+pub struct CandleBar {
+    pub instrument: String,
+    pub time: SystemTime,
+    pub open: String,
+    pub high: String,
+    pub low: String,
+    pub close: String,
+    pub volume: i64,
+    pub complete: bool,
+}
+
+impl From<Candle> for CandleBar {
+    fn from(candle: Candle) -> Self {
+        Self {
+            time: candle.timestamp.into(),
+            open: candle.open.to_string(),
+            high: candle.high.to_string(),
+            low: candle.low.to_string(),
+            close: candle.close.to_string(),
+            volume: candle.volume,
+            complete: candle.complete,
+            instrument: candle.instrument,
+        }
+    }
+}
+
+/// Tradeable instrument
+#[derive(Debug, Clone, PartialEq, uniffi::Record)]
+pub struct InstrumentInfo {
+    pub name: String,
+    pub display_name: String,
+    pub pip_location: i32,
+    pub display_precision: Option<u32>,
+    pub minimum_trade_size: String,
+    pub maximum_trade_size: String,
+    pub margin_rate: String,
+}
+
+impl From<Instrument> for InstrumentInfo {
+    fn from(instrument: Instrument) -> Self {
+        Self {
+            name: instrument.name,
+            display_name: instrument.display_name,
+            pip_location: instrument.pip_location,
+            display_precision: instrument.display_precision,
+            minimum_trade_size: instrument.minimum_trade_size.to_string(),
+            maximum_trade_size: instrument.maximum_trade_size.to_string(),
+            margin_rate: instrument.margin_rate.to_string(),
+        }
+    }
+}
+
+/// Account balances, for portfolio views
+#[derive(Debug, Clone, PartialEq, uniffi::Record)]
+pub struct AccountOverview {
+    pub id: String,
+    pub currency: String,
+    pub balance: String,
+    pub nav: String,
+    pub unrealized_pl: String,
+    pub realized_pl: String,
+    pub margin_used: String,
+    pub margin_available: String,
+    pub open_trade_count: i32,
+    pub open_position_count: i32,
+}
+
+impl From<AccountSummary> for AccountOverview {
+    fn from(summary: AccountSummary) -> Self {
+        Self {
+            id: summary.id,
+            currency: summary.currency,
+            balance: summary.balance.to_string(),
+            nav: summary.nav.to_string(),
+            unrealized_pl: summary.unrealized_pl.to_string(),
+            realized_pl: summary.realized_pl.to_string(),
+            margin_used: summary.margin_used.to_string(),
+            margin_available: summary.margin_available.to_string(),
+            open_trade_count: summary.open_trade_count,
+            open_position_count: summary.open_position_count,
+        }
+    }
+}
+
+/// Market data and account view for mobile apps
+#[derive(uniffi::Object)]
+pub struct MarketDataClient {
+    client: OandaClient,
+}
+
+#[uniffi::export(async_runtime = "tokio")]
+impl MarketDataClient {
+    /// Client for an account; `practice` picks the demo environment
+    #[uniffi::constructor]
+    pub fn new(
+        api_key: String,
+        account_id: String,
+        practice: bool,
+    ) -> Result<Arc<Self>, OandaError> {
+        let client = OandaClient::new(OandaConfig::new(api_key, account_id, practice))?;
+        Ok(Arc::new(Self { client }))
+    }
+
+    pub async fn price(&self, instrument: String) -> Result<PriceQuote, OandaError> {
+        Ok(self.client.get_current_price(&instrument).await?.into())
+    }
+
+    pub async fn prices(&self, instruments: Vec<String>) -> Result<Vec<PriceQuote>, OandaError> {
+        let ticks = self.client.get_current_prices(&instruments).await?;
+        Ok(ticks.into_iter().map(PriceQuote::from).collect())
+    }
+
+    /// Latest `count` candles at a granularity such as `"M5"` or `"H1"`
+    pub async fn candles(
+        &self,
+        instrument: String,
+        granularity: String,
+        count: u32,
+    ) -> Result<Vec<CandleBar>, OandaError> {
+        let granularity: Granularity = granularity.parse()?;
+        let candles = self
+            .client
+            .get_candles(&instrument, granularity, count as usize)
+            .await?;
+        Ok(candles.into_iter().map(CandleBar::from).collect())
+    }
+
+    pub async fn instruments(&self) -> Result<Vec<InstrumentInfo>, OandaError> {
+        let instruments = self.client.get_instruments().await?;
+        Ok(instruments.into_iter().map(InstrumentInfo::from).collect())
+    }
+
+    pub async fn account_summary(&self) -> Result<AccountOverview, OandaError> {
+        Ok(self.client.get_account_summary().await?.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::PriceStatus;
+    use chrono::DateTime;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_records_keep_exact_prices() {
+        let timestamp = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let quote = PriceQuote::from(Tick {
+            instrument: "EUR_USD".to_string(),
+            timestamp,
+            bid: dec!(1.10000),
+            ask: dec!(1.10020),
+            tradeable: true,
+            status: PriceStatus::Tradeable,
+            closeout_bid: None,
+            closeout_ask: None,
+        });
+        assert_eq!(quote.bid, "1.10000");
+        assert_eq!(quote.mid, "1.10010");
+        assert_eq!(quote.spread, "0.00020");
+        assert_eq!(quote.time, SystemTime::from(timestamp));
+    }
+
+    #[test]
+    fn test_errors_grouped_by_kind() {
+        let error = OandaError::from("M7".parse::<Granularity>().unwrap_err());
+        assert!(matches!(error, OandaError::InvalidRequest { .. }));
+        assert!(matches!(
+            OandaError::from(Error::AuthenticationFailed),
+            OandaError::Auth { .. }
+        ));
+    }
+}
@@ -0,0 +1,426 @@
+//! Blocking client for code that isn't async
+//!
+//! `blocking::OandaClient` wraps the async client in its own Tokio runtime,
+//! in the spirit of `reqwest::blocking`. Each call blocks the current thread
+//! until the request (including retries) completes.
+//!
+//! Don't use it from inside an async runtime: blocking a runtime thread
+//! panics. Async code should use [`crate::OandaClient`] directly.
+//!
+//! ```no_run
+//! use oanda_connector::blocking::OandaClient;
+//! use oanda_connector::OandaConfig;
+//!
+//! # fn main() -> oanda_connector::Result<()> {
+//! let client = OandaClient::new(OandaConfig::from_env()?)?;
+//! let tick = client.get_current_price("EUR_USD")?;
+//! println!("EUR/USD: {}", tick.mid());
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::client::{OandaClient as AsyncClient, OandaClientBuilder};
+use crate::config::{OandaConfig, RuntimeSettings};
+use crate::endpoints::EndpointGroup;
+use crate::error::{Error, Result};
+use crate::ids::{TradeId, TransactionId};
+use crate::models::{
+    AccountSummary, Candle, Granularity, Instrument, LatencyReport, OrderBook, Position,
+    PositionBook, PricingOptions, PricingSnapshot, Quote, Tick, Trade,
+};
+use crate::orders::{
+    ClosePositionResponse, FlattenOptions, FlattenReport, Order, OrderRequest, OrderResponse,
+    OrderSpecifier,
+};
+use crate::rate_limiter::Priority;
+use crate::reconcile::StateDiff;
+use crate::stats::EndpointStats;
+use crate::transactions::{Transaction, TransactionFilter};
+use chrono::{DateTime, Utc};
+use futures::{Stream, StreamExt};
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::runtime::Runtime;
+
+/// Synchronous OANDA client
+///
+/// Cheap to clone; clones share the runtime, rate limiter and settings.
+#[derive(Clone)]
+pub struct OandaClient {
+    inner: AsyncClient,
+    runtime: Arc<Runtime>,
+}
+
+impl OandaClient {
+    /// Create new blocking client
+    pub fn new(config: OandaConfig) -> Result<Self> {
+        Self::from_builder(OandaClientBuilder::new(config))
+    }
+
+    /// Create a blocking client from a configured async client builder
+    pub fn from_builder(builder: OandaClientBuilder) -> Result<Self> {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(|e| Error::ConfigError(format!("Failed to start runtime: {}", e)))?;
+
+        // Inside the runtime so background parts of the client find it
+        let inner = runtime.block_on(async { builder.build() })?;
+
+        Ok(Self {
+            inner,
+            runtime: Arc::new(runtime),
+        })
+    }
+
+    /// The async client this wraps
+    pub fn inner(&self) -> &AsyncClient {
+        &self.inner
+    }
+
+    /// Handle whose requests use `timeout` instead of the configured ones
+    pub fn with_timeout(&self, timeout: Duration) -> Self {
+        Self {
+            inner: self.inner.with_timeout(timeout),
+            runtime: self.runtime.clone(),
+        }
+    }
+
+    /// Handle whose requests queue for the rate limiter at `priority`
+    pub fn with_priority(&self, priority: Priority) -> Self {
+        Self {
+            inner: self.inner.with_priority(priority),
+            runtime: self.runtime.clone(),
+        }
+    }
+
+    fn block_on<F: std::future::Future>(&self, future: F) -> F::Output {
+        self.runtime.block_on(future)
+    }
+
+    /// Replace the API key used for subsequent requests
+    pub fn update_credentials(&self, api_key: impl Into<String>) -> Result<()> {
+        self.inner.update_credentials(api_key)
+    }
+
+    /// Current rate limit, timeout and retry settings
+    pub fn settings(&self) -> RuntimeSettings {
+        self.inner.settings()
+    }
+
+    /// Change rate limit, timeout and retry settings at runtime
+    pub fn apply_settings(&self, settings: RuntimeSettings) -> Result<()> {
+        self.inner.apply_settings(settings)
+    }
+
+    /// Rolling latency and error statistics per endpoint group
+    pub fn stats(&self) -> HashMap<EndpointGroup, EndpointStats> {
+        self.inner.stats()
+    }
+
+    /// Get current price for instrument
+    pub fn get_current_price(&self, instrument: &str) -> Result<Tick> {
+        self.block_on(self.inner.get_current_price(instrument))
+    }
+
+    /// Get multiple current prices
+    pub fn get_current_prices(&self, instruments: &[String]) -> Result<Vec<Tick>> {
+        self.block_on(self.inner.get_current_prices(instruments))
+    }
+
+    /// Get prices with conversion factors and home-currency data
+    pub fn get_pricing(
+        &self,
+        instruments: &[String],
+        options: &PricingOptions,
+    ) -> Result<PricingSnapshot> {
+        self.block_on(self.inner.get_pricing(instruments, options))
+    }
+
+    /// Measure round-trip time and clock skew against the API
+    pub fn measure_latency(&self, probes: usize) -> Result<LatencyReport> {
+        self.block_on(self.inner.measure_latency(probes))
+    }
+
+    /// Get the full quote for an instrument
+    pub fn get_quote(&self, instrument: &str) -> Result<Quote> {
+        self.block_on(self.inner.get_quote(instrument))
+    }
+
+    /// Get full quotes for several instruments
+    pub fn get_quotes(&self, instruments: &[String]) -> Result<Vec<Quote>> {
+        self.block_on(self.inner.get_quotes(instruments))
+    }
+
+    /// Get historical candles
+    pub fn get_candles(
+        &self,
+        instrument: &str,
+        granularity: Granularity,
+        count: usize,
+    ) -> Result<Vec<Candle>> {
+        self.block_on(self.inner.get_candles(instrument, granularity, count))
+    }
+
+    /// Get candles for a time range (RFC3339 bounds)
+    pub fn get_candles_range(
+        &self,
+        instrument: &str,
+        granularity: Granularity,
+        from: &str,
+        to: &str,
+    ) -> Result<Vec<Candle>> {
+        self.block_on(
+            self.inner
+                .get_candles_range(instrument, granularity, from, to),
+        )
+    }
+
+    /// Get OANDA's aggregated order book for an instrument
+    pub fn get_order_book(
+        &self,
+        instrument: &str,
+        time: Option<DateTime<Utc>>,
+    ) -> Result<OrderBook> {
+        self.block_on(self.inner.get_order_book(instrument, time))
+    }
+
+    /// Get OANDA's aggregated position book for an instrument
+    pub fn get_position_book(
+        &self,
+        instrument: &str,
+        time: Option<DateTime<Utc>>,
+    ) -> Result<PositionBook> {
+        self.block_on(self.inner.get_position_book(instrument, time))
+    }
+
+    /// Get account summary
+    pub fn get_account_summary(&self) -> Result<AccountSummary> {
+        self.block_on(self.inner.get_account_summary())
+    }
+
+    /// Get open trades
+    pub fn get_open_trades(&self) -> Result<Vec<Trade>> {
+        self.block_on(self.inner.get_open_trades())
+    }
+
+    /// Get positions with units held
+    pub fn get_open_positions(&self) -> Result<Vec<Position>> {
+        self.block_on(self.inner.get_open_positions())
+    }
+
+    /// Get pending orders, including those attached to open trades
+    pub fn get_pending_orders(&self) -> Result<Vec<Order>> {
+        self.block_on(self.inner.get_pending_orders())
+    }
+
+    /// Get tradeable instruments for the account
+    pub fn get_instruments(&self) -> Result<Vec<Instrument>> {
+        self.block_on(self.inner.get_instruments())
+    }
+
+    /// Drop the cached instrument list so the next call fetches it again
+    pub fn clear_instrument_cache(&self) {
+        self.inner.clear_instrument_cache()
+    }
+
+    /// Get transactions after an ID
+    pub fn get_transactions_since(&self, id: &TransactionId) -> Result<Vec<Transaction>> {
+        self.block_on(self.inner.get_transactions_since(id))
+    }
+
+    /// Latest transaction ID reported by the API to this client
+    pub fn last_transaction_id(&self) -> Option<TransactionId> {
+        self.inner.last_transaction_id()
+    }
+
+    /// Get transactions after the last transaction ID this client saw
+    pub fn get_transactions_since_last(&self) -> Result<Vec<Transaction>> {
+        self.block_on(self.inner.get_transactions_since_last())
+    }
+
+    /// Get transactions after an ID, limited to some types
+    pub fn get_transactions_since_filtered(
+        &self,
+        id: &TransactionId,
+        filter: &TransactionFilter,
+    ) -> Result<Vec<Transaction>> {
+        self.block_on(self.inner.get_transactions_since_filtered(id, filter))
+    }
+
+    /// Changes to orders, trades and positions since a transaction
+    pub fn reconcile_state(&self, since: &TransactionId) -> Result<StateDiff> {
+        self.block_on(self.inner.reconcile_state(since))
+    }
+
+    /// Get transactions in an ID range
+    pub fn get_transactions_range(
+        &self,
+        from: &TransactionId,
+        to: &TransactionId,
+    ) -> Result<Vec<Transaction>> {
+        self.block_on(self.inner.get_transactions_range(from, to))
+    }
+
+    /// Get transactions in an ID range, limited to some types
+    pub fn get_transactions_range_filtered(
+        &self,
+        from: &TransactionId,
+        to: &TransactionId,
+        filter: &TransactionFilter,
+    ) -> Result<Vec<Transaction>> {
+        self.block_on(self.inner.get_transactions_range_filtered(from, to, filter))
+    }
+
+    /// Iterate over transactions in an ID range, a page at a time
+    pub fn transaction_history<'a>(
+        &'a self,
+        from: &'a TransactionId,
+        to: &'a TransactionId,
+    ) -> BlockingIter<'a, Result<Transaction>> {
+        BlockingIter::new(
+            &self.runtime,
+            self.inner.stream_transaction_history(from, to),
+        )
+    }
+
+    /// Iterate over transactions between two times, a page at a time
+    pub fn transactions_between(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        filter: TransactionFilter,
+    ) -> BlockingIter<'_, Result<Transaction>> {
+        BlockingIter::new(
+            &self.runtime,
+            self.inner.stream_transactions_between(from, to, filter),
+        )
+    }
+
+    /// Submit an order (never retried)
+    pub fn place_order(&self, order: &OrderRequest) -> Result<OrderResponse> {
+        self.block_on(self.inner.place_order(order))
+    }
+
+    /// Get one order by ID or client ID
+    pub fn get_order(&self, order: impl Into<OrderSpecifier>) -> Result<Order> {
+        self.block_on(self.inner.get_order(order))
+    }
+
+    /// Replace a pending order (never retried)
+    pub fn replace_order(
+        &self,
+        order: impl Into<OrderSpecifier>,
+        replacement: &OrderRequest,
+    ) -> Result<OrderResponse> {
+        self.block_on(self.inner.replace_order(order, replacement))
+    }
+
+    /// Cancel a pending order (never retried)
+    pub fn cancel_order(&self, order: impl Into<OrderSpecifier>) -> Result<OrderResponse> {
+        self.block_on(self.inner.cancel_order(order))
+    }
+
+    /// Close an open trade at market (never retried)
+    pub fn close_trade(&self, trade: &TradeId, units: Option<Decimal>) -> Result<OrderResponse> {
+        self.block_on(self.inner.close_trade(trade, units))
+    }
+
+    /// Close both sides of a position at market (never retried)
+    pub fn close_position(&self, position: &Position) -> Result<ClosePositionResponse> {
+        self.block_on(self.inner.close_position(position))
+    }
+
+    /// Cancel every pending order, then close every open position
+    pub fn flatten_all(&self) -> Result<FlattenReport> {
+        self.block_on(self.inner.flatten_all())
+    }
+
+    /// Flatten the account with explicit concurrency and retries
+    pub fn flatten_all_with(&self, options: &FlattenOptions) -> Result<FlattenReport> {
+        self.block_on(self.inner.flatten_all_with(options))
+    }
+
+    /// GET any API path, returning the JSON body untyped
+    pub fn get_raw(&self, path: &str, query: &[(&str, &str)]) -> Result<serde_json::Value> {
+        self.block_on(self.inner.get_raw(path, query))
+    }
+
+    /// POST a JSON body to any API path (never retried)
+    pub fn post_raw(&self, path: &str, body: &serde_json::Value) -> Result<serde_json::Value> {
+        self.block_on(self.inner.post_raw(path, body))
+    }
+
+    /// Check if client is connected and authenticated
+    pub fn health_check(&self) -> Result<bool> {
+        self.block_on(self.inner.health_check())
+    }
+}
+
+/// Iterator that fetches from an async stream as it is advanced
+pub struct BlockingIter<'a, T> {
+    runtime: &'a Runtime,
+    stream: Pin<Box<dyn Stream<Item = T> + 'a>>,
+}
+
+impl<'a, T> BlockingIter<'a, T> {
+    fn new(runtime: &'a Runtime, stream: impl Stream<Item = T> + 'a) -> Self {
+        Self {
+            runtime,
+            stream: Box::pin(stream),
+        }
+    }
+}
+
+impl<T> Iterator for BlockingIter<'_, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.runtime.block_on(self.stream.next())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_blocking_account_summary() {
+        let mut server = mockito::Server::new();
+        let mock = server
+            .mock("GET", "/v3/accounts/test_account_id")
+            .with_status(200)
+            .with_body(
+                r#"{
+                "account": {
+                    "id": "test_account_id",
+                    "balance": "100000.0000",
+                    "NAV": "100000.0000",
+                    "unrealizedPL": "0.0000",
+                    "pl": "0.0000",
+                    "marginUsed": "0.0000",
+                    "marginAvailable": "100000.0000",
+                    "openTradeCount": 0,
+                    "openPositionCount": 0,
+                    "currency": "USD"
+                },
+                "lastTransactionID": "1"
+            }"#,
+            )
+            .create();
+
+        let mut config = OandaConfig::new(
+            "test_api_key".to_string(),
+            "test_account_id".to_string(),
+            true,
+        );
+        config.base_url = Some(server.url());
+        let client = OandaClient::new(config).unwrap();
+
+        assert_eq!(client.get_account_summary().unwrap().currency, "USD");
+        mock.assert();
+    }
+}
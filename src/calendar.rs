@@ -0,0 +1,299 @@
+//! FX market hours and holidays
+//!
+//! The FX market trades around the clock from 17:00 New York time on
+//! Sunday until 17:00 New York time on Friday, which is 21:00 or 22:00 UTC
+//! depending on US daylight saving time. Each trading day runs from 17:00
+//! New York time on the previous day, matching OANDA's daily candles, and
+//! a holiday closes the whole trading day: with the default calendar the
+//! market shuts at 17:00 on December 24th and reopens at 17:00 on the 25th.
+//!
+//! [`Session`] splits the trading week into the Sydney, Tokyo, London and
+//! New York sessions, each following its own city's daylight saving time.
+//!
+//! ```
+//! use chrono::{TimeZone, Utc};
+//! use oanda_connector::calendar::{self, MarketCalendar};
+//!
+//! // Saturday
+//! let now = Utc.with_ymd_and_hms(2025, 7, 5, 12, 0, 0).unwrap();
+//! assert!(!calendar::is_market_open(now));
+//! assert_eq!(
+//!     calendar::next_open(now),
+//!     Utc.with_ymd_and_hms(2025, 7, 6, 21, 0, 0).unwrap()
+//! );
+//!
+//! let calendar = MarketCalendar::default().with_holiday(2025, 7, 4);
+//! assert!(!calendar.is_open(Utc.with_ymd_and_hms(2025, 7, 3, 22, 0, 0).unwrap()));
+//! ```
+//!
+//! Sessions work as filters on candle series:
+//!
+//! ```
+//! use oanda_connector::calendar::Session;
+//! # use oanda_connector::Candle;
+//! # fn run(candles: Vec<Candle>) {
+//! let london_ny: Vec<Candle> = candles
+//!     .into_iter()
+//!     .filter(|c| Session::London.is_active(c.timestamp) && Session::NewYork.is_active(c.timestamp))
+//!     .collect();
+//! # }
+//! ```
+
+use crate::models::CandleAlignment;
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Timelike, Utc, Weekday};
+use std::collections::BTreeSet;
+
+/// Trading days the market is closed on
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MarketCalendar {
+    /// (month, day) closed every year
+    pub annual_holidays: Vec<(u32, u32)>,
+    /// One-off closures
+    pub holidays: BTreeSet<NaiveDate>,
+}
+
+impl Default for MarketCalendar {
+    /// Closed on Christmas Day and New Year's Day
+    fn default() -> Self {
+        Self {
+            annual_holidays: vec![(12, 25), (1, 1)],
+            holidays: BTreeSet::new(),
+        }
+    }
+}
+
+impl MarketCalendar {
+    /// Close the market for one more trading day
+    pub fn with_holiday(mut self, year: i32, month: u32, day: u32) -> Self {
+        if let Some(date) = NaiveDate::from_ymd_opt(year, month, day) {
+            self.holidays.insert(date);
+        }
+        self
+    }
+
+    /// Trading day `time` falls in
+    pub fn trading_date(&self, time: DateTime<Utc>) -> NaiveDate {
+        let alignment = CandleAlignment::default();
+        let start = alignment.day_start(time);
+        // The day starting at 17:00 is named after the following date
+        (start + Duration::hours(12)).date_naive()
+    }
+
+    /// Whether the market trades on a trading day
+    pub fn is_trading_day(&self, date: NaiveDate) -> bool {
+        !matches!(date.weekday(), Weekday::Sat | Weekday::Sun)
+            && !self.holidays.contains(&date)
+            && !self.annual_holidays.contains(&(date.month(), date.day()))
+    }
+
+    pub fn is_open(&self, time: DateTime<Utc>) -> bool {
+        self.is_trading_day(self.trading_date(time))
+    }
+
+    /// `time` if the market is open, otherwise when it next opens
+    pub fn next_open(&self, time: DateTime<Utc>) -> DateTime<Utc> {
+        let mut date = self.trading_date(time);
+        while !self.is_trading_day(date) {
+            date = date.succ_opt().unwrap_or(NaiveDate::MAX);
+        }
+        day_start(date).max(time)
+    }
+
+    /// When the current or next session ends
+    pub fn next_close(&self, time: DateTime<Utc>) -> DateTime<Utc> {
+        let mut date = self.trading_date(self.next_open(time));
+        while self.is_trading_day(date) {
+            date = date.succ_opt().unwrap_or(NaiveDate::MAX);
+        }
+        day_start(date)
+    }
+}
+
+/// Start of a trading day: 17:00 New York time the day before
+fn day_start(date: NaiveDate) -> DateTime<Utc> {
+    CandleAlignment::default().at_alignment(date.pred_opt().unwrap_or(date))
+}
+
+/// Whether the market is open at `now` under the default calendar
+pub fn is_market_open(now: DateTime<Utc>) -> bool {
+    MarketCalendar::default().is_open(now)
+}
+
+/// When the market next opens under the default calendar (`now` if open)
+pub fn next_open(now: DateTime<Utc>) -> DateTime<Utc> {
+    MarketCalendar::default().next_open(now)
+}
+
+/// Regional trading session
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Session {
+    Sydney,
+    Tokyo,
+    London,
+    NewYork,
+}
+
+impl Session {
+    /// Every session, in the order they open each day
+    pub const ALL: [Session; 4] = [
+        Session::Sydney,
+        Session::Tokyo,
+        Session::London,
+        Session::NewYork,
+    ];
+
+    /// Opening and closing hour in the session's local time
+    pub fn local_hours(&self) -> (u32, u32) {
+        match self {
+            Session::Sydney => (7, 16),
+            Session::Tokyo => (9, 18),
+            Session::London => (8, 17),
+            Session::NewYork => (8, 17),
+        }
+    }
+
+    /// Whether the session is trading at `time`
+    ///
+    /// Sessions run on local weekdays while the market is open under the
+    /// default calendar.
+    pub fn is_active(&self, time: DateTime<Utc>) -> bool {
+        let local = time + self.utc_offset(time);
+        let (open, close) = self.local_hours();
+        !matches!(local.weekday(), Weekday::Sat | Weekday::Sun)
+            && (open..close).contains(&local.hour())
+            && is_market_open(time)
+    }
+
+    /// Offset of the session's city from UTC at an instant
+    fn utc_offset(&self, time: DateTime<Utc>) -> Duration {
+        let year = time.year();
+        let sunday = |month, n| {
+            NaiveDate::from_weekday_of_month_opt(year, month, Weekday::Sun, n)
+                .or_else(|| NaiveDate::from_weekday_of_month_opt(year, month, Weekday::Sun, 4))
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+                .map(|date| date.and_utc())
+        };
+        match self {
+            // AEDT from 02:00 local on the first Sunday of October until
+            // 03:00 local on the first Sunday of April
+            Session::Sydney => match (sunday(4, 1), sunday(10, 1)) {
+                (Some(end), Some(start))
+                    if time < end - Duration::hours(8) || time >= start - Duration::hours(8) =>
+                {
+                    Duration::hours(11)
+                }
+                _ => Duration::hours(10),
+            },
+            Session::Tokyo => Duration::hours(9),
+            // BST from 01:00 UTC on the last Sunday of March until 01:00 UTC
+            // on the last Sunday of October
+            Session::London => match (sunday(3, 5), sunday(10, 5)) {
+                (Some(start), Some(end))
+                    if time >= start + Duration::hours(1) && time < end + Duration::hours(1) =>
+                {
+                    Duration::hours(1)
+                }
+                _ => Duration::zero(),
+            },
+            Session::NewYork => CandleAlignment::default().utc_offset(time),
+        }
+    }
+}
+
+impl std::fmt::Display for Session {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Session::Sydney => "Sydney",
+            Session::Tokyo => "Tokyo",
+            Session::London => "London",
+            Session::NewYork => "New York",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Sessions trading at `time`, in opening order
+pub fn active_sessions(time: DateTime<Utc>) -> Vec<Session> {
+    Session::ALL
+        .into_iter()
+        .filter(|session| session.is_active(time))
+        .collect()
+}
+
+/// Whether two or more sessions are trading at `time`
+pub fn is_overlap(time: DateTime<Utc>) -> bool {
+    active_sessions(time).len() > 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn utc(month: u32, day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2025, month, day, hour, minute, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_weekend_follows_new_york_dst() {
+        // Summer: Friday close and Sunday open at 21:00 UTC
+        assert!(is_market_open(utc(7, 11, 20, 59)));
+        assert!(!is_market_open(utc(7, 11, 21, 0)));
+        assert!(!is_market_open(utc(7, 13, 20, 59)));
+        assert!(is_market_open(utc(7, 13, 21, 0)));
+        // Winter: an hour later
+        assert!(is_market_open(utc(1, 17, 21, 30)));
+        assert!(!is_market_open(utc(1, 17, 22, 0)));
+        assert_eq!(next_open(utc(1, 18, 9, 0)), utc(1, 19, 22, 0));
+        assert_eq!(next_open(utc(1, 15, 9, 0)), utc(1, 15, 9, 0));
+    }
+
+    #[test]
+    fn test_holidays() {
+        let calendar = MarketCalendar::default();
+        // Christmas: closed from 17:00 New York on the 24th to 17:00 on the 25th
+        assert!(calendar.is_open(utc(12, 24, 21, 59)));
+        assert!(!calendar.is_open(utc(12, 24, 22, 0)));
+        assert_eq!(calendar.next_open(utc(12, 25, 3, 0)), utc(12, 25, 22, 0));
+        assert_eq!(calendar.next_close(utc(12, 23, 3, 0)), utc(12, 24, 22, 0));
+        // New Year's Day 2027 is a Friday, so the market stays shut until Sunday
+        let new_year = Utc.with_ymd_and_hms(2026, 12, 31, 23, 0, 0).unwrap();
+        assert_eq!(
+            calendar.next_open(new_year),
+            Utc.with_ymd_and_hms(2027, 1, 3, 22, 0, 0).unwrap()
+        );
+
+        let calendar = calendar.with_holiday(2025, 7, 4);
+        assert_eq!(calendar.next_close(utc(7, 2, 12, 0)), utc(7, 3, 21, 0));
+        assert_eq!(calendar.next_open(utc(7, 4, 12, 0)), utc(7, 6, 21, 0));
+    }
+
+    #[test]
+    fn test_sessions_follow_local_dst() {
+        // July: London is on BST, Sydney on standard time
+        assert_eq!(
+            active_sessions(utc(7, 9, 5, 0)),
+            vec![Session::Sydney, Session::Tokyo]
+        );
+        assert_eq!(active_sessions(utc(7, 9, 6, 0)), vec![Session::Tokyo]);
+        assert_eq!(
+            active_sessions(utc(7, 9, 7, 30)),
+            vec![Session::Tokyo, Session::London]
+        );
+        assert_eq!(
+            active_sessions(utc(7, 9, 12, 0)),
+            vec![Session::London, Session::NewYork]
+        );
+        assert!(!is_overlap(utc(7, 9, 17, 0)));
+        // January: London opens at 08:00 UTC, Sydney closes at 05:00 UTC
+        assert!(!Session::London.is_active(utc(1, 15, 7, 30)));
+        assert!(Session::London.is_active(utc(1, 15, 8, 0)));
+        assert!(Session::Sydney.is_active(utc(1, 15, 4, 59)));
+        assert!(!Session::Sydney.is_active(utc(1, 15, 5, 0)));
+        // Nothing trades on Saturday, nor during the Sydney Monday morning
+        // before New York opens the week
+        assert!(active_sessions(utc(7, 12, 9, 0)).is_empty());
+        assert!(!Session::Sydney.is_active(utc(1, 19, 20, 30)));
+    }
+}
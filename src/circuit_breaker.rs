@@ -0,0 +1,119 @@
+//! Circuit breaker for failing API hosts
+//!
+//! After `failure_threshold` consecutive failed requests the circuit opens
+//! and requests fail immediately with `Error::CircuitOpen` instead of adding
+//! load to a struggling API. Once the cool-down has passed one probe request
+//! is let through (half-open): success closes the circuit, failure opens it
+//! for another cool-down.
+
+use crate::error::{Error, Result};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Closed { failures: u32 },
+    Open { until: Instant },
+    HalfOpen { since: Instant },
+}
+
+/// Consecutive-failure circuit breaker
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    failure_threshold: u32,
+    cooldown: Duration,
+    state: Mutex<State>,
+}
+
+impl CircuitBreaker {
+    /// Create a closed circuit breaker
+    pub fn new(failure_threshold: u32, cooldown: Duration) -> Self {
+        Self {
+            failure_threshold,
+            cooldown,
+            state: Mutex::new(State::Closed { failures: 0 }),
+        }
+    }
+
+    /// Check whether a request may be sent
+    ///
+    /// While half-open only the probe request is allowed. A probe that never
+    /// reports back (e.g. a cancelled future) is replaced after the cool-down.
+    pub fn check(&self) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        let now = Instant::now();
+
+        match *state {
+            State::Closed { .. } => Ok(()),
+            State::Open { until } | State::HalfOpen { since: until } if now < until => {
+                Err(Error::CircuitOpen {
+                    retry_after_seconds: (until - now).as_secs_f64().ceil() as u64,
+                })
+            }
+            State::Open { .. } | State::HalfOpen { .. } => {
+                *state = State::HalfOpen {
+                    since: now + self.cooldown,
+                };
+                Ok(())
+            }
+        }
+    }
+
+    /// Record a successful request, closing the circuit
+    pub fn record_success(&self) {
+        *self.state.lock().unwrap() = State::Closed { failures: 0 };
+    }
+
+    /// Record a failed request
+    pub fn record_failure(&self) {
+        let mut state = self.state.lock().unwrap();
+        let open = State::Open {
+            until: Instant::now() + self.cooldown,
+        };
+
+        *state = match *state {
+            State::Closed { failures } if failures + 1 < self.failure_threshold => State::Closed {
+                failures: failures + 1,
+            },
+            State::Closed { .. } | State::HalfOpen { .. } => open,
+            State::Open { until } => State::Open { until },
+        };
+    }
+
+    /// Whether requests are currently being rejected
+    pub fn is_open(&self) -> bool {
+        !matches!(*self.state.lock().unwrap(), State::Closed { .. })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_opens_after_threshold_and_probes() {
+        let breaker = CircuitBreaker::new(2, Duration::from_millis(50));
+
+        breaker.record_failure();
+        assert!(breaker.check().is_ok());
+        breaker.record_success();
+        breaker.record_failure();
+        assert!(breaker.check().is_ok());
+        breaker.record_failure();
+        assert!(matches!(breaker.check(), Err(Error::CircuitOpen { .. })));
+
+        // Half-open: one probe, everyone else still rejected
+        std::thread::sleep(Duration::from_millis(60));
+        assert!(breaker.check().is_ok());
+        assert!(breaker.check().is_err());
+
+        // Failed probe reopens, successful probe closes
+        breaker.record_failure();
+        assert!(breaker.check().is_err());
+        std::thread::sleep(Duration::from_millis(60));
+        assert!(breaker.check().is_ok());
+        breaker.record_success();
+        assert!(!breaker.is_open());
+        assert!(breaker.check().is_ok());
+    }
+}
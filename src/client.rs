@@ -1,43 +1,371 @@
 //! OANDA API client implementation
 
 use crate::{
-    config::OandaConfig,
-    endpoints::Endpoints,
-    error::{Error, Result},
+    circuit_breaker::CircuitBreaker,
+    config::{CircuitBreakerConfig, OandaConfig, ProxyConfig, RetryPolicy, RuntimeSettings},
+    datetime::DatetimeFormat,
+    endpoints::{EndpointGroup, Endpoints},
+    error::{Error, RequestContext, Result},
+    ids::{TradeId, TransactionId},
+    middleware::{Middleware, Next},
     models::*,
-    rate_limiter::RateLimiter,
+    orders::{
+        ClosePositionResponse, FlattenOptions, FlattenReport, Order, OrderRejectBody, OrderRequest,
+        OrderResponse, OrderSpecifier, PendingOrdersResponse,
+    },
+    rate_limiter::{ConnectionBudget, Priority, RateLimiter, StreamPermit},
+    reconcile::{AccountChangesResponse, StateDiff},
+    response::{request_id, ResponseEnvelope, ResponseMetadata},
+    stats::{EndpointStats, LatencyTracker},
+    transactions::{Transaction, TransactionFilter},
+    transport::Transport,
 };
-use reqwest::{Client as HttpClient, Response, StatusCode};
-use std::sync::Arc;
-use tokio::time::{sleep, Duration};
+use chrono::{DateTime, SecondsFormat, Utc};
+use futures::{stream, Future, Stream, StreamExt, TryStreamExt};
+use reqwest::header::{HeaderValue, ETAG, IF_NONE_MATCH};
+use reqwest::{Client as HttpClient, RequestBuilder, Response, StatusCode};
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, RwLock};
+use tokio::sync::Semaphore;
+use tokio::time::{sleep, Duration, Instant};
+
+/// Number of transaction IDs requested per page when walking history
+const TRANSACTION_PAGE_SIZE: u64 = 1000;
 
 /// OANDA API client
 #[derive(Clone)]
 pub struct OandaClient {
     http_client: HttpClient,
     config: Arc<OandaConfig>,
+    api_key: Arc<RwLock<String>>,
+    settings: Arc<RwLock<RuntimeSettings>>,
     rate_limiter: Arc<RateLimiter>,
+    owns_rate_limiter: bool,
+    stream_budget: ConnectionBudget,
+    in_flight: Option<Arc<Semaphore>>,
+    middleware: Arc<[Arc<dyn Middleware>]>,
+    transport: Arc<dyn Transport>,
+    circuit_breaker: Option<Arc<CircuitBreaker>>,
+    timeout_override: Option<Duration>,
+    priority_override: Option<Priority>,
+    last_transaction_id: Arc<RwLock<Option<TransactionId>>>,
+    instrument_cache: Arc<Mutex<Option<InstrumentCache>>>,
+    latency: LatencyTracker,
+    metadata: Option<Arc<Mutex<Option<ResponseMetadata>>>>,
+}
+
+/// Last instrument list fetched, with its validator
+struct InstrumentCache {
+    instruments: Vec<Instrument>,
+    etag: Option<HeaderValue>,
+    fetched_at: Instant,
 }
 
 impl OandaClient {
     /// Create new OANDA client
     pub fn new(config: OandaConfig) -> Result<Self> {
+        OandaClientBuilder::new(config).build()
+    }
+    
+    /// Create a client drawing on an existing rate limiter
+    /// 
+    /// OANDA enforces limits per access token, so clients for several
+    /// accounts or configs using one token should share one budget. The
+    /// shared limiter keeps its own quotas: the config's rate settings,
+    /// including later `apply_settings` calls, don't change it.
+    /// 
+    /// ```no_run
+    /// use oanda_connector::{rate_limiter::RateLimiter, OandaClient, OandaConfig};
+    /// use std::sync::Arc;
+    /// 
+    /// # fn main() -> oanda_connector::Result<()> {
+    /// let limiter = Arc::new(RateLimiter::new(100, 100));
+    /// let primary = OandaClient::with_rate_limiter(
+    ///     OandaConfig::new("token".to_string(), "101-001-1-001".to_string(), true),
+    ///     limiter.clone(),
+    /// )?;
+    /// let hedge = OandaClient::with_rate_limiter(
+    ///     OandaConfig::new("token".to_string(), "101-001-1-002".to_string(), true),
+    ///     limiter,
+    /// )?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_rate_limiter(config: OandaConfig, rate_limiter: Arc<RateLimiter>) -> Result<Self> {
+        OandaClientBuilder::new(config).rate_limiter(rate_limiter).build()
+    }
+    
+    fn create(builder: OandaClientBuilder) -> Result<Self> {
+        let OandaClientBuilder { config, rate_limiter: shared_rate_limiter, middleware, transport, http_client } = builder;
         config.validate()?;
         
-        let http_client = HttpClient::builder()
-            .timeout(config.timeout())
-            .build()
-            .map_err(Error::HttpError)?;
+        let http_client = match http_client {
+            Some(http_client) => http_client,
+            None => Self::build_http_client(&config)?,
+        };
         
-        let rate_limiter = Arc::new(RateLimiter::new(config.requests_per_second));
+        let settings = RuntimeSettings::from(&config);
+        let owns_rate_limiter = shared_rate_limiter.is_none();
+        let rate_limiter = shared_rate_limiter.unwrap_or_else(|| {
+            let rate_limiter = Arc::new(RateLimiter::new(settings.requests_per_second, settings.burst()));
+            for group in EndpointGroup::ALL {
+                rate_limiter.set_group_rate(
+                    group,
+                    settings.rate_limits.get(group),
+                    settings.rate_limits.burst(group, settings.burst()),
+                );
+            }
+            rate_limiter
+        });
+        let transport = transport.unwrap_or_else(|| Arc::new(http_client.clone()));
+        let circuit_breaker = config.circuit_breaker.as_ref().map(|breaker| Arc::new(
+            CircuitBreaker::new(breaker.failure_threshold, Duration::from_secs(breaker.cooldown_seconds))
+        ));
         
         Ok(Self {
             http_client,
+            api_key: Arc::new(RwLock::new(config.api_key.clone())),
+            settings: Arc::new(RwLock::new(settings)),
+            stream_budget: ConnectionBudget::new(config.max_streams),
+            in_flight: config.max_in_flight.map(|max| Arc::new(Semaphore::new(max as usize))),
+            middleware: middleware.into(),
+            transport,
             config: Arc::new(config),
             rate_limiter,
+            owns_rate_limiter,
+            circuit_breaker,
+            timeout_override: None,
+            priority_override: None,
+            last_transaction_id: Arc::new(RwLock::new(None)),
+            instrument_cache: Arc::new(Mutex::new(None)),
+            latency: LatencyTracker::default(),
+            metadata: None,
+        })
+    }
+    
+    /// reqwest client with the config's transport settings
+    fn build_http_client(config: &OandaConfig) -> Result<HttpClient> {
+        let mut http_builder = config.tls.apply(config.connection.apply(
+            HttpClient::builder()
+                .user_agent(config.user_agent())
+                .default_headers(config.header_map()?)
+                .gzip(config.compression)
+                .brotli(config.compression)
+        ))?;
+        if let Some(proxy) = &config.proxy {
+            http_builder = http_builder.proxy(proxy.to_proxy()?);
+        }
+        
+        http_builder
+            .build()
+            .map_err(Error::HttpError)
+    }
+    
+    /// Replace the API key used for subsequent requests
+    /// 
+    /// Applies to every clone of this client, including requests that are
+    /// retried after the update. Requests already sent are not affected.
+    pub fn update_credentials(&self, api_key: impl Into<String>) -> Result<()> {
+        let api_key = api_key.into();
+        if api_key.is_empty() {
+            return Err(Error::ConfigError("API key cannot be empty".to_string()));
+        }
+        
+        *self.api_key.write().unwrap() = api_key;
+        Ok(())
+    }
+    
+    /// Account this client trades on
+    pub fn account_id(&self) -> &str {
+        &self.config.account_id
+    }
+    
+    /// Current rate limit, timeout and retry settings
+    pub fn settings(&self) -> RuntimeSettings {
+        self.settings.read().unwrap().clone()
+    }
+    
+    /// Change rate limit, timeout and retry settings at runtime
+    /// 
+    /// Like `update_credentials`, this applies to every clone of the client.
+    /// A changed rate limit starts over with a full burst.
+    pub fn apply_settings(&self, settings: RuntimeSettings) -> Result<()> {
+        settings.validate()?;
+        
+        let mut current = self.settings.write().unwrap();
+        if self.owns_rate_limiter {
+            if (settings.requests_per_second, settings.burst()) != (current.requests_per_second, current.burst()) {
+                self.rate_limiter.set_rate(settings.requests_per_second, settings.burst());
+            }
+            for group in EndpointGroup::ALL {
+                let rate = settings.rate_limits.get(group);
+                let burst = settings.rate_limits.burst(group, settings.burst());
+                if (rate, burst) != (current.rate_limits.get(group), current.rate_limits.burst(group, current.burst())) {
+                    self.rate_limiter.set_group_rate(group, rate, burst);
+                }
+            }
+        }
+        *current = settings;
+        Ok(())
+    }
+    
+    /// Rate limiter used by this client, for sharing with other clients
+    pub fn rate_limiter(&self) -> Arc<RateLimiter> {
+        self.rate_limiter.clone()
+    }
+    
+    /// Reserve one of the `max_streams` streaming connection slots
+    /// 
+    /// Waits while all slots are taken. Hold the permit for as long as the
+    /// stream stays open. Streams are budgeted separately from REST requests,
+    /// so open streams never slow down REST calls and vice versa.
+    pub async fn acquire_stream_slot(&self) -> StreamPermit {
+        self.stream_budget.acquire().await
+    }
+    
+    /// Reserve a streaming connection slot without waiting
+    pub fn try_acquire_stream_slot(&self) -> Option<StreamPermit> {
+        self.stream_budget.try_acquire()
+    }
+    
+    /// Authorization header value for the current API key
+    fn authorization(&self) -> String {
+        format!("Bearer {}", self.api_key.read().unwrap())
+    }
+    
+    /// Describe a request for error reports
+    fn context(&self, method: &str, endpoint: &str, instrument: Option<&str>) -> RequestContext {
+        RequestContext {
+            method: method.to_string(),
+            path: endpoint.to_string(),
+            account_id: Some(self.config.account_id.clone()),
+            instrument: instrument.map(str::to_string),
+        }
+    }
+    
+    /// Rolling latency and error statistics per endpoint group
+    /// 
+    /// Covers every HTTP attempt, retries included, made by this client or
+    /// its clones; time spent waiting on the rate limiter is excluded.
+    /// Groups with no requests yet are left out.
+    pub fn stats(&self) -> HashMap<EndpointGroup, EndpointStats> {
+        self.latency.snapshot()
+    }
+    
+    /// Discard the samples behind `stats`
+    pub fn reset_stats(&self) {
+        self.latency.reset();
+    }
+    
+    /// Latest transaction ID reported by the API to this client
+    /// 
+    /// Updated from account summaries and `get_transactions_since*`
+    /// responses, and shared by all clones. Range queries don't update it,
+    /// since they say nothing about the transactions in between.
+    pub fn last_transaction_id(&self) -> Option<TransactionId> {
+        self.last_transaction_id.read().unwrap().clone()
+    }
+    
+    /// Remember a reported transaction ID if it is newer than the last one
+    fn record_transaction_id(&self, id: Option<TransactionId>) {
+        let Some(id) = id else { return };
+        let mut last = self.last_transaction_id.write().unwrap();
+        if last.as_ref().is_none_or(|last| id.as_u64() > last.as_u64()) {
+            *last = Some(id);
+        }
+    }
+    
+    /// Handle whose requests use `timeout` instead of the configured ones
+    /// 
+    /// The handle shares everything else with this client, including the
+    /// connection pool, rate limiter and settings, so it is cheap to make
+    /// for a single call.
+    /// 
+    /// ```no_run
+    /// use oanda_connector::{models::Granularity, OandaClient, OandaConfig};
+    /// use std::time::Duration;
+    /// 
+    /// # async fn backfill(client: &OandaClient) -> oanda_connector::Result<()> {
+    /// let candles = client
+    ///     .with_timeout(Duration::from_secs(60))
+    ///     .get_candles_range("EUR_USD", Granularity::M1, "2024-01-01T00:00:00Z", "2024-02-01T00:00:00Z")
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_timeout(&self, timeout: Duration) -> Self {
+        Self { timeout_override: Some(timeout), ..self.clone() }
+    }
+    
+    /// Run a call and return its result with the response metadata
+    /// 
+    /// Works with any method: `call` gets a handle that records the status,
+    /// headers and `RequestID` of each response it parses, and the last one
+    /// is returned with the body.
+    /// 
+    /// ```no_run
+    /// # async fn audit(client: &oanda_connector::OandaClient) -> oanda_connector::Result<()> {
+    /// let summary = client
+    ///     .with_metadata(|client| async move { client.get_account_summary().await })
+    ///     .await?;
+    /// println!("NAV {} (RequestID {:?})", summary.body.nav, summary.request_id());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn with_metadata<T, F, Fut>(&self, call: F) -> Result<ResponseEnvelope<T>>
+    where
+        F: FnOnce(OandaClient) -> Fut,
+        Fut: std::future::Future<Output = Result<T>>,
+    {
+        let sink = Arc::new(Mutex::new(None));
+        let body = call(Self { metadata: Some(sink.clone()), ..self.clone() }).await?;
+        let metadata = sink.lock().unwrap().take();
+        Ok(ResponseEnvelope { body, metadata })
+    }
+    
+    /// Remember a response's metadata for `with_metadata`
+    fn record_metadata(&self, response: &Response) {
+        if let Some(sink) = &self.metadata {
+            *sink.lock().unwrap() = Some(ResponseMetadata::from_response(response));
+        }
+    }
+    
+    /// Handle whose requests queue for the rate limiter at `priority`
+    /// 
+    /// By default orders queue as `Priority::Trading` and everything else as
+    /// `Priority::Normal`. Give bulk downloads `Priority::Backfill` so they
+    /// don't hold up live traffic on the same client:
+    /// 
+    /// ```no_run
+    /// use oanda_connector::{models::Granularity, rate_limiter::Priority, OandaClient};
+    /// 
+    /// # async fn backfill(client: &OandaClient) -> oanda_connector::Result<()> {
+    /// let history = client
+    ///     .with_priority(Priority::Backfill)
+    ///     .get_candles("EUR_USD", Granularity::M1, 5000)
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_priority(&self, priority: Priority) -> Self {
+        Self { priority_override: Some(priority), ..self.clone() }
+    }
+    
+    /// Rate limiter priority for a request to an endpoint group
+    fn priority_for(&self, group: EndpointGroup) -> Priority {
+        self.priority_override.unwrap_or(match group {
+            EndpointGroup::Orders => Priority::Trading,
+            _ => Priority::Normal,
         })
     }
     
+    /// Timeout for requests to an endpoint group under current settings
+    fn timeout_for(&self, group: EndpointGroup) -> Duration {
+        self.timeout_override
+            .unwrap_or_else(|| self.settings.read().unwrap().timeout_for(group))
+    }
+    
     /// Get current price for instrument
     /// 
     /// # Arguments
@@ -61,18 +389,16 @@ impl OandaClient {
         let endpoint = Endpoints::pricing(&self.config.account_id);
         let url = format!("{}{}?instruments={}", self.config.get_base_url(), endpoint, instrument);
         
-        let response = self.request_with_retry(|| async {
-            self.rate_limiter.acquire().await;
-            
+        let context = self.context("GET", &endpoint, Some(instrument));
+        let response = self.request_with_retry(EndpointGroup::Pricing, &context, || {
             self.http_client
                 .get(&url)
-                .header("Authorization", format!("Bearer {}", self.config.api_key))
-                .header("Accept-Datetime-Format", "RFC3339")
-                .send()
-                .await
+                .timeout(self.timeout_for(EndpointGroup::Pricing))
+                .header("Authorization", self.authorization())
+                .header("Accept-Datetime-Format", self.config.datetime_format.header_value())
         }).await?;
         
-        let pricing_response: PricingResponse = self.handle_response(response).await?;
+        let pricing_response: PricingResponse = self.handle_response(&context, response).await?;
         
         pricing_response.prices
             .into_iter()
@@ -91,18 +417,16 @@ impl OandaClient {
         let url = format!("{}{}?instruments={}", 
             self.config.get_base_url(), endpoint, instruments_param);
         
-        let response = self.request_with_retry(|| async {
-            self.rate_limiter.acquire().await;
-            
+        let context = self.context("GET", &endpoint, Some(&instruments_param));
+        let response = self.request_with_retry(EndpointGroup::Pricing, &context, || {
             self.http_client
                 .get(&url)
-                .header("Authorization", format!("Bearer {}", self.config.api_key))
-                .header("Accept-Datetime-Format", "RFC3339")
-                .send()
-                .await
+                .timeout(self.timeout_for(EndpointGroup::Pricing))
+                .header("Authorization", self.authorization())
+                .header("Accept-Datetime-Format", self.config.datetime_format.header_value())
         }).await?;
         
-        let pricing_response: PricingResponse = self.handle_response(response).await?;
+        let pricing_response: PricingResponse = self.handle_response(&context, response).await?;
         
         pricing_response.prices
             .into_iter()
@@ -110,6 +434,231 @@ impl OandaClient {
             .collect()
     }
     
+    /// Get current prices with extra pricing options
+    /// 
+    /// # Arguments
+    /// * `instruments` - List of instrument names
+    /// * `options` - `since` filter and home conversion settings
+    /// 
+    /// # Example
+    /// ```no_run
+    /// use oanda_connector::{OandaClient, OandaConfig, models::PricingOptions};
+    /// 
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let config = OandaConfig::from_env()?;
+    ///     let client = OandaClient::new(config)?;
+    ///     let instruments = vec!["EUR_USD".to_string(), "USD_JPY".to_string()];
+    ///     
+    ///     let mut options = PricingOptions { include_home_conversions: true, ..Default::default() };
+    ///     let snapshot = client.get_pricing(&instruments, &options).await?;
+    ///     
+    ///     // Poll again for changed prices only
+    ///     options.since = snapshot.time;
+    ///     let changes = client.get_pricing(&instruments, &options).await?;
+    ///     println!("{} prices changed", changes.prices.len());
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn get_pricing(
+        &self,
+        instruments: &[String],
+        options: &PricingOptions,
+    ) -> Result<PricingSnapshot> {
+        let endpoint = Endpoints::pricing(&self.config.account_id);
+        let instruments_param = instruments.join(",");
+        let mut url = format!("{}{}?instruments={}", 
+            self.config.get_base_url(), endpoint, instruments_param);
+        if let Some(since) = options.since {
+            url.push_str(&format!("&since={}", self.config.datetime_format.format(&since, SecondsFormat::Nanos)));
+        }
+        if options.include_home_conversions {
+            url.push_str("&includeHomeConversions=true");
+        }
+        
+        let context = self.context("GET", &endpoint, Some(&instruments_param));
+        let response = self.request_with_retry(EndpointGroup::Pricing, &context, || {
+            self.http_client
+                .get(&url)
+                .timeout(self.timeout_for(EndpointGroup::Pricing))
+                .header("Authorization", self.authorization())
+                .header("Accept-Datetime-Format", self.config.datetime_format.header_value())
+        }).await?;
+        
+        let pricing_response: PricingResponse = self.handle_response(&context, response).await?;
+        
+        Ok(PricingSnapshot {
+            prices: pricing_response.prices
+                .iter()
+                .map(|p| p.to_tick())
+                .collect::<Result<_>>()?,
+            home_conversions: pricing_response.home_conversions
+                .iter()
+                .map(|c| c.to_home_conversion())
+                .collect(),
+            time: pricing_response.time,
+        })
+    }
+    
+    /// Stream prices for instruments as they change
+    /// 
+    /// Connects to the streaming host and holds one of the `max_streams`
+    /// slots until the stream is dropped. Heartbeats are skipped. The stream
+    /// ends when OANDA closes the connection and yields an error if it
+    /// breaks; reconnecting is up to the caller.
+    /// 
+    /// # Example
+    /// ```no_run
+    /// use futures::TryStreamExt;
+    /// use oanda_connector::{OandaClient, OandaConfig};
+    /// 
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let client = OandaClient::new(OandaConfig::from_env()?)?;
+    ///     let instruments = vec!["EUR_USD".to_string(), "GBP_USD".to_string()];
+    ///     
+    ///     let mut prices = Box::pin(client.stream_prices(&instruments).await?);
+    ///     while let Some(tick) = prices.try_next().await? {
+    ///         println!("{} {} / {}", tick.instrument, tick.bid, tick.ask);
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn stream_prices(
+        &self,
+        instruments: &[String],
+    ) -> Result<impl Stream<Item = Result<Tick>> + Send + 'static> {
+        let permit = self.acquire_stream_slot().await;
+        let endpoint = Endpoints::pricing_stream(&self.config.account_id);
+        let instruments_param = instruments.join(",");
+        let url = format!("{}{}?instruments={}", 
+            self.config.get_stream_url(), endpoint, instruments_param);
+        
+        let context = self.context("GET", &endpoint, Some(&instruments_param));
+        let response = self.request_once(EndpointGroup::Pricing, &context, || {
+            let request = self.http_client
+                .get(&url)
+                .header("Authorization", self.authorization())
+                .header("Accept-Datetime-Format", self.config.datetime_format.header_value());
+            match self.config.stream_timeout() {
+                Some(timeout) => request.timeout(timeout),
+                None => request,
+            }
+        }).await?;
+        if response.status() != StatusCode::OK {
+            let error = self.handle_response::<serde_json::Value>(&context, response).await.err();
+            return Err(error.unwrap_or_else(|| Error::SchemaMismatch(
+                "pricing stream did not open".to_string()
+            )).with_context(&context));
+        }
+        
+        let strict = self.config.strict_deserialization;
+        let lines = stream::try_unfold(
+            (response, Vec::new(), permit),
+            move |(mut response, mut buffer, permit)| async move {
+                loop {
+                    if let Some(end) = buffer.iter().position(|&b| b == b'\n') {
+                        let line: Vec<u8> = buffer.drain(..=end).collect();
+                        if line.trim_ascii().is_empty() {
+                            continue;
+                        }
+                        match crate::decimal_number::from_json(&line, strict)? {
+                            PricingStreamMessage::Price(price) => {
+                                return Ok(Some((price.to_tick()?, (response, buffer, permit))));
+                            }
+                            PricingStreamMessage::Other => continue,
+                        }
+                    }
+                    match response.chunk().await? {
+                        Some(chunk) => buffer.extend_from_slice(&chunk),
+                        None => return Ok(None),
+                    }
+                }
+            },
+        );
+        Ok(lines.map_err(move |e: Error| e.with_context(&context)))
+    }
+    
+    /// Measure round-trip time and clock skew against the API
+    /// 
+    /// Makes `probes` sequential EUR/USD pricing requests and compares the
+    /// server time in each response with the local clock at the midpoint
+    /// of the request. The fastest probe gives the skew estimate, accurate
+    /// to within half its round-trip time. Large skew breaks `since`
+    /// polling and GTD expiry times computed from the local clock.
+    /// 
+    /// ```no_run
+    /// # async fn check(client: &oanda_connector::OandaClient) -> oanda_connector::Result<()> {
+    /// let report = client.measure_latency(5).await?;
+    /// println!("RTT {:?}, skew {:?}", report.median_rtt, report.clock_skew);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn measure_latency(&self, probes: usize) -> Result<LatencyReport> {
+        let instruments = ["EUR_USD".to_string()];
+        let mut samples = Vec::with_capacity(probes.max(1));
+        for _ in 0..probes.max(1) {
+            let sent_at = Utc::now();
+            let started = Instant::now();
+            let snapshot = self.get_pricing(&instruments, &PricingOptions::default()).await?;
+            let rtt = started.elapsed();
+            
+            let midpoint = sent_at + chrono::Duration::from_std(rtt / 2).unwrap_or_default();
+            samples.push((rtt, snapshot.time.map(|time| time - midpoint)));
+        }
+        
+        samples.sort_by_key(|(rtt, _)| *rtt);
+        Ok(LatencyReport {
+            probes: samples.len(),
+            min_rtt: samples[0].0,
+            median_rtt: samples[samples.len() / 2].0,
+            max_rtt: samples[samples.len() - 1].0,
+            clock_skew: samples.iter().find_map(|(_, skew)| *skew),
+        })
+    }
+    
+    /// Get full-depth quote for instrument
+    /// 
+    /// Unlike [`get_current_price`](Self::get_current_price), every bid/ask
+    /// liquidity level is kept so order size can be checked against the book.
+    /// 
+    /// # Arguments
+    /// * `instrument` - Instrument name (e.g., "EUR_USD")
+    pub async fn get_quote(&self, instrument: &str) -> Result<Quote> {
+        self.get_quotes(&[instrument.to_string()])
+            .await?
+            .into_iter()
+            .find(|q| q.instrument == instrument)
+            .ok_or_else(|| Error::InvalidInstrument(instrument.to_string()))
+    }
+    
+    /// Get full-depth quotes for multiple instruments
+    /// 
+    /// # Arguments
+    /// * `instruments` - List of instrument names
+    pub async fn get_quotes(&self, instruments: &[String]) -> Result<Vec<Quote>> {
+        let endpoint = Endpoints::pricing(&self.config.account_id);
+        let instruments_param = instruments.join(",");
+        let url = format!("{}{}?instruments={}", 
+            self.config.get_base_url(), endpoint, instruments_param);
+        
+        let context = self.context("GET", &endpoint, Some(&instruments_param));
+        let response = self.request_with_retry(EndpointGroup::Pricing, &context, || {
+            self.http_client
+                .get(&url)
+                .timeout(self.timeout_for(EndpointGroup::Pricing))
+                .header("Authorization", self.authorization())
+                .header("Accept-Datetime-Format", self.config.datetime_format.header_value())
+        }).await?;
+        
+        let pricing_response: PricingResponse = self.handle_response(&context, response).await?;
+        
+        pricing_response.prices
+            .into_iter()
+            .map(|p| p.to_quote())
+            .collect()
+    }
+    
     /// Get historical candles for instrument
     /// 
     /// # Arguments
@@ -153,23 +702,18 @@ impl OandaClient {
             count
         );
         
-        let response = self.request_with_retry(|| async {
-            self.rate_limiter.acquire().await;
-            
+        let context = self.context("GET", &endpoint, Some(instrument));
+        let response = self.request_with_retry(EndpointGroup::Candles, &context, || {
             self.http_client
                 .get(&url)
-                .header("Authorization", format!("Bearer {}", self.config.api_key))
-                .header("Accept-Datetime-Format", "RFC3339")
-                .send()
-                .await
+                .timeout(self.timeout_for(EndpointGroup::Candles))
+                .header("Authorization", self.authorization())
+                .header("Accept-Datetime-Format", self.config.datetime_format.header_value())
         }).await?;
         
-        let candles_response: CandlesResponse = self.handle_response(response).await?;
+        let candles_response: CandlesResponse = self.handle_response(&context, response).await?;
         
-        candles_response.candles
-            .into_iter()
-            .map(|c| c.to_candle(instrument.to_string()))
-            .collect()
+        candles_response.into_candles()
     }
     
     /// Get candles with date range
@@ -196,23 +740,76 @@ impl OandaClient {
             to
         );
         
-        let response = self.request_with_retry(|| async {
-            self.rate_limiter.acquire().await;
-            
+        let context = self.context("GET", &endpoint, Some(instrument));
+        let response = self.request_with_retry(EndpointGroup::Candles, &context, || {
             self.http_client
                 .get(&url)
-                .header("Authorization", format!("Bearer {}", self.config.api_key))
-                .header("Accept-Datetime-Format", "RFC3339")
-                .send()
-                .await
+                .timeout(self.timeout_for(EndpointGroup::Candles))
+                .header("Authorization", self.authorization())
+                .header("Accept-Datetime-Format", self.config.datetime_format.header_value())
         }).await?;
         
-        let candles_response: CandlesResponse = self.handle_response(response).await?;
+        let candles_response: CandlesResponse = self.handle_response(&context, response).await?;
         
-        candles_response.candles
-            .into_iter()
-            .map(|c| c.to_candle(instrument.to_string()))
-            .collect()
+        candles_response.into_candles()
+    }
+    
+    /// Get OANDA's aggregated order book for an instrument
+    /// 
+    /// # Arguments
+    /// * `instrument` - Instrument name (e.g., "EUR_USD")
+    /// * `time` - Snapshot time (None for the most recent snapshot)
+    pub async fn get_order_book(
+        &self,
+        instrument: &str,
+        time: Option<DateTime<Utc>>,
+    ) -> Result<OrderBook> {
+        let endpoint = Endpoints::order_book(instrument);
+        let mut url = format!("{}{}", self.config.get_base_url(), endpoint);
+        if let Some(time) = time {
+            url.push_str(&format!("?time={}", self.config.datetime_format.format(&time, SecondsFormat::Secs)));
+        }
+        
+        let context = self.context("GET", &endpoint, Some(instrument));
+        let response = self.request_with_retry(EndpointGroup::Books, &context, || {
+            self.http_client
+                .get(&url)
+                .timeout(self.timeout_for(EndpointGroup::Books))
+                .header("Authorization", self.authorization())
+                .header("Accept-Datetime-Format", self.config.datetime_format.header_value())
+        }).await?;
+        
+        let book_response: OrderBookResponse = self.handle_response(&context, response).await?;
+        book_response.order_book.to_order_book()
+    }
+    
+    /// Get OANDA's aggregated position book for an instrument
+    /// 
+    /// # Arguments
+    /// * `instrument` - Instrument name (e.g., "EUR_USD")
+    /// * `time` - Snapshot time (None for the most recent snapshot)
+    pub async fn get_position_book(
+        &self,
+        instrument: &str,
+        time: Option<DateTime<Utc>>,
+    ) -> Result<PositionBook> {
+        let endpoint = Endpoints::position_book(instrument);
+        let mut url = format!("{}{}", self.config.get_base_url(), endpoint);
+        if let Some(time) = time {
+            url.push_str(&format!("?time={}", self.config.datetime_format.format(&time, SecondsFormat::Secs)));
+        }
+        
+        let context = self.context("GET", &endpoint, Some(instrument));
+        let response = self.request_with_retry(EndpointGroup::Books, &context, || {
+            self.http_client
+                .get(&url)
+                .timeout(self.timeout_for(EndpointGroup::Books))
+                .header("Authorization", self.authorization())
+                .header("Accept-Datetime-Format", self.config.datetime_format.header_value())
+        }).await?;
+        
+        let book_response: PositionBookResponse = self.handle_response(&context, response).await?;
+        book_response.position_book.to_position_book()
     }
     
     /// Get account summary information
@@ -235,167 +832,1022 @@ impl OandaClient {
         let endpoint = Endpoints::account(&self.config.account_id);
         let url = format!("{}{}", self.config.get_base_url(), endpoint);
         
-        let response = self.request_with_retry(|| async {
-            self.rate_limiter.acquire().await;
-            
+        let context = self.context("GET", &endpoint, None);
+        let response = self.request_with_retry(EndpointGroup::Account, &context, || {
             self.http_client
                 .get(&url)
-                .header("Authorization", format!("Bearer {}", self.config.api_key))
-                .send()
-                .await
+                .timeout(self.timeout_for(EndpointGroup::Account))
+                .header("Authorization", self.authorization())
         }).await?;
         
-        let account_response: AccountResponse = self.handle_response(response).await?;
+        let account_response: AccountResponse = self.handle_response(&context, response).await?;
+        self.record_transaction_id(account_response.last_transaction_id);
         Ok(account_response.account.to_summary())
     }
     
-    /// Get available instruments for the account
-    pub async fn get_instruments(&self) -> Result<Vec<Instrument>> {
-        let endpoint = Endpoints::instruments(&self.config.account_id);
+    /// Get the account's open trades
+    pub async fn get_open_trades(&self) -> Result<Vec<Trade>> {
+        let endpoint = Endpoints::open_trades(&self.config.account_id);
         let url = format!("{}{}", self.config.get_base_url(), endpoint);
         
-        let response = self.request_with_retry(|| async {
-            self.rate_limiter.acquire().await;
-            
+        let context = self.context("GET", &endpoint, None);
+        let response = self.request_with_retry(EndpointGroup::Orders, &context, || {
             self.http_client
                 .get(&url)
-                .header("Authorization", format!("Bearer {}", self.config.api_key))
-                .send()
-                .await
+                .timeout(self.timeout_for(EndpointGroup::Orders))
+                .header("Authorization", self.authorization())
         }).await?;
         
-        #[derive(serde::Deserialize)]
-        struct InstrumentsResponse {
-            instruments: Vec<Instrument>,
-        }
-        
-        let instruments_response: InstrumentsResponse = self.handle_response(response).await?;
-        Ok(instruments_response.instruments)
+        let trades_response: OpenTradesResponse = self.handle_response(&context, response).await?;
+        self.record_transaction_id(trades_response.last_transaction_id);
+        Ok(trades_response.trades)
     }
     
-    /// Check if client is connected and authenticated
-    pub async fn health_check(&self) -> Result<bool> {
-        match self.get_account_summary().await {
-            Ok(_) => Ok(true),
-            Err(Error::AuthenticationFailed) => Ok(false),
-            Err(e) => Err(e),
-        }
+    /// Get positions in instruments the account currently holds units of
+    pub async fn get_open_positions(&self) -> Result<Vec<Position>> {
+        let endpoint = Endpoints::open_positions(&self.config.account_id);
+        let url = format!("{}{}", self.config.get_base_url(), endpoint);
+        
+        let context = self.context("GET", &endpoint, None);
+        let response = self.request_with_retry(EndpointGroup::Orders, &context, || {
+            self.http_client
+                .get(&url)
+                .timeout(self.timeout_for(EndpointGroup::Orders))
+                .header("Authorization", self.authorization())
+        }).await?;
+        
+        let positions_response: OpenPositionsResponse = self.handle_response(&context, response).await?;
+        self.record_transaction_id(positions_response.last_transaction_id);
+        Ok(positions_response.positions)
     }
     
-    // ============================================================
-    // PRIVATE HELPER METHODS
-    // ============================================================
-    
-    /// Make request with automatic retry logic
-    async fn request_with_retry<F, Fut>(&self, mut f: F) -> Result<Response>
-    where
-        F: FnMut() -> Fut,
-        Fut: std::future::Future<Output = reqwest::Result<Response>>,
-    {
-        if !self.config.enable_retries {
-            return f().await.map_err(Error::HttpError);
-        }
+    /// Get the account's pending orders
+    /// 
+    /// Includes stop loss, take profit and trailing stop orders attached to
+    /// open trades.
+    pub async fn get_pending_orders(&self) -> Result<Vec<Order>> {
+        let endpoint = Endpoints::pending_orders(&self.config.account_id);
+        let url = format!("{}{}", self.config.get_base_url(), endpoint);
         
-        let mut attempts = 0;
-        let max_attempts = self.config.max_retries + 1;
+        let context = self.context("GET", &endpoint, None);
+        let response = self.request_with_retry(EndpointGroup::Orders, &context, || {
+            self.http_client
+                .get(&url)
+                .timeout(self.timeout_for(EndpointGroup::Orders))
+                .header("Authorization", self.authorization())
+                .header("Accept-Datetime-Format", self.config.datetime_format.header_value())
+        }).await?;
+        
+        let orders_response: PendingOrdersResponse = self.handle_response(&context, response).await?;
+        self.record_transaction_id(orders_response.last_transaction_id);
+        Ok(orders_response.orders)
+    }
+    
+    /// Get available instruments for the account
+    /// 
+    /// Instrument metadata rarely changes, so the list is reused for
+    /// `instrument_cache_seconds` (five minutes by default) and shared by all
+    /// clones. Once it expires the request is made conditional when the API
+    /// supplied an `ETag`, and a `304 Not Modified` keeps the cached list.
+    pub async fn get_instruments(&self) -> Result<Vec<Instrument>> {
+        let ttl = Duration::from_secs(self.config.instrument_cache_seconds);
+        let mut etag = match &*self.instrument_cache.lock().unwrap() {
+            Some(cache) if cache.fetched_at.elapsed() < ttl => {
+                return Ok(cache.instruments.clone())
+            }
+            Some(cache) => cache.etag.clone(),
+            None => None,
+        };
+        
+        let endpoint = Endpoints::instruments(&self.config.account_id);
+        let url = format!("{}{}", self.config.get_base_url(), endpoint);
+        
+        let context = self.context("GET", &endpoint, None);
+        let response = loop {
+            let response = self
+                .request_with_retry(EndpointGroup::Account, &context, || {
+                    let request = self
+                        .http_client
+                        .get(&url)
+                        .timeout(self.timeout_for(EndpointGroup::Account))
+                        .header("Authorization", self.authorization());
+                    match &etag {
+                        Some(etag) => request.header(IF_NONE_MATCH, etag.clone()),
+                        None => request,
+                    }
+                })
+                .await?;
+
+            if response.status() != StatusCode::NOT_MODIFIED || etag.is_none() {
+                break response;
+            }
+            self.record_metadata(&response);
+            if let Some(cache) = self.instrument_cache.lock().unwrap().as_mut() {
+                cache.fetched_at = Instant::now();
+                return Ok(cache.instruments.clone());
+            }
+            // The cache was cleared while revalidating, so there is nothing
+            // for the 304 to refer to; ask for the full list instead
+            etag = None;
+        };
+
+        #[derive(serde::Deserialize)]
+        struct InstrumentsResponse {
+            instruments: Vec<Instrument>,
+        }
+        
+        let etag = response.headers().get(ETAG).cloned();
+        let instruments_response: InstrumentsResponse = self.handle_response(&context, response).await?;
+        if !ttl.is_zero() {
+            *self.instrument_cache.lock().unwrap() = Some(InstrumentCache {
+                instruments: instruments_response.instruments.clone(),
+                etag,
+                fetched_at: Instant::now(),
+            });
+        }
+        Ok(instruments_response.instruments)
+    }
+    
+    /// Drop the cached instrument list so the next call fetches it again
+    pub fn clear_instrument_cache(&self) {
+        *self.instrument_cache.lock().unwrap() = None;
+    }
+    
+    /// Get all transactions that occurred after a given transaction ID
+    /// 
+    /// Pass the ID of the last transaction you processed to fill any gap
+    /// left by a process restart or stream disconnect.
+    /// 
+    /// # Arguments
+    /// * `id` - Transaction ID to start after (exclusive)
+    pub async fn get_transactions_since(&self, id: &TransactionId) -> Result<Vec<Transaction>> {
+        self.get_transactions_since_filtered(id, &TransactionFilter::new()).await
+    }
+    
+    /// Get all transactions after the last transaction ID this client saw
+    /// 
+    /// Each call picks up where the previous one, or the last account
+    /// summary, left off, so polling this after taking an account snapshot
+    /// keeps local state in sync. If no ID has been seen yet, the account
+    /// summary is fetched to set the starting point and no transactions are
+    /// returned.
+    /// 
+    /// ```no_run
+    /// # async fn sync(client: &oanda_connector::OandaClient) -> oanda_connector::Result<()> {
+    /// let summary = client.get_account_summary().await?;
+    /// loop {
+    ///     for transaction in client.get_transactions_since_last().await? {
+    ///         println!("{:?} {}", transaction.id(), transaction.type_name());
+    ///     }
+    ///     tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+    /// }
+    /// # }
+    /// ```
+    pub async fn get_transactions_since_last(&self) -> Result<Vec<Transaction>> {
+        match self.last_transaction_id() {
+            Some(id) => self.get_transactions_since(&id).await,
+            None => {
+                self.get_account_summary().await?;
+                Ok(Vec::new())
+            }
+        }
+    }
+    
+    /// Get transactions of selected types that occurred after a given transaction ID
+    /// 
+    /// # Arguments
+    /// * `id` - Transaction ID to start after (exclusive)
+    /// * `filter` - Transaction types to return
+    pub async fn get_transactions_since_filtered(
+        &self,
+        id: &TransactionId,
+        filter: &TransactionFilter,
+    ) -> Result<Vec<Transaction>> {
+        let endpoint = Endpoints::transactions_since(&self.config.account_id);
+        let mut url = format!("{}{}?id={}", self.config.get_base_url(), endpoint, id);
+        if let Some(types) = filter.query_value() {
+            url.push_str(&format!("&type={}", types));
+        }
+        
+        let context = self.context("GET", &endpoint, None);
+        let response = self.request_with_retry(EndpointGroup::Transactions, &context, || {
+            self.http_client
+                .get(&url)
+                .timeout(self.timeout_for(EndpointGroup::Transactions))
+                .header("Authorization", self.authorization())
+                .header("Accept-Datetime-Format", self.config.datetime_format.header_value())
+        }).await?;
+        
+        let transactions_response: TransactionsResponse = self.handle_response(&context, response).await?;
+        self.check_transactions(&transactions_response.transactions)?;
+        self.record_transaction_id(transactions_response.last_transaction_id);
+        
+        Ok(transactions_response.transactions
+            .into_iter()
+            .filter(|t| filter.matches(t))
+            .collect())
+    }
+    
+    /// Changes to orders, trades and positions since a transaction
+    /// 
+    /// Call after a reconnect or restart with the ID of the last
+    /// transaction the application processed to find fills, closed trades
+    /// and cancelled orders it missed. See [`StateDiff`].
+    /// 
+    /// # Arguments
+    /// * `since` - Last transaction ID processed (exclusive)
+    pub async fn reconcile_state(&self, since: &TransactionId) -> Result<StateDiff> {
+        let endpoint = Endpoints::account_changes(&self.config.account_id);
+        let url = format!("{}{}?sinceTransactionID={}", self.config.get_base_url(), endpoint, since);
+        
+        let context = self.context("GET", &endpoint, None);
+        let response = self.request_with_retry(EndpointGroup::Account, &context, || {
+            self.http_client
+                .get(&url)
+                .timeout(self.timeout_for(EndpointGroup::Account))
+                .header("Authorization", self.authorization())
+                .header("Accept-Datetime-Format", self.config.datetime_format.header_value())
+        }).await?;
+        
+        let changes_response: AccountChangesResponse = self.handle_response(&context, response).await?;
+        self.check_transactions(&changes_response.changes.transactions)?;
+        self.record_transaction_id(changes_response.last_transaction_id.clone());
+        
+        Ok(StateDiff {
+            last_transaction_id: changes_response.last_transaction_id,
+            ..changes_response.changes
+        })
+    }
+    
+    /// Get transactions within an ID range
+    /// 
+    /// # Arguments
+    /// * `from` - First transaction ID (inclusive)
+    /// * `to` - Last transaction ID (inclusive)
+    pub async fn get_transactions_range(
+        &self,
+        from: &TransactionId,
+        to: &TransactionId,
+    ) -> Result<Vec<Transaction>> {
+        self.get_transactions_range_filtered(from, to, &TransactionFilter::new()).await
+    }
+    
+    /// Get transactions of selected types within an ID range
+    /// 
+    /// # Arguments
+    /// * `from` - First transaction ID (inclusive)
+    /// * `to` - Last transaction ID (inclusive)
+    /// * `filter` - Transaction types to return
+    pub async fn get_transactions_range_filtered(
+        &self,
+        from: &TransactionId,
+        to: &TransactionId,
+        filter: &TransactionFilter,
+    ) -> Result<Vec<Transaction>> {
+        let endpoint = Endpoints::transactions_id_range(&self.config.account_id);
+        let mut url = format!("{}{}?from={}&to={}", self.config.get_base_url(), endpoint, from, to);
+        if let Some(types) = filter.query_value() {
+            url.push_str(&format!("&type={}", types));
+        }
+        
+        let context = self.context("GET", &endpoint, None);
+        let response = self.request_with_retry(EndpointGroup::Transactions, &context, || {
+            self.http_client
+                .get(&url)
+                .timeout(self.timeout_for(EndpointGroup::Transactions))
+                .header("Authorization", self.authorization())
+                .header("Accept-Datetime-Format", self.config.datetime_format.header_value())
+        }).await?;
+        
+        let transactions_response: TransactionsResponse = self.handle_response(&context, response).await?;
+        self.check_transactions(&transactions_response.transactions)?;
+        
+        Ok(transactions_response.transactions
+            .into_iter()
+            .filter(|t| filter.matches(t))
+            .collect())
+    }
+    
+    /// Stream transaction history between two IDs
+    /// 
+    /// Walks the range in pages of 1000 IDs, fetching the next page only once
+    /// the previous one has been consumed, so large histories are never
+    /// buffered in memory at once.
+    /// 
+    /// # Arguments
+    /// * `from` - First transaction ID (inclusive)
+    /// * `to` - Last transaction ID (inclusive)
+    /// 
+    /// # Example
+    /// ```no_run
+    /// use futures::TryStreamExt;
+    /// use oanda_connector::{OandaClient, OandaConfig, TransactionId};
+    /// 
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let config = OandaConfig::from_env()?;
+    ///     let client = OandaClient::new(config)?;
+    ///     
+    ///     let mut history = Box::pin(client.stream_transaction_history(&TransactionId::from(1), &TransactionId::from(250000)));
+    ///     while let Some(transaction) = history.try_next().await? {
+    ///         println!("{:?} {}", transaction.id(), transaction.type_name());
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn stream_transaction_history<'a>(
+        &'a self,
+        from: &TransactionId,
+        to: &TransactionId,
+    ) -> impl Stream<Item = Result<Transaction>> + 'a {
+        self.stream_transaction_history_filtered(from, to, TransactionFilter::new())
+    }
+    
+    /// Stream transactions of selected types between two IDs
+    /// 
+    /// Same paging behaviour as [`stream_transaction_history`](Self::stream_transaction_history),
+    /// with the filter applied server-side on every page.
+    /// 
+    /// # Arguments
+    /// * `from` - First transaction ID (inclusive)
+    /// * `to` - Last transaction ID (inclusive)
+    /// * `filter` - Transaction types to return
+    pub fn stream_transaction_history_filtered<'a>(
+        &'a self,
+        from: &TransactionId,
+        to: &TransactionId,
+        filter: TransactionFilter,
+    ) -> impl Stream<Item = Result<Transaction>> + 'a {
+        let filter = Arc::new(filter);
+        let to = to.as_u64();
+        
+        stream::try_unfold(from.as_u64(), move |page_start| {
+            let filter = filter.clone();
+            async move {
+                if page_start > to {
+                    return Ok::<_, Error>(None);
+                }
+                
+                let page_end = to.min(page_start + TRANSACTION_PAGE_SIZE - 1);
+                let page = self
+                    .get_transactions_range_filtered(
+                        &TransactionId::from(page_start),
+                        &TransactionId::from(page_end),
+                        &filter,
+                    )
+                    .await?;
+                
+                Ok(Some((stream::iter(page.into_iter().map(Ok)), page_end + 1)))
+            }
+        })
+        .try_flatten()
+    }
+    
+    /// Stream transactions of selected types executed within a time range
+    /// 
+    /// OANDA splits the range into ID pages up front; each page is fetched
+    /// lazily as the stream is consumed.
+    /// 
+    /// # Arguments
+    /// * `from` - Start time (inclusive)
+    /// * `to` - End time (inclusive)
+    /// * `filter` - Transaction types to return
+    pub fn stream_transactions_between<'a>(
+        &'a self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        filter: TransactionFilter,
+    ) -> impl Stream<Item = Result<Transaction>> + 'a {
+        let filter = Arc::new(filter);
+        
+        stream::once(self.get_transaction_page_ranges(from, to, filter.clone()))
+            .map_ok(move |ranges| {
+                let filter = filter.clone();
+                stream::iter(ranges.into_iter().map(Ok::<_, Error>))
+                    .and_then(move |(page_from, page_to)| {
+                        let filter = filter.clone();
+                        async move {
+                            self.get_transactions_range_filtered(&page_from, &page_to, &filter).await
+                        }
+                    })
+                    .map_ok(|page| stream::iter(page.into_iter().map(Ok)))
+                    .try_flatten()
+            })
+            .try_flatten()
+    }
+    
+    /// Submit an order
+    /// 
+    /// Order requests are never retried, since a request that timed out may
+    /// still have been executed. A rejected order fails with
+    /// `Error::OrderRejected`.
+    /// 
+    /// # Example
+    /// ```no_run
+    /// use oanda_connector::{OandaClient, OandaConfig};
+    /// use oanda_connector::orders::OrderRequest;
+    /// use rust_decimal_macros::dec;
+    /// 
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let config = OandaConfig::from_env()?;
+    ///     let client = OandaClient::new(config)?;
+    ///     
+    ///     let order = OrderRequest::market("EUR_USD", dec!(1000)).stop_loss(dec!(1.0950));
+    ///     let response = client.place_order(&order).await?;
+    ///     println!("Filled: {}", response.order_fill_transaction.is_some());
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn place_order(&self, order: &OrderRequest) -> Result<OrderResponse> {
+        let endpoint = Endpoints::orders(&self.config.account_id);
+        let body = serde_json::json!({ "order": order });
+        
+        let context = self.context("POST", &endpoint, Some(&order.instrument));
+        self.send_order_request(reqwest::Method::POST, &endpoint, &context, Some(&body)).await
+    }
+    
+    /// Get one order, pending or not
+    /// 
+    /// # Arguments
+    /// * `order` - OANDA order ID, or `OrderSpecifier::client_id` for the
+    ///   client extensions ID it was created with
+    pub async fn get_order(&self, order: impl Into<OrderSpecifier>) -> Result<Order> {
+        let endpoint = Endpoints::order(&self.config.account_id, &order.into().to_string());
+        let url = format!("{}{}", self.config.get_base_url(), endpoint);
+        
+        let context = self.context("GET", &endpoint, None);
+        let response = self.request_with_retry(EndpointGroup::Orders, &context, || {
+            self.http_client
+                .get(&url)
+                .timeout(self.timeout_for(EndpointGroup::Orders))
+                .header("Authorization", self.authorization())
+                .header("Accept-Datetime-Format", self.config.datetime_format.header_value())
+        }).await?;
+        
+        #[derive(serde::Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct OrderDetailsResponse {
+            order: Order,
+            #[serde(rename = "lastTransactionID", default)]
+            last_transaction_id: Option<TransactionId>,
+        }
+        
+        let order_response: OrderDetailsResponse = self.handle_response(&context, response).await?;
+        self.record_transaction_id(order_response.last_transaction_id);
+        Ok(order_response.order)
+    }
+    
+    /// Replace a pending order with a new one
+    /// 
+    /// OANDA cancels the existing order and creates the replacement, which
+    /// gets a new ID, in one step; the response carries both the cancel and
+    /// the create transactions. Like `place_order`, never retried.
+    pub async fn replace_order(
+        &self,
+        order: impl Into<OrderSpecifier>,
+        replacement: &OrderRequest,
+    ) -> Result<OrderResponse> {
+        let endpoint = Endpoints::order(&self.config.account_id, &order.into().to_string());
+        let body = serde_json::json!({ "order": replacement });
+        
+        let context = self.context("PUT", &endpoint, Some(&replacement.instrument));
+        self.send_order_request(reqwest::Method::PUT, &endpoint, &context, Some(&body)).await
+    }
+    
+    /// Cancel a pending order
+    /// 
+    /// The response's `order_cancel_transaction` confirms the cancellation.
+    /// Never retried.
+    pub async fn cancel_order(&self, order: impl Into<OrderSpecifier>) -> Result<OrderResponse> {
+        let endpoint = Endpoints::cancel_order(&self.config.account_id, &order.into().to_string());
+        
+        let context = self.context("PUT", &endpoint, None);
+        self.send_order_request(reqwest::Method::PUT, &endpoint, &context, None).await
+    }
+    
+    /// Close an open trade at market
+    /// 
+    /// # Arguments
+    /// * `trade` - Trade to close
+    /// * `units` - Units to close, or `None` for the whole trade
+    /// 
+    /// The response's `order_fill_transaction` carries the closing fill.
+    /// Never retried.
+    pub async fn close_trade(&self, trade: &TradeId, units: Option<Decimal>) -> Result<OrderResponse> {
+        let endpoint = Endpoints::close_trade(&self.config.account_id, trade.as_ref());
+        let units = units.map_or_else(|| "ALL".to_string(), |units| units.abs().to_string());
+        let body = serde_json::json!({ "units": units });
+        
+        let context = self.context("PUT", &endpoint, None);
+        self.send_order_request(reqwest::Method::PUT, &endpoint, &context, Some(&body)).await
+    }
+    
+    /// Close a position at market
+    /// 
+    /// Every unit held on each side of `position` is closed; sides without
+    /// units are left out, as OANDA rejects closing them. Never retried.
+    pub async fn close_position(&self, position: &Position) -> Result<ClosePositionResponse> {
+        let endpoint = Endpoints::close_position(&self.config.account_id, &position.instrument);
+        let mut body = serde_json::Map::new();
+        if !position.long.units.is_zero() {
+            body.insert("longUnits".to_string(), "ALL".into());
+        }
+        if !position.short.units.is_zero() {
+            body.insert("shortUnits".to_string(), "ALL".into());
+        }
+        let body = serde_json::Value::Object(body);
+        
+        let context = self.context("PUT", &endpoint, Some(&position.instrument));
+        self.send_order_request(reqwest::Method::PUT, &endpoint, &context, Some(&body)).await
+    }
+    
+    /// Cancel every pending order, then close every open position
+    /// 
+    /// The kill switch for a live bot. Uses [`FlattenOptions::default`]:
+    /// four requests at a time, each tried up to three times while it
+    /// fails transiently.
+    /// 
+    /// ```no_run
+    /// # async fn panic_button(client: &oanda_connector::OandaClient) -> oanda_connector::Result<()> {
+    /// let report = client.flatten_all().await?;
+    /// for (item, error) in report.errors() {
+    ///     eprintln!("{} still open: {}", item, error);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn flatten_all(&self) -> Result<FlattenReport> {
+        self.flatten_all_with(&FlattenOptions::default()).await
+    }
+    
+    /// Cancel every pending order, then close every open position
+    /// 
+    /// Fails only if the orders or positions can't be listed, in which case
+    /// nothing is touched. Otherwise each cancel and close is reported on
+    /// its own. A retried request may have gone through the first time, in
+    /// which case the retry is rejected because the order or position is
+    /// already gone; check `get_open_positions` when the report has errors.
+    pub async fn flatten_all_with(&self, options: &FlattenOptions) -> Result<FlattenReport> {
+        let (orders, positions) = tokio::try_join!(self.get_pending_orders(), self.get_open_positions())?;
+        let concurrency = options.concurrency.max(1);
+        
+        let cancelled = stream::iter(orders)
+            .map(|order| async move {
+                let result = retry_transient(options, || self.cancel_order(&order.id)).await;
+                (order.id, result)
+            })
+            .buffered(concurrency)
+            .collect()
+            .await;
+        let closed = stream::iter(positions)
+            .map(|position| async move {
+                let result = retry_transient(options, || self.close_position(&position)).await;
+                (position.instrument, result)
+            })
+            .buffered(concurrency)
+            .collect()
+            .await;
+        
+        Ok(FlattenReport { cancelled, closed })
+    }
+    
+    /// Send an order-changing request once, mapping OANDA's reject bodies
+    /// to `Error::OrderRejected`
+    async fn send_order_request<T>(
+        &self,
+        method: reqwest::Method,
+        endpoint: &str,
+        context: &RequestContext,
+        body: Option<&serde_json::Value>,
+    ) -> Result<T>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        let url = format!("{}{}", self.config.get_base_url(), endpoint);
+        let response = self.request_once(EndpointGroup::Orders, context, || {
+            let request = self.http_client
+                .request(method.clone(), &url)
+                .timeout(self.timeout_for(EndpointGroup::Orders))
+                .header("Authorization", self.authorization())
+                .header("Accept-Datetime-Format", self.config.datetime_format.header_value());
+            match body {
+                Some(body) => request.json(body),
+                None => request,
+            }
+        }).await?;
+        
+        let status = response.status();
+        if status == StatusCode::BAD_REQUEST || status == StatusCode::NOT_FOUND {
+            let request_id = request_id(&response);
+            let body = response.bytes().await.map_err(|e| Error::HttpError(e).with_context(context))?;
+            let error = match serde_json::from_slice::<OrderRejectBody>(&body) {
+                Ok(reject) => reject.into_error(),
+                Err(_) => Error::from_response_body(status.as_u16(), &String::from_utf8_lossy(&body)),
+            };
+            return Err(error.with_request_id(request_id).with_context(context));
+        }
+        
+        self.handle_response(context, response).await
+    }
+    
+    /// GET any API path, returning the JSON body untyped
+    /// 
+    /// For endpoints the crate doesn't cover yet. Authentication, rate
+    /// limiting, retries, middleware and error mapping work as for typed
+    /// calls; the path (e.g. `/v3/accounts/{id}/changes`) picks the
+    /// endpoint group via [`EndpointGroup::for_path`].
+    /// 
+    /// ```no_run
+    /// # async fn changes(client: &oanda_connector::OandaClient) -> oanda_connector::Result<()> {
+    /// let path = format!("/v3/accounts/{}/changes", client.account_id());
+    /// let changes = client.get_raw(&path, &[("sinceTransactionID", "6358")]).await?;
+    /// println!("{}", changes["lastTransactionID"]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn get_raw(&self, path: &str, query: &[(&str, &str)]) -> Result<serde_json::Value> {
+        let group = EndpointGroup::for_path(path);
+        let url = format!("{}{}", self.config.get_base_url(), path);
+        
+        let context = self.context("GET", path, None);
+        let response = self.request_with_retry(group, &context, || {
+            self.http_client
+                .get(&url)
+                .query(query)
+                .timeout(self.timeout_for(group))
+                .header("Authorization", self.authorization())
+                .header("Accept-Datetime-Format", self.config.datetime_format.header_value())
+        }).await?;
+        
+        self.handle_response(&context, response).await
+    }
+    
+    /// POST a JSON body to any API path, returning the JSON body untyped
+    /// 
+    /// Like [`get_raw`](Self::get_raw), except that the request is never
+    /// retried, since a POST that timed out may still have taken effect.
+    pub async fn post_raw(&self, path: &str, body: &serde_json::Value) -> Result<serde_json::Value> {
+        let group = EndpointGroup::for_path(path);
+        let url = format!("{}{}", self.config.get_base_url(), path);
+        
+        let context = self.context("POST", path, None);
+        let response = self.request_once(group, &context, || {
+            self.http_client
+                .post(&url)
+                .timeout(self.timeout_for(group))
+                .header("Authorization", self.authorization())
+                .header("Accept-Datetime-Format", self.config.datetime_format.header_value())
+                .json(body)
+        }).await?;
+        
+        self.handle_response(&context, response).await
+    }
+    
+    /// Check if client is connected and authenticated
+    pub async fn health_check(&self) -> Result<bool> {
+        match self.get_account_summary().await {
+            Ok(_) => Ok(true),
+            Err(e) if e.is_auth_error() => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+    
+    // ============================================================
+    // PRIVATE HELPER METHODS
+    // ============================================================
+    
+    /// Make request through the circuit breaker, with automatic retry logic
+    /// 
+    /// `group` selects the rate limit quota and the timeout to report.
+    async fn request_with_retry<F>(
+        &self,
+        group: EndpointGroup,
+        context: &RequestContext,
+        f: F,
+    ) -> Result<Response>
+    where
+        F: FnMut() -> RequestBuilder,
+    {
+        let request = self.with_circuit_breaker(self.send_with_retry(group, context, f));
+        #[cfg(feature = "tracing")]
+        let request = tracing::Instrument::instrument(request, request_span(context));
+        
+        request.await.map_err(|e| e.with_context(context))
+    }
+    
+    /// Make request through the circuit breaker, without retrying
+    async fn request_once<F>(
+        &self,
+        group: EndpointGroup,
+        context: &RequestContext,
+        f: F,
+    ) -> Result<Response>
+    where
+        F: FnOnce() -> RequestBuilder,
+    {
+        let request = self.with_circuit_breaker(async {
+            self.send(group, context, f()).await?.map_err(|e| self.request_error(group, e))
+        });
+        #[cfg(feature = "tracing")]
+        let request = tracing::Instrument::instrument(request, request_span(context));
+        
+        request.await.map_err(|e| e.with_context(context))
+    }
+    
+    /// Run request unless the circuit is open, recording its outcome
+    async fn with_circuit_breaker(
+        &self,
+        request: impl std::future::Future<Output = Result<Response>>,
+    ) -> Result<Response> {
+        let Some(breaker) = &self.circuit_breaker else {
+            return request.await;
+        };
+        
+        breaker.check()?;
+        let result = request.await;
+        match &result {
+            Ok(response) if !response.status().is_server_error() => breaker.record_success(),
+            _ => breaker.record_failure(),
+        }
+        result
+    }
+    
+    /// Send one attempt through the middleware once the rate limiter allows it
+    /// 
+    /// Holds an in-flight slot until the response arrives: rate limiting
+    /// alone still lets slow requests pile up during API slowdowns, and
+    /// `max_in_flight` bounds how many sockets they tie up. Waiting longer
+    /// than `queue_timeout_ms` for either fails with `Error::Timeout`, which
+    /// is returned as the outer error so it is never retried.
+    async fn send(
+        &self,
+        group: EndpointGroup,
+        context: &RequestContext,
+        request: RequestBuilder,
+    ) -> Result<Result<Response>> {
+        let request = request.build()?;
+        let queue_timeout = self.settings.read().unwrap().queue_timeout();
+        let turn = async {
+            let slot = match &self.in_flight {
+                Some(semaphore) => Some(
+                    semaphore.acquire().await.expect("in-flight semaphore is never closed")
+                ),
+                None => None,
+            };
+            self.rate_limiter.acquire_with_priority(group, self.priority_for(group)).await;
+            slot
+        };
+        
+        let _slot = match queue_timeout {
+            Some(limit) => tokio::time::timeout(limit, turn)
+                .await
+                .map_err(|_| Error::Timeout(limit.as_secs_f64().ceil() as u64))?,
+            None => turn.await,
+        };
+        let started = Instant::now();
+        let outcome = Next::new(self.transport.as_ref(), &self.middleware).run(request, context).await;
+        let failed = match &outcome {
+            Ok(response) => response.status().is_server_error() || response.status() == StatusCode::TOO_MANY_REQUESTS,
+            Err(_) => true,
+        };
+        self.latency.record(group, started.elapsed(), failed);
+        #[cfg(feature = "tracing")]
+        trace_outcome(&outcome, started.elapsed());
+        Ok(outcome)
+    }
+    
+    /// Send request, retrying transient failures
+    async fn send_with_retry<F>(
+        &self,
+        group: EndpointGroup,
+        context: &RequestContext,
+        mut f: F,
+    ) -> Result<Response>
+    where
+        F: FnMut() -> RequestBuilder,
+    {
+        let (enable_retries, max_retries, policy) = {
+            let settings = self.settings.read().unwrap();
+            (settings.enable_retries, settings.max_retries, settings.retry.clone())
+        };
+        if !enable_retries {
+            return self.send(group, context, f()).await?.map_err(|e| self.request_error(group, e));
+        }
+        
+        let mut attempts = 0;
+        let max_attempts = max_retries + 1;
+        let started = Instant::now();
         
         loop {
             attempts += 1;
             
-            match f().await {
-                Ok(response) => return Ok(response),
-                Err(e) if attempts >= max_attempts => {
-                    return Err(Error::HttpError(e));
-                }
-                Err(e) if e.is_timeout() => {
-                    // Exponential backoff for timeouts
-                    let delay = Duration::from_millis(100 * 2u64.pow(attempts - 1));
-                    sleep(delay).await;
-                    continue;
-                }
-                Err(e) if e.is_connect() => {
-                    // Network error, retry with backoff
-                    let delay = Duration::from_millis(500 * 2u64.pow(attempts - 1));
-                    sleep(delay).await;
-                    continue;
-                }
-                Err(e) => {
-                    // Other errors, don't retry
-                    return Err(Error::HttpError(e));
-                }
+            let attempt = self.send(group, context, f());
+            #[cfg(feature = "tracing")]
+            let attempt = tracing::Instrument::instrument(attempt, tracing::debug_span!("attempt", number = attempts));
+            let outcome = attempt.await?;
+            let retryable = match &outcome {
+                // Transient server error or rate limit
+                Ok(response) => policy.should_retry(response.status().as_u16()),
+                // Timeouts and network errors; anything else won't get better
+                Err(Error::HttpError(e)) => e.is_timeout() || e.is_connect(),
+                Err(_) => false,
+            };
+            if !retryable || attempts >= max_attempts {
+                return outcome.map_err(|e| self.request_error(group, e));
+            }
+            
+            let delay = outcome
+                .as_ref()
+                .ok()
+                .and_then(retry_after)
+                .unwrap_or_else(|| policy.backoff(attempts));
+            if !policy.within_budget(started.elapsed() + delay) {
+                return outcome.map_err(|e| self.request_error(group, e));
             }
+            #[cfg(feature = "tracing")]
+            tracing::debug!(attempt = attempts, delay_ms = delay.as_millis() as u64, "retrying");
+            sleep(delay).await;
+        }
+    }
+    
+    /// Report an expired request timeout as `Error::Timeout`
+    fn request_error(&self, group: EndpointGroup, error: Error) -> Error {
+        match error {
+            Error::HttpError(e) if e.is_timeout() => Error::Timeout(self.timeout_for(group).as_secs()),
+            error => error,
+        }
+    }
+    
+    /// Get the (from, to) transaction ID ranges covering a time range
+    async fn get_transaction_page_ranges(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        filter: Arc<TransactionFilter>,
+    ) -> Result<Vec<(TransactionId, TransactionId)>> {
+        if from > to {
+            return Err(Error::InvalidDateRange {
+                start: from.to_rfc3339(),
+                end: to.to_rfc3339(),
+            });
+        }
+        
+        let endpoint = Endpoints::transactions(&self.config.account_id);
+        let mut url = format!(
+            "{}{}?from={}&to={}&pageSize={}",
+            self.config.get_base_url(),
+            endpoint,
+            self.config.datetime_format.format(&from, SecondsFormat::Secs),
+            self.config.datetime_format.format(&to, SecondsFormat::Secs),
+            TRANSACTION_PAGE_SIZE
+        );
+        if let Some(types) = filter.query_value() {
+            url.push_str(&format!("&type={}", types));
+        }
+        
+        let context = self.context("GET", &endpoint, None);
+        let response = self.request_with_retry(EndpointGroup::Transactions, &context, || {
+            self.http_client
+                .get(&url)
+                .timeout(self.timeout_for(EndpointGroup::Transactions))
+                .header("Authorization", self.authorization())
+                .header("Accept-Datetime-Format", self.config.datetime_format.header_value())
+        }).await?;
+        
+        let pages_response: TransactionPagesResponse = self.handle_response(&context, response).await?;
+        
+        pages_response.pages
+            .iter()
+            .map(|page| {
+                let page_url = reqwest::Url::parse(page).map_err(|e| Error::SchemaMismatch(
+                    format!("Invalid transaction page URL {}: {}", page, e)
+                ))?;
+                let query = |key: &str| page_url
+                    .query_pairs()
+                    .find(|(k, _)| k == key)
+                    .map(|(_, v)| v.into_owned());
+                
+                match (query("from"), query("to")) {
+                    (Some(from), Some(to)) => Ok((from.parse()?, to.parse()?)),
+                    _ => Err(Error::SchemaMismatch(
+                        format!("Transaction page URL without ID range: {}", page)
+                    )),
+                }
+            })
+            .collect()
+    }
+    
+    /// Fail on transactions that matched no known type, in strict mode
+    /// 
+    /// Outside strict mode these quietly deserialize as `Transaction::Unknown`.
+    fn check_transactions(&self, transactions: &[Transaction]) -> Result<()> {
+        if !self.config.strict_deserialization {
+            return Ok(());
+        }
+        
+        match transactions.iter().find_map(|t| match t {
+            Transaction::Unknown(value) => Some(value),
+            _ => None,
+        }) {
+            Some(value) => Err(Error::SchemaMismatch(format!(
+                "unrecognized transaction {} of type {}",
+                value.get("id").and_then(|v| v.as_str()).unwrap_or("?"),
+                value.get("type").and_then(|v| v.as_str()).unwrap_or("?"),
+            ))),
+            None => Ok(()),
         }
     }
     
     /// Handle HTTP response and convert to typed result
-    async fn handle_response<T>(&self, response: Response) -> Result<T>
+    async fn handle_response<T>(&self, context: &RequestContext, response: Response) -> Result<T>
     where
         T: serde::de::DeserializeOwned,
     {
         let status = response.status();
+        let request_id = request_id(&response);
+        self.record_metadata(&response);
         
-        match status {
-            StatusCode::OK => {
-                response.json::<T>().await.map_err(|e| Error::ApiError {
-                    code: 0,
-                    message: format!("Failed to parse response: {}", e),
-                })
-            }
-            StatusCode::BAD_REQUEST => {
-                let error_text = response.text().await.unwrap_or_default();
-                Err(Error::ApiError {
-                    code: 400,
-                    message: error_text,
-                })
-            }
-            StatusCode::UNAUTHORIZED => {
-                Err(Error::AuthenticationFailed)
-            }
-            StatusCode::FORBIDDEN => {
-                Err(Error::AuthenticationFailed)
-            }
-            StatusCode::NOT_FOUND => {
-                let error_text = response.text().await.unwrap_or_default();
-                Err(Error::ApiError {
-                    code: 404,
-                    message: format!("Resource not found: {}", error_text),
-                })
-            }
+        let result = match status {
+            StatusCode::OK | StatusCode::CREATED => match response.bytes().await {
+                Ok(body) => crate::decimal_number::from_json(&body, self.config.strict_deserialization),
+                Err(e) => Err(Error::HttpError(e)),
+            },
             StatusCode::TOO_MANY_REQUESTS => {
-                let retry_after = response
-                    .headers()
-                    .get("Retry-After")
-                    .and_then(|h| h.to_str().ok())
-                    .and_then(|s| s.parse::<u64>().ok())
+                let retry_after = retry_after(&response)
+                    .map(|d| d.as_secs())
                     .unwrap_or(60);
                 
                 Err(Error::RateLimitExceeded {
                     retry_after_seconds: retry_after,
                 })
             }
-            StatusCode::INTERNAL_SERVER_ERROR => {
-                Err(Error::ApiError {
-                    code: 500,
-                    message: "OANDA server error".to_string(),
-                })
-            }
-            StatusCode::SERVICE_UNAVAILABLE => {
-                Err(Error::ApiError {
-                    code: 503,
-                    message: "OANDA service temporarily unavailable".to_string(),
-                })
-            }
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => Err(Error::AuthenticationFailed),
             _ => {
                 let error_text = response.text().await.unwrap_or_default();
-                Err(Error::ApiError {
-                    code: status.as_u16(),
-                    message: error_text,
-                })
+                if error_text.trim().is_empty() {
+                    let reason = status.canonical_reason().unwrap_or("Unexpected status");
+                    Err(Error::from_response_body(status.as_u16(), reason))
+                } else {
+                    Err(Error::from_response_body(status.as_u16(), &error_text))
+                }
             }
+        };
+        
+        result.map_err(|e| e.with_request_id(request_id).with_context(context))
+    }
+}
+
+/// Delay requested by a `Retry-After` header, in seconds
+fn retry_after(response: &Response) -> Option<Duration> {
+    response
+        .headers()
+        .get("Retry-After")
+        .and_then(|h| h.to_str().ok())
+        .and_then(|s| s.parse::<u64>().ok())
+        .map(Duration::from_secs)
+}
+
+/// Run a never-retried order request again while it fails transiently
+async fn retry_transient<T, F, Fut>(options: &FlattenOptions, call: F) -> Result<T>
+where
+    F: Fn() -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    let mut attempt = 1;
+    loop {
+        match call().await {
+            Err(e) if e.is_retryable() && attempt < options.max_attempts => {
+                sleep(options.retry_delay * 2u32.saturating_pow(attempt - 1)).await;
+                attempt += 1;
+            }
+            result => return result,
         }
     }
 }
 
+/// Span covering one API call and its retries
+#[cfg(feature = "tracing")]
+fn request_span(context: &RequestContext) -> tracing::Span {
+    tracing::info_span!(
+        "oanda_request",
+        method = %context.method,
+        endpoint = %context.path,
+        account = context.account_id.as_deref(),
+        instrument = context.instrument.as_deref(),
+    )
+}
+
+/// Record the status and latency of one attempt
+#[cfg(feature = "tracing")]
+fn trace_outcome(outcome: &Result<Response>, latency: Duration) {
+    let latency_ms = latency.as_millis() as u64;
+    match outcome {
+        Ok(response) => tracing::debug!(status = response.status().as_u16(), latency_ms, "response"),
+        Err(e) => tracing::debug!(error = %e, latency_ms, "request failed"),
+    }
+}
+
 // ============================================================
 // BUILDER PATTERN FOR CLIENT
 // ============================================================
@@ -403,12 +1855,54 @@ impl OandaClient {
 /// Builder for OandaClient
 pub struct OandaClientBuilder {
     config: OandaConfig,
+    rate_limiter: Option<Arc<RateLimiter>>,
+    middleware: Vec<Arc<dyn Middleware>>,
+    transport: Option<Arc<dyn Transport>>,
+    http_client: Option<HttpClient>,
 }
 
 impl OandaClientBuilder {
     /// Create new builder with config
     pub fn new(config: OandaConfig) -> Self {
-        Self { config }
+        Self {
+            config,
+            rate_limiter: None,
+            middleware: Vec::new(),
+            transport: None,
+            http_client: None,
+        }
+    }
+    
+    /// Use an existing reqwest client, sharing its connection pool
+    /// 
+    /// The client's own settings then apply: the config's proxy, TLS,
+    /// connection, compression, User-Agent and default header settings are
+    /// ignored. Authorization and per-request timeouts still come from the
+    /// config.
+    pub fn http_client(mut self, http_client: HttpClient) -> Self {
+        self.http_client = Some(http_client);
+        self
+    }
+    
+    /// Send requests through a custom transport instead of reqwest
+    pub fn transport(mut self, transport: impl Transport) -> Self {
+        self.transport = Some(Arc::new(transport));
+        self
+    }
+    
+    /// Add middleware around every request
+    /// 
+    /// Middleware runs in the order added; the first added sees the request
+    /// first and the response last.
+    pub fn middleware(mut self, middleware: impl Middleware) -> Self {
+        self.middleware.push(Arc::new(middleware));
+        self
+    }
+    
+    /// Share a rate limiter with other clients
+    pub fn rate_limiter(mut self, rate_limiter: Arc<RateLimiter>) -> Self {
+        self.rate_limiter = Some(rate_limiter);
+        self
     }
     
     /// Set timeout
@@ -417,6 +1911,12 @@ impl OandaClientBuilder {
         self
     }
     
+    /// Override timeout for one endpoint group
+    pub fn endpoint_timeout(mut self, group: EndpointGroup, seconds: u64) -> Self {
+        self.config.timeouts.set(group, Some(seconds));
+        self
+    }
+    
     /// Set rate limit
     pub fn rate_limit(mut self, requests_per_second: u32) -> Self {
         self.config.requests_per_second = requests_per_second;
@@ -435,9 +1935,84 @@ impl OandaClientBuilder {
         self
     }
     
+    /// Set which failed responses are retried
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.config.retry = policy;
+        self
+    }
+    
+    /// Open a circuit breaker after `failure_threshold` consecutive failures
+    pub fn circuit_breaker(mut self, failure_threshold: u32, cooldown_seconds: u64) -> Self {
+        self.config.circuit_breaker = Some(CircuitBreakerConfig {
+            failure_threshold,
+            cooldown_seconds,
+        });
+        self
+    }
+    
+    /// Set datetime format requested from the API
+    pub fn datetime_format(mut self, format: DatetimeFormat) -> Self {
+        self.config.datetime_format = format;
+        self
+    }
+    
+    /// Route requests through a proxy
+    pub fn proxy(mut self, proxy: ProxyConfig) -> Self {
+        self.config.proxy = Some(proxy);
+        self
+    }
+    
+    /// Keep up to `max` idle connections per host
+    pub fn pool_max_idle_per_host(mut self, max: usize) -> Self {
+        self.config.connection.pool_max_idle_per_host = Some(max);
+        self
+    }
+    
+    /// Close idle pooled connections after `seconds`
+    pub fn pool_idle_timeout(mut self, seconds: u64) -> Self {
+        self.config.connection.pool_idle_timeout_seconds = Some(seconds);
+        self
+    }
+    
+    /// Enable TCP keepalive with the given interval
+    pub fn tcp_keepalive(mut self, seconds: u64) -> Self {
+        self.config.connection.tcp_keepalive_seconds = Some(seconds);
+        self
+    }
+    
+    /// Use HTTP/2 without negotiation
+    pub fn http2_prior_knowledge(mut self) -> Self {
+        self.config.connection.http2_prior_knowledge = true;
+        self
+    }
+    
+    /// Ping HTTP/2 connections every `seconds` to keep them open
+    pub fn http2_keep_alive(mut self, seconds: u64) -> Self {
+        self.config.connection.http2_keep_alive_seconds = Some(seconds);
+        self
+    }
+    
+    /// Set User-Agent
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.config.user_agent = Some(user_agent.into());
+        self
+    }
+    
+    /// Add a header sent with every request
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.config.default_headers.insert(name.into(), value.into());
+        self
+    }
+    
+    /// Enable/disable strict deserialization
+    pub fn strict(mut self, enable: bool) -> Self {
+        self.config.strict_deserialization = enable;
+        self
+    }
+    
     /// Build client
     pub fn build(self) -> Result<OandaClient> {
-        OandaClient::new(self.config)
+        OandaClient::create(self)
     }
 }
 
@@ -459,6 +2034,7 @@ mod tests {
             requests_per_second: 100,
             enable_retries: true,
             max_retries: 3,
+            ..Default::default()
         }
     }
 
@@ -469,6 +2045,202 @@ mod tests {
         assert!(client.is_ok());
     }
 
+    #[tokio::test]
+    async fn test_custom_transport() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        
+        // Fails once with 503, then answers
+        struct Flaky(AtomicUsize);
+        
+        #[async_trait::async_trait]
+        impl Transport for Flaky {
+            async fn execute(&self, request: reqwest::Request) -> Result<Response> {
+                assert_eq!(request.url().path(), "/v3/accounts/test_account_id");
+                let response = match self.0.fetch_add(1, Ordering::SeqCst) {
+                    0 => http::Response::builder().status(503).body(String::new()).unwrap(),
+                    _ => http::Response::new(r#"{
+                        "account": {
+                            "id": "test_account_id",
+                            "balance": "100000.0000",
+                            "NAV": "100000.0000",
+                            "unrealizedPL": "0.0000",
+                            "pl": "0.0000",
+                            "marginUsed": "0.0000",
+                            "marginAvailable": "100000.0000",
+                            "openTradeCount": 0,
+                            "openPositionCount": 0,
+                            "currency": "USD"
+                        },
+                        "lastTransactionID": "1"
+                    }"#.to_string()),
+                };
+                Ok(Response::from(response))
+            }
+        }
+        
+        let mut config = test_config();
+        config.retry.base_delay_ms = 1;
+        let client = OandaClientBuilder::new(config)
+            .transport(Flaky(AtomicUsize::new(0)))
+            .build()
+            .unwrap();
+        
+        let summary = client.get_account_summary().await.unwrap();
+        assert_eq!(summary.currency, "USD");
+    }
+
+    #[tokio::test]
+    async fn test_shared_rate_limiter() {
+        let limiter = Arc::new(RateLimiter::new(2, 2));
+        let first = OandaClient::with_rate_limiter(test_config(), limiter.clone()).unwrap();
+        let second = OandaClientBuilder::new(test_config())
+            .rate_limiter(first.rate_limiter())
+            .build()
+            .unwrap();
+        
+        first.rate_limiter().try_acquire().unwrap();
+        second.rate_limiter().try_acquire().unwrap();
+        assert!(limiter.try_acquire().is_none());
+        
+        // Settings of one client don't retune the shared budget
+        second.apply_settings(RuntimeSettings { requests_per_second: 50, ..second.settings() }).unwrap();
+        assert!(limiter.try_acquire().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_instrument_cache() {
+        let mut server = mockito::Server::new_async().await;
+        let body = r#"{"instruments": [{
+            "name": "EUR_USD",
+            "type": "CURRENCY",
+            "displayName": "EUR/USD",
+            "pipLocation": -4,
+            "displayPrecision": 5,
+            "tradeUnitsPrecision": 0,
+            "minimumTradeSize": "1",
+            "marginRate": "0.0333"
+        }]}"#;
+        let fetch = server.mock("GET", "/v3/accounts/test_account_id/instruments")
+            .match_header("If-None-Match", mockito::Matcher::Missing)
+            .with_status(200)
+            .with_header("ETag", "\"v1\"")
+            .with_body(body)
+            .expect(2)
+            .create_async()
+            .await;
+        let revalidate = server.mock("GET", "/v3/accounts/test_account_id/instruments")
+            .match_header("If-None-Match", "\"v1\"")
+            .with_status(304)
+            .expect(1)
+            .create_async()
+            .await;
+        
+        let client = OandaClient::new(OandaConfig { base_url: Some(server.url()), ..test_config() }).unwrap();
+        assert_eq!(client.get_instruments().await.unwrap()[0].name, "EUR_USD");
+        assert_eq!(client.clone().get_instruments().await.unwrap().len(), 1);
+        
+        // Expired: revalidated with the ETag and kept
+        client.instrument_cache.lock().unwrap().as_mut().unwrap().fetched_at -= Duration::from_secs(301);
+        assert_eq!(client.get_instruments().await.unwrap()[0].name, "EUR_USD");
+        assert_eq!(client.get_instruments().await.unwrap().len(), 1);
+        
+        client.clear_instrument_cache();
+        client.get_instruments().await.unwrap();
+        
+        fetch.assert_async().await;
+        revalidate.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_instrument_cache_cleared_during_revalidation() {
+        let mut server = mockito::Server::new_async().await;
+        let fetch = server
+            .mock("GET", "/v3/accounts/test_account_id/instruments")
+            .match_header("If-None-Match", mockito::Matcher::Missing)
+            .with_status(200)
+            .with_header("ETag", "\"v1\"")
+            .with_body(r#"{"instruments": []}"#)
+            .expect(2)
+            .create_async()
+            .await;
+
+        let client = OandaClient::new(OandaConfig {
+            base_url: Some(server.url()),
+            ..test_config()
+        })
+        .unwrap();
+        let clearing = client.clone();
+        let revalidate = server
+            .mock("GET", "/v3/accounts/test_account_id/instruments")
+            .match_header("If-None-Match", "\"v1\"")
+            .with_status(304)
+            .with_body_from_request(move |_| {
+                clearing.clear_instrument_cache();
+                Vec::new()
+            })
+            .expect(1)
+            .create_async()
+            .await;
+
+        client.get_instruments().await.unwrap();
+        client
+            .instrument_cache
+            .lock()
+            .unwrap()
+            .as_mut()
+            .unwrap()
+            .fetched_at -= Duration::from_secs(301);
+
+        // The 304 has no list to refer to, so the full list is fetched
+        assert!(client.get_instruments().await.unwrap().is_empty());
+        assert!(client.instrument_cache.lock().unwrap().is_some());
+
+        fetch.assert_async().await;
+        revalidate.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_shared_http_client() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server.mock("GET", "/v3/accounts/test_account_id")
+            .match_header("User-Agent", "shared-pool/1.0")
+            .match_header("Authorization", "Bearer test_api_key")
+            .with_status(401)
+            .create_async()
+            .await;
+        
+        let http_client = HttpClient::builder().user_agent("shared-pool/1.0").build().unwrap();
+        let client = OandaClientBuilder::new(OandaConfig { base_url: Some(server.url()), ..test_config() })
+            .http_client(http_client)
+            .build()
+            .unwrap();
+        
+        assert!(!client.health_check().await.unwrap());
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_with_timeout() {
+        let client = OandaClient::new(test_config()).unwrap();
+        let backfill = client.with_timeout(Duration::from_secs(60));
+        
+        assert_eq!(backfill.timeout_for(EndpointGroup::Candles), Duration::from_secs(60));
+        assert_eq!(backfill.timeout_for(EndpointGroup::Pricing), Duration::from_secs(60));
+        assert_eq!(client.timeout_for(EndpointGroup::Pricing), Duration::from_secs(10));
+        assert!(Arc::ptr_eq(&client.rate_limiter(), &backfill.rate_limiter()));
+    }
+
+    #[tokio::test]
+    async fn test_stream_slots() {
+        let client = OandaClient::new(OandaConfig { max_streams: 1, ..test_config() }).unwrap();
+        let clone = client.clone();
+        
+        let slot = client.acquire_stream_slot().await;
+        assert!(clone.try_acquire_stream_slot().is_none());
+        drop(slot);
+        assert!(clone.try_acquire_stream_slot().is_some());
+    }
+
     #[test]
     fn test_client_builder() {
         let config = test_config();
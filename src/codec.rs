@@ -0,0 +1,241 @@
+//! Binary encodings for models
+//!
+//! [`Format`] writes and reads any of the crate's serializable types
+//! ([`Candle`](crate::Candle), [`Tick`](crate::Tick),
+//! [`Transaction`](crate::transactions::Transaction), ...) as JSON or, with
+//! the `msgpack` and `cbor` features, as MessagePack or CBOR. The binary
+//! formats are smaller and faster to parse than JSON, which matters for
+//! recorded data and for passing ticks between services.
+//!
+//! All formats carry the same data: prices stay decimal strings and times
+//! RFC3339 strings, so nothing is rounded on the way. MessagePack structs
+//! are written as maps with field names rather than positional arrays, so
+//! optional fields and transaction types decode the same way they do from
+//! JSON.
+//!
+//! ```
+//! # #[cfg(feature = "msgpack")]
+//! # fn run(candles: &[oanda_connector::Candle]) -> oanda_connector::Result<()> {
+//! use oanda_connector::codec::Format;
+//! use oanda_connector::Candle;
+//!
+//! let bytes = Format::MessagePack.encode(candles)?;
+//! let decoded: Vec<Candle> = Format::MessagePack.decode(&bytes)?;
+//! assert_eq!(decoded, candles);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::error::{Error, Result};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Serialization format for models
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    #[default]
+    Json,
+    /// MessagePack (`msgpack` feature)
+    #[cfg(feature = "msgpack")]
+    #[serde(rename = "msgpack")]
+    MessagePack,
+    /// CBOR, RFC 8949 (`cbor` feature)
+    #[cfg(feature = "cbor")]
+    Cbor,
+}
+
+impl Format {
+    /// MIME type, for HTTP bodies and message headers
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Format::Json => "application/json",
+            #[cfg(feature = "msgpack")]
+            Format::MessagePack => "application/msgpack",
+            #[cfg(feature = "cbor")]
+            Format::Cbor => "application/cbor",
+        }
+    }
+
+    /// Serialize a value
+    pub fn encode<T: Serialize + ?Sized>(&self, value: &T) -> Result<Vec<u8>> {
+        match self {
+            Format::Json => Ok(serde_json::to_vec(value)?),
+            #[cfg(feature = "msgpack")]
+            Format::MessagePack => {
+                rmp_serde::to_vec_named(value).map_err(|e| Error::EncodingError(e.to_string()))
+            }
+            #[cfg(feature = "cbor")]
+            Format::Cbor => {
+                let mut bytes = Vec::new();
+                ciborium::into_writer(value, &mut bytes)
+                    .map_err(|e| Error::EncodingError(e.to_string()))?;
+                Ok(bytes)
+            }
+        }
+    }
+
+    /// Deserialize a value written by [`encode`](Self::encode)
+    pub fn decode<T: DeserializeOwned>(&self, bytes: &[u8]) -> Result<T> {
+        match self {
+            Format::Json => Ok(serde_json::from_slice(bytes)?),
+            #[cfg(feature = "msgpack")]
+            Format::MessagePack => {
+                rmp_serde::from_slice(bytes).map_err(|e| Error::EncodingError(e.to_string()))
+            }
+            #[cfg(feature = "cbor")]
+            Format::Cbor => {
+                ciborium::from_reader(bytes).map_err(|e| Error::EncodingError(e.to_string()))
+            }
+        }
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Format::Json => "json",
+            #[cfg(feature = "msgpack")]
+            Format::MessagePack => "msgpack",
+            #[cfg(feature = "cbor")]
+            Format::Cbor => "cbor",
+        })
+    }
+}
+
+impl std::str::FromStr for Format {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "json" => Ok(Format::Json),
+            #[cfg(feature = "msgpack")]
+            "msgpack" | "messagepack" => Ok(Format::MessagePack),
+            #[cfg(feature = "cbor")]
+            "cbor" => Ok(Format::Cbor),
+            _ => Err(Error::ParseError {
+                field: "format".to_string(),
+                value: s.to_string(),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Candle, Tick};
+    use crate::transactions::Transaction;
+    use rust_decimal_macros::dec;
+
+    fn formats() -> Vec<Format> {
+        vec![
+            Format::Json,
+            #[cfg(feature = "msgpack")]
+            Format::MessagePack,
+            #[cfg(feature = "cbor")]
+            Format::Cbor,
+        ]
+    }
+
+    fn transactions() -> Vec<Transaction> {
+        serde_json::from_str(
+            r#"[{
+                "id": "6410",
+                "time": "2024-01-02T14:30:00.000000000Z",
+                "userID": 1234567,
+                "accountID": "101-004-1234567-001",
+                "batchID": "6409",
+                "type": "ORDER_FILL",
+                "orderID": "6409",
+                "instrument": "EUR_USD",
+                "units": "-1000",
+                "price": "1.10020",
+                "reason": "MARKET_ORDER",
+                "pl": "12.3456",
+                "financing": "0.0000",
+                "accountBalance": "100012.3456",
+                "tradesClosed": [{"tradeID": "6400", "units": "-1000", "realizedPL": "12.3456"}]
+            }, {
+                "id": "6501",
+                "time": "2024-01-02T21:00:00Z",
+                "accountID": "101-004-1234567-001",
+                "batchID": "6501",
+                "type": "MARKET_ORDER",
+                "instrument": "USD_JPY",
+                "units": "5000",
+                "timeInForce": "FOK",
+                "reason": "CLIENT_ORDER"
+            }, {
+                "id": "7000",
+                "time": "2024-01-03T00:00:00.000000000Z",
+                "accountID": "101-004-1234567-001",
+                "batchID": "7000",
+                "type": "SOME_FUTURE_TYPE",
+                "newField": "42"
+            }]"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_models_round_trip() {
+        let candles = vec![
+            Candle::builder()
+                .ohlc(dec!(1.10000), dec!(1.10250), dec!(1.09875), dec!(1.10125))
+                .build(),
+            Candle::builder().volume(0).complete(false).build(),
+        ];
+        let ticks = vec![
+            Tick::builder()
+                .bid(dec!(1.10000))
+                .ask(dec!(1.10020))
+                .build(),
+            Tick::builder()
+                .closeout(dec!(1.09990), dec!(1.10030))
+                .tradeable(false)
+                .build(),
+        ];
+        let transactions = transactions();
+
+        for format in formats() {
+            let decoded: Vec<Candle> = format.decode(&format.encode(&candles).unwrap()).unwrap();
+            assert_eq!(decoded, candles, "{}", format);
+            // Trailing zeros survive, since decimals are written as text
+            assert_eq!(decoded[0].open.to_string(), "1.10000");
+
+            let decoded: Vec<Tick> = format.decode(&format.encode(&ticks).unwrap()).unwrap();
+            assert_eq!(decoded, ticks, "{}", format);
+
+            let decoded: Vec<Transaction> = format
+                .decode(&format.encode(&transactions).unwrap())
+                .unwrap();
+            assert_eq!(decoded, transactions, "{}", format);
+            assert!(matches!(decoded[2], Transaction::Unknown(_)));
+        }
+    }
+
+    #[test]
+    fn test_binary_formats_are_smaller() {
+        let candles: Vec<Candle> = (0..100).map(|_| Candle::builder().build()).collect();
+        let json = Format::Json.encode(&candles).unwrap().len();
+        for format in formats().into_iter().skip(1) {
+            assert!(format.encode(&candles).unwrap().len() < json, "{}", format);
+        }
+    }
+
+    #[test]
+    fn test_format_names() {
+        for format in formats() {
+            assert_eq!(format.to_string().parse::<Format>().unwrap(), format);
+            let json = serde_json::to_string(&format).unwrap();
+            assert_eq!(json, format!("\"{}\"", format));
+        }
+        assert!(matches!(
+            "protobuf".parse::<Format>(),
+            Err(Error::ParseError { .. })
+        ));
+        assert!(Format::Json.decode::<Tick>(b"\x92\x01").is_err());
+    }
+}
@@ -1,31 +1,72 @@
 //! Configuration for OANDA connector
 
-use serde::{Deserialize, Serialize};
+use crate::datetime::DatetimeFormat;
+use crate::endpoints::EndpointGroup;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OandaConfig {
     /// OANDA API key (Bearer token)
+    /// 
+    /// May be left empty in config files when `keyring` is set.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub api_key: String,
     
     /// OANDA account ID
     pub account_id: String,
     
     /// Use practice account (true) or live (false)
+    #[serde(default = "default_true")]
     pub practice: bool,
     
     /// Base URL (auto-set based on practice flag)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub base_url: Option<String>,
     
+    /// Streaming base URL (auto-set based on practice flag)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stream_url: Option<String>,
+    
     /// Request timeout in seconds
     #[serde(default = "default_timeout")]
     pub timeout_seconds: u64,
     
+    /// Per-endpoint overrides of `timeout_seconds`
+    #[serde(default)]
+    pub timeouts: TimeoutConfig,
+    
     /// Maximum requests per second
     #[serde(default = "default_rate_limit")]
     pub requests_per_second: u32,
     
+    /// Requests allowed back to back (defaults to `requests_per_second`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub burst: Option<u32>,
+    
+    /// Dedicated quotas for endpoint groups, kept apart from `requests_per_second`
+    #[serde(default)]
+    pub rate_limits: RateLimitConfig,
+    
+    /// Longest a request may wait for the rate limiter before failing with
+    /// `Error::Timeout` (waits indefinitely when unset)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub queue_timeout_ms: Option<u64>,
+    
+    /// Maximum concurrent streaming connections, counted apart from REST requests
+    #[serde(default = "default_max_streams")]
+    pub max_streams: u32,
+    
+    /// Maximum REST requests awaiting a response at once (unlimited when unset)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_in_flight: Option<u32>,
+    
+    /// How long `get_instruments` results are reused, in seconds (0 disables caching)
+    #[serde(default = "default_instrument_cache_seconds")]
+    pub instrument_cache_seconds: u64,
+    
     /// Enable automatic retries
     #[serde(default = "default_true")]
     pub enable_retries: bool,
@@ -33,14 +74,562 @@ pub struct OandaConfig {
     /// Maximum retry attempts
     #[serde(default = "default_max_retries")]
     pub max_retries: u32,
+    
+    /// Which failed responses are retried
+    #[serde(default)]
+    pub retry: RetryPolicy,
+    
+    /// Fail fast after repeated failures (disabled when unset)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub circuit_breaker: Option<CircuitBreakerConfig>,
+    
+    /// Datetime format requested from the API
+    #[serde(default)]
+    pub datetime_format: DatetimeFormat,
+    
+    /// Reject responses with fields or transaction types the crate doesn't know
+    #[serde(default)]
+    pub strict_deserialization: bool,
+    
+    /// OS keyring entry holding the API key (requires the `keyring` feature)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keyring: Option<KeyringEntry>,
+    
+    /// Proxy for all requests
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<ProxyConfig>,
+    
+    /// TLS backend, extra root certificates and client certificate
+    #[serde(default)]
+    pub tls: TlsConfig,
+    
+    /// Ask for gzip or brotli compressed responses
+    #[serde(default = "default_true")]
+    pub compression: bool,
+    
+    /// Connection pool, keepalive and HTTP/2 settings
+    #[serde(default)]
+    pub connection: ConnectionConfig,
+    
+    /// User-Agent sent with every request (defaults to `oanda-connector/<version>`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_agent: Option<String>,
+    
+    /// Extra headers sent with every request
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub default_headers: BTreeMap<String, String>,
+}
+
+/// User-Agent used when none is configured
+pub const DEFAULT_USER_AGENT: &str = concat!("oanda-connector/", env!("CARGO_PKG_VERSION"));
+
+/// Timeout overrides by endpoint group, in seconds
+/// 
+/// REST groups left unset use `timeout_seconds`. Streams have no timeout
+/// unless `streaming_seconds` is set, since they stay open indefinitely.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimeoutConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pricing_seconds: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub candles_seconds: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub books_seconds: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub account_seconds: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transactions_seconds: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub orders_seconds: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub streaming_seconds: Option<u64>,
+}
+
+impl TimeoutConfig {
+    /// Override for an endpoint group
+    pub fn get(&self, group: EndpointGroup) -> Option<u64> {
+        *self.slot(group)
+    }
+    
+    /// Set or clear the override for an endpoint group
+    pub fn set(&mut self, group: EndpointGroup, seconds: Option<u64>) {
+        *self.slot_mut(group) = seconds;
+    }
+    
+    fn slot(&self, group: EndpointGroup) -> &Option<u64> {
+        match group {
+            EndpointGroup::Pricing => &self.pricing_seconds,
+            EndpointGroup::Candles => &self.candles_seconds,
+            EndpointGroup::Books => &self.books_seconds,
+            EndpointGroup::Account => &self.account_seconds,
+            EndpointGroup::Orders => &self.orders_seconds,
+            EndpointGroup::Transactions => &self.transactions_seconds,
+        }
+    }
+    
+    fn slot_mut(&mut self, group: EndpointGroup) -> &mut Option<u64> {
+        match group {
+            EndpointGroup::Pricing => &mut self.pricing_seconds,
+            EndpointGroup::Candles => &mut self.candles_seconds,
+            EndpointGroup::Books => &mut self.books_seconds,
+            EndpointGroup::Account => &mut self.account_seconds,
+            EndpointGroup::Orders => &mut self.orders_seconds,
+            EndpointGroup::Transactions => &mut self.transactions_seconds,
+        }
+    }
+}
+
+/// Dedicated rate limits by endpoint group, in requests per second
+/// 
+/// A group with its own quota no longer draws on `requests_per_second`, so a
+/// candle backfill limited here can't delay pricing or order requests that
+/// share the client. Groups left unset share the client-wide limit.
+/// Dedicated quotas allow the client-wide `burst`, capped at their own rate.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RateLimitConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pricing_per_second: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub candles_per_second: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub books_per_second: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub account_per_second: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transactions_per_second: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub orders_per_second: Option<u32>,
+}
+
+impl RateLimitConfig {
+    /// Dedicated quota for an endpoint group
+    pub fn get(&self, group: EndpointGroup) -> Option<u32> {
+        match group {
+            EndpointGroup::Pricing => self.pricing_per_second,
+            EndpointGroup::Candles => self.candles_per_second,
+            EndpointGroup::Books => self.books_per_second,
+            EndpointGroup::Account => self.account_per_second,
+            EndpointGroup::Orders => self.orders_per_second,
+            EndpointGroup::Transactions => self.transactions_per_second,
+        }
+    }
+    
+    /// Burst for an endpoint group's dedicated quota, if it has one
+    /// 
+    /// The client-wide `burst`, capped at the group's rate so a group
+    /// never saves up more than a second of requests.
+    pub fn burst(&self, group: EndpointGroup, burst: u32) -> Option<u32> {
+        self.get(group).map(|rate| burst.min(rate))
+    }
+    
+    /// Set or clear the dedicated quota for an endpoint group
+    pub fn set(&mut self, group: EndpointGroup, requests_per_second: Option<u32>) {
+        let slot = match group {
+            EndpointGroup::Pricing => &mut self.pricing_per_second,
+            EndpointGroup::Candles => &mut self.candles_per_second,
+            EndpointGroup::Books => &mut self.books_per_second,
+            EndpointGroup::Account => &mut self.account_per_second,
+            EndpointGroup::Orders => &mut self.orders_per_second,
+            EndpointGroup::Transactions => &mut self.transactions_per_second,
+        };
+        *slot = requests_per_second;
+    }
+}
+
+/// Which failed requests are retried, and how long to wait in between
+/// 
+/// Transport errors (timeouts, refused connections) are always retried.
+/// Applies only when `enable_retries` is set, within `max_retries`.
+/// 
+/// Delays grow from `base_delay_ms` by `multiplier` per attempt up to
+/// `max_delay_ms`. With `jitter` each delay is drawn uniformly from zero to
+/// that value ("full jitter"), so clients that failed together don't all
+/// retry together. A `Retry-After` header on a response takes precedence.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RetryPolicy {
+    /// HTTP statuses retried with backoff
+    #[serde(default = "default_retry_statuses")]
+    pub retry_statuses: Vec<u16>,
+    
+    /// Retry 429 responses once the `Retry-After` delay has passed
+    #[serde(default = "default_true")]
+    pub retry_rate_limited: bool,
+    
+    /// Delay before the first retry
+    #[serde(default = "default_base_delay_ms")]
+    pub base_delay_ms: u64,
+    
+    /// Factor applied to the delay after each attempt
+    #[serde(default = "default_multiplier")]
+    pub multiplier: f64,
+    
+    /// Upper bound for a single delay
+    #[serde(default = "default_max_delay_ms")]
+    pub max_delay_ms: u64,
+    
+    /// Randomize delays between zero and the computed backoff
+    #[serde(default = "default_true")]
+    pub jitter: bool,
+    
+    /// Give up once retrying would take longer than this in total
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_budget_ms: Option<u64>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            retry_statuses: default_retry_statuses(),
+            retry_rate_limited: true,
+            base_delay_ms: default_base_delay_ms(),
+            multiplier: default_multiplier(),
+            max_delay_ms: default_max_delay_ms(),
+            jitter: true,
+            retry_budget_ms: None,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Whether a response with this status should be retried
+    pub fn should_retry(&self, status: u16) -> bool {
+        self.retry_statuses.contains(&status) || (status == 429 && self.retry_rate_limited)
+    }
+    
+    /// Whether a request that failed with `error` would be retried
+    /// 
+    /// Mirrors the client's own retry loop, for retry wrappers around calls
+    /// that are not retried internally (e.g. `place_order`).
+    pub fn retries(&self, error: &crate::Error) -> bool {
+        match error.inner() {
+            crate::Error::ApiError { code, .. } => self.should_retry(*code),
+            crate::Error::RateLimitExceeded { .. } => self.retry_rate_limited,
+            crate::Error::Timeout(_) => true,
+            crate::Error::HttpError(e) => e.is_timeout() || e.is_connect(),
+            _ => false,
+        }
+    }
+    
+    /// Delay before retry number `attempt` (starting at 1)
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(i32::MAX as u32) as i32;
+        let delay = (self.base_delay_ms as f64 * self.multiplier.powi(exponent))
+            .min(self.max_delay_ms as f64);
+        let delay = if self.jitter { delay * fastrand::f64() } else { delay };
+        Duration::from_millis(delay as u64)
+    }
+    
+    /// Whether retrying until `elapsed` stays within the retry budget
+    pub fn within_budget(&self, elapsed: Duration) -> bool {
+        self.retry_budget_ms
+            .is_none_or(|budget| elapsed <= Duration::from_millis(budget))
+    }
+    
+    fn validate(&self) -> crate::Result<()> {
+        if self.multiplier.is_nan() || self.multiplier < 1.0 {
+            return Err(crate::Error::ConfigError(
+                "Retry multiplier must be at least 1".to_string()
+            ));
+        }
+        
+        if self.base_delay_ms > self.max_delay_ms {
+            return Err(crate::Error::ConfigError(
+                "Retry base delay cannot exceed max delay".to_string()
+            ));
+        }
+        
+        Ok(())
+    }
+}
+
+/// Circuit breaker settings
+/// 
+/// A request fails when it can't be sent or gets a 5xx response after
+/// retries. See `crate::circuit_breaker`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CircuitBreakerConfig {
+    /// Consecutive failed requests that open the circuit
+    #[serde(default = "default_failure_threshold")]
+    pub failure_threshold: u32,
+    
+    /// Seconds to wait before probing again
+    #[serde(default = "default_cooldown")]
+    pub cooldown_seconds: u64,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: default_failure_threshold(),
+            cooldown_seconds: default_cooldown(),
+        }
+    }
+}
+
+/// TLS implementation used by the HTTP client
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TlsBackend {
+    /// Platform TLS (OpenSSL, Secure Transport or SChannel)
+    #[default]
+    Native,
+    /// rustls (requires the `rustls` feature)
+    Rustls,
+}
+
+/// Client certificate for mutual TLS, as PEM files
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClientCertificate {
+    pub cert: PathBuf,
+    pub key: PathBuf, // PKCS#8 private key
+}
+
+/// TLS settings
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TlsConfig {
+    #[serde(default)]
+    pub backend: TlsBackend,
+    
+    /// PEM files with additional trusted root certificates
+    /// 
+    /// Needed behind TLS-intercepting proxies; the system roots stay trusted.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub root_certificates: Vec<PathBuf>,
+    
+    /// Certificate presented to the server
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_certificate: Option<ClientCertificate>,
+}
+
+impl TlsConfig {
+    /// Apply these settings to a reqwest client builder
+    pub(crate) fn apply(&self, mut builder: reqwest::ClientBuilder) -> crate::Result<reqwest::ClientBuilder> {
+        builder = match self.backend {
+            TlsBackend::Native => builder.use_native_tls(),
+            #[cfg(feature = "rustls")]
+            TlsBackend::Rustls => builder.use_rustls_tls(),
+            #[cfg(not(feature = "rustls"))]
+            TlsBackend::Rustls => return Err(crate::Error::ConfigError(
+                "rustls TLS backend requires the `rustls` feature".to_string()
+            )),
+        };
+        
+        for path in &self.root_certificates {
+            let certificates = reqwest::Certificate::from_pem_bundle(&read_pem(path)?)
+                .map_err(|e| crate::Error::ConfigError(
+                    format!("Invalid certificate in {}: {}", path.display(), e)
+                ))?;
+            for certificate in certificates {
+                builder = builder.add_root_certificate(certificate);
+            }
+        }
+        
+        if let Some(client) = &self.client_certificate {
+            builder = builder.identity(self.identity(client)?);
+        }
+        
+        Ok(builder)
+    }
+    
+    fn identity(&self, client: &ClientCertificate) -> crate::Result<reqwest::Identity> {
+        let cert = read_pem(&client.cert)?;
+        let key = read_pem(&client.key)?;
+        
+        let identity = match self.backend {
+            TlsBackend::Native => reqwest::Identity::from_pkcs8_pem(&cert, &key),
+            #[cfg(feature = "rustls")]
+            TlsBackend::Rustls => reqwest::Identity::from_pem(&[key, cert].concat()),
+            #[cfg(not(feature = "rustls"))]
+            TlsBackend::Rustls => unreachable!("rejected when selecting the backend"),
+        };
+        
+        identity.map_err(|e| crate::Error::ConfigError(
+            format!("Invalid client certificate {}: {}", client.cert.display(), e)
+        ))
+    }
+}
+
+/// Connection pool and socket settings
+/// 
+/// Unset fields keep reqwest's defaults. Keeping a few idle connections warm
+/// avoids a TLS handshake on every request after a quiet period.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConnectionConfig {
+    /// Idle connections kept open per host
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pool_max_idle_per_host: Option<usize>,
+    
+    /// How long an idle connection is kept before closing it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pool_idle_timeout_seconds: Option<u64>,
+    
+    /// TCP keepalive interval
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tcp_keepalive_seconds: Option<u64>,
+    
+    /// Speak HTTP/2 without negotiating it first
+    #[serde(default)]
+    pub http2_prior_knowledge: bool,
+    
+    /// Interval of HTTP/2 pings that keep connections alive
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub http2_keep_alive_seconds: Option<u64>,
+}
+
+impl ConnectionConfig {
+    /// Apply these settings to a reqwest client builder
+    pub(crate) fn apply(&self, mut builder: reqwest::ClientBuilder) -> reqwest::ClientBuilder {
+        if let Some(max) = self.pool_max_idle_per_host {
+            builder = builder.pool_max_idle_per_host(max);
+        }
+        if let Some(seconds) = self.pool_idle_timeout_seconds {
+            builder = builder.pool_idle_timeout(Duration::from_secs(seconds));
+        }
+        if let Some(seconds) = self.tcp_keepalive_seconds {
+            builder = builder.tcp_keepalive(Duration::from_secs(seconds));
+        }
+        if self.http2_prior_knowledge {
+            builder = builder.http2_prior_knowledge();
+        }
+        if let Some(seconds) = self.http2_keep_alive_seconds {
+            builder = builder
+                .http2_keep_alive_interval(Duration::from_secs(seconds))
+                .http2_keep_alive_while_idle(true);
+        }
+        builder
+    }
+}
+
+fn read_pem(path: &Path) -> crate::Result<Vec<u8>> {
+    std::fs::read(path)
+        .map_err(|e| crate::Error::ConfigError(
+            format!("Failed to read {}: {}", path.display(), e)
+        ))
+}
+
+/// HTTP(S) or SOCKS5 proxy settings
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProxyConfig {
+    /// Proxy URL, e.g. `http://proxy:3128` or `socks5h://proxy:1080`
+    pub url: String,
+    
+    /// Username for proxy authentication
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    
+    /// Password for proxy authentication
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+    
+    /// Hosts that bypass the proxy (same syntax as the NO_PROXY variable)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub no_proxy: Vec<String>,
+}
+
+impl ProxyConfig {
+    /// Proxy without authentication
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            username: None,
+            password: None,
+            no_proxy: Vec::new(),
+        }
+    }
+    
+    /// Authenticate with username and password
+    pub fn with_auth(mut self, username: impl Into<String>, password: impl Into<String>) -> Self {
+        self.username = Some(username.into());
+        self.password = Some(password.into());
+        self
+    }
+    
+    /// Add hosts that bypass the proxy
+    pub fn with_no_proxy(mut self, hosts: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.no_proxy.extend(hosts.into_iter().map(Into::into));
+        self
+    }
+    
+    /// Build the reqwest proxy
+    pub(crate) fn to_proxy(&self) -> crate::Result<reqwest::Proxy> {
+        let mut proxy = reqwest::Proxy::all(&self.url)
+            .map_err(|e| crate::Error::ConfigError(
+                format!("Invalid proxy URL {}: {}", self.url, e)
+            ))?;
+        
+        if let Some(username) = &self.username {
+            proxy = proxy.basic_auth(username, self.password.as_deref().unwrap_or(""));
+        }
+        
+        if !self.no_proxy.is_empty() {
+            proxy = proxy.no_proxy(reqwest::NoProxy::from_string(&self.no_proxy.join(",")));
+        }
+        
+        Ok(proxy)
+    }
+}
+
+/// Reference to a secret stored in the OS keyring
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyringEntry {
+    pub service: String,
+    pub user: String,
+}
+
+impl KeyringEntry {
+    /// Read the secret from the OS keyring
+    #[cfg(feature = "keyring")]
+    pub fn load(&self) -> crate::Result<String> {
+        keyring::Entry::new(&self.service, &self.user)
+            .and_then(|entry| entry.get_password())
+            .map_err(|e| crate::Error::ConfigError(format!(
+                "Failed to read keyring entry {}/{}: {}",
+                self.service, self.user, e
+            )))
+    }
+    
+    /// Read the secret from the OS keyring
+    #[cfg(not(feature = "keyring"))]
+    pub fn load(&self) -> crate::Result<String> {
+        Err(crate::Error::ConfigError(format!(
+            "Keyring entry {}/{} configured but the `keyring` feature is disabled",
+            self.service, self.user
+        )))
+    }
 }
 
 fn default_timeout() -> u64 { 10 }
 fn default_rate_limit() -> u32 { 100 }
+fn default_max_streams() -> u32 { 20 }
+fn default_instrument_cache_seconds() -> u64 { 300 }
 fn default_true() -> bool { true }
 fn default_max_retries() -> u32 { 3 }
+fn default_retry_statuses() -> Vec<u16> { vec![500, 502, 503, 504] }
+fn default_base_delay_ms() -> u64 { 100 }
+fn default_multiplier() -> f64 { 2.0 }
+fn default_max_delay_ms() -> u64 { 10_000 }
+fn default_failure_threshold() -> u32 { 5 }
+fn default_cooldown() -> u64 { 30 }
 
 impl OandaConfig {
+    /// Start building a configuration
+    /// 
+    /// ```
+    /// use oanda_connector::OandaConfig;
+    /// use std::time::Duration;
+    /// 
+    /// let config = OandaConfig::builder()
+    ///     .api_key("your-token")
+    ///     .account_id("101-004-1234567-001")
+    ///     .timeout(Duration::from_secs(20))
+    ///     .build()
+    ///     .unwrap();
+    /// assert!(config.practice);
+    /// ```
+    pub fn builder() -> OandaConfigBuilder {
+        OandaConfigBuilder::default()
+    }
+    
     /// Create new configuration
     pub fn new(api_key: String, account_id: String, practice: bool) -> Self {
         Self {
@@ -48,10 +637,29 @@ impl OandaConfig {
             account_id,
             practice,
             base_url: None,
+            stream_url: None,
             timeout_seconds: default_timeout(),
+            timeouts: TimeoutConfig::default(),
             requests_per_second: default_rate_limit(),
+            burst: None,
+            rate_limits: RateLimitConfig::default(),
+            queue_timeout_ms: None,
+            max_streams: default_max_streams(),
+            max_in_flight: None,
+            instrument_cache_seconds: default_instrument_cache_seconds(),
             enable_retries: default_true(),
             max_retries: default_max_retries(),
+            retry: RetryPolicy::default(),
+            circuit_breaker: None,
+            datetime_format: DatetimeFormat::default(),
+            strict_deserialization: false,
+            keyring: None,
+            proxy: None,
+            tls: TlsConfig::default(),
+            compression: default_true(),
+            connection: ConnectionConfig::default(),
+            user_agent: None,
+            default_headers: BTreeMap::new(),
         }
     }
     
@@ -64,43 +672,137 @@ impl OandaConfig {
     /// - OANDA_TIMEOUT_SECONDS (optional, default: 10)
     /// - OANDA_REQUESTS_PER_SECOND (optional, default: 100)
     pub fn from_env() -> crate::Result<Self> {
-        let api_key = std::env::var("OANDA_API_KEY")
-            .map_err(|_| crate::Error::ConfigError(
-                "OANDA_API_KEY environment variable not set".to_string()
-            ))?;
+        Self::from_env_with_prefix("OANDA_")
+    }
+    
+    /// Load configuration from environment variables with a custom prefix
+    /// 
+    /// Reads the same variables as `from_env` with `OANDA_` replaced by
+    /// `prefix`, so `from_env_with_prefix("MYAPP_OANDA_")` reads
+    /// `MYAPP_OANDA_API_KEY`, `MYAPP_OANDA_ACCOUNT_ID` and so on.
+    pub fn from_env_with_prefix(prefix: &str) -> crate::Result<Self> {
+        let var = |name: &str| std::env::var(format!("{}{}", prefix, name));
+        let required = |name: &str| var(name).map_err(|_| crate::Error::ConfigError(
+            format!("{}{} environment variable not set", prefix, name)
+        ));
         
-        let account_id = std::env::var("OANDA_ACCOUNT_ID")
-            .map_err(|_| crate::Error::ConfigError(
-                "OANDA_ACCOUNT_ID environment variable not set".to_string()
-            ))?;
+        let api_key = required("API_KEY")?;
         
-        let practice = std::env::var("OANDA_PRACTICE")
+        let account_id = required("ACCOUNT_ID")?;
+        
+        let practice = var("PRACTICE")
             .unwrap_or_else(|_| "true".to_string())
             .parse()
             .unwrap_or(true);
         
-        let timeout_seconds = std::env::var("OANDA_TIMEOUT_SECONDS")
+        let timeout_seconds = var("TIMEOUT_SECONDS")
             .ok()
             .and_then(|s| s.parse().ok())
             .unwrap_or(default_timeout());
         
-        let requests_per_second = std::env::var("OANDA_REQUESTS_PER_SECOND")
+        let requests_per_second = var("REQUESTS_PER_SECOND")
             .ok()
             .and_then(|s| s.parse().ok())
             .unwrap_or(default_rate_limit());
         
         Ok(Self {
-            api_key,
-            account_id,
-            practice,
-            base_url: None,
             timeout_seconds,
             requests_per_second,
-            enable_retries: default_true(),
-            max_retries: default_max_retries(),
+            ..Self::new(api_key, account_id, practice)
         })
     }
     
+    /// Load configuration from a TOML or YAML file
+    /// 
+    /// The format is picked from the extension (`.toml`, `.yaml` or `.yml`).
+    /// Field names match the struct; everything but `api_key` and
+    /// `account_id` is optional. The API key can instead come from the OS
+    /// keyring via a `keyring` table with `service` and `user`.
+    /// 
+    /// ```toml
+    /// api_key = "your-token"
+    /// account_id = "101-004-1234567-001"
+    /// practice = true
+    /// timeout_seconds = 30
+    /// ```
+    pub fn from_file(path: impl AsRef<Path>) -> crate::Result<Self> {
+        let mut config: Self = read_config_file(path.as_ref())?;
+        config.resolve_api_key()?;
+        config.validate()?;
+        Ok(config)
+    }
+    
+    /// Load a named profile from the default config file
+    /// 
+    /// The file is taken from `OANDA_CONFIG`, falling back to
+    /// `~/.config/oanda/config.toml`. See `from_profile_file` for the layout.
+    pub fn from_profile(name: &str) -> crate::Result<Self> {
+        Self::from_profile_file(default_config_path()?, name)
+    }
+    
+    /// Load a named profile from a TOML or YAML file
+    /// 
+    /// Profiles live under a `profiles` table. Top-level fields are shared
+    /// by every profile and a profile's own fields take precedence.
+    /// 
+    /// ```toml
+    /// timeout_seconds = 20
+    /// 
+    /// [profiles.practice]
+    /// api_key = "practice-token"
+    /// account_id = "101-004-1234567-001"
+    /// 
+    /// [profiles.live]
+    /// api_key = "live-token"
+    /// account_id = "001-004-1234567-001"
+    /// practice = false
+    /// ```
+    pub fn from_profile_file(path: impl AsRef<Path>, name: &str) -> crate::Result<Self> {
+        let path = path.as_ref();
+        let mut file: serde_json::Map<String, serde_json::Value> = read_config_file(path)?;
+        
+        let mut profiles = match file.remove("profiles") {
+            Some(serde_json::Value::Object(profiles)) => profiles,
+            _ => return Err(crate::Error::ConfigError(
+                format!("No profiles defined in {}", path.display())
+            )),
+        };
+        
+        let profile = match profiles.remove(name) {
+            Some(serde_json::Value::Object(profile)) => profile,
+            _ => {
+                let mut available: Vec<&String> = profiles.keys().collect();
+                available.sort();
+                return Err(crate::Error::ConfigError(format!(
+                    "Profile '{}' not found in {} (available: {:?})",
+                    name, path.display(), available
+                )));
+            }
+        };
+        
+        file.extend(profile);
+        let mut config: Self = serde_json::from_value(serde_json::Value::Object(file))
+            .map_err(|e| crate::Error::ConfigError(
+                format!("Invalid profile '{}' in {}: {}", name, path.display(), e)
+            ))?;
+        
+        config.resolve_api_key()?;
+        config.validate()?;
+        Ok(config)
+    }
+    
+    /// Fill in the API key from the keyring entry, if one is configured
+    /// 
+    /// An API key already present takes precedence over the keyring.
+    pub fn resolve_api_key(&mut self) -> crate::Result<()> {
+        if self.api_key.is_empty() {
+            if let Some(entry) = &self.keyring {
+                self.api_key = entry.load()?;
+            }
+        }
+        Ok(())
+    }
+    
     /// Get base URL based on practice flag
     pub fn get_base_url(&self) -> String {
         self.base_url.clone().unwrap_or_else(|| {
@@ -112,11 +814,55 @@ impl OandaConfig {
         })
     }
     
+    /// Get streaming base URL based on practice flag
+    /// 
+    /// Pricing and transaction streams are served from a separate host.
+    pub fn get_stream_url(&self) -> String {
+        self.stream_url.clone().unwrap_or_else(|| {
+            if self.practice {
+                "https://stream-fxpractice.oanda.com".to_string()
+            } else {
+                "https://stream-fxtrade.oanda.com".to_string()
+            }
+        })
+    }
+    
+    /// User-Agent header value
+    pub fn user_agent(&self) -> &str {
+        self.user_agent.as_deref().unwrap_or(DEFAULT_USER_AGENT)
+    }
+    
+    /// Default headers as a reqwest header map
+    pub(crate) fn header_map(&self) -> crate::Result<reqwest::header::HeaderMap> {
+        self.default_headers
+            .iter()
+            .map(|(name, value)| {
+                let invalid = |e: &dyn std::fmt::Display| crate::Error::ConfigError(
+                    format!("Invalid header {}: {}", name, e)
+                );
+                Ok((
+                    reqwest::header::HeaderName::try_from(name.as_str()).map_err(|e| invalid(&e))?,
+                    reqwest::header::HeaderValue::try_from(value.as_str()).map_err(|e| invalid(&e))?,
+                ))
+            })
+            .collect()
+    }
+    
     /// Get timeout as Duration
     pub fn timeout(&self) -> Duration {
         Duration::from_secs(self.timeout_seconds)
     }
     
+    /// Timeout for requests to an endpoint group
+    pub fn timeout_for(&self, group: EndpointGroup) -> Duration {
+        Duration::from_secs(self.timeouts.get(group).unwrap_or(self.timeout_seconds))
+    }
+    
+    /// Timeout for streaming connections, if any
+    pub fn stream_timeout(&self) -> Option<Duration> {
+        self.timeouts.streaming_seconds.map(Duration::from_secs)
+    }
+    
     /// Validate configuration
     pub fn validate(&self) -> crate::Result<()> {
         if self.api_key.is_empty() {
@@ -131,37 +877,410 @@ impl OandaConfig {
             ));
         }
         
+        RuntimeSettings::from(self).validate()?;
+        
+        if self.max_streams == 0 {
+            return Err(crate::Error::ConfigError(
+                "Maximum streams must be greater than 0".to_string()
+            ));
+        }
+        
+        if self.max_in_flight == Some(0) {
+            return Err(crate::Error::ConfigError(
+                "Maximum in-flight requests must be greater than 0".to_string()
+            ));
+        }
+        
+        if let Some(proxy) = &self.proxy {
+            proxy.to_proxy()?;
+        }
+        
+        reqwest::header::HeaderValue::try_from(self.user_agent())
+            .map_err(|_| crate::Error::ConfigError(
+                format!("Invalid User-Agent: {}", self.user_agent())
+            ))?;
+        self.header_map()?;
+        
+        if let Some(breaker) = &self.circuit_breaker {
+            if breaker.failure_threshold == 0 {
+                return Err(crate::Error::ConfigError(
+                    "Circuit breaker failure threshold must be greater than 0".to_string()
+                ));
+            }
+        }
+        
+        Ok(())
+    }
+}
+
+/// Settings that can be changed on a live client
+/// 
+/// Field names match `OandaConfig`, so a config file can be read as
+/// `RuntimeSettings` with everything else in it ignored. See
+/// `OandaClient::apply_settings` and `ConfigWatcher`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RuntimeSettings {
+    /// Request timeout in seconds
+    #[serde(default = "default_timeout")]
+    pub timeout_seconds: u64,
+    
+    /// Per-endpoint overrides of `timeout_seconds`
+    #[serde(default)]
+    pub timeouts: TimeoutConfig,
+    
+    /// Maximum requests per second
+    #[serde(default = "default_rate_limit")]
+    pub requests_per_second: u32,
+    
+    /// Requests allowed back to back (defaults to `requests_per_second`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub burst: Option<u32>,
+    
+    /// Dedicated quotas for endpoint groups, kept apart from `requests_per_second`
+    #[serde(default)]
+    pub rate_limits: RateLimitConfig,
+    
+    /// Longest a request may wait for the rate limiter before failing with
+    /// `Error::Timeout` (waits indefinitely when unset)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub queue_timeout_ms: Option<u64>,
+    
+    /// Enable automatic retries
+    #[serde(default = "default_true")]
+    pub enable_retries: bool,
+    
+    /// Maximum retry attempts
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+    
+    /// Which failed responses are retried
+    #[serde(default)]
+    pub retry: RetryPolicy,
+}
+
+impl RuntimeSettings {
+    /// Read settings from a TOML or YAML config file
+    /// 
+    /// Only top-level fields are read; profiles are not applied.
+    pub fn from_file(path: impl AsRef<Path>) -> crate::Result<Self> {
+        let settings: Self = read_config_file(path.as_ref())?;
+        settings.validate()?;
+        Ok(settings)
+    }
+    
+    /// Timeout for requests to an endpoint group
+    pub fn timeout_for(&self, group: EndpointGroup) -> Duration {
+        Duration::from_secs(self.timeouts.get(group).unwrap_or(self.timeout_seconds))
+    }
+    
+    /// Burst size for the rate limiter
+    pub fn burst(&self) -> u32 {
+        self.burst.unwrap_or(self.requests_per_second)
+    }
+    
+    /// Deadline for waiting on the rate limiter, if any
+    pub fn queue_timeout(&self) -> Option<Duration> {
+        self.queue_timeout_ms.map(Duration::from_millis)
+    }
+    
+    /// Validate settings
+    pub fn validate(&self) -> crate::Result<()> {
         if self.timeout_seconds == 0 {
             return Err(crate::Error::ConfigError(
                 "Timeout must be greater than 0".to_string()
             ));
         }
         
+        let timeouts = &self.timeouts;
+        if [
+            timeouts.pricing_seconds,
+            timeouts.candles_seconds,
+            timeouts.books_seconds,
+            timeouts.account_seconds,
+            timeouts.transactions_seconds,
+            timeouts.orders_seconds,
+            timeouts.streaming_seconds,
+        ].contains(&Some(0)) {
+            return Err(crate::Error::ConfigError(
+                "Endpoint timeouts must be greater than 0".to_string()
+            ));
+        }
+        
         if self.requests_per_second == 0 {
             return Err(crate::Error::ConfigError(
                 "Requests per second must be greater than 0".to_string()
             ));
         }
         
-        Ok(())
+        if self.burst == Some(0) {
+            return Err(crate::Error::ConfigError(
+                "Burst must be greater than 0".to_string()
+            ));
+        }
+        
+        if EndpointGroup::ALL.iter().any(|&group| self.rate_limits.get(group) == Some(0)) {
+            return Err(crate::Error::ConfigError(
+                "Endpoint rate limits must be greater than 0".to_string()
+            ));
+        }
+        
+        self.retry.validate()
     }
 }
 
-impl Default for OandaConfig {
-    fn default() -> Self {
+impl From<&OandaConfig> for RuntimeSettings {
+    fn from(config: &OandaConfig) -> Self {
         Self {
-            api_key: String::new(),
-            account_id: String::new(),
-            practice: true,
-            base_url: None,
-            timeout_seconds: default_timeout(),
-            requests_per_second: default_rate_limit(),
-            enable_retries: default_true(),
-            max_retries: default_max_retries(),
+            timeout_seconds: config.timeout_seconds,
+            timeouts: config.timeouts.clone(),
+            requests_per_second: config.requests_per_second,
+            burst: config.burst,
+            rate_limits: config.rate_limits.clone(),
+            queue_timeout_ms: config.queue_timeout_ms,
+            enable_retries: config.enable_retries,
+            max_retries: config.max_retries,
+            retry: config.retry.clone(),
         }
     }
 }
 
+/// Builder for `OandaConfig`
+/// 
+/// Starts from the defaults (practice account) and validates on `build`.
+#[derive(Debug, Clone, Default)]
+pub struct OandaConfigBuilder {
+    config: OandaConfig,
+}
+
+impl OandaConfigBuilder {
+    /// Set API key
+    pub fn api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.config.api_key = api_key.into();
+        self
+    }
+    
+    /// Set account ID
+    pub fn account_id(mut self, account_id: impl Into<String>) -> Self {
+        self.config.account_id = account_id.into();
+        self
+    }
+    
+    /// Use practice (true) or live (false) environment
+    pub fn practice(mut self, practice: bool) -> Self {
+        self.config.practice = practice;
+        self
+    }
+    
+    /// Override base URL
+    pub fn base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.config.base_url = Some(base_url.into());
+        self
+    }
+    
+    /// Override streaming base URL
+    pub fn stream_url(mut self, stream_url: impl Into<String>) -> Self {
+        self.config.stream_url = Some(stream_url.into());
+        self
+    }
+    
+    /// Set request timeout (whole seconds)
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.config.timeout_seconds = timeout.as_secs();
+        self
+    }
+    
+    /// Override timeout for one endpoint group (whole seconds)
+    pub fn endpoint_timeout(mut self, group: EndpointGroup, timeout: Duration) -> Self {
+        self.config.timeouts.set(group, Some(timeout.as_secs()));
+        self
+    }
+    
+    /// Set timeout for streaming connections (whole seconds)
+    pub fn stream_timeout(mut self, timeout: Duration) -> Self {
+        self.config.timeouts.streaming_seconds = Some(timeout.as_secs());
+        self
+    }
+    
+    /// Set rate limit
+    pub fn requests_per_second(mut self, requests_per_second: u32) -> Self {
+        self.config.requests_per_second = requests_per_second;
+        self
+    }
+    
+    /// Give an endpoint group its own rate limit
+    pub fn endpoint_rate_limit(mut self, group: EndpointGroup, requests_per_second: u32) -> Self {
+        self.config.rate_limits.set(group, Some(requests_per_second));
+        self
+    }
+    
+    /// Fail requests that wait longer than `timeout` for the rate limiter
+    pub fn queue_timeout(mut self, timeout: Duration) -> Self {
+        self.config.queue_timeout_ms = Some(timeout.as_millis() as u64);
+        self
+    }
+    
+    /// Set how many requests may be sent back to back
+    /// 
+    /// Use 1 for strictly even spacing; defaults to the per-second rate.
+    pub fn burst(mut self, burst: u32) -> Self {
+        self.config.burst = Some(burst);
+        self
+    }
+    
+    /// Enable or disable compressed responses (on by default)
+    pub fn compression(mut self, enable: bool) -> Self {
+        self.config.compression = enable;
+        self
+    }
+    
+    /// Set the maximum number of concurrent streaming connections
+    pub fn max_streams(mut self, max_streams: u32) -> Self {
+        self.config.max_streams = max_streams;
+        self
+    }
+    
+    /// Limit how many REST requests may await a response at once
+    pub fn max_in_flight(mut self, max_in_flight: u32) -> Self {
+        self.config.max_in_flight = Some(max_in_flight);
+        self
+    }
+    
+    /// Set how long the instrument list is reused before it is fetched again
+    /// 
+    /// Use `Duration::ZERO` to fetch it on every call.
+    pub fn instrument_cache_ttl(mut self, ttl: Duration) -> Self {
+        self.config.instrument_cache_seconds = ttl.as_secs();
+        self
+    }
+    
+    /// Enable/disable retries
+    pub fn retries(mut self, enable: bool) -> Self {
+        self.config.enable_retries = enable;
+        self
+    }
+    
+    /// Set max retry attempts
+    pub fn max_retries(mut self, max: u32) -> Self {
+        self.config.max_retries = max;
+        self
+    }
+    
+    /// Set which failed responses are retried
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.config.retry = policy;
+        self
+    }
+    
+    /// Open a circuit breaker after `failure_threshold` consecutive failures
+    pub fn circuit_breaker(mut self, failure_threshold: u32, cooldown: Duration) -> Self {
+        self.config.circuit_breaker = Some(CircuitBreakerConfig {
+            failure_threshold,
+            cooldown_seconds: cooldown.as_secs(),
+        });
+        self
+    }
+    
+    /// Set datetime format requested from the API
+    pub fn datetime_format(mut self, format: DatetimeFormat) -> Self {
+        self.config.datetime_format = format;
+        self
+    }
+    
+    /// Enable/disable strict deserialization
+    pub fn strict(mut self, enable: bool) -> Self {
+        self.config.strict_deserialization = enable;
+        self
+    }
+    
+    /// Read the API key from the OS keyring
+    pub fn keyring(mut self, service: impl Into<String>, user: impl Into<String>) -> Self {
+        self.config.keyring = Some(KeyringEntry {
+            service: service.into(),
+            user: user.into(),
+        });
+        self
+    }
+    
+    /// Route requests through a proxy
+    pub fn proxy(mut self, proxy: ProxyConfig) -> Self {
+        self.config.proxy = Some(proxy);
+        self
+    }
+    
+    /// Set TLS configuration
+    pub fn tls(mut self, tls: TlsConfig) -> Self {
+        self.config.tls = tls;
+        self
+    }
+    
+    /// Set connection pool, keepalive and HTTP/2 configuration
+    pub fn connection(mut self, connection: ConnectionConfig) -> Self {
+        self.config.connection = connection;
+        self
+    }
+    
+    /// Set User-Agent
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.config.user_agent = Some(user_agent.into());
+        self
+    }
+    
+    /// Add a header sent with every request
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.config.default_headers.insert(name.into(), value.into());
+        self
+    }
+    
+    /// Resolve the keyring entry (if any) and validate
+    pub fn build(self) -> crate::Result<OandaConfig> {
+        let mut config = self.config;
+        config.resolve_api_key()?;
+        config.validate()?;
+        Ok(config)
+    }
+}
+
+/// Config file used by `OandaConfig::from_profile`
+fn default_config_path() -> crate::Result<PathBuf> {
+    if let Ok(path) = std::env::var("OANDA_CONFIG") {
+        return Ok(PathBuf::from(path));
+    }
+    
+    std::env::var("HOME")
+        .map(|home| PathBuf::from(home).join(".config/oanda/config.toml"))
+        .map_err(|_| crate::Error::ConfigError(
+            "Neither OANDA_CONFIG nor HOME is set".to_string()
+        ))
+}
+
+/// Read a TOML or YAML file, picking the format from the extension
+fn read_config_file<T: DeserializeOwned>(path: &Path) -> crate::Result<T> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| crate::Error::ConfigError(
+            format!("Failed to read {}: {}", path.display(), e)
+        ))?;
+    
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("toml") => toml::from_str(&contents)
+            .map_err(|e| crate::Error::ConfigError(
+                format!("Invalid TOML in {}: {}", path.display(), e)
+            )),
+        Some("yaml") | Some("yml") => serde_yaml::from_str(&contents)
+            .map_err(|e| crate::Error::ConfigError(
+                format!("Invalid YAML in {}: {}", path.display(), e)
+            )),
+        _ => Err(crate::Error::ConfigError(
+            format!("Unsupported config file format: {}", path.display())
+        )),
+    }
+}
+
+impl Default for OandaConfig {
+    fn default() -> Self {
+        Self::new(String::new(), String::new(), true)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -174,6 +1293,7 @@ mod tests {
             true
         );
         assert!(config_practice.get_base_url().contains("fxpractice"));
+        assert_eq!(config_practice.get_stream_url(), "https://stream-fxpractice.oanda.com");
         
         let config_live = OandaConfig::new(
             "key".to_string(),
@@ -181,6 +1301,7 @@ mod tests {
             false
         );
         assert!(config_live.get_base_url().contains("fxtrade"));
+        assert_eq!(config_live.get_stream_url(), "https://stream-fxtrade.oanda.com");
     }
 
     #[test]
@@ -192,4 +1313,274 @@ mod tests {
         config.account_id = "test_id".to_string();
         assert!(config.validate().is_ok());
     }
+
+    #[test]
+    fn test_config_from_file() {
+        let dir = std::env::temp_dir();
+        
+        let toml_path = dir.join("oanda_connector_test_config.toml");
+        std::fs::write(&toml_path, r#"
+api_key = "toml_key"
+account_id = "101-004-1234567-001"
+practice = false
+timeout_seconds = 30
+datetime_format = "UNIX"
+"#).unwrap();
+        let config = OandaConfig::from_file(&toml_path).unwrap();
+        std::fs::remove_file(&toml_path).unwrap();
+        assert_eq!(config.api_key, "toml_key");
+        assert!(!config.practice);
+        assert_eq!(config.timeout_seconds, 30);
+        assert_eq!(config.datetime_format, DatetimeFormat::Unix);
+        assert_eq!(config.requests_per_second, default_rate_limit());
+        
+        let yaml_path = dir.join("oanda_connector_test_config.yml");
+        std::fs::write(&yaml_path, "api_key: yaml_key\naccount_id: '101-004-1234567-001'\n").unwrap();
+        let config = OandaConfig::from_file(&yaml_path).unwrap();
+        std::fs::remove_file(&yaml_path).unwrap();
+        assert_eq!(config.api_key, "yaml_key");
+        assert!(config.practice);
+        
+        assert!(OandaConfig::from_file(dir.join("missing.toml")).is_err());
+        assert!(OandaConfig::from_file(dir.join("config.json")).is_err());
+    }
+
+    #[test]
+    fn test_config_profiles() {
+        let path = std::env::temp_dir().join("oanda_connector_test_profiles.toml");
+        std::fs::write(&path, r#"
+timeout_seconds = 20
+
+[profiles.practice]
+api_key = "practice_key"
+account_id = "101-004-1234567-001"
+
+[profiles.live]
+api_key = "live_key"
+account_id = "001-004-1234567-001"
+practice = false
+timeout_seconds = 5
+"#).unwrap();
+        
+        let practice = OandaConfig::from_profile_file(&path, "practice").unwrap();
+        let live = OandaConfig::from_profile_file(&path, "live").unwrap();
+        let missing = OandaConfig::from_profile_file(&path, "demo2");
+        std::fs::remove_file(&path).unwrap();
+        
+        assert_eq!(practice.api_key, "practice_key");
+        assert!(practice.practice);
+        assert_eq!(practice.timeout_seconds, 20);
+        
+        assert_eq!(live.api_key, "live_key");
+        assert!(!live.practice);
+        assert_eq!(live.timeout_seconds, 5);
+        
+        match missing {
+            Err(crate::Error::ConfigError(message)) => assert!(message.contains("live")),
+            other => panic!("Expected ConfigError, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_keyring_entry_config() {
+        let path = std::env::temp_dir().join("oanda_connector_test_keyring.yaml");
+        std::fs::write(&path, "account_id: '101-004-1234567-001'\nkeyring:\n  service: oanda\n  user: practice\n").unwrap();
+        let config: OandaConfig = read_config_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        
+        assert!(config.api_key.is_empty());
+        assert_eq!(config.keyring, Some(KeyringEntry {
+            service: "oanda".to_string(),
+            user: "practice".to_string(),
+        }));
+        
+        // An explicit key wins without touching the keyring
+        let mut explicit = config.clone();
+        explicit.api_key = "explicit_key".to_string();
+        explicit.resolve_api_key().unwrap();
+        assert_eq!(explicit.api_key, "explicit_key");
+    }
+
+    #[test]
+    fn test_proxy_config() {
+        let mut config = OandaConfig::new("key".to_string(), "id".to_string(), true);
+        config.proxy = Some(ProxyConfig::new("socks5h://127.0.0.1:1080")
+            .with_auth("user", "secret")
+            .with_no_proxy(["localhost", ".internal"]));
+        assert!(config.validate().is_ok());
+        
+        config.proxy = Some(ProxyConfig::new("not a url"));
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_tls_config() {
+        let config: OandaConfig = toml::from_str(r#"
+api_key = "key"
+account_id = "id"
+
+[tls]
+backend = "rustls"
+root_certificates = ["/etc/ssl/corp-ca.pem"]
+"#).unwrap();
+        assert_eq!(config.tls.backend, TlsBackend::Rustls);
+        assert_eq!(config.tls.root_certificates.len(), 1);
+        
+        // Unreadable certificates fail when the client is built
+        let result = config.tls.apply(reqwest::Client::builder());
+        assert!(matches!(result, Err(crate::Error::ConfigError(_))));
+    }
+
+    #[test]
+    fn test_connection_config() {
+        let config: OandaConfig = toml::from_str(r#"
+api_key = "key"
+account_id = "id"
+
+[connection]
+pool_max_idle_per_host = 4
+pool_idle_timeout_seconds = 300
+tcp_keepalive_seconds = 30
+http2_keep_alive_seconds = 20
+"#).unwrap();
+        assert_eq!(config.connection.pool_max_idle_per_host, Some(4));
+        assert_eq!(config.connection.pool_idle_timeout_seconds, Some(300));
+        assert!(!config.connection.http2_prior_knowledge);
+        assert!(config.connection.apply(reqwest::Client::builder()).build().is_ok());
+    }
+
+    #[test]
+    fn test_headers_config() {
+        let mut config = OandaConfig::new("key".to_string(), "id".to_string(), true);
+        assert!(config.user_agent().starts_with("oanda-connector/"));
+        
+        config.default_headers.insert("X-Desk".to_string(), "fx-1".to_string());
+        assert_eq!(config.header_map().unwrap()["x-desk"], "fx-1");
+        
+        config.default_headers.insert("bad header".to_string(), "x".to_string());
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_endpoint_timeouts() {
+        let mut config = OandaConfig::new("key".to_string(), "id".to_string(), true);
+        config.timeouts.pricing_seconds = Some(2);
+        config.timeouts.candles_seconds = Some(120);
+        
+        assert_eq!(config.timeout_for(EndpointGroup::Pricing), Duration::from_secs(2));
+        assert_eq!(config.timeout_for(EndpointGroup::Candles), Duration::from_secs(120));
+        assert_eq!(config.timeout_for(EndpointGroup::Account), config.timeout());
+        assert_eq!(config.stream_timeout(), None);
+        
+        config.timeouts.books_seconds = Some(0);
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_config_builder() {
+        let config = OandaConfig::builder()
+            .api_key("key")
+            .account_id("id")
+            .practice(false)
+            .endpoint_timeout(EndpointGroup::Pricing, Duration::from_secs(2))
+            .header("X-Desk", "fx-1")
+            .build()
+            .unwrap();
+        assert!(!config.practice);
+        assert_eq!(config.timeout_for(EndpointGroup::Pricing), Duration::from_secs(2));
+        
+        assert!(OandaConfig::builder().api_key("key").build().is_err());
+        assert!(OandaConfig::builder()
+            .api_key("key")
+            .account_id("id")
+            .timeout(Duration::from_millis(500))
+            .build()
+            .is_err());
+        assert!(OandaConfig::builder()
+            .api_key("key")
+            .account_id("id")
+            .max_streams(0)
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    fn test_from_env_with_prefix() {
+        std::env::set_var("CFGTEST_OANDA_API_KEY", "key");
+        std::env::set_var("CFGTEST_OANDA_ACCOUNT_ID", "101-001-1-001");
+        std::env::set_var("CFGTEST_OANDA_PRACTICE", "false");
+        
+        let config = OandaConfig::from_env_with_prefix("CFGTEST_OANDA_").unwrap();
+        assert_eq!(config.api_key, "key");
+        assert_eq!(config.account_id, "101-001-1-001");
+        assert!(!config.practice);
+        
+        match OandaConfig::from_env_with_prefix("CFGTEST_MISSING_") {
+            Err(crate::Error::ConfigError(message)) => {
+                assert!(message.contains("CFGTEST_MISSING_API_KEY"));
+            }
+            other => panic!("expected ConfigError, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_runtime_settings_from_file() {
+        let path = std::env::temp_dir().join("oanda_connector_test_settings.toml");
+        std::fs::write(&path, r#"
+            api_key = "ignored"
+            requests_per_second = 20
+            max_retries = 1
+            
+            [timeouts]
+            candles_seconds = 60
+        "#).unwrap();
+        let settings = RuntimeSettings::from_file(&path).unwrap();
+        assert_eq!(settings.requests_per_second, 20);
+        assert_eq!(settings.burst(), 20);
+        assert_eq!(settings.max_retries, 1);
+        assert!(settings.enable_retries);
+        assert_eq!(settings.timeout_for(EndpointGroup::Candles), Duration::from_secs(60));
+        assert_eq!(settings.timeout_for(EndpointGroup::Pricing), Duration::from_secs(10));
+        
+        std::fs::write(&path, "requests_per_second = 0").unwrap();
+        assert!(RuntimeSettings::from_file(&path).is_err());
+        
+        std::fs::write(&path, "[rate_limits]\ncandles_per_second = 5").unwrap();
+        let settings = RuntimeSettings::from_file(&path).unwrap();
+        assert_eq!(settings.rate_limits.get(EndpointGroup::Candles), Some(5));
+        assert_eq!(settings.rate_limits.get(EndpointGroup::Pricing), None);
+        assert_eq!(settings.rate_limits.burst(EndpointGroup::Candles, settings.burst()), Some(5));
+        assert_eq!(settings.rate_limits.burst(EndpointGroup::Candles, 1), Some(1));
+        assert_eq!(settings.rate_limits.burst(EndpointGroup::Pricing, 1), None);
+        std::fs::write(&path, "[rate_limits]\norders_per_second = 0").unwrap();
+        assert!(RuntimeSettings::from_file(&path).is_err());
+        
+        std::fs::write(&path, "burst = 1").unwrap();
+        assert_eq!(RuntimeSettings::from_file(&path).unwrap().burst(), 1);
+        std::fs::write(&path, "burst = 0").unwrap();
+        assert!(RuntimeSettings::from_file(&path).is_err());
+    }
+
+    #[test]
+    fn test_retry_policy_backoff() {
+        let policy = RetryPolicy {
+            jitter: false,
+            max_delay_ms: 1000,
+            retry_budget_ms: Some(5000),
+            ..Default::default()
+        };
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(3), Duration::from_millis(400));
+        assert_eq!(policy.backoff(10), Duration::from_millis(1000));
+        assert!(policy.within_budget(Duration::from_secs(5)));
+        assert!(!policy.within_budget(Duration::from_secs(6)));
+        
+        let jittered = RetryPolicy { jitter: true, ..policy.clone() };
+        for attempt in 1..10 {
+            assert!(jittered.backoff(attempt) <= policy.backoff(attempt));
+        }
+        
+        let invalid = RetryPolicy { multiplier: 0.5, ..Default::default() };
+        assert!(invalid.validate().is_err());
+    }
 }
\ No newline at end of file
@@ -0,0 +1,112 @@
+//! Reload runtime settings from a config file
+//!
+//! `ConfigWatcher` polls a TOML or YAML file and applies its rate limit,
+//! timeout and retry settings to a live client whenever they change, so
+//! throughput can be tuned without restarting the process. Credentials,
+//! account and transport settings are only read when the client is built.
+//!
+//! ```no_run
+//! use oanda_connector::{ConfigWatcher, OandaClient, OandaConfig};
+//! use std::time::Duration;
+//!
+//! # fn main() -> oanda_connector::Result<()> {
+//! let config = OandaConfig::from_file("oanda.toml")?;
+//! let client = OandaClient::new(config)?;
+//! let _watcher = ConfigWatcher::spawn(client.clone(), "oanda.toml", Duration::from_secs(5));
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::client::OandaClient;
+use crate::config::RuntimeSettings;
+use crate::shutdown::ShutdownHandle;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Background task applying config file changes to a client
+///
+/// Stops when dropped. Must be created inside a Tokio runtime.
+pub struct ConfigWatcher {
+    task: ShutdownHandle,
+    last_error: Arc<Mutex<Option<String>>>,
+}
+
+impl ConfigWatcher {
+    /// Check `path` every `interval` and apply changed settings to `client`
+    ///
+    /// An unreadable or invalid file leaves the current settings in place;
+    /// the problem is reported by `last_error` until the file is fixed.
+    pub fn spawn(client: OandaClient, path: impl Into<PathBuf>, interval: Duration) -> Self {
+        let path = path.into();
+        let last_error = Arc::new(Mutex::new(None));
+        let errors = last_error.clone();
+
+        let task = ShutdownHandle::spawn(|mut signal| async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                tokio::select! {
+                    _ = ticker.tick() => {}
+                    _ = signal.requested() => break,
+                }
+
+                let result = RuntimeSettings::from_file(&path).and_then(|settings| {
+                    if settings != client.settings() {
+                        client.apply_settings(settings)?;
+                    }
+                    Ok(())
+                });
+                *errors.lock().unwrap() = result.err().map(|e| e.to_string());
+            }
+        });
+
+        Self { task, last_error }
+    }
+
+    /// Why the last reload failed, if it did
+    pub fn last_error(&self) -> Option<String> {
+        self.last_error.lock().unwrap().clone()
+    }
+
+    /// Stop watching
+    pub fn stop(self) {}
+
+    /// Stop watching once any reload in progress has been applied
+    pub async fn shutdown(self) {
+        self.task.shutdown().await;
+    }
+}
+
+impl From<ConfigWatcher> for ShutdownHandle {
+    fn from(watcher: ConfigWatcher) -> Self {
+        watcher.task
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::OandaConfig;
+
+    #[tokio::test]
+    async fn test_watcher_applies_changes() {
+        let path = std::env::temp_dir().join("oanda_connector_test_watcher.toml");
+        std::fs::write(&path, "requests_per_second = 100").unwrap();
+
+        let client =
+            OandaClient::new(OandaConfig::new("key".to_string(), "id".to_string(), true)).unwrap();
+        let watcher = ConfigWatcher::spawn(client.clone(), &path, Duration::from_millis(20));
+
+        std::fs::write(&path, "requests_per_second = 20\nmax_retries = 0").unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(client.settings().requests_per_second, 20);
+        assert_eq!(client.settings().max_retries, 0);
+
+        std::fs::write(&path, "timeout_seconds = 0").unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(watcher.last_error().is_some());
+        assert_eq!(client.settings().requests_per_second, 20);
+
+        watcher.shutdown().await;
+    }
+}
@@ -0,0 +1,309 @@
+//! Cross-rate currency conversion
+//!
+//! Resolves which instruments are needed to convert between two currencies
+//! (directly, or triangulated through USD) and prices the conversion from
+//! current mid prices.
+
+use crate::client::OandaClient;
+use crate::error::{Error, Result};
+use crate::models::{Instrument, Tick};
+use rust_decimal::Decimal;
+use std::collections::{HashMap, HashSet};
+use tokio::sync::RwLock;
+
+/// Currency used to triangulate when no direct instrument exists
+pub const CROSS_CURRENCY: &str = "USD";
+
+/// One step of a conversion path
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConversionLeg {
+    pub instrument: String,
+    pub inverted: bool, // true if the amount is in the quote currency
+}
+
+impl ConversionLeg {
+    /// Apply this leg to an amount given the instrument's mid price
+    ///
+    /// Returns None if the result does not fit or an inverted leg is priced
+    /// at zero.
+    pub fn apply(&self, amount: Decimal, mid: Decimal) -> Option<Decimal> {
+        if self.inverted {
+            amount.checked_div(mid)
+        } else {
+            amount.checked_mul(mid)
+        }
+    }
+}
+
+/// Find the instruments needed to convert `from` into `to`
+///
+/// Prefers a direct pair in either direction and falls back to a cross
+/// through USD. Returns an empty path when the currencies are equal and
+/// `None` when no path exists in the given instrument list.
+pub fn resolve_path(
+    from: &str,
+    to: &str,
+    instruments: &[Instrument],
+) -> Option<Vec<ConversionLeg>> {
+    let names: HashSet<&str> = instruments.iter().map(|i| i.name.as_str()).collect();
+    resolve_path_in(from, to, &names)
+}
+
+fn resolve_path_in(from: &str, to: &str, names: &HashSet<&str>) -> Option<Vec<ConversionLeg>> {
+    if from == to {
+        return Some(Vec::new());
+    }
+
+    if let Some(leg) = direct_leg(from, to, names) {
+        return Some(vec![leg]);
+    }
+
+    if from != CROSS_CURRENCY && to != CROSS_CURRENCY {
+        let first = direct_leg(from, CROSS_CURRENCY, names)?;
+        let second = direct_leg(CROSS_CURRENCY, to, names)?;
+        return Some(vec![first, second]);
+    }
+
+    None
+}
+
+fn direct_leg(from: &str, to: &str, names: &HashSet<&str>) -> Option<ConversionLeg> {
+    let forward = format!("{}_{}", from, to);
+    if names.contains(forward.as_str()) {
+        return Some(ConversionLeg {
+            instrument: forward,
+            inverted: false,
+        });
+    }
+
+    let inverse = format!("{}_{}", to, from);
+    if names.contains(inverse.as_str()) {
+        return Some(ConversionLeg {
+            instrument: inverse,
+            inverted: true,
+        });
+    }
+
+    None
+}
+
+/// Convert an amount along a resolved path using the given prices
+///
+/// Fails with `Error::InvalidInstrument` if a leg has no price and with
+/// `Error::ParseError` if a price can't be converted at, e.g. zero.
+pub fn convert_with_prices(
+    amount: Decimal,
+    path: &[ConversionLeg],
+    prices: &[Tick],
+) -> Result<Decimal> {
+    path.iter().try_fold(amount, |amount, leg| {
+        let tick = prices
+            .iter()
+            .find(|t| t.instrument == leg.instrument)
+            .ok_or_else(|| Error::InvalidInstrument(leg.instrument.clone()))?;
+        leg.apply(amount, tick.mid())
+            .ok_or_else(|| Error::ParseError {
+                field: format!("{} price", leg.instrument),
+                value: tick.mid().to_string(),
+            })
+    })
+}
+
+/// Converts amounts between currencies using live pricing
+///
+/// The instrument list is fetched once and cached; call
+/// `refresh_instruments` to reload it. Prices are fetched on every
+/// conversion.
+pub struct CurrencyConverter {
+    client: OandaClient,
+    instruments: RwLock<Option<Vec<String>>>,
+}
+
+impl CurrencyConverter {
+    /// Create converter backed by the given client
+    pub fn new(client: OandaClient) -> Self {
+        Self {
+            client,
+            instruments: RwLock::new(None),
+        }
+    }
+
+    /// Create converter with a known instrument list
+    pub fn with_instruments(client: OandaClient, instruments: &[Instrument]) -> Self {
+        let names = instruments.iter().map(|i| i.name.clone()).collect();
+        Self {
+            client,
+            instruments: RwLock::new(Some(names)),
+        }
+    }
+
+    /// Reload the cached instrument list
+    pub async fn refresh_instruments(&self) -> Result<()> {
+        let instruments = self.client.get_instruments().await?;
+        *self.instruments.write().await = Some(instruments.into_iter().map(|i| i.name).collect());
+        Ok(())
+    }
+
+    /// Resolve the conversion path from `from` to `to`
+    pub async fn path(&self, from: &str, to: &str) -> Result<Vec<ConversionLeg>> {
+        if self.instruments.read().await.is_none() {
+            self.refresh_instruments().await?;
+        }
+
+        let cache = self.instruments.read().await;
+        let names: HashSet<&str> = cache.iter().flatten().map(String::as_str).collect();
+        resolve_path_in(from, to, &names).ok_or_else(|| {
+            Error::InvalidInstrument(format!("No conversion path from {} to {}", from, to))
+        })
+    }
+
+    /// Convert `amount` of `from` currency into `to` currency at mid prices
+    pub async fn convert(&self, amount: Decimal, from: &str, to: &str) -> Result<Decimal> {
+        let path = self.path(from, to).await?;
+        if path.is_empty() {
+            return Ok(amount);
+        }
+
+        let instruments: Vec<String> = path.iter().map(|leg| leg.instrument.clone()).collect();
+        let prices = self.client.get_current_prices(&instruments).await?;
+        convert_with_prices(amount, &path, &prices)
+    }
+
+    /// Value of one unit of each of `currencies` in `to`
+    ///
+    /// Prices every conversion with a single pricing request. Currencies
+    /// with no conversion path, or whose instruments come back unpriced,
+    /// are left out of the result.
+    pub async fn rates<'a>(
+        &self,
+        currencies: impl IntoIterator<Item = &'a str>,
+        to: &str,
+    ) -> Result<HashMap<String, Decimal>> {
+        let mut paths = HashMap::new();
+        for currency in currencies {
+            if paths.contains_key(currency) {
+                continue;
+            }
+            match self.path(currency, to).await {
+                Ok(path) => {
+                    paths.insert(currency.to_string(), path);
+                }
+                Err(Error::InvalidInstrument(_)) => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        let mut instruments: Vec<String> = paths
+            .values()
+            .flatten()
+            .map(|leg| leg.instrument.clone())
+            .collect();
+        instruments.sort();
+        instruments.dedup();
+        let prices = if instruments.is_empty() {
+            Vec::new()
+        } else {
+            self.client.get_current_prices(&instruments).await?
+        };
+
+        Ok(paths
+            .into_iter()
+            .filter_map(|(currency, path)| {
+                let rate = convert_with_prices(Decimal::ONE, &path, &prices).ok()?;
+                Some((currency, rate))
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::PriceStatus;
+    use chrono::Utc;
+    use rust_decimal_macros::dec;
+
+    fn instrument(name: &str) -> Instrument {
+        Instrument {
+            name: name.to_string(),
+            display_name: name.replace('_', "/"),
+            pip_location: -4,
+            trade_units_precision: 0,
+            minimum_trade_size: dec!(1),
+            maximum_trade_size: dec!(100000000),
+            margin_rate: dec!(0.02),
+            ..Default::default()
+        }
+    }
+
+    fn tick(instrument: &str, mid: Decimal) -> Tick {
+        Tick {
+            instrument: instrument.to_string(),
+            timestamp: Utc::now(),
+            bid: mid,
+            ask: mid,
+            tradeable: true,
+            status: PriceStatus::Tradeable,
+            closeout_bid: None,
+            closeout_ask: None,
+        }
+    }
+
+    #[test]
+    fn test_resolve_path() {
+        let instruments = vec![
+            instrument("EUR_USD"),
+            instrument("USD_JPY"),
+            instrument("GBP_USD"),
+        ];
+
+        assert_eq!(resolve_path("EUR", "EUR", &instruments), Some(vec![]));
+
+        let direct = resolve_path("EUR", "USD", &instruments).unwrap();
+        assert_eq!(direct.len(), 1);
+        assert!(!direct[0].inverted);
+
+        let inverse = resolve_path("USD", "EUR", &instruments).unwrap();
+        assert_eq!(inverse[0].instrument, "EUR_USD");
+        assert!(inverse[0].inverted);
+
+        let cross = resolve_path("EUR", "JPY", &instruments).unwrap();
+        assert_eq!(cross.len(), 2);
+        assert_eq!(cross[0].instrument, "EUR_USD");
+        assert_eq!(cross[1].instrument, "USD_JPY");
+
+        assert!(resolve_path("EUR", "CHF", &instruments).is_none());
+    }
+
+    #[test]
+    fn test_convert_with_prices() {
+        let instruments = vec![instrument("EUR_USD"), instrument("USD_JPY")];
+        let prices = vec![tick("EUR_USD", dec!(1.10)), tick("USD_JPY", dec!(150))];
+
+        let path = resolve_path("EUR", "JPY", &instruments).unwrap();
+        let jpy = convert_with_prices(dec!(100), &path, &prices).unwrap();
+        assert_eq!(jpy, dec!(16500));
+
+        let path = resolve_path("JPY", "EUR", &instruments).unwrap();
+        let eur = convert_with_prices(dec!(16500), &path, &prices).unwrap();
+        assert_eq!(eur, dec!(100));
+
+        let missing = convert_with_prices(dec!(1), &path, &prices[..1]);
+        assert!(matches!(missing, Err(Error::InvalidInstrument(_))));
+    }
+
+    #[test]
+    fn test_zero_price_is_an_error() {
+        let instruments = vec![instrument("EUR_USD")];
+        let prices = vec![tick("EUR_USD", Decimal::ZERO)];
+
+        let path = resolve_path("USD", "EUR", &instruments).unwrap();
+        match convert_with_prices(dec!(1), &path, &prices) {
+            Err(Error::ParseError { field, value }) => {
+                assert_eq!(field, "EUR_USD price");
+                assert_eq!(value, "0");
+            }
+            other => panic!("expected ParseError, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,436 @@
+//! Local store for historical candles
+//!
+//! [`DataStore::query_candles`] answers candle queries from local storage
+//! and fetches only the parts of the range it hasn't seen before. The
+//! default [`FileStore`] keeps candles as JSON lines under a directory, one
+//! file per instrument, granularity and month, plus a record of which time
+//! ranges have been fetched:
+//!
+//! ```text
+//! candles/
+//! └── EUR_USD/
+//!     └── M5/
+//!         ├── coverage.json
+//!         ├── 2025-10.jsonl
+//!         └── 2025-11.jsonl
+//! ```
+//!
+//! Ranges the API returned nothing for (weekends, holidays) count as
+//! fetched, so they aren't requested again. Incomplete candles are returned
+//! but never stored. Queries read and write files on Tokio's blocking
+//! thread pool, so a large backfill doesn't stall other tasks.
+//!
+//! ```no_run
+//! use chrono::{TimeZone, Utc};
+//! use oanda_connector::data_store::{DataStore, FileStore};
+//! use oanda_connector::{Granularity, OandaClient, OandaConfig};
+//!
+//! # async fn run() -> oanda_connector::Result<()> {
+//! let client = OandaClient::new(OandaConfig::from_env()?)?;
+//! let store = FileStore::new(client, "candles");
+//!
+//! let from = Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap();
+//! let to = Utc.with_ymd_and_hms(2025, 7, 1, 0, 0, 0).unwrap();
+//! // The first query downloads; later ones over the same range are local
+//! let candles = store.query_candles("EUR_USD", Granularity::H1, from..to).await?;
+//! println!("{} candles", candles.len());
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::client::OandaClient;
+use crate::error::Result;
+use crate::models::{Candle, Granularity};
+use async_trait::async_trait;
+use chrono::{DateTime, Datelike, NaiveDate, SecondsFormat, Utc};
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+/// Most candles OANDA returns per request
+const MAX_CANDLES_PER_REQUEST: i64 = 5000;
+
+/// Time range of candle start times, end exclusive
+pub type TimeRange = Range<DateTime<Utc>>;
+
+/// Source of historical candles
+#[async_trait]
+pub trait DataStore: Send + Sync {
+    /// Candles starting within `range`, oldest first
+    async fn query_candles(
+        &self,
+        instrument: &str,
+        granularity: Granularity,
+        range: TimeRange,
+    ) -> Result<Vec<Candle>>;
+}
+
+/// [`DataStore`] on the local filesystem, backfilled from the API
+pub struct FileStore {
+    client: OandaClient,
+    directory: PathBuf,
+    /// Serializes backfills so concurrent queries don't fetch twice
+    lock: tokio::sync::Mutex<()>,
+}
+
+impl FileStore {
+    pub fn new(client: OandaClient, directory: impl Into<PathBuf>) -> Self {
+        Self {
+            client,
+            directory: directory.into(),
+            lock: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    /// Ranges already fetched for an instrument and granularity, merged
+    pub fn coverage(&self, instrument: &str, granularity: Granularity) -> Result<Vec<TimeRange>> {
+        read_coverage(&self.series_dir(instrument, granularity))
+    }
+
+    /// Stored candles starting within `range`, without contacting the API
+    pub fn load_candles(
+        &self,
+        instrument: &str,
+        granularity: Granularity,
+        range: &TimeRange,
+    ) -> Result<Vec<Candle>> {
+        load_series(&self.series_dir(instrument, granularity), range)
+    }
+
+    /// Add candles to storage and mark `range` as fetched
+    pub fn store_candles(
+        &self,
+        instrument: &str,
+        granularity: Granularity,
+        range: &TimeRange,
+        candles: &[Candle],
+    ) -> Result<()> {
+        store_series(&self.series_dir(instrument, granularity), range, candles)
+    }
+
+    /// Fetch `range` from the API in request-sized pieces
+    async fn fetch(
+        &self,
+        instrument: &str,
+        granularity: Granularity,
+        range: &TimeRange,
+    ) -> Result<Vec<Candle>> {
+        let step = granularity.to_duration() * MAX_CANDLES_PER_REQUEST as i32;
+        let mut candles = Vec::new();
+        let mut from = range.start;
+        while from < range.end {
+            let to = (from + step).min(range.end);
+            let page = self
+                .client
+                .get_candles_range(
+                    instrument,
+                    granularity,
+                    &from.to_rfc3339_opts(SecondsFormat::Secs, true),
+                    &to.to_rfc3339_opts(SecondsFormat::Secs, true),
+                )
+                .await?;
+            // `to` is inclusive on OANDA's side
+            candles.extend(
+                page.into_iter()
+                    .filter(|c| c.timestamp >= from && c.timestamp < to),
+            );
+            from = to;
+        }
+        Ok(candles)
+    }
+
+    fn series_dir(&self, instrument: &str, granularity: Granularity) -> PathBuf {
+        self.directory
+            .join(instrument)
+            .join(granularity.to_string())
+    }
+}
+
+#[async_trait]
+impl DataStore for FileStore {
+    async fn query_candles(
+        &self,
+        instrument: &str,
+        granularity: Granularity,
+        range: TimeRange,
+    ) -> Result<Vec<Candle>> {
+        let _guard = self.lock.lock().await;
+        let dir = self.series_dir(instrument, granularity);
+        let now = Utc::now();
+        // The candle in progress can't be stored, so it is always fetched
+        let settled = granularity.align_down(now);
+        let mut recent = Vec::new();
+
+        let coverage = blocking({
+            let dir = dir.clone();
+            move || read_coverage(&dir)
+        })
+        .await?;
+        for gap in missing(&coverage, &range) {
+            if gap.start >= now {
+                break;
+            }
+            let candles = self
+                .fetch(instrument, granularity, &(gap.start..gap.end.min(now)))
+                .await?;
+            recent.extend(candles.iter().filter(|c| !c.complete).cloned());
+            let fetched = gap.start..gap.end.min(settled).max(gap.start);
+            let dir = dir.clone();
+            blocking(move || store_series(&dir, &fetched, &candles)).await?;
+        }
+
+        let mut candles = blocking(move || load_series(&dir, &range)).await?;
+        candles.extend(recent);
+        candles.sort_by_key(|candle| candle.timestamp);
+        candles.dedup_by_key(|candle| candle.timestamp);
+        Ok(candles)
+    }
+}
+
+/// Run file I/O on Tokio's blocking thread pool
+async fn blocking<T, F>(io: F) -> Result<T>
+where
+    F: FnOnce() -> Result<T> + Send + 'static,
+    T: Send + 'static,
+{
+    tokio::task::spawn_blocking(io)
+        .await
+        .map_err(|e| std::io::Error::other(e.to_string()))?
+}
+
+fn read_coverage(dir: &Path) -> Result<Vec<TimeRange>> {
+    match fs::read(dir.join("coverage.json")) {
+        Ok(body) => {
+            let pairs: Vec<(DateTime<Utc>, DateTime<Utc>)> = serde_json::from_slice(&body)?;
+            Ok(pairs.into_iter().map(|(start, end)| start..end).collect())
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn load_series(dir: &Path, range: &TimeRange) -> Result<Vec<Candle>> {
+    let mut candles = Vec::new();
+    for month in months(range) {
+        for candle in read_month(&dir.join(month_file(month)))?.into_values() {
+            if range.contains(&candle.timestamp) {
+                candles.push(candle);
+            }
+        }
+    }
+    Ok(candles)
+}
+
+fn store_series(dir: &Path, range: &TimeRange, candles: &[Candle]) -> Result<()> {
+    fs::create_dir_all(dir)?;
+
+    let mut by_month: BTreeMap<NaiveDate, Vec<&Candle>> = BTreeMap::new();
+    for candle in candles.iter().filter(|candle| candle.complete) {
+        by_month
+            .entry(month_of(candle.timestamp))
+            .or_default()
+            .push(candle);
+    }
+    for (month, new) in by_month {
+        let path = dir.join(month_file(month));
+        let mut stored = read_month(&path)?;
+        for candle in new {
+            stored.insert(candle.timestamp, candle.clone());
+        }
+        write_month(&path, stored.values())?;
+    }
+
+    let mut coverage = read_coverage(dir)?;
+    coverage.push(range.clone());
+    let pairs: Vec<_> = merge(coverage)
+        .into_iter()
+        .map(|range| (range.start, range.end))
+        .collect();
+    write_atomic(&dir.join("coverage.json"), &serde_json::to_vec(&pairs)?)
+}
+
+/// Sort ranges and join those that overlap or touch
+fn merge(mut ranges: Vec<TimeRange>) -> Vec<TimeRange> {
+    ranges.retain(|range| range.start < range.end);
+    ranges.sort_by_key(|range| range.start);
+    let mut merged: Vec<TimeRange> = Vec::new();
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+            _ => merged.push(range),
+        }
+    }
+    merged
+}
+
+/// Parts of `wanted` not in `covered` (which must be merged)
+fn missing(covered: &[TimeRange], wanted: &TimeRange) -> Vec<TimeRange> {
+    let mut gaps = Vec::new();
+    let mut start = wanted.start;
+    for range in covered {
+        if range.end <= start {
+            continue;
+        }
+        if range.start >= wanted.end {
+            break;
+        }
+        if range.start > start {
+            gaps.push(start..range.start);
+        }
+        start = start.max(range.end);
+    }
+    if start < wanted.end {
+        gaps.push(start..wanted.end);
+    }
+    gaps
+}
+
+fn month_of(time: DateTime<Utc>) -> NaiveDate {
+    time.date_naive().with_day(1).unwrap_or(time.date_naive())
+}
+
+/// First day of each month overlapping `range`
+fn months(range: &TimeRange) -> Vec<NaiveDate> {
+    let mut months = Vec::new();
+    if range.start >= range.end {
+        return months;
+    }
+    let mut month = month_of(range.start);
+    let last = month_of(range.end - chrono::Duration::nanoseconds(1));
+    while month <= last {
+        months.push(month);
+        month = month
+            .checked_add_months(chrono::Months::new(1))
+            .unwrap_or(NaiveDate::MAX);
+    }
+    months
+}
+
+fn month_file(month: NaiveDate) -> String {
+    format!("{}.jsonl", month.format("%Y-%m"))
+}
+
+fn read_month(path: &Path) -> Result<BTreeMap<DateTime<Utc>, Candle>> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(BTreeMap::new()),
+        Err(e) => return Err(e.into()),
+    };
+    let mut candles = BTreeMap::new();
+    for line in BufReader::new(file).lines() {
+        let line = line?;
+        if !line.trim().is_empty() {
+            let candle: Candle = serde_json::from_str(&line)?;
+            candles.insert(candle.timestamp, candle);
+        }
+    }
+    Ok(candles)
+}
+
+fn write_month<'a>(path: &Path, candles: impl Iterator<Item = &'a Candle>) -> Result<()> {
+    let mut body = Vec::new();
+    for candle in candles {
+        serde_json::to_writer(&mut body, candle)?;
+        body.push(b'\n');
+    }
+    write_atomic(path, &body)
+}
+
+/// Replace a file so readers never see it half written
+pub(crate) fn write_atomic(path: &Path, body: &[u8]) -> Result<()> {
+    let temp = path.with_extension("tmp");
+    let mut file = BufWriter::new(File::create(&temp)?);
+    file.write_all(body)?;
+    file.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+    fs::rename(&temp, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::OandaConfig;
+    use chrono::TimeZone;
+    use rust_decimal::Decimal;
+
+    fn at(day: u32, hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2025, 10, day, hour, 0, 0).unwrap()
+    }
+
+    fn candle(time: DateTime<Utc>, complete: bool) -> Candle {
+        Candle {
+            instrument: "EUR_USD".to_string(),
+            timestamp: time,
+            open: Decimal::ONE,
+            high: Decimal::ONE,
+            low: Decimal::ONE,
+            close: Decimal::ONE,
+            volume: 1,
+            complete,
+        }
+    }
+
+    #[test]
+    fn test_missing_ranges() {
+        let covered = merge(vec![
+            at(3, 0)..at(4, 0),
+            at(1, 0)..at(2, 0),
+            at(2, 0)..at(2, 12),
+        ]);
+        assert_eq!(covered, vec![at(1, 0)..at(2, 12), at(3, 0)..at(4, 0)]);
+
+        assert_eq!(
+            missing(&covered, &(at(1, 6)..at(5, 0))),
+            vec![at(2, 12)..at(3, 0), at(4, 0)..at(5, 0)]
+        );
+        assert!(missing(&covered, &(at(3, 1)..at(3, 2))).is_empty());
+        assert_eq!(
+            missing(&[], &(at(1, 0)..at(2, 0))),
+            vec![at(1, 0)..at(2, 0)]
+        );
+    }
+
+    #[test]
+    fn test_store_and_load_across_months() {
+        let dir = std::env::temp_dir().join(format!("oanda-store-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let config = OandaConfig::new("key".to_string(), "101-001-1-001".to_string(), true);
+        let store = FileStore::new(OandaClient::new(config).unwrap(), &dir);
+
+        let end = Utc.with_ymd_and_hms(2025, 11, 1, 2, 0, 0).unwrap();
+        let candles = vec![
+            candle(at(31, 23), true),
+            candle(end - chrono::Duration::hours(2), true),
+            candle(end - chrono::Duration::hours(1), false),
+        ];
+        store
+            .store_candles("EUR_USD", Granularity::H1, &(at(31, 23)..end), &candles)
+            .unwrap();
+        // Storing again replaces rather than duplicates
+        store
+            .store_candles(
+                "EUR_USD",
+                Granularity::H1,
+                &(at(31, 0)..at(31, 23)),
+                &candles[..1],
+            )
+            .unwrap();
+
+        let loaded = store
+            .load_candles("EUR_USD", Granularity::H1, &(at(1, 0)..end))
+            .unwrap();
+        assert_eq!(loaded, candles[..2].to_vec());
+        assert_eq!(
+            store.coverage("EUR_USD", Granularity::H1).unwrap(),
+            vec![at(31, 0)..end]
+        );
+        assert!(store
+            .coverage("EUR_USD", Granularity::M5)
+            .unwrap()
+            .is_empty());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
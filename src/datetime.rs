@@ -0,0 +1,145 @@
+//! Datetime formats accepted and returned by the API
+//!
+//! OANDA sends times either as RFC3339 strings or, when requested with
+//! `Accept-Datetime-Format: UNIX`, as seconds since the epoch with nanosecond
+//! decimals (`"1704110400.000000000"`). UNIX times are much cheaper to parse,
+//! which adds up when ingesting large candle or tick histories.
+//!
+//! The serde functions here accept both formats so response types don't
+//! depend on how the client was configured.
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::de::{self, Deserializer, Visitor};
+use serde::{Deserialize, Serialize, Serializer};
+use std::fmt;
+
+/// Format requested via the `Accept-Datetime-Format` header
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum DatetimeFormat {
+    #[default]
+    Rfc3339,
+    Unix,
+}
+
+impl DatetimeFormat {
+    /// Value of the `Accept-Datetime-Format` header
+    pub fn header_value(&self) -> &'static str {
+        match self {
+            DatetimeFormat::Rfc3339 => "RFC3339",
+            DatetimeFormat::Unix => "UNIX",
+        }
+    }
+
+    /// Format a time for use as a query parameter
+    ///
+    /// `precision` applies to RFC3339 only; UNIX times always carry
+    /// nanoseconds.
+    pub fn format(&self, time: &DateTime<Utc>, precision: SecondsFormat) -> String {
+        match self {
+            DatetimeFormat::Rfc3339 => time.to_rfc3339_opts(precision, true),
+            DatetimeFormat::Unix => {
+                format!("{}.{:09}", time.timestamp(), time.timestamp_subsec_nanos())
+            }
+        }
+    }
+}
+
+/// Parse a time in either RFC3339 or UNIX format
+pub fn parse(value: &str) -> Option<DateTime<Utc>> {
+    if value.contains('T') {
+        return DateTime::parse_from_rfc3339(value)
+            .ok()
+            .map(|t| t.with_timezone(&Utc));
+    }
+
+    let (seconds, fraction) = value.split_once('.').unwrap_or((value, ""));
+    if fraction.len() > 9 || !fraction.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let nanos = if fraction.is_empty() {
+        0
+    } else {
+        fraction.parse::<u32>().ok()? * 10u32.pow(9 - fraction.len() as u32)
+    };
+    DateTime::from_timestamp(seconds.parse().ok()?, nanos)
+}
+
+/// Serialize a time as RFC3339
+pub fn serialize<S: Serializer>(time: &DateTime<Utc>, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(&time.to_rfc3339_opts(SecondsFormat::AutoSi, true))
+}
+
+/// Deserialize a time in either RFC3339 or UNIX format
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<DateTime<Utc>, D::Error> {
+    deserializer.deserialize_str(TimeVisitor)
+}
+
+struct TimeVisitor;
+
+impl Visitor<'_> for TimeVisitor {
+    type Value = DateTime<Utc>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("an RFC3339 or UNIX timestamp")
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        parse(v).ok_or_else(|| E::invalid_value(de::Unexpected::Str(v), &self))
+    }
+}
+
+/// Same as the parent module, for optional fields
+///
+/// Combine with `#[serde(default)]` so a missing field reads as `None`.
+pub mod option {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        time: &Option<DateTime<Utc>>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match time {
+            Some(t) => super::serialize(t, serializer),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<DateTime<Utc>>, D::Error> {
+        #[derive(Deserialize)]
+        struct Wrapper(#[serde(with = "super")] DateTime<Utc>);
+
+        Ok(Option::<Wrapper>::deserialize(deserializer)?.map(|w| w.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_both_formats() {
+        let rfc3339 = parse("2024-01-01T12:00:00.500000000Z").unwrap();
+        let unix = parse("1704110400.500000000").unwrap();
+        assert_eq!(rfc3339, unix);
+
+        assert_eq!(parse("1704110400.5"), Some(unix));
+        assert_eq!(parse("1704110400").unwrap().timestamp(), 1704110400);
+        assert!(parse("1704110400.x").is_none());
+        assert!(parse("yesterday").is_none());
+    }
+
+    #[test]
+    fn test_format() {
+        let time = parse("2024-01-01T12:00:00.25Z").unwrap();
+        assert_eq!(
+            DatetimeFormat::Rfc3339.format(&time, SecondsFormat::Millis),
+            "2024-01-01T12:00:00.250Z"
+        );
+        let unix = DatetimeFormat::Unix.format(&time, SecondsFormat::Secs);
+        assert_eq!(unix, "1704110400.250000000");
+        assert_eq!(parse(&unix), Some(time));
+    }
+}
@@ -0,0 +1,228 @@
+//! Serde support for OANDA's string-encoded numbers
+//!
+//! OANDA sends prices, amounts and rates as JSON strings (`"1.10020"`) so
+//! that no precision is lost in transit. Use this module with
+//! `#[serde(with = "crate::decimal_number")]` on any field whose type
+//! implements `FromStr` and `Display`, typically `Decimal` or `f64`. Plain
+//! JSON numbers are accepted too, and values serialize back to strings.
+
+use serde::de::{self, Deserializer, Visitor};
+use serde::Serializer;
+use std::fmt;
+use std::marker::PhantomData;
+use std::str::FromStr;
+
+/// Serialize a number as a JSON string
+pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: fmt::Display,
+    S: Serializer,
+{
+    serializer.collect_str(value)
+}
+
+/// Deserialize a number from a JSON string or number
+pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+where
+    T: FromStr,
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_any(NumberVisitor(PhantomData))
+}
+
+struct NumberVisitor<T>(PhantomData<T>);
+
+impl<T: FromStr> NumberVisitor<T> {
+    fn parse<E: de::Error>(text: &str) -> Result<T, E> {
+        text.parse()
+            .map_err(|_| E::invalid_value(de::Unexpected::Str(text), &"a decimal number"))
+    }
+}
+
+impl<T: FromStr> Visitor<'_> for NumberVisitor<T> {
+    type Value = T;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a decimal number or numeric string")
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<T, E> {
+        Self::parse(v)
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<T, E> {
+        Self::parse(&v.to_string())
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<T, E> {
+        Self::parse(&v.to_string())
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<T, E> {
+        Self::parse(&v.to_string())
+    }
+}
+
+/// Same as the parent module, for optional fields
+///
+/// Combine with `#[serde(default)]` so a missing field reads as `None`.
+pub mod option {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::fmt;
+    use std::str::FromStr;
+
+    pub fn serialize<T, S>(value: &Option<T>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: fmt::Display,
+        S: Serializer,
+    {
+        match value {
+            Some(v) => super::serialize(v, serializer),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<Option<T>, D::Error>
+    where
+        T: FromStr,
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(bound = "T: FromStr")]
+        struct Wrapper<T>(#[serde(deserialize_with = "super::deserialize")] T);
+
+        Ok(Option::<Wrapper<T>>::deserialize(deserializer)?.map(|w| w.0))
+    }
+}
+
+/// Decode a JSON response body
+///
+/// A string that fails to parse as a number is reported as
+/// `Error::ParseError` naming its path in the body (e.g. `account.balance`).
+/// Any other failure, such as a missing field, is an
+/// `Error::SchemaMismatch`.
+///
+/// In strict mode the first field the target type doesn't declare fails the
+/// whole response with `Error::SchemaMismatch`. Fields inside flattened or
+/// untagged types (transactions) can't be tracked and are not reported.
+pub(crate) fn from_json<T: serde::de::DeserializeOwned>(
+    body: &[u8],
+    strict: bool,
+) -> crate::Result<T> {
+    let mut unknown = None;
+    let deserializer = &mut serde_json::Deserializer::from_slice(body);
+    let result = if strict {
+        let mut record = |path: serde_ignored::Path| {
+            unknown.get_or_insert_with(|| path.to_string());
+        };
+        serde_path_to_error::deserialize(serde_ignored::Deserializer::new(
+            deserializer,
+            &mut record,
+        ))
+    } else {
+        serde_path_to_error::deserialize(deserializer)
+    };
+
+    if let Some(path) = unknown {
+        return Err(crate::Error::SchemaMismatch(format!(
+            "unexpected field {}",
+            path
+        )));
+    }
+
+    result.map_err(|e| {
+        let path = e.path().to_string();
+        let value = serde_json::from_slice::<serde_json::Value>(body)
+            .ok()
+            .and_then(|root| value_at(&root, e.path()).cloned());
+
+        match value {
+            Some(serde_json::Value::String(value)) if e.inner().is_data() => {
+                crate::Error::ParseError { field: path, value }
+            }
+            _ => crate::Error::SchemaMismatch(format!("{} at {}", e.inner(), path)),
+        }
+    })
+}
+
+fn value_at<'a>(
+    root: &'a serde_json::Value,
+    path: &serde_path_to_error::Path,
+) -> Option<&'a serde_json::Value> {
+    use serde_path_to_error::Segment;
+
+    path.iter().try_fold(root, |value, segment| match segment {
+        Segment::Seq { index } => value.get(*index),
+        Segment::Map { key } => value.get(key),
+        Segment::Enum { .. } => Some(value),
+        Segment::Unknown => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal::Decimal;
+    use rust_decimal_macros::dec;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Amounts {
+        #[serde(with = "super")]
+        price: Decimal,
+        #[serde(with = "super")]
+        percent: f64,
+        #[serde(default, with = "super::option")]
+        limit: Option<Decimal>,
+    }
+
+    #[test]
+    fn test_string_and_number_encodings() {
+        let amounts: Amounts =
+            serde_json::from_str(r#"{"price": "1.10020", "percent": 0.25}"#).unwrap();
+        assert_eq!(amounts.price, dec!(1.10020));
+        assert_eq!(amounts.percent, 0.25);
+        assert_eq!(amounts.limit, None);
+
+        let json = serde_json::to_string(&Amounts {
+            limit: Some(dec!(2.5)),
+            ..amounts
+        })
+        .unwrap();
+        assert_eq!(
+            json,
+            r#"{"price":"1.10020","percent":"0.25","limit":"2.5"}"#
+        );
+    }
+
+    #[test]
+    fn test_parse_error_names_path() {
+        let result = super::from_json::<Vec<Amounts>>(
+            br#"[{"price": "1.1", "percent": "0.1"}, {"price": "1.1O", "percent": "0.1"}]"#,
+            false,
+        );
+
+        match result {
+            Err(crate::Error::ParseError { field, value }) => {
+                assert_eq!(field, "[1].price");
+                assert_eq!(value, "1.1O");
+            }
+            other => panic!("expected ParseError, got {:?}", other),
+        }
+
+        let missing = super::from_json::<Amounts>(br#"{"price": "1.1"}"#, false);
+        assert!(matches!(missing, Err(crate::Error::SchemaMismatch(_))));
+    }
+
+    #[test]
+    fn test_strict_rejects_unknown_fields() {
+        let body = br#"[{"price": "1.1", "percent": "0.1", "newField": true}]"#;
+        assert!(super::from_json::<Vec<Amounts>>(body, false).is_ok());
+
+        match super::from_json::<Vec<Amounts>>(body, true) {
+            Err(crate::Error::SchemaMismatch(message)) => {
+                assert_eq!(message, "unexpected field 0.newField");
+            }
+            other => panic!("expected SchemaMismatch, got {:?}", other),
+        }
+    }
+}
@@ -1,5 +1,52 @@
 //! OANDA API endpoint definitions
 
+/// Groups of endpoints that share timeout and rate limit settings
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EndpointGroup {
+    Pricing,
+    Candles,
+    Books,
+    Account, // account details and instruments
+    Orders,
+    Transactions,
+}
+
+impl EndpointGroup {
+    /// Every endpoint group
+    pub const ALL: [EndpointGroup; 6] = [
+        EndpointGroup::Pricing,
+        EndpointGroup::Candles,
+        EndpointGroup::Books,
+        EndpointGroup::Account,
+        EndpointGroup::Orders,
+        EndpointGroup::Transactions,
+    ];
+    
+    /// Group an API path belongs to, for requests the crate doesn't type
+    /// 
+    /// Order, trade and position paths share the `Orders` group; anything
+    /// unrecognised counts as `Account`.
+    pub fn for_path(path: &str) -> EndpointGroup {
+        let path = path.split('?').next().unwrap_or(path);
+        if path.contains("/pricing") {
+            EndpointGroup::Pricing
+        } else if path.ends_with("/candles") {
+            EndpointGroup::Candles
+        } else if path.ends_with("/orderBook") || path.ends_with("/positionBook") {
+            EndpointGroup::Books
+        } else if path.contains("/transactions") {
+            EndpointGroup::Transactions
+        } else if ["/orders", "/pendingOrders", "/trades", "/positions", "/openTrades", "/openPositions"]
+            .iter()
+            .any(|segment| path.contains(segment))
+        {
+            EndpointGroup::Orders
+        } else {
+            EndpointGroup::Account
+        }
+    }
+}
+
 /// API endpoint paths
 pub struct Endpoints;
 
@@ -10,12 +57,30 @@ impl Endpoints {
         format!("/v3/accounts/{}/pricing", account_id)
     }
     
+    /// Stream prices for instruments
+    /// GET /v3/accounts/{accountID}/pricing/stream (streaming host)
+    pub fn pricing_stream(account_id: &str) -> String {
+        format!("/v3/accounts/{}/pricing/stream", account_id)
+    }
+    
     /// Get candles for an instrument
     /// GET /v3/instruments/{instrument}/candles
     pub fn candles(instrument: &str) -> String {
         format!("/v3/instruments/{}/candles", instrument)
     }
     
+    /// Get order book for an instrument
+    /// GET /v3/instruments/{instrument}/orderBook
+    pub fn order_book(instrument: &str) -> String {
+        format!("/v3/instruments/{}/orderBook", instrument)
+    }
+    
+    /// Get position book for an instrument
+    /// GET /v3/instruments/{instrument}/positionBook
+    pub fn position_book(instrument: &str) -> String {
+        format!("/v3/instruments/{}/positionBook", instrument)
+    }
+    
     /// Get account summary
     /// GET /v3/accounts/{accountID}
     pub fn account(account_id: &str) -> String {
@@ -34,17 +99,83 @@ impl Endpoints {
         format!("/v3/accounts/{}/orders", account_id)
     }
     
+    /// Get or replace one order
+    /// GET/PUT /v3/accounts/{accountID}/orders/{orderSpecifier}
+    pub fn order(account_id: &str, specifier: &str) -> String {
+        format!("/v3/accounts/{}/orders/{}", account_id, specifier)
+    }
+    
+    /// Cancel a pending order
+    /// PUT /v3/accounts/{accountID}/orders/{orderSpecifier}/cancel
+    pub fn cancel_order(account_id: &str, specifier: &str) -> String {
+        format!("/v3/accounts/{}/orders/{}/cancel", account_id, specifier)
+    }
+    
+    /// Get pending orders
+    /// GET /v3/accounts/{accountID}/pendingOrders
+    pub fn pending_orders(account_id: &str) -> String {
+        format!("/v3/accounts/{}/pendingOrders", account_id)
+    }
+    
     /// Get open trades
     /// GET /v3/accounts/{accountID}/trades
     pub fn trades(account_id: &str) -> String {
         format!("/v3/accounts/{}/trades", account_id)
     }
     
+    /// Close an open trade
+    /// PUT /v3/accounts/{accountID}/trades/{tradeSpecifier}/close
+    pub fn close_trade(account_id: &str, trade_specifier: &str) -> String {
+        format!("/v3/accounts/{}/trades/{}/close", account_id, trade_specifier)
+    }
+    
     /// Get open positions
     /// GET /v3/accounts/{accountID}/positions
     pub fn positions(account_id: &str) -> String {
         format!("/v3/accounts/{}/positions", account_id)
     }
+    
+    /// Close one or both sides of a position
+    /// PUT /v3/accounts/{accountID}/positions/{instrument}/close
+    pub fn close_position(account_id: &str, instrument: &str) -> String {
+        format!("/v3/accounts/{}/positions/{}/close", account_id, instrument)
+    }
+    
+    /// Get open trades
+    /// GET /v3/accounts/{accountID}/openTrades
+    pub fn open_trades(account_id: &str) -> String {
+        format!("/v3/accounts/{}/openTrades", account_id)
+    }
+    
+    /// Get positions with units held
+    /// GET /v3/accounts/{accountID}/openPositions
+    pub fn open_positions(account_id: &str) -> String {
+        format!("/v3/accounts/{}/openPositions", account_id)
+    }
+    
+    /// Get transaction pages for a time range
+    /// GET /v3/accounts/{accountID}/transactions
+    pub fn transactions(account_id: &str) -> String {
+        format!("/v3/accounts/{}/transactions", account_id)
+    }
+    
+    /// Get account changes since a transaction
+    /// GET /v3/accounts/{accountID}/changes
+    pub fn account_changes(account_id: &str) -> String {
+        format!("/v3/accounts/{}/changes", account_id)
+    }
+    
+    /// Get transactions after a transaction ID
+    /// GET /v3/accounts/{accountID}/transactions/sinceid
+    pub fn transactions_since(account_id: &str) -> String {
+        format!("/v3/accounts/{}/transactions/sinceid", account_id)
+    }
+    
+    /// Get transactions within an ID range
+    /// GET /v3/accounts/{accountID}/transactions/idrange
+    pub fn transactions_id_range(account_id: &str) -> String {
+        format!("/v3/accounts/{}/transactions/idrange", account_id)
+    }
 }
 
 #[cfg(test)]
@@ -62,5 +193,25 @@ mod tests {
             Endpoints::candles("EUR_USD"),
             "/v3/instruments/EUR_USD/candles"
         );
+        
+        assert_eq!(
+            Endpoints::transactions_since("123-456"),
+            "/v3/accounts/123-456/transactions/sinceid"
+        );
+        
+        assert_eq!(
+            Endpoints::open_positions("123-456"),
+            "/v3/accounts/123-456/openPositions"
+        );
+    }
+
+    #[test]
+    fn test_group_for_path() {
+        assert_eq!(EndpointGroup::for_path("/v3/accounts/1/pricing?instruments=EUR_USD"), EndpointGroup::Pricing);
+        assert_eq!(EndpointGroup::for_path("/v3/instruments/EUR_USD/candles"), EndpointGroup::Candles);
+        assert_eq!(EndpointGroup::for_path("/v3/instruments/EUR_USD/positionBook"), EndpointGroup::Books);
+        assert_eq!(EndpointGroup::for_path("/v3/accounts/1/trades/42/close"), EndpointGroup::Orders);
+        assert_eq!(EndpointGroup::for_path("/v3/accounts/1/transactions/idrange"), EndpointGroup::Transactions);
+        assert_eq!(EndpointGroup::for_path("/v3/accounts/1/changes"), EndpointGroup::Account);
     }
 }
\ No newline at end of file
@@ -0,0 +1,407 @@
+//! Live equity curve
+//!
+//! [`EquityTracker`] records the account's balance and NAV over time,
+//! either every interval or after each fill reported by an
+//! [`OrderManager`], and keeps [`DrawdownStats`] up to date as points
+//! arrive. Give it a file and every point is appended to it as a line of
+//! JSON, so the curve is picked up again on restart.
+//!
+//! ```no_run
+//! use oanda_connector::equity::EquityTracker;
+//! use oanda_connector::{OandaClient, OandaConfig};
+//! use std::time::Duration;
+//!
+//! # async fn run() -> oanda_connector::Result<()> {
+//! let client = OandaClient::new(OandaConfig::from_env()?)?;
+//! let tracker = EquityTracker::open("equity.jsonl")?;
+//! let task = tracker.spawn(client, Duration::from_secs(60));
+//!
+//! tokio::time::sleep(Duration::from_secs(3600)).await;
+//! let stats = tracker.stats();
+//! println!(
+//!     "drawdown {} (worst {}), peak NAV {}",
+//!     stats.current_drawdown, stats.max_drawdown, stats.peak_nav
+//! );
+//! task.shutdown().await;
+//! # Ok(())
+//! # }
+//! ```
+
+pub use crate::backtest::EquityPoint;
+use crate::client::OandaClient;
+use crate::data_store::write_atomic;
+use crate::error::Result;
+use crate::models::AccountSummary;
+use crate::order_manager::{OrderEvent, OrderManager};
+use crate::shutdown::ShutdownHandle;
+use chrono::{DateTime, Duration as TimeDelta, Utc};
+use rust_decimal::Decimal;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
+
+/// Drawdown of NAV from its running peak
+///
+/// Drawdowns are fractions of the peak, so `0.05` is 5% below it.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct DrawdownStats {
+    /// Highest NAV seen
+    pub peak_nav: Decimal,
+    pub peak_at: Option<DateTime<Utc>>,
+    pub current_drawdown: Decimal,
+    pub max_drawdown: Decimal,
+    /// Largest fall from a peak, in the account currency
+    pub max_drawdown_amount: Decimal,
+    /// When the largest drawdown bottomed out
+    pub max_drawdown_at: Option<DateTime<Utc>>,
+    /// Longest time spent below a peak, including the current drawdown
+    pub longest_drawdown: TimeDelta,
+}
+
+impl DrawdownStats {
+    /// Statistics of a whole curve, oldest point first
+    pub fn from_curve(curve: &[EquityPoint]) -> Self {
+        let mut stats = Self::default();
+        for point in curve {
+            stats.update(point);
+        }
+        stats
+    }
+
+    /// Whether NAV is below its peak
+    pub fn in_drawdown(&self) -> bool {
+        self.current_drawdown > Decimal::ZERO
+    }
+
+    fn update(&mut self, point: &EquityPoint) {
+        if self.peak_at.is_none() || point.nav >= self.peak_nav {
+            self.peak_nav = point.nav;
+            self.peak_at = Some(point.timestamp);
+            self.current_drawdown = Decimal::ZERO;
+            return;
+        }
+        if !self.peak_nav.is_zero() {
+            self.current_drawdown = (self.peak_nav - point.nav) / self.peak_nav;
+        }
+        if self.current_drawdown > self.max_drawdown {
+            self.max_drawdown = self.current_drawdown;
+            self.max_drawdown_at = Some(point.timestamp);
+        }
+        self.max_drawdown_amount = self.max_drawdown_amount.max(self.peak_nav - point.nav);
+        if let Some(peak_at) = self.peak_at {
+            self.longest_drawdown = self.longest_drawdown.max(point.timestamp - peak_at);
+        }
+    }
+}
+
+/// Account value over time with its drawdown statistics
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EquityCurve {
+    points: Vec<EquityPoint>,
+    stats: DrawdownStats,
+}
+
+impl EquityCurve {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Curve from points, oldest first
+    pub fn from_points(points: Vec<EquityPoint>) -> Self {
+        let stats = DrawdownStats::from_curve(&points);
+        Self { points, stats }
+    }
+
+    /// Read a curve written by [`save`](Self::save)
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let body = std::fs::read(path)?;
+        Ok(Self::from_points(serde_json::from_slice(&body)?))
+    }
+
+    /// Write the curve as JSON, replacing the file atomically
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        write_atomic(path.as_ref(), &serde_json::to_vec(&self.points)?)
+    }
+
+    /// Add a point; points older than the last one are ignored
+    pub fn push(&mut self, point: EquityPoint) -> bool {
+        if self
+            .points
+            .last()
+            .is_some_and(|last| point.timestamp < last.timestamp)
+        {
+            return false;
+        }
+        self.stats.update(&point);
+        self.points.push(point);
+        true
+    }
+
+    pub fn points(&self) -> &[EquityPoint] {
+        &self.points
+    }
+
+    pub fn last(&self) -> Option<&EquityPoint> {
+        self.points.last()
+    }
+
+    pub fn stats(&self) -> DrawdownStats {
+        self.stats
+    }
+
+    /// NAV change since the first point, as a fraction of it
+    pub fn total_return(&self) -> Decimal {
+        match (self.points.first(), self.points.last()) {
+            (Some(first), Some(last)) if !first.nav.is_zero() => {
+                last.nav / first.nav - Decimal::ONE
+            }
+            _ => Decimal::ZERO,
+        }
+    }
+}
+
+struct TrackerState {
+    curve: EquityCurve,
+    path: Option<PathBuf>,
+    last_error: Option<String>,
+}
+
+/// Builds an [`EquityCurve`] from live account summaries
+///
+/// Cloning is cheap and clones share the curve, so the tracker can be
+/// sampled from a background task and read from anywhere else.
+#[derive(Clone)]
+pub struct EquityTracker {
+    state: Arc<Mutex<TrackerState>>,
+}
+
+impl Default for EquityTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EquityTracker {
+    /// Track an empty curve kept in memory only
+    pub fn new() -> Self {
+        Self::with_curve(EquityCurve::new())
+    }
+
+    /// Carry on from an existing curve
+    pub fn with_curve(curve: EquityCurve) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(TrackerState {
+                curve,
+                path: None,
+                last_error: None,
+            })),
+        }
+    }
+
+    /// Track the curve saved at `path`, starting a new one if the file
+    /// does not exist, and append every new point to it
+    ///
+    /// The file holds one JSON point per line. A last line cut short by a
+    /// crash is dropped.
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let curve = if path.exists() {
+            read_lines(&path)?
+        } else {
+            EquityCurve::new()
+        };
+        let tracker = Self::with_curve(curve);
+        tracker.state.lock().unwrap().path = Some(path);
+        Ok(tracker)
+    }
+
+    /// Add a point taken from an account summary now
+    pub fn record(&self, summary: &AccountSummary) -> Result<EquityPoint> {
+        let point = EquityPoint {
+            timestamp: Utc::now(),
+            balance: summary.balance,
+            nav: summary.nav,
+        };
+        self.record_point(point)?;
+        Ok(point)
+    }
+
+    /// Add a point, appending it to the tracker's file if it has one
+    pub fn record_point(&self, point: EquityPoint) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        if !state.curve.push(point) {
+            return Ok(());
+        }
+        match &state.path {
+            Some(path) => append_line(path, &point),
+            None => Ok(()),
+        }
+    }
+
+    /// Fetch the account summary and record it
+    pub async fn sample(&self, client: &OandaClient) -> Result<EquityPoint> {
+        let summary = client.get_account_summary().await?;
+        self.record(&summary)
+    }
+
+    /// Copy of the curve so far
+    pub fn curve(&self) -> EquityCurve {
+        self.state.lock().unwrap().curve.clone()
+    }
+
+    pub fn stats(&self) -> DrawdownStats {
+        self.state.lock().unwrap().curve.stats()
+    }
+
+    /// Error from the latest background sample, cleared once one succeeds
+    pub fn last_error(&self) -> Option<String> {
+        self.state.lock().unwrap().last_error.clone()
+    }
+
+    /// Sample every `interval` in the background, starting now
+    ///
+    /// Must be called inside a Tokio runtime.
+    pub fn spawn(&self, client: OandaClient, interval: Duration) -> ShutdownHandle {
+        let tracker = self.clone();
+        ShutdownHandle::spawn(|mut signal| async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                tokio::select! {
+                    _ = ticker.tick() => {}
+                    _ = signal.requested() => break,
+                }
+                tracker.background_sample(&client).await;
+            }
+        })
+    }
+
+    /// Sample after every fill of an order sent through `orders`
+    ///
+    /// Must be called inside a Tokio runtime.
+    pub fn spawn_on_fills(&self, client: OandaClient, orders: &OrderManager) -> ShutdownHandle {
+        let tracker = self.clone();
+        let mut events = orders.subscribe();
+        ShutdownHandle::spawn(|mut signal| async move {
+            loop {
+                tokio::select! {
+                    event = events.recv() => match event {
+                        Ok(OrderEvent::Filled { .. }) | Err(RecvError::Lagged(_)) => {
+                            tracker.background_sample(&client).await;
+                        }
+                        Ok(_) => {}
+                        Err(RecvError::Closed) => break,
+                    },
+                    _ = signal.requested() => break,
+                }
+            }
+        })
+    }
+
+    async fn background_sample(&self, client: &OandaClient) {
+        let error = self.sample(client).await.err().map(|e| e.to_string());
+        self.state.lock().unwrap().last_error = error;
+    }
+}
+
+/// Read a curve written by `append_line`, dropping a torn last line
+fn read_lines(path: &Path) -> Result<EquityCurve> {
+    let body = std::fs::read_to_string(path)?;
+    let lines: Vec<&str> = body.lines().filter(|line| !line.trim().is_empty()).collect();
+    let mut points = Vec::with_capacity(lines.len());
+    for (i, line) in lines.iter().enumerate() {
+        match serde_json::from_str(line) {
+            Ok(point) => points.push(point),
+            Err(_) if i + 1 == lines.len() && !body.ends_with('\n') => {
+                // Rewrite without it, so the next point starts a clean line
+                let mut clean = Vec::new();
+                for point in &points {
+                    serde_json::to_writer(&mut clean, point)?;
+                    clean.push(b'\n');
+                }
+                write_atomic(path, &clean)?;
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(EquityCurve::from_points(points))
+}
+
+/// Append one point as a line of JSON
+fn append_line(path: &Path, point: &EquityPoint) -> Result<()> {
+    let mut line = serde_json::to_vec(point)?;
+    line.push(b'\n');
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    file.write_all(&line)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn point(minutes: i64, nav: Decimal) -> EquityPoint {
+        EquityPoint {
+            timestamp: DateTime::from_timestamp(1_700_000_000 + minutes * 60, 0).unwrap(),
+            balance: dec!(10000),
+            nav,
+        }
+    }
+
+    #[test]
+    fn test_drawdown_stats() {
+        let mut curve = EquityCurve::new();
+        curve.push(point(0, dec!(10000)));
+        curve.push(point(1, dec!(11000)));
+        curve.push(point(2, dec!(9900)));
+        curve.push(point(3, dec!(10450)));
+
+        let stats = curve.stats();
+        assert_eq!(stats.peak_nav, dec!(11000));
+        assert_eq!(stats.max_drawdown, dec!(0.1));
+        assert_eq!(stats.max_drawdown_amount, dec!(1100));
+        assert_eq!(stats.max_drawdown_at, Some(point(2, dec!(0)).timestamp));
+        assert_eq!(stats.current_drawdown, dec!(0.05));
+        assert_eq!(stats.longest_drawdown, TimeDelta::minutes(2));
+        assert!(stats.in_drawdown());
+
+        // A new high ends the drawdown but keeps the worst one
+        curve.push(point(4, dec!(11500)));
+        let stats = curve.stats();
+        assert!(!stats.in_drawdown());
+        assert_eq!(stats.max_drawdown, dec!(0.1));
+        assert_eq!(curve.total_return(), dec!(0.15));
+
+        // Out-of-order points are dropped
+        assert!(!curve.push(point(3, dec!(1))));
+        assert_eq!(curve.stats(), stats);
+        assert_eq!(DrawdownStats::from_curve(curve.points()), stats);
+    }
+
+    #[test]
+    fn test_tracker_persists_curve() {
+        let path = std::env::temp_dir().join(format!("oanda-equity-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let tracker = EquityTracker::open(&path).unwrap();
+        tracker.record_point(point(0, dec!(10000))).unwrap();
+        tracker.record_point(point(1, dec!(9000))).unwrap();
+
+        let reopened = EquityTracker::open(&path).unwrap();
+        assert_eq!(reopened.curve(), tracker.curve());
+        assert_eq!(reopened.stats().max_drawdown, dec!(0.1));
+
+        // A point cut short by a crash is dropped and the file repaired
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(br#"{"timestamp":"#).unwrap();
+        let reopened = EquityTracker::open(&path).unwrap();
+        assert_eq!(reopened.curve(), tracker.curve());
+        reopened.record_point(point(2, dec!(9500))).unwrap();
+        assert_eq!(EquityTracker::open(&path).unwrap().curve().points().len(), 3);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
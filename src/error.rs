@@ -1,5 +1,6 @@
 //! Error types for OANDA connector
 
+use crate::config::RetryPolicy;
 use thiserror::Error;
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -9,10 +10,14 @@ pub enum Error {
     #[error("HTTP request failed: {0}")]
     HttpError(#[from] reqwest::Error),
     
-    #[error("OANDA API error {code}: {message}")]
+    #[error("OANDA API error {code}: {message}{}", request_id_suffix(.request_id))]
     ApiError {
         code: u16,
         message: String,
+        /// `errorCode` from the response body, when OANDA sent one
+        oanda_error_code: Option<String>,
+        /// `RequestID` response header, needed by OANDA support
+        request_id: Option<String>,
     },
     
     #[error("Rate limit exceeded, retry after {retry_after_seconds}s")]
@@ -20,6 +25,22 @@ pub enum Error {
         retry_after_seconds: u64,
     },
     
+    #[error("Circuit breaker open, retry in {retry_after_seconds}s")]
+    CircuitOpen {
+        retry_after_seconds: u64,
+    },
+    
+    #[error("Order rejected ({reason}): {message}")]
+    OrderRejected {
+        reason: crate::orders::OrderRejectReason,
+        message: String,
+        /// ID of the `*_ORDER_REJECT` transaction
+        transaction_id: Option<crate::ids::TransactionId>,
+    },
+    
+    #[error("Order blocked by risk limit: {0}")]
+    RiskLimitExceeded(crate::risk::RiskViolation),
+    
     #[error("Invalid instrument: {0}")]
     InvalidInstrument(String),
     
@@ -35,9 +56,29 @@ pub enum Error {
     #[error("Deserialization error: {0}")]
     DeserializationError(#[from] serde_json::Error),
     
+    /// MessagePack or CBOR data could not be written or read
+    #[error("Encoding error: {0}")]
+    EncodingError(String),
+    
+    /// A message broker (Kafka, Redis) did not accept a message
+    #[error("Publish error: {0}")]
+    PublishError(String),
+    
+    #[error("Failed to parse {field}: {value:?}")]
+    ParseError {
+        field: String,
+        value: String,
+    },
+    
+    #[error("Response does not match expected schema: {0}")]
+    SchemaMismatch(String),
+    
     #[error("Configuration error: {0}")]
     ConfigError(String),
     
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    
     #[error("Invalid date range: start={start}, end={end}")]
     InvalidDateRange {
         start: String,
@@ -46,24 +87,307 @@ pub enum Error {
     
     #[error("Insufficient account balance: required={required}, available={available}")]
     InsufficientBalance {
-        required: f64,
-        available: f64,
+        required: rust_decimal::Decimal,
+        available: rust_decimal::Decimal,
+    },
+    
+    #[error("{source} ({context})")]
+    WithContext {
+        context: Box<RequestContext>,
+        source: Box<Error>,
     },
 }
 
+/// Request that an error came from
+/// 
+/// Never includes credentials.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestContext {
+    pub method: String,
+    /// Endpoint path without query string
+    pub path: String,
+    pub account_id: Option<String>,
+    /// Instrument or comma-separated instruments
+    pub instrument: Option<String>,
+}
+
+impl std::fmt::Display for RequestContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.method, self.path)?;
+        if let Some(account_id) = &self.account_id {
+            write!(f, ", account {}", account_id)?;
+        }
+        if let Some(instrument) = &self.instrument {
+            write!(f, ", instrument {}", instrument)?;
+        }
+        Ok(())
+    }
+}
+
+/// Broad error categories for deciding how to react
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorKind {
+    /// Network trouble or timeout; retry with backoff
+    Transient,
+    /// Rate limit hit; retry after the advertised delay
+    RateLimited,
+    /// Credentials rejected; retrying won't help
+    Auth,
+    /// Invalid request or configuration; fix the calling code
+    ClientBug,
+    /// OANDA returned a 5xx; retry with backoff
+    ServerError,
+    /// Response could not be understood
+    Data,
+}
+
 impl Error {
-    /// Check if error is retryable
-    pub fn is_retryable(&self) -> bool {
-        matches!(
-            self,
+    /// Wrap with the request that failed, unless already wrapped
+    pub(crate) fn with_context(self, context: &RequestContext) -> Self {
+        match self {
+            Error::WithContext { .. } => self,
+            source => Error::WithContext {
+                context: Box::new(context.clone()),
+                source: Box::new(source),
+            },
+        }
+    }
+    
+    /// Request that failed, if known
+    pub fn context(&self) -> Option<&RequestContext> {
+        match self {
+            Error::WithContext { context, .. } => Some(context),
+            _ => None,
+        }
+    }
+    
+    /// The underlying error, without request context
+    /// 
+    /// Match on this to handle specific variants:
+    /// 
+    /// ```
+    /// # use oanda_connector::Error;
+    /// # fn handle(err: &Error) {
+    /// match err.inner() {
+    ///     Error::AuthenticationFailed => eprintln!("check your API key"),
+    ///     other => eprintln!("{}", other),
+    /// }
+    /// # }
+    /// ```
+    pub fn inner(&self) -> &Error {
+        match self {
+            Error::WithContext { source, .. } => source.inner(),
+            other => other,
+        }
+    }
+    
+    /// Owned version of `inner`
+    pub fn into_inner(self) -> Error {
+        match self {
+            Error::WithContext { source, .. } => source.into_inner(),
+            other => other,
+        }
+    }
+    
+    /// Category of this error
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Error::WithContext { source, .. } => source.kind(),
+            Error::HttpError(e) if e.is_builder() => ErrorKind::ClientBug,
             Error::HttpError(_) | 
             Error::Timeout(_) | 
-            Error::RateLimitExceeded { .. }
-        )
+            Error::CircuitOpen { .. } | 
+            Error::PublishError(_) => ErrorKind::Transient,
+            Error::RateLimitExceeded { .. } => ErrorKind::RateLimited,
+            Error::AuthenticationFailed => ErrorKind::Auth,
+            Error::ApiError { code, .. } => match code {
+                429 => ErrorKind::RateLimited,
+                401 | 403 => ErrorKind::Auth,
+                500..=599 => ErrorKind::ServerError,
+                400..=499 => ErrorKind::ClientBug,
+                _ => ErrorKind::Data,
+            },
+            Error::DeserializationError(_) | 
+            Error::EncodingError(_) | 
+            Error::ParseError { .. } | 
+            Error::SchemaMismatch(_) => ErrorKind::Data,
+            Error::OrderRejected { .. } | 
+            Error::RiskLimitExceeded(_) | 
+            Error::InvalidInstrument(_) | 
+            Error::InvalidGranularity(_) | 
+            Error::ConfigError(_) | 
+            Error::Io(_) | 
+            Error::InvalidDateRange { .. } | 
+            Error::InsufficientBalance { .. } => ErrorKind::ClientBug,
+        }
+    }
+    
+    /// Build an `ApiError` from an error response body
+    /// 
+    /// OANDA answers failed requests with `{"errorCode": ..., "errorMessage": ...}`
+    /// (`errorCode` is often omitted). Bodies in any other shape are kept
+    /// verbatim as the message.
+    pub(crate) fn from_response_body(code: u16, body: &str) -> Self {
+        #[derive(serde::Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct ErrorBody {
+            error_message: String,
+            #[serde(default)]
+            error_code: Option<String>,
+        }
+        
+        match serde_json::from_str::<ErrorBody>(body) {
+            Ok(parsed) => Error::ApiError {
+                code,
+                message: parsed.error_message,
+                oanda_error_code: parsed.error_code,
+                request_id: None,
+            },
+            Err(_) => Error::ApiError {
+                code,
+                message: body.to_string(),
+                oanda_error_code: None,
+                request_id: None,
+            },
+        }
+    }
+    
+    /// Attach the `RequestID` of the response that caused an `ApiError`
+    pub(crate) fn with_request_id(mut self, id: Option<String>) -> Self {
+        if let Error::ApiError { request_id, .. } = &mut self {
+            *request_id = id;
+        }
+        self
+    }
+    
+    /// OANDA `RequestID` of the failed request, if known
+    pub fn request_id(&self) -> Option<&str> {
+        match self.inner() {
+            Error::ApiError { request_id, .. } => request_id.as_deref(),
+            _ => None,
+        }
+    }
+    
+    /// Check if error is retryable
+    /// 
+    /// Exactly what the default `RetryPolicy` retries: timeouts, connection
+    /// failures, rate limits and 500/502/503/504 responses.
+    /// 
+    /// `Error::CircuitOpen` counts as retryable as well, though the client
+    /// never retries it itself: the request was not sent, and the same call
+    /// goes through once the breaker's cool-down has passed.
+    pub fn is_retryable(&self) -> bool {
+        RetryPolicy::default().retries(self) || matches!(self.inner(), Error::CircuitOpen { .. })
     }
     
     /// Check if error is related to authentication
+    ///
+    /// True for `AuthenticationFailed`, which 401 and 403 responses produce.
     pub fn is_auth_error(&self) -> bool {
-        matches!(self, Error::AuthenticationFailed)
+        self.kind() == ErrorKind::Auth
+    }
+}
+
+fn request_id_suffix(request_id: &Option<String>) -> String {
+    request_id
+        .as_ref()
+        .map(|id| format!(" (RequestID {})", id))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_response_body() {
+        let error = Error::from_response_body(
+            400,
+            r#"{"errorCode": "INVALID_INSTRUMENT", "errorMessage": "Invalid value specified for 'instrument'"}"#,
+        );
+        match error {
+            Error::ApiError { code, message, oanda_error_code, .. } => {
+                assert_eq!(code, 400);
+                assert_eq!(message, "Invalid value specified for 'instrument'");
+                assert_eq!(oanda_error_code.as_deref(), Some("INVALID_INSTRUMENT"));
+            }
+            other => panic!("expected ApiError, got {:?}", other),
+        }
+        
+        match Error::from_response_body(502, "<html>Bad Gateway</html>") {
+            Error::ApiError { message, oanda_error_code, .. } => {
+                assert_eq!(message, "<html>Bad Gateway</html>");
+                assert_eq!(oanda_error_code, None);
+            }
+            other => panic!("expected ApiError, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_error_kind() {
+        assert_eq!(Error::Timeout(10).kind(), ErrorKind::Transient);
+        assert_eq!(Error::AuthenticationFailed.kind(), ErrorKind::Auth);
+        assert_eq!(Error::from_response_body(503, "").kind(), ErrorKind::ServerError);
+        assert_eq!(Error::from_response_body(400, "").kind(), ErrorKind::ClientBug);
+        assert_eq!(Error::SchemaMismatch("x".to_string()).kind(), ErrorKind::Data);
+        assert_eq!(
+            Error::RateLimitExceeded { retry_after_seconds: 1 }.kind(),
+            ErrorKind::RateLimited
+        );
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_is_retryable_matches_default_policy() {
+        for code in [500, 502, 503, 504] {
+            assert!(Error::from_response_body(code, "").is_retryable(), "{}", code);
+        }
+        assert!(!Error::from_response_body(501, "").is_retryable());
+        assert!(!Error::from_response_body(400, "").is_retryable());
+        assert!(Error::RateLimitExceeded { retry_after_seconds: 1 }.is_retryable());
+        assert!(Error::CircuitOpen { retry_after_seconds: 1 }.is_retryable());
+        assert!(!RetryPolicy::default().retries(&Error::CircuitOpen { retry_after_seconds: 1 }));
+        
+        let policy = RetryPolicy {
+            retry_statuses: vec![502],
+            retry_rate_limited: false,
+            ..RetryPolicy::default()
+        };
+        assert!(policy.retries(&Error::from_response_body(502, "")));
+        assert!(!policy.retries(&Error::from_response_body(503, "")));
+        assert!(!policy.retries(&Error::RateLimitExceeded { retry_after_seconds: 1 }));
+        assert!(policy.retries(&Error::Timeout(10)));
+    }
+
+    #[test]
+    fn test_context_wrapper() {
+        let context = RequestContext {
+            method: "GET".to_string(),
+            path: "/v3/instruments/EUR_USD/candles".to_string(),
+            account_id: Some("101-001-1-001".to_string()),
+            instrument: Some("EUR_USD".to_string()),
+        };
+        let error = Error::from_response_body(400, "Invalid value specified for 'granularity'")
+            .with_context(&context)
+            .with_context(&context);
+        
+        assert_eq!(
+            error.to_string(),
+            "OANDA API error 400: Invalid value specified for 'granularity' \
+             (GET /v3/instruments/EUR_USD/candles, account 101-001-1-001, instrument EUR_USD)"
+        );
+        assert_eq!(error.context(), Some(&context));
+        assert_eq!(error.kind(), ErrorKind::ClientBug);
+        assert!(matches!(error.into_inner(), Error::ApiError { code: 400, .. }));
+    }
+
+    #[test]
+    fn test_request_id_in_message() {
+        let error = Error::from_response_body(404, "Resource not found")
+            .with_request_id(Some("42".to_string()));
+        assert_eq!(error.request_id(), Some("42"));
+        assert_eq!(error.to_string(), "OANDA API error 404: Resource not found (RequestID 42)");
+        
+        let error = Error::from_response_body(404, "Resource not found");
+        assert_eq!(error.to_string(), "OANDA API error 404: Resource not found");
+    }
+}
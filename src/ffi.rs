@@ -0,0 +1,366 @@
+//! C ABI for embedding the connector (`ffi` feature)
+//!
+//! Lets C, C++ and C# code drive a [`blocking::OandaClient`] through an
+//! opaque handle. Calls block until the request completes and return
+//! results as JSON strings in the same shape the crate's models serialize
+//! to. The declarations live in `include/oanda_connector.h`; build the
+//! shared library with
+//!
+//! ```text
+//! cargo rustc --release --features ffi --lib --crate-type cdylib
+//! ```
+//!
+//! Conventions:
+//!
+//! - Strings passed in are NUL-terminated UTF-8 and only borrowed for the
+//!   call.
+//! - Strings returned are owned by the caller and released with
+//!   [`oanda_string_free`]. `NULL` means the call failed.
+//! - [`oanda_last_error`] describes the latest failure on the calling
+//!   thread, and is cleared by every call that succeeds.
+//! - A handle may be used from several threads, until it is freed.
+//!
+//! ```c
+//! OandaClient *client = oanda_client_from_env();
+//! if (!client) {
+//!     fprintf(stderr, "%s\n", oanda_last_error());
+//!     return 1;
+//! }
+//! char *tick = oanda_get_price(client, "EUR_USD");
+//! if (tick) {
+//!     puts(tick);
+//!     oanda_string_free(tick);
+//! }
+//! oanda_client_free(client);
+//! ```
+
+use crate::blocking;
+use crate::config::OandaConfig;
+use crate::error::{Error, Result};
+use crate::ids::OrderId;
+use crate::models::Granularity;
+use crate::orders::{OrderRequest, OrderSpecifier};
+use serde::Serialize;
+use std::cell::RefCell;
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+
+/// Opaque client handle given to C callers
+pub struct OandaClient {
+    inner: blocking::OandaClient,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(message: String) {
+    // Interior NULs would truncate the message in C anyway
+    let message = CString::new(message.replace('\0', " ")).unwrap_or_default();
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(message));
+}
+
+/// Run `call`, turning errors and panics into a `NULL` result plus a
+/// message for `oanda_last_error`
+fn guard<T>(call: impl FnOnce() -> Result<*mut T>) -> *mut T {
+    match panic::catch_unwind(AssertUnwindSafe(call)) {
+        Ok(Ok(value)) => {
+            LAST_ERROR.with(|last| *last.borrow_mut() = None);
+            value
+        }
+        Ok(Err(e)) => {
+            set_last_error(e.to_string());
+            ptr::null_mut()
+        }
+        Err(_) => {
+            set_last_error("panic inside oanda-connector".to_string());
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Borrow a C string argument
+///
+/// # Safety
+///
+/// `value` must be `NULL` or a valid NUL-terminated string that outlives
+/// the returned reference.
+unsafe fn str_arg<'a>(name: &str, value: *const c_char) -> Result<&'a str> {
+    if value.is_null() {
+        return Err(Error::ParseError {
+            field: name.to_string(),
+            value: "NULL".to_string(),
+        });
+    }
+    CStr::from_ptr(value)
+        .to_str()
+        .map_err(|_| Error::ParseError {
+            field: name.to_string(),
+            value: String::from_utf8_lossy(CStr::from_ptr(value).to_bytes()).into_owned(),
+        })
+}
+
+/// Borrow the client behind a handle
+///
+/// # Safety
+///
+/// `client` must be `NULL` or a handle from `oanda_client_new` or
+/// `oanda_client_from_env` that has not been freed.
+unsafe fn client_arg<'a>(client: *const OandaClient) -> Result<&'a blocking::OandaClient> {
+    client
+        .as_ref()
+        .map(|client| &client.inner)
+        .ok_or_else(|| Error::ParseError {
+            field: "client".to_string(),
+            value: "NULL".to_string(),
+        })
+}
+
+fn to_json<T: Serialize>(value: &T) -> Result<*mut c_char> {
+    let json = serde_json::to_string(value)?;
+    // serde_json escapes NUL, so this can't fail
+    Ok(CString::new(json).unwrap_or_default().into_raw())
+}
+
+fn new_handle(config: OandaConfig) -> Result<*mut OandaClient> {
+    let inner = blocking::OandaClient::new(config)?;
+    Ok(Box::into_raw(Box::new(OandaClient { inner })))
+}
+
+/// Create a client for an account
+///
+/// `practice` picks the practice (demo) environment over live trading.
+/// Returns `NULL` on failure.
+///
+/// # Safety
+///
+/// `api_key` and `account_id` must be valid NUL-terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn oanda_client_new(
+    api_key: *const c_char,
+    account_id: *const c_char,
+    practice: bool,
+) -> *mut OandaClient {
+    guard(|| {
+        let api_key = str_arg("api_key", api_key)?;
+        let account_id = str_arg("account_id", account_id)?;
+        new_handle(OandaConfig::new(
+            api_key.to_string(),
+            account_id.to_string(),
+            practice,
+        ))
+    })
+}
+
+/// Create a client configured from `OANDA_*` environment variables
+///
+/// Returns `NULL` on failure.
+#[no_mangle]
+pub extern "C" fn oanda_client_from_env() -> *mut OandaClient {
+    guard(|| new_handle(OandaConfig::from_env()?))
+}
+
+/// Release a client handle; `NULL` is ignored
+///
+/// # Safety
+///
+/// `client` must be `NULL` or a live handle, and is invalid afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn oanda_client_free(client: *mut OandaClient) {
+    if !client.is_null() {
+        drop(Box::from_raw(client));
+    }
+}
+
+/// Release a string returned by this library; `NULL` is ignored
+///
+/// # Safety
+///
+/// `value` must be `NULL` or a string returned by this library that has
+/// not been freed.
+#[no_mangle]
+pub unsafe extern "C" fn oanda_string_free(value: *mut c_char) {
+    if !value.is_null() {
+        drop(CString::from_raw(value));
+    }
+}
+
+/// Message for the latest failure on this thread, or `NULL`
+///
+/// The string belongs to the library and stays valid until the next call
+/// on the same thread.
+#[no_mangle]
+pub extern "C" fn oanda_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| {
+        last.borrow()
+            .as_ref()
+            .map_or(ptr::null(), |message| message.as_ptr())
+    })
+}
+
+/// Current price of an instrument, as a JSON tick
+///
+/// # Safety
+///
+/// `client` must be a live handle and `instrument` a valid string.
+#[no_mangle]
+pub unsafe extern "C" fn oanda_get_price(
+    client: *const OandaClient,
+    instrument: *const c_char,
+) -> *mut c_char {
+    guard(|| {
+        let client = client_arg(client)?;
+        to_json(&client.get_current_price(str_arg("instrument", instrument)?)?)
+    })
+}
+
+/// Latest `count` candles at a granularity such as `"M5"`, as a JSON array
+///
+/// # Safety
+///
+/// `client` must be a live handle; `instrument` and `granularity` valid
+/// strings.
+#[no_mangle]
+pub unsafe extern "C" fn oanda_get_candles(
+    client: *const OandaClient,
+    instrument: *const c_char,
+    granularity: *const c_char,
+    count: usize,
+) -> *mut c_char {
+    guard(|| {
+        let client = client_arg(client)?;
+        let instrument = str_arg("instrument", instrument)?;
+        let granularity: Granularity = str_arg("granularity", granularity)?.parse()?;
+        to_json(&client.get_candles(instrument, granularity, count)?)
+    })
+}
+
+/// Account summary as JSON
+///
+/// # Safety
+///
+/// `client` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn oanda_get_account_summary(client: *const OandaClient) -> *mut c_char {
+    guard(|| to_json(&client_arg(client)?.get_account_summary()?))
+}
+
+/// Open positions as a JSON array
+///
+/// # Safety
+///
+/// `client` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn oanda_get_open_positions(client: *const OandaClient) -> *mut c_char {
+    guard(|| to_json(&client_arg(client)?.get_open_positions()?))
+}
+
+/// Pending orders as a JSON array
+///
+/// # Safety
+///
+/// `client` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn oanda_get_pending_orders(client: *const OandaClient) -> *mut c_char {
+    guard(|| to_json(&client_arg(client)?.get_pending_orders()?))
+}
+
+/// Submit an order given as JSON, returning OANDA's response as JSON
+///
+/// The order uses the API's field names, e.g.
+/// `{"type":"MARKET","instrument":"EUR_USD","units":"1000"}`. Never
+/// retried.
+///
+/// # Safety
+///
+/// `client` must be a live handle and `order_json` a valid string.
+#[no_mangle]
+pub unsafe extern "C" fn oanda_place_order(
+    client: *const OandaClient,
+    order_json: *const c_char,
+) -> *mut c_char {
+    guard(|| {
+        let client = client_arg(client)?;
+        let order: OrderRequest = serde_json::from_str(str_arg("order_json", order_json)?)?;
+        to_json(&client.place_order(&order)?)
+    })
+}
+
+/// Cancel a pending order by OANDA ID, or by client ID prefixed with `@`
+///
+/// # Safety
+///
+/// `client` must be a live handle and `order` a valid string.
+#[no_mangle]
+pub unsafe extern "C" fn oanda_cancel_order(
+    client: *const OandaClient,
+    order: *const c_char,
+) -> *mut c_char {
+    guard(|| {
+        let client = client_arg(client)?;
+        let order = str_arg("order", order)?;
+        let specifier = match order.strip_prefix('@') {
+            Some(client_id) => OrderSpecifier::client_id(client_id),
+            None => OrderSpecifier::Id(order.parse::<OrderId>()?),
+        };
+        to_json(&client.cancel_order(specifier)?)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn last_error() -> Option<String> {
+        let message = oanda_last_error();
+        (!message.is_null()).then(|| {
+            unsafe { CStr::from_ptr(message) }
+                .to_string_lossy()
+                .into_owned()
+        })
+    }
+
+    #[test]
+    fn test_handle_lifecycle_and_errors() {
+        let key = CString::new("test_api_key").unwrap();
+        let account = CString::new("101-001-1234567-001").unwrap();
+        let client = unsafe { oanda_client_new(key.as_ptr(), account.as_ptr(), true) };
+        assert!(!client.is_null());
+        assert!(last_error().is_none());
+
+        // Argument errors are reported before any request is made
+        let instrument = CString::new("EUR_USD").unwrap();
+        let granularity = CString::new("M7").unwrap();
+        let candles =
+            unsafe { oanda_get_candles(client, instrument.as_ptr(), granularity.as_ptr(), 10) };
+        assert!(candles.is_null());
+        assert!(last_error().unwrap().contains("M7"));
+
+        let price = unsafe { oanda_get_price(client, ptr::null()) };
+        assert!(price.is_null());
+        assert!(last_error().unwrap().contains("instrument"));
+
+        let order = CString::new("{\"units\": \"1000\"}").unwrap();
+        assert!(unsafe { oanda_place_order(client, order.as_ptr()) }.is_null());
+        assert!(unsafe { oanda_get_account_summary(ptr::null()) }.is_null());
+        assert!(last_error().unwrap().contains("client"));
+
+        unsafe {
+            oanda_client_free(client);
+            oanda_client_free(ptr::null_mut());
+            oanda_string_free(ptr::null_mut());
+        }
+    }
+
+    #[test]
+    fn test_json_strings_round_trip() {
+        let json = to_json(&serde_json::json!({"instrument": "EUR_USD"})).unwrap();
+        let text = unsafe { CStr::from_ptr(json) }
+            .to_str()
+            .unwrap()
+            .to_string();
+        unsafe { oanda_string_free(json) };
+        assert_eq!(text, r#"{"instrument":"EUR_USD"}"#);
+    }
+}
@@ -0,0 +1,474 @@
+//! Local HTTP gateway in front of OANDA (`gateway` feature)
+//!
+//! Serves a small JSON API backed by one [`OandaClient`], so internal tools
+//! share a single OANDA token, its rate limits and a short-lived response
+//! cache instead of each holding credentials. Tools authenticate to the
+//! gateway with their own bearer tokens, each optionally limited to a
+//! number of requests per second.
+//!
+//! | Route | Answer |
+//! |-------|--------|
+//! | `GET /health` | `{"status":"ok"}`, without authentication |
+//! | `GET /prices?instruments=EUR_USD,GBP_USD` | Latest ticks |
+//! | `GET /candles/{instrument}?granularity=H1&count=100` | Candles, oldest first |
+//! | `GET /account` | Account summary |
+//! | `GET /positions` | Open positions |
+//! | `GET /trades` | Open trades |
+//! | `GET /orders` | Pending orders |
+//! | `GET /instruments` | Tradeable instruments |
+//! | `POST /orders` | Place the [`OrderRequest`] in the body, when enabled |
+//!
+//! Failures answer with `{"error": "..."}` and a status derived from the
+//! error's [`ErrorKind`]. The `oanda-gateway` binary runs a gateway
+//! configured from the environment:
+//!
+//! ```text
+//! OANDA_GATEWAY_TOKENS=tool-a-secret,tool-b-secret \
+//!     cargo run --release --features gateway --bin oanda-gateway
+//! ```
+
+use crate::client::OandaClient;
+use crate::error::{Error, ErrorKind, Result};
+use crate::models::Granularity;
+use crate::orders::OrderRequest;
+use axum::extract::{Path, Query, Request, State};
+use axum::http::{header, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use governor::{DefaultKeyedRateLimiter, Quota, RateLimiter};
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::num::NonZeroU32;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Entries kept before expired ones are swept
+const CACHE_SWEEP_THRESHOLD: usize = 1024;
+
+/// How the gateway listens, authenticates and caches
+#[derive(Debug, Clone, PartialEq)]
+pub struct GatewayConfig {
+    pub bind: SocketAddr,
+    /// Bearer tokens accepted from callers; empty disables authentication
+    pub tokens: Vec<String>,
+    /// Requests per second allowed per token
+    pub per_token_rps: Option<NonZeroU32>,
+    pub price_ttl: Duration,
+    pub candle_ttl: Duration,
+    /// Account, position, trade and order listings
+    pub account_ttl: Duration,
+    /// Whether `POST /orders` is served
+    pub allow_orders: bool,
+}
+
+impl Default for GatewayConfig {
+    fn default() -> Self {
+        Self {
+            bind: SocketAddr::from(([127, 0, 0, 1], 8080)),
+            tokens: Vec::new(),
+            per_token_rps: None,
+            price_ttl: Duration::from_millis(500),
+            candle_ttl: Duration::from_secs(5),
+            account_ttl: Duration::from_secs(1),
+            allow_orders: false,
+        }
+    }
+}
+
+impl GatewayConfig {
+    /// Read `OANDA_GATEWAY_*` environment variables over the defaults
+    ///
+    /// `BIND` (address), `TOKENS` (comma-separated), `TOKEN_RPS`,
+    /// `PRICE_TTL_MS`, `CANDLE_TTL_MS`, `ACCOUNT_TTL_MS` and
+    /// `ALLOW_ORDERS` (`true` or `false`).
+    pub fn from_env() -> Result<Self> {
+        let var = |name: &str| std::env::var(format!("OANDA_GATEWAY_{}", name)).ok();
+        fn parse<T: std::str::FromStr>(name: &str, value: String) -> Result<T> {
+            value.parse().map_err(|_| Error::ParseError {
+                field: format!("OANDA_GATEWAY_{}", name),
+                value,
+            })
+        }
+        let millis = |name: &str| -> Result<Option<Duration>> {
+            var(name)
+                .map(|v| parse(name, v).map(Duration::from_millis))
+                .transpose()
+        };
+
+        let defaults = Self::default();
+        Ok(Self {
+            bind: var("BIND")
+                .map(|v| parse("BIND", v))
+                .transpose()?
+                .unwrap_or(defaults.bind),
+            tokens: var("TOKENS")
+                .map(|v| {
+                    v.split(',')
+                        .map(str::trim)
+                        .filter(|t| !t.is_empty())
+                        .map(str::to_string)
+                        .collect()
+                })
+                .unwrap_or_default(),
+            per_token_rps: var("TOKEN_RPS")
+                .map(|v| parse("TOKEN_RPS", v))
+                .transpose()?,
+            price_ttl: millis("PRICE_TTL_MS")?.unwrap_or(defaults.price_ttl),
+            candle_ttl: millis("CANDLE_TTL_MS")?.unwrap_or(defaults.candle_ttl),
+            account_ttl: millis("ACCOUNT_TTL_MS")?.unwrap_or(defaults.account_ttl),
+            allow_orders: var("ALLOW_ORDERS")
+                .map(|v| parse("ALLOW_ORDERS", v))
+                .transpose()?
+                .unwrap_or(defaults.allow_orders),
+        })
+    }
+}
+
+/// Error answered to a gateway caller
+struct GatewayError {
+    status: StatusCode,
+    message: String,
+}
+
+impl GatewayError {
+    fn new(status: StatusCode, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            message: message.into(),
+        }
+    }
+}
+
+impl From<Error> for GatewayError {
+    fn from(error: Error) -> Self {
+        let status = match error.kind() {
+            ErrorKind::ClientBug => StatusCode::BAD_REQUEST,
+            ErrorKind::RateLimited => StatusCode::TOO_MANY_REQUESTS,
+            ErrorKind::Transient => StatusCode::SERVICE_UNAVAILABLE,
+            // Bad upstream credentials are the gateway's problem, not the caller's
+            ErrorKind::Auth | ErrorKind::ServerError | ErrorKind::Data => StatusCode::BAD_GATEWAY,
+        };
+        Self::new(status, error.to_string())
+    }
+}
+
+impl IntoResponse for GatewayError {
+    fn into_response(self) -> Response {
+        (
+            self.status,
+            Json(serde_json::json!({ "error": self.message })),
+        )
+            .into_response()
+    }
+}
+
+type Reply = std::result::Result<Json<Value>, GatewayError>;
+
+struct Shared {
+    client: OandaClient,
+    config: GatewayConfig,
+    cache: Mutex<HashMap<String, (Instant, Value)>>,
+    limiter: Option<DefaultKeyedRateLimiter<String>>,
+}
+
+impl Shared {
+    /// Answer from the cache while fresh, otherwise fetch and remember
+    async fn cached<T, F, Fut>(&self, key: String, ttl: Duration, fetch: F) -> Reply
+    where
+        T: serde::Serialize,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        if let Some((stored, value)) = self.cache.lock().unwrap().get(&key) {
+            if stored.elapsed() < ttl {
+                return Ok(Json(value.clone()));
+            }
+        }
+        let value = serde_json::to_value(fetch().await?).map_err(Error::from)?;
+        let mut cache = self.cache.lock().unwrap();
+        if cache.len() >= CACHE_SWEEP_THRESHOLD {
+            let config = &self.config;
+            let longest = config
+                .price_ttl
+                .max(config.candle_ttl)
+                .max(config.account_ttl);
+            cache.retain(|_, (stored, _)| stored.elapsed() < longest);
+        }
+        cache.insert(key, (Instant::now(), value.clone()));
+        Ok(Json(value))
+    }
+}
+
+/// HTTP gateway sharing one client between many callers
+#[derive(Clone)]
+pub struct Gateway {
+    shared: Arc<Shared>,
+}
+
+impl Gateway {
+    pub fn new(client: OandaClient, config: GatewayConfig) -> Self {
+        let limiter = config
+            .per_token_rps
+            .map(|rps| RateLimiter::keyed(Quota::per_second(rps)));
+        Self {
+            shared: Arc::new(Shared {
+                client,
+                config,
+                cache: Mutex::new(HashMap::new()),
+                limiter,
+            }),
+        }
+    }
+
+    /// Routes, for serving with your own listener or nesting in another app
+    pub fn router(&self) -> Router {
+        let mut api = Router::new()
+            .route("/prices", get(prices))
+            .route("/candles/{instrument}", get(candles))
+            .route("/account", get(account))
+            .route("/positions", get(positions))
+            .route("/trades", get(trades))
+            .route("/instruments", get(instruments));
+        api = if self.shared.config.allow_orders {
+            api.route("/orders", get(pending_orders).post(place_order))
+        } else {
+            api.route("/orders", get(pending_orders))
+        };
+        api.route_layer(middleware::from_fn_with_state(
+            self.shared.clone(),
+            authorize,
+        ))
+        .route(
+            "/health",
+            get(|| async { Json(serde_json::json!({ "status": "ok" })) }),
+        )
+        .with_state(self.shared.clone())
+    }
+
+    /// Serve on the configured address until `shutdown` completes
+    pub async fn serve(&self, shutdown: impl Future<Output = ()> + Send + 'static) -> Result<()> {
+        let listener = tokio::net::TcpListener::bind(self.shared.config.bind).await?;
+        log::info!("gateway listening on {}", listener.local_addr()?);
+        axum::serve(listener, self.router())
+            .with_graceful_shutdown(shutdown)
+            .await?;
+        Ok(())
+    }
+}
+
+async fn authorize(
+    State(shared): State<Arc<Shared>>,
+    request: Request,
+    next: Next,
+) -> std::result::Result<Response, GatewayError> {
+    let token = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .unwrap_or_default();
+    if !shared.config.tokens.is_empty() && !shared.config.tokens.iter().any(|t| t == token) {
+        return Err(GatewayError::new(
+            StatusCode::UNAUTHORIZED,
+            "missing or unknown bearer token",
+        ));
+    }
+    if let Some(limiter) = &shared.limiter {
+        if limiter.check_key(&token.to_string()).is_err() {
+            return Err(GatewayError::new(
+                StatusCode::TOO_MANY_REQUESTS,
+                "request rate for this token exceeded",
+            ));
+        }
+    }
+    Ok(next.run(request).await)
+}
+
+#[derive(Deserialize)]
+struct PricesQuery {
+    instruments: String,
+}
+
+async fn prices(State(shared): State<Arc<Shared>>, Query(query): Query<PricesQuery>) -> Reply {
+    let instruments: Vec<String> = query
+        .instruments
+        .split(',')
+        .map(str::trim)
+        .filter(|i| !i.is_empty())
+        .map(str::to_string)
+        .collect();
+    if instruments.is_empty() {
+        return Err(GatewayError::new(
+            StatusCode::BAD_REQUEST,
+            "instruments must not be empty",
+        ));
+    }
+    let key = format!("prices:{}", instruments.join(","));
+    let ttl = shared.config.price_ttl;
+    shared
+        .cached(key, ttl, || shared.client.get_current_prices(&instruments))
+        .await
+}
+
+#[derive(Deserialize)]
+struct CandlesQuery {
+    granularity: Option<String>,
+    count: Option<usize>,
+}
+
+async fn candles(
+    State(shared): State<Arc<Shared>>,
+    Path(instrument): Path<String>,
+    Query(query): Query<CandlesQuery>,
+) -> Reply {
+    let granularity: Granularity = query.granularity.as_deref().unwrap_or("H1").parse()?;
+    let count = query.count.unwrap_or(100);
+    let key = format!("candles:{}:{}:{}", instrument, granularity, count);
+    let ttl = shared.config.candle_ttl;
+    shared
+        .cached(key, ttl, || {
+            shared.client.get_candles(&instrument, granularity, count)
+        })
+        .await
+}
+
+async fn account(State(shared): State<Arc<Shared>>) -> Reply {
+    let ttl = shared.config.account_ttl;
+    shared
+        .cached("account".to_string(), ttl, || {
+            shared.client.get_account_summary()
+        })
+        .await
+}
+
+async fn positions(State(shared): State<Arc<Shared>>) -> Reply {
+    let ttl = shared.config.account_ttl;
+    shared
+        .cached("positions".to_string(), ttl, || {
+            shared.client.get_open_positions()
+        })
+        .await
+}
+
+async fn trades(State(shared): State<Arc<Shared>>) -> Reply {
+    let ttl = shared.config.account_ttl;
+    shared
+        .cached("trades".to_string(), ttl, || {
+            shared.client.get_open_trades()
+        })
+        .await
+}
+
+async fn pending_orders(State(shared): State<Arc<Shared>>) -> Reply {
+    let ttl = shared.config.account_ttl;
+    shared
+        .cached("orders".to_string(), ttl, || {
+            shared.client.get_pending_orders()
+        })
+        .await
+}
+
+async fn instruments(State(shared): State<Arc<Shared>>) -> Reply {
+    // The client keeps its own instrument cache
+    let instruments = shared.client.get_instruments().await?;
+    Ok(Json(
+        serde_json::to_value(instruments).map_err(Error::from)?,
+    ))
+}
+
+async fn place_order(State(shared): State<Arc<Shared>>, Json(order): Json<OrderRequest>) -> Reply {
+    let response = shared.client.place_order(&order).await?;
+    Ok(Json(serde_json::to_value(response).map_err(Error::from)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::OandaConfig;
+
+    async fn start(server: &mockito::Server, config: GatewayConfig) -> String {
+        let mut oanda = OandaConfig::new(
+            "test_api_key".to_string(),
+            "test_account_id".to_string(),
+            true,
+        );
+        oanda.base_url = Some(server.url());
+        oanda.enable_retries = false;
+        let gateway = Gateway::new(OandaClient::new(oanda).unwrap(), config);
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, gateway.router()).await });
+        format!("http://{}", address)
+    }
+
+    #[tokio::test]
+    async fn test_gateway_authenticates_and_caches() {
+        let mut server = mockito::Server::new_async().await;
+        let pricing = server
+            .mock("GET", "/v3/accounts/test_account_id/pricing")
+            .match_query(mockito::Matcher::Any)
+            .with_status(200)
+            .with_body(
+                r#"{"prices": [{
+                    "instrument": "EUR_USD", "time": "2024-01-01T12:00:00.000000000Z",
+                    "bids": [{"price": "1.10000", "liquidity": 1000000}],
+                    "asks": [{"price": "1.10020", "liquidity": 1000000}],
+                    "tradeable": true, "status": "tradeable"
+                }]}"#,
+            )
+            .expect(1)
+            .create_async()
+            .await;
+
+        let base = start(
+            &server,
+            GatewayConfig {
+                tokens: vec!["secret".to_string()],
+                price_ttl: Duration::from_secs(60),
+                ..GatewayConfig::default()
+            },
+        )
+        .await;
+        let http = reqwest::Client::new();
+        let url = format!("{}/prices?instruments=EUR_USD", base);
+
+        let health = http.get(format!("{}/health", base)).send().await.unwrap();
+        assert_eq!(health.status(), 200);
+        let anonymous = http.get(&url).send().await.unwrap();
+        assert_eq!(anonymous.status(), 401);
+
+        for _ in 0..2 {
+            let response = http.get(&url).bearer_auth("secret").send().await.unwrap();
+            assert_eq!(response.status(), 200);
+            let body: Value = response.json().await.unwrap();
+            assert_eq!(body[0]["instrument"], "EUR_USD");
+        }
+        // Orders are off unless enabled
+        let order = http
+            .post(format!("{}/orders", base))
+            .bearer_auth("secret")
+            .json(&OrderRequest::market("EUR_USD", rust_decimal::Decimal::ONE))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(order.status(), 405);
+
+        let candles = http
+            .get(format!("{}/candles/EUR_USD?granularity=M7", base))
+            .bearer_auth("secret")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(candles.status(), 400);
+        let body: Value = candles.json().await.unwrap();
+        assert!(body["error"].as_str().unwrap().contains("M7"));
+
+        pricing.assert_async().await;
+    }
+}
@@ -0,0 +1,209 @@
+//! Background connection health probing
+//!
+//! `HealthProber` periodically makes a lightweight authenticated request and
+//! publishes the result as a `ConnectionStatus` on a watch channel, so a
+//! service can answer readiness checks from the last probe instead of
+//! calling the API on every check.
+//!
+//! ```no_run
+//! use oanda_connector::health::HealthProber;
+//! use oanda_connector::{OandaClient, OandaConfig};
+//! use std::time::Duration;
+//!
+//! # async fn run() -> oanda_connector::Result<()> {
+//! let client = OandaClient::new(OandaConfig::from_env()?)?;
+//! let prober = HealthProber::spawn(client.clone(), Duration::from_secs(30));
+//!
+//! let mut status = prober.subscribe();
+//! while status.changed().await.is_ok() {
+//!     println!("OANDA connection: {:?}", *status.borrow());
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::client::OandaClient;
+use crate::error::Error;
+use crate::shutdown::ShutdownHandle;
+use std::time::Duration;
+use tokio::sync::watch;
+
+/// Consecutive failed probes before the connection counts as down
+pub const DOWN_AFTER_FAILURES: u32 = 3;
+
+/// Connection state as seen by the last probe
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConnectionStatus {
+    /// No probe has completed yet
+    Unknown,
+    /// The last probe succeeded
+    Healthy,
+    /// Recent probes failed, but fewer than `DOWN_AFTER_FAILURES` in a row
+    Degraded { last_error: String },
+    /// Probes keep failing, or the credentials were rejected
+    Down { last_error: String },
+}
+
+impl ConnectionStatus {
+    /// Whether requests are currently expected to succeed
+    pub fn is_healthy(&self) -> bool {
+        matches!(self, ConnectionStatus::Healthy)
+    }
+
+    /// Error from the last failed probe, if the connection isn't healthy
+    pub fn last_error(&self) -> Option<&str> {
+        match self {
+            ConnectionStatus::Degraded { last_error } | ConnectionStatus::Down { last_error } => {
+                Some(last_error)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Background task probing the connection
+///
+/// Stops when dropped. Must be created inside a Tokio runtime.
+pub struct HealthProber {
+    task: ShutdownHandle,
+    status: watch::Receiver<ConnectionStatus>,
+}
+
+impl HealthProber {
+    /// Probe `client` every `interval`, starting immediately
+    ///
+    /// Probes go through the client's rate limiter, retries and circuit
+    /// breaker like any other request, so they see what callers see.
+    pub fn spawn(client: OandaClient, interval: Duration) -> Self {
+        let (sender, status) = watch::channel(ConnectionStatus::Unknown);
+
+        let task = ShutdownHandle::spawn(|mut signal| async move {
+            let mut ticker = tokio::time::interval(interval);
+            let mut failures = 0;
+            loop {
+                tokio::select! {
+                    _ = ticker.tick() => {}
+                    _ = signal.requested() => break,
+                }
+
+                let next = probe_status(client.health_check().await, &mut failures);
+                sender.send_if_modified(|status| {
+                    let changed = *status != next;
+                    *status = next;
+                    changed
+                });
+            }
+        });
+
+        Self { task, status }
+    }
+
+    /// Status from the last probe
+    pub fn status(&self) -> ConnectionStatus {
+        self.status.borrow().clone()
+    }
+
+    /// Receiver notified whenever the status changes
+    pub fn subscribe(&self) -> watch::Receiver<ConnectionStatus> {
+        self.status.clone()
+    }
+
+    /// Stop probing
+    pub fn stop(self) {}
+
+    /// Stop probing once any probe in progress has completed
+    pub async fn shutdown(self) {
+        self.task.shutdown().await;
+    }
+}
+
+impl From<HealthProber> for ShutdownHandle {
+    fn from(prober: HealthProber) -> Self {
+        prober.task
+    }
+}
+
+/// Status after a probe, given the failures before it
+fn probe_status(result: crate::Result<bool>, failures: &mut u32) -> ConnectionStatus {
+    let last_error = match result {
+        Ok(true) => {
+            *failures = 0;
+            return ConnectionStatus::Healthy;
+        }
+        Ok(false) => {
+            *failures += 1;
+            return ConnectionStatus::Down {
+                last_error: Error::AuthenticationFailed.to_string(),
+            };
+        }
+        Err(e) => e.to_string(),
+    };
+
+    *failures += 1;
+    if *failures >= DOWN_AFTER_FAILURES {
+        ConnectionStatus::Down { last_error }
+    } else {
+        ConnectionStatus::Degraded { last_error }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::OandaConfig;
+
+    #[test]
+    fn test_probe_status_transitions() {
+        let mut failures = 0;
+        let timeout = || Err(Error::Timeout(10));
+
+        assert_eq!(
+            probe_status(Ok(true), &mut failures),
+            ConnectionStatus::Healthy
+        );
+        assert!(matches!(
+            probe_status(timeout(), &mut failures),
+            ConnectionStatus::Degraded { .. }
+        ));
+        assert!(matches!(
+            probe_status(timeout(), &mut failures),
+            ConnectionStatus::Degraded { .. }
+        ));
+        let down = probe_status(timeout(), &mut failures);
+        assert!(matches!(down, ConnectionStatus::Down { .. }));
+        assert!(down.last_error().unwrap().contains("timeout"));
+
+        assert!(probe_status(Ok(true), &mut failures).is_healthy());
+        assert!(matches!(
+            probe_status(Ok(false), &mut failures),
+            ConnectionStatus::Down { .. }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_prober_publishes_status() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/v3/accounts/test_account_id")
+            .with_status(401)
+            .expect_at_least(1)
+            .create_async()
+            .await;
+
+        let mut config = OandaConfig::new(
+            "test_api_key".to_string(),
+            "test_account_id".to_string(),
+            true,
+        );
+        config.base_url = Some(server.url());
+        let client = OandaClient::new(config).unwrap();
+
+        let prober = HealthProber::spawn(client, Duration::from_secs(60));
+        let mut status = prober.subscribe();
+        status.changed().await.unwrap();
+
+        assert!(matches!(prober.status(), ConnectionStatus::Down { .. }));
+        mock.assert_async().await;
+        prober.shutdown().await;
+    }
+}
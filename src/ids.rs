@@ -0,0 +1,469 @@
+//! Strongly typed identifiers
+//!
+//! OANDA identifies accounts, orders, trades and transactions with plain
+//! strings. Wrapping each in its own type means a trade ID can't be passed
+//! where an order ID is expected, and malformed IDs are rejected before a
+//! request is ever sent.
+
+use crate::error::{Error, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+macro_rules! string_id {
+    ($(#[$meta:meta])* $name:ident, $validate:ident) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+        #[serde(try_from = "String", into = "String")]
+        pub struct $name(String);
+
+        impl $name {
+            /// Validate and wrap an identifier
+            pub fn new(id: impl Into<String>) -> Result<Self> {
+                let id = id.into();
+                $validate(&id)?;
+                Ok(Self(id))
+            }
+
+            /// Identifier as sent to the API
+            pub fn as_str(&self) -> &str {
+                &self.0
+            }
+
+            /// Unwrap into the underlying string
+            pub fn into_inner(self) -> String {
+                self.0
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(&self.0)
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = Error;
+
+            fn from_str(s: &str) -> Result<Self> {
+                Self::new(s)
+            }
+        }
+
+        impl TryFrom<String> for $name {
+            type Error = Error;
+
+            fn try_from(s: String) -> Result<Self> {
+                Self::new(s)
+            }
+        }
+
+        impl TryFrom<&str> for $name {
+            type Error = Error;
+
+            fn try_from(s: &str) -> Result<Self> {
+                Self::new(s)
+            }
+        }
+
+        impl From<$name> for String {
+            fn from(id: $name) -> String {
+                id.0
+            }
+        }
+
+        impl AsRef<str> for $name {
+            fn as_ref(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl PartialEq<str> for $name {
+            fn eq(&self, other: &str) -> bool {
+                self.0 == other
+            }
+        }
+
+        impl PartialEq<&str> for $name {
+            fn eq(&self, other: &&str) -> bool {
+                self.0 == *other
+            }
+        }
+    };
+}
+
+macro_rules! numeric_id {
+    ($(#[$meta:meta])* $name:ident, $validate:ident) => {
+        string_id!($(#[$meta])* $name, $validate);
+
+        impl $name {
+            /// Numeric value of the identifier
+            pub fn as_u64(&self) -> u64 {
+                // Validated on construction
+                self.0.parse().expect("validated numeric ID")
+            }
+        }
+
+        impl From<u64> for $name {
+            fn from(id: u64) -> Self {
+                Self(id.to_string())
+            }
+        }
+
+        impl PartialOrd for $name {
+            fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+
+        impl Ord for $name {
+            fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+                self.as_u64().cmp(&other.as_u64())
+            }
+        }
+    };
+}
+
+fn validate_numeric(kind: &str, id: &str) -> Result<()> {
+    // No leading zeros, so equal numbers are always equal strings
+    let canonical = id == "0" || !id.starts_with('0');
+    if canonical && id.bytes().all(|b| b.is_ascii_digit()) && id.parse::<u64>().is_ok() {
+        Ok(())
+    } else {
+        Err(Error::ConfigError(format!("Invalid {}: {}", kind, id)))
+    }
+}
+
+fn validate_transaction_id(id: &str) -> Result<()> {
+    validate_numeric("transaction ID", id)
+}
+
+fn validate_order_id(id: &str) -> Result<()> {
+    validate_numeric("order ID", id)
+}
+
+fn validate_trade_id(id: &str) -> Result<()> {
+    validate_numeric("trade ID", id)
+}
+
+fn validate_account_id(id: &str) -> Result<()> {
+    // Must be usable as a URL path segment
+    let valid = !id.is_empty()
+        && id
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_');
+    if valid {
+        Ok(())
+    } else {
+        Err(Error::ConfigError(format!("Invalid account ID: {}", id)))
+    }
+}
+
+fn validate_instrument_name(name: &str) -> Result<()> {
+    let part_ok = |part: &str| {
+        !part.is_empty()
+            && part
+                .bytes()
+                .all(|b| b.is_ascii_uppercase() || b.is_ascii_digit())
+    };
+    match name.split_once('_') {
+        Some((base, quote)) if part_ok(base) && part_ok(quote) => Ok(()),
+        _ => Err(Error::InvalidInstrument(name.to_string())),
+    }
+}
+
+string_id!(
+    /// Account identifier, e.g. `101-004-1234567-001`
+    AccountId,
+    validate_account_id
+);
+
+string_id!(
+    /// Instrument name in `BASE_QUOTE` form, e.g. `EUR_USD` or `SPX500_USD`
+    InstrumentName,
+    validate_instrument_name
+);
+
+numeric_id!(
+    /// Order identifier assigned by OANDA
+    OrderId,
+    validate_order_id
+);
+
+numeric_id!(
+    /// Trade identifier assigned by OANDA
+    TradeId,
+    validate_trade_id
+);
+
+numeric_id!(
+    /// Transaction identifier; IDs increase monotonically per account
+    TransactionId,
+    validate_transaction_id
+);
+
+impl InstrumentName {
+    /// Base currency or asset (the part before the underscore)
+    pub fn base(&self) -> &str {
+        self.0
+            .split_once('_')
+            .map(|(base, _)| base)
+            .unwrap_or(&self.0)
+    }
+
+    /// Quote currency (the part after the underscore)
+    pub fn quote(&self) -> &str {
+        self.0.split_once('_').map(|(_, quote)| quote).unwrap_or("")
+    }
+}
+
+impl TransactionId {
+    /// The transaction ID following this one
+    pub fn next(&self) -> TransactionId {
+        TransactionId::from(self.as_u64() + 1)
+    }
+}
+
+/// Broad class of a known instrument
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InstrumentCategory {
+    Major,
+    Cross,
+    Metal,
+    Index,
+}
+
+macro_rules! known_instruments {
+    ($($variant:ident => $name:literal, $category:ident;)*) => {
+        /// OANDA's standard instruments, with an escape hatch for the rest
+        ///
+        /// Known instruments are checked at compile time; anything else goes
+        /// through [`InstrumentId::Other`], which is validated like an
+        /// [`InstrumentName`] when parsed.
+        #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+        #[serde(try_from = "String", into = "String")]
+        pub enum InstrumentId {
+            $($variant,)*
+            Other(String),
+        }
+
+        impl InstrumentId {
+            /// Every known instrument
+            pub const KNOWN: &'static [InstrumentId] = &[$(InstrumentId::$variant,)*];
+
+            /// OANDA instrument name, e.g. `EUR_USD`
+            pub fn as_str(&self) -> &str {
+                match self {
+                    $(InstrumentId::$variant => $name,)*
+                    InstrumentId::Other(name) => name,
+                }
+            }
+
+            /// Category of a known instrument, None for `Other`
+            pub fn category(&self) -> Option<InstrumentCategory> {
+                match self {
+                    $(InstrumentId::$variant => Some(InstrumentCategory::$category),)*
+                    InstrumentId::Other(_) => None,
+                }
+            }
+
+            fn known(name: &str) -> Option<Self> {
+                match name {
+                    $($name => Some(InstrumentId::$variant),)*
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+known_instruments! {
+    EurUsd => "EUR_USD", Major;
+    GbpUsd => "GBP_USD", Major;
+    UsdJpy => "USD_JPY", Major;
+    UsdChf => "USD_CHF", Major;
+    AudUsd => "AUD_USD", Major;
+    UsdCad => "USD_CAD", Major;
+    NzdUsd => "NZD_USD", Major;
+    EurGbp => "EUR_GBP", Cross;
+    EurJpy => "EUR_JPY", Cross;
+    EurChf => "EUR_CHF", Cross;
+    EurAud => "EUR_AUD", Cross;
+    EurCad => "EUR_CAD", Cross;
+    EurNzd => "EUR_NZD", Cross;
+    GbpJpy => "GBP_JPY", Cross;
+    GbpChf => "GBP_CHF", Cross;
+    GbpAud => "GBP_AUD", Cross;
+    GbpCad => "GBP_CAD", Cross;
+    GbpNzd => "GBP_NZD", Cross;
+    AudJpy => "AUD_JPY", Cross;
+    AudCad => "AUD_CAD", Cross;
+    AudChf => "AUD_CHF", Cross;
+    AudNzd => "AUD_NZD", Cross;
+    CadJpy => "CAD_JPY", Cross;
+    CadChf => "CAD_CHF", Cross;
+    ChfJpy => "CHF_JPY", Cross;
+    NzdJpy => "NZD_JPY", Cross;
+    NzdCad => "NZD_CAD", Cross;
+    NzdChf => "NZD_CHF", Cross;
+    XauUsd => "XAU_USD", Metal;
+    XagUsd => "XAG_USD", Metal;
+    XptUsd => "XPT_USD", Metal;
+    XpdUsd => "XPD_USD", Metal;
+    XauEur => "XAU_EUR", Metal;
+    Spx500Usd => "SPX500_USD", Index;
+    Nas100Usd => "NAS100_USD", Index;
+    Us30Usd => "US30_USD", Index;
+    Us2000Usd => "US2000_USD", Index;
+    De30Eur => "DE30_EUR", Index;
+    Uk100Gbp => "UK100_GBP", Index;
+    Eu50Eur => "EU50_EUR", Index;
+    Fr40Eur => "FR40_EUR", Index;
+    Jp225Usd => "JP225_USD", Index;
+    Au200Aud => "AU200_AUD", Index;
+    Hk33Hkd => "HK33_HKD", Index;
+}
+
+impl InstrumentId {
+    /// Check whether this is one of the known instruments
+    pub fn is_known(&self) -> bool {
+        !matches!(self, InstrumentId::Other(_))
+    }
+}
+
+impl fmt::Display for InstrumentId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for InstrumentId {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match InstrumentId::known(s) {
+            Some(id) => Ok(id),
+            None => {
+                validate_instrument_name(s)?;
+                Ok(InstrumentId::Other(s.to_string()))
+            }
+        }
+    }
+}
+
+impl TryFrom<String> for InstrumentId {
+    type Error = Error;
+
+    fn try_from(s: String) -> Result<Self> {
+        s.parse()
+    }
+}
+
+impl From<InstrumentId> for String {
+    fn from(id: InstrumentId) -> String {
+        match id {
+            InstrumentId::Other(name) => name,
+            known => known.as_str().to_string(),
+        }
+    }
+}
+
+impl AsRef<str> for InstrumentId {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl TryFrom<InstrumentId> for InstrumentName {
+    type Error = Error;
+
+    fn try_from(id: InstrumentId) -> Result<Self> {
+        InstrumentName::new(String::from(id))
+    }
+}
+
+impl From<InstrumentName> for InstrumentId {
+    fn from(name: InstrumentName) -> Self {
+        InstrumentId::known(name.as_str()).unwrap_or(InstrumentId::Other(name.into_inner()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_numeric_ids() {
+        let id = TransactionId::new("6410").unwrap();
+        assert_eq!(id.as_u64(), 6410);
+        assert_eq!(id.next(), TransactionId::from(6411));
+        assert_eq!(id, "6410");
+        assert!(TransactionId::new("abc").is_err());
+        assert!(TransactionId::new("").is_err());
+        assert!(OrderId::new("-1").is_err());
+        assert!(OrderId::new("007").is_err());
+
+        // Numeric, not lexicographic, ordering
+        assert!(TradeId::from(9) < TradeId::from(10));
+    }
+
+    #[test]
+    fn test_instrument_name() {
+        let name: InstrumentName = "EUR_USD".parse().unwrap();
+        assert_eq!(name.base(), "EUR");
+        assert_eq!(name.quote(), "USD");
+        assert!(InstrumentName::new("SPX500_USD").is_ok());
+
+        for invalid in ["EURUSD", "eur_usd", "EUR_", "EUR/USD", "EUR_USD_X"] {
+            assert!(
+                matches!(
+                    InstrumentName::new(invalid),
+                    Err(Error::InvalidInstrument(_))
+                ),
+                "{} should be rejected",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn test_account_id() {
+        assert!(AccountId::new("101-004-1234567-001").is_ok());
+        assert!(AccountId::new("").is_err());
+        assert!(AccountId::new("101/../orders").is_err());
+    }
+
+    #[test]
+    fn test_serde_validates() {
+        let id: OrderId = serde_json::from_str(r#""42""#).unwrap();
+        assert_eq!(serde_json::to_string(&id).unwrap(), r#""42""#);
+        assert!(serde_json::from_str::<OrderId>(r#""not-an-id""#).is_err());
+    }
+
+    #[test]
+    fn test_instrument_id() {
+        assert_eq!(
+            "USD_JPY".parse::<InstrumentId>().unwrap(),
+            InstrumentId::UsdJpy
+        );
+        assert_eq!(InstrumentId::Spx500Usd.to_string(), "SPX500_USD");
+        assert_eq!(
+            InstrumentId::XauUsd.category(),
+            Some(InstrumentCategory::Metal)
+        );
+
+        let other: InstrumentId = "SGD_CHF".parse().unwrap();
+        assert_eq!(other, InstrumentId::Other("SGD_CHF".to_string()));
+        assert!(!other.is_known());
+        assert!("EURUSD".parse::<InstrumentId>().is_err());
+
+        // Every known name round-trips through its string form
+        for id in InstrumentId::KNOWN {
+            assert_eq!(&id.as_str().parse::<InstrumentId>().unwrap(), id);
+            assert!(InstrumentName::try_from(id.clone()).is_ok());
+        }
+
+        let json = serde_json::to_string(&InstrumentId::EurUsd).unwrap();
+        assert_eq!(json, r#""EUR_USD""#);
+    }
+}
@@ -0,0 +1,451 @@
+//! Kafka publisher (`kafka` feature)
+//!
+//! [`KafkaSink`] publishes ticks, candles and transactions to Kafka topics,
+//! so the connector can feed a market data pipeline directly. Messages are
+//! encoded in the configured [`Format`], keyed by instrument so each
+//! instrument stays ordered within its partition, and timestamped with the
+//! event's own time. Every message carries a `content-type` header.
+//!
+//! Transactions not about a single instrument (transfers, financing, margin
+//! calls) are keyed by account ID.
+//!
+//! ```no_run
+//! use oanda_connector::kafka::{KafkaConfig, KafkaSink};
+//! use oanda_connector::{Granularity, OandaClient, OandaConfig};
+//! use std::time::Duration;
+//!
+//! # async fn run() -> oanda_connector::Result<()> {
+//! let client = OandaClient::new(OandaConfig::from_env()?)?;
+//! let sink = KafkaSink::new(KafkaConfig::new("localhost:9092"))?;
+//!
+//! let instruments = vec!["EUR_USD".to_string(), "USD_JPY".to_string()];
+//! let prices = sink.spawn_prices(client.clone(), instruments, Some(Granularity::M1));
+//! let transactions = sink.spawn_transactions(client, Duration::from_secs(5));
+//!
+//! tokio::signal::ctrl_c().await.ok();
+//! prices.merge(transactions).shutdown().await;
+//! sink.flush(Duration::from_secs(10))?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::aggregator::CandleAggregator;
+use crate::client::OandaClient;
+use crate::codec::Format;
+use crate::error::{Error, Result};
+use crate::models::{Candle, Granularity, Tick};
+use crate::shutdown::{ShutdownHandle, ShutdownSignal};
+use crate::transactions::Transaction;
+use chrono::{DateTime, Utc};
+use futures::StreamExt;
+use rdkafka::config::ClientConfig;
+use rdkafka::message::{Header, OwnedHeaders};
+use rdkafka::producer::{DeliveryFuture, FutureProducer, FutureRecord, Producer};
+use rdkafka::util::Timeout;
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Brokers, topics and encoding
+#[derive(Debug, Clone, PartialEq)]
+pub struct KafkaConfig {
+    /// `bootstrap.servers`, e.g. `"kafka-1:9092,kafka-2:9092"`
+    pub brokers: String,
+    pub tick_topic: String,
+    pub candle_topic: String,
+    pub transaction_topic: String,
+    pub format: Format,
+    /// Extra librdkafka producer settings, e.g. `("compression.type", "lz4")`
+    pub properties: Vec<(String, String)>,
+    /// Give up on a message not acknowledged within this time
+    pub delivery_timeout: Duration,
+    /// Wait before reopening a dropped stream
+    pub reconnect_delay: Duration,
+}
+
+impl KafkaConfig {
+    /// JSON messages to `oanda.ticks`, `oanda.candles` and
+    /// `oanda.transactions`
+    pub fn new(brokers: impl Into<String>) -> Self {
+        Self {
+            brokers: brokers.into(),
+            tick_topic: "oanda.ticks".to_string(),
+            candle_topic: "oanda.candles".to_string(),
+            transaction_topic: "oanda.transactions".to_string(),
+            format: Format::Json,
+            properties: Vec::new(),
+            delivery_timeout: Duration::from_secs(30),
+            reconnect_delay: Duration::from_secs(5),
+        }
+    }
+}
+
+/// Publishes models to Kafka
+///
+/// Cloning is cheap and clones share the producer and counters.
+#[derive(Clone)]
+pub struct KafkaSink {
+    producer: FutureProducer,
+    config: Arc<KafkaConfig>,
+    published: Arc<AtomicU64>,
+    last_error: Arc<Mutex<Option<String>>>,
+}
+
+impl KafkaSink {
+    /// Create the producer; brokers are contacted on the first message
+    pub fn new(config: KafkaConfig) -> Result<Self> {
+        let mut client_config = ClientConfig::new();
+        client_config.set("bootstrap.servers", &config.brokers).set(
+            "message.timeout.ms",
+            config.delivery_timeout.as_millis().to_string(),
+        );
+        for (key, value) in &config.properties {
+            client_config.set(key, value);
+        }
+        let producer = client_config
+            .create()
+            .map_err(|e| Error::ConfigError(format!("Kafka producer: {}", e)))?;
+        Ok(Self {
+            producer,
+            config: Arc::new(config),
+            published: Arc::new(AtomicU64::new(0)),
+            last_error: Arc::new(Mutex::new(None)),
+        })
+    }
+
+    pub fn config(&self) -> &KafkaConfig {
+        &self.config
+    }
+
+    /// Messages acknowledged by the brokers so far
+    pub fn published(&self) -> u64 {
+        self.published.load(Ordering::Relaxed)
+    }
+
+    /// Error from the latest background publish, cleared once one succeeds
+    pub fn last_error(&self) -> Option<String> {
+        self.last_error.lock().unwrap().clone()
+    }
+
+    /// Publish a tick and wait for the brokers to acknowledge it
+    pub async fn publish_tick(&self, tick: &Tick) -> Result<()> {
+        self.send(tick).await
+    }
+
+    /// Publish a candle and wait for the brokers to acknowledge it
+    pub async fn publish_candle(&self, candle: &Candle) -> Result<()> {
+        self.send(candle).await
+    }
+
+    /// Publish a transaction and wait for the brokers to acknowledge it
+    pub async fn publish_transaction(&self, transaction: &Transaction) -> Result<()> {
+        self.send(transaction).await
+    }
+
+    /// Wait for every queued message to be delivered
+    pub fn flush(&self, timeout: Duration) -> Result<()> {
+        self.producer
+            .flush(Timeout::After(timeout))
+            .map_err(|e| Error::PublishError(e.to_string()))
+    }
+
+    async fn send<T: Routed>(&self, value: &T) -> Result<()> {
+        let topic = T::topic(&self.config);
+        let delivery = self.enqueue(value)?;
+        self.delivered(topic, delivery).await
+    }
+
+    /// Hand a message to the producer's queue
+    fn enqueue<T: Routed>(&self, value: &T) -> Result<DeliveryFuture> {
+        let topic = T::topic(&self.config);
+        let payload = self.config.format.encode(value)?;
+        let key = value.key();
+        let headers = OwnedHeaders::new().insert(Header {
+            key: "content-type",
+            value: Some(self.config.format.content_type()),
+        });
+        let mut record = FutureRecord::to(topic)
+            .key(key.as_str())
+            .payload(&payload)
+            .headers(headers);
+        if let Some(time) = value.time() {
+            record = record.timestamp(time.timestamp_millis());
+        }
+        self.producer
+            .send_result(record)
+            .map_err(|(e, _)| Error::PublishError(format!("{}: {}", topic, e)))
+    }
+
+    async fn delivered(&self, topic: &str, delivery: DeliveryFuture) -> Result<()> {
+        match delivery.await {
+            Ok(Ok(_)) => {
+                self.published.fetch_add(1, Ordering::Relaxed);
+                Ok(())
+            }
+            Ok(Err((e, _))) => Err(Error::PublishError(format!("{}: {}", topic, e))),
+            Err(_) => Err(Error::PublishError(format!("{}: producer closed", topic))),
+        }
+    }
+
+    /// Queue a message without waiting for it, recording any failure in
+    /// `last_error`
+    ///
+    /// Background tasks publish this way so a slow broker doesn't hold up
+    /// the stream; a message that can't even be queued is dropped.
+    fn publish_in_background<T: Routed>(&self, value: &T) {
+        match self.enqueue(value) {
+            Ok(delivery) => {
+                let sink = self.clone();
+                tokio::spawn(async move {
+                    let result = sink.delivered(T::topic(&sink.config), delivery).await;
+                    sink.report(result);
+                });
+            }
+            Err(e) => self.report(Err(e)),
+        }
+    }
+
+    fn report(&self, result: Result<()>) {
+        match result {
+            Ok(()) => *self.last_error.lock().unwrap() = None,
+            Err(e) => {
+                log::warn!("Kafka publish failed: {}", e);
+                *self.last_error.lock().unwrap() = Some(e.to_string());
+            }
+        }
+    }
+
+    /// Publish the pricing stream in the background
+    ///
+    /// With a granularity, candles built from the ticks are published too
+    /// as each one completes. Reconnects after `reconnect_delay` whenever
+    /// the stream drops. Must be called inside a Tokio runtime.
+    pub fn spawn_prices(
+        &self,
+        client: OandaClient,
+        instruments: Vec<String>,
+        candles: Option<Granularity>,
+    ) -> ShutdownHandle {
+        let sink = self.clone();
+        ShutdownHandle::spawn(|mut signal| async move {
+            let mut aggregator = candles.map(CandleAggregator::new);
+            while sink
+                .publish_stream(&client, &instruments, aggregator.as_mut(), &mut signal)
+                .await
+            {
+                tokio::select! {
+                    _ = tokio::time::sleep(sink.config.reconnect_delay) => {}
+                    _ = signal.requested() => break,
+                }
+            }
+        })
+    }
+
+    /// Run one connection of the pricing stream; false once shutdown is
+    /// requested
+    async fn publish_stream(
+        &self,
+        client: &OandaClient,
+        instruments: &[String],
+        mut aggregator: Option<&mut CandleAggregator>,
+        signal: &mut ShutdownSignal,
+    ) -> bool {
+        let connected = tokio::select! {
+            connected = client.stream_prices(instruments) => connected,
+            _ = signal.requested() => return false,
+        };
+        let stream = match connected {
+            Ok(stream) => stream,
+            Err(e) => {
+                self.report(Err(e));
+                return true;
+            }
+        };
+        let mut stream = Box::pin(stream);
+        let mut ticker = tokio::time::interval(Duration::from_secs(1));
+        loop {
+            let next = tokio::select! {
+                next = stream.next() => next,
+                _ = ticker.tick() => {
+                    // Close candles of instruments that have gone quiet
+                    if let Some(aggregator) = aggregator.as_deref_mut() {
+                        for candle in aggregator.flush(Utc::now()) {
+                            self.publish_in_background(&candle);
+                        }
+                    }
+                    continue;
+                }
+                _ = signal.requested() => return false,
+            };
+            match next {
+                Some(Ok(tick)) => {
+                    self.publish_in_background(&tick);
+                    let closed = aggregator.as_deref_mut().and_then(|a| a.on_tick(&tick));
+                    if let Some(candle) = closed {
+                        self.publish_in_background(&candle);
+                    }
+                }
+                Some(Err(e)) => {
+                    self.report(Err(e));
+                    return true;
+                }
+                None => return true,
+            }
+        }
+    }
+
+    /// Poll for new transactions every `interval` and publish them
+    ///
+    /// Starts from the client's last seen transaction, so only
+    /// transactions from now on are published. Must be called inside a
+    /// Tokio runtime.
+    pub fn spawn_transactions(&self, client: OandaClient, interval: Duration) -> ShutdownHandle {
+        let sink = self.clone();
+        ShutdownHandle::spawn(|mut signal| async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                tokio::select! {
+                    _ = ticker.tick() => {}
+                    _ = signal.requested() => break,
+                }
+                match client.get_transactions_since_last().await {
+                    Ok(transactions) => {
+                        for transaction in &transactions {
+                            sink.publish_in_background(transaction);
+                        }
+                    }
+                    Err(e) => sink.report(Err(e)),
+                }
+            }
+        })
+    }
+}
+
+/// Topic, key and timestamp of a published model
+trait Routed: Serialize {
+    fn topic(config: &KafkaConfig) -> &str;
+    fn key(&self) -> String;
+    fn time(&self) -> Option<DateTime<Utc>>;
+}
+
+impl Routed for Tick {
+    fn topic(config: &KafkaConfig) -> &str {
+        &config.tick_topic
+    }
+
+    fn key(&self) -> String {
+        self.instrument.clone()
+    }
+
+    fn time(&self) -> Option<DateTime<Utc>> {
+        Some(self.timestamp)
+    }
+}
+
+impl Routed for Candle {
+    fn topic(config: &KafkaConfig) -> &str {
+        &config.candle_topic
+    }
+
+    fn key(&self) -> String {
+        self.instrument.clone()
+    }
+
+    fn time(&self) -> Option<DateTime<Utc>> {
+        Some(self.timestamp)
+    }
+}
+
+impl Routed for Transaction {
+    fn topic(config: &KafkaConfig) -> &str {
+        &config.transaction_topic
+    }
+
+    fn key(&self) -> String {
+        transaction_key(self)
+    }
+
+    fn time(&self) -> Option<DateTime<Utc>> {
+        Transaction::time(self)
+    }
+}
+
+/// Partition key: the instrument, else the account
+fn transaction_key(transaction: &Transaction) -> String {
+    if let Some(instrument) = transaction.instrument() {
+        return instrument.to_string();
+    }
+    match transaction {
+        Transaction::Unknown(value) => value
+            .get("accountID")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .to_string(),
+        _ => transaction
+            .header()
+            .map(|h| h.account_id.as_str().to_string())
+            .unwrap_or_default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+
+    #[test]
+    fn test_transaction_keys() {
+        let transactions: Vec<Transaction> = serde_json::from_str(
+            r#"[{
+                "id": "6501",
+                "time": "2024-01-02T21:00:00Z",
+                "accountID": "101-004-1234567-001",
+                "batchID": "6501",
+                "type": "MARKET_ORDER",
+                "instrument": "USD_JPY",
+                "units": "5000",
+                "timeInForce": "FOK",
+                "reason": "CLIENT_ORDER"
+            }, {
+                "id": "7000",
+                "time": "2024-01-03T00:00:00Z",
+                "accountID": "101-004-1234567-001",
+                "batchID": "7000",
+                "type": "SOME_FUTURE_TYPE"
+            }]"#,
+        )
+        .unwrap();
+        assert_eq!(transaction_key(&transactions[0]), "USD_JPY");
+        assert_eq!(transaction_key(&transactions[1]), "101-004-1234567-001");
+    }
+
+    #[tokio::test]
+    async fn test_undeliverable_message_is_an_error() {
+        let mut config = KafkaConfig::new("127.0.0.1:1");
+        config.delivery_timeout = Duration::from_millis(200);
+        let sink = KafkaSink::new(config).unwrap();
+
+        let tick = Tick::builder().build();
+        let error = sink.publish_tick(&tick).await.unwrap_err();
+        assert!(matches!(error, Error::PublishError(_)));
+        assert_eq!(error.kind(), ErrorKind::Transient);
+        assert_eq!(sink.published(), 0);
+
+        // Background publishing reports failures instead of returning them
+        sink.publish_in_background(&tick);
+        for _ in 0..50 {
+            if sink.last_error().is_some() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        assert!(sink.last_error().unwrap().contains("oanda.ticks"));
+
+        let mut bad = KafkaConfig::new("127.0.0.1:1");
+        bad.properties
+            .push(("no.such.setting".to_string(), "1".to_string()));
+        assert!(matches!(KafkaSink::new(bad), Err(Error::ConfigError(_))));
+    }
+}
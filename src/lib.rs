@@ -3,18 +3,98 @@
 //! High-performance Rust client for OANDA's REST and streaming APIs.
 //! Handles rate limiting, retries, and error recovery automatically.
 
+pub mod aggregator;
+pub mod alerts;
+#[cfg(feature = "arrow")]
+pub mod arrow;
+pub mod backtest;
+#[cfg(feature = "uniffi")]
+pub mod bindings;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+pub mod calendar;
+pub mod circuit_breaker;
 pub mod client;
+pub mod codec;
 pub mod config;
+pub mod config_watcher;
+pub mod conversion;
+pub mod data_store;
+pub mod datetime;
+pub mod decimal_number;
 pub mod endpoints;
+pub mod equity;
 pub mod error;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "gateway")]
+pub mod gateway;
+pub mod health;
+pub mod ids;
+#[cfg(feature = "kafka")]
+pub mod kafka;
+pub mod middleware;
 pub mod models;
+pub mod order_manager;
+pub mod orders;
+pub mod paper;
+pub mod portfolio;
 pub mod rate_limiter;
+pub mod reconcile;
+#[cfg(feature = "redis")]
+pub mod redis_bridge;
+#[cfg(feature = "recorder")]
+pub mod recorder;
+pub mod reports;
+pub mod response;
+pub mod risk;
+pub mod scheduler;
+pub mod shutdown;
+pub mod spread_monitor;
+pub mod stats;
+pub mod strategy;
+#[cfg(any(test, feature = "test-util"))]
+pub mod test_util;
+pub mod trading;
+pub mod trailing_stop;
+pub mod transactions;
+pub mod transport;
+
+#[cfg(feature = "uniffi")]
+uniffi::setup_scaffolding!();
 
 // Re-export main types
-pub use client::OandaClient;
-pub use config::OandaConfig;
-pub use error::{Error, Result};
-pub use models::{Candle, Tick, Granularity, AccountSummary, Instrument};
+pub use aggregator::CandleAggregator;
+pub use alerts::{AlertCondition, AlertEngine, AlertEvent};
+pub use backtest::{Backtest, BacktestConfig, BacktestReport, MarketEvent};
+pub use calendar::{MarketCalendar, Session};
+pub use client::{OandaClient, OandaClientBuilder};
+pub use config::{CircuitBreakerConfig, ClientCertificate, ConnectionConfig, KeyringEntry, OandaConfig, OandaConfigBuilder, ProxyConfig, RateLimitConfig, RetryPolicy, RuntimeSettings, TimeoutConfig, TlsBackend, TlsConfig};
+pub use config_watcher::ConfigWatcher;
+pub use endpoints::EndpointGroup;
+pub use equity::{DrawdownStats, EquityCurve, EquityTracker};
+pub use conversion::CurrencyConverter;
+pub use data_store::{DataStore, FileStore};
+pub use datetime::DatetimeFormat;
+pub use error::{Error, ErrorKind, RequestContext, Result};
+pub use health::{ConnectionStatus, HealthProber};
+pub use ids::{AccountId, InstrumentCategory, InstrumentId, InstrumentName, OrderId, TradeId, TransactionId};
+pub use models::{Candle, Tick, LatencyReport, PriceStatus, Quote, PriceLevel, Granularity, CandleAlignment, AccountSummary, PositionMode, Trade, Position, PositionSide, Instrument, InstrumentFinancing, OrderBook, PositionBook, BookBucket};
+pub use order_manager::{OrderEvent, OrderManager};
+pub use orders::{ClosePositionResponse, FlattenOptions, FlattenReport, Order, OrderRejectReason, OrderRequest, OrderResponse, OrderSpecifier};
+pub use paper::{PaperConfig, PaperTrader};
+pub use portfolio::{CurrencyExposure, ExposureReport, InstrumentExposure, Portfolio, PortfolioSnapshot};
+pub use reconcile::StateDiff;
+pub use response::{ResponseEnvelope, ResponseMetadata};
+pub use risk::{RiskLimits, RiskManager, RiskViolation};
+pub use scheduler::{Schedule, ScheduledJob, Scheduler};
+pub use shutdown::ShutdownHandle;
+pub use spread_monitor::{SpreadMonitor, SpreadStats};
+pub use stats::EndpointStats;
+pub use strategy::{Strategy, StrategyContext, StrategyRunner};
+pub use trading::Trading;
+pub use trailing_stop::{TrailDistance, TrailingStop, TrailingStopManager};
+pub use transactions::Transaction;
 
 #[cfg(test)]
 mod tests {
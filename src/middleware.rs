@@ -0,0 +1,284 @@
+//! Request and response middleware
+//!
+//! Middleware wraps every HTTP attempt the client makes, after rate
+//! limiting and before retry decisions, so it can add headers, audit or
+//! time requests, or answer from a cache without calling the API.
+//!
+//! ```no_run
+//! use async_trait::async_trait;
+//! use oanda_connector::middleware::{Middleware, Next};
+//! use oanda_connector::{OandaClientBuilder, OandaConfig, RequestContext, Result};
+//! use reqwest::{Request, Response};
+//! use std::time::Instant;
+//!
+//! struct Timing;
+//!
+//! #[async_trait]
+//! impl Middleware for Timing {
+//!     async fn handle(
+//!         &self,
+//!         mut request: Request,
+//!         context: &RequestContext,
+//!         next: Next<'_>,
+//!     ) -> Result<Response> {
+//!         request.headers_mut().insert("X-Desk", "fx-1".parse().unwrap());
+//!         let started = Instant::now();
+//!         let response = next.run(request, context).await;
+//!         println!("{} took {:?}", context, started.elapsed());
+//!         response
+//!     }
+//! }
+//!
+//! # fn main() -> oanda_connector::Result<()> {
+//! let client = OandaClientBuilder::new(OandaConfig::from_env()?)
+//!     .middleware(Timing)
+//!     .build()?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::error::{Error, RequestContext, Result};
+use crate::transport::Transport;
+use async_trait::async_trait;
+use reqwest::header::{HeaderMap, AUTHORIZATION};
+use reqwest::{Request, Response};
+use std::sync::Arc;
+use std::time::Instant;
+
+/// Hook around each HTTP attempt
+///
+/// Call `next.run` to pass the request on, or return a response (or error)
+/// directly to short-circuit. Errors other than transport failures are not
+/// retried.
+#[async_trait]
+pub trait Middleware: Send + Sync + 'static {
+    async fn handle(
+        &self,
+        request: Request,
+        context: &RequestContext,
+        next: Next<'_>,
+    ) -> Result<Response>;
+}
+
+/// The rest of the middleware chain, ending with the transport
+pub struct Next<'a> {
+    transport: &'a dyn Transport,
+    middleware: &'a [Arc<dyn Middleware>],
+}
+
+impl<'a> Next<'a> {
+    pub(crate) fn new(transport: &'a dyn Transport, middleware: &'a [Arc<dyn Middleware>]) -> Self {
+        Self {
+            transport,
+            middleware,
+        }
+    }
+
+    /// Pass the request to the next middleware, or send it
+    pub async fn run(self, request: Request, context: &RequestContext) -> Result<Response> {
+        match self.middleware.split_first() {
+            Some((first, rest)) => {
+                first
+                    .handle(request, context, Next::new(self.transport, rest))
+                    .await
+            }
+            None => self.transport.execute(request).await,
+        }
+    }
+}
+
+/// Logs each request and response at debug level through the `log` crate
+///
+/// Records method, URL, headers, status, latency and bodies cut to
+/// `max_body_bytes`. The `Authorization` header is always redacted.
+/// Responses are only buffered when debug logging is enabled for this
+/// module, so leaving the middleware installed costs nothing otherwise.
+/// Streaming responses are never buffered; for those only the status,
+/// headers and time to first byte are logged.
+///
+/// ```no_run
+/// use oanda_connector::middleware::RequestLogger;
+/// use oanda_connector::{OandaClientBuilder, OandaConfig};
+///
+/// # fn main() -> oanda_connector::Result<()> {
+/// let client = OandaClientBuilder::new(OandaConfig::from_env()?)
+///     .middleware(RequestLogger::new().max_body_bytes(512))
+///     .build()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct RequestLogger {
+    max_body_bytes: usize,
+}
+
+impl RequestLogger {
+    /// Log bodies up to 1 KiB
+    pub fn new() -> Self {
+        Self {
+            max_body_bytes: 1024,
+        }
+    }
+
+    /// Truncate logged bodies to `max_body_bytes`
+    pub fn max_body_bytes(mut self, max_body_bytes: usize) -> Self {
+        self.max_body_bytes = max_body_bytes;
+        self
+    }
+}
+
+impl Default for RequestLogger {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Middleware for RequestLogger {
+    async fn handle(
+        &self,
+        request: Request,
+        context: &RequestContext,
+        next: Next<'_>,
+    ) -> Result<Response> {
+        if !log::log_enabled!(log::Level::Debug) {
+            return next.run(request, context).await;
+        }
+
+        let method = request.method().clone();
+        let url = request.url().clone();
+        let body = request
+            .body()
+            .and_then(|body| body.as_bytes())
+            .map(|body| truncate_body(body, self.max_body_bytes))
+            .unwrap_or_default();
+        log::debug!(
+            "--> {} {} {} {}",
+            method,
+            url,
+            redacted_headers(request.headers()),
+            body
+        );
+
+        let started = Instant::now();
+        let response = match next.run(request, context).await {
+            Ok(response) => response,
+            Err(e) => {
+                log::debug!(
+                    "<-- {} {} failed after {:?}: {}",
+                    method,
+                    url,
+                    started.elapsed(),
+                    e
+                );
+                return Err(e);
+            }
+        };
+
+        // A stream body never ends, so log the head and pass it through
+        if is_streaming(context) {
+            log::debug!(
+                "<-- {} {} {} in {:?} {} (streaming)",
+                response.status().as_u16(),
+                method,
+                url,
+                started.elapsed(),
+                redacted_headers(response.headers())
+            );
+            return Ok(response);
+        }
+
+        // Buffer the body to log it, then hand on an identical response
+        let status = response.status();
+        let version = response.version();
+        let headers = response.headers().clone();
+        let body = response.bytes().await.map_err(Error::HttpError)?;
+        log::debug!(
+            "<-- {} {} {} in {:?} {}",
+            status.as_u16(),
+            method,
+            url,
+            started.elapsed(),
+            truncate_body(&body, self.max_body_bytes)
+        );
+
+        let mut rebuilt = http::Response::new(body);
+        *rebuilt.status_mut() = status;
+        *rebuilt.version_mut() = version;
+        *rebuilt.headers_mut() = headers;
+        Ok(Response::from(rebuilt))
+    }
+}
+
+/// Whether the request opens a long-lived stream
+fn is_streaming(context: &RequestContext) -> bool {
+    context.path.ends_with("/stream")
+}
+
+/// Headers for logging, with credentials replaced
+fn redacted_headers(headers: &HeaderMap) -> String {
+    let headers: Vec<String> = headers
+        .iter()
+        .map(|(name, value)| {
+            let value = if name == AUTHORIZATION {
+                "<redacted>"
+            } else {
+                value.to_str().unwrap_or("<binary>")
+            };
+            format!("{}: {}", name, value)
+        })
+        .collect();
+    format!("[{}]", headers.join(", "))
+}
+
+/// Body as text, cut to `max` bytes
+fn truncate_body(body: &[u8], max: usize) -> String {
+    let text = String::from_utf8_lossy(&body[..body.len().min(max)]);
+    if body.len() > max {
+        format!("{}... ({} bytes)", text, body.len())
+    } else {
+        text.into_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    #[test]
+    fn test_redacts_authorization() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            AUTHORIZATION,
+            HeaderValue::from_static("Bearer secret-token"),
+        );
+        headers.insert(
+            "Accept-Datetime-Format",
+            HeaderValue::from_static("RFC3339"),
+        );
+
+        let logged = redacted_headers(&headers);
+        assert!(!logged.contains("secret-token"));
+        assert!(logged.contains("authorization: <redacted>"));
+        assert!(logged.contains("accept-datetime-format: RFC3339"));
+    }
+
+    #[test]
+    fn test_is_streaming() {
+        let context = |path: &str| RequestContext {
+            method: "GET".to_string(),
+            path: path.to_string(),
+            account_id: None,
+            instrument: None,
+        };
+        assert!(is_streaming(&context("/v3/accounts/101/pricing/stream")));
+        assert!(!is_streaming(&context("/v3/accounts/101/pricing")));
+    }
+
+    #[test]
+    fn test_truncate_body() {
+        assert_eq!(truncate_body(b"{}", 10), "{}");
+        assert_eq!(truncate_body(b"0123456789", 4), "0123... (10 bytes)");
+    }
+}
@@ -1,6 +1,7 @@
 //! Data models for OANDA API
 
 use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 
 /// OHLCV candle data
@@ -8,35 +9,310 @@ use serde::{Deserialize, Serialize};
 pub struct Candle {
     pub instrument: String,
     pub timestamp: DateTime<Utc>,
-    pub open: f64,
-    pub high: f64,
-    pub low: f64,
-    pub close: f64,
+    pub open: Decimal,
+    pub high: Decimal,
+    pub low: Decimal,
+    pub close: Decimal,
     pub volume: i64,
     pub complete: bool, // true if candle is finalized
 }
 
+impl Candle {
+    /// High minus low
+    pub fn range(&self) -> Decimal {
+        self.high - self.low
+    }
+
+    /// Size of the body, regardless of direction
+    pub fn body(&self) -> Decimal {
+        (self.close - self.open).abs()
+    }
+
+    /// Distance from the top of the body to the high
+    pub fn upper_wick(&self) -> Decimal {
+        self.high - self.open.max(self.close)
+    }
+
+    /// Distance from the bottom of the body to the low
+    pub fn lower_wick(&self) -> Decimal {
+        self.open.min(self.close) - self.low
+    }
+
+    /// Check if the candle closed above its open
+    pub fn is_bullish(&self) -> bool {
+        self.close > self.open
+    }
+
+    /// Check if the candle closed below its open
+    pub fn is_bearish(&self) -> bool {
+        self.close < self.open
+    }
+
+    /// Midpoint of high and low
+    pub fn median_price(&self) -> Decimal {
+        (self.high + self.low) / Decimal::TWO
+    }
+
+    /// Average of high, low and close
+    pub fn typical_price(&self) -> Decimal {
+        (self.high + self.low + self.close) / Decimal::from(3)
+    }
+
+    /// True range, accounting for gaps from the previous close
+    ///
+    /// Without a previous candle this is just the range.
+    pub fn true_range(&self, prev: Option<&Candle>) -> Decimal {
+        match prev {
+            Some(prev) => {
+                let high_gap = (self.high - prev.close).abs();
+                let low_gap = (self.low - prev.close).abs();
+                self.range().max(high_gap).max(low_gap)
+            }
+            None => self.range(),
+        }
+    }
+}
+
 /// Real-time tick/quote
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Tick {
     pub instrument: String,
     pub timestamp: DateTime<Utc>,
-    pub bid: f64,
-    pub ask: f64,
+    pub bid: Decimal,
+    pub ask: Decimal,
+    #[serde(default = "default_tradeable")]
+    pub tradeable: bool, // false while the market is closed or halted
+    #[serde(default)]
+    pub status: PriceStatus,
+    #[serde(default)]
+    pub closeout_bid: Option<Decimal>, // price used for margin closeout of long positions
+    #[serde(default)]
+    pub closeout_ask: Option<Decimal>, // price used for margin closeout of short positions
+}
+
+fn default_tradeable() -> bool {
+    true
+}
+
+/// Trading status of a price
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum PriceStatus {
+    #[default]
+    Tradeable,
+    NonTradeable,
+    Invalid,
+}
+
+impl std::str::FromStr for PriceStatus {
+    type Err = crate::error::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "tradeable" => Ok(PriceStatus::Tradeable),
+            "non-tradeable" => Ok(PriceStatus::NonTradeable),
+            "invalid" => Ok(PriceStatus::Invalid),
+            _ => Err(crate::error::Error::ParseError {
+                field: "status".to_string(),
+                value: s.to_string(),
+            }),
+        }
+    }
 }
 
 impl Tick {
     /// Calculate spread
-    pub fn spread(&self) -> f64 {
+    pub fn spread(&self) -> Decimal {
         self.ask - self.bid
     }
 
     /// Calculate mid price
-    pub fn mid(&self) -> f64 {
-        (self.bid + self.ask) / 2.0
+    pub fn mid(&self) -> Decimal {
+        (self.bid + self.ask) / Decimal::TWO
+    }
+
+    /// Calculate closeout spread, if closeout prices are known
+    ///
+    /// OANDA values positions at closeout prices for margin calls, and these
+    /// are usually wider than the tradeable top of book.
+    pub fn closeout_spread(&self) -> Option<Decimal> {
+        Some(self.closeout_ask? - self.closeout_bid?)
+    }
+
+    /// Spread in pips of the given instrument
+    ///
+    /// 1.5 for a 0.00015 EUR_USD spread and for a 0.015 USD_JPY spread alike.
+    pub fn spread_pips(&self, instrument: &Instrument) -> Decimal {
+        instrument.to_pips(self.spread())
+    }
+
+    /// Bid rounded to the instrument's display precision
+    pub fn display_bid(&self, instrument: &Instrument) -> Decimal {
+        instrument.round_price(self.bid)
+    }
+
+    /// Ask rounded to the instrument's display precision
+    pub fn display_ask(&self, instrument: &Instrument) -> Decimal {
+        instrument.round_price(self.ask)
+    }
+
+    /// Mid price rounded to the instrument's display precision
+    pub fn display_mid(&self, instrument: &Instrument) -> Decimal {
+        instrument.round_price(self.mid())
+    }
+}
+
+/// Single price level on one side of a quote
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct PriceLevel {
+    pub price: Decimal,
+    pub liquidity: Option<i64>, // units available at this price, None if unreported
+}
+
+/// Full-depth quote with every bid/ask liquidity level
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Quote {
+    pub instrument: String,
+    pub timestamp: DateTime<Utc>,
+    pub bids: Vec<PriceLevel>, // best (highest) first
+    pub asks: Vec<PriceLevel>, // best (lowest) first
+    #[serde(default = "default_tradeable")]
+    pub tradeable: bool,
+    #[serde(default)]
+    pub status: PriceStatus,
+    #[serde(default)]
+    pub closeout_bid: Option<Decimal>,
+    #[serde(default)]
+    pub closeout_ask: Option<Decimal>,
+}
+
+impl Quote {
+    /// Best bid level
+    pub fn best_bid(&self) -> Option<&PriceLevel> {
+        self.bids.first()
+    }
+
+    /// Best ask level
+    pub fn best_ask(&self) -> Option<&PriceLevel> {
+        self.asks.first()
+    }
+
+    /// Top-of-book tick for this quote
+    pub fn to_tick(&self) -> Option<Tick> {
+        Some(Tick {
+            instrument: self.instrument.clone(),
+            timestamp: self.timestamp,
+            bid: self.best_bid()?.price,
+            ask: self.best_ask()?.price,
+            tradeable: self.tradeable,
+            status: self.status,
+            closeout_bid: self.closeout_bid,
+            closeout_ask: self.closeout_ask,
+        })
+    }
+
+    /// Estimate the average fill price for an order of `units`
+    ///
+    /// Positive units buy against the asks, negative units sell against the
+    /// bids. Levels are treated as additive depth; a level without reported
+    /// liquidity absorbs the remainder. Returns None if the book is too thin.
+    pub fn average_fill_price(&self, units: i64) -> Option<Decimal> {
+        let levels = if units >= 0 { &self.asks } else { &self.bids };
+        let mut remaining = units.unsigned_abs();
+        if remaining == 0 {
+            return levels.first().map(|l| l.price);
+        }
+
+        let total = Decimal::from(remaining);
+        let mut notional = Decimal::ZERO;
+
+        for level in levels {
+            let take = match level.liquidity {
+                Some(liquidity) => remaining.min(liquidity.max(0) as u64),
+                None => remaining,
+            };
+            notional += Decimal::from(take) * level.price;
+            remaining -= take;
+
+            if remaining == 0 {
+                return Some(notional / total);
+            }
+        }
+
+        None
+    }
+
+    /// Estimate slippage versus top of book for an order of `units`
+    ///
+    /// Always non-negative: the extra price paid (buys) or given up (sells).
+    pub fn estimated_slippage(&self, units: i64) -> Option<Decimal> {
+        let average = self.average_fill_price(units)?;
+        if units >= 0 {
+            Some(average - self.best_ask()?.price)
+        } else {
+            Some(self.best_bid()?.price - average)
+        }
+    }
+}
+
+/// Options for [`get_pricing`](crate::OandaClient::get_pricing)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PricingOptions {
+    /// Only return prices that changed after this time
+    pub since: Option<DateTime<Utc>>,
+    /// Include home currency conversion factors in the response
+    pub include_home_conversions: bool,
+}
+
+/// Factors converting an amount in some currency into the account's home currency
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct HomeConversion {
+    pub currency: String,
+    pub account_gain: Decimal,   // factor for gains (positive amounts)
+    pub account_loss: Decimal,   // factor for losses (negative amounts)
+    pub position_value: Decimal, // factor for position values
+}
+
+impl HomeConversion {
+    /// Convert a P/L amount in `currency` into the home currency
+    pub fn convert_pl(&self, amount: Decimal) -> Decimal {
+        if amount >= Decimal::ZERO {
+            amount * self.account_gain
+        } else {
+            amount * self.account_loss
+        }
     }
 }
 
+/// Result of a pricing request
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PricingSnapshot {
+    pub prices: Vec<Tick>,
+    pub home_conversions: Vec<HomeConversion>, // empty unless requested
+    pub time: Option<DateTime<Utc>>,           // server time, use as the next `since`
+}
+
+impl PricingSnapshot {
+    /// Home conversion factors for a currency
+    pub fn home_conversion(&self, currency: &str) -> Option<&HomeConversion> {
+        self.home_conversions
+            .iter()
+            .find(|c| c.currency == currency)
+    }
+}
+
+/// Round-trip time and clock skew measured against the API
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LatencyReport {
+    pub probes: usize,
+    pub min_rtt: std::time::Duration,
+    pub median_rtt: std::time::Duration,
+    pub max_rtt: std::time::Duration,
+    /// Server clock minus local clock, estimated from the fastest probe
+    /// (None if the API didn't report its time)
+    pub clock_skew: Option<chrono::Duration>,
+}
+
 /// Time granularity for candles
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
 pub enum Granularity {
@@ -90,6 +366,213 @@ impl Granularity {
             Granularity::M => 2592000, // Approximate
         }
     }
+
+    /// Get duration as a chrono Duration (approximate for monthly candles)
+    pub fn to_duration(&self) -> chrono::Duration {
+        chrono::Duration::seconds(self.duration_seconds() as i64)
+    }
+
+    /// Start of the candle containing `time`, using OANDA's default alignment
+    pub fn align_down(&self, time: DateTime<Utc>) -> DateTime<Utc> {
+        self.align_down_with(time, &CandleAlignment::default())
+    }
+
+    /// Start of the candle following the one containing `time`
+    pub fn next_boundary(&self, time: DateTime<Utc>) -> DateTime<Utc> {
+        self.next_boundary_with(time, &CandleAlignment::default())
+    }
+
+    /// Start of the candle containing `time` under a custom alignment
+    pub fn align_down_with(
+        &self,
+        time: DateTime<Utc>,
+        alignment: &CandleAlignment,
+    ) -> DateTime<Utc> {
+        match self {
+            Granularity::H4 => {
+                let day_start = alignment.day_start(time);
+                let elapsed = (alignment.local_time(time) - alignment.local_time(day_start))
+                    .num_seconds()
+                    .max(0);
+                let hours = elapsed / 3600 / 4 * 4;
+                alignment
+                    .instant_at(alignment.local_time(day_start) + chrono::Duration::hours(hours))
+            }
+            Granularity::D => alignment.day_start(time),
+            Granularity::W => alignment.week_start(time),
+            Granularity::M => alignment.month_start(time),
+            // Sub-hourly and hourly candles don't depend on the time zone
+            _ => {
+                let seconds = self.duration_seconds() as i64;
+                let timestamp = time.timestamp().div_euclid(seconds) * seconds;
+                DateTime::from_timestamp(timestamp, 0).unwrap_or(time)
+            }
+        }
+    }
+
+    /// Start of the candle following the one containing `time` under a custom alignment
+    pub fn next_boundary_with(
+        &self,
+        time: DateTime<Utc>,
+        alignment: &CandleAlignment,
+    ) -> DateTime<Utc> {
+        let start = self.align_down_with(time, alignment);
+        // Overshoot by enough to absorb DST shifts and month lengths, then
+        // snap back onto the boundary
+        let slack = match self {
+            Granularity::H4 | Granularity::D | Granularity::W => chrono::Duration::hours(1),
+            Granularity::M => chrono::Duration::days(2),
+            _ => return start + self.to_duration(),
+        };
+        self.align_down_with(start + self.to_duration() + slack, alignment)
+    }
+}
+
+/// Time zone candles are aligned in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlignmentTimezone {
+    Utc,
+    AmericaNewYork, // OANDA's default, observes US daylight saving time
+}
+
+/// How daily, weekly and monthly candles are aligned
+///
+/// Mirrors the `dailyAlignment`, `alignmentTimezone` and `weeklyAlignment`
+/// candle request parameters. The default matches OANDA's: days start at
+/// 17:00 New York time and weeks start on Friday.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CandleAlignment {
+    pub daily_alignment: u32, // hour of day, 0-23
+    pub timezone: AlignmentTimezone,
+    pub weekly_alignment: chrono::Weekday,
+}
+
+impl Default for CandleAlignment {
+    fn default() -> Self {
+        Self {
+            daily_alignment: 17,
+            timezone: AlignmentTimezone::AmericaNewYork,
+            weekly_alignment: chrono::Weekday::Fri,
+        }
+    }
+}
+
+impl CandleAlignment {
+    /// UTC candle alignment at the given hour
+    pub fn utc(daily_alignment: u32) -> Self {
+        Self {
+            daily_alignment: daily_alignment % 24,
+            timezone: AlignmentTimezone::Utc,
+            weekly_alignment: chrono::Weekday::Fri,
+        }
+    }
+
+    /// Offset of the alignment time zone from UTC at an instant
+    pub(crate) fn utc_offset(&self, time: DateTime<Utc>) -> chrono::Duration {
+        use chrono::{Datelike, NaiveDate, Weekday};
+
+        match self.timezone {
+            AlignmentTimezone::Utc => chrono::Duration::zero(),
+            AlignmentTimezone::AmericaNewYork => {
+                // DST runs from 02:00 local on the second Sunday of March
+                // until 02:00 local on the first Sunday of November
+                let year = time.year();
+                let sunday = |month, n| {
+                    NaiveDate::from_weekday_of_month_opt(year, month, Weekday::Sun, n)
+                        .and_then(|d| d.and_hms_opt(0, 0, 0))
+                        .map(|d| d.and_utc())
+                };
+                let dst = match (sunday(3, 2), sunday(11, 1)) {
+                    (Some(start), Some(end)) => {
+                        time >= start + chrono::Duration::hours(7)
+                            && time < end + chrono::Duration::hours(6)
+                    }
+                    _ => false,
+                };
+                chrono::Duration::hours(if dst { -4 } else { -5 })
+            }
+        }
+    }
+
+    /// Wall-clock time in the alignment time zone
+    fn local_time(&self, time: DateTime<Utc>) -> chrono::NaiveDateTime {
+        (time + self.utc_offset(time)).naive_utc()
+    }
+
+    /// Instant of a wall-clock time in the alignment time zone
+    fn instant_at(&self, local: chrono::NaiveDateTime) -> DateTime<Utc> {
+        let guess = local.and_utc() - self.utc_offset(local.and_utc());
+        local.and_utc() - self.utc_offset(guess)
+    }
+
+    /// Alignment instant on a local date
+    pub(crate) fn at_alignment(&self, date: chrono::NaiveDate) -> DateTime<Utc> {
+        let hour = self.daily_alignment.min(23);
+        self.instant_at(date.and_hms_opt(hour, 0, 0).unwrap_or_default())
+    }
+
+    /// Most recent daily alignment instant at or before `time`
+    pub(crate) fn day_start(&self, time: DateTime<Utc>) -> DateTime<Utc> {
+        let date = self.local_time(time).date();
+        let start = self.at_alignment(date);
+        if start > time {
+            self.at_alignment(date - chrono::Duration::days(1))
+        } else {
+            start
+        }
+    }
+
+    /// Most recent weekly alignment instant at or before `time`
+    fn week_start(&self, time: DateTime<Utc>) -> DateTime<Utc> {
+        use chrono::Datelike;
+
+        let day_start = self.day_start(time);
+        let date = self.local_time(day_start).date();
+        let days_back = (date.weekday().num_days_from_monday() + 7
+            - self.weekly_alignment.num_days_from_monday())
+            % 7;
+        self.at_alignment(date - chrono::Duration::days(days_back as i64))
+    }
+
+    /// Start of the month containing `time`
+    ///
+    /// A month starts at the daily alignment instant closest to local
+    /// midnight on its first day, so with the default 17:00 alignment it
+    /// begins the evening before.
+    fn month_start(&self, time: DateTime<Utc>) -> DateTime<Utc> {
+        use chrono::{Datelike, NaiveDate};
+
+        let boundary = |year: i32, month: u32| {
+            let first = NaiveDate::from_ymd_opt(year, month, 1).unwrap_or_default();
+            if self.daily_alignment >= 12 {
+                self.at_alignment(first - chrono::Duration::days(1))
+            } else {
+                self.at_alignment(first)
+            }
+        };
+
+        // The next month may already have started late on the last day
+        let local = self.local_time(time).date();
+        let (next_year, next_month) = if local.month() == 12 {
+            (local.year() + 1, 1)
+        } else {
+            (local.year(), local.month() + 1)
+        };
+        let (prev_year, prev_month) = if local.month() == 1 {
+            (local.year() - 1, 12)
+        } else {
+            (local.year(), local.month() - 1)
+        };
+
+        [
+            boundary(next_year, next_month),
+            boundary(local.year(), local.month()),
+            boundary(prev_year, prev_month),
+        ]
+        .into_iter()
+        .find(|start| *start <= time)
+        .unwrap_or(time)
+    }
 }
 
 impl std::fmt::Display for Granularity {
@@ -138,31 +621,349 @@ impl std::str::FromStr for Granularity {
     }
 }
 
+/// Price bucket of an order or position book
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct BookBucket {
+    pub price: Decimal,
+    pub long_count_percent: f64,
+    pub short_count_percent: f64,
+}
+
+impl BookBucket {
+    /// Long minus short percentage (positive means long-heavy)
+    pub fn net_percent(&self) -> f64 {
+        self.long_count_percent - self.short_count_percent
+    }
+}
+
+/// Aggregated OANDA order book snapshot for an instrument
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct OrderBook {
+    pub instrument: String,
+    pub time: DateTime<Utc>,
+    pub price: Decimal, // instrument price when the snapshot was taken
+    pub bucket_width: Decimal,
+    pub buckets: Vec<BookBucket>, // ascending by price
+}
+
+impl OrderBook {
+    /// Bucket containing a price
+    pub fn bucket_for_price(&self, price: Decimal) -> Option<&BookBucket> {
+        self.buckets
+            .iter()
+            .find(|b| price >= b.price && price < b.price + self.bucket_width)
+    }
+
+    /// Buckets at or above the snapshot price
+    pub fn buckets_above(&self) -> impl Iterator<Item = &BookBucket> {
+        self.buckets.iter().filter(move |b| b.price >= self.price)
+    }
+
+    /// Buckets below the snapshot price
+    pub fn buckets_below(&self) -> impl Iterator<Item = &BookBucket> {
+        self.buckets.iter().filter(move |b| b.price < self.price)
+    }
+}
+
+/// Aggregated OANDA position book snapshot for an instrument
+///
+/// Shows where open retail positions sit relative to the current price.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PositionBook {
+    pub instrument: String,
+    pub time: DateTime<Utc>,
+    pub price: Decimal, // instrument price when the snapshot was taken
+    pub bucket_width: Decimal,
+    pub buckets: Vec<BookBucket>, // ascending by price
+}
+
+impl PositionBook {
+    /// Bucket containing a price
+    pub fn bucket_for_price(&self, price: Decimal) -> Option<&BookBucket> {
+        self.buckets
+            .iter()
+            .find(|b| price >= b.price && price < b.price + self.bucket_width)
+    }
+
+    /// Share of positions that are long across all buckets
+    pub fn total_long_percent(&self) -> f64 {
+        self.buckets.iter().map(|b| b.long_count_percent).sum()
+    }
+
+    /// Share of positions that are short across all buckets
+    pub fn total_short_percent(&self) -> f64 {
+        self.buckets.iter().map(|b| b.short_count_percent).sum()
+    }
+}
+
 /// Account summary information
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AccountSummary {
     pub id: String,
-    pub balance: f64,
-    pub nav: f64, // Net Asset Value
-    pub unrealized_pl: f64,
-    pub realized_pl: f64,
-    pub margin_used: f64,
-    pub margin_available: f64,
+    pub balance: Decimal,
+    pub nav: Decimal, // Net Asset Value
+    pub unrealized_pl: Decimal,
+    pub realized_pl: Decimal,
+    pub margin_used: Decimal,
+    pub margin_available: Decimal,
     pub open_trade_count: i32,
     pub open_position_count: i32,
     pub currency: String,
+    /// Whether long and short trades in one instrument are kept apart
+    #[serde(default)]
+    pub hedging_enabled: bool,
+}
+
+impl AccountSummary {
+    pub fn position_mode(&self) -> PositionMode {
+        if self.hedging_enabled {
+            PositionMode::Hedging
+        } else {
+            PositionMode::Netting
+        }
+    }
+}
+
+/// How an account combines trades in one instrument
+///
+/// On a netting account an order against the position reduces it. On a
+/// hedging account it opens an opposing trade instead, unless placed with
+/// `PositionFill::ReduceOnly`, and long and short trades coexist.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PositionMode {
+    #[default]
+    Netting,
+    Hedging,
+}
+
+/// Open trade
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Trade {
+    pub id: crate::ids::TradeId,
+    pub instrument: String,
+    #[serde(with = "crate::decimal_number")]
+    pub price: Decimal, // average fill price
+    #[serde(with = "crate::datetime")]
+    pub open_time: DateTime<Utc>,
+    #[serde(with = "crate::decimal_number")]
+    pub initial_units: Decimal,
+    #[serde(with = "crate::decimal_number")]
+    pub current_units: Decimal, // negative for shorts
+    #[serde(rename = "realizedPL", default, with = "crate::decimal_number::option")]
+    pub realized_pl: Option<Decimal>,
+    #[serde(rename = "unrealizedPL", default, with = "crate::decimal_number::option")]
+    pub unrealized_pl: Option<Decimal>,
+    #[serde(default, with = "crate::decimal_number::option")]
+    pub margin_used: Option<Decimal>,
+}
+
+impl Trade {
+    pub fn is_long(&self) -> bool {
+        self.current_units > Decimal::ZERO
+    }
+}
+
+/// Long and short positions held in one instrument
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Position {
+    pub instrument: String,
+    #[serde(with = "crate::decimal_number")]
+    pub pl: Decimal, // realized over the position's lifetime
+    #[serde(rename = "unrealizedPL", default, with = "crate::decimal_number::option")]
+    pub unrealized_pl: Option<Decimal>,
+    #[serde(default, with = "crate::decimal_number::option")]
+    pub margin_used: Option<Decimal>,
+    pub long: PositionSide,
+    pub short: PositionSide,
+}
+
+impl Position {
+    /// Long units plus (negative) short units
+    pub fn net_units(&self) -> Decimal {
+        self.long.units + self.short.units
+    }
+}
+
+/// One side of a position
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PositionSide {
+    #[serde(with = "crate::decimal_number")]
+    pub units: Decimal, // negative on the short side
+    #[serde(default, with = "crate::decimal_number::option")]
+    pub average_price: Option<Decimal>, // absent when no units are held
+    #[serde(rename = "tradeIDs", default)]
+    pub trade_ids: Vec<crate::ids::TradeId>,
+    #[serde(rename = "unrealizedPL", default, with = "crate::decimal_number::option")]
+    pub unrealized_pl: Option<Decimal>,
 }
 
 /// Instrument information
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct Instrument {
     pub name: String,
     pub display_name: String,
     pub pip_location: i32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display_precision: Option<u32>, // decimal places prices are quoted with
     pub trade_units_precision: i32,
-    pub minimum_trade_size: f64,
-    pub maximum_trade_size: f64,
-    pub margin_rate: f64,
+    pub minimum_trade_size: Decimal,
+    #[serde(default, alias = "maximumOrderUnits")]
+    pub maximum_trade_size: Decimal, // reported by OANDA as maximumOrderUnits
+    pub margin_rate: Decimal,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub maximum_position_size: Option<Decimal>, // zero or None means no limit
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub minimum_trailing_stop_distance: Option<Decimal>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub guaranteed_stop_loss_order_mode: Option<GuaranteedStopLossOrderMode>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<InstrumentTag>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub financing: Option<InstrumentFinancing>,
+}
+
+/// Whether guaranteed stop loss orders may be used on an instrument
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum GuaranteedStopLossOrderMode {
+    Disabled,
+    Allowed,
+    Required,
+}
+
+/// Tag attached to an instrument, e.g. `{"type": "ASSET_CLASS", "name": "CURRENCY"}`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct InstrumentTag {
+    #[serde(rename = "type")]
+    pub tag_type: String,
+    pub name: String,
+}
+
+/// Financing rates charged or paid on open positions
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct InstrumentFinancing {
+    pub long_rate: Decimal,  // annual rate for long positions
+    pub short_rate: Decimal, // annual rate for short positions
+    #[serde(default)]
+    pub financing_days_of_week: Vec<FinancingDayOfWeek>,
+}
+
+/// Number of days' financing charged on a given weekday
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct FinancingDayOfWeek {
+    pub day_of_week: String, // e.g. "WEDNESDAY"
+    pub days_charged: i32,
+}
+
+impl InstrumentFinancing {
+    /// Days of financing charged on a weekday (0 if not listed)
+    pub fn days_charged(&self, day_of_week: &str) -> i32 {
+        self.financing_days_of_week
+            .iter()
+            .find(|d| d.day_of_week.eq_ignore_ascii_case(day_of_week))
+            .map(|d| d.days_charged)
+            .unwrap_or(0)
+    }
+}
+
+impl Instrument {
+    /// Base currency or asset (before the underscore in the name)
+    pub fn base_currency(&self) -> &str {
+        self.name
+            .split_once('_')
+            .map(|(base, _)| base)
+            .unwrap_or(&self.name)
+    }
+
+    /// Quote currency (after the underscore in the name)
+    pub fn quote_currency(&self) -> &str {
+        self.name
+            .split_once('_')
+            .map(|(_, quote)| quote)
+            .unwrap_or("")
+    }
+
+    /// Size of one pip in price terms, i.e. 10^pip_location
+    ///
+    /// 0.0001 for EUR_USD, 0.01 for USD_JPY.
+    pub fn pip_size(&self) -> Decimal {
+        if self.pip_location < 0 {
+            Decimal::new(1, self.pip_location.unsigned_abs())
+        } else {
+            Decimal::from(10u64.pow(self.pip_location as u32))
+        }
+    }
+
+    /// Decimal places prices are displayed with
+    ///
+    /// Falls back to one digit past the pip (fractional pips) when the API
+    /// didn't report a display precision.
+    pub fn price_precision(&self) -> u32 {
+        self.display_precision
+            .unwrap_or_else(|| (1 - self.pip_location).max(0) as u32)
+    }
+
+    /// Round a price to the instrument's display precision
+    pub fn round_price(&self, price: Decimal) -> Decimal {
+        price.round_dp_with_strategy(
+            self.price_precision(),
+            rust_decimal::RoundingStrategy::MidpointAwayFromZero,
+        )
+    }
+
+    /// Express a price difference in pips
+    pub fn to_pips(&self, price_difference: Decimal) -> Decimal {
+        price_difference / self.pip_size()
+    }
+
+    /// Value of a one-pip move for `units`, in the quote currency
+    pub fn pip_value_in_quote(&self, units: Decimal) -> Decimal {
+        units.abs() * self.pip_size()
+    }
+
+    /// Value of a one-pip move for `units`, in the account currency
+    ///
+    /// `quote_price` is the instrument's current price. Only works when the
+    /// account currency is the base or quote currency of the instrument;
+    /// returns None otherwise, see
+    /// [`pip_value_with_conversion`](Self::pip_value_with_conversion).
+    pub fn pip_value(
+        &self,
+        units: Decimal,
+        quote_price: Decimal,
+        account_ccy: &str,
+    ) -> Option<Decimal> {
+        let in_quote = self.pip_value_in_quote(units);
+        if account_ccy == self.quote_currency() {
+            Some(in_quote)
+        } else if account_ccy == self.base_currency() {
+            in_quote.checked_div(quote_price)
+        } else {
+            None
+        }
+    }
+
+    /// Value of a one-pip move for `units`, in the account currency
+    ///
+    /// Uses the home conversion factors for the quote currency, as returned
+    /// by [`get_pricing`](crate::OandaClient::get_pricing), so it works for
+    /// any instrument. Returns None if the factors are for another currency.
+    pub fn pip_value_with_conversion(
+        &self,
+        units: Decimal,
+        conversion: &HomeConversion,
+    ) -> Option<Decimal> {
+        if conversion.currency != self.quote_currency() {
+            return None;
+        }
+        Some(self.pip_value_in_quote(units) * conversion.position_value)
+    }
 }
 
 /// Internal OANDA API response structures
@@ -175,7 +976,8 @@ pub(crate) struct CandlesResponse {
 
 #[derive(Debug, Deserialize)]
 pub(crate) struct OandaCandle {
-    pub time: String,
+    #[serde(with = "crate::datetime")]
+    pub time: DateTime<Utc>,
     pub volume: i64,
     pub complete: bool,
     pub mid: Option<OandaPriceData>,
@@ -185,75 +987,193 @@ pub(crate) struct OandaCandle {
 
 #[derive(Debug, Deserialize)]
 pub(crate) struct OandaPriceData {
-    pub o: String,
-    pub h: String,
-    pub l: String,
-    pub c: String,
+    #[serde(with = "crate::decimal_number")]
+    pub o: Decimal,
+    #[serde(with = "crate::decimal_number")]
+    pub h: Decimal,
+    #[serde(with = "crate::decimal_number")]
+    pub l: Decimal,
+    #[serde(with = "crate::decimal_number")]
+    pub c: Decimal,
 }
 
 #[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub(crate) struct PricingResponse {
     pub prices: Vec<OandaPrice>,
+    #[serde(default)]
+    pub home_conversions: Vec<OandaHomeConversion>,
+    #[serde(default, with = "crate::datetime::option")]
+    pub time: Option<DateTime<Utc>>,
+}
+
+/// One line of the pricing stream
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type")]
+pub(crate) enum PricingStreamMessage {
+    #[serde(rename = "PRICE")]
+    Price(OandaPrice),
+    /// Heartbeats, sent every few seconds while prices are quiet
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct OandaHomeConversion {
+    pub currency: String,
+    #[serde(with = "crate::decimal_number")]
+    pub account_gain: Decimal,
+    #[serde(with = "crate::decimal_number")]
+    pub account_loss: Decimal,
+    #[serde(with = "crate::decimal_number")]
+    pub position_value: Decimal,
 }
 
 #[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub(crate) struct OandaPrice {
     pub instrument: String,
-    pub time: String,
-    pub bids: Vec<PriceLevel>,
-    pub asks: Vec<PriceLevel>,
+    #[serde(with = "crate::datetime")]
+    pub time: DateTime<Utc>,
+    pub bids: Vec<OandaPriceBucket>,
+    pub asks: Vec<OandaPriceBucket>,
+    pub tradeable: Option<bool>,
+    pub status: Option<String>,
+    #[serde(default, with = "crate::decimal_number::option")]
+    pub closeout_bid: Option<Decimal>,
+    #[serde(default, with = "crate::decimal_number::option")]
+    pub closeout_ask: Option<Decimal>,
 }
 
 #[derive(Debug, Deserialize)]
-pub(crate) struct PriceLevel {
-    pub price: String,
+pub(crate) struct OandaPriceBucket {
+    #[serde(with = "crate::decimal_number")]
+    pub price: Decimal,
     pub liquidity: Option<i64>,
 }
 
 #[derive(Debug, Deserialize)]
 pub(crate) struct AccountResponse {
     pub account: OandaAccount,
+    #[serde(rename = "lastTransactionID", default)]
+    pub last_transaction_id: Option<crate::ids::TransactionId>,
 }
 
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub(crate) struct OandaAccount {
     pub id: String,
-    pub balance: String,
-    pub nav: String,
-    pub unrealized_pl: String,
-    pub realized_pl: String,
-    pub margin_used: String,
-    pub margin_available: String,
+    #[serde(with = "crate::decimal_number")]
+    pub balance: Decimal,
+    #[serde(rename = "NAV", with = "crate::decimal_number")]
+    pub nav: Decimal,
+    #[serde(rename = "unrealizedPL", with = "crate::decimal_number")]
+    pub unrealized_pl: Decimal,
+    #[serde(rename = "pl", with = "crate::decimal_number")]
+    pub realized_pl: Decimal,
+    #[serde(with = "crate::decimal_number")]
+    pub margin_used: Decimal,
+    #[serde(with = "crate::decimal_number")]
+    pub margin_available: Decimal,
     pub open_trade_count: i32,
     pub open_position_count: i32,
     pub currency: String,
+    #[serde(default)]
+    pub hedging_enabled: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct OpenTradesResponse {
+    pub trades: Vec<Trade>,
+    #[serde(rename = "lastTransactionID", default)]
+    pub last_transaction_id: Option<crate::ids::TransactionId>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct OpenPositionsResponse {
+    pub positions: Vec<Position>,
+    #[serde(rename = "lastTransactionID", default)]
+    pub last_transaction_id: Option<crate::ids::TransactionId>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct TransactionsResponse {
+    pub transactions: Vec<crate::transactions::Transaction>,
+    #[serde(rename = "lastTransactionID", default)]
+    pub last_transaction_id: Option<crate::ids::TransactionId>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct TransactionPagesResponse {
+    pub pages: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct OrderBookResponse {
+    pub order_book: OandaBook,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct OandaBook {
+    pub instrument: String,
+    #[serde(with = "crate::datetime")]
+    pub time: DateTime<Utc>,
+    #[serde(with = "crate::decimal_number")]
+    pub price: Decimal,
+    #[serde(with = "crate::decimal_number")]
+    pub bucket_width: Decimal,
+    pub buckets: Vec<OandaBookBucket>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct OandaBookBucket {
+    #[serde(with = "crate::decimal_number")]
+    pub price: Decimal,
+    #[serde(with = "crate::decimal_number")]
+    pub long_count_percent: f64,
+    #[serde(with = "crate::decimal_number")]
+    pub short_count_percent: f64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct PositionBookResponse {
+    pub position_book: OandaBook,
+}
+
+impl CandlesResponse {
+    /// Convert to our Candle type
+    pub(crate) fn into_candles(self) -> crate::Result<Vec<Candle>> {
+        self.candles
+            .iter()
+            .map(|c| c.to_candle(&self.instrument, &self.granularity))
+            .collect()
+    }
 }
 
 impl OandaCandle {
     /// Convert to our Candle type
-    pub(crate) fn to_candle(&self, instrument: String) -> crate::Result<Candle> {
+    pub(crate) fn to_candle(&self, instrument: &str, granularity: &str) -> crate::Result<Candle> {
         let price_data =
             self.mid
                 .as_ref()
                 .or(self.bid.as_ref())
-                .ok_or_else(|| crate::Error::ApiError {
-                    code: 0,
-                    message: format!("No price data in candle."),
-                })?;
+                .or(self.ask.as_ref())
+                .ok_or_else(|| crate::Error::SchemaMismatch(
+                    format!("No price data in {} {} candle at {}.", instrument, granularity, self.time),
+                ))?;
 
         Ok(Candle {
-            instrument,
-            timestamp: DateTime::parse_from_rfc3339(&self.time)
-                .map_err(|e| crate::Error::ApiError {
-                    code: 0,
-                    message: format!("Failed to parse datetime: {}", e),
-                })?
-                .with_timezone(&Utc),
-            open: price_data.o.parse().unwrap_or(0.0),
-            high: price_data.h.parse().unwrap_or(0.0),
-            low: price_data.l.parse().unwrap_or(0.0),
-            close: price_data.c.parse().unwrap_or(0.0),
+            instrument: instrument.to_string(),
+            timestamp: self.time,
+            open: price_data.o,
+            high: price_data.h,
+            low: price_data.l,
+            close: price_data.c,
             volume: self.volume,
             complete: self.complete,
         })
@@ -263,56 +1183,136 @@ impl OandaCandle {
 impl OandaPrice {
     /// Convert to our Tick type
     pub(crate) fn to_tick(&self) -> crate::Result<Tick> {
-        let bid = self
-            .bids
-            .first()
-            .ok_or_else(|| crate::Error::ApiError {
-                code: 0,
-                message: format!("No bid data."),
-            })?
-            .price
-            .parse()
-            .unwrap_or(0.0);
-
-        let ask = self
-            .asks
-            .first()
-            .ok_or_else(|| crate::Error::ApiError {
-                code: 0,
-                message: format!("No ask data."),
-            })?
-            .price
-            .parse()
-            .unwrap_or(0.0);
+        let bid = self.bids.first().ok_or_else(|| {
+            crate::Error::SchemaMismatch(format!("No bid data for {}.", self.instrument))
+        })?;
+        let bid = bid.price;
+
+        let ask = self.asks.first().ok_or_else(|| {
+            crate::Error::SchemaMismatch(format!("No ask data for {}.", self.instrument))
+        })?;
+        let ask = ask.price;
 
         Ok(Tick {
             instrument: self.instrument.clone(),
-            timestamp: DateTime::parse_from_rfc3339(&self.time)
-                .map_err(|e| crate::Error::ApiError {
-                    code: 0,
-                    message: format!("Invalid timestamp: {}", e),
-                })?
-                .with_timezone(&Utc),
+            timestamp: self.time,
             bid,
             ask,
+            tradeable: self.is_tradeable(),
+            status: self.price_status(),
+            closeout_bid: self.closeout_bid,
+            closeout_ask: self.closeout_ask,
+        })
+    }
+}
+
+impl OandaPrice {
+    /// Tradeable flag, falling back to the deprecated status field
+    fn is_tradeable(&self) -> bool {
+        self.tradeable
+            .unwrap_or(self.price_status() == PriceStatus::Tradeable)
+    }
+
+    /// Price status, derived from the tradeable flag when not reported
+    fn price_status(&self) -> PriceStatus {
+        match self.status.as_deref().and_then(|s| s.parse().ok()) {
+            Some(status) => status,
+            None if self.tradeable == Some(false) => PriceStatus::NonTradeable,
+            None => PriceStatus::Tradeable,
+        }
+    }
+
+    /// Convert to our full-depth Quote type
+    pub(crate) fn to_quote(&self) -> crate::Result<Quote> {
+        let levels = |buckets: &[OandaPriceBucket]| {
+            buckets
+                .iter()
+                .map(|b| PriceLevel {
+                    price: b.price,
+                    liquidity: b.liquidity,
+                })
+                .collect::<Vec<_>>()
+        };
+
+        Ok(Quote {
+            instrument: self.instrument.clone(),
+            timestamp: self.time,
+            bids: levels(&self.bids),
+            asks: levels(&self.asks),
+            tradeable: self.is_tradeable(),
+            status: self.price_status(),
+            closeout_bid: self.closeout_bid,
+            closeout_ask: self.closeout_ask,
+        })
+    }
+}
+
+impl OandaBook {
+    /// Convert to our OrderBook type
+    pub(crate) fn to_order_book(&self) -> crate::Result<OrderBook> {
+        Ok(OrderBook {
+            instrument: self.instrument.clone(),
+            time: self.time,
+            price: self.price,
+            bucket_width: self.bucket_width,
+            buckets: self.buckets.iter().map(|b| b.to_bucket()).collect(),
         })
     }
 }
 
+impl OandaBook {
+    /// Convert to our PositionBook type
+    pub(crate) fn to_position_book(&self) -> crate::Result<PositionBook> {
+        let book = self.to_order_book()?;
+
+        Ok(PositionBook {
+            instrument: book.instrument,
+            time: book.time,
+            price: book.price,
+            bucket_width: book.bucket_width,
+            buckets: book.buckets,
+        })
+    }
+}
+
+impl OandaBookBucket {
+    /// Convert to our BookBucket type
+    pub(crate) fn to_bucket(&self) -> BookBucket {
+        BookBucket {
+            price: self.price,
+            long_count_percent: self.long_count_percent,
+            short_count_percent: self.short_count_percent,
+        }
+    }
+}
+
+impl OandaHomeConversion {
+    /// Convert to our HomeConversion type
+    pub(crate) fn to_home_conversion(&self) -> HomeConversion {
+        HomeConversion {
+            currency: self.currency.clone(),
+            account_gain: self.account_gain,
+            account_loss: self.account_loss,
+            position_value: self.position_value,
+        }
+    }
+}
+
 impl OandaAccount {
     /// Convert to our AccountSummary type
     pub(crate) fn to_summary(&self) -> AccountSummary {
         AccountSummary {
             id: self.id.clone(),
-            balance: self.balance.parse().unwrap_or(0.0),
-            nav: self.nav.parse().unwrap_or(0.0),
-            unrealized_pl: self.unrealized_pl.parse().unwrap_or(0.0),
-            realized_pl: self.realized_pl.parse().unwrap_or(0.0),
-            margin_used: self.margin_used.parse().unwrap_or(0.0),
-            margin_available: self.margin_available.parse().unwrap_or(0.0),
+            balance: self.balance,
+            nav: self.nav,
+            unrealized_pl: self.unrealized_pl,
+            realized_pl: self.realized_pl,
+            margin_used: self.margin_used,
+            margin_available: self.margin_available,
             open_trade_count: self.open_trade_count,
             open_position_count: self.open_position_count,
             currency: self.currency.clone(),
+            hedging_enabled: self.hedging_enabled,
         }
     }
 }
@@ -320,18 +1320,24 @@ impl OandaAccount {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use rust_decimal_macros::dec;
 
     #[test]
     fn test_tick_spread() {
         let tick = Tick {
             instrument: "EUR_USD".to_string(),
             timestamp: Utc::now(),
-            bid: 1.1000,
-            ask: 1.1002,
+            bid: dec!(1.1000),
+            ask: dec!(1.1002),
+            tradeable: true,
+            status: PriceStatus::Tradeable,
+            closeout_bid: Some(dec!(1.0999)),
+            closeout_ask: Some(dec!(1.1003)),
         };
 
-        assert!((tick.spread() - 0.0002).abs() < f64::EPSILON);
-        assert!((tick.mid() - 1.1001).abs() < f64::EPSILON);
+        assert_eq!(tick.spread(), dec!(0.0002));
+        assert_eq!(tick.mid(), dec!(1.1001));
+        assert_eq!(tick.closeout_spread(), Some(dec!(0.0004)));
     }
 
     #[test]
@@ -339,14 +1345,17 @@ mod tests {
         let tick = Tick {
             instrument: "USD_JPY".to_string(),
             timestamp: Utc::now(),
-            bid: 110.50,
-            ask: 110.52,
+            bid: dec!(110.50),
+            ask: dec!(110.52),
+            tradeable: true,
+            status: PriceStatus::Tradeable,
+            closeout_bid: None,
+            closeout_ask: None,
         };
-        const FLOAT_TOLERANCE: f64 = 1e-10;
 
         assert_eq!(tick.instrument, "USD_JPY");
-        assert!((tick.spread() - 0.02).abs() < FLOAT_TOLERANCE);
-        assert!((tick.mid() - 110.51).abs() < FLOAT_TOLERANCE);
+        assert_eq!(tick.spread(), dec!(0.02));
+        assert_eq!(tick.mid(), dec!(110.51));
     }
 
     #[test]
@@ -356,6 +1365,15 @@ mod tests {
         assert!("INVALID".parse::<Granularity>().is_err());
     }
 
+    #[test]
+    fn test_price_status_from_str() {
+        assert_eq!("non-tradeable".parse::<PriceStatus>().unwrap(), PriceStatus::NonTradeable);
+        assert!(matches!(
+            "halted".parse::<PriceStatus>(),
+            Err(crate::Error::ParseError { field, value }) if field == "status" && value == "halted"
+        ));
+    }
+
     #[test]
     fn test_granularity_duration() {
         assert_eq!(Granularity::M5.duration_seconds(), 300);
@@ -376,10 +1394,10 @@ mod tests {
         let candle = Candle {
             instrument: "GBP_USD".to_string(),
             timestamp: Utc::now(),
-            open: 1.3000,
-            high: 1.3010,
-            low: 1.2990,
-            close: 1.3005,
+            open: dec!(1.3000),
+            high: dec!(1.3010),
+            low: dec!(1.2990),
+            close: dec!(1.3005),
             volume: 100,
             complete: true,
         };
@@ -388,4 +1406,483 @@ mod tests {
         assert!(candle.high >= candle.low);
         assert!(candle.complete);
     }
+
+    #[test]
+    fn test_candle_analytics() {
+        let candle = Candle {
+            instrument: "GBP_USD".to_string(),
+            timestamp: Utc::now(),
+            open: dec!(1.3000),
+            high: dec!(1.3020),
+            low: dec!(1.2980),
+            close: dec!(1.2990),
+            volume: 100,
+            complete: true,
+        };
+
+        assert_eq!(candle.range(), dec!(0.0040));
+        assert_eq!(candle.body(), dec!(0.0010));
+        assert_eq!(candle.upper_wick(), dec!(0.0020));
+        assert_eq!(candle.lower_wick(), dec!(0.0010));
+        assert!(candle.is_bearish());
+        assert!(!candle.is_bullish());
+        assert_eq!(candle.median_price(), dec!(1.3000));
+        assert_eq!(candle.typical_price().round_dp(6), dec!(1.299667));
+
+        // Gap up from the previous close widens the true range
+        let prev = Candle {
+            close: dec!(1.2900),
+            ..candle.clone()
+        };
+        assert_eq!(candle.true_range(None), dec!(0.0040));
+        assert_eq!(candle.true_range(Some(&prev)), dec!(0.0120));
+    }
+
+    fn depth_quote() -> Quote {
+        Quote {
+            instrument: "EUR_USD".to_string(),
+            timestamp: Utc::now(),
+            bids: vec![
+                PriceLevel {
+                    price: dec!(1.1000),
+                    liquidity: Some(1_000_000),
+                },
+                PriceLevel {
+                    price: dec!(1.0999),
+                    liquidity: Some(2_000_000),
+                },
+            ],
+            asks: vec![
+                PriceLevel {
+                    price: dec!(1.1002),
+                    liquidity: Some(1_000_000),
+                },
+                PriceLevel {
+                    price: dec!(1.1004),
+                    liquidity: Some(2_000_000),
+                },
+            ],
+            tradeable: true,
+            status: PriceStatus::Tradeable,
+            closeout_bid: None,
+            closeout_ask: None,
+        }
+    }
+
+    #[test]
+    fn test_quote_top_of_book() {
+        let quote = depth_quote();
+        let tick = quote.to_tick().unwrap();
+
+        assert_eq!(tick.bid, dec!(1.1000));
+        assert_eq!(tick.ask, dec!(1.1002));
+    }
+
+    #[test]
+    fn test_quote_average_fill_price() {
+        let quote = depth_quote();
+
+        // Fits in the first level
+        assert_eq!(quote.average_fill_price(500_000), Some(dec!(1.1002)));
+        assert_eq!(quote.estimated_slippage(500_000), Some(Decimal::ZERO));
+
+        // Buy 2M: 1M @ 1.1002 + 1M @ 1.1004
+        let average = quote.average_fill_price(2_000_000).unwrap();
+        assert_eq!(average, dec!(1.1003));
+        assert_eq!(quote.estimated_slippage(2_000_000), Some(dec!(0.0001)));
+
+        // Sell 3M: 1M @ 1.1000 + 2M @ 1.0999
+        let average = quote.average_fill_price(-3_000_000).unwrap();
+        assert_eq!(average.round_dp(10), dec!(1.0999333333));
+
+        // Deeper than the book
+        assert!(quote.average_fill_price(5_000_000).is_none());
+    }
+
+    #[test]
+    fn test_order_book_conversion() {
+        let raw: OandaBook = serde_json::from_str(
+            r#"{
+                "instrument": "EUR_USD",
+                "time": "2024-01-01T12:00:00Z",
+                "price": "1.10010",
+                "bucketWidth": "0.00050",
+                "buckets": [
+                    {"price": "1.09950", "longCountPercent": "0.4", "shortCountPercent": "0.1"},
+                    {"price": "1.10000", "longCountPercent": "0.2", "shortCountPercent": "0.3"},
+                    {"price": "1.10050", "longCountPercent": "0.1", "shortCountPercent": "0.5"}
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        let book = raw.to_order_book().unwrap();
+        assert_eq!(book.buckets.len(), 3);
+        assert_eq!(book.bucket_width, dec!(0.0005));
+        assert_eq!(
+            book.bucket_for_price(dec!(1.10020)).unwrap().price,
+            dec!(1.10000)
+        );
+        assert_eq!(book.buckets_below().count(), 2);
+        assert_eq!(book.buckets_above().count(), 1);
+        assert!((book.buckets[0].net_percent() - 0.3).abs() < 1e-10);
+    }
+
+    #[test]
+    fn test_position_book_totals() {
+        let raw: OandaBook = serde_json::from_str(
+            r#"{
+                "instrument": "USD_JPY",
+                "time": "2024-01-01T12:00:00Z",
+                "price": "145.100",
+                "bucketWidth": "0.050",
+                "buckets": [
+                    {"price": "145.000", "longCountPercent": "0.30", "shortCountPercent": "0.10"},
+                    {"price": "145.050", "longCountPercent": "0.25", "shortCountPercent": "0.35"}
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        let book = raw.to_position_book().unwrap();
+        assert!((book.total_long_percent() - 0.55).abs() < 1e-10);
+        assert!((book.total_short_percent() - 0.45).abs() < 1e-10);
+        assert_eq!(
+            book.bucket_for_price(dec!(145.07)).unwrap().price,
+            dec!(145.05)
+        );
+    }
+
+    #[test]
+    fn test_home_conversion() {
+        let conversion = HomeConversion {
+            currency: "JPY".to_string(),
+            account_gain: dec!(0.0068),
+            account_loss: dec!(0.0069),
+            position_value: dec!(0.00685),
+        };
+
+        assert_eq!(conversion.convert_pl(dec!(1000)), dec!(6.8));
+        assert_eq!(conversion.convert_pl(dec!(-1000)), dec!(-6.9));
+    }
+
+    #[test]
+    fn test_tick_tradeable_status() {
+        let raw: OandaPrice = serde_json::from_str(
+            r#"{
+                "instrument": "EUR_USD",
+                "time": "2024-01-06T12:00:00Z",
+                "bids": [{"price": "1.10000"}],
+                "asks": [{"price": "1.10020"}],
+                "tradeable": false,
+                "status": "non-tradeable"
+            }"#,
+        )
+        .unwrap();
+
+        let tick = raw.to_tick().unwrap();
+        assert!(!tick.tradeable);
+        assert_eq!(tick.status, PriceStatus::NonTradeable);
+
+        // Status alone is enough when the tradeable flag is missing
+        let raw: OandaPrice = serde_json::from_str(
+            r#"{
+                "instrument": "EUR_USD",
+                "time": "2024-01-06T12:00:00Z",
+                "bids": [{"price": "1.10000"}],
+                "asks": [{"price": "1.10020"}],
+                "status": "invalid"
+            }"#,
+        )
+        .unwrap();
+        assert!(!raw.to_tick().unwrap().tradeable);
+    }
+
+    #[test]
+    fn test_closeout_prices() {
+        let raw: OandaPrice = serde_json::from_str(
+            r#"{
+                "instrument": "EUR_USD",
+                "time": "2024-01-06T12:00:00Z",
+                "bids": [{"price": "1.10000", "liquidity": 1000000}],
+                "asks": [{"price": "1.10020", "liquidity": 1000000}],
+                "closeoutBid": "1.09990",
+                "closeoutAsk": "1.10030"
+            }"#,
+        )
+        .unwrap();
+
+        let quote = raw.to_quote().unwrap();
+        assert_eq!(quote.closeout_bid, Some(dec!(1.0999)));
+        assert_eq!(quote.closeout_ask, Some(dec!(1.1003)));
+
+        let tick = raw.to_tick().unwrap();
+        assert_eq!(tick.closeout_bid, Some(dec!(1.0999)));
+        assert_eq!(tick.closeout_spread(), Some(dec!(0.0004)));
+    }
+
+    fn instrument(name: &str, pip_location: i32) -> Instrument {
+        Instrument {
+            name: name.to_string(),
+            display_name: name.replace('_', "/"),
+            pip_location,
+            trade_units_precision: 0,
+            minimum_trade_size: dec!(1),
+            maximum_trade_size: dec!(100000000),
+            margin_rate: dec!(0.02),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_pip_size() {
+        assert_eq!(instrument("EUR_USD", -4).pip_size(), dec!(0.0001));
+        assert_eq!(instrument("USD_JPY", -2).pip_size(), dec!(0.01));
+        assert_eq!(instrument("SPX500_USD", 0).pip_size(), dec!(1));
+    }
+
+    #[test]
+    fn test_pip_value() {
+        let eur_usd = instrument("EUR_USD", -4);
+        let usd_jpy = instrument("USD_JPY", -2);
+
+        // Quote currency is the account currency
+        assert_eq!(
+            eur_usd.pip_value(dec!(100000), dec!(1.1), "USD"),
+            Some(dec!(10))
+        );
+
+        // Base currency is the account currency: 1000 JPY / 150
+        let value = usd_jpy.pip_value(dec!(-100000), dec!(150), "USD").unwrap();
+        assert_eq!(value.round_dp(4), dec!(6.6667));
+
+        // Cross to a third currency needs conversion factors
+        assert!(usd_jpy.pip_value(dec!(100000), dec!(150), "EUR").is_none());
+
+        let jpy_to_eur = HomeConversion {
+            currency: "JPY".to_string(),
+            account_gain: dec!(0.0061),
+            account_loss: dec!(0.0062),
+            position_value: dec!(0.00615),
+        };
+        assert_eq!(
+            usd_jpy.pip_value_with_conversion(dec!(100000), &jpy_to_eur),
+            Some(dec!(6.15))
+        );
+        assert!(eur_usd
+            .pip_value_with_conversion(dec!(100000), &jpy_to_eur)
+            .is_none());
+    }
+
+    #[test]
+    fn test_malformed_price_is_an_error() {
+        let raw = crate::decimal_number::from_json::<OandaPrice>(
+            br#"{
+                "instrument": "EUR_USD",
+                "time": "2024-01-06T12:00:00Z",
+                "bids": [{"price": "1.1O000"}],
+                "asks": [{"price": "1.10020"}]
+            }"#,
+            false,
+        );
+
+        match raw {
+            Err(crate::Error::ParseError { field, value }) => {
+                assert_eq!(field, "bids[0].price");
+                assert_eq!(value, "1.1O000");
+            }
+            other => panic!("expected ParseError, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_instrument_metadata() {
+        let instrument: Instrument = serde_json::from_str(
+            r#"{
+                "name": "EUR_USD",
+                "type": "CURRENCY",
+                "displayName": "EUR/USD",
+                "pipLocation": -4,
+                "displayPrecision": 5,
+                "tradeUnitsPrecision": 0,
+                "minimumTradeSize": "1",
+                "maximumTrailingStopDistance": "1.00000",
+                "minimumTrailingStopDistance": "0.00050",
+                "maximumPositionSize": "0",
+                "maximumOrderUnits": "100000000",
+                "marginRate": "0.0333",
+                "guaranteedStopLossOrderMode": "ALLOWED",
+                "tags": [{"type": "ASSET_CLASS", "name": "CURRENCY"}],
+                "financing": {
+                    "longRate": "-0.0394",
+                    "shortRate": "0.0155",
+                    "financingDaysOfWeek": [
+                        {"dayOfWeek": "MONDAY", "daysCharged": 1},
+                        {"dayOfWeek": "WEDNESDAY", "daysCharged": 3}
+                    ]
+                }
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(instrument.display_name, "EUR/USD");
+        assert_eq!(instrument.maximum_trade_size, dec!(100000000));
+        assert_eq!(
+            instrument.minimum_trailing_stop_distance,
+            Some(dec!(0.0005))
+        );
+        assert_eq!(
+            instrument.guaranteed_stop_loss_order_mode,
+            Some(GuaranteedStopLossOrderMode::Allowed)
+        );
+        assert_eq!(instrument.tags[0].name, "CURRENCY");
+
+        let financing = instrument.financing.unwrap();
+        assert_eq!(financing.long_rate, dec!(-0.0394));
+        assert_eq!(financing.days_charged("WEDNESDAY"), 3);
+        assert_eq!(financing.days_charged("SATURDAY"), 0);
+    }
+
+    fn utc(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_granularity_to_duration() {
+        assert_eq!(
+            Granularity::M15.to_duration(),
+            chrono::Duration::minutes(15)
+        );
+        assert_eq!(Granularity::D.to_duration(), chrono::Duration::days(1));
+    }
+
+    #[test]
+    fn test_intraday_alignment() {
+        let time = utc("2024-03-05T14:37:12Z");
+        assert_eq!(
+            Granularity::M5.align_down(time),
+            utc("2024-03-05T14:35:00Z")
+        );
+        assert_eq!(
+            Granularity::M5.next_boundary(time),
+            utc("2024-03-05T14:40:00Z")
+        );
+        assert_eq!(
+            Granularity::H1.align_down(time),
+            utc("2024-03-05T14:00:00Z")
+        );
+
+        // Already on a boundary
+        let boundary = utc("2024-03-05T14:35:00Z");
+        assert_eq!(Granularity::M5.align_down(boundary), boundary);
+    }
+
+    #[test]
+    fn test_daily_alignment_follows_new_york_dst() {
+        // Winter: 17:00 New York is 22:00 UTC
+        let winter = utc("2024-01-10T12:00:00Z");
+        assert_eq!(
+            Granularity::D.align_down(winter),
+            utc("2024-01-09T22:00:00Z")
+        );
+        assert_eq!(
+            Granularity::D.next_boundary(winter),
+            utc("2024-01-10T22:00:00Z")
+        );
+        assert_eq!(
+            Granularity::H4.align_down(winter),
+            utc("2024-01-10T10:00:00Z")
+        );
+
+        // Summer: 17:00 New York is 21:00 UTC
+        let summer = utc("2024-07-10T21:30:00Z");
+        assert_eq!(
+            Granularity::D.align_down(summer),
+            utc("2024-07-10T21:00:00Z")
+        );
+        assert_eq!(
+            Granularity::H4.align_down(summer),
+            utc("2024-07-10T21:00:00Z")
+        );
+
+        // Across the March DST change the day is only 23 hours long
+        let before = utc("2024-03-09T23:00:00Z");
+        assert_eq!(
+            Granularity::D.align_down(before),
+            utc("2024-03-09T22:00:00Z")
+        );
+        assert_eq!(
+            Granularity::D.next_boundary(before),
+            utc("2024-03-10T21:00:00Z")
+        );
+    }
+
+    #[test]
+    fn test_weekly_and_monthly_alignment() {
+        // Wednesday 2024-01-10; the week started Friday 2024-01-05 17:00 NY
+        let time = utc("2024-01-10T12:00:00Z");
+        assert_eq!(Granularity::W.align_down(time), utc("2024-01-05T22:00:00Z"));
+        assert_eq!(
+            Granularity::W.next_boundary(time),
+            utc("2024-01-12T22:00:00Z")
+        );
+
+        // January starts on the evening of December 31st
+        assert_eq!(Granularity::M.align_down(time), utc("2023-12-31T22:00:00Z"));
+        assert_eq!(
+            Granularity::M.next_boundary(time),
+            utc("2024-01-31T22:00:00Z")
+        );
+
+        // Late on the last day of the month already belongs to the next one
+        let late = utc("2024-01-31T23:00:00Z");
+        assert_eq!(Granularity::M.align_down(late), utc("2024-01-31T22:00:00Z"));
+    }
+
+    #[test]
+    fn test_utc_alignment() {
+        let alignment = CandleAlignment::utc(0);
+        let time = utc("2024-01-10T12:00:00Z");
+        assert_eq!(
+            Granularity::D.align_down_with(time, &alignment),
+            utc("2024-01-10T00:00:00Z")
+        );
+        assert_eq!(
+            Granularity::M.align_down_with(time, &alignment),
+            utc("2024-01-01T00:00:00Z")
+        );
+    }
+
+    #[test]
+    fn test_spread_pips() {
+        let eur_usd = instrument("EUR_USD", -4);
+        let usd_jpy = instrument("USD_JPY", -2);
+
+        let tick = |instrument: &str, bid, ask| Tick {
+            instrument: instrument.to_string(),
+            timestamp: Utc::now(),
+            bid,
+            ask,
+            tradeable: true,
+            status: PriceStatus::Tradeable,
+            closeout_bid: None,
+            closeout_ask: None,
+        };
+
+        let eur = tick("EUR_USD", dec!(1.10001), dec!(1.10016));
+        let jpy = tick("USD_JPY", dec!(150.001), dec!(150.016));
+        assert_eq!(eur.spread_pips(&eur_usd), dec!(1.5));
+        assert_eq!(jpy.spread_pips(&usd_jpy), dec!(1.5));
+
+        // Mid of 1.100085 shown with 5 decimals, rounding half away from zero
+        assert_eq!(eur_usd.price_precision(), 5);
+        assert_eq!(eur.display_mid(&eur_usd), dec!(1.10009));
+        assert_eq!(jpy.display_mid(&usd_jpy), dec!(150.009));
+
+        let reported = Instrument {
+            display_precision: Some(3),
+            ..eur_usd.clone()
+        };
+        assert_eq!(eur.display_bid(&reported), dec!(1.100));
+    }
 }
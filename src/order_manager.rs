@@ -0,0 +1,905 @@
+//! Order lifecycle management
+//!
+//! [`OrderManager`] submits, amends and cancels orders on behalf of the
+//! application and tracks each one by its client order ID. Every order it
+//! submits carries a client ID, so a request that times out can be checked
+//! against OANDA and retried without creating a duplicate. All lifecycle
+//! changes, whether from a response or from a later fill, arrive on one
+//! broadcast channel.
+//!
+//! ```no_run
+//! use oanda_connector::order_manager::{OrderEvent, OrderManager};
+//! use oanda_connector::{OandaClient, OandaConfig, OrderRequest};
+//! use rust_decimal_macros::dec;
+//! use std::time::Duration;
+//!
+//! # async fn run() -> oanda_connector::Result<()> {
+//! let client = OandaClient::new(OandaConfig::from_env()?)?;
+//! let manager = OrderManager::new(client);
+//! let mut events = manager.subscribe();
+//! let _sync = manager.spawn_sync(Duration::from_secs(2));
+//!
+//! let id = manager
+//!     .submit(OrderRequest::limit("EUR_USD", dec!(1000), dec!(1.0800)))
+//!     .await?;
+//! manager
+//!     .amend(&id, OrderRequest::limit("EUR_USD", dec!(1000), dec!(1.0820)))
+//!     .await?;
+//!
+//! while let Ok(event) = events.recv().await {
+//!     if let OrderEvent::Filled { client_id, fill, .. } = event {
+//!         println!("{} filled at {:?}", client_id, fill.price);
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::client::OandaClient;
+use crate::error::{Error, Result};
+use crate::ids::{OrderId, TransactionId};
+use crate::orders::{
+    Order, OrderRejectReason, OrderRequest, OrderResponse, OrderSpecifier, OrderState,
+};
+use crate::reconcile::StateDiff;
+use crate::shutdown::{ShutdownHandle, EVENT_CAPACITY};
+use crate::transactions::{ClientExtensions, OrderFillTransaction, Transaction};
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::broadcast;
+
+/// Where a managed order is in its lifecycle
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrderStatus {
+    /// Sent, no response yet
+    Submitting,
+    /// Accepted and waiting for its price
+    Pending,
+    Filled,
+    Cancelled,
+    Rejected,
+    /// Retries ran out without learning whether OANDA accepted the order
+    Unknown,
+}
+
+impl OrderStatus {
+    /// Whether the order can still change
+    pub fn is_open(&self) -> bool {
+        matches!(self, OrderStatus::Submitting | OrderStatus::Pending)
+    }
+}
+
+/// An order and what the manager knows about it
+#[derive(Debug, Clone, PartialEq)]
+pub struct ManagedOrder {
+    pub client_id: String,
+    /// Latest request, i.e. the replacement after an amendment
+    pub request: OrderRequest,
+    /// OANDA's ID for the current order; changes when amended
+    pub order_id: Option<OrderId>,
+    pub status: OrderStatus,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Order lifecycle update
+#[derive(Debug, Clone, PartialEq)]
+pub enum OrderEvent {
+    /// OANDA created the order
+    Accepted {
+        client_id: String,
+        order_id: OrderId,
+    },
+    Filled {
+        client_id: String,
+        order_id: OrderId,
+        fill: Box<OrderFillTransaction>,
+    },
+    /// The order was amended; OANDA gave the replacement a new ID
+    Replaced {
+        client_id: String,
+        old_order_id: OrderId,
+        order_id: OrderId,
+    },
+    Cancelled {
+        client_id: String,
+        order_id: OrderId,
+        reason: Option<String>,
+    },
+    Rejected {
+        client_id: String,
+        reason: OrderRejectReason,
+        message: String,
+    },
+    /// The request failed and its outcome is unknown
+    Failed { client_id: String, error: String },
+}
+
+impl OrderEvent {
+    /// Client ID of the order the event is about
+    pub fn client_id(&self) -> &str {
+        match self {
+            OrderEvent::Accepted { client_id, .. }
+            | OrderEvent::Filled { client_id, .. }
+            | OrderEvent::Replaced { client_id, .. }
+            | OrderEvent::Cancelled { client_id, .. }
+            | OrderEvent::Rejected { client_id, .. }
+            | OrderEvent::Failed { client_id, .. } => client_id,
+        }
+    }
+}
+
+/// Submits and tracks orders
+///
+/// Cheap to clone; clones share the tracked orders and the event channel.
+#[derive(Clone)]
+pub struct OrderManager {
+    client: OandaClient,
+    orders: Arc<Mutex<HashMap<String, ManagedOrder>>>,
+    events: broadcast::Sender<OrderEvent>,
+    /// Last transaction applied by `sync`
+    cursor: Arc<Mutex<Option<TransactionId>>>,
+    next_id: Arc<AtomicU64>,
+    id_prefix: String,
+    max_retries: u32,
+    retry_delay: Duration,
+}
+
+impl OrderManager {
+    /// Manage orders through `client`
+    ///
+    /// Retries failed requests up to 3 times, 500ms apart.
+    pub fn new(client: OandaClient) -> Self {
+        let (events, _) = broadcast::channel(EVENT_CAPACITY);
+        Self {
+            client,
+            orders: Arc::new(Mutex::new(HashMap::new())),
+            events,
+            cursor: Arc::new(Mutex::new(None)),
+            next_id: Arc::new(AtomicU64::new(1)),
+            id_prefix: format!("om-{}", Utc::now().timestamp_millis()),
+            max_retries: 3,
+            retry_delay: Duration::from_millis(500),
+        }
+    }
+
+    /// Retries after a transient failure (0 disables)
+    pub fn max_retries(mut self, max: u32) -> Self {
+        self.max_retries = max;
+        self
+    }
+
+    /// Delay before checking and retrying a failed request
+    pub fn retry_delay(mut self, delay: Duration) -> Self {
+        self.retry_delay = delay;
+        self
+    }
+
+    /// Prefix of generated client order IDs
+    ///
+    /// Defaults to one based on the start time, so IDs don't collide with
+    /// those of an earlier run.
+    pub fn client_id_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.id_prefix = prefix.into();
+        self
+    }
+
+    /// Receiver for every lifecycle event from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<OrderEvent> {
+        self.events.subscribe()
+    }
+
+    /// Tracked order by client ID
+    pub fn order(&self, client_id: &str) -> Option<ManagedOrder> {
+        self.orders.lock().unwrap().get(client_id).cloned()
+    }
+
+    /// Every tracked order
+    pub fn orders(&self) -> Vec<ManagedOrder> {
+        self.orders.lock().unwrap().values().cloned().collect()
+    }
+
+    /// Tracked orders that can still change
+    pub fn open_orders(&self) -> Vec<ManagedOrder> {
+        self.orders
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|order| order.status.is_open())
+            .cloned()
+            .collect()
+    }
+
+    /// Submit an order, returning its client ID
+    ///
+    /// Uses the order's client extensions ID if set, otherwise generates
+    /// one. After a transient failure the order is looked up by that ID
+    /// before being sent again, and OANDA refuses a second order with the
+    /// same ID, so a retry never creates a duplicate.
+    ///
+    /// Fails with `Error::OrderRejected` if OANDA rejects the order or the
+    /// client ID is already tracked.
+    pub async fn submit(&self, mut order: OrderRequest) -> Result<String> {
+        let extensions = order.client_extensions.get_or_insert_with(Default::default);
+        let client_id = extensions
+            .id
+            .get_or_insert_with(|| {
+                let n = self.next_id.fetch_add(1, Ordering::Relaxed);
+                format!("{}-{}", self.id_prefix, n)
+            })
+            .clone();
+
+        {
+            let mut orders = self.orders.lock().unwrap();
+            if orders.contains_key(&client_id) {
+                return Err(Error::OrderRejected {
+                    reason: OrderRejectReason::ClientOrderIdAlreadyExists,
+                    message: format!("order {} already submitted", client_id),
+                    transaction_id: None,
+                });
+            }
+            orders.insert(
+                client_id.clone(),
+                ManagedOrder {
+                    client_id: client_id.clone(),
+                    request: order.clone(),
+                    order_id: None,
+                    status: OrderStatus::Submitting,
+                    updated_at: Utc::now(),
+                },
+            );
+        }
+
+        let mut attempt = 0;
+        loop {
+            let error = match self.client.place_order(&order).await {
+                Ok(response) => {
+                    self.apply_response(&client_id, &response);
+                    return Ok(client_id);
+                }
+                Err(e) => e,
+            };
+
+            // An earlier attempt got through after all
+            let duplicate = matches!(
+                error.inner(),
+                Error::OrderRejected {
+                    reason: OrderRejectReason::ClientOrderIdAlreadyExists,
+                    ..
+                }
+            );
+            if duplicate && attempt > 0 {
+                match self
+                    .client
+                    .get_order(OrderSpecifier::client_id(&client_id))
+                    .await
+                {
+                    Ok(order) => {
+                        self.apply_order(&client_id, &order);
+                        return Ok(client_id);
+                    }
+                    Err(e) => {
+                        self.fail(&client_id, &e);
+                        return Err(e);
+                    }
+                }
+            }
+
+            if !error.is_retryable() || attempt >= self.max_retries {
+                self.fail(&client_id, &error);
+                return Err(error);
+            }
+            attempt += 1;
+            tokio::time::sleep(self.retry_delay).await;
+
+            if let Ok(order) = self
+                .client
+                .get_order(OrderSpecifier::client_id(&client_id))
+                .await
+            {
+                self.apply_order(&client_id, &order);
+                return Ok(client_id);
+            }
+        }
+    }
+
+    /// Replace a pending order with `replacement`
+    ///
+    /// The replacement keeps the client ID (and the original tag and
+    /// comment unless it sets its own) but gets a new OANDA order ID.
+    ///
+    /// Fails with `Error::OrderRejected` if the order was filled or
+    /// cancelled before the replacement went through.
+    pub async fn amend(&self, client_id: &str, mut replacement: OrderRequest) -> Result<()> {
+        let (order_id, extensions) = self.pending(client_id)?;
+        let extensions = replacement.client_extensions.take().unwrap_or(extensions);
+        replacement.client_extensions = Some(ClientExtensions {
+            id: Some(client_id.to_string()),
+            ..extensions
+        });
+
+        let mut attempt = 0;
+        loop {
+            let error = match self.client.replace_order(&order_id, &replacement).await {
+                Ok(response) => {
+                    self.update(client_id, |order| order.request = replacement.clone());
+                    self.apply_response(client_id, &response);
+                    return Ok(());
+                }
+                Err(e) => e,
+            };
+            if !error.is_retryable() || attempt >= self.max_retries {
+                return Err(error);
+            }
+            attempt += 1;
+            tokio::time::sleep(self.retry_delay).await;
+
+            // Retry only if the original is still pending
+            if let Ok(order) = self.client.get_order(&order_id).await {
+                if order.state != OrderState::Pending {
+                    let replaced = order.state == OrderState::Cancelled
+                        && order.replaced_by_order_id.is_some();
+                    if replaced {
+                        self.update(client_id, |order| order.request = replacement.clone());
+                    }
+                    self.apply_order(client_id, &order);
+                    return if replaced {
+                        Ok(())
+                    } else {
+                        Err(no_longer_pending(&order))
+                    };
+                }
+            }
+        }
+    }
+
+    /// Cancel a pending order
+    ///
+    /// Fails with `Error::OrderRejected` if the order was filled or
+    /// replaced before the cancellation went through.
+    pub async fn cancel(&self, client_id: &str) -> Result<()> {
+        let (order_id, _) = self.pending(client_id)?;
+
+        let mut attempt = 0;
+        loop {
+            let error = match self.client.cancel_order(&order_id).await {
+                Ok(response) => {
+                    self.apply_response(client_id, &response);
+                    return Ok(());
+                }
+                Err(e) => e,
+            };
+            if !error.is_retryable() || attempt >= self.max_retries {
+                return Err(error);
+            }
+            attempt += 1;
+            tokio::time::sleep(self.retry_delay).await;
+
+            if let Ok(order) = self.client.get_order(&order_id).await {
+                if order.state != OrderState::Pending {
+                    self.apply_order(client_id, &order);
+                    let cancelled = order.state == OrderState::Cancelled
+                        && order.replaced_by_order_id.is_none();
+                    return if cancelled {
+                        Ok(())
+                    } else {
+                        Err(no_longer_pending(&order))
+                    };
+                }
+            }
+        }
+    }
+
+    /// Apply transactions since the last sync, e.g. fills of pending orders
+    ///
+    /// Only transactions after the first order this manager submitted are
+    /// read; before that there is nothing to track.
+    pub async fn sync(&self) -> Result<()> {
+        let Some(cursor) = self.cursor.lock().unwrap().clone() else {
+            return Ok(());
+        };
+        for transaction in self.client.get_transactions_since(&cursor).await? {
+            self.apply_transaction(&transaction);
+        }
+        Ok(())
+    }
+
+    /// Call `sync` every `interval` in the background
+    ///
+    /// Failed syncs are retried on the next tick. Must be called inside a
+    /// Tokio runtime.
+    pub fn spawn_sync(&self, interval: Duration) -> ShutdownHandle {
+        let manager = self.clone();
+        ShutdownHandle::spawn(|mut signal| async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                tokio::select! {
+                    _ = ticker.tick() => {}
+                    _ = signal.requested() => break,
+                }
+                let _ = manager.sync().await;
+            }
+        })
+    }
+
+    /// Update tracked orders from a transaction, e.g. one from the
+    /// transaction stream
+    ///
+    /// Transactions for untracked orders, and changes the manager already
+    /// knows about, are ignored.
+    pub fn apply_transaction(&self, transaction: &Transaction) {
+        if let Some(id) = transaction.transaction_id() {
+            let mut cursor = self.cursor.lock().unwrap();
+            if cursor.as_ref().is_some_and(|cursor| *cursor < id) {
+                *cursor = Some(id);
+            }
+        }
+
+        match transaction {
+            Transaction::OrderFill(fill) => {
+                if let Some(client_id) = self.tracking(&fill.order_id, &fill.client_order_id) {
+                    self.filled(&client_id, fill);
+                }
+            }
+            Transaction::OrderCancel(cancel) => {
+                if let Some(client_id) = self.tracking(&cancel.order_id, &cancel.client_order_id) {
+                    match &cancel.replaced_by_order_id {
+                        Some(replacement) => {
+                            self.replaced(&client_id, &cancel.order_id, replacement)
+                        }
+                        None => self.cancelled(&client_id, &cancel.order_id, cancel.reason.clone()),
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Apply every transaction of a reconciliation diff
+    ///
+    /// Fills and cancellations the manager missed while disconnected are
+    /// published as events like any others.
+    pub fn apply_changes(&self, diff: &StateDiff) {
+        for transaction in &diff.transactions {
+            self.apply_transaction(transaction);
+        }
+    }
+
+    /// Order ID and client extensions of a pending tracked order
+    fn pending(&self, client_id: &str) -> Result<(OrderId, ClientExtensions)> {
+        let orders = self.orders.lock().unwrap();
+        match orders.get(client_id) {
+            Some(ManagedOrder {
+                order_id: Some(order_id),
+                status: OrderStatus::Pending,
+                request,
+                ..
+            }) => Ok((
+                order_id.clone(),
+                request.client_extensions.clone().unwrap_or_default(),
+            )),
+            _ => Err(Error::OrderRejected {
+                reason: OrderRejectReason::Other("ORDER_DOESNT_EXIST".to_string()),
+                message: format!("no pending order {} tracked by this manager", client_id),
+                transaction_id: None,
+            }),
+        }
+    }
+
+    /// Client ID of the tracked order a transaction refers to
+    fn tracking(&self, order_id: &OrderId, client_order_id: &Option<String>) -> Option<String> {
+        let orders = self.orders.lock().unwrap();
+        if let Some(client_id) = client_order_id {
+            if orders.contains_key(client_id) {
+                return Some(client_id.clone());
+            }
+        }
+        orders
+            .values()
+            .find(|order| order.order_id.as_ref() == Some(order_id))
+            .map(|order| order.client_id.clone())
+    }
+
+    fn apply_response(&self, client_id: &str, response: &OrderResponse) {
+        if let Some(Transaction::OrderCancel(cancel)) = &response.order_cancel_transaction {
+            match &cancel.replaced_by_order_id {
+                Some(replacement) => self.replaced(client_id, &cancel.order_id, replacement),
+                // FOK/IOC orders OANDA couldn't fill are cancelled at once
+                None => {
+                    if response.order_create_transaction.is_some() {
+                        self.accepted(client_id, response);
+                    }
+                    self.cancelled(client_id, &cancel.order_id, cancel.reason.clone());
+                    return;
+                }
+            }
+        }
+        if let Some(create) = &response.order_create_transaction {
+            let is_replacement = matches!(
+                &response.order_cancel_transaction,
+                Some(Transaction::OrderCancel(cancel)) if cancel.replaced_by_order_id.is_some()
+            );
+            if !is_replacement {
+                self.accepted(client_id, response);
+            }
+            if let Some(id) = create.transaction_id() {
+                let mut cursor = self.cursor.lock().unwrap();
+                if cursor.is_none() {
+                    *cursor = Some(TransactionId::from(id.as_u64().saturating_sub(1)));
+                }
+            }
+        }
+        if let Some(Transaction::OrderFill(fill)) = &response.order_fill_transaction {
+            self.filled(client_id, fill);
+        }
+    }
+
+    /// Apply an order looked up after a failed request
+    fn apply_order(&self, client_id: &str, order: &Order) {
+        let known = self.order(client_id).and_then(|managed| managed.order_id);
+        if known.is_none() {
+            self.update(client_id, |managed| {
+                managed.order_id = Some(order.id.clone());
+                managed.status = OrderStatus::Pending;
+            });
+            self.emit(OrderEvent::Accepted {
+                client_id: client_id.to_string(),
+                order_id: order.id.clone(),
+            });
+            let mut cursor = self.cursor.lock().unwrap();
+            if cursor.is_none() {
+                *cursor = Some(TransactionId::from(order.id.as_u64().saturating_sub(1)));
+            }
+        }
+
+        match (order.state, &order.replaced_by_order_id) {
+            (OrderState::Cancelled, Some(replacement)) => {
+                self.replaced(client_id, &order.id, replacement)
+            }
+            (OrderState::Cancelled, None) => self.cancelled(client_id, &order.id, None),
+            // The fill itself arrives with the next sync
+            _ => {}
+        }
+    }
+
+    fn accepted(&self, client_id: &str, response: &OrderResponse) {
+        let Some(order_id) = response
+            .order_create_transaction
+            .as_ref()
+            .and_then(Transaction::transaction_id)
+            .map(|id| OrderId::from(id.as_u64()))
+        else {
+            return;
+        };
+        self.update(client_id, |order| {
+            order.order_id = Some(order_id.clone());
+            order.status = OrderStatus::Pending;
+        });
+        self.emit(OrderEvent::Accepted {
+            client_id: client_id.to_string(),
+            order_id,
+        });
+    }
+
+    fn filled(&self, client_id: &str, fill: &OrderFillTransaction) {
+        if self.order(client_id).map(|order| order.status) == Some(OrderStatus::Filled) {
+            return;
+        }
+        self.update(client_id, |order| {
+            order.order_id = Some(fill.order_id.clone());
+            order.status = OrderStatus::Filled;
+        });
+        self.emit(OrderEvent::Filled {
+            client_id: client_id.to_string(),
+            order_id: fill.order_id.clone(),
+            fill: Box::new(fill.clone()),
+        });
+    }
+
+    fn cancelled(&self, client_id: &str, order_id: &OrderId, reason: Option<String>) {
+        let Some(order) = self.order(client_id) else {
+            return;
+        };
+        if order.status == OrderStatus::Cancelled || order.order_id.as_ref() != Some(order_id) {
+            return;
+        }
+        self.update(client_id, |order| order.status = OrderStatus::Cancelled);
+        self.emit(OrderEvent::Cancelled {
+            client_id: client_id.to_string(),
+            order_id: order_id.clone(),
+            reason,
+        });
+    }
+
+    fn replaced(&self, client_id: &str, old_order_id: &OrderId, order_id: &OrderId) {
+        let Some(order) = self.order(client_id) else {
+            return;
+        };
+        if order.order_id.as_ref() != Some(old_order_id) {
+            return;
+        }
+        self.update(client_id, |order| {
+            order.order_id = Some(order_id.clone());
+            order.status = OrderStatus::Pending;
+        });
+        self.emit(OrderEvent::Replaced {
+            client_id: client_id.to_string(),
+            old_order_id: old_order_id.clone(),
+            order_id: order_id.clone(),
+        });
+    }
+
+    fn fail(&self, client_id: &str, error: &Error) {
+        let (status, event) = match error.inner() {
+            Error::OrderRejected {
+                reason, message, ..
+            } => (
+                OrderStatus::Rejected,
+                OrderEvent::Rejected {
+                    client_id: client_id.to_string(),
+                    reason: reason.clone(),
+                    message: message.clone(),
+                },
+            ),
+            _ => (
+                OrderStatus::Unknown,
+                OrderEvent::Failed {
+                    client_id: client_id.to_string(),
+                    error: error.to_string(),
+                },
+            ),
+        };
+        self.update(client_id, |order| order.status = status);
+        self.emit(event);
+    }
+
+    fn update(&self, client_id: &str, change: impl FnOnce(&mut ManagedOrder)) {
+        if let Some(order) = self.orders.lock().unwrap().get_mut(client_id) {
+            change(order);
+            order.updated_at = Utc::now();
+        }
+    }
+
+    fn emit(&self, event: OrderEvent) {
+        // No subscribers is fine
+        let _ = self.events.send(event);
+    }
+}
+
+/// Error for an order that left the pending state other than as asked
+fn no_longer_pending(order: &Order) -> Error {
+    let reason = match (order.state, &order.replaced_by_order_id) {
+        (OrderState::Cancelled, Some(_)) => "ORDER_ALREADY_REPLACED",
+        (OrderState::Cancelled, None) => "ORDER_ALREADY_CANCELLED",
+        _ => "ORDER_ALREADY_FILLED",
+    };
+    Error::OrderRejected {
+        reason: OrderRejectReason::Other(reason.to_string()),
+        message: format!("order {} is no longer pending", order.id),
+        transaction_id: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mockito::{Matcher, Server};
+    use rust_decimal_macros::dec;
+
+    fn client(server: &Server) -> OandaClient {
+        let mut config = crate::OandaConfig::new(
+            "test_api_key".to_string(),
+            "test_account_id".to_string(),
+            true,
+        );
+        config.base_url = Some(server.url());
+        config.enable_retries = false;
+        OandaClient::new(config).unwrap()
+    }
+
+    /// Manager tracking pending order 100 as `om-test-1`
+    async fn pending_order(server: &mut Server) -> OrderManager {
+        let place = server
+            .mock("POST", "/v3/accounts/test_account_id/orders")
+            .with_status(201)
+            .with_body(
+                r#"{
+                "orderCreateTransaction": {
+                    "id": "100", "accountID": "test_account_id", "batchID": "100",
+                    "time": "2024-01-01T12:00:00.000000000Z", "type": "LIMIT_ORDER",
+                    "instrument": "EUR_USD", "units": "1000", "price": "1.08000",
+                    "clientExtensions": {"id": "om-test-1"}
+                },
+                "lastTransactionID": "100"
+            }"#,
+            )
+            .create_async()
+            .await;
+        let manager = OrderManager::new(client(server))
+            .client_id_prefix("om-test")
+            .retry_delay(Duration::ZERO);
+        manager
+            .submit(OrderRequest::limit("EUR_USD", dec!(1000), dec!(1.08000)))
+            .await
+            .unwrap();
+        place.remove_async().await;
+        manager
+    }
+
+    async fn mock_lookup(server: &mut Server, order: &str) -> mockito::Mock {
+        server
+            .mock("GET", "/v3/accounts/test_account_id/orders/100")
+            .with_status(200)
+            .with_body(format!(
+                r#"{{"order": {}, "lastTransactionID": "103"}}"#,
+                order
+            ))
+            .create_async()
+            .await
+    }
+
+    fn rejected_with(result: Result<()>, code: &str) -> bool {
+        matches!(
+            result.map_err(Error::into_inner),
+            Err(Error::OrderRejected { reason: OrderRejectReason::Other(reason), .. })
+                if reason == code
+        )
+    }
+
+    const FILLED: &str = r#"{
+        "id": "100", "type": "LIMIT", "state": "FILLED",
+        "instrument": "EUR_USD", "units": "1000", "price": "1.08000",
+        "fillingTransactionID": "103"
+    }"#;
+
+    #[tokio::test]
+    async fn test_amend_after_fill_is_an_error() {
+        let mut server = Server::new_async().await;
+        let manager = pending_order(&mut server).await;
+        let _replace = server
+            .mock("PUT", "/v3/accounts/test_account_id/orders/100")
+            .with_status(503)
+            .create_async()
+            .await;
+        let _lookup = mock_lookup(&mut server, FILLED).await;
+
+        let result = manager
+            .amend(
+                "om-test-1",
+                OrderRequest::limit("EUR_USD", dec!(1000), dec!(1.08200)),
+            )
+            .await;
+        assert!(rejected_with(result, "ORDER_ALREADY_FILLED"));
+        // The request is still the one that was filled
+        let order = manager.order("om-test-1").unwrap();
+        assert_eq!(order.request.price, Some(dec!(1.08000)));
+    }
+
+    #[tokio::test]
+    async fn test_amend_finds_replacement() {
+        let mut server = Server::new_async().await;
+        let manager = pending_order(&mut server).await;
+        let _replace = server
+            .mock("PUT", "/v3/accounts/test_account_id/orders/100")
+            .with_status(503)
+            .create_async()
+            .await;
+        let _lookup = mock_lookup(
+            &mut server,
+            r#"{
+                "id": "100", "type": "LIMIT", "state": "CANCELLED",
+                "instrument": "EUR_USD", "units": "1000", "price": "1.08000",
+                "replacedByOrderID": "102"
+            }"#,
+        )
+        .await;
+
+        manager
+            .amend(
+                "om-test-1",
+                OrderRequest::limit("EUR_USD", dec!(1000), dec!(1.08200)),
+            )
+            .await
+            .unwrap();
+        let order = manager.order("om-test-1").unwrap();
+        assert_eq!(order.order_id.unwrap().as_u64(), 102);
+        assert_eq!(order.status, OrderStatus::Pending);
+        assert_eq!(order.request.price, Some(dec!(1.08200)));
+    }
+
+    #[tokio::test]
+    async fn test_cancel_after_fill_is_an_error() {
+        let mut server = Server::new_async().await;
+        let manager = pending_order(&mut server).await;
+        let _cancel = server
+            .mock("PUT", "/v3/accounts/test_account_id/orders/100/cancel")
+            .with_status(503)
+            .create_async()
+            .await;
+        let _lookup = mock_lookup(&mut server, FILLED).await;
+
+        let result = manager.cancel("om-test-1").await;
+        assert!(rejected_with(result, "ORDER_ALREADY_FILLED"));
+    }
+
+    #[tokio::test]
+    async fn test_cancel_finds_cancellation() {
+        let mut server = Server::new_async().await;
+        let manager = pending_order(&mut server).await;
+        let _cancel = server
+            .mock("PUT", "/v3/accounts/test_account_id/orders/100/cancel")
+            .with_status(503)
+            .create_async()
+            .await;
+        let _lookup = mock_lookup(
+            &mut server,
+            r#"{
+                "id": "100", "type": "LIMIT", "state": "CANCELLED",
+                "instrument": "EUR_USD", "units": "1000", "price": "1.08000",
+                "cancellingTransactionID": "103"
+            }"#,
+        )
+        .await;
+
+        manager.cancel("om-test-1").await.unwrap();
+        assert_eq!(
+            manager.order("om-test-1").unwrap().status,
+            OrderStatus::Cancelled
+        );
+    }
+
+    #[tokio::test]
+    async fn test_failed_duplicate_lookup_leaves_no_submitting_order() {
+        let mut server = Server::new_async().await;
+        let first = server
+            .mock("POST", "/v3/accounts/test_account_id/orders")
+            .with_status(503)
+            .expect(1)
+            .create_async()
+            .await;
+        let duplicate = server
+            .mock("POST", "/v3/accounts/test_account_id/orders")
+            .with_status(400)
+            .with_body(
+                r#"{
+                "orderRejectTransaction": {
+                    "id": "101", "accountID": "test_account_id", "batchID": "101",
+                    "time": "2024-01-01T12:00:00.000000000Z", "type": "LIMIT_ORDER_REJECT",
+                    "instrument": "EUR_USD", "units": "1000", "price": "1.08000",
+                    "rejectReason": "CLIENT_ORDER_ID_ALREADY_EXISTS"
+                },
+                "errorCode": "CLIENT_ORDER_ID_ALREADY_EXISTS",
+                "errorMessage": "Client order ID already exists"
+            }"#,
+            )
+            .expect(1)
+            .create_async()
+            .await;
+        let lookup = server
+            .mock("GET", "/v3/accounts/test_account_id/orders/@om-test-1")
+            .match_query(Matcher::Any)
+            .with_status(500)
+            .expect(2)
+            .create_async()
+            .await;
+        let manager = OrderManager::new(client(&server))
+            .client_id_prefix("om-test")
+            .retry_delay(Duration::ZERO);
+
+        let result = manager
+            .submit(OrderRequest::limit("EUR_USD", dec!(1000), dec!(1.08000)))
+            .await;
+        assert!(result.is_err());
+        assert_eq!(
+            manager.order("om-test-1").unwrap().status,
+            OrderStatus::Unknown
+        );
+        assert!(manager.open_orders().is_empty());
+
+        first.assert_async().await;
+        duplicate.assert_async().await;
+        lookup.assert_async().await;
+    }
+}
@@ -0,0 +1,654 @@
+//! Order placement
+//!
+//! Build an [`OrderRequest`] and submit it with `OandaClient::place_order`.
+//! Units are signed: positive buys, negative sells.
+//!
+//! ```
+//! use oanda_connector::orders::{OrderRequest, TimeInForce};
+//! use rust_decimal_macros::dec;
+//!
+//! let order = OrderRequest::limit("EUR_USD", dec!(-1000), dec!(1.1050))
+//!     .time_in_force(TimeInForce::Gtc)
+//!     .stop_loss(dec!(1.1100))
+//!     .take_profit(dec!(1.0950));
+//! assert!(order.is_sell());
+//! ```
+//!
+//! Rejected orders fail with `Error::OrderRejected` carrying a typed
+//! [`OrderRejectReason`].
+
+use crate::ids::{OrderId, TransactionId};
+use crate::transactions::{ClientExtensions, Transaction};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Kind of order to create
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum OrderType {
+    Market,
+    Limit,
+    Stop,
+    MarketIfTouched,
+}
+
+/// How long an order stays open
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum TimeInForce {
+    /// Good until cancelled
+    Gtc,
+    /// Good until `gtd_time`
+    Gtd,
+    /// Good for the trading day
+    Gfd,
+    /// Fill completely or cancel
+    Fok,
+    /// Fill what is possible immediately, cancel the rest
+    Ioc,
+}
+
+/// How a fill interacts with an existing position
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum PositionFill {
+    Default,
+    ReduceFirst,
+    ReduceOnly,
+    OpenOnly,
+}
+
+/// Price for a dependent order created on fill
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PriceDetails {
+    #[serde(with = "crate::decimal_number")]
+    pub price: Decimal,
+}
+
+/// Distance for a trailing stop created on fill
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DistanceDetails {
+    #[serde(with = "crate::decimal_number")]
+    pub distance: Decimal,
+}
+
+/// Order to submit
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrderRequest {
+    #[serde(rename = "type")]
+    pub order_type: OrderType,
+    pub instrument: String,
+    #[serde(with = "crate::decimal_number")]
+    pub units: Decimal,
+    #[serde(
+        default,
+        with = "crate::decimal_number::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub price: Option<Decimal>,
+    #[serde(
+        default,
+        with = "crate::decimal_number::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub price_bound: Option<Decimal>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time_in_force: Option<TimeInForce>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub position_fill: Option<PositionFill>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stop_loss_on_fill: Option<PriceDetails>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub take_profit_on_fill: Option<PriceDetails>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trailing_stop_loss_on_fill: Option<DistanceDetails>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_extensions: Option<ClientExtensions>,
+}
+
+impl OrderRequest {
+    fn new(
+        order_type: OrderType,
+        instrument: &str,
+        units: Decimal,
+        price: Option<Decimal>,
+    ) -> Self {
+        Self {
+            order_type,
+            instrument: instrument.to_string(),
+            units,
+            price,
+            price_bound: None,
+            time_in_force: None,
+            position_fill: None,
+            stop_loss_on_fill: None,
+            take_profit_on_fill: None,
+            trailing_stop_loss_on_fill: None,
+            client_extensions: None,
+        }
+    }
+
+    /// Fill now at the current price
+    pub fn market(instrument: &str, units: Decimal) -> Self {
+        Self::new(OrderType::Market, instrument, units, None)
+    }
+
+    /// Fill at `price` or better
+    pub fn limit(instrument: &str, units: Decimal, price: Decimal) -> Self {
+        Self::new(OrderType::Limit, instrument, units, Some(price))
+    }
+
+    /// Fill once the market reaches `price` or worse
+    pub fn stop(instrument: &str, units: Decimal, price: Decimal) -> Self {
+        Self::new(OrderType::Stop, instrument, units, Some(price))
+    }
+
+    /// Fill once the market touches `price`
+    pub fn market_if_touched(instrument: &str, units: Decimal, price: Decimal) -> Self {
+        Self::new(OrderType::MarketIfTouched, instrument, units, Some(price))
+    }
+
+    /// Set time in force
+    pub fn time_in_force(mut self, time_in_force: TimeInForce) -> Self {
+        self.time_in_force = Some(time_in_force);
+        self
+    }
+
+    /// Set position fill behaviour
+    pub fn position_fill(mut self, position_fill: PositionFill) -> Self {
+        self.position_fill = Some(position_fill);
+        self
+    }
+
+    /// Worst acceptable fill price for market and stop orders
+    pub fn price_bound(mut self, price_bound: Decimal) -> Self {
+        self.price_bound = Some(price_bound);
+        self
+    }
+
+    /// Attach a stop loss at `price` to the resulting trade
+    pub fn stop_loss(mut self, price: Decimal) -> Self {
+        self.stop_loss_on_fill = Some(PriceDetails { price });
+        self
+    }
+
+    /// Attach a take profit at `price` to the resulting trade
+    pub fn take_profit(mut self, price: Decimal) -> Self {
+        self.take_profit_on_fill = Some(PriceDetails { price });
+        self
+    }
+
+    /// Attach a trailing stop `distance` away to the resulting trade
+    pub fn trailing_stop_loss(mut self, distance: Decimal) -> Self {
+        self.trailing_stop_loss_on_fill = Some(DistanceDetails { distance });
+        self
+    }
+
+    /// Tag the order with client extensions
+    pub fn client_extensions(mut self, extensions: ClientExtensions) -> Self {
+        self.client_extensions = Some(extensions);
+        self
+    }
+
+    /// Whether the order sells
+    pub fn is_sell(&self) -> bool {
+        self.units.is_sign_negative()
+    }
+}
+
+/// Order to act on: its OANDA ID, or the client ID it was created with
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum OrderSpecifier {
+    Id(OrderId),
+    ClientId(String),
+}
+
+impl OrderSpecifier {
+    /// Refer to an order by its client extensions ID
+    pub fn client_id(id: impl Into<String>) -> Self {
+        OrderSpecifier::ClientId(id.into())
+    }
+}
+
+impl From<OrderId> for OrderSpecifier {
+    fn from(id: OrderId) -> Self {
+        OrderSpecifier::Id(id)
+    }
+}
+
+impl From<&OrderId> for OrderSpecifier {
+    fn from(id: &OrderId) -> Self {
+        OrderSpecifier::Id(id.clone())
+    }
+}
+
+/// Path segment OANDA expects: the ID, or `@` and the client ID
+impl fmt::Display for OrderSpecifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OrderSpecifier::Id(id) => write!(f, "{}", id),
+            OrderSpecifier::ClientId(id) => write!(f, "@{}", id),
+        }
+    }
+}
+
+/// Lifecycle state OANDA reports for an order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum OrderState {
+    Pending,
+    Filled,
+    Triggered,
+    Cancelled,
+}
+
+/// An order as stored by OANDA
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Order {
+    pub id: OrderId,
+    /// OANDA type name, including dependent orders such as `STOP_LOSS`
+    #[serde(rename = "type")]
+    pub order_type: String,
+    pub state: OrderState,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub instrument: Option<String>,
+    #[serde(
+        default,
+        with = "crate::decimal_number::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub units: Option<Decimal>,
+    #[serde(
+        default,
+        with = "crate::decimal_number::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub price: Option<Decimal>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_extensions: Option<ClientExtensions>,
+    #[serde(
+        rename = "fillingTransactionID",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub filling_transaction_id: Option<TransactionId>,
+    #[serde(
+        rename = "cancellingTransactionID",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub cancelling_transaction_id: Option<TransactionId>,
+    #[serde(
+        rename = "replacedByOrderID",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub replaced_by_order_id: Option<OrderId>,
+}
+
+/// Transactions created by a successful order request
+///
+/// A market order that could not be filled (e.g. FOK without liquidity) is
+/// accepted and cancelled, so check `order_cancel_transaction`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrderResponse {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub order_create_transaction: Option<Transaction>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub order_fill_transaction: Option<Transaction>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub order_cancel_transaction: Option<Transaction>,
+    #[serde(rename = "relatedTransactionIDs", default)]
+    pub related_transaction_ids: Vec<TransactionId>,
+    #[serde(
+        rename = "lastTransactionID",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub last_transaction_id: Option<TransactionId>,
+}
+
+/// Result of closing a position
+///
+/// Each side that was closed gets its own market order, so its
+/// transactions are reported separately.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClosePositionResponse {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub long_order_create_transaction: Option<Transaction>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub long_order_fill_transaction: Option<Transaction>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub long_order_cancel_transaction: Option<Transaction>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub short_order_create_transaction: Option<Transaction>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub short_order_fill_transaction: Option<Transaction>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub short_order_cancel_transaction: Option<Transaction>,
+    #[serde(rename = "relatedTransactionIDs", default)]
+    pub related_transaction_ids: Vec<TransactionId>,
+    #[serde(
+        rename = "lastTransactionID",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub last_transaction_id: Option<TransactionId>,
+}
+
+/// How `OandaClient::flatten_all_with` goes about it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FlattenOptions {
+    /// Cancels or closes in flight at once
+    pub concurrency: usize,
+    /// Tries per order or position, including the first
+    pub max_attempts: u32,
+    /// Delay before the first retry; doubles for each one after
+    pub retry_delay: Duration,
+}
+
+impl Default for FlattenOptions {
+    fn default() -> Self {
+        Self {
+            concurrency: 4,
+            max_attempts: 3,
+            retry_delay: Duration::from_millis(500),
+        }
+    }
+}
+
+/// What `OandaClient::flatten_all` did, item by item
+#[derive(Debug, Default)]
+pub struct FlattenReport {
+    /// Pending orders and the outcome of cancelling each
+    pub cancelled: Vec<(OrderId, crate::Result<OrderResponse>)>,
+    /// Open positions by instrument and the outcome of closing each
+    pub closed: Vec<(String, crate::Result<ClosePositionResponse>)>,
+}
+
+impl FlattenReport {
+    /// Whether every cancel and close succeeded
+    pub fn is_complete(&self) -> bool {
+        self.errors().is_empty()
+    }
+
+    /// Failed items: order IDs and instruments with their errors
+    pub fn errors(&self) -> Vec<(String, &crate::Error)> {
+        let orders = self
+            .cancelled
+            .iter()
+            .filter_map(|(id, result)| result.as_ref().err().map(|e| (id.to_string(), e)));
+        let positions = self.closed.iter().filter_map(|(instrument, result)| {
+            result.as_ref().err().map(|e| (instrument.clone(), e))
+        });
+        orders.chain(positions).collect()
+    }
+}
+
+/// Why OANDA rejected an order
+///
+/// Common reasons have their own variant; the rest are kept as `Other`
+/// with the raw OANDA value.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum OrderRejectReason {
+    InsufficientMargin,
+    InsufficientLiquidity,
+    InstrumentNotTradeable,
+    MarketHalted,
+    AccountLocked,
+    AccountOrderCreationLocked,
+    UnitsInvalid,
+    UnitsPrecisionExceeded,
+    UnitsLimitExceeded,
+    UnitsMinimumNotMet,
+    PriceInvalid,
+    PricePrecisionExceeded,
+    PriceBoundInvalid,
+    StopLossOnFillLoss,
+    TakeProfitOnFillLoss,
+    TrailingStopLossOnFillPriceDistanceMinimumNotMet,
+    PositionSizeExceeded,
+    PendingOrdersAllowedExceeded,
+    OpenTradesAllowedExceeded,
+    ClientOrderIdAlreadyExists,
+    FifoViolation,
+    Other(String),
+}
+
+const REJECT_REASONS: &[(&str, OrderRejectReason)] = &[
+    ("INSUFFICIENT_MARGIN", OrderRejectReason::InsufficientMargin),
+    (
+        "INSUFFICIENT_LIQUIDITY",
+        OrderRejectReason::InsufficientLiquidity,
+    ),
+    (
+        "INSTRUMENT_NOT_TRADEABLE",
+        OrderRejectReason::InstrumentNotTradeable,
+    ),
+    ("MARKET_HALTED", OrderRejectReason::MarketHalted),
+    ("ACCOUNT_LOCKED", OrderRejectReason::AccountLocked),
+    (
+        "ACCOUNT_ORDER_CREATION_LOCKED",
+        OrderRejectReason::AccountOrderCreationLocked,
+    ),
+    ("UNITS_INVALID", OrderRejectReason::UnitsInvalid),
+    (
+        "UNITS_PRECISION_EXCEEDED",
+        OrderRejectReason::UnitsPrecisionExceeded,
+    ),
+    (
+        "UNITS_LIMIT_EXCEEDED",
+        OrderRejectReason::UnitsLimitExceeded,
+    ),
+    (
+        "UNITS_MINIMUM_NOT_MET",
+        OrderRejectReason::UnitsMinimumNotMet,
+    ),
+    ("PRICE_INVALID", OrderRejectReason::PriceInvalid),
+    (
+        "PRICE_PRECISION_EXCEEDED",
+        OrderRejectReason::PricePrecisionExceeded,
+    ),
+    ("PRICE_BOUND_INVALID", OrderRejectReason::PriceBoundInvalid),
+    (
+        "STOP_LOSS_ON_FILL_LOSS",
+        OrderRejectReason::StopLossOnFillLoss,
+    ),
+    (
+        "TAKE_PROFIT_ON_FILL_LOSS",
+        OrderRejectReason::TakeProfitOnFillLoss,
+    ),
+    (
+        "TRAILING_STOP_LOSS_ON_FILL_PRICE_DISTANCE_MINIMUM_NOT_MET",
+        OrderRejectReason::TrailingStopLossOnFillPriceDistanceMinimumNotMet,
+    ),
+    (
+        "POSITION_SIZE_EXCEEDED",
+        OrderRejectReason::PositionSizeExceeded,
+    ),
+    (
+        "PENDING_ORDERS_ALLOWED_EXCEEDED",
+        OrderRejectReason::PendingOrdersAllowedExceeded,
+    ),
+    (
+        "OPEN_TRADES_ALLOWED_EXCEEDED",
+        OrderRejectReason::OpenTradesAllowedExceeded,
+    ),
+    (
+        "CLIENT_ORDER_ID_ALREADY_EXISTS",
+        OrderRejectReason::ClientOrderIdAlreadyExists,
+    ),
+    ("FIFO_VIOLATION", OrderRejectReason::FifoViolation),
+];
+
+impl OrderRejectReason {
+    /// OANDA's name for this reason
+    pub fn as_str(&self) -> &str {
+        match self {
+            OrderRejectReason::Other(reason) => reason,
+            known => REJECT_REASONS
+                .iter()
+                .find(|(_, reason)| reason == known)
+                .map(|(name, _)| *name)
+                .unwrap_or_default(),
+        }
+    }
+}
+
+impl FromStr for OrderRejectReason {
+    type Err = std::convert::Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(REJECT_REASONS
+            .iter()
+            .find(|(name, _)| *name == s)
+            .map(|(_, reason)| reason.clone())
+            .unwrap_or_else(|| OrderRejectReason::Other(s.to_string())))
+    }
+}
+
+impl fmt::Display for OrderRejectReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Serialize for OrderRejectReason {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for OrderRejectReason {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        Ok(s.parse().unwrap_or_else(|never| match never {}))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct PendingOrdersResponse {
+    pub orders: Vec<Order>,
+    #[serde(rename = "lastTransactionID", default)]
+    pub last_transaction_id: Option<TransactionId>,
+}
+
+/// Body of a rejected order request
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct OrderRejectBody {
+    #[serde(
+        alias = "orderCancelRejectTransaction",
+        alias = "longOrderRejectTransaction",
+        alias = "shortOrderRejectTransaction"
+    )]
+    order_reject_transaction: Transaction,
+    #[serde(default)]
+    error_message: Option<String>,
+}
+
+impl OrderRejectBody {
+    /// Convert into `Error::OrderRejected`
+    pub(crate) fn into_error(self) -> crate::Error {
+        let header = self.order_reject_transaction.header();
+        let transaction_id = header.map(|h| h.id.clone());
+        let reason = match &self.order_reject_transaction {
+            Transaction::MarketOrderReject(t)
+            | Transaction::LimitOrderReject(t)
+            | Transaction::StopOrderReject(t)
+            | Transaction::MarketIfTouchedOrderReject(t)
+            | Transaction::TakeProfitOrderReject(t)
+            | Transaction::StopLossOrderReject(t)
+            | Transaction::GuaranteedStopLossOrderReject(t)
+            | Transaction::TrailingStopLossOrderReject(t) => t.reject_reason.clone(),
+            Transaction::OrderCancelReject(t) => t.reject_reason.clone(),
+            Transaction::Unknown(raw) => raw
+                .get("rejectReason")
+                .and_then(|r| r.as_str())
+                .map(str::to_string),
+            _ => None,
+        };
+        let reason = reason
+            .unwrap_or_default()
+            .parse()
+            .unwrap_or_else(|never| match never {});
+
+        crate::Error::OrderRejected {
+            message: self.error_message.unwrap_or_else(|| format!("{}", reason)),
+            reason,
+            transaction_id,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_order_request_json() {
+        let order = OrderRequest::market("EUR_USD", dec!(100))
+            .time_in_force(TimeInForce::Fok)
+            .stop_loss(dec!(1.0950));
+        let json = serde_json::to_value(&order).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "type": "MARKET",
+                "instrument": "EUR_USD",
+                "units": "100",
+                "timeInForce": "FOK",
+                "stopLossOnFill": {"price": "1.0950"}
+            })
+        );
+    }
+
+    #[test]
+    fn test_reject_reason_roundtrip() {
+        let reason: OrderRejectReason = "INSUFFICIENT_MARGIN".parse().unwrap();
+        assert_eq!(reason, OrderRejectReason::InsufficientMargin);
+        assert_eq!(reason.to_string(), "INSUFFICIENT_MARGIN");
+
+        let other: OrderRejectReason = "TAG_TOO_LONG".parse().unwrap();
+        assert_eq!(other, OrderRejectReason::Other("TAG_TOO_LONG".to_string()));
+        assert_eq!(other.as_str(), "TAG_TOO_LONG");
+    }
+
+    #[test]
+    fn test_reject_body_into_error() {
+        let body: OrderRejectBody = serde_json::from_str(
+            r#"{
+                "orderRejectTransaction": {
+                    "id": "6372", "accountID": "101-001-1-001", "userID": 1,
+                    "batchID": "6372", "time": "2024-01-01T12:00:00.000000000Z",
+                    "type": "MARKET_ORDER_REJECT", "instrument": "EUR_USD",
+                    "units": "100000000", "rejectReason": "INSUFFICIENT_MARGIN"
+                },
+                "errorCode": "INSUFFICIENT_MARGIN",
+                "errorMessage": "Insufficient margin"
+            }"#,
+        )
+        .unwrap();
+
+        match body.into_error() {
+            crate::Error::OrderRejected {
+                reason,
+                message,
+                transaction_id,
+            } => {
+                assert_eq!(reason, OrderRejectReason::InsufficientMargin);
+                assert_eq!(message, "Insufficient margin");
+                assert_eq!(transaction_id.unwrap().as_str(), "6372");
+            }
+            other => panic!("expected OrderRejected, got {:?}", other),
+        }
+    }
+}
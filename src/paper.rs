@@ -0,0 +1,774 @@
+//! Paper trading against live prices
+//!
+//! `PaperTrader` implements [`Trading`] like the live client, but fills
+//! orders locally against the prices it is fed instead of calling the
+//! orders endpoint. Feed it the pricing stream (or poll with
+//! [`OandaClient`]) and run a strategy against it unchanged.
+//!
+//! ```no_run
+//! use oanda_connector::orders::OrderRequest;
+//! use oanda_connector::paper::{PaperConfig, PaperTrader};
+//! use oanda_connector::trading::Trading;
+//! use oanda_connector::{OandaClient, OandaConfig};
+//! use rust_decimal_macros::dec;
+//!
+//! # async fn run() -> oanda_connector::Result<()> {
+//! let client = OandaClient::new(OandaConfig::from_env()?)?;
+//! let paper = PaperTrader::with_client(client, PaperConfig {
+//!     slippage_bps: dec!(0.5),
+//!     ..PaperConfig::default()
+//! });
+//!
+//! paper.place_order(&OrderRequest::market("EUR_USD", dec!(10000))).await?;
+//! println!("{:?}", paper.get_account_summary().await?);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Positions are netted per instrument and P/L is taken to be in the
+//! account currency, without conversion. Liquidity is unlimited. Price
+//! bounds, dependent orders (stop loss, take profit and trailing stop on
+//! fill), `position_fill` and GTD expiry are not simulated, and financing is
+//! only charged when [`PaperTrader::apply_financing`] is called.
+
+use crate::client::OandaClient;
+use crate::error::{Error, Result};
+use crate::ids::{AccountId, OrderId, TradeId, TransactionId};
+use crate::models::{AccountSummary, InstrumentFinancing, Position, PositionSide, Tick};
+use crate::orders::{OrderRejectReason, OrderRequest, OrderResponse, OrderType};
+use crate::trading::Trading;
+use crate::transactions::{
+    DailyFinancingTransaction, OrderFillTransaction, OrderTransaction, PositionFinancing,
+    TradeOpen, Transaction, TransactionHeader,
+};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures::{Stream, StreamExt};
+use rust_decimal::prelude::Signed;
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// Price a simulated order fills at, before slippage
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SpreadModel {
+    /// Buy at the ask, sell at the bid
+    Quoted,
+    /// Fill at the mid price, ignoring the spread
+    Mid,
+    /// Quoted spread scaled by a factor around the mid, e.g. 1.5 to model
+    /// execution during news
+    Scaled(Decimal),
+}
+
+/// Simulated account settings
+#[derive(Debug, Clone, PartialEq)]
+pub struct PaperConfig {
+    pub initial_balance: Decimal,
+    pub currency: String,
+    /// Margin required per unit of notional, e.g. 0.02 for 50:1 leverage
+    pub margin_rate: Decimal,
+    /// Adverse slippage applied to every fill, in basis points of price
+    pub slippage_bps: Decimal,
+    pub spread: SpreadModel,
+}
+
+impl Default for PaperConfig {
+    fn default() -> Self {
+        Self {
+            initial_balance: Decimal::from(100_000),
+            currency: "USD".to_string(),
+            margin_rate: Decimal::new(2, 2),
+            slippage_bps: Decimal::ZERO,
+            spread: SpreadModel::Quoted,
+        }
+    }
+}
+
+/// Net position in one instrument
+#[derive(Debug, Clone, PartialEq)]
+pub struct PaperPosition {
+    pub instrument: String,
+    /// Signed: positive long, negative short
+    pub units: Decimal,
+    pub average_price: Decimal,
+}
+
+impl PaperPosition {
+    /// The position as the API would report it
+    ///
+    /// Realized P/L isn't tracked per instrument and is reported as zero.
+    pub fn to_position(&self) -> Position {
+        let side = |units: Decimal| PositionSide {
+            units,
+            average_price: (!units.is_zero()).then_some(self.average_price),
+            trade_ids: Vec::new(),
+            unrealized_pl: None,
+        };
+        let (long, short) = if self.units.is_sign_positive() {
+            (self.units, Decimal::ZERO)
+        } else {
+            (Decimal::ZERO, self.units)
+        };
+        Position {
+            instrument: self.instrument.clone(),
+            pl: Decimal::ZERO,
+            unrealized_pl: None,
+            margin_used: None,
+            long: side(long),
+            short: side(short),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct PaperState {
+    balance: Decimal,
+    realized_pl: Decimal,
+    positions: HashMap<String, PaperPosition>,
+    pending: Vec<(OrderId, OrderRequest)>,
+    prices: HashMap<String, Tick>,
+    transactions: Vec<Transaction>,
+    last_id: u64,
+}
+
+/// Simulated account filling orders locally
+///
+/// Cheap to clone; clones share the account.
+#[derive(Clone)]
+pub struct PaperTrader {
+    config: Arc<PaperConfig>,
+    client: Option<OandaClient>,
+    state: Arc<Mutex<PaperState>>,
+}
+
+impl PaperTrader {
+    /// Simulated account priced only by ticks passed to `on_tick` or `run`
+    pub fn new(config: PaperConfig) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(PaperState {
+                balance: config.initial_balance,
+                realized_pl: Decimal::ZERO,
+                positions: HashMap::new(),
+                pending: Vec::new(),
+                prices: HashMap::new(),
+                transactions: Vec::new(),
+                last_id: 0,
+            })),
+            config: Arc::new(config),
+            client: None,
+        }
+    }
+
+    /// Simulated account that fetches prices from `client`
+    ///
+    /// `get_current_price` and market orders use a fresh price from the API
+    /// whenever they are called; ticks fed to `on_tick` still trigger
+    /// pending orders.
+    pub fn with_client(client: OandaClient, config: PaperConfig) -> Self {
+        Self {
+            client: Some(client),
+            ..Self::new(config)
+        }
+    }
+
+    /// Record a price and fill any pending orders it triggers
+    ///
+    /// Returns the fills, in the order they happened.
+    pub fn on_tick(&self, tick: &Tick) -> Vec<OrderResponse> {
+        let mut state = self.state.lock().unwrap();
+        state.prices.insert(tick.instrument.clone(), tick.clone());
+        if !tick.tradeable {
+            return Vec::new();
+        }
+
+        let pending = std::mem::take(&mut state.pending);
+        let mut fills = Vec::new();
+        for (id, order) in pending {
+            if order.instrument != tick.instrument {
+                state.pending.push((id, order));
+                continue;
+            }
+            match self.trigger_price(&order, tick) {
+                Some(price) => match self.fill(&mut state, &id, &order, price, tick.timestamp) {
+                    Ok(fill) => fills.push(OrderResponse {
+                        order_create_transaction: None,
+                        last_transaction_id: Some(TransactionId::from(state.last_id)),
+                        related_transaction_ids: vec![fill.header.id.clone()],
+                        order_fill_transaction: Some(Transaction::OrderFill(fill)),
+                        order_cancel_transaction: None,
+                    }),
+                    // Not enough margin when triggered: keep waiting
+                    Err(_) => state.pending.push((id, order)),
+                },
+                None => state.pending.push((id, order)),
+            }
+        }
+        fills
+    }
+
+    /// Apply every tick from a price stream until it ends or fails
+    pub async fn run<S>(&self, ticks: S) -> Result<()>
+    where
+        S: Stream<Item = Result<Tick>>,
+    {
+        futures::pin_mut!(ticks);
+        while let Some(tick) = ticks.next().await {
+            self.on_tick(&tick?);
+        }
+        Ok(())
+    }
+
+    /// Open positions
+    pub fn positions(&self) -> Vec<PaperPosition> {
+        let state = self.state.lock().unwrap();
+        state.positions.values().cloned().collect()
+    }
+
+    /// Orders waiting for their trigger price
+    pub fn pending_orders(&self) -> Vec<(OrderId, OrderRequest)> {
+        self.state.lock().unwrap().pending.clone()
+    }
+
+    /// Cancel a pending order; returns whether it was pending
+    pub fn cancel_order(&self, id: &OrderId) -> bool {
+        let mut state = self.state.lock().unwrap();
+        let before = state.pending.len();
+        state.pending.retain(|(pending, _)| pending != id);
+        state.pending.len() != before
+    }
+
+    /// Every simulated transaction so far
+    pub fn transactions(&self) -> Vec<Transaction> {
+        self.state.lock().unwrap().transactions.clone()
+    }
+
+    /// Balance, NAV, P/L and margin at the latest prices
+    pub fn account_summary(&self) -> AccountSummary {
+        let state = self.state.lock().unwrap();
+        let (nav, unrealized_pl) = self.valuation(&state);
+        let margin_used: Decimal = state
+            .positions
+            .values()
+            .map(|position| self.margin(&state, position))
+            .sum();
+        AccountSummary {
+            id: "paper".to_string(),
+            balance: state.balance,
+            nav,
+            unrealized_pl,
+            realized_pl: state.realized_pl,
+            margin_used,
+            margin_available: nav - margin_used,
+            open_trade_count: state.positions.len() as i32,
+            open_position_count: state.positions.len() as i32,
+            currency: self.config.currency.clone(),
+            hedging_enabled: false,
+        }
+    }
+
+    /// Charge one rollover's financing on the open positions
+    ///
+    /// Each position pays or earns `units * mid * rate / 365` for every day
+    /// its instrument charges on `time`'s weekday (e.g. three on Wednesdays).
+    /// Instruments missing from `rates` are not charged. Returns the total
+    /// applied to the balance.
+    pub fn apply_financing(
+        &self,
+        rates: &HashMap<String, InstrumentFinancing>,
+        time: DateTime<Utc>,
+    ) -> Decimal {
+        let mut state = self.state.lock().unwrap();
+        let weekday = time.format("%A").to_string().to_uppercase();
+        let mut position_financings = Vec::new();
+        for position in state.positions.values() {
+            let Some(financing) = rates.get(&position.instrument) else {
+                continue;
+            };
+            let rate = if position.units.is_sign_positive() {
+                financing.long_rate
+            } else {
+                financing.short_rate
+            };
+            let price = state
+                .prices
+                .get(&position.instrument)
+                .map(Tick::mid)
+                .unwrap_or(position.average_price);
+            let days = Decimal::from(financing.days_charged(&weekday));
+            let amount = position.units.abs() * price * rate * days / Decimal::from(365);
+            if !amount.is_zero() {
+                position_financings.push((position.instrument.clone(), amount));
+            }
+        }
+        if position_financings.is_empty() {
+            return Decimal::ZERO;
+        }
+
+        let total: Decimal = position_financings.iter().map(|(_, amount)| amount).sum();
+        state.balance += total;
+        let header = Self::header(&mut state, time);
+        let transaction = DailyFinancingTransaction {
+            header,
+            financing: total,
+            account_balance: Some(state.balance),
+            account_financing_mode: Some("DAILY".to_string()),
+            position_financings: position_financings
+                .into_iter()
+                .map(|(instrument, amount)| PositionFinancing {
+                    instrument,
+                    financing: amount,
+                    open_trade_financings: Vec::new(),
+                })
+                .collect(),
+        };
+        state
+            .transactions
+            .push(Transaction::DailyFinancing(transaction));
+        total
+    }
+
+    /// Price the order fills at on this tick, if it triggers
+    fn trigger_price(&self, order: &OrderRequest, tick: &Tick) -> Option<Decimal> {
+        let buy = !order.is_sell();
+        let market = self.quote(tick, buy);
+        let better = |limit: Decimal| {
+            if buy {
+                market <= limit
+            } else {
+                market >= limit
+            }
+        };
+        let slipped = self.slipped(market, buy);
+        match (order.order_type, order.price) {
+            (OrderType::Market, _) => Some(slipped),
+            // Limits never fill beyond their price
+            (OrderType::Limit, Some(limit)) if better(limit) => Some(if buy {
+                slipped.min(limit)
+            } else {
+                slipped.max(limit)
+            }),
+            (OrderType::MarketIfTouched, Some(price)) if better(price) => Some(slipped),
+            (OrderType::Stop, Some(stop)) if market == stop || !better(stop) => Some(slipped),
+            _ => None,
+        }
+    }
+
+    /// Side of the book an order trades against under the spread model
+    fn quote(&self, tick: &Tick, buy: bool) -> Decimal {
+        let half_spread = match self.config.spread {
+            SpreadModel::Quoted => tick.spread() / Decimal::TWO,
+            SpreadModel::Mid => Decimal::ZERO,
+            SpreadModel::Scaled(factor) => tick.spread() * factor / Decimal::TWO,
+        };
+        if buy {
+            tick.mid() + half_spread
+        } else {
+            tick.mid() - half_spread
+        }
+    }
+
+    fn slipped(&self, price: Decimal, buy: bool) -> Decimal {
+        let slippage = price * self.config.slippage_bps / Decimal::from(10_000);
+        if buy {
+            price + slippage
+        } else {
+            price - slippage
+        }
+    }
+
+    fn header(state: &mut PaperState, time: DateTime<Utc>) -> TransactionHeader {
+        state.last_id += 1;
+        TransactionHeader {
+            id: TransactionId::from(state.last_id),
+            time,
+            user_id: None,
+            account_id: AccountId::new("paper").expect("valid account ID"),
+            batch_id: TransactionId::from(state.last_id),
+            request_id: None,
+        }
+    }
+
+    /// Apply a fill to the account, if margin allows
+    fn fill(
+        &self,
+        state: &mut PaperState,
+        order_id: &OrderId,
+        order: &OrderRequest,
+        price: Decimal,
+        time: DateTime<Utc>,
+    ) -> Result<OrderFillTransaction> {
+        let position = state
+            .positions
+            .get(&order.instrument)
+            .cloned()
+            .unwrap_or(PaperPosition {
+                instrument: order.instrument.clone(),
+                units: Decimal::ZERO,
+                average_price: price,
+            });
+        let units = position.units + order.units;
+
+        // Margin after the fill, with this instrument's position replaced
+        let other_margin: Decimal = state
+            .positions
+            .values()
+            .filter(|p| p.instrument != order.instrument)
+            .map(|p| self.margin(state, p))
+            .sum();
+        let required = other_margin + units.abs() * price * self.config.margin_rate;
+        let (nav, _) = self.valuation(state);
+        if required > nav {
+            return Err(Error::OrderRejected {
+                reason: OrderRejectReason::InsufficientMargin,
+                message: format!("margin required {} exceeds NAV {}", required, nav),
+                transaction_id: None,
+            });
+        }
+
+        // Units closed against the existing position realize P/L
+        let opposite = position.units.is_sign_positive() != order.units.is_sign_positive();
+        let closed = if opposite && !position.units.is_zero() {
+            order.units.abs().min(position.units.abs())
+        } else {
+            Decimal::ZERO
+        };
+        let pl = closed * (price - position.average_price) * position.units.signum();
+        let average_price = if units.is_zero() {
+            price
+        } else if units.is_sign_positive() != position.units.is_sign_positive()
+            || position.units.is_zero()
+        {
+            // Flipped or newly opened
+            price
+        } else if closed.is_zero() {
+            (position.units * position.average_price + order.units * price) / units
+        } else {
+            position.average_price
+        };
+
+        state.balance += pl;
+        state.realized_pl += pl;
+        if units.is_zero() {
+            state.positions.remove(&order.instrument);
+        } else {
+            state.positions.insert(
+                order.instrument.clone(),
+                PaperPosition {
+                    instrument: order.instrument.clone(),
+                    units,
+                    average_price,
+                },
+            );
+        }
+
+        let header = Self::header(state, time);
+        let opened = order.units.abs() - closed;
+        let fill = OrderFillTransaction {
+            order_id: order_id.clone(),
+            client_order_id: order
+                .client_extensions
+                .as_ref()
+                .and_then(|extensions| extensions.id.clone()),
+            instrument: order.instrument.clone(),
+            units: order.units,
+            price: Some(price),
+            reason: Some(format!("{}_ORDER", order_type_name(order.order_type))),
+            pl,
+            financing: Decimal::ZERO,
+            commission: Decimal::ZERO,
+            guaranteed_execution_fee: None,
+            half_spread_cost: None,
+            account_balance: Some(state.balance),
+            trade_opened: (!opened.is_zero()).then(|| TradeOpen {
+                trade_id: TradeId::from(header.id.as_u64()),
+                units: opened * order.units.signum(),
+                price: Some(price),
+                half_spread_cost: None,
+            }),
+            trades_closed: Vec::new(),
+            trade_reduced: None,
+            header,
+        };
+        state
+            .transactions
+            .push(Transaction::OrderFill(fill.clone()));
+        Ok(fill)
+    }
+
+    /// Margin held by a position at its latest price
+    fn margin(&self, state: &PaperState, position: &PaperPosition) -> Decimal {
+        let price = state
+            .prices
+            .get(&position.instrument)
+            .map(Tick::mid)
+            .unwrap_or(position.average_price);
+        position.units.abs() * price * self.config.margin_rate
+    }
+
+    /// NAV and unrealized P/L, valuing positions at closeout prices
+    fn valuation(&self, state: &PaperState) -> (Decimal, Decimal) {
+        let unrealized: Decimal = state
+            .positions
+            .values()
+            .map(|position| {
+                let price = match state.prices.get(&position.instrument) {
+                    Some(tick) if position.units.is_sign_positive() => tick.bid,
+                    Some(tick) => tick.ask,
+                    None => position.average_price,
+                };
+                position.units * (price - position.average_price)
+            })
+            .sum();
+        (state.balance + unrealized, unrealized)
+    }
+
+    fn cached_price(&self, instrument: &str) -> Option<Tick> {
+        self.state.lock().unwrap().prices.get(instrument).cloned()
+    }
+}
+
+fn order_type_name(order_type: OrderType) -> &'static str {
+    match order_type {
+        OrderType::Market => "MARKET",
+        OrderType::Limit => "LIMIT",
+        OrderType::Stop => "STOP",
+        OrderType::MarketIfTouched => "MARKET_IF_TOUCHED",
+    }
+}
+
+#[async_trait]
+impl Trading for PaperTrader {
+    async fn get_current_price(&self, instrument: &str) -> Result<Tick> {
+        match &self.client {
+            Some(client) => {
+                let tick = client.get_current_price(instrument).await?;
+                self.state
+                    .lock()
+                    .unwrap()
+                    .prices
+                    .insert(tick.instrument.clone(), tick.clone());
+                Ok(tick)
+            }
+            None => self.cached_price(instrument).ok_or_else(|| {
+                Error::InvalidInstrument(format!("no price yet for {}", instrument))
+            }),
+        }
+    }
+
+    async fn get_account_summary(&self) -> Result<AccountSummary> {
+        Ok(self.account_summary())
+    }
+
+    async fn get_open_positions(&self) -> Result<Vec<Position>> {
+        Ok(self
+            .positions()
+            .iter()
+            .map(PaperPosition::to_position)
+            .collect())
+    }
+
+    async fn place_order(&self, order: &OrderRequest) -> Result<OrderResponse> {
+        if order.units.is_zero() {
+            return Err(Error::OrderRejected {
+                reason: OrderRejectReason::UnitsInvalid,
+                message: "units must not be zero".to_string(),
+                transaction_id: None,
+            });
+        }
+        if order.order_type != OrderType::Market && order.price.is_none() {
+            return Err(Error::OrderRejected {
+                reason: OrderRejectReason::PriceInvalid,
+                message: "price required".to_string(),
+                transaction_id: None,
+            });
+        }
+
+        let tick = match order.order_type {
+            OrderType::Market => Some(self.get_current_price(&order.instrument).await?),
+            _ => self.cached_price(&order.instrument),
+        };
+        if order.order_type == OrderType::Market && !tick.as_ref().is_some_and(|t| t.tradeable) {
+            return Err(Error::OrderRejected {
+                reason: OrderRejectReason::MarketHalted,
+                message: format!("{} is not tradeable", order.instrument),
+                transaction_id: None,
+            });
+        }
+
+        let mut state = self.state.lock().unwrap();
+        let time = tick.as_ref().map_or_else(Utc::now, |t| t.timestamp);
+        let header = Self::header(&mut state, time);
+        let order_id = OrderId::from(header.id.as_u64());
+        let create = OrderTransaction {
+            instrument: Some(order.instrument.clone()),
+            units: Some(order.units),
+            price: order.price,
+            price_bound: None,
+            distance: None,
+            trade_id: None,
+            time_in_force: order
+                .time_in_force
+                .map(|tif| format!("{:?}", tif).to_uppercase()),
+            gtd_time: None,
+            position_fill: None,
+            trigger_condition: None,
+            reason: Some("CLIENT_ORDER".to_string()),
+            client_extensions: order.client_extensions.clone(),
+            replaces_order_id: None,
+            reject_reason: None,
+            header,
+        };
+        let create = match order.order_type {
+            OrderType::Market => Transaction::MarketOrder(create),
+            OrderType::Limit => Transaction::LimitOrder(create),
+            OrderType::Stop => Transaction::StopOrder(create),
+            OrderType::MarketIfTouched => Transaction::MarketIfTouchedOrder(create),
+        };
+        state.transactions.push(create.clone());
+
+        let trigger = tick.as_ref().filter(|t| t.tradeable).and_then(|t| {
+            self.trigger_price(order, t)
+                .map(|price| (price, t.timestamp))
+        });
+        let fill = match trigger {
+            Some((price, time)) => Some(self.fill(&mut state, &order_id, order, price, time)?),
+            None => {
+                state.pending.push((order_id, order.clone()));
+                None
+            }
+        };
+
+        let mut related_transaction_ids = vec![create.id().map(TransactionId::new).transpose()?]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>();
+        related_transaction_ids.extend(fill.as_ref().map(|fill| fill.header.id.clone()));
+        Ok(OrderResponse {
+            order_create_transaction: Some(create),
+            order_fill_transaction: fill.map(Transaction::OrderFill),
+            order_cancel_transaction: None,
+            related_transaction_ids,
+            last_transaction_id: Some(TransactionId::from(state.last_id)),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::PriceStatus;
+    use rust_decimal_macros::dec;
+
+    fn tick(bid: Decimal, ask: Decimal) -> Tick {
+        Tick {
+            instrument: "EUR_USD".to_string(),
+            timestamp: Utc::now(),
+            bid,
+            ask,
+            tradeable: true,
+            status: PriceStatus::Tradeable,
+            closeout_bid: None,
+            closeout_ask: None,
+        }
+    }
+
+    fn fill_price(response: &OrderResponse) -> Option<Decimal> {
+        match response.order_fill_transaction.as_ref()? {
+            Transaction::OrderFill(fill) => fill.price,
+            _ => None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_market_round_trip() {
+        let paper = PaperTrader::new(PaperConfig::default());
+        paper.on_tick(&tick(dec!(1.1000), dec!(1.1002)));
+
+        let buy = paper
+            .place_order(&OrderRequest::market("EUR_USD", dec!(10000)))
+            .await
+            .unwrap();
+        assert_eq!(fill_price(&buy), Some(dec!(1.1002)));
+        assert_eq!(paper.positions()[0].units, dec!(10000));
+
+        paper.on_tick(&tick(dec!(1.1050), dec!(1.1052)));
+        let summary = paper.get_account_summary().await.unwrap();
+        assert_eq!(summary.unrealized_pl, dec!(48));
+
+        paper
+            .place_order(&OrderRequest::market("EUR_USD", dec!(-10000)))
+            .await
+            .unwrap();
+        let summary = paper.get_account_summary().await.unwrap();
+        assert!(paper.positions().is_empty());
+        assert_eq!(summary.realized_pl, dec!(48));
+        assert_eq!(summary.balance, dec!(100048));
+        assert_eq!(paper.transactions().len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_pending_orders_trigger_on_ticks() {
+        let paper = PaperTrader::new(PaperConfig::default());
+        paper.on_tick(&tick(dec!(1.1000), dec!(1.1002)));
+
+        let limit = paper
+            .place_order(&OrderRequest::limit("EUR_USD", dec!(1000), dec!(1.0990)))
+            .await
+            .unwrap();
+        assert!(limit.order_fill_transaction.is_none());
+        paper
+            .place_order(&OrderRequest::stop("EUR_USD", dec!(-1000), dec!(1.0900)))
+            .await
+            .unwrap();
+        assert_eq!(paper.pending_orders().len(), 2);
+
+        // Ask drops through the limit: filled at the better market price
+        let fills = paper.on_tick(&tick(dec!(1.0985), dec!(1.0987)));
+        assert_eq!(fills.len(), 1);
+        assert_eq!(fill_price(&fills[0]), Some(dec!(1.0987)));
+        assert_eq!(paper.pending_orders().len(), 1);
+
+        let stop_id = paper.pending_orders()[0].0.clone();
+        assert!(paper.cancel_order(&stop_id));
+        assert!(paper.on_tick(&tick(dec!(1.0800), dec!(1.0802))).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_spread_model_and_slippage() {
+        let paper = PaperTrader::new(PaperConfig {
+            spread: SpreadModel::Scaled(dec!(2)),
+            slippage_bps: dec!(1),
+            ..PaperConfig::default()
+        });
+        paper.on_tick(&tick(dec!(1.0000), dec!(1.0002)));
+
+        // Mid 1.0001, doubled half spread 0.0002, then 1bp of slippage
+        let sell = paper
+            .place_order(&OrderRequest::market("EUR_USD", dec!(-1000)))
+            .await
+            .unwrap();
+        assert_eq!(fill_price(&sell), Some(dec!(0.99980001)));
+    }
+
+    #[tokio::test]
+    async fn test_insufficient_margin() {
+        let paper = PaperTrader::new(PaperConfig {
+            initial_balance: dec!(1000),
+            ..PaperConfig::default()
+        });
+        paper.on_tick(&tick(dec!(1.1000), dec!(1.1002)));
+
+        let result = paper
+            .place_order(&OrderRequest::market("EUR_USD", dec!(100000)))
+            .await;
+        assert!(matches!(
+            result,
+            Err(Error::OrderRejected {
+                reason: OrderRejectReason::InsufficientMargin,
+                ..
+            })
+        ));
+        assert!(paper.positions().is_empty());
+    }
+}
@@ -0,0 +1,608 @@
+//! Combined view of account, trades and positions
+//!
+//! A [`PortfolioSnapshot`] joins the account summary, open trades and open
+//! positions, and breaks exposure down per instrument and per currency.
+//! [`Portfolio`] keeps one up to date in the background, so dashboards can
+//! read it without reassembling it from three endpoints themselves. It
+//! refreshes on a fixed interval, or with
+//! [`spawn_on_transactions`](Portfolio::spawn_on_transactions) only when the
+//! account's transaction log has moved on.
+//!
+//! ```no_run
+//! use oanda_connector::portfolio::Portfolio;
+//! use oanda_connector::{OandaClient, OandaConfig};
+//! use std::time::Duration;
+//!
+//! # async fn run() -> oanda_connector::Result<()> {
+//! let client = OandaClient::new(OandaConfig::from_env()?)?;
+//! let portfolio = Portfolio::spawn(client.clone(), Duration::from_secs(5));
+//!
+//! let mut updates = portfolio.subscribe();
+//! while updates.changed().await.is_ok() {
+//!     let Some(snapshot) = updates.borrow().clone() else { continue };
+//!     println!("NAV {}", snapshot.summary.nav);
+//!     for (currency, units) in &snapshot.by_currency {
+//!         println!("  {}: {}", currency, units);
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::client::OandaClient;
+use crate::conversion::CurrencyConverter;
+use crate::error::Result;
+use crate::ids::TransactionId;
+use crate::models::{AccountSummary, Position, PositionMode, Trade};
+use crate::orders::{OrderRequest, PositionFill};
+use crate::shutdown::ShutdownHandle;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::watch;
+
+/// Exposure in one instrument
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct InstrumentExposure {
+    pub long_units: Decimal,
+    /// Negative, like OANDA's short position units
+    pub short_units: Decimal,
+    pub net_units: Decimal,
+    pub trade_count: usize,
+    pub unrealized_pl: Decimal,
+    pub margin_used: Decimal,
+    /// Signed value of the units at their average open price, in the
+    /// instrument's quote currency
+    pub notional: Decimal,
+}
+
+/// One currency's share of the account's positions
+///
+/// Amounts are in the currency itself; values are the same amounts in the
+/// account currency.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CurrencyExposure {
+    /// Sum of the legs that are long this currency
+    pub long: Decimal,
+    /// Sum of the legs that are short this currency (negative)
+    pub short: Decimal,
+    pub net: Decimal,
+    pub long_value: Decimal,
+    pub short_value: Decimal,
+    pub net_value: Decimal,
+}
+
+/// Positions broken down into per-currency legs, valued in the account
+/// currency
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExposureReport {
+    pub account_currency: String,
+    pub by_currency: BTreeMap<String, CurrencyExposure>,
+    /// Currencies left out of `by_currency` for lack of a conversion rate
+    pub unpriced: Vec<String>,
+}
+
+impl ExposureReport {
+    /// Exposure in one currency, if any position has a leg in it
+    pub fn currency(&self, currency: &str) -> Option<&CurrencyExposure> {
+        self.by_currency.get(currency)
+    }
+
+    /// Sum of the absolute net values, in the account currency
+    ///
+    /// The account currency itself is left out, since holding it is not a
+    /// currency risk.
+    pub fn gross_value(&self) -> Decimal {
+        self.by_currency
+            .iter()
+            .filter(|(currency, _)| **currency != self.account_currency)
+            .map(|(_, exposure)| exposure.net_value.abs())
+            .sum()
+    }
+}
+
+/// Account summary, trades and positions fetched together
+#[derive(Debug, Clone)]
+pub struct PortfolioSnapshot {
+    pub summary: AccountSummary,
+    pub trades: Vec<Trade>,
+    pub positions: Vec<Position>,
+    pub by_instrument: BTreeMap<String, InstrumentExposure>,
+    /// Net amount held per currency: the `net` of each currency's legs, see
+    /// [`exposure_report`](Self::exposure_report)
+    pub by_currency: BTreeMap<String, Decimal>,
+    /// Latest transaction reflected in the snapshot, if OANDA reported it
+    pub last_transaction_id: Option<TransactionId>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl PortfolioSnapshot {
+    /// Build a snapshot from already-fetched parts
+    pub fn new(summary: AccountSummary, trades: Vec<Trade>, positions: Vec<Position>) -> Self {
+        let mut by_instrument: BTreeMap<String, InstrumentExposure> = BTreeMap::new();
+
+        for position in &positions {
+            let side_notional = |side: &crate::models::PositionSide| {
+                side.units * side.average_price.unwrap_or_default()
+            };
+            let notional = side_notional(&position.long) + side_notional(&position.short);
+            let exposure = by_instrument
+                .entry(position.instrument.clone())
+                .or_default();
+            exposure.long_units = position.long.units;
+            exposure.short_units = position.short.units;
+            exposure.net_units = position.net_units();
+            exposure.unrealized_pl = position.unrealized_pl.unwrap_or_default();
+            exposure.margin_used = position.margin_used.unwrap_or_default();
+            exposure.notional = notional;
+        }
+        for trade in &trades {
+            by_instrument
+                .entry(trade.instrument.clone())
+                .or_default()
+                .trade_count += 1;
+        }
+        let by_currency = currency_legs(&positions)
+            .into_iter()
+            .map(|(currency, legs)| (currency, legs.net))
+            .collect();
+
+        Self {
+            summary,
+            trades,
+            positions,
+            by_instrument,
+            by_currency,
+            last_transaction_id: None,
+            updated_at: Utc::now(),
+        }
+    }
+
+    /// Fetch the summary, trades and positions concurrently
+    pub async fn fetch(client: &OandaClient) -> Result<Self> {
+        let (summary, trades, positions) = futures::try_join!(
+            client.get_account_summary(),
+            client.get_open_trades(),
+            client.get_open_positions()
+        )?;
+        Ok(Self {
+            last_transaction_id: client.last_transaction_id(),
+            ..Self::new(summary, trades, positions)
+        })
+    }
+
+    /// Exposure in an instrument, if the account holds it
+    pub fn exposure(&self, instrument: &str) -> Option<&InstrumentExposure> {
+        self.by_instrument.get(instrument)
+    }
+
+    /// Net amount held in a currency (zero if none)
+    pub fn currency_exposure(&self, currency: &str) -> Decimal {
+        self.by_currency.get(currency).copied().unwrap_or_default()
+    }
+
+    /// Break positions down into currency legs valued at `rates`
+    ///
+    /// Each side of a position is long its units of the base currency and
+    /// short their value at the average open price in the quote currency,
+    /// or the reverse for shorts, so a long EUR_USD and a long USD_JPY
+    /// offset each other's USD leg. `rates` gives the value of one unit of
+    /// each currency in the account currency; currencies missing from it
+    /// are left out and listed in `unpriced`.
+    pub fn exposure_report(&self, rates: &HashMap<String, Decimal>) -> ExposureReport {
+        let account_currency = self.summary.currency.clone();
+        let mut by_currency = currency_legs(&self.positions);
+        let mut unpriced = Vec::new();
+
+        by_currency.retain(|currency, exposure| {
+            let rate = match rates.get(currency) {
+                Some(rate) => *rate,
+                None if *currency == account_currency => Decimal::ONE,
+                None => {
+                    unpriced.push(currency.clone());
+                    return false;
+                }
+            };
+            exposure.long_value = exposure.long * rate;
+            exposure.short_value = exposure.short * rate;
+            exposure.net_value = exposure.net * rate;
+            true
+        });
+
+        ExposureReport {
+            account_currency,
+            by_currency,
+            unpriced,
+        }
+    }
+
+    /// Break positions down into currency legs valued at current prices
+    ///
+    /// All rates are priced with one request. Currencies the converter has
+    /// no path or price for are listed in `unpriced`; see
+    /// [`exposure_report`](Self::exposure_report).
+    pub async fn currency_exposure_report(
+        &self,
+        converter: &CurrencyConverter,
+    ) -> Result<ExposureReport> {
+        let currencies = self
+            .positions
+            .iter()
+            .flat_map(|position| position.instrument.split('_'));
+        let rates = converter.rates(currencies, &self.summary.currency).await?;
+        Ok(self.exposure_report(&rates))
+    }
+
+    /// Whether the account nets or hedges trades
+    pub fn position_mode(&self) -> PositionMode {
+        self.summary.position_mode()
+    }
+
+    /// Open trades in an instrument, oldest first
+    pub fn trades_in(&self, instrument: &str) -> Vec<&Trade> {
+        let mut trades: Vec<&Trade> = self
+            .trades
+            .iter()
+            .filter(|trade| trade.instrument == instrument)
+            .collect();
+        trades.sort_by_key(|trade| trade.open_time);
+        trades
+    }
+
+    /// Units held in an instrument with long and short trades netted
+    ///
+    /// On a hedging account a long and a short trade of the same size net
+    /// to zero here, although both stay open.
+    pub fn net_position(&self, instrument: &str) -> Decimal {
+        self.trades_in(instrument)
+            .iter()
+            .map(|trade| trade.current_units)
+            .sum()
+    }
+
+    /// Units held in an instrument counting both directions
+    ///
+    /// Equals the absolute net position on a netting account.
+    pub fn gross_position(&self, instrument: &str) -> Decimal {
+        self.trades_in(instrument)
+            .iter()
+            .map(|trade| trade.current_units.abs())
+            .sum()
+    }
+
+    /// Market order reducing the net position in an instrument by up to
+    /// `units`, or `None` if it is flat
+    ///
+    /// On a hedging account the order is `ReduceOnly`, so it closes
+    /// existing trades (oldest first) instead of opening an opposing one.
+    pub fn reduce_order(&self, instrument: &str, units: Decimal) -> Option<OrderRequest> {
+        let net = self.net_position(instrument);
+        if net.is_zero() {
+            return None;
+        }
+        let units = units.abs().min(net.abs());
+        let units = if net > Decimal::ZERO { -units } else { units };
+        let order = OrderRequest::market(instrument, units);
+        Some(match self.position_mode() {
+            PositionMode::Netting => order,
+            PositionMode::Hedging => order.position_fill(PositionFill::ReduceOnly),
+        })
+    }
+}
+
+/// Long and short legs per currency, not yet valued
+fn currency_legs(positions: &[Position]) -> BTreeMap<String, CurrencyExposure> {
+    let mut legs: BTreeMap<String, CurrencyExposure> = BTreeMap::new();
+    let mut add = |currency: &str, amount: Decimal| {
+        let exposure = legs.entry(currency.to_string()).or_default();
+        if amount > Decimal::ZERO {
+            exposure.long += amount;
+        } else {
+            exposure.short += amount;
+        }
+        exposure.net += amount;
+    };
+
+    for position in positions {
+        let Some((base, quote)) = position.instrument.split_once('_') else {
+            continue;
+        };
+        for side in [&position.long, &position.short] {
+            if side.units.is_zero() {
+                continue;
+            }
+            add(base, side.units);
+            add(quote, -side.units * side.average_price.unwrap_or_default());
+        }
+    }
+    legs
+}
+
+/// Background task keeping a [`PortfolioSnapshot`] current
+///
+/// Stops when dropped. Must be created inside a Tokio runtime.
+pub struct Portfolio {
+    task: ShutdownHandle,
+    snapshot: watch::Receiver<Option<Arc<PortfolioSnapshot>>>,
+    last_error: Arc<Mutex<Option<String>>>,
+}
+
+impl Portfolio {
+    /// Refresh from `client` every `interval`, starting immediately
+    ///
+    /// A failed refresh keeps the previous snapshot and is reported by
+    /// `last_error` until a refresh succeeds.
+    pub fn spawn(client: OandaClient, interval: Duration) -> Self {
+        Self::start(client, interval, false)
+    }
+
+    /// Refresh from `client` whenever the account has new transactions
+    ///
+    /// Every `poll_interval` asks for the transactions since the one the
+    /// snapshot reflects and refetches only if there are any, so fills,
+    /// closes and financing show up within one poll while a quiet account
+    /// costs a single request. Unrealized P/L is as of the last refresh.
+    pub fn spawn_on_transactions(client: OandaClient, poll_interval: Duration) -> Self {
+        Self::start(client, poll_interval, true)
+    }
+
+    fn start(client: OandaClient, interval: Duration, on_transactions: bool) -> Self {
+        let (sender, snapshot) = watch::channel(None::<Arc<PortfolioSnapshot>>);
+        let last_error = Arc::new(Mutex::new(None));
+        let errors = last_error.clone();
+
+        let task = ShutdownHandle::spawn(|mut signal| async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                tokio::select! {
+                    _ = ticker.tick() => {}
+                    _ = signal.requested() => break,
+                }
+
+                let since = on_transactions
+                    .then(|| {
+                        sender
+                            .borrow()
+                            .as_ref()
+                            .and_then(|snapshot| snapshot.last_transaction_id.clone())
+                    })
+                    .flatten();
+                if let Some(id) = since {
+                    match client.get_transactions_since(&id).await {
+                        Ok(transactions) if transactions.is_empty() => continue,
+                        Ok(_) => {}
+                        Err(e) => {
+                            *errors.lock().unwrap() = Some(e.to_string());
+                            continue;
+                        }
+                    }
+                }
+
+                match PortfolioSnapshot::fetch(&client).await {
+                    Ok(next) => {
+                        *errors.lock().unwrap() = None;
+                        sender.send_replace(Some(Arc::new(next)));
+                    }
+                    Err(e) => *errors.lock().unwrap() = Some(e.to_string()),
+                }
+            }
+        });
+
+        Self {
+            task,
+            snapshot,
+            last_error,
+        }
+    }
+
+    /// Latest snapshot; `None` until the first refresh succeeds
+    pub fn snapshot(&self) -> Option<Arc<PortfolioSnapshot>> {
+        self.snapshot.borrow().clone()
+    }
+
+    /// Receiver notified after every successful refresh
+    pub fn subscribe(&self) -> watch::Receiver<Option<Arc<PortfolioSnapshot>>> {
+        self.snapshot.clone()
+    }
+
+    /// Currency exposure of the latest snapshot at current prices, or
+    /// `None` before the first refresh
+    pub async fn exposure(&self, converter: &CurrencyConverter) -> Result<Option<ExposureReport>> {
+        match self.snapshot() {
+            Some(snapshot) => Ok(Some(snapshot.currency_exposure_report(converter).await?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Error from the last refresh, if it failed
+    pub fn last_error(&self) -> Option<String> {
+        self.last_error.lock().unwrap().clone()
+    }
+
+    /// Stop refreshing and wait for the task to exit; same as
+    /// [`shutdown`](Self::shutdown)
+    pub async fn stop(self) {
+        self.shutdown().await;
+    }
+
+    /// Stop refreshing once any refresh in progress has completed
+    pub async fn shutdown(self) {
+        self.task.shutdown().await;
+    }
+}
+
+impl From<Portfolio> for ShutdownHandle {
+    fn from(portfolio: Portfolio) -> Self {
+        portfolio.task
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ids::TradeId;
+    use crate::models::PositionSide;
+    use rust_decimal_macros::dec;
+
+    fn side(units: Decimal, average_price: Option<Decimal>) -> PositionSide {
+        PositionSide {
+            units,
+            average_price,
+            trade_ids: Vec::new(),
+            unrealized_pl: None,
+        }
+    }
+
+    fn trade(id: u64, units: Decimal, minutes: i64) -> Trade {
+        Trade {
+            id: TradeId::from(id),
+            instrument: "EUR_USD".to_string(),
+            price: dec!(1.1),
+            open_time: DateTime::from_timestamp(1_700_000_000 + minutes * 60, 0).unwrap(),
+            initial_units: units,
+            current_units: units,
+            realized_pl: None,
+            unrealized_pl: None,
+            margin_used: None,
+        }
+    }
+
+    fn summary() -> AccountSummary {
+        AccountSummary {
+            id: "001-001-1234567-001".to_string(),
+            balance: dec!(10000),
+            nav: dec!(10000),
+            unrealized_pl: Decimal::ZERO,
+            realized_pl: Decimal::ZERO,
+            margin_used: Decimal::ZERO,
+            margin_available: dec!(10000),
+            open_trade_count: 0,
+            open_position_count: 2,
+            currency: "USD".to_string(),
+            hedging_enabled: false,
+        }
+    }
+
+    #[test]
+    fn test_exposure_per_instrument_and_currency() {
+        let positions = vec![
+            Position {
+                instrument: "EUR_USD".to_string(),
+                pl: Decimal::ZERO,
+                unrealized_pl: Some(dec!(12.5)),
+                margin_used: Some(dec!(220)),
+                long: side(dec!(10000), Some(dec!(1.1))),
+                short: side(dec!(-2000), Some(dec!(1.2))),
+            },
+            Position {
+                instrument: "USD_JPY".to_string(),
+                pl: Decimal::ZERO,
+                unrealized_pl: None,
+                margin_used: None,
+                long: side(Decimal::ZERO, None),
+                short: side(dec!(-1000), Some(dec!(150))),
+            },
+        ];
+
+        let snapshot = PortfolioSnapshot::new(summary(), Vec::new(), positions);
+
+        let eur_usd = snapshot.exposure("EUR_USD").unwrap();
+        assert_eq!(eur_usd.net_units, dec!(8000));
+        assert_eq!(eur_usd.notional, dec!(8600));
+        assert_eq!(eur_usd.unrealized_pl, dec!(12.5));
+        assert_eq!(snapshot.currency_exposure("EUR"), dec!(8000));
+        // -8600 from EUR_USD, -1000 from the USD_JPY short
+        assert_eq!(snapshot.currency_exposure("USD"), dec!(-9600));
+        assert_eq!(snapshot.currency_exposure("JPY"), dec!(150000));
+        assert_eq!(snapshot.currency_exposure("GBP"), Decimal::ZERO);
+    }
+
+    #[test]
+    fn test_hedging_positions_net_trades() {
+        let trades = vec![
+            trade(2, dec!(-4000), 5),
+            trade(1, dec!(10000), 0),
+            trade(3, dec!(-6000), 10),
+        ];
+        let hedging = AccountSummary {
+            hedging_enabled: true,
+            ..summary()
+        };
+        let snapshot = PortfolioSnapshot::new(hedging, trades.clone(), Vec::new());
+
+        assert_eq!(snapshot.position_mode(), PositionMode::Hedging);
+        assert_eq!(snapshot.trades_in("EUR_USD")[0].id, TradeId::from(1));
+        assert_eq!(snapshot.net_position("EUR_USD"), Decimal::ZERO);
+        assert_eq!(snapshot.gross_position("EUR_USD"), dec!(20000));
+        assert_eq!(snapshot.reduce_order("EUR_USD", dec!(1000)), None);
+
+        let snapshot = PortfolioSnapshot::new(summary(), trades[1..].to_vec(), Vec::new());
+        let order = snapshot.reduce_order("EUR_USD", dec!(10000)).unwrap();
+        assert_eq!(order.units, dec!(-4000));
+        assert_eq!(order.position_fill, None);
+
+        let snapshot = PortfolioSnapshot::new(
+            AccountSummary {
+                hedging_enabled: true,
+                ..summary()
+            },
+            trades[1..].to_vec(),
+            Vec::new(),
+        );
+        let order = snapshot.reduce_order("EUR_USD", dec!(1000)).unwrap();
+        assert_eq!(order.units, dec!(-1000));
+        assert_eq!(order.position_fill, Some(PositionFill::ReduceOnly));
+    }
+
+    #[test]
+    fn test_exposure_report_nets_currency_legs() {
+        let positions = vec![
+            Position {
+                instrument: "EUR_USD".to_string(),
+                pl: Decimal::ZERO,
+                unrealized_pl: None,
+                margin_used: None,
+                long: side(dec!(10000), Some(dec!(1.1))),
+                short: side(Decimal::ZERO, None),
+            },
+            Position {
+                instrument: "USD_JPY".to_string(),
+                pl: Decimal::ZERO,
+                unrealized_pl: None,
+                margin_used: None,
+                long: side(dec!(10000), Some(dec!(150))),
+                short: side(Decimal::ZERO, None),
+            },
+        ];
+        let snapshot = PortfolioSnapshot::new(summary(), Vec::new(), positions);
+
+        let rates: HashMap<String, Decimal> = [
+            ("EUR".to_string(), dec!(1.1)),
+            ("JPY".to_string(), dec!(0.0066)),
+        ]
+        .into_iter()
+        .collect();
+        let report = snapshot.exposure_report(&rates);
+
+        let usd = report.currency("USD").unwrap();
+        assert_eq!(
+            (usd.long, usd.short, usd.net),
+            (dec!(10000), dec!(-11000), dec!(-1000))
+        );
+        assert_eq!(usd.net_value, dec!(-1000));
+        let jpy = report.currency("JPY").unwrap();
+        assert_eq!(jpy.short, dec!(-1500000));
+        assert_eq!(jpy.net_value, dec!(-9900));
+        assert_eq!(report.currency("EUR").unwrap().long_value, dec!(11000));
+        assert_eq!(report.gross_value(), dec!(20900));
+
+        assert!(report.unpriced.is_empty());
+
+        let report = snapshot.exposure_report(&HashMap::new());
+        assert_eq!(report.unpriced, vec!["EUR", "JPY"]);
+        assert_eq!(report.currency("USD").unwrap().net_value, dec!(-1000));
+        assert_eq!(report.gross_value(), Decimal::ZERO);
+    }
+}
@@ -1,17 +1,55 @@
 //! Rate limiter implementation using Governor's GCRA algorithm
+//! 
+//! REST throughput and streaming connections are limited separately: OANDA
+//! caps concurrent streams on their own, so `ConnectionBudget` counts open
+//! streams while `RateLimiter` paces REST requests, and neither can use up
+//! the other's allowance.
+//!
+//! Quotas are kept on `std::time::Instant` rather than Governor's default
+//! TSC clock, so permits line up with the Tokio timers callers wait on.
 
 use governor::{
-    clock::DefaultClock,
+    clock::{Clock, MonotonicClock},
+    middleware::NoOpMiddleware,
     state::{InMemoryState, NotKeyed},
     Quota, RateLimiter as GovernorRateLimiter,
 };
+use crate::endpoints::EndpointGroup;
+use std::collections::HashMap;
 use std::num::NonZeroU32;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+use tokio::sync::{Notify, OwnedSemaphorePermit, Semaphore};
+
+type Governor = GovernorRateLimiter<NotKeyed, InMemoryState, MonotonicClock, NoOpMiddleware<Instant>>;
+
+/// How urgently a request needs a rate limit permit
+/// 
+/// While a quota is saturated, waiting requests of a higher priority are
+/// let through before lower ones. Order placement defaults to `Trading`,
+/// everything else to `Normal`; mark bulk downloads `Backfill` so they
+/// yield to live traffic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum Priority {
+    Backfill,
+    #[default]
+    Normal,
+    Trading,
+}
+
+/// Requests waiting per quota (None for the shared one) and priority
+type Waiting = HashMap<Option<EndpointGroup>, [usize; 3]>;
 
 /// Token bucket rate limiter using Governor
+/// 
+/// Endpoint groups given their own quota with `set_group_rate` are limited
+/// by that quota alone; all other requests share the main one.
 #[derive(Clone)]
 pub struct RateLimiter {
-    governor: Arc<GovernorRateLimiter<NotKeyed, InMemoryState, DefaultClock>>,
+    governor: Arc<RwLock<Arc<Governor>>>,
+    groups: Arc<RwLock<HashMap<EndpointGroup, Arc<Governor>>>>,
+    waiting: Arc<Mutex<Waiting>>,
+    turn: Arc<Notify>,
 }
 
 impl RateLimiter {
@@ -19,17 +57,70 @@ impl RateLimiter {
     /// 
     /// # Arguments
     /// * `requests_per_second` - Maximum requests allowed per second
+    /// * `burst` - Requests allowed back to back before pacing kicks in
+    ///   (1 for strictly even spacing, `requests_per_second` for a full second)
     /// 
     /// # Panics
-    /// Panics if requests_per_second is 0
-    pub fn new(requests_per_second: u32) -> Self {
+    /// Panics if requests_per_second or burst is 0
+    pub fn new(requests_per_second: u32, burst: u32) -> Self {
+        Self {
+            governor: Arc::new(RwLock::new(Self::governor(requests_per_second, burst))),
+            groups: Arc::new(RwLock::new(HashMap::new())),
+            waiting: Arc::new(Mutex::new(HashMap::new())),
+            turn: Arc::new(Notify::new()),
+        }
+    }
+    
+    /// Change the rate for subsequent requests
+    /// 
+    /// Starts over with a full burst at the new rate. Callers already
+    /// waiting finish waiting at the old rate.
+    /// 
+    /// # Panics
+    /// Panics if requests_per_second or burst is 0
+    pub fn set_rate(&self, requests_per_second: u32, burst: u32) {
+        *self.governor.write().unwrap() = Self::governor(requests_per_second, burst);
+    }
+    
+    /// Give an endpoint group its own quota, or return it to the shared one
+    /// 
+    /// `burst` defaults to `requests_per_second`, i.e. a full second.
+    /// 
+    /// # Panics
+    /// Panics if requests_per_second or burst is 0
+    pub fn set_group_rate(&self, group: EndpointGroup, requests_per_second: Option<u32>, burst: Option<u32>) {
+        let mut groups = self.groups.write().unwrap();
+        match requests_per_second {
+            Some(rate) => groups.insert(group, Self::governor(rate, burst.unwrap_or(rate))),
+            None => groups.remove(&group),
+        };
+    }
+    
+    fn governor(requests_per_second: u32, burst: u32) -> Arc<Governor> {
         let quota = Quota::per_second(
             NonZeroU32::new(requests_per_second)
                 .expect("requests_per_second must be greater than 0")
+        ).allow_burst(
+            NonZeroU32::new(burst)
+                .expect("burst must be greater than 0")
         );
         
-        Self {
-            governor: Arc::new(GovernorRateLimiter::direct(quota)),
+        Arc::new(GovernorRateLimiter::direct_with_clock(quota, MonotonicClock))
+    }
+    
+    fn current(&self) -> Arc<Governor> {
+        self.governor.read().unwrap().clone()
+    }
+    
+    fn current_for(&self, group: EndpointGroup) -> Arc<Governor> {
+        self.quota_for(group).1
+    }
+    
+    /// The governor a group draws on, keyed by group if it has its own
+    fn quota_for(&self, group: EndpointGroup) -> (Option<EndpointGroup>, Arc<Governor>) {
+        match self.groups.read().unwrap().get(&group) {
+            Some(governor) => (Some(group), governor.clone()),
+            None => (None, self.current()),
         }
     }
     
@@ -39,23 +130,111 @@ impl RateLimiter {
     /// This method will block until a permit becomes available.
     pub async fn acquire(&self) -> RateLimitPermit {
         // Wait until we're allowed to proceed
-        self.governor.until_ready().await;
+        self.current().until_ready().await;
         
         RateLimitPermit {
             _private: (),
         }
     }
     
+    /// Acquire permission, giving up after `timeout`
+    /// 
+    /// Returns None if no permit became available in time, so latency
+    /// sensitive callers don't queue indefinitely behind a burst.
+    pub async fn acquire_timeout(&self, timeout: Duration) -> Option<RateLimitPermit> {
+        tokio::time::timeout(timeout, self.acquire()).await.ok()
+    }
+    
     /// Try to acquire permission immediately (non-blocking)
     /// 
     /// Returns Some(permit) if rate limit allows, None if rate exceeded.
     pub fn try_acquire(&self) -> Option<RateLimitPermit> {
-        self.governor.check().is_ok().then_some(RateLimitPermit {
+        self.current().check().is_ok().then_some(RateLimitPermit {
+            _private: (),
+        })
+    }
+    
+    /// Acquire permission for a request to an endpoint group
+    /// 
+    /// Uses the group's own quota if it has one, otherwise the shared quota.
+    pub async fn acquire_for(&self, group: EndpointGroup) -> RateLimitPermit {
+        self.acquire_with_priority(group, Priority::Normal).await
+    }
+    
+    /// Acquire permission for an endpoint group, ahead of lower priorities
+    /// 
+    /// Waits while requests of a higher priority are queued for the same
+    /// quota, so they get the next free permits.
+    pub async fn acquire_with_priority(&self, group: EndpointGroup, priority: Priority) -> RateLimitPermit {
+        let (quota, _) = self.quota_for(group);
+        let _queued = Queued::new(self, quota, priority);
+        
+        loop {
+            let turn = self.turn.notified();
+            tokio::pin!(turn);
+            turn.as_mut().enable();
+            
+            if self.outranked(quota, priority) {
+                turn.await;
+                continue;
+            }
+            
+            let governor = self.current_for(group);
+            match governor.check() {
+                Ok(()) => {
+                    return RateLimitPermit {
+                        _private: (),
+                    }
+                }
+                Err(not_until) => {
+                    let wait = not_until.wait_time_from(MonotonicClock.now());
+                    tokio::select! {
+                        _ = tokio::time::sleep(wait) => {}
+                        _ = turn => {}
+                    }
+                }
+            }
+        }
+    }
+    
+    /// Whether higher priority requests are waiting on a quota
+    fn outranked(&self, quota: Option<EndpointGroup>, priority: Priority) -> bool {
+        self.waiting.lock().unwrap()
+            .get(&quota)
+            .is_some_and(|counts| counts[priority as usize + 1..].iter().any(|&n| n > 0))
+    }
+    
+    /// Try to acquire permission for an endpoint group without waiting
+    pub fn try_acquire_for(&self, group: EndpointGroup) -> Option<RateLimitPermit> {
+        self.current_for(group).check().is_ok().then_some(RateLimitPermit {
             _private: (),
         })
     }
 }
 
+/// Counts a request as waiting until it gets its permit or gives up
+struct Queued<'a> {
+    limiter: &'a RateLimiter,
+    quota: Option<EndpointGroup>,
+    priority: Priority,
+}
+
+impl<'a> Queued<'a> {
+    fn new(limiter: &'a RateLimiter, quota: Option<EndpointGroup>, priority: Priority) -> Self {
+        limiter.waiting.lock().unwrap().entry(quota).or_default()[priority as usize] += 1;
+        Self { limiter, quota, priority }
+    }
+}
+
+impl Drop for Queued<'_> {
+    fn drop(&mut self) {
+        if let Some(counts) = self.limiter.waiting.lock().unwrap().get_mut(&self.quota) {
+            counts[self.priority as usize] -= 1;
+        }
+        self.limiter.turn.notify_waiters();
+    }
+}
+
 /// RAII guard for rate limit permit
 /// 
 /// Governor handles permit lifecycle internally, so this is just a marker type
@@ -64,6 +243,59 @@ pub struct RateLimitPermit {
     _private: (),
 }
 
+/// Limit on concurrently open streaming connections
+#[derive(Clone)]
+pub struct ConnectionBudget {
+    max_streams: u32,
+    semaphore: Arc<Semaphore>,
+}
+
+impl ConnectionBudget {
+    /// Create a budget allowing `max_streams` open streams
+    pub fn new(max_streams: u32) -> Self {
+        Self {
+            max_streams,
+            semaphore: Arc::new(Semaphore::new(max_streams as usize)),
+        }
+    }
+    
+    /// Reserve a slot for a stream, waiting until one is free
+    /// 
+    /// The slot is released when the permit is dropped, so keep it alive
+    /// for as long as the stream is open.
+    pub async fn acquire(&self) -> StreamPermit {
+        let permit = self.semaphore.clone()
+            .acquire_owned()
+            .await
+            .expect("connection budget semaphore is never closed");
+        
+        StreamPermit { _permit: permit }
+    }
+    
+    /// Reserve a slot for a stream if one is free right now
+    pub fn try_acquire(&self) -> Option<StreamPermit> {
+        self.semaphore.clone()
+            .try_acquire_owned()
+            .ok()
+            .map(|permit| StreamPermit { _permit: permit })
+    }
+    
+    /// Number of streams that can still be opened
+    pub fn available(&self) -> u32 {
+        self.semaphore.available_permits() as u32
+    }
+    
+    /// Maximum number of concurrent streams
+    pub fn max_streams(&self) -> u32 {
+        self.max_streams
+    }
+}
+
+/// Slot for one open stream, released on drop
+pub struct StreamPermit {
+    _permit: OwnedSemaphorePermit,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -71,7 +303,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_rate_limiter_basic() {
-        let limiter = RateLimiter::new(10); // 10 req/sec
+        let limiter = RateLimiter::new(10, 10); // 10 req/sec
         
         let start = Instant::now();
         
@@ -87,7 +319,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_rate_limiter_enforcement() {
-        let limiter = RateLimiter::new(10); // 10 req/sec
+        let limiter = RateLimiter::new(10, 10); // 10 req/sec
         
         let start = Instant::now();
         
@@ -103,7 +335,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_try_acquire() {
-        let limiter = RateLimiter::new(5);
+        let limiter = RateLimiter::new(5, 5);
         
         // Should succeed 5 times (burst capacity)
         for _ in 0..5 {
@@ -122,7 +354,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_rate_limiter_smooth_distribution() {
-        let limiter = RateLimiter::new(10); // 10 req/sec
+        let limiter = RateLimiter::new(10, 10); // 10 req/sec
         
         let start = Instant::now();
         let mut timestamps = Vec::new();
@@ -140,9 +372,117 @@ mod tests {
         assert!(total_duration <= Duration::from_millis(2500));
     }
 
+    #[tokio::test]
+    async fn test_set_rate() {
+        let limiter = RateLimiter::new(2, 2);
+        let clone = limiter.clone();
+        
+        assert!(limiter.try_acquire().is_some());
+        assert!(limiter.try_acquire().is_some());
+        assert!(limiter.try_acquire().is_none());
+        
+        // Applies to clones too
+        limiter.set_rate(5, 5);
+        for _ in 0..5 {
+            assert!(clone.try_acquire().is_some());
+        }
+        assert!(clone.try_acquire().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_connection_budget_independent_of_rate() {
+        let limiter = RateLimiter::new(1, 1);
+        let budget = ConnectionBudget::new(2);
+        
+        let first = budget.acquire().await;
+        let _second = budget.acquire().await;
+        assert!(budget.try_acquire().is_none());
+        assert_eq!(budget.available(), 0);
+        
+        // Streams don't consume REST permits
+        assert!(limiter.try_acquire().is_some());
+        
+        drop(first);
+        assert_eq!(budget.available(), 1);
+        assert!(budget.try_acquire().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_acquire_timeout() {
+        let limiter = RateLimiter::new(2, 1);
+        
+        assert!(limiter.acquire_timeout(Duration::from_millis(10)).await.is_some());
+        // Next permit is 500ms away
+        assert!(limiter.acquire_timeout(Duration::from_millis(50)).await.is_none());
+        assert!(limiter.acquire_timeout(Duration::from_millis(600)).await.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_group_quotas() {
+        let limiter = RateLimiter::new(2, 2);
+        limiter.set_group_rate(EndpointGroup::Candles, Some(1), None);
+        
+        // A drained candle quota leaves the shared quota untouched
+        assert!(limiter.try_acquire_for(EndpointGroup::Candles).is_some());
+        assert!(limiter.try_acquire_for(EndpointGroup::Candles).is_none());
+        assert!(limiter.try_acquire_for(EndpointGroup::Pricing).is_some());
+        assert!(limiter.try_acquire_for(EndpointGroup::Orders).is_some());
+        assert!(limiter.try_acquire_for(EndpointGroup::Pricing).is_none());
+        
+        limiter.set_group_rate(EndpointGroup::Candles, None, None);
+        assert!(limiter.try_acquire_for(EndpointGroup::Candles).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_group_burst() {
+        let limiter = RateLimiter::new(10, 10);
+        limiter.set_group_rate(EndpointGroup::Candles, Some(10), Some(1));
+        
+        assert!(limiter.try_acquire_for(EndpointGroup::Candles).is_some());
+        assert!(limiter.try_acquire_for(EndpointGroup::Candles).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_burst_size() {
+        let strict = RateLimiter::new(10, 1);
+        assert!(strict.try_acquire().is_some());
+        assert!(strict.try_acquire().is_none());
+        
+        let bursty = RateLimiter::new(1, 5);
+        for _ in 0..5 {
+            assert!(bursty.try_acquire().is_some());
+        }
+        assert!(bursty.try_acquire().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_priority_jumps_queue() {
+        let limiter = RateLimiter::new(10, 1);
+        limiter.acquire_for(EndpointGroup::Candles).await;
+        
+        let (done, mut order) = tokio::sync::mpsc::unbounded_channel();
+        let backfill = {
+            let (limiter, done) = (limiter.clone(), done.clone());
+            tokio::spawn(async move {
+                limiter.acquire_with_priority(EndpointGroup::Candles, Priority::Backfill).await;
+                done.send(Priority::Backfill).unwrap();
+            })
+        };
+        sleep(Duration::from_millis(10)).await;
+        let trading = tokio::spawn(async move {
+            limiter.acquire_with_priority(EndpointGroup::Orders, Priority::Trading).await;
+            done.send(Priority::Trading).unwrap();
+        });
+        
+        trading.await.unwrap();
+        backfill.await.unwrap();
+        assert_eq!(order.recv().await, Some(Priority::Trading));
+        assert_eq!(order.recv().await, Some(Priority::Backfill));
+    }
+
     #[test]
     #[should_panic(expected = "requests_per_second must be greater than 0")]
     fn test_zero_rate_panics() {
-        let _ = RateLimiter::new(0);
+        let _ = RateLimiter::new(0, 1);
     }
 }
\ No newline at end of file
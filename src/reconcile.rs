@@ -0,0 +1,98 @@
+//! Catching up on account state after a disconnect
+//!
+//! [`OandaClient::reconcile_state`](crate::OandaClient::reconcile_state)
+//! asks OANDA what changed since the last transaction the application
+//! processed, and returns it as a [`StateDiff`]: orders filled, cancelled
+//! or created, trades opened, reduced or closed, the positions they touched
+//! and the transactions behind it all. Persist the diff's
+//! `last_transaction_id` to pick up from there next time.
+//!
+//! ```no_run
+//! use oanda_connector::{OandaClient, OandaConfig, OrderManager, TransactionId};
+//!
+//! # async fn run(last_seen: TransactionId) -> oanda_connector::Result<()> {
+//! let client = OandaClient::new(OandaConfig::from_env()?)?;
+//! let manager = OrderManager::new(client.clone());
+//!
+//! let diff = client.reconcile_state(&last_seen).await?;
+//! for trade in &diff.trades_closed {
+//!     println!("trade {} closed while away, P/L {:?}", trade.id, trade.realized_pl);
+//! }
+//! manager.apply_changes(&diff);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::ids::{OrderId, TradeId, TransactionId};
+use crate::models::{Position, Trade};
+use crate::orders::Order;
+use crate::transactions::{OrderFillTransaction, Transaction};
+use serde::{Deserialize, Serialize};
+
+/// What changed in the account since a transaction
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StateDiff {
+    #[serde(default)]
+    pub orders_created: Vec<Order>,
+    #[serde(default)]
+    pub orders_cancelled: Vec<Order>,
+    #[serde(default)]
+    pub orders_filled: Vec<Order>,
+    #[serde(default)]
+    pub orders_triggered: Vec<Order>,
+    #[serde(default)]
+    pub trades_opened: Vec<Trade>,
+    #[serde(default)]
+    pub trades_reduced: Vec<Trade>,
+    #[serde(default)]
+    pub trades_closed: Vec<Trade>,
+    /// Current state of every position that changed
+    #[serde(default)]
+    pub positions: Vec<Position>,
+    /// Transactions since the one asked about, oldest first
+    #[serde(default)]
+    pub transactions: Vec<Transaction>,
+    /// Latest transaction in the account; reconcile from here next time
+    #[serde(skip)]
+    pub last_transaction_id: Option<TransactionId>,
+}
+
+impl StateDiff {
+    /// Whether nothing changed
+    pub fn is_empty(&self) -> bool {
+        self.transactions.is_empty()
+            && self.orders_created.is_empty()
+            && self.orders_cancelled.is_empty()
+            && self.orders_filled.is_empty()
+            && self.orders_triggered.is_empty()
+            && self.trades_opened.is_empty()
+            && self.trades_reduced.is_empty()
+            && self.trades_closed.is_empty()
+    }
+
+    /// Fills missed, oldest first
+    pub fn fills(&self) -> impl Iterator<Item = &OrderFillTransaction> {
+        self.transactions.iter().filter_map(|t| match t {
+            Transaction::OrderFill(fill) => Some(fill),
+            _ => None,
+        })
+    }
+
+    /// IDs of orders cancelled
+    pub fn cancelled_order_ids(&self) -> Vec<OrderId> {
+        self.orders_cancelled.iter().map(|o| o.id.clone()).collect()
+    }
+
+    /// IDs of trades closed
+    pub fn closed_trade_ids(&self) -> Vec<TradeId> {
+        self.trades_closed.iter().map(|t| t.id.clone()).collect()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct AccountChangesResponse {
+    pub changes: StateDiff,
+    #[serde(rename = "lastTransactionID", default)]
+    pub last_transaction_id: Option<TransactionId>,
+}
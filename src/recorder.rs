@@ -0,0 +1,570 @@
+//! Pricing stream recorder
+//!
+//! [`TickRecorder`] keeps the pricing stream open in the background and
+//! writes every tick to zstd-compressed JSON lines files, one directory per
+//! instrument and UTC day:
+//!
+//! ```text
+//! ticks/
+//! ├── index.jsonl
+//! └── EUR_USD/
+//!     └── 2025-11-03/
+//!         ├── 000000000.jsonl.zst
+//!         └── 010000412.jsonl.zst
+//! ```
+//!
+//! Files are named after the time of their first tick and rotate after
+//! `max_ticks_per_file` ticks, after `max_file_age` and at midnight UTC.
+//! Each finished file gets a line in `index.jsonl` giving its instrument,
+//! day, time range and tick count. [`TickArchive`] reads both back.
+//!
+//! Requires the `recorder` feature.
+//!
+//! ```no_run
+//! use oanda_connector::recorder::{RecorderConfig, TickArchive, TickRecorder};
+//! use oanda_connector::{OandaClient, OandaConfig};
+//!
+//! # async fn run() -> oanda_connector::Result<()> {
+//! let client = OandaClient::new(OandaConfig::from_env()?)?;
+//! let config = RecorderConfig::new("ticks", vec!["EUR_USD".to_string()]);
+//! let recorder = TickRecorder::spawn(client, config)?;
+//!
+//! tokio::signal::ctrl_c().await.ok();
+//! recorder.shutdown().await; // finishes the open files
+//!
+//! let archive = TickArchive::open("ticks");
+//! for day in archive.days("EUR_USD")? {
+//!     println!("{}: {} ticks", day, archive.read_day("EUR_USD", day)?.len());
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::client::OandaClient;
+use crate::error::Result;
+use crate::models::Tick;
+use crate::shutdown::ShutdownHandle;
+use chrono::{DateTime, NaiveDate, Utc};
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Index file in the recording directory
+pub const INDEX_FILE: &str = "index.jsonl";
+
+/// Where and how to record
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecorderConfig {
+    pub directory: PathBuf,
+    pub instruments: Vec<String>,
+    pub max_ticks_per_file: usize,
+    pub max_file_age: Duration,
+    /// zstd level, 1 (fastest) to 22 (smallest)
+    pub compression_level: i32,
+    /// Wait before reopening a dropped stream
+    pub reconnect_delay: Duration,
+}
+
+impl RecorderConfig {
+    /// Record `instruments` under `directory`, rotating hourly or every
+    /// 100,000 ticks
+    pub fn new(directory: impl Into<PathBuf>, instruments: Vec<String>) -> Self {
+        Self {
+            directory: directory.into(),
+            instruments,
+            max_ticks_per_file: 100_000,
+            max_file_age: Duration::from_secs(3600),
+            compression_level: 3,
+            reconnect_delay: Duration::from_secs(5),
+        }
+    }
+}
+
+/// One finished file, as listed in `index.jsonl`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IndexEntry {
+    pub instrument: String,
+    pub date: NaiveDate,
+    /// Relative to the recording directory, `/`-separated
+    pub path: String,
+    pub first: DateTime<Utc>,
+    pub last: DateTime<Utc>,
+    pub ticks: usize,
+}
+
+/// File being written for one instrument
+struct OpenFile {
+    encoder: zstd::Encoder<'static, File>,
+    entry: IndexEntry,
+    opened: Instant,
+}
+
+/// Writes ticks to rotating compressed files and indexes them
+///
+/// Used by [`TickRecorder`]; usable directly to archive ticks from another
+/// source. A file can only be read once finished, which happens on
+/// rotation, `finish` or drop.
+pub struct TickWriter {
+    config: RecorderConfig,
+    open: HashMap<String, OpenFile>,
+    index: File,
+}
+
+impl TickWriter {
+    /// Create the directory if needed and append to its index
+    pub fn create(config: RecorderConfig) -> Result<Self> {
+        fs::create_dir_all(&config.directory)?;
+        let index = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(config.directory.join(INDEX_FILE))?;
+        Ok(Self {
+            config,
+            open: HashMap::new(),
+            index,
+        })
+    }
+
+    pub fn write(&mut self, tick: &Tick) -> Result<()> {
+        let rotate = self.open.get(&tick.instrument).is_some_and(|file| {
+            file.entry.date != tick.timestamp.date_naive()
+                || file.entry.ticks >= self.config.max_ticks_per_file
+                || file.opened.elapsed() >= self.config.max_file_age
+        });
+        if rotate {
+            self.close(&tick.instrument)?;
+        }
+
+        let file = match self.open.entry(tick.instrument.clone()) {
+            Entry::Occupied(open) => open.into_mut(),
+            Entry::Vacant(slot) => slot.insert(open_file(&self.config, tick)?),
+        };
+        serde_json::to_writer(&mut file.encoder, tick)?;
+        file.encoder.write_all(b"\n")?;
+        file.entry.ticks += 1;
+        file.entry.last = tick.timestamp;
+        Ok(())
+    }
+
+    /// Finish every open file and return their index entries
+    pub fn finish(mut self) -> Result<Vec<IndexEntry>> {
+        self.close_all()
+    }
+
+    fn close_all(&mut self) -> Result<Vec<IndexEntry>> {
+        let instruments: Vec<String> = self.open.keys().cloned().collect();
+        let mut entries = Vec::new();
+        for instrument in instruments {
+            entries.extend(self.close(&instrument)?);
+        }
+        Ok(entries)
+    }
+
+    /// Finish an instrument's file and add it to the index
+    fn close(&mut self, instrument: &str) -> Result<Option<IndexEntry>> {
+        let Some(file) = self.open.remove(instrument) else {
+            return Ok(None);
+        };
+        file.encoder.finish()?.sync_all()?;
+        let mut line = serde_json::to_vec(&file.entry)?;
+        line.push(b'\n');
+        self.index.write_all(&line)?;
+        Ok(Some(file.entry))
+    }
+}
+
+impl Drop for TickWriter {
+    fn drop(&mut self) {
+        if let Err(e) = self.close_all() {
+            log::warn!("failed to finish tick files: {}", e);
+        }
+    }
+}
+
+/// Start a file named after the tick's time, avoiding existing names
+fn open_file(config: &RecorderConfig, tick: &Tick) -> Result<OpenFile> {
+    let date = tick.timestamp.date_naive();
+    let day = format!("{}/{}", tick.instrument, date);
+    fs::create_dir_all(config.directory.join(&day))?;
+
+    let stem = tick.timestamp.format("%H%M%S%3f").to_string();
+    let mut path = format!("{}/{}.jsonl.zst", day, stem);
+    let mut suffix = 1;
+    while config.directory.join(&path).exists() {
+        path = format!("{}/{}-{}.jsonl.zst", day, stem, suffix);
+        suffix += 1;
+    }
+
+    let file = File::create(config.directory.join(&path))?;
+    Ok(OpenFile {
+        encoder: zstd::Encoder::new(file, config.compression_level)?,
+        entry: IndexEntry {
+            instrument: tick.instrument.clone(),
+            date,
+            path,
+            first: tick.timestamp,
+            last: tick.timestamp,
+            ticks: 0,
+        },
+        opened: Instant::now(),
+    })
+}
+
+/// Reads a recording directory
+#[derive(Debug, Clone)]
+pub struct TickArchive {
+    directory: PathBuf,
+}
+
+impl TickArchive {
+    pub fn open(directory: impl Into<PathBuf>) -> Self {
+        Self {
+            directory: directory.into(),
+        }
+    }
+
+    /// Every finished file, in the order they were finished
+    pub fn index(&self) -> Result<Vec<IndexEntry>> {
+        let file = match File::open(self.directory.join(INDEX_FILE)) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        let mut entries = Vec::new();
+        for line in BufReader::new(file).lines() {
+            let line = line?;
+            if !line.trim().is_empty() {
+                entries.push(serde_json::from_str(&line)?);
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Days with recorded ticks for an instrument, oldest first
+    pub fn days(&self, instrument: &str) -> Result<Vec<NaiveDate>> {
+        let mut days: Vec<NaiveDate> = self
+            .index()?
+            .into_iter()
+            .filter(|entry| entry.instrument == instrument)
+            .map(|entry| entry.date)
+            .collect();
+        days.sort();
+        days.dedup();
+        Ok(days)
+    }
+
+    /// Ticks in one file
+    pub fn read_file(&self, entry: &IndexEntry) -> Result<Vec<Tick>> {
+        let decoder = zstd::Decoder::new(File::open(self.directory.join(&entry.path))?)?;
+        let mut ticks = Vec::with_capacity(entry.ticks);
+        for line in BufReader::new(decoder).lines() {
+            ticks.push(serde_json::from_str(&line?)?);
+        }
+        Ok(ticks)
+    }
+
+    /// Ticks for an instrument on one UTC day, in file order
+    pub fn read_day(&self, instrument: &str, date: NaiveDate) -> Result<Vec<Tick>> {
+        let mut entries: Vec<IndexEntry> = self
+            .index()?
+            .into_iter()
+            .filter(|entry| entry.instrument == instrument && entry.date == date)
+            .collect();
+        entries.sort_by_key(|entry| entry.first);
+
+        let mut ticks = Vec::new();
+        for entry in &entries {
+            ticks.extend(self.read_file(entry)?);
+        }
+        Ok(ticks)
+    }
+}
+
+/// Background task recording the pricing stream
+///
+/// Reconnects after `reconnect_delay` whenever the stream drops. Files are
+/// written on a dedicated thread fed by the stream task, so compression and
+/// syncing to disk never hold up the async runtime. Use `shutdown` to stop;
+/// dropping the recorder aborts the task, after which the writer thread
+/// still finishes the open files but ticks received meanwhile may be lost.
+pub struct TickRecorder {
+    task: ShutdownHandle,
+    recorded: Arc<AtomicU64>,
+    last_error: Arc<Mutex<Option<String>>>,
+}
+
+impl TickRecorder {
+    /// Start recording; fails if the directory can't be set up
+    pub fn spawn(client: OandaClient, config: RecorderConfig) -> Result<Self> {
+        let instruments = config.instruments.clone();
+        let reconnect_delay = config.reconnect_delay;
+        let writer = TickWriter::create(config)?;
+        let recorded = Arc::new(AtomicU64::new(0));
+        let last_error = Arc::new(Mutex::new(None));
+
+        let (ticks, received) = mpsc::channel::<Tick>();
+        let (count, errors) = (recorded.clone(), last_error.clone());
+        let thread = thread::Builder::new()
+            .name("oanda-tick-writer".to_string())
+            .spawn(move || write_ticks(writer, received, &count, &errors))?;
+
+        let errors = last_error.clone();
+        let task = ShutdownHandle::spawn(|mut signal| async move {
+            let report = |e: crate::Error| *errors.lock().unwrap() = Some(e.to_string());
+            'connect: loop {
+                let connected = tokio::select! {
+                    connected = client.stream_prices(&instruments) => connected,
+                    _ = signal.requested() => break,
+                };
+                match connected {
+                    Ok(stream) => {
+                        *errors.lock().unwrap() = None;
+                        let mut stream = Box::pin(stream);
+                        loop {
+                            let next = tokio::select! {
+                                next = stream.next() => next,
+                                _ = signal.requested() => break 'connect,
+                            };
+                            match next {
+                                Some(Ok(tick)) => {
+                                    if ticks.send(tick).is_err() {
+                                        // The writer thread is gone
+                                        break 'connect;
+                                    }
+                                }
+                                Some(Err(e)) => {
+                                    report(e);
+                                    break;
+                                }
+                                None => break,
+                            }
+                        }
+                    }
+                    Err(e) => report(e),
+                }
+
+                tokio::select! {
+                    _ = tokio::time::sleep(reconnect_delay) => {}
+                    _ = signal.requested() => break,
+                }
+            }
+
+            // Closing the channel lets the writer drain it and finish
+            drop(ticks);
+            if tokio::task::spawn_blocking(move || thread.join())
+                .await
+                .map_or(true, |joined| joined.is_err())
+            {
+                log::warn!("tick writer thread panicked");
+            }
+        });
+
+        Ok(Self {
+            task,
+            recorded,
+            last_error,
+        })
+    }
+
+    /// Ticks written so far
+    pub fn ticks_recorded(&self) -> u64 {
+        self.recorded.load(Ordering::Relaxed)
+    }
+
+    /// Latest stream or write error, cleared on reconnecting
+    pub fn last_error(&self) -> Option<String> {
+        self.last_error.lock().unwrap().clone()
+    }
+
+    /// Stop recording and wait for the open files to be finished
+    pub async fn shutdown(self) {
+        self.task.shutdown().await;
+    }
+}
+
+/// Writer thread: record ticks until the channel closes, then finish
+fn write_ticks(
+    mut writer: TickWriter,
+    ticks: mpsc::Receiver<Tick>,
+    recorded: &AtomicU64,
+    last_error: &Mutex<Option<String>>,
+) {
+    let report = |e: crate::Error| *last_error.lock().unwrap() = Some(e.to_string());
+    for tick in ticks {
+        match writer.write(&tick) {
+            Ok(()) => {
+                recorded.fetch_add(1, Ordering::Relaxed);
+            }
+            Err(e) => report(e),
+        }
+    }
+    if let Err(e) = writer.finish() {
+        report(e);
+    }
+}
+
+impl From<TickRecorder> for ShutdownHandle {
+    fn from(recorder: TickRecorder) -> Self {
+        recorder.task
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::PriceStatus;
+    use chrono::TimeZone;
+    use rust_decimal::Decimal;
+
+    fn tick(instrument: &str, time: DateTime<Utc>, bid: Decimal) -> Tick {
+        Tick {
+            instrument: instrument.to_string(),
+            timestamp: time,
+            bid,
+            ask: bid + Decimal::new(2, 4),
+            tradeable: true,
+            status: PriceStatus::Tradeable,
+            closeout_bid: None,
+            closeout_ask: None,
+        }
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("oanda-recorder-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn test_rotation_and_index() {
+        let dir = temp_dir("rotation");
+        let mut config = RecorderConfig::new(&dir, Vec::new());
+        config.max_ticks_per_file = 2;
+        let start = Utc.with_ymd_and_hms(2025, 11, 3, 23, 59, 57).unwrap();
+
+        let mut writer = TickWriter::create(config).unwrap();
+        for second in 0..4 {
+            let time = start + chrono::Duration::seconds(second);
+            writer
+                .write(&tick("EUR_USD", time, Decimal::new(10800 + second, 4)))
+                .unwrap();
+        }
+        writer
+            .write(&tick("USD_JPY", start, Decimal::new(150, 0)))
+            .unwrap();
+        writer.finish().unwrap();
+
+        let archive = TickArchive::open(&dir);
+        let index = archive.index().unwrap();
+        // Two ticks, then the size limit, then midnight
+        let eur_usd: Vec<_> = index
+            .iter()
+            .filter(|entry| entry.instrument == "EUR_USD")
+            .map(|entry| (entry.path.as_str(), entry.ticks))
+            .collect();
+        assert_eq!(
+            eur_usd,
+            vec![
+                ("EUR_USD/2025-11-03/235957000.jsonl.zst", 2),
+                ("EUR_USD/2025-11-03/235959000.jsonl.zst", 1),
+                ("EUR_USD/2025-11-04/000000000.jsonl.zst", 1),
+            ]
+        );
+
+        let first_day = NaiveDate::from_ymd_opt(2025, 11, 3).unwrap();
+        assert_eq!(archive.days("EUR_USD").unwrap().len(), 2);
+        let ticks = archive.read_day("EUR_USD", first_day).unwrap();
+        assert_eq!(ticks.len(), 3);
+        assert_eq!(ticks[2].bid, Decimal::new(10802, 4));
+        assert_eq!(archive.read_day("USD_JPY", first_day).unwrap().len(), 1);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_recorder_writes_stream_off_the_runtime() {
+        let dir = temp_dir("spawn");
+        let mut server = mockito::Server::new_async().await;
+        let _stream = server
+            .mock("GET", "/v3/accounts/test_account_id/pricing/stream")
+            .match_query(mockito::Matcher::Any)
+            .with_status(200)
+            .with_body(concat!(
+                r#"{"type":"PRICE","instrument":"EUR_USD","time":"2025-11-03T12:00:00.000000000Z","bids":[{"price":"1.10000"}],"asks":[{"price":"1.10020"}]}"#,
+                "\n",
+                r#"{"type":"HEARTBEAT","time":"2025-11-03T12:00:05.000000000Z"}"#,
+                "\n",
+                r#"{"type":"PRICE","instrument":"EUR_USD","time":"2025-11-03T12:00:06.000000000Z","bids":[{"price":"1.10010"}],"asks":[{"price":"1.10030"}]}"#,
+                "\n",
+            ))
+            .create_async()
+            .await;
+
+        let mut oanda = crate::OandaConfig::new(
+            "test_api_key".to_string(),
+            "test_account_id".to_string(),
+            true,
+        );
+        oanda.stream_url = Some(server.url());
+        let client = OandaClient::new(oanda).unwrap();
+        let mut config = RecorderConfig::new(&dir, vec!["EUR_USD".to_string()]);
+        config.reconnect_delay = Duration::from_secs(60);
+
+        let recorder = TickRecorder::spawn(client, config).unwrap();
+        for _ in 0..100 {
+            if recorder.ticks_recorded() == 2 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert_eq!(recorder.ticks_recorded(), 2);
+        assert_eq!(recorder.last_error(), None);
+        recorder.shutdown().await;
+
+        // Shutdown waits for the writer thread to finish the file
+        let archive = TickArchive::open(&dir);
+        let ticks = archive
+            .read_day("EUR_USD", NaiveDate::from_ymd_opt(2025, 11, 3).unwrap())
+            .unwrap();
+        assert_eq!(ticks.len(), 2);
+        assert_eq!(ticks[1].bid, Decimal::new(110010, 5));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_drop_finishes_files_without_overwriting() {
+        let dir = temp_dir("drop");
+        let time = Utc.with_ymd_and_hms(2025, 11, 3, 12, 0, 0).unwrap();
+
+        for _ in 0..2 {
+            let mut writer = TickWriter::create(RecorderConfig::new(&dir, Vec::new())).unwrap();
+            writer
+                .write(&tick("EUR_USD", time, Decimal::new(10800, 4)))
+                .unwrap();
+        }
+
+        let archive = TickArchive::open(&dir);
+        let index = archive.index().unwrap();
+        assert_eq!(index.len(), 2);
+        assert_eq!(index[1].path, "EUR_USD/2025-11-03/120000000-1.jsonl.zst");
+        assert_eq!(
+            archive
+                .read_day("EUR_USD", time.date_naive())
+                .unwrap()
+                .len(),
+            2
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,320 @@
+//! Redis price fan-out (`redis` feature)
+//!
+//! [`RedisBridge`] mirrors the pricing stream into Redis so web dashboards
+//! and other services can follow prices without their own OANDA
+//! connection. Each tick is published on a per-instrument pub/sub channel
+//! and stored as the instrument's latest quote under a key that expires
+//! after `quote_ttl`, so quotes disappear rather than go stale when the
+//! feed stops:
+//!
+//! ```text
+//! PUBLISH oanda:prices:EUR_USD  {"instrument":"EUR_USD","bid":"1.10000",...}
+//! SET     oanda:quote:EUR_USD   {"instrument":"EUR_USD","bid":"1.10000",...} PX 60000
+//! ```
+//!
+//! Subscribers can use `PSUBSCRIBE oanda:prices:*` for every instrument.
+//! Values are encoded in the configured [`Format`], JSON by default.
+//!
+//! ```no_run
+//! use oanda_connector::redis_bridge::{RedisBridge, RedisConfig};
+//! use oanda_connector::{OandaClient, OandaConfig};
+//!
+//! # async fn run() -> oanda_connector::Result<()> {
+//! let client = OandaClient::new(OandaConfig::from_env()?)?;
+//! let bridge = RedisBridge::connect(RedisConfig::new("redis://127.0.0.1/")).await?;
+//!
+//! let task = bridge.spawn(client, vec!["EUR_USD".to_string(), "GBP_USD".to_string()]);
+//! tokio::signal::ctrl_c().await.ok();
+//! task.shutdown().await;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::client::OandaClient;
+use crate::codec::Format;
+use crate::error::{Error, Result};
+use crate::models::Tick;
+use crate::shutdown::{ShutdownHandle, ShutdownSignal};
+use futures::StreamExt;
+use redis::aio::ConnectionManager;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Server, naming and expiry
+#[derive(Debug, Clone, PartialEq)]
+pub struct RedisConfig {
+    /// Connection URL, e.g. `redis://127.0.0.1/` or `rediss://host:6380/0`
+    pub url: String,
+    /// Ticks are published on `{channel_prefix}:{instrument}`
+    pub channel_prefix: String,
+    /// Latest quotes are stored at `{key_prefix}:{instrument}`
+    pub key_prefix: String,
+    /// Expiry of latest quote keys
+    pub quote_ttl: Duration,
+    pub format: Format,
+    /// Wait before reopening a dropped stream
+    pub reconnect_delay: Duration,
+}
+
+impl RedisConfig {
+    /// JSON on `oanda:prices:*` channels, quotes at `oanda:quote:*`
+    /// expiring after a minute
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            channel_prefix: "oanda:prices".to_string(),
+            key_prefix: "oanda:quote".to_string(),
+            quote_ttl: Duration::from_secs(60),
+            format: Format::Json,
+            reconnect_delay: Duration::from_secs(5),
+        }
+    }
+}
+
+/// Publishes ticks to Redis channels and latest-quote keys
+///
+/// Cloning is cheap and clones share the connection, which reconnects by
+/// itself after Redis restarts.
+#[derive(Clone)]
+pub struct RedisBridge {
+    connection: ConnectionManager,
+    config: Arc<RedisConfig>,
+    published: Arc<AtomicU64>,
+    last_error: Arc<Mutex<Option<String>>>,
+}
+
+fn redis_error(e: redis::RedisError) -> Error {
+    Error::PublishError(format!("Redis: {}", e))
+}
+
+impl RedisBridge {
+    /// Connect to the server in `config.url`
+    pub async fn connect(config: RedisConfig) -> Result<Self> {
+        let client = redis::Client::open(config.url.as_str())
+            .map_err(|e| Error::ConfigError(format!("Redis URL: {}", e)))?;
+        let connection = ConnectionManager::new(client).await.map_err(redis_error)?;
+        Ok(Self {
+            connection,
+            config: Arc::new(config),
+            published: Arc::new(AtomicU64::new(0)),
+            last_error: Arc::new(Mutex::new(None)),
+        })
+    }
+
+    pub fn config(&self) -> &RedisConfig {
+        &self.config
+    }
+
+    /// Pub/sub channel for an instrument's ticks
+    pub fn channel(&self, instrument: &str) -> String {
+        format!("{}:{}", self.config.channel_prefix, instrument)
+    }
+
+    /// Key holding an instrument's latest quote
+    pub fn quote_key(&self, instrument: &str) -> String {
+        format!("{}:{}", self.config.key_prefix, instrument)
+    }
+
+    /// Ticks published so far
+    pub fn published(&self) -> u64 {
+        self.published.load(Ordering::Relaxed)
+    }
+
+    /// Error from the latest background publish, cleared once one succeeds
+    pub fn last_error(&self) -> Option<String> {
+        self.last_error.lock().unwrap().clone()
+    }
+
+    /// Publish a tick and store it as the latest quote
+    pub async fn publish_tick(&self, tick: &Tick) -> Result<()> {
+        let payload = self.config.format.encode(tick)?;
+        let ttl = self.config.quote_ttl.as_millis().max(1) as u64;
+        redis::pipe()
+            .cmd("PUBLISH")
+            .arg(self.channel(&tick.instrument))
+            .arg(&payload)
+            .ignore()
+            .cmd("SET")
+            .arg(self.quote_key(&tick.instrument))
+            .arg(&payload)
+            .arg("PX")
+            .arg(ttl)
+            .ignore()
+            .query_async::<()>(&mut self.connection.clone())
+            .await
+            .map_err(redis_error)?;
+        self.published.fetch_add(1, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Latest quote stored for an instrument, unless it has expired
+    pub async fn latest(&self, instrument: &str) -> Result<Option<Tick>> {
+        let value: Option<Vec<u8>> = redis::cmd("GET")
+            .arg(self.quote_key(instrument))
+            .query_async(&mut self.connection.clone())
+            .await
+            .map_err(redis_error)?;
+        value
+            .map(|bytes| self.config.format.decode(&bytes))
+            .transpose()
+    }
+
+    fn report(&self, result: Result<()>) {
+        let error = result.err().map(|e| {
+            log::warn!("Redis publish failed: {}", e);
+            e.to_string()
+        });
+        *self.last_error.lock().unwrap() = error;
+    }
+
+    /// Mirror the pricing stream in the background
+    ///
+    /// Reconnects after `reconnect_delay` whenever the stream drops. Must
+    /// be called inside a Tokio runtime.
+    pub fn spawn(&self, client: OandaClient, instruments: Vec<String>) -> ShutdownHandle {
+        let bridge = self.clone();
+        ShutdownHandle::spawn(|mut signal| async move {
+            while bridge
+                .mirror_stream(&client, &instruments, &mut signal)
+                .await
+            {
+                tokio::select! {
+                    _ = tokio::time::sleep(bridge.config.reconnect_delay) => {}
+                    _ = signal.requested() => break,
+                }
+            }
+        })
+    }
+
+    /// Run one connection of the pricing stream; false once shutdown is
+    /// requested
+    async fn mirror_stream(
+        &self,
+        client: &OandaClient,
+        instruments: &[String],
+        signal: &mut ShutdownSignal,
+    ) -> bool {
+        let connected = tokio::select! {
+            connected = client.stream_prices(instruments) => connected,
+            _ = signal.requested() => return false,
+        };
+        let mut stream = match connected {
+            Ok(stream) => Box::pin(stream),
+            Err(e) => {
+                self.report(Err(e));
+                return true;
+            }
+        };
+        loop {
+            let next = tokio::select! {
+                next = stream.next() => next,
+                _ = signal.requested() => return false,
+            };
+            match next {
+                Some(Ok(tick)) => self.report(self.publish_tick(&tick).await),
+                Some(Err(e)) => {
+                    self.report(Err(e));
+                    return true;
+                }
+                None => return true,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+    use std::collections::HashMap;
+    use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::TcpListener;
+
+    /// Just enough of a Redis server for PUBLISH, SET and GET
+    async fn fake_redis() -> (String, Arc<Mutex<Vec<Vec<String>>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("redis://{}/", listener.local_addr().unwrap());
+        let commands = Arc::new(Mutex::new(Vec::new()));
+        let log = commands.clone();
+        tokio::spawn(async move {
+            let mut store: HashMap<String, Vec<u8>> = HashMap::new();
+            let (socket, _) = listener.accept().await.unwrap();
+            let (read, mut write) = socket.into_split();
+            let mut read = BufReader::new(read);
+            loop {
+                let mut line = String::new();
+                if read.read_line(&mut line).await.unwrap_or(0) == 0 {
+                    return;
+                }
+                let count: usize = line.trim_start_matches('*').trim().parse().unwrap();
+                let mut args = Vec::new();
+                for _ in 0..count {
+                    line.clear();
+                    read.read_line(&mut line).await.unwrap();
+                    let len: usize = line.trim_start_matches('$').trim().parse().unwrap();
+                    let mut arg = vec![0; len + 2];
+                    read.read_exact(&mut arg).await.unwrap();
+                    arg.truncate(len);
+                    args.push(arg);
+                }
+                let name = String::from_utf8_lossy(&args[0]).to_uppercase();
+                let reply = match name.as_str() {
+                    "PUBLISH" => b":1\r\n".to_vec(),
+                    "SET" => {
+                        let key = String::from_utf8_lossy(&args[1]).into_owned();
+                        store.insert(key, args[2].clone());
+                        b"+OK\r\n".to_vec()
+                    }
+                    "GET" => match store.get(String::from_utf8_lossy(&args[1]).as_ref()) {
+                        Some(value) => {
+                            let mut reply = format!("${}\r\n", value.len()).into_bytes();
+                            reply.extend_from_slice(value);
+                            reply.extend_from_slice(b"\r\n");
+                            reply
+                        }
+                        None => b"$-1\r\n".to_vec(),
+                    },
+                    _ => b"+OK\r\n".to_vec(),
+                };
+                log.lock().unwrap().push(
+                    args.iter()
+                        .map(|arg| String::from_utf8_lossy(arg).into_owned())
+                        .collect(),
+                );
+                write.write_all(&reply).await.unwrap();
+            }
+        });
+        (url, commands)
+    }
+
+    #[tokio::test]
+    async fn test_publishes_and_stores_latest_quote() {
+        let (url, commands) = fake_redis().await;
+        let bridge = RedisBridge::connect(RedisConfig::new(url)).await.unwrap();
+
+        let tick = Tick::builder()
+            .instrument("EUR_USD")
+            .bid(dec!(1.10000))
+            .ask(dec!(1.10020))
+            .build();
+        bridge.publish_tick(&tick).await.unwrap();
+        assert_eq!(bridge.published(), 1);
+        assert_eq!(bridge.latest("EUR_USD").await.unwrap(), Some(tick.clone()));
+        assert_eq!(bridge.latest("USD_JPY").await.unwrap(), None);
+
+        let commands = commands.lock().unwrap();
+        let publish = commands.iter().find(|c| c[0] == "PUBLISH").unwrap();
+        assert_eq!(publish[1], "oanda:prices:EUR_USD");
+        assert_eq!(publish[2], serde_json::to_string(&tick).unwrap());
+        let set = commands.iter().find(|c| c[0] == "SET").unwrap();
+        assert_eq!(set[1], "oanda:quote:EUR_USD");
+        assert_eq!(set[3..], ["PX".to_string(), "60000".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_bad_url_is_a_config_error() {
+        let result = RedisBridge::connect(RedisConfig::new("http://localhost")).await;
+        assert!(matches!(result, Err(Error::ConfigError(_))));
+    }
+}
@@ -0,0 +1,507 @@
+//! Accounting reports built from transaction history
+//!
+//! Reports are plain aggregations over [`Transaction`]s, so they can be fed
+//! from a live client, from a stored history, or from test fixtures alike.
+
+use crate::{
+    client::OandaClient,
+    error::Result,
+    ids::TransactionId,
+    transactions::{Transaction, TransactionFilter, TransactionType},
+};
+use chrono::{DateTime, NaiveDate, Utc};
+use futures::{Stream, TryStreamExt};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Realized P/L and cost totals in the account's home currency
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct PnlTotals {
+    #[serde(with = "crate::decimal_number")]
+    pub realized_pl: Decimal,
+    #[serde(with = "crate::decimal_number")]
+    pub financing: Decimal,
+    #[serde(with = "crate::decimal_number")]
+    pub commission: Decimal, // always positive, reduces balance
+    #[serde(with = "crate::decimal_number")]
+    pub guaranteed_execution_fee: Decimal, // always positive, reduces balance
+    pub fill_count: u32,
+}
+
+impl PnlTotals {
+    /// Net result after financing, commission and fees
+    pub fn net(&self) -> Decimal {
+        self.realized_pl + self.financing - self.commission - self.guaranteed_execution_fee
+    }
+
+    fn add(&mut self, other: &PnlTotals) {
+        self.realized_pl += other.realized_pl;
+        self.financing += other.financing;
+        self.commission += other.commission;
+        self.guaranteed_execution_fee += other.guaranteed_execution_fee;
+        self.fill_count += other.fill_count;
+    }
+}
+
+/// Realized P/L broken down per instrument and per UTC day
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PnlReport {
+    pub by_instrument: BTreeMap<String, PnlTotals>,
+    pub by_day: BTreeMap<NaiveDate, PnlTotals>,
+    pub total: PnlTotals,
+}
+
+impl PnlReport {
+    /// Build a report from already-fetched transactions
+    pub fn from_transactions<'a, I>(transactions: I) -> Self
+    where
+        I: IntoIterator<Item = &'a Transaction>,
+    {
+        let mut builder = PnlReportBuilder::new();
+        for transaction in transactions {
+            builder.add(transaction);
+        }
+        builder.build()
+    }
+
+    /// Build a report by consuming a transaction stream
+    ///
+    /// Only the running totals are kept, so arbitrarily long histories can be
+    /// processed in constant memory.
+    pub async fn from_stream<S>(stream: S, builder: PnlReportBuilder) -> Result<Self>
+    where
+        S: Stream<Item = Result<Transaction>>,
+    {
+        let builder = stream
+            .try_fold(builder, |mut builder, transaction| async move {
+                builder.add(&transaction);
+                Ok(builder)
+            })
+            .await?;
+
+        Ok(builder.build())
+    }
+
+    /// Per-instrument totals as CSV (header included)
+    pub fn instrument_csv(&self) -> String {
+        let mut csv = CSV_HEADER.replacen("key", "instrument", 1);
+        for (instrument, totals) in &self.by_instrument {
+            csv.push_str(&csv_row(instrument, totals));
+        }
+        csv
+    }
+
+    /// Per-day totals as CSV (header included)
+    pub fn daily_csv(&self) -> String {
+        let mut csv = CSV_HEADER.replacen("key", "date", 1);
+        for (day, totals) in &self.by_day {
+            csv.push_str(&csv_row(&day.to_string(), totals));
+        }
+        csv
+    }
+}
+
+const CSV_HEADER: &str =
+    "key,realized_pl,financing,commission,guaranteed_execution_fee,net,fill_count\n";
+
+/// Amounts drop trailing zeros, whose number varies between OANDA fields
+fn csv_row(key: &str, totals: &PnlTotals) -> String {
+    format!(
+        "{},{},{},{},{},{},{}\n",
+        key,
+        totals.realized_pl.normalize(),
+        totals.financing.normalize(),
+        totals.commission.normalize(),
+        totals.guaranteed_execution_fee.normalize(),
+        totals.net().normalize(),
+        totals.fill_count
+    )
+}
+
+/// Incremental builder for [`PnlReport`]
+///
+/// Feed transactions in any order with [`add`](Self::add); anything that
+/// doesn't affect realized P/L is ignored.
+#[derive(Debug, Clone, Default)]
+pub struct PnlReportBuilder {
+    from: Option<DateTime<Utc>>,
+    to: Option<DateTime<Utc>>,
+    report: PnlReport,
+}
+
+impl PnlReportBuilder {
+    /// Create new builder covering all transactions
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only include transactions with `from <= time < to`
+    pub fn date_range(mut self, from: DateTime<Utc>, to: DateTime<Utc>) -> Self {
+        self.from = Some(from);
+        self.to = Some(to);
+        self
+    }
+
+    /// Account for one transaction
+    pub fn add(&mut self, transaction: &Transaction) {
+        let Some(time) = transaction.time() else {
+            return;
+        };
+
+        if self.from.is_some_and(|from| time < from) || self.to.is_some_and(|to| time >= to) {
+            return;
+        }
+
+        match transaction {
+            Transaction::OrderFill(fill) => {
+                let totals = PnlTotals {
+                    realized_pl: fill.pl,
+                    financing: fill.financing,
+                    commission: fill.commission,
+                    guaranteed_execution_fee: fill.guaranteed_execution_fee.unwrap_or_default(),
+                    fill_count: 1,
+                };
+                self.record(&fill.instrument, time, &totals);
+            }
+            Transaction::DailyFinancing(daily) => {
+                for position in &daily.position_financings {
+                    let totals = PnlTotals {
+                        financing: position.financing,
+                        ..Default::default()
+                    };
+                    self.report
+                        .by_instrument
+                        .entry(position.instrument.clone())
+                        .or_default()
+                        .add(&totals);
+                }
+
+                let totals = PnlTotals {
+                    financing: daily.financing,
+                    ..Default::default()
+                };
+                self.report
+                    .by_day
+                    .entry(time.date_naive())
+                    .or_default()
+                    .add(&totals);
+                self.report.total.add(&totals);
+            }
+            _ => {}
+        }
+    }
+
+    /// Finish and return the report
+    pub fn build(self) -> PnlReport {
+        self.report
+    }
+
+    fn record(&mut self, instrument: &str, time: DateTime<Utc>, totals: &PnlTotals) {
+        self.report
+            .by_instrument
+            .entry(instrument.to_string())
+            .or_default()
+            .add(totals);
+        self.report
+            .by_day
+            .entry(time.date_naive())
+            .or_default()
+            .add(totals);
+        self.report.total.add(totals);
+    }
+}
+
+/// Build a realized P/L report by walking the account's transaction history
+///
+/// Only fills and daily financing are requested from OANDA.
+///
+/// # Arguments
+/// * `client` - Client for the account to report on
+/// * `from` - First transaction ID (inclusive)
+/// * `to` - Last transaction ID (inclusive)
+pub async fn realized_pnl_report(
+    client: &OandaClient,
+    from: &TransactionId,
+    to: &TransactionId,
+) -> Result<PnlReport> {
+    let filter = TransactionFilter::new()
+        .with_type(TransactionType::OrderFill)
+        .with_type(TransactionType::DailyFinancing);
+
+    let stream = client.stream_transaction_history_filtered(from, to, filter);
+    PnlReport::from_stream(stream, PnlReportBuilder::new()).await
+}
+
+/// Financing and dividend carry costs for one instrument
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct CarryCosts {
+    #[serde(with = "crate::decimal_number")]
+    pub financing: Decimal, // signed, negative is a cost
+    #[serde(with = "crate::decimal_number")]
+    pub dividend_adjustment: Decimal, // signed, negative is a cost
+    pub financing_days: u32,
+}
+
+impl CarryCosts {
+    /// Combined carry cost (financing plus dividend adjustments)
+    pub fn total(&self) -> Decimal {
+        self.financing + self.dividend_adjustment
+    }
+
+    /// Average financing per financed day
+    pub fn average_daily_financing(&self) -> Decimal {
+        if self.financing_days == 0 {
+            Decimal::ZERO
+        } else {
+            self.financing / Decimal::from(self.financing_days)
+        }
+    }
+}
+
+/// Per-instrument carry costs over a date range
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FinancingReport {
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub by_instrument: BTreeMap<String, CarryCosts>,
+    /// Account-level totals, including financing OANDA didn't attribute to a position
+    pub total: CarryCosts,
+}
+
+impl FinancingReport {
+    /// Per-instrument carry costs as CSV (header included)
+    pub fn to_csv(&self) -> String {
+        let mut csv =
+            String::from("instrument,financing,dividend_adjustment,total,financing_days\n");
+        for (instrument, costs) in &self.by_instrument {
+            csv.push_str(&format!(
+                "{},{},{},{},{}\n",
+                instrument,
+                costs.financing.normalize(),
+                costs.dividend_adjustment.normalize(),
+                costs.total().normalize(),
+                costs.financing_days
+            ));
+        }
+        csv
+    }
+}
+
+/// Incremental builder for [`FinancingReport`]
+///
+/// Only DAILY_FINANCING and DIVIDEND_ADJUSTMENT transactions with
+/// `from <= time < to` are counted.
+#[derive(Debug, Clone)]
+pub struct FinancingReportBuilder {
+    report: FinancingReport,
+}
+
+impl FinancingReportBuilder {
+    /// Create new builder for a date range
+    pub fn new(from: DateTime<Utc>, to: DateTime<Utc>) -> Self {
+        Self {
+            report: FinancingReport {
+                from,
+                to,
+                by_instrument: BTreeMap::new(),
+                total: CarryCosts::default(),
+            },
+        }
+    }
+
+    /// Account for one transaction
+    pub fn add(&mut self, transaction: &Transaction) {
+        let Some(time) = transaction.time() else {
+            return;
+        };
+
+        if time < self.report.from || time >= self.report.to {
+            return;
+        }
+
+        match transaction {
+            Transaction::DailyFinancing(daily) => {
+                for position in &daily.position_financings {
+                    let costs = self
+                        .report
+                        .by_instrument
+                        .entry(position.instrument.clone())
+                        .or_default();
+                    costs.financing += position.financing;
+                    costs.financing_days += 1;
+                }
+
+                self.report.total.financing += daily.financing;
+                self.report.total.financing_days += 1;
+            }
+            Transaction::DividendAdjustment(dividend) => {
+                self.report
+                    .by_instrument
+                    .entry(dividend.instrument.clone())
+                    .or_default()
+                    .dividend_adjustment += dividend.dividend_adjustment;
+
+                self.report.total.dividend_adjustment += dividend.dividend_adjustment;
+            }
+            _ => {}
+        }
+    }
+
+    /// Finish and return the report
+    pub fn build(self) -> FinancingReport {
+        self.report
+    }
+}
+
+/// Build a carry-cost report for the account over a date range
+///
+/// Only financing and dividend adjustment transactions are requested from OANDA.
+///
+/// # Arguments
+/// * `client` - Client for the account to report on
+/// * `from` - Start time (inclusive)
+/// * `to` - End time (exclusive)
+pub async fn financing_report(
+    client: &OandaClient,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> Result<FinancingReport> {
+    let filter = TransactionFilter::new()
+        .with_type(TransactionType::DailyFinancing)
+        .with_type(TransactionType::DividendAdjustment);
+
+    let builder = client
+        .stream_transactions_between(from, to, filter)
+        .try_fold(
+            FinancingReportBuilder::new(from, to),
+            |mut builder, transaction| async move {
+                builder.add(&transaction);
+                Ok(builder)
+            },
+        )
+        .await?;
+
+    Ok(builder.build())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn fill(id: &str, time: &str, instrument: &str, pl: &str, commission: &str) -> Transaction {
+        serde_json::from_str(&format!(
+            r#"{{
+                "id": "{id}", "time": "{time}", "accountID": "a", "batchID": "{id}",
+                "type": "ORDER_FILL", "orderID": "{id}", "instrument": "{instrument}",
+                "units": "-1000", "pl": "{pl}", "financing": "0.0000",
+                "commission": "{commission}"
+            }}"#
+        ))
+        .unwrap()
+    }
+
+    fn financing(id: &str, time: &str) -> Transaction {
+        serde_json::from_str(&format!(
+            r#"{{
+                "id": "{id}", "time": "{time}", "accountID": "a", "batchID": "{id}",
+                "type": "DAILY_FINANCING", "financing": "-1.5",
+                "positionFinancings": [
+                    {{"instrument": "EUR_USD", "financing": "-1.0"}},
+                    {{"instrument": "USD_JPY", "financing": "-0.5"}}
+                ]
+            }}"#
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_pnl_report_aggregation() {
+        let transactions = vec![
+            fill("1", "2024-01-02T10:00:00Z", "EUR_USD", "10.0", "0.5"),
+            fill("2", "2024-01-02T12:00:00Z", "USD_JPY", "-4.0", "0.0"),
+            financing("3", "2024-01-02T21:00:00Z"),
+            fill("4", "2024-01-03T09:00:00Z", "EUR_USD", "6.0", "0.5"),
+        ];
+
+        let report = PnlReport::from_transactions(&transactions);
+
+        let eur = report.by_instrument["EUR_USD"];
+        assert_eq!(eur.realized_pl, dec!(16.0));
+        assert_eq!(eur.financing, dec!(-1.0));
+        assert_eq!(eur.commission, dec!(1.0));
+        assert_eq!(eur.fill_count, 2);
+        assert_eq!(eur.net(), dec!(14.0));
+
+        let day = report.by_day[&NaiveDate::from_ymd_opt(2024, 1, 2).unwrap()];
+        assert_eq!(day.realized_pl, dec!(6.0));
+        assert_eq!(day.financing, dec!(-1.5));
+
+        assert_eq!(report.total.realized_pl, dec!(12.0));
+        assert_eq!(report.total.financing, dec!(-1.5));
+        assert_eq!(report.total.fill_count, 3);
+    }
+
+    #[test]
+    fn test_pnl_report_date_range() {
+        let from = "2024-01-03T00:00:00Z".parse().unwrap();
+        let to = "2024-01-04T00:00:00Z".parse().unwrap();
+        let mut builder = PnlReportBuilder::new().date_range(from, to);
+
+        builder.add(&fill("1", "2024-01-02T10:00:00Z", "EUR_USD", "10.0", "0.0"));
+        builder.add(&fill("2", "2024-01-03T10:00:00Z", "EUR_USD", "3.0", "0.0"));
+        let report = builder.build();
+
+        assert_eq!(report.total.realized_pl, dec!(3.0));
+        assert_eq!(report.by_day.len(), 1);
+    }
+
+    #[test]
+    fn test_pnl_report_csv() {
+        let transactions = vec![fill("1", "2024-01-02T10:00:00Z", "EUR_USD", "10.0", "0.5")];
+        let report = PnlReport::from_transactions(&transactions);
+
+        assert_eq!(
+            report.instrument_csv(),
+            "instrument,realized_pl,financing,commission,guaranteed_execution_fee,net,fill_count\n\
+             EUR_USD,10,0,0.5,0,9.5,1\n"
+        );
+        assert!(report.daily_csv().contains("2024-01-02,10,0,0.5,0,9.5,1\n"));
+    }
+
+    #[test]
+    fn test_financing_report() {
+        let dividend: Transaction = serde_json::from_str(
+            r#"{
+                "id": "5", "time": "2024-01-03T21:00:00Z", "accountID": "a", "batchID": "5",
+                "type": "DIVIDEND_ADJUSTMENT", "instrument": "SPX500_USD",
+                "dividendAdjustment": "-2.25"
+            }"#,
+        )
+        .unwrap();
+
+        let from = "2024-01-02T00:00:00Z".parse().unwrap();
+        let to = "2024-01-04T00:00:00Z".parse().unwrap();
+        let mut builder = FinancingReportBuilder::new(from, to);
+        builder.add(&financing("1", "2024-01-01T21:00:00Z")); // before range
+        builder.add(&financing("2", "2024-01-02T21:00:00Z"));
+        builder.add(&financing("3", "2024-01-03T21:00:00Z"));
+        builder.add(&fill("4", "2024-01-03T10:00:00Z", "EUR_USD", "10.0", "0.0"));
+        builder.add(&dividend);
+        let report = builder.build();
+
+        let eur = report.by_instrument["EUR_USD"];
+        assert_eq!(eur.financing, dec!(-2.0));
+        assert_eq!(eur.financing_days, 2);
+        assert_eq!(eur.average_daily_financing(), dec!(-1.0));
+
+        let spx = report.by_instrument["SPX500_USD"];
+        assert_eq!(spx.dividend_adjustment, dec!(-2.25));
+        assert_eq!(spx.total(), dec!(-2.25));
+
+        assert_eq!(report.total.financing, dec!(-3.0));
+        assert_eq!(report.total.total(), dec!(-5.25));
+        assert!(report.to_csv().contains("USD_JPY,-1,0,-1,2\n"));
+    }
+}
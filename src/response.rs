@@ -0,0 +1,58 @@
+//! Response metadata alongside parsed bodies
+//!
+//! Typed calls return only the parsed body. Wrapping a call in
+//! [`OandaClient::with_metadata`](crate::OandaClient::with_metadata) also
+//! returns the status, headers and `RequestID` of the response it parsed, so
+//! they can be logged or passed to OANDA support.
+
+use reqwest::header::HeaderMap;
+use reqwest::{Response, StatusCode};
+
+/// Status and headers of an API response
+#[derive(Debug, Clone)]
+pub struct ResponseMetadata {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    /// OANDA's `RequestID` header, if present
+    pub request_id: Option<String>,
+}
+
+impl ResponseMetadata {
+    pub(crate) fn from_response(response: &Response) -> Self {
+        Self {
+            status: response.status(),
+            headers: response.headers().clone(),
+            request_id: request_id(response),
+        }
+    }
+}
+
+/// Value of the `RequestID` response header
+pub(crate) fn request_id(response: &Response) -> Option<String> {
+    response
+        .headers()
+        .get("RequestID")
+        .and_then(|h| h.to_str().ok())
+        .map(str::to_string)
+}
+
+/// A parsed body with the metadata of the response it came from
+#[derive(Debug, Clone)]
+pub struct ResponseEnvelope<T> {
+    pub body: T,
+    /// The last response the call parsed; `None` if it made no request, e.g.
+    /// when the instrument list came from the cache
+    pub metadata: Option<ResponseMetadata>,
+}
+
+impl<T> ResponseEnvelope<T> {
+    /// OANDA's `RequestID` for the response
+    pub fn request_id(&self) -> Option<&str> {
+        self.metadata.as_ref()?.request_id.as_deref()
+    }
+
+    /// Drop the metadata
+    pub fn into_body(self) -> T {
+        self.body
+    }
+}
@@ -0,0 +1,430 @@
+//! Pre-trade risk limits
+//!
+//! [`RiskManager`] wraps any [`Trading`] implementation and checks each
+//! order against [`RiskLimits`] before passing it on. Orders that would
+//! breach a limit fail locally with `Error::RiskLimitExceeded` and never
+//! reach OANDA. Orders that only reduce an existing position are always
+//! allowed, so a breached limit never stops you from getting out.
+//!
+//! ```no_run
+//! use oanda_connector::risk::{RiskLimits, RiskManager};
+//! use oanda_connector::trading::Trading;
+//! use oanda_connector::{Error, OandaClient, OandaConfig, OrderRequest};
+//! use rust_decimal_macros::dec;
+//!
+//! # async fn run() -> oanda_connector::Result<()> {
+//! let client = OandaClient::new(OandaConfig::from_env()?)?;
+//! let broker = RiskManager::new(client, RiskLimits {
+//!     max_units_per_instrument: Some(dec!(100000)),
+//!     max_daily_loss: Some(dec!(500)),
+//!     max_open_trades: Some(10),
+//!     ..RiskLimits::default()
+//! });
+//!
+//! match broker.place_order(&OrderRequest::market("EUR_USD", dec!(250000))).await {
+//!     Err(Error::RiskLimitExceeded(violation)) => eprintln!("blocked: {}", violation),
+//!     other => println!("{:?}", other),
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::error::{Error, Result};
+use crate::models::{AccountSummary, Position, Tick};
+use crate::orders::{OrderRequest, OrderResponse};
+use crate::trading::Trading;
+use async_trait::async_trait;
+use chrono::{NaiveDate, Utc};
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Mutex;
+
+/// Limits checked before each order; `None` disables a check
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RiskLimits {
+    /// Largest net position, long or short, in any one instrument
+    pub max_units_per_instrument: Option<Decimal>,
+    /// Per-instrument overrides of `max_units_per_instrument`
+    pub instrument_units: HashMap<String, Decimal>,
+    /// Largest combined value of all positions, in the account currency
+    pub max_total_exposure: Option<Decimal>,
+    /// Largest fall in NAV since the first check of the UTC day
+    pub max_daily_loss: Option<Decimal>,
+    pub max_open_trades: Option<i32>,
+}
+
+impl RiskLimits {
+    /// Unit limit that applies to an instrument
+    pub fn units_limit(&self, instrument: &str) -> Option<Decimal> {
+        self.instrument_units
+            .get(instrument)
+            .copied()
+            .or(self.max_units_per_instrument)
+    }
+}
+
+/// The limit an order would have breached
+#[derive(Debug, Clone, PartialEq)]
+pub enum RiskViolation {
+    InstrumentUnits {
+        instrument: String,
+        /// Net position after the order
+        units: Decimal,
+        limit: Decimal,
+    },
+    TotalExposure {
+        /// Exposure after the order, in the account currency
+        exposure: Decimal,
+        limit: Decimal,
+    },
+    DailyLoss {
+        loss: Decimal,
+        limit: Decimal,
+    },
+    OpenTrades {
+        count: i32,
+        limit: i32,
+    },
+}
+
+impl fmt::Display for RiskViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RiskViolation::InstrumentUnits {
+                instrument,
+                units,
+                limit,
+            } => write!(
+                f,
+                "{} position would be {} units, limit {}",
+                instrument, units, limit
+            ),
+            RiskViolation::TotalExposure { exposure, limit } => {
+                write!(f, "total exposure would be {}, limit {}", exposure, limit)
+            }
+            RiskViolation::DailyLoss { loss, limit } => {
+                write!(f, "daily loss {} reached limit {}", loss, limit)
+            }
+            RiskViolation::OpenTrades { count, limit } => {
+                write!(f, "{} open trades, limit {}", count, limit)
+            }
+        }
+    }
+}
+
+/// [`Trading`] wrapper enforcing [`RiskLimits`]
+///
+/// Checks and placement are serialized, so concurrent orders are each
+/// checked against the positions left by the ones before them.
+pub struct RiskManager<T> {
+    inner: T,
+    limits: RiskLimits,
+    /// UTC day and NAV at its first check
+    day_start: Mutex<Option<(NaiveDate, Decimal)>>,
+    order_lock: tokio::sync::Mutex<()>,
+}
+
+impl<T: Trading> RiskManager<T> {
+    pub fn new(inner: T, limits: RiskLimits) -> Self {
+        Self {
+            inner,
+            limits,
+            day_start: Mutex::new(None),
+            order_lock: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    pub fn limits(&self) -> &RiskLimits {
+        &self.limits
+    }
+
+    /// The wrapped implementation, for calls that bypass the checks
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    /// Check an order against the limits without placing it
+    pub async fn check(&self, order: &OrderRequest) -> Result<()> {
+        let positions = self.inner.get_open_positions().await?;
+        let net_units = |instrument: &str| {
+            positions
+                .iter()
+                .filter(|p| p.instrument == instrument)
+                .map(Position::net_units)
+                .sum::<Decimal>()
+        };
+        let current = net_units(&order.instrument);
+        let after = current + order.units;
+        if after.abs() <= current.abs() && after * current >= Decimal::ZERO {
+            // Only reduces an existing position
+            return Ok(());
+        }
+
+        if let Some(limit) = self.limits.units_limit(&order.instrument) {
+            if after.abs() > limit {
+                return Err(violation(RiskViolation::InstrumentUnits {
+                    instrument: order.instrument.clone(),
+                    units: after,
+                    limit,
+                }));
+            }
+        }
+
+        let limits = &self.limits;
+        if limits.max_daily_loss.is_none()
+            && limits.max_open_trades.is_none()
+            && limits.max_total_exposure.is_none()
+        {
+            return Ok(());
+        }
+        let summary = self.inner.get_account_summary().await?;
+        if let Some(limit) = limits.max_daily_loss {
+            let loss = self.daily_loss(&summary);
+            if loss >= limit {
+                return Err(violation(RiskViolation::DailyLoss { loss, limit }));
+            }
+        }
+        if let Some(limit) = limits.max_open_trades {
+            if summary.open_trade_count >= limit {
+                return Err(violation(RiskViolation::OpenTrades {
+                    count: summary.open_trade_count,
+                    limit,
+                }));
+            }
+        }
+
+        if let Some(limit) = limits.max_total_exposure {
+            let currency = summary.currency;
+            let mut exposure = Decimal::ZERO;
+            let mut instruments: Vec<&str> = positions
+                .iter()
+                .map(|p| p.instrument.as_str())
+                .filter(|instrument| *instrument != order.instrument)
+                .collect();
+            instruments.dedup();
+            for instrument in instruments {
+                exposure += self
+                    .value(instrument, net_units(instrument), &currency)
+                    .await?;
+            }
+            exposure += self.value(&order.instrument, after, &currency).await?;
+            if exposure > limit {
+                return Err(violation(RiskViolation::TotalExposure { exposure, limit }));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// NAV lost since the day's first check
+    fn daily_loss(&self, summary: &AccountSummary) -> Decimal {
+        let today = Utc::now().date_naive();
+        let mut day_start = self.day_start.lock().unwrap();
+        let start_nav = match *day_start {
+            Some((day, nav)) if day == today => nav,
+            _ => {
+                *day_start = Some((today, summary.nav));
+                summary.nav
+            }
+        };
+        (start_nav - summary.nav).max(Decimal::ZERO)
+    }
+
+    /// Absolute value of `units` of an instrument in `currency`
+    ///
+    /// Prices the instrument's base currency against `currency` directly,
+    /// e.g. EUR_GBP in a USD account through EUR_USD.
+    async fn value(&self, instrument: &str, units: Decimal, currency: &str) -> Result<Decimal> {
+        if units.is_zero() {
+            return Ok(Decimal::ZERO);
+        }
+        let base = instrument
+            .split_once('_')
+            .map_or(instrument, |(base, _)| base);
+        if base == currency {
+            return Ok(units.abs());
+        }
+        if let Ok(tick) = self
+            .inner
+            .get_current_price(&format!("{}_{}", base, currency))
+            .await
+        {
+            return Ok(units.abs() * tick.mid());
+        }
+        let tick = self
+            .inner
+            .get_current_price(&format!("{}_{}", currency, base))
+            .await?;
+        units
+            .abs()
+            .checked_div(tick.mid())
+            .ok_or_else(|| Error::InvalidInstrument(format!("no usable price for {}", base)))
+    }
+}
+
+fn violation(violation: RiskViolation) -> Error {
+    Error::RiskLimitExceeded(violation)
+}
+
+#[async_trait]
+impl<T: Trading> Trading for RiskManager<T> {
+    async fn get_current_price(&self, instrument: &str) -> Result<Tick> {
+        self.inner.get_current_price(instrument).await
+    }
+
+    async fn get_account_summary(&self) -> Result<AccountSummary> {
+        self.inner.get_account_summary().await
+    }
+
+    async fn get_open_positions(&self) -> Result<Vec<Position>> {
+        self.inner.get_open_positions().await
+    }
+
+    async fn place_order(&self, order: &OrderRequest) -> Result<OrderResponse> {
+        let _guard = self.order_lock.lock().await;
+        self.check(order).await?;
+        self.inner.place_order(order).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::PriceStatus;
+    use crate::paper::{PaperConfig, PaperTrader};
+    use rust_decimal_macros::dec;
+
+    fn tick(instrument: &str, mid: Decimal) -> Tick {
+        Tick {
+            instrument: instrument.to_string(),
+            timestamp: Utc::now(),
+            bid: mid,
+            ask: mid,
+            tradeable: true,
+            status: PriceStatus::Tradeable,
+            closeout_bid: None,
+            closeout_ask: None,
+        }
+    }
+
+    fn blocked(result: Result<OrderResponse>) -> Option<RiskViolation> {
+        match result {
+            Err(Error::RiskLimitExceeded(violation)) => Some(violation),
+            _ => None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_instrument_units_and_reducing_orders() {
+        let paper = PaperTrader::new(PaperConfig::default());
+        paper.on_tick(&tick("EUR_USD", dec!(1.1)));
+        let broker = RiskManager::new(
+            paper,
+            RiskLimits {
+                max_units_per_instrument: Some(dec!(1000)),
+                instrument_units: HashMap::from([("USD_JPY".to_string(), dec!(50))]),
+                ..RiskLimits::default()
+            },
+        );
+
+        broker
+            .place_order(&OrderRequest::market("EUR_USD", dec!(800)))
+            .await
+            .unwrap();
+        assert!(matches!(
+            blocked(
+                broker
+                    .place_order(&OrderRequest::market("EUR_USD", dec!(300)))
+                    .await
+            ),
+            Some(RiskViolation::InstrumentUnits { units, .. }) if units == dec!(1100)
+        ));
+        // Flipping to a short beyond the limit is blocked too
+        assert!(blocked(
+            broker
+                .place_order(&OrderRequest::market("EUR_USD", dec!(-2000)))
+                .await
+        )
+        .is_some());
+        broker
+            .place_order(&OrderRequest::market("EUR_USD", dec!(-500)))
+            .await
+            .unwrap();
+        assert_eq!(broker.limits().units_limit("USD_JPY"), Some(dec!(50)));
+    }
+
+    #[tokio::test]
+    async fn test_total_exposure_in_account_currency() {
+        let paper = PaperTrader::new(PaperConfig::default());
+        paper.on_tick(&tick("EUR_USD", dec!(1.1)));
+        paper.on_tick(&tick("EUR_GBP", dec!(0.85)));
+        paper.on_tick(&tick("USD_JPY", dec!(150)));
+        let broker = RiskManager::new(
+            paper,
+            RiskLimits {
+                max_total_exposure: Some(dec!(25000)),
+                ..RiskLimits::default()
+            },
+        );
+
+        // 10000 EUR valued through EUR_USD: 11000 USD
+        broker
+            .place_order(&OrderRequest::market("EUR_GBP", dec!(10000)))
+            .await
+            .unwrap();
+        // USD-based pairs count their units: 11000 + 10000
+        broker
+            .place_order(&OrderRequest::market("USD_JPY", dec!(-10000)))
+            .await
+            .unwrap();
+        let violation = blocked(
+            broker
+                .place_order(&OrderRequest::market("EUR_USD", dec!(5000)))
+                .await,
+        );
+        assert_eq!(
+            violation,
+            Some(RiskViolation::TotalExposure {
+                exposure: dec!(26500),
+                limit: dec!(25000)
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_daily_loss_and_open_trades() {
+        let paper = PaperTrader::new(PaperConfig::default());
+        paper.on_tick(&tick("EUR_USD", dec!(1.1)));
+        let broker = RiskManager::new(
+            paper.clone(),
+            RiskLimits {
+                max_daily_loss: Some(dec!(100)),
+                max_open_trades: Some(5),
+                ..RiskLimits::default()
+            },
+        );
+
+        broker
+            .place_order(&OrderRequest::market("EUR_USD", dec!(10000)))
+            .await
+            .unwrap();
+        paper.on_tick(&tick("EUR_USD", dec!(1.09)));
+        assert_eq!(
+            blocked(
+                broker
+                    .place_order(&OrderRequest::market("EUR_USD", dec!(1000)))
+                    .await
+            ),
+            Some(RiskViolation::DailyLoss {
+                loss: dec!(100),
+                limit: dec!(100)
+            })
+        );
+        // Closing is still allowed
+        broker
+            .place_order(&OrderRequest::market("EUR_USD", dec!(-10000)))
+            .await
+            .unwrap();
+    }
+}
@@ -0,0 +1,780 @@
+//! Scheduled orders and rebalances
+//!
+//! [`Scheduler`] runs [`ScheduledJob`]s at fixed times or intervals, such
+//! as a daily dollar-cost-averaging buy or a weekly rebalance to target
+//! positions. Jobs run through any [`Trading`] implementation, so a
+//! schedule can be tried on a [`PaperTrader`](crate::paper::PaperTrader)
+//! first.
+//!
+//! Every run is identified by the job name and its scheduled time, and
+//! orders carry that key as their client order ID. Before a failed run is
+//! retried its orders are looked up by that ID with
+//! [`Trading::find_order`], so an order that went through despite a
+//! timeout is not sent again. (OANDA only refuses a reused client ID while
+//! the first order is still pending, so a filled market order would
+//! otherwise be repeated.) A run is never repeated once it has completed,
+//! and runs that fall while the market is closed wait for it to open, or
+//! are skipped.
+//!
+//! Progress is kept in memory and a job added without a start time begins
+//! at the time it is added. To catch up after a restart, persist
+//! [`Scheduler::completed_until`] and pass it back through
+//! [`ScheduledJob::starting`]; runs missed in between are then coalesced
+//! into one.
+//!
+//! ```no_run
+//! use chrono::NaiveTime;
+//! use oanda_connector::scheduler::{Schedule, ScheduledJob, Scheduler};
+//! use oanda_connector::{OandaClient, OandaConfig, OrderRequest};
+//! use rust_decimal_macros::dec;
+//!
+//! # async fn run() -> oanda_connector::Result<()> {
+//! let client = OandaClient::new(OandaConfig::from_env()?)?;
+//! let scheduler = Scheduler::new(client);
+//! scheduler.add(ScheduledJob::order(
+//!     "dca-gold",
+//!     Schedule::Daily(NaiveTime::from_hms_opt(14, 0, 0).unwrap()),
+//!     OrderRequest::market("XAU_USD", dec!(1)),
+//! ));
+//!
+//! let mut events = scheduler.subscribe();
+//! let _task = scheduler.spawn();
+//! while let Ok(event) = events.recv().await {
+//!     println!("{:?}", event);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::calendar::MarketCalendar;
+use crate::error::{Error, Result};
+use crate::orders::{OrderRejectReason, OrderRequest, OrderResponse};
+use crate::shutdown::{ShutdownHandle, EVENT_CAPACITY};
+use crate::trading::Trading;
+use chrono::{DateTime, Datelike, Duration as TimeDelta, NaiveTime, Utc, Weekday};
+use rust_decimal::Decimal;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::broadcast;
+
+/// Longest the background task sleeps before looking at the jobs again
+const MAX_SLEEP: Duration = Duration::from_secs(60);
+
+/// When a job runs; times are UTC
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Schedule {
+    /// At every multiple of the interval since the UNIX epoch, so an hourly
+    /// job runs on the hour
+    Interval(Duration),
+    /// Every day at a time
+    Daily(NaiveTime),
+    /// Every week on a day at a time
+    Weekly(Weekday, NaiveTime),
+}
+
+impl Schedule {
+    /// First scheduled time strictly after `after`
+    pub fn next_after(&self, after: DateTime<Utc>) -> DateTime<Utc> {
+        match *self {
+            Schedule::Interval(interval) => {
+                let step = interval.as_secs().max(1) as i64;
+                let next = (after.timestamp().div_euclid(step) + 1) * step;
+                DateTime::from_timestamp(next, 0).unwrap_or(DateTime::<Utc>::MAX_UTC)
+            }
+            Schedule::Daily(time) => {
+                let today = after.date_naive().and_time(time).and_utc();
+                if today > after {
+                    today
+                } else {
+                    today + TimeDelta::days(1)
+                }
+            }
+            Schedule::Weekly(weekday, time) => {
+                let days_ahead = (weekday.num_days_from_monday() + 7
+                    - after.weekday().num_days_from_monday())
+                    % 7;
+                let candidate = (after.date_naive() + TimeDelta::days(days_ahead as i64))
+                    .and_time(time)
+                    .and_utc();
+                if candidate > after {
+                    candidate
+                } else {
+                    candidate + TimeDelta::weeks(1)
+                }
+            }
+        }
+    }
+}
+
+/// What a job does when it runs
+#[derive(Debug, Clone, PartialEq)]
+pub enum JobAction {
+    /// Submit an order
+    Order(OrderRequest),
+    /// Trade each instrument to a target net position, in units
+    Rebalance(BTreeMap<String, Decimal>),
+}
+
+/// What to do with a run that falls while the market is closed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ClosedMarketPolicy {
+    /// Run as soon as the market opens
+    #[default]
+    Defer,
+    /// Skip the run and wait for the next one
+    Skip,
+}
+
+/// A named action and when to run it
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScheduledJob {
+    /// Unique name; part of every client order ID the job sends
+    pub name: String,
+    pub schedule: Schedule,
+    pub action: JobAction,
+    pub when_closed: ClosedMarketPolicy,
+    /// First run is the first scheduled time after this; `None` for the
+    /// time the job is added
+    pub start: Option<DateTime<Utc>>,
+}
+
+impl ScheduledJob {
+    pub fn new(name: &str, schedule: Schedule, action: JobAction) -> Self {
+        Self {
+            name: name.to_string(),
+            schedule,
+            action,
+            when_closed: ClosedMarketPolicy::default(),
+            start: None,
+        }
+    }
+
+    /// Submit `order` on every run
+    pub fn order(name: &str, schedule: Schedule, order: OrderRequest) -> Self {
+        Self::new(name, schedule, JobAction::Order(order))
+    }
+
+    /// Trade to `targets` (instrument to net units) on every run
+    pub fn rebalance<I>(name: &str, schedule: Schedule, targets: I) -> Self
+    where
+        I: IntoIterator<Item = (String, Decimal)>,
+    {
+        Self::new(
+            name,
+            schedule,
+            JobAction::Rebalance(targets.into_iter().collect()),
+        )
+    }
+
+    /// Skip runs that fall while the market is closed
+    pub fn skip_when_closed(mut self) -> Self {
+        self.when_closed = ClosedMarketPolicy::Skip;
+        self
+    }
+
+    /// Only run at scheduled times after `start`
+    pub fn starting(mut self, start: DateTime<Utc>) -> Self {
+        self.start = Some(start);
+        self
+    }
+
+    /// Client order ID prefix of the run scheduled at `run`
+    pub fn run_key(&self, run: DateTime<Utc>) -> String {
+        format!("{}-{}", self.name, run.format("%Y%m%dT%H%M%S"))
+    }
+}
+
+/// Outcome of a scheduled run
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScheduleEvent {
+    /// The run completed; responses of the orders it sent
+    Executed {
+        job: String,
+        run: DateTime<Utc>,
+        responses: Vec<OrderResponse>,
+    },
+    /// The market was closed and the job skips closed runs
+    Skipped { job: String, run: DateTime<Utc> },
+    /// The run failed after every retry; the job waits for its next run
+    Failed {
+        job: String,
+        run: DateTime<Utc>,
+        error: String,
+    },
+}
+
+impl ScheduleEvent {
+    pub fn job(&self) -> &str {
+        match self {
+            ScheduleEvent::Executed { job, .. }
+            | ScheduleEvent::Skipped { job, .. }
+            | ScheduleEvent::Failed { job, .. } => job,
+        }
+    }
+}
+
+struct Job {
+    spec: ScheduledJob,
+    /// Runs up to and including this time are done
+    cursor: DateTime<Utc>,
+}
+
+impl Job {
+    /// Latest scheduled run at or before `now` that hasn't completed
+    fn due(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let mut run = self.spec.schedule.next_after(self.cursor);
+        if run > now {
+            return None;
+        }
+        loop {
+            let next = self.spec.schedule.next_after(run);
+            if next > now {
+                return Some(run);
+            }
+            run = next;
+        }
+    }
+}
+
+/// Runs scheduled jobs against a broker
+///
+/// Cloning is cheap and clones share the same jobs and channel.
+pub struct Scheduler<T> {
+    broker: Arc<T>,
+    calendar: MarketCalendar,
+    max_retries: u32,
+    retry_delay: Duration,
+    jobs: Arc<Mutex<Vec<Job>>>,
+    events: broadcast::Sender<ScheduleEvent>,
+}
+
+impl<T> Clone for Scheduler<T> {
+    fn clone(&self) -> Self {
+        Self {
+            broker: self.broker.clone(),
+            calendar: self.calendar.clone(),
+            max_retries: self.max_retries,
+            retry_delay: self.retry_delay,
+            jobs: self.jobs.clone(),
+            events: self.events.clone(),
+        }
+    }
+}
+
+impl<T: Trading> Scheduler<T> {
+    /// Run jobs through `broker` under the default market calendar
+    ///
+    /// Failed runs are retried up to 3 times, 5 seconds apart.
+    pub fn new(broker: T) -> Self {
+        let (events, _) = broadcast::channel(EVENT_CAPACITY);
+        Self {
+            broker: Arc::new(broker),
+            calendar: MarketCalendar::default(),
+            max_retries: 3,
+            retry_delay: Duration::from_secs(5),
+            jobs: Arc::new(Mutex::new(Vec::new())),
+            events,
+        }
+    }
+
+    /// Market hours and holidays used for closed-market handling
+    pub fn with_calendar(mut self, calendar: MarketCalendar) -> Self {
+        self.calendar = calendar;
+        self
+    }
+
+    /// Retries after a transient failure (0 disables)
+    pub fn max_retries(mut self, max: u32) -> Self {
+        self.max_retries = max;
+        self
+    }
+
+    /// Delay between retries
+    pub fn retry_delay(mut self, delay: Duration) -> Self {
+        self.retry_delay = delay;
+        self
+    }
+
+    pub fn broker(&self) -> &T {
+        &self.broker
+    }
+
+    /// Schedule a job, replacing any job with the same name
+    pub fn add(&self, job: ScheduledJob) {
+        let cursor = job.start.unwrap_or_else(Utc::now);
+        let mut jobs = self.jobs.lock().unwrap();
+        jobs.retain(|j| j.spec.name != job.name);
+        jobs.push(Job { spec: job, cursor });
+    }
+
+    /// Unschedule a job; returns it if it was scheduled
+    pub fn remove(&self, name: &str) -> Option<ScheduledJob> {
+        let mut jobs = self.jobs.lock().unwrap();
+        let index = jobs.iter().position(|j| j.spec.name == name)?;
+        Some(jobs.remove(index).spec)
+    }
+
+    /// Scheduled jobs
+    pub fn jobs(&self) -> Vec<ScheduledJob> {
+        let jobs = self.jobs.lock().unwrap();
+        jobs.iter().map(|j| j.spec.clone()).collect()
+    }
+
+    /// Next scheduled time of a job, before any closed-market delay
+    pub fn next_run(&self, name: &str) -> Option<DateTime<Utc>> {
+        let jobs = self.jobs.lock().unwrap();
+        let job = jobs.iter().find(|j| j.spec.name == name)?;
+        Some(job.spec.schedule.next_after(job.cursor))
+    }
+
+    /// Time up to which a job's runs are done, including failed and
+    /// skipped ones
+    ///
+    /// Store this to resume the job with [`ScheduledJob::starting`] after
+    /// a restart.
+    pub fn completed_until(&self, name: &str) -> Option<DateTime<Utc>> {
+        let jobs = self.jobs.lock().unwrap();
+        jobs.iter()
+            .find(|j| j.spec.name == name)
+            .map(|job| job.cursor)
+    }
+
+    /// Receiver for run outcomes from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<ScheduleEvent> {
+        self.events.subscribe()
+    }
+
+    /// Run every job that is due at `now`
+    ///
+    /// Returns the outcomes, which are also published to subscribers.
+    /// Deferred runs stay due until the market opens.
+    pub async fn run_due(&self, now: DateTime<Utc>) -> Vec<ScheduleEvent> {
+        let open = self.calendar.is_open(now);
+        let due: Vec<(ScheduledJob, DateTime<Utc>)> = {
+            let jobs = self.jobs.lock().unwrap();
+            jobs.iter()
+                .filter_map(|job| Some((job.spec.clone(), job.due(now)?)))
+                .filter(|(job, _)| open || job.when_closed == ClosedMarketPolicy::Skip)
+                .collect()
+        };
+
+        let mut outcomes = Vec::new();
+        for (job, run) in due {
+            let event = if !open {
+                ScheduleEvent::Skipped {
+                    job: job.name.clone(),
+                    run,
+                }
+            } else {
+                match self.execute(&job, run).await {
+                    Ok(responses) => ScheduleEvent::Executed {
+                        job: job.name.clone(),
+                        run,
+                        responses,
+                    },
+                    Err(e) => {
+                        log::warn!("scheduled job {} failed: {}", job.name, e);
+                        ScheduleEvent::Failed {
+                            job: job.name.clone(),
+                            run,
+                            error: e.to_string(),
+                        }
+                    }
+                }
+            };
+
+            let mut jobs = self.jobs.lock().unwrap();
+            if let Some(entry) = jobs.iter_mut().find(|j| j.spec.name == job.name) {
+                entry.cursor = entry.cursor.max(run);
+            }
+            drop(jobs);
+            let _ = self.events.send(event.clone());
+            outcomes.push(event);
+        }
+        outcomes
+    }
+
+    /// When a job next needs attention, allowing for closed markets
+    pub fn next_wake(&self) -> Option<DateTime<Utc>> {
+        let jobs = self.jobs.lock().unwrap();
+        jobs.iter()
+            .map(|job| {
+                let run = job.spec.schedule.next_after(job.cursor);
+                match job.spec.when_closed {
+                    ClosedMarketPolicy::Defer => self.calendar.next_open(run),
+                    ClosedMarketPolicy::Skip => run,
+                }
+            })
+            .min()
+    }
+
+    /// Run one job, retrying transient failures
+    async fn execute(&self, job: &ScheduledJob, run: DateTime<Utc>) -> Result<Vec<OrderResponse>> {
+        let key = job.run_key(run);
+        let mut attempt = 0;
+        loop {
+            match self.try_execute(&job.action, &key, attempt > 0).await {
+                Ok(responses) => return Ok(responses),
+                Err(e) if e.is_retryable() && attempt < self.max_retries => {
+                    attempt += 1;
+                    log::debug!("retrying scheduled job {} ({}): {}", job.name, attempt, e);
+                    tokio::time::sleep(self.retry_delay).await;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    async fn try_execute(
+        &self,
+        action: &JobAction,
+        key: &str,
+        retry: bool,
+    ) -> Result<Vec<OrderResponse>> {
+        match action {
+            JobAction::Order(order) => {
+                let order = with_client_id(order.clone(), key.to_string());
+                Ok(self.place(&order, retry).await?.into_iter().collect())
+            }
+            JobAction::Rebalance(targets) => {
+                let positions = self.broker.get_open_positions().await?;
+                let mut responses = Vec::new();
+                for (instrument, target) in targets {
+                    let current = positions
+                        .iter()
+                        .find(|p| &p.instrument == instrument)
+                        .map_or(Decimal::ZERO, |p| p.net_units());
+                    let units = target - current;
+                    if units.is_zero() {
+                        continue;
+                    }
+                    let order = with_client_id(
+                        OrderRequest::market(instrument, units),
+                        format!("{}-{}", key, instrument),
+                    );
+                    responses.extend(self.place(&order, retry).await?);
+                }
+                Ok(responses)
+            }
+        }
+    }
+
+    /// Place an order; `None` if an earlier attempt already placed it
+    ///
+    /// On a retry the order is looked up by client ID first.
+    async fn place(&self, order: &OrderRequest, retry: bool) -> Result<Option<OrderResponse>> {
+        let client_id = order
+            .client_extensions
+            .as_ref()
+            .and_then(|extensions| extensions.id.as_deref());
+        if let (true, Some(client_id)) = (retry, client_id) {
+            if let Some(existing) = self.broker.find_order(client_id).await? {
+                log::debug!("order {} already placed as {}", client_id, existing.id);
+                return Ok(None);
+            }
+        }
+        match self.broker.place_order(order).await {
+            Ok(response) => Ok(Some(response)),
+            Err(e)
+                if matches!(
+                    e.inner(),
+                    Error::OrderRejected {
+                        reason: OrderRejectReason::ClientOrderIdAlreadyExists,
+                        ..
+                    }
+                ) =>
+            {
+                Ok(None)
+            }
+            Err(e) => Err(e),
+        }
+    }
+}
+
+impl<T: Trading + 'static> Scheduler<T> {
+    /// Run due jobs in the background
+    ///
+    /// The task runs until shut down through the returned handle, or until
+    /// the handle is dropped.
+    pub fn spawn(&self) -> ShutdownHandle {
+        let scheduler = self.clone();
+        ShutdownHandle::spawn(|mut signal| async move {
+            loop {
+                scheduler.run_due(Utc::now()).await;
+                let sleep = scheduler
+                    .next_wake()
+                    .and_then(|wake| (wake - Utc::now()).to_std().ok())
+                    .unwrap_or(Duration::ZERO)
+                    .clamp(Duration::from_millis(100), MAX_SLEEP);
+                tokio::select! {
+                    _ = tokio::time::sleep(sleep) => {}
+                    _ = signal.requested() => break,
+                }
+            }
+        })
+    }
+}
+
+/// Tag an order with a client ID, keeping its tag and comment
+fn with_client_id(mut order: OrderRequest, id: String) -> OrderRequest {
+    order
+        .client_extensions
+        .get_or_insert_with(Default::default)
+        .id = Some(id);
+    order
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{AccountSummary, Position, PriceStatus, Tick};
+    use crate::orders::Order;
+    use crate::paper::{PaperConfig, PaperTrader};
+    use async_trait::async_trait;
+    use chrono::TimeZone;
+    use rust_decimal_macros::dec;
+    use std::collections::HashMap;
+
+    /// Places orders but loses the first response, as after a timeout
+    struct LostResponse {
+        paper: PaperTrader,
+        placed: Mutex<HashMap<String, Order>>,
+    }
+
+    #[async_trait]
+    impl Trading for LostResponse {
+        async fn get_current_price(&self, instrument: &str) -> Result<Tick> {
+            self.paper.get_current_price(instrument).await
+        }
+
+        async fn get_account_summary(&self) -> Result<AccountSummary> {
+            self.paper.get_account_summary().await
+        }
+
+        async fn get_open_positions(&self) -> Result<Vec<Position>> {
+            self.paper.get_open_positions().await
+        }
+
+        async fn place_order(&self, order: &OrderRequest) -> Result<OrderResponse> {
+            let response = self.paper.place_order(order).await?;
+            let client_id = order.client_extensions.clone().unwrap().id.unwrap();
+            let mut placed = self.placed.lock().unwrap();
+            let first = placed.is_empty();
+            let stored = serde_json::json!({
+                "id": (placed.len() + 1).to_string(),
+                "type": "MARKET",
+                "state": "FILLED",
+                "clientExtensions": {"id": client_id},
+            });
+            placed.insert(client_id, serde_json::from_value(stored).unwrap());
+            if first {
+                return Err(Error::Timeout(10));
+            }
+            Ok(response)
+        }
+
+        async fn find_order(&self, client_id: &str) -> Result<Option<Order>> {
+            Ok(self.placed.lock().unwrap().get(client_id).cloned())
+        }
+    }
+
+    fn utc(day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+        // July 2025; the 7th is a Monday
+        Utc.with_ymd_and_hms(2025, 7, day, hour, minute, 0).unwrap()
+    }
+
+    fn paper() -> PaperTrader {
+        let paper = PaperTrader::new(PaperConfig::default());
+        for (instrument, price) in [("XAU_USD", dec!(2000)), ("EUR_USD", dec!(1.1000))] {
+            paper.on_tick(&Tick {
+                instrument: instrument.to_string(),
+                timestamp: Utc::now(),
+                bid: price,
+                ask: price,
+                tradeable: true,
+                status: PriceStatus::Tradeable,
+                closeout_bid: None,
+                closeout_ask: None,
+            });
+        }
+        paper
+    }
+
+    fn at(hour: u32, minute: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(hour, minute, 0).unwrap()
+    }
+
+    #[test]
+    fn test_schedule_next_after() {
+        let hourly = Schedule::Interval(Duration::from_secs(3600));
+        assert_eq!(hourly.next_after(utc(7, 10, 15)), utc(7, 11, 0));
+        assert_eq!(hourly.next_after(utc(7, 11, 0)), utc(7, 12, 0));
+
+        let daily = Schedule::Daily(at(14, 0));
+        assert_eq!(daily.next_after(utc(7, 13, 59)), utc(7, 14, 0));
+        assert_eq!(daily.next_after(utc(7, 14, 0)), utc(8, 14, 0));
+
+        let weekly = Schedule::Weekly(Weekday::Wed, at(9, 30));
+        assert_eq!(weekly.next_after(utc(7, 0, 0)), utc(9, 9, 30));
+        assert_eq!(weekly.next_after(utc(9, 9, 30)), utc(16, 9, 30));
+    }
+
+    #[tokio::test]
+    async fn test_daily_order_runs_once_and_coalesces() {
+        let paper = paper();
+        let scheduler = Scheduler::new(paper.clone());
+        scheduler.add(
+            ScheduledJob::order(
+                "dca",
+                Schedule::Daily(at(14, 0)),
+                OrderRequest::market("XAU_USD", dec!(1)),
+            )
+            .starting(utc(7, 0, 0)),
+        );
+
+        assert!(scheduler.run_due(utc(7, 13, 0)).await.is_empty());
+        let events = scheduler.run_due(utc(7, 14, 5)).await;
+        assert_eq!(events.len(), 1);
+        assert!(
+            matches!(&events[0], ScheduleEvent::Executed { responses, .. } if responses.len() == 1)
+        );
+        // Already done
+        assert!(scheduler.run_due(utc(7, 15, 0)).await.is_empty());
+
+        // Two missed days run once, under the latest run's key
+        let events = scheduler.run_due(utc(10, 15, 0)).await;
+        assert_eq!(events.len(), 1);
+        assert!(
+            matches!(&events[0], ScheduleEvent::Executed { run, .. } if *run == utc(10, 14, 0))
+        );
+        assert_eq!(paper.positions()[0].units, dec!(2));
+        assert_eq!(scheduler.next_run("dca"), Some(utc(11, 14, 0)));
+    }
+
+    #[tokio::test]
+    async fn test_retry_finds_order_placed_despite_error() {
+        let paper = paper();
+        let scheduler = Scheduler::new(LostResponse {
+            paper: paper.clone(),
+            placed: Mutex::new(HashMap::new()),
+        })
+        .retry_delay(Duration::ZERO);
+        scheduler.add(
+            ScheduledJob::order(
+                "dca",
+                Schedule::Daily(at(14, 0)),
+                OrderRequest::market("XAU_USD", dec!(1)),
+            )
+            .starting(utc(7, 0, 0)),
+        );
+
+        let events = scheduler.run_due(utc(7, 14, 5)).await;
+        // Filled once; the retry found the order instead of sending it again
+        assert!(
+            matches!(&events[0], ScheduleEvent::Executed { responses, .. } if responses.is_empty())
+        );
+        assert_eq!(paper.positions()[0].units, dec!(1));
+        assert!(scheduler
+            .broker()
+            .placed
+            .lock()
+            .unwrap()
+            .contains_key("dca-20250707T140000"));
+    }
+
+    #[tokio::test]
+    async fn test_resume_from_completed_until() {
+        let job = ScheduledJob::order(
+            "dca",
+            Schedule::Daily(at(14, 0)),
+            OrderRequest::market("XAU_USD", dec!(1)),
+        );
+        let scheduler = Scheduler::new(paper());
+        scheduler.add(job.clone().starting(utc(7, 0, 0)));
+        scheduler.run_due(utc(7, 14, 5)).await;
+        let saved = scheduler.completed_until("dca").unwrap();
+        assert_eq!(saved, utc(7, 14, 0));
+        assert_eq!(scheduler.completed_until("other"), None);
+
+        // Restarted three days later: the missed runs happen once
+        let paper = paper();
+        let restarted = Scheduler::new(paper.clone());
+        restarted.add(job.starting(saved));
+        let events = restarted.run_due(utc(10, 15, 0)).await;
+        assert_eq!(events.len(), 1);
+        assert_eq!(paper.positions()[0].units, dec!(1));
+        assert_eq!(restarted.completed_until("dca"), Some(utc(10, 14, 0)));
+    }
+
+    #[tokio::test]
+    async fn test_closed_market_defers_or_skips() {
+        let scheduler = Scheduler::new(paper());
+        // Saturday the 12th
+        scheduler.add(
+            ScheduledJob::order(
+                "deferred",
+                Schedule::Daily(at(12, 0)),
+                OrderRequest::market("XAU_USD", dec!(1)),
+            )
+            .starting(utc(11, 13, 0)),
+        );
+        scheduler.add(
+            ScheduledJob::order(
+                "skipped",
+                Schedule::Daily(at(12, 0)),
+                OrderRequest::market("XAU_USD", dec!(1)),
+            )
+            .skip_when_closed()
+            .starting(utc(11, 13, 0)),
+        );
+
+        let events = scheduler.run_due(utc(12, 12, 30)).await;
+        assert_eq!(
+            events,
+            vec![ScheduleEvent::Skipped {
+                job: "skipped".to_string(),
+                run: utc(12, 12, 0)
+            }]
+        );
+        // The deferred run waits for Sunday's open
+        assert_eq!(scheduler.next_wake(), Some(utc(13, 12, 0)));
+        assert_eq!(scheduler.jobs().len(), 2);
+        scheduler.remove("skipped");
+        assert_eq!(scheduler.next_wake(), Some(utc(13, 21, 0)));
+
+        let events = scheduler.run_due(utc(13, 21, 0)).await;
+        assert!(
+            matches!(&events[0], ScheduleEvent::Executed { run, .. } if *run == utc(13, 12, 0))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rebalance_trades_to_targets() {
+        let paper = paper();
+        paper
+            .place_order(&OrderRequest::market("EUR_USD", dec!(3000)))
+            .await
+            .unwrap();
+        let scheduler = Scheduler::new(paper.clone());
+        scheduler.add(
+            ScheduledJob::rebalance(
+                "rebalance",
+                Schedule::Weekly(Weekday::Mon, at(8, 0)),
+                [
+                    ("EUR_USD".to_string(), dec!(1000)),
+                    ("XAU_USD".to_string(), dec!(2)),
+                ],
+            )
+            .starting(utc(1, 0, 0)),
+        );
+
+        let events = scheduler.run_due(utc(7, 9, 0)).await;
+        assert!(
+            matches!(&events[0], ScheduleEvent::Executed { responses, .. } if responses.len() == 2)
+        );
+        let units: Vec<Decimal> = paper.positions().iter().map(|p| p.units).collect();
+        assert_eq!(units.len(), 2);
+        assert!(units.contains(&dec!(1000)) && units.contains(&dec!(2)));
+    }
+}
@@ -0,0 +1,161 @@
+//! Graceful shutdown of background tasks
+//!
+//! Everything in the crate that spawns a background task (the config
+//! watcher, health prober and spread poller) can hand it over as a
+//! `ShutdownHandle`. Handles merge into one, so a service can stop all of
+//! its tasks together and wait until each has finished its current request
+//! and exited.
+//!
+//! ```no_run
+//! use oanda_connector::{ConfigWatcher, HealthProber, OandaClient, OandaConfig, ShutdownHandle};
+//! use std::time::Duration;
+//!
+//! # async fn run() -> oanda_connector::Result<()> {
+//! let client = OandaClient::new(OandaConfig::from_file("oanda.toml")?)?;
+//! let watcher = ConfigWatcher::spawn(client.clone(), "oanda.toml", Duration::from_secs(5));
+//! let prober = HealthProber::spawn(client.clone(), Duration::from_secs(30));
+//!
+//! let tasks = ShutdownHandle::from(watcher).merge(prober.into());
+//! tokio::signal::ctrl_c().await.ok();
+//! tasks.shutdown().await;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::future::Future;
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+
+/// Events buffered for slow subscribers of a background task's broadcast
+/// channel before they start lagging
+pub(crate) const EVENT_CAPACITY: usize = 1024;
+
+/// Background tasks that can be stopped together
+///
+/// Dropping the handle aborts any tasks still running; `shutdown` lets them
+/// finish cleanly instead.
+#[derive(Default)]
+pub struct ShutdownHandle {
+    tasks: Vec<Task>,
+}
+
+struct Task {
+    stop: watch::Sender<bool>,
+    handle: JoinHandle<()>,
+}
+
+impl ShutdownHandle {
+    /// Spawn a task that should return once its signal fires
+    ///
+    /// Must be called inside a Tokio runtime.
+    pub fn spawn<F, Fut>(task: F) -> Self
+    where
+        F: FnOnce(ShutdownSignal) -> Fut,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let (stop, signal) = watch::channel(false);
+        let handle = tokio::spawn(task(ShutdownSignal(signal)));
+        Self {
+            tasks: vec![Task { stop, handle }],
+        }
+    }
+
+    /// Combine with another handle so both are stopped together
+    pub fn merge(mut self, mut other: ShutdownHandle) -> Self {
+        self.tasks.append(&mut other.tasks);
+        self
+    }
+
+    /// Whether every task has exited
+    pub fn is_finished(&self) -> bool {
+        self.tasks.iter().all(|task| task.handle.is_finished())
+    }
+
+    /// Signal every task to stop and wait for all of them to exit
+    pub async fn shutdown(mut self) {
+        let tasks = std::mem::take(&mut self.tasks);
+        for task in &tasks {
+            task.stop.send_replace(true);
+        }
+        for task in tasks {
+            let _ = task.handle.await;
+        }
+    }
+
+    /// Like `shutdown`, but abort tasks still running after `timeout`
+    ///
+    /// Returns whether every task stopped on its own.
+    pub async fn shutdown_timeout(mut self, timeout: Duration) -> bool {
+        let aborts: Vec<_> = self
+            .tasks
+            .iter()
+            .map(|task| task.handle.abort_handle())
+            .collect();
+        let clean = tokio::time::timeout(timeout, std::mem::take(&mut self).shutdown())
+            .await
+            .is_ok();
+        if !clean {
+            aborts.iter().for_each(|abort| abort.abort());
+        }
+        clean
+    }
+}
+
+impl Drop for ShutdownHandle {
+    fn drop(&mut self) {
+        for task in &self.tasks {
+            task.handle.abort();
+        }
+    }
+}
+
+/// Tells a background task when to stop
+pub struct ShutdownSignal(watch::Receiver<bool>);
+
+impl ShutdownSignal {
+    /// Wait until shutdown is requested
+    pub async fn requested(&mut self) {
+        // The sender only goes away with the handle, which aborts the task
+        let _ = self.0.wait_for(|stop| *stop).await;
+    }
+
+    /// Whether shutdown has been requested
+    pub fn is_requested(&self) -> bool {
+        *self.0.borrow()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_shutdown_waits_for_tasks() {
+        let cleaned_up = Arc::new(AtomicBool::new(false));
+        let flag = cleaned_up.clone();
+
+        let first = ShutdownHandle::spawn(|mut signal| async move {
+            signal.requested().await;
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            flag.store(true, Ordering::SeqCst);
+        });
+        let second = ShutdownHandle::spawn(|mut signal| async move {
+            signal.requested().await;
+        });
+        let tasks = first.merge(second);
+        assert!(!tasks.is_finished());
+
+        tasks.shutdown().await;
+        assert!(cleaned_up.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_timeout_aborts_stuck_tasks() {
+        let tasks = ShutdownHandle::spawn(|_signal| std::future::pending());
+
+        assert!(!tasks.shutdown_timeout(Duration::from_millis(20)).await);
+    }
+}
@@ -0,0 +1,283 @@
+//! Rolling spread monitoring
+//!
+//! Keeps a window of recent spreads per instrument so callers can compare
+//! the current spread against its recent distribution, e.g. to pause
+//! execution while spreads blow out around news.
+
+use crate::client::OandaClient;
+use crate::error::Result;
+use crate::models::Tick;
+use crate::shutdown::ShutdownHandle;
+use crate::stats::percentile_of;
+use futures::{Stream, StreamExt};
+use rust_decimal::Decimal;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+/// Default number of samples kept per instrument
+pub const DEFAULT_WINDOW: usize = 1000;
+
+/// Summary of the recent spread distribution for one instrument
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpreadStats {
+    pub current: Decimal,
+    pub min: Decimal,
+    pub max: Decimal,
+    pub mean: Decimal,
+    pub p50: Decimal,
+    pub p90: Decimal,
+    pub p99: Decimal,
+    pub samples: usize,
+}
+
+#[derive(Debug, Default)]
+struct SpreadHistory {
+    samples: VecDeque<Decimal>,
+}
+
+impl SpreadHistory {
+    fn push(&mut self, spread: Decimal, window: usize) {
+        if self.samples.len() == window {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(spread);
+    }
+
+    fn sorted(&self) -> Vec<Decimal> {
+        let mut sorted: Vec<Decimal> = self.samples.iter().copied().collect();
+        sorted.sort();
+        sorted
+    }
+}
+
+/// Samples spreads and tracks rolling percentiles per instrument
+///
+/// Cloning is cheap and clones share the same history.
+#[derive(Debug, Clone)]
+pub struct SpreadMonitor {
+    window: usize,
+    history: Arc<RwLock<HashMap<String, SpreadHistory>>>,
+}
+
+impl Default for SpreadMonitor {
+    fn default() -> Self {
+        Self::new(DEFAULT_WINDOW)
+    }
+}
+
+impl SpreadMonitor {
+    /// Create a monitor keeping the last `window` spreads per instrument
+    pub fn new(window: usize) -> Self {
+        Self {
+            window: window.max(1),
+            history: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    /// Number of samples kept per instrument
+    pub fn window(&self) -> usize {
+        self.window
+    }
+
+    /// Record the spread of a tick
+    ///
+    /// Non-tradeable prices are skipped, since their spreads are not
+    /// representative of what an order would pay.
+    pub fn record(&self, tick: &Tick) {
+        if !tick.tradeable {
+            return;
+        }
+
+        let mut history = self.history.write().unwrap();
+        history
+            .entry(tick.instrument.clone())
+            .or_default()
+            .push(tick.spread(), self.window);
+    }
+
+    /// Most recently recorded spread
+    pub fn current(&self, instrument: &str) -> Option<Decimal> {
+        let history = self.history.read().unwrap();
+        history.get(instrument)?.samples.back().copied()
+    }
+
+    /// Spread at the given percentile (0-100) of the rolling window
+    pub fn percentile(&self, instrument: &str, percentile: f64) -> Option<Decimal> {
+        let history = self.history.read().unwrap();
+        let sorted = history.get(instrument)?.sorted();
+        if sorted.is_empty() {
+            return None;
+        }
+        Some(percentile_of(&sorted, percentile))
+    }
+
+    /// Summary statistics of the rolling window
+    pub fn stats(&self, instrument: &str) -> Option<SpreadStats> {
+        let history = self.history.read().unwrap();
+        let entry = history.get(instrument)?;
+        let current = *entry.samples.back()?;
+        let sorted = entry.sorted();
+
+        Some(SpreadStats {
+            current,
+            min: sorted[0],
+            max: sorted[sorted.len() - 1],
+            mean: sorted.iter().sum::<Decimal>() / Decimal::from(sorted.len()),
+            p50: percentile_of(&sorted, 50.0),
+            p90: percentile_of(&sorted, 90.0),
+            p99: percentile_of(&sorted, 99.0),
+            samples: sorted.len(),
+        })
+    }
+
+    /// Check whether the current spread exceeds the given percentile
+    ///
+    /// Returns false until at least one sample has been recorded.
+    pub fn is_wide(&self, instrument: &str, percentile: f64) -> bool {
+        match (
+            self.current(instrument),
+            self.percentile(instrument, percentile),
+        ) {
+            (Some(current), Some(threshold)) => current > threshold,
+            _ => false,
+        }
+    }
+
+    /// Instruments with at least one recorded sample
+    pub fn instruments(&self) -> Vec<String> {
+        let history = self.history.read().unwrap();
+        let mut instruments: Vec<String> = history.keys().cloned().collect();
+        instruments.sort();
+        instruments
+    }
+
+    /// Drop the history of one instrument
+    pub fn reset(&self, instrument: &str) {
+        self.history.write().unwrap().remove(instrument);
+    }
+
+    /// Fetch current prices once and record their spreads
+    pub async fn sample(&self, client: &OandaClient, instruments: &[String]) -> Result<()> {
+        let ticks = client.get_current_prices(instruments).await?;
+        for tick in &ticks {
+            self.record(tick);
+        }
+        Ok(())
+    }
+
+    /// Record every tick from a price stream until it ends
+    ///
+    /// Errors in the stream are returned immediately.
+    pub async fn consume<S>(&self, ticks: S) -> Result<()>
+    where
+        S: Stream<Item = Result<Tick>>,
+    {
+        futures::pin_mut!(ticks);
+        while let Some(tick) = ticks.next().await {
+            self.record(&tick?);
+        }
+        Ok(())
+    }
+
+    /// Poll prices in the background at a fixed interval
+    ///
+    /// Failed polls are skipped. The task runs until shut down through the
+    /// returned handle, or until the handle is dropped.
+    pub fn spawn_polling(
+        &self,
+        client: OandaClient,
+        instruments: Vec<String>,
+        interval: Duration,
+    ) -> ShutdownHandle {
+        let monitor = self.clone();
+        ShutdownHandle::spawn(|mut signal| async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                tokio::select! {
+                    _ = ticker.tick() => {}
+                    _ = signal.requested() => break,
+                }
+                let _ = monitor.sample(&client, &instruments).await;
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::PriceStatus;
+    use chrono::Utc;
+    use rust_decimal_macros::dec;
+
+    fn tick(instrument: &str, spread: Decimal) -> Tick {
+        Tick {
+            instrument: instrument.to_string(),
+            timestamp: Utc::now(),
+            bid: Decimal::ONE,
+            ask: Decimal::ONE + spread,
+            tradeable: true,
+            status: PriceStatus::Tradeable,
+            closeout_bid: None,
+            closeout_ask: None,
+        }
+    }
+
+    #[test]
+    fn test_percentiles() {
+        let monitor = SpreadMonitor::new(100);
+        for i in 1..=100 {
+            monitor.record(&tick("EUR_USD", Decimal::from(i)));
+        }
+
+        assert_eq!(monitor.percentile("EUR_USD", 50.0), Some(dec!(50)));
+        assert_eq!(monitor.percentile("EUR_USD", 90.0), Some(dec!(90)));
+        assert_eq!(monitor.percentile("EUR_USD", 0.0), Some(dec!(1)));
+        assert_eq!(monitor.percentile("EUR_USD", 100.0), Some(dec!(100)));
+        assert_eq!(monitor.percentile("GBP_USD", 50.0), None);
+
+        let stats = monitor.stats("EUR_USD").unwrap();
+        assert_eq!(stats.samples, 100);
+        assert_eq!(stats.current, dec!(100));
+        assert_eq!(stats.min, dec!(1));
+        assert_eq!(stats.mean, dec!(50.5));
+    }
+
+    #[test]
+    fn test_rolling_window() {
+        let monitor = SpreadMonitor::new(3);
+        for spread in [dec!(5), dec!(1), dec!(2), dec!(3)] {
+            monitor.record(&tick("EUR_USD", spread));
+        }
+
+        let stats = monitor.stats("EUR_USD").unwrap();
+        assert_eq!(stats.samples, 3);
+        assert_eq!(stats.max, dec!(3));
+    }
+
+    #[test]
+    fn test_is_wide() {
+        let monitor = SpreadMonitor::new(10);
+        assert!(!monitor.is_wide("EUR_USD", 90.0));
+
+        for _ in 0..9 {
+            monitor.record(&tick("EUR_USD", dec!(0.0001)));
+        }
+        assert!(!monitor.is_wide("EUR_USD", 90.0));
+
+        monitor.record(&tick("EUR_USD", dec!(0.0010)));
+        assert!(monitor.is_wide("EUR_USD", 50.0));
+    }
+
+    #[test]
+    fn test_skips_non_tradeable() {
+        let monitor = SpreadMonitor::default();
+        let mut closed = tick("EUR_USD", dec!(0.01));
+        closed.tradeable = false;
+        monitor.record(&closed);
+
+        assert!(monitor.current("EUR_USD").is_none());
+        assert!(monitor.instruments().is_empty());
+    }
+}
@@ -0,0 +1,140 @@
+//! Rolling request latency statistics
+//!
+//! The client records the latency and outcome of every HTTP attempt per
+//! endpoint group, keeping the most recent `DEFAULT_WINDOW` samples, so
+//! operators can spot an API slowdown from `client.stats()` before it
+//! shows up as missed fills.
+//!
+//! ```no_run
+//! # fn report(client: &oanda_connector::OandaClient) {
+//! for (group, stats) in client.stats() {
+//!     println!(
+//!         "{:?}: p50 {:?} p99 {:?} errors {:.1}%",
+//!         group,
+//!         stats.p50,
+//!         stats.p99,
+//!         stats.error_rate * 100.0
+//!     );
+//! }
+//! # }
+//! ```
+
+use crate::endpoints::EndpointGroup;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Default number of attempts kept per endpoint group
+pub const DEFAULT_WINDOW: usize = 1000;
+
+/// Latency distribution and error rate over recent attempts
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EndpointStats {
+    pub samples: usize,
+    pub p50: Duration,
+    pub p95: Duration,
+    pub p99: Duration,
+    pub max: Duration,
+    /// Share of attempts (0-1) that failed, were throttled or got a 5xx
+    pub error_rate: f64,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Sample {
+    latency: Duration,
+    failed: bool,
+}
+
+/// Nearest-rank percentile of an already sorted, non-empty slice
+///
+/// `percentile` is clamped to 0..=100.
+pub(crate) fn percentile_of<T: Copy>(sorted: &[T], percentile: f64) -> T {
+    let p = percentile.clamp(0.0, 100.0);
+    let rank = ((p / 100.0) * sorted.len() as f64).ceil() as usize;
+    sorted[rank.saturating_sub(1).min(sorted.len() - 1)]
+}
+
+/// Rolling window of attempts per endpoint group, shared by client clones
+#[derive(Debug, Clone, Default)]
+pub(crate) struct LatencyTracker {
+    samples: Arc<Mutex<HashMap<EndpointGroup, VecDeque<Sample>>>>,
+}
+
+impl LatencyTracker {
+    pub(crate) fn record(&self, group: EndpointGroup, latency: Duration, failed: bool) {
+        let mut samples = self.samples.lock().unwrap();
+        let window = samples.entry(group).or_default();
+        if window.len() == DEFAULT_WINDOW {
+            window.pop_front();
+        }
+        window.push_back(Sample { latency, failed });
+    }
+
+    pub(crate) fn snapshot(&self) -> HashMap<EndpointGroup, EndpointStats> {
+        let samples = self.samples.lock().unwrap();
+        samples
+            .iter()
+            .filter(|(_, window)| !window.is_empty())
+            .map(|(group, window)| {
+                let mut sorted: Vec<Duration> = window.iter().map(|s| s.latency).collect();
+                sorted.sort();
+                let failures = window.iter().filter(|s| s.failed).count();
+                let stats = EndpointStats {
+                    samples: sorted.len(),
+                    p50: percentile_of(&sorted, 50.0),
+                    p95: percentile_of(&sorted, 95.0),
+                    p99: percentile_of(&sorted, 99.0),
+                    max: sorted[sorted.len() - 1],
+                    error_rate: failures as f64 / sorted.len() as f64,
+                };
+                (*group, stats)
+            })
+            .collect()
+    }
+
+    pub(crate) fn reset(&self) {
+        self.samples.lock().unwrap().clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percentiles_and_error_rate() {
+        let tracker = LatencyTracker::default();
+        for ms in 1..=100 {
+            tracker.record(EndpointGroup::Pricing, Duration::from_millis(ms), ms > 90);
+        }
+        tracker.record(EndpointGroup::Orders, Duration::from_millis(40), false);
+
+        let stats = tracker.snapshot();
+        let pricing = stats[&EndpointGroup::Pricing];
+        assert_eq!(pricing.samples, 100);
+        assert_eq!(pricing.p50, Duration::from_millis(50));
+        assert_eq!(pricing.p95, Duration::from_millis(95));
+        assert_eq!(pricing.p99, Duration::from_millis(99));
+        assert_eq!(pricing.max, Duration::from_millis(100));
+        assert!((pricing.error_rate - 0.1).abs() < 1e-9);
+        assert_eq!(stats[&EndpointGroup::Orders].p99, Duration::from_millis(40));
+        assert!(!stats.contains_key(&EndpointGroup::Candles));
+
+        tracker.reset();
+        assert!(tracker.snapshot().is_empty());
+    }
+
+    #[test]
+    fn test_window_drops_oldest() {
+        let tracker = LatencyTracker::default();
+        tracker.record(EndpointGroup::Account, Duration::from_secs(10), true);
+        for _ in 0..DEFAULT_WINDOW {
+            tracker.record(EndpointGroup::Account, Duration::from_millis(5), false);
+        }
+
+        let account = tracker.snapshot()[&EndpointGroup::Account];
+        assert_eq!(account.samples, DEFAULT_WINDOW);
+        assert_eq!(account.max, Duration::from_millis(5));
+        assert_eq!(account.error_rate, 0.0);
+    }
+}
@@ -0,0 +1,542 @@
+//! Strategies driven by live data
+//!
+//! A [`Strategy`] is plain logic: it reacts to ticks, completed candles,
+//! account transactions and a timer, and asks for orders through its
+//! [`StrategyContext`]. [`StrategyRunner`] does the plumbing. It follows
+//! the pricing stream, builds candles with a
+//! [`CandleAggregator`], polls the transaction history, and sends orders
+//! through an [`OrderManager`], which also tracks their fills.
+//!
+//! ```no_run
+//! use oanda_connector::strategy::{Strategy, StrategyContext, StrategyRunner};
+//! use oanda_connector::{Candle, Granularity, OandaClient, OandaConfig, OrderRequest};
+//! use rust_decimal_macros::dec;
+//!
+//! struct Breakout {
+//!     last_high: Option<rust_decimal::Decimal>,
+//! }
+//!
+//! impl Strategy for Breakout {
+//!     fn on_candle(&mut self, candle: &Candle, ctx: &mut StrategyContext<'_>) {
+//!         if self.last_high.is_some_and(|high| candle.close > high) {
+//!             ctx.submit(OrderRequest::market(&candle.instrument, dec!(1000)));
+//!         }
+//!         self.last_high = Some(candle.high);
+//!     }
+//! }
+//!
+//! # async fn run() -> oanda_connector::Result<()> {
+//! let client = OandaClient::new(OandaConfig::from_env()?)?;
+//! let runner = StrategyRunner::new(client, Breakout { last_high: None })
+//!     .instruments(vec!["EUR_USD".to_string()])
+//!     .granularity(Granularity::M5);
+//! let task = runner.spawn();
+//! tokio::signal::ctrl_c().await.ok();
+//! task.shutdown().await;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::aggregator::CandleAggregator;
+use crate::client::OandaClient;
+use crate::error::Result;
+use crate::ids::TransactionId;
+use crate::models::{Candle, Granularity, Tick};
+use crate::order_manager::{ManagedOrder, OrderManager};
+use crate::orders::OrderRequest;
+use crate::shutdown::{ShutdownHandle, ShutdownSignal};
+use crate::transactions::Transaction;
+use chrono::{DateTime, Utc};
+use futures::{Stream, StreamExt};
+use std::pin::Pin;
+use std::time::Duration;
+
+/// Trading logic run by a [`StrategyRunner`]
+///
+/// Every callback has a default that does nothing, so a strategy only
+/// implements what it uses. Callbacks run one at a time on the runner's
+/// task and should return quickly; the orders they ask for are sent once
+/// they return.
+pub trait Strategy: Send {
+    /// A new price for one of the runner's instruments
+    fn on_tick(&mut self, _tick: &Tick, _ctx: &mut StrategyContext<'_>) {}
+
+    /// A completed candle at the runner's granularity
+    fn on_candle(&mut self, _candle: &Candle, _ctx: &mut StrategyContext<'_>) {}
+
+    /// A new account transaction, e.g. a fill or a stop loss
+    fn on_transaction(&mut self, _transaction: &Transaction, _ctx: &mut StrategyContext<'_>) {}
+
+    /// The runner's timer fired
+    fn on_timer(&mut self, _ctx: &mut StrategyContext<'_>) {}
+}
+
+/// Order request made by a strategy
+#[derive(Debug, Clone, PartialEq)]
+enum Action {
+    Submit(OrderRequest),
+    Amend(String, OrderRequest),
+    Cancel(String),
+}
+
+/// What a strategy can see and do from a callback
+pub struct StrategyContext<'a> {
+    now: DateTime<Utc>,
+    orders: &'a OrderManager,
+    actions: Vec<Action>,
+}
+
+impl<'a> StrategyContext<'a> {
+    fn new(now: DateTime<Utc>, orders: &'a OrderManager) -> Self {
+        Self {
+            now,
+            orders,
+            actions: Vec::new(),
+        }
+    }
+
+    /// Time of the event being handled
+    pub fn now(&self) -> DateTime<Utc> {
+        self.now
+    }
+
+    /// Submit an order through the order manager
+    pub fn submit(&mut self, order: OrderRequest) {
+        self.actions.push(Action::Submit(order));
+    }
+
+    /// Replace a pending order by client ID
+    pub fn amend(&mut self, client_id: &str, replacement: OrderRequest) {
+        self.actions
+            .push(Action::Amend(client_id.to_string(), replacement));
+    }
+
+    /// Cancel a pending order by client ID
+    pub fn cancel(&mut self, client_id: &str) {
+        self.actions.push(Action::Cancel(client_id.to_string()));
+    }
+
+    /// Order tracked by the order manager
+    pub fn order(&self, client_id: &str) -> Option<ManagedOrder> {
+        self.orders.order(client_id)
+    }
+
+    /// Orders that can still change
+    pub fn open_orders(&self) -> Vec<ManagedOrder> {
+        self.orders.open_orders()
+    }
+}
+
+/// Runs a strategy against live data
+pub struct StrategyRunner<S> {
+    client: OandaClient,
+    strategy: S,
+    orders: OrderManager,
+    instruments: Vec<String>,
+    aggregator: Option<CandleAggregator>,
+    timer: Option<Duration>,
+    transaction_interval: Duration,
+    reconnect_delay: Duration,
+    cursor: Option<TransactionId>,
+}
+
+impl<S: Strategy + 'static> StrategyRunner<S> {
+    /// Run `strategy` with orders sent through a new [`OrderManager`]
+    ///
+    /// Polls transactions every 5 seconds and reopens the pricing stream 5
+    /// seconds after it drops.
+    pub fn new(client: OandaClient, strategy: S) -> Self {
+        Self {
+            orders: OrderManager::new(client.clone()),
+            client,
+            strategy,
+            instruments: Vec::new(),
+            aggregator: None,
+            timer: None,
+            transaction_interval: Duration::from_secs(5),
+            reconnect_delay: Duration::from_secs(5),
+            cursor: None,
+        }
+    }
+
+    /// Instruments whose prices are streamed to the strategy
+    pub fn instruments(mut self, instruments: Vec<String>) -> Self {
+        self.instruments = instruments;
+        self
+    }
+
+    /// Build candles at `granularity` and call `on_candle` as each closes
+    pub fn granularity(mut self, granularity: Granularity) -> Self {
+        self.aggregator = Some(CandleAggregator::new(granularity));
+        self
+    }
+
+    /// Call `on_timer` every `interval`
+    pub fn timer(mut self, interval: Duration) -> Self {
+        self.timer = Some(interval);
+        self
+    }
+
+    /// How often new transactions are fetched
+    pub fn transaction_interval(mut self, interval: Duration) -> Self {
+        self.transaction_interval = interval;
+        self
+    }
+
+    /// Delay before reopening a dropped pricing stream
+    ///
+    /// Transactions, the timer and candle closes are still handled while
+    /// the stream is down.
+    pub fn reconnect_delay(mut self, delay: Duration) -> Self {
+        self.reconnect_delay = delay;
+        self
+    }
+
+    /// Send orders through an existing manager, e.g. one shared with
+    /// other components
+    pub fn order_manager(mut self, orders: OrderManager) -> Self {
+        self.orders = orders;
+        self
+    }
+
+    /// Manager the strategy's orders go through
+    pub fn orders(&self) -> &OrderManager {
+        &self.orders
+    }
+
+    /// Run the strategy in the background
+    ///
+    /// The task runs until shut down through the returned handle, or until
+    /// the handle is dropped. Must be called inside a Tokio runtime.
+    pub fn spawn(self) -> ShutdownHandle {
+        ShutdownHandle::spawn(|signal| self.run(signal))
+    }
+
+    async fn run(mut self, mut signal: ShutdownSignal) {
+        let mut timer = tokio::time::interval(self.timer.unwrap_or(Duration::from_secs(3600)));
+        let mut transactions = tokio::time::interval(self.transaction_interval);
+        // Catch up on candles even when the market is quiet
+        let mut flush = tokio::time::interval(Duration::from_secs(1));
+        for interval in [&mut timer, &mut transactions, &mut flush] {
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        }
+        // The first tick of an interval fires immediately
+        timer.tick().await;
+
+        // Transactions and the timer keep going while the stream is down
+        let mut ticks = None;
+        let reconnect = tokio::time::sleep(Duration::ZERO);
+        futures::pin_mut!(reconnect);
+
+        loop {
+            tokio::select! {
+                _ = &mut reconnect, if ticks.is_none() => {
+                    match self.client.stream_prices(&self.instruments).await {
+                        Ok(stream) => ticks = Some(Box::pin(stream)),
+                        Err(e) => {
+                            log::warn!("strategy price stream failed: {}", e);
+                            reconnect.as_mut().reset(tokio::time::Instant::now() + self.reconnect_delay);
+                        }
+                    }
+                }
+                tick = next_tick(&mut ticks) => match tick {
+                    Some(Ok(tick)) => self.handle_tick(&tick).await,
+                    end => {
+                        if let Some(Err(e)) = end {
+                            log::warn!("strategy price stream failed: {}", e);
+                        }
+                        ticks = None;
+                        reconnect.as_mut().reset(tokio::time::Instant::now() + self.reconnect_delay);
+                    }
+                },
+                _ = timer.tick(), if self.timer.is_some() => {
+                    self.dispatch(Utc::now(), |strategy, ctx| strategy.on_timer(ctx)).await;
+                }
+                _ = transactions.tick() => {
+                    if let Err(e) = self.poll_transactions().await {
+                        log::warn!("strategy transaction poll failed: {}", e);
+                    }
+                }
+                _ = flush.tick() => self.flush_candles(Utc::now()).await,
+                _ = signal.requested() => return,
+            }
+        }
+    }
+
+    async fn handle_tick(&mut self, tick: &Tick) {
+        let closed = self
+            .aggregator
+            .as_mut()
+            .and_then(|aggregator| aggregator.on_tick(tick));
+        if let Some(candle) = closed {
+            self.dispatch(tick.timestamp, |strategy, ctx| {
+                strategy.on_candle(&candle, ctx)
+            })
+            .await;
+        }
+        self.dispatch(tick.timestamp, |strategy, ctx| strategy.on_tick(tick, ctx))
+            .await;
+    }
+
+    async fn flush_candles(&mut self, now: DateTime<Utc>) {
+        let closed = match self.aggregator.as_mut() {
+            Some(aggregator) => aggregator.flush(now),
+            None => return,
+        };
+        for candle in closed {
+            self.dispatch(now, |strategy, ctx| strategy.on_candle(&candle, ctx))
+                .await;
+        }
+    }
+
+    /// Pass transactions since the last poll to the order manager and the
+    /// strategy
+    ///
+    /// The first poll only finds the starting point.
+    async fn poll_transactions(&mut self) -> Result<()> {
+        let Some(cursor) = self.cursor.clone() else {
+            if self.client.last_transaction_id().is_none() {
+                self.client.get_account_summary().await?;
+            }
+            self.cursor = self.client.last_transaction_id();
+            return Ok(());
+        };
+        for transaction in self.client.get_transactions_since(&cursor).await? {
+            if let Some(id) = transaction.transaction_id() {
+                if self.cursor.as_ref().is_none_or(|cursor| *cursor < id) {
+                    self.cursor = Some(id);
+                }
+            }
+            self.orders.apply_transaction(&transaction);
+            let now = Utc::now();
+            self.dispatch(now, |strategy, ctx| {
+                strategy.on_transaction(&transaction, ctx)
+            })
+            .await;
+        }
+        Ok(())
+    }
+
+    /// Call the strategy, then carry out the orders it asked for
+    async fn dispatch<F>(&mut self, now: DateTime<Utc>, callback: F)
+    where
+        F: FnOnce(&mut S, &mut StrategyContext<'_>),
+    {
+        let mut ctx = StrategyContext::new(now, &self.orders);
+        callback(&mut self.strategy, &mut ctx);
+        for action in ctx.actions {
+            let result = match action {
+                Action::Submit(order) => self.orders.submit(order).await.map(|_| ()),
+                Action::Amend(client_id, order) => self.orders.amend(&client_id, order).await,
+                Action::Cancel(client_id) => self.orders.cancel(&client_id).await,
+            };
+            if let Err(e) = result {
+                log::warn!("strategy order failed: {}", e);
+            }
+        }
+    }
+}
+
+/// Next tick from the pricing stream, or never while it is down
+async fn next_tick<T>(ticks: &mut Option<Pin<Box<T>>>) -> Option<Result<Tick>>
+where
+    T: Stream<Item = Result<Tick>>,
+{
+    match ticks {
+        Some(ticks) => ticks.next().await,
+        None => std::future::pending().await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mockito::{Matcher, Server};
+    use rust_decimal_macros::dec;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Default)]
+    struct Log {
+        events: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl Strategy for Log {
+        fn on_tick(&mut self, tick: &Tick, _ctx: &mut StrategyContext<'_>) {
+            self.events
+                .lock()
+                .unwrap()
+                .push(format!("tick {}", tick.bid));
+        }
+
+        fn on_transaction(&mut self, transaction: &Transaction, _ctx: &mut StrategyContext<'_>) {
+            self.events
+                .lock()
+                .unwrap()
+                .push(transaction.type_name().to_string());
+        }
+
+        fn on_timer(&mut self, _ctx: &mut StrategyContext<'_>) {
+            self.events.lock().unwrap().push("timer".to_string());
+        }
+    }
+
+    fn client(server: &Server) -> OandaClient {
+        let mut config = crate::OandaConfig::new(
+            "test_api_key".to_string(),
+            "test_account_id".to_string(),
+            true,
+        );
+        config.base_url = Some(server.url());
+        config.stream_url = Some(server.url());
+        config.enable_retries = false;
+        OandaClient::new(config).unwrap()
+    }
+
+    async fn mock_transactions(server: &mut Server) -> Vec<mockito::Mock> {
+        let summary = server
+            .mock("GET", "/v3/accounts/test_account_id")
+            .with_status(200)
+            .with_body(
+                r#"{
+                "account": {
+                    "id": "test_account_id", "balance": "100000.0000", "NAV": "100000.0000",
+                    "unrealizedPL": "0.0000", "pl": "0.0000", "marginUsed": "0.0000",
+                    "marginAvailable": "100000.0000", "openTradeCount": 0,
+                    "openPositionCount": 0, "currency": "USD"
+                },
+                "lastTransactionID": "100"
+            }"#,
+            )
+            .create_async()
+            .await;
+        let since = server
+            .mock("GET", "/v3/accounts/test_account_id/transactions/sinceid")
+            .match_query(Matcher::Any)
+            .with_status(200)
+            .with_body(
+                r#"{
+                "transactions": [{
+                    "id": "101", "accountID": "test_account_id", "batchID": "101",
+                    "time": "2025-11-03T21:00:00.000000000Z", "type": "DAILY_FINANCING",
+                    "financing": "-0.1234"
+                }],
+                "lastTransactionID": "101"
+            }"#,
+            )
+            .create_async()
+            .await;
+        vec![summary, since]
+    }
+
+    async fn wait_for(events: &Arc<Mutex<Vec<String>>>, event: &str) {
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(5);
+        while !events.lock().unwrap().iter().any(|e| e == event) {
+            assert!(
+                tokio::time::Instant::now() < deadline,
+                "no {} in {:?}",
+                event,
+                events.lock().unwrap()
+            );
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_transactions_and_timer_run_while_stream_is_down() {
+        let mut server = Server::new_async().await;
+        let stream = server
+            .mock("GET", "/v3/accounts/test_account_id/pricing/stream")
+            .match_query(Matcher::Any)
+            .with_status(503)
+            .expect(1)
+            .create_async()
+            .await;
+        let _transactions = mock_transactions(&mut server).await;
+
+        let log = Log::default();
+        let events = log.events.clone();
+        let task = StrategyRunner::new(client(&server), log)
+            .instruments(vec!["EUR_USD".to_string()])
+            .timer(Duration::from_millis(20))
+            .transaction_interval(Duration::from_millis(20))
+            .reconnect_delay(Duration::from_secs(60))
+            .spawn();
+
+        wait_for(&events, "DAILY_FINANCING").await;
+        wait_for(&events, "timer").await;
+        task.shutdown().await;
+
+        // Still waiting to reconnect
+        stream.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_stream_reopens_after_it_ends() {
+        let mut server = Server::new_async().await;
+        let stream = server
+            .mock("GET", "/v3/accounts/test_account_id/pricing/stream")
+            .match_query(Matcher::Any)
+            .with_status(200)
+            .with_body(concat!(
+                r#"{"type":"PRICE","instrument":"EUR_USD","time":"2025-11-03T12:00:00.000000000Z","bids":[{"price":"1.10000"}],"asks":[{"price":"1.10020"}]}"#,
+                "\n",
+            ))
+            .expect_at_least(2)
+            .create_async()
+            .await;
+        let _transactions = mock_transactions(&mut server).await;
+
+        let log = Log::default();
+        let events = log.events.clone();
+        let task = StrategyRunner::new(client(&server), log)
+            .instruments(vec!["EUR_USD".to_string()])
+            .reconnect_delay(Duration::from_millis(20))
+            .spawn();
+
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(5);
+        while events
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|e| *e == "tick 1.10000")
+            .count()
+            < 2
+        {
+            assert!(tokio::time::Instant::now() < deadline);
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        task.shutdown().await;
+
+        stream.assert_async().await;
+    }
+
+    #[test]
+    fn test_context_collects_actions_in_order() {
+        let orders = OrderManager::new(
+            OandaClient::new(crate::OandaConfig::new(
+                "test_api_key".to_string(),
+                "test_account_id".to_string(),
+                true,
+            ))
+            .unwrap(),
+        );
+        let now = Utc::now();
+        let mut ctx = StrategyContext::new(now, &orders);
+
+        let order = OrderRequest::market("EUR_USD", dec!(1000));
+        ctx.submit(order.clone());
+        ctx.amend("entry", order.clone());
+        ctx.cancel("entry");
+
+        assert_eq!(ctx.now(), now);
+        assert!(ctx.open_orders().is_empty());
+        assert_eq!(
+            ctx.actions,
+            vec![
+                Action::Submit(order.clone()),
+                Action::Amend("entry".to_string(), order),
+                Action::Cancel("entry".to_string()),
+            ]
+        );
+    }
+}
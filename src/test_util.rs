@@ -0,0 +1,333 @@
+//! Fixture builders for tests
+//!
+//! Enabled by the `test-util` feature. Every field starts from a fixed,
+//! plausible default (EUR_USD around 1.1000 at 2024-01-01 00:00 UTC) so
+//! tests only spell out what they actually care about.
+//!
+//! ```
+//! use oanda_connector::Candle;
+//! use rust_decimal_macros::dec;
+//!
+//! let candle = Candle::builder()
+//!     .instrument("USD_JPY")
+//!     .ohlc(dec!(150.00), dec!(150.50), dec!(149.80), dec!(150.20))
+//!     .build();
+//! assert!(candle.is_bullish());
+//! ```
+
+use crate::models::{AccountSummary, Candle, PriceStatus, Tick};
+use chrono::{DateTime, TimeZone, Utc};
+use rust_decimal::Decimal;
+
+fn default_time() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap()
+}
+
+impl Candle {
+    /// Start building a candle fixture
+    pub fn builder() -> CandleBuilder {
+        CandleBuilder::default()
+    }
+}
+
+impl Tick {
+    /// Start building a tick fixture
+    pub fn builder() -> TickBuilder {
+        TickBuilder::default()
+    }
+}
+
+impl AccountSummary {
+    /// Start building an account summary fixture
+    pub fn builder() -> AccountSummaryBuilder {
+        AccountSummaryBuilder::default()
+    }
+}
+
+/// Builder for `Candle` fixtures
+#[derive(Debug, Clone)]
+pub struct CandleBuilder {
+    candle: Candle,
+}
+
+impl Default for CandleBuilder {
+    fn default() -> Self {
+        let price = Decimal::new(11000, 4);
+        Self {
+            candle: Candle {
+                instrument: "EUR_USD".to_string(),
+                timestamp: default_time(),
+                open: price,
+                high: price,
+                low: price,
+                close: price,
+                volume: 100,
+                complete: true,
+            },
+        }
+    }
+}
+
+impl CandleBuilder {
+    /// Set instrument
+    pub fn instrument(mut self, instrument: &str) -> Self {
+        self.candle.instrument = instrument.to_string();
+        self
+    }
+
+    /// Set candle start time
+    pub fn timestamp(mut self, timestamp: DateTime<Utc>) -> Self {
+        self.candle.timestamp = timestamp;
+        self
+    }
+
+    /// Set all four prices at once
+    pub fn ohlc(mut self, open: Decimal, high: Decimal, low: Decimal, close: Decimal) -> Self {
+        self.candle.open = open;
+        self.candle.high = high;
+        self.candle.low = low;
+        self.candle.close = close;
+        self
+    }
+
+    /// Set open price
+    pub fn open(mut self, open: Decimal) -> Self {
+        self.candle.open = open;
+        self
+    }
+
+    /// Set high price
+    pub fn high(mut self, high: Decimal) -> Self {
+        self.candle.high = high;
+        self
+    }
+
+    /// Set low price
+    pub fn low(mut self, low: Decimal) -> Self {
+        self.candle.low = low;
+        self
+    }
+
+    /// Set close price
+    pub fn close(mut self, close: Decimal) -> Self {
+        self.candle.close = close;
+        self
+    }
+
+    /// Set tick volume
+    pub fn volume(mut self, volume: i64) -> Self {
+        self.candle.volume = volume;
+        self
+    }
+
+    /// Mark candle as complete or still forming
+    pub fn complete(mut self, complete: bool) -> Self {
+        self.candle.complete = complete;
+        self
+    }
+
+    /// Build candle
+    pub fn build(self) -> Candle {
+        self.candle
+    }
+}
+
+/// Builder for `Tick` fixtures
+#[derive(Debug, Clone)]
+pub struct TickBuilder {
+    tick: Tick,
+}
+
+impl Default for TickBuilder {
+    fn default() -> Self {
+        Self {
+            tick: Tick {
+                instrument: "EUR_USD".to_string(),
+                timestamp: default_time(),
+                bid: Decimal::new(110000, 5),
+                ask: Decimal::new(110020, 5),
+                tradeable: true,
+                status: PriceStatus::Tradeable,
+                closeout_bid: None,
+                closeout_ask: None,
+            },
+        }
+    }
+}
+
+impl TickBuilder {
+    /// Set instrument
+    pub fn instrument(mut self, instrument: &str) -> Self {
+        self.tick.instrument = instrument.to_string();
+        self
+    }
+
+    /// Set price time
+    pub fn timestamp(mut self, timestamp: DateTime<Utc>) -> Self {
+        self.tick.timestamp = timestamp;
+        self
+    }
+
+    /// Set bid price
+    pub fn bid(mut self, bid: Decimal) -> Self {
+        self.tick.bid = bid;
+        self
+    }
+
+    /// Set ask price
+    pub fn ask(mut self, ask: Decimal) -> Self {
+        self.tick.ask = ask;
+        self
+    }
+
+    /// Set closeout bid and ask
+    pub fn closeout(mut self, bid: Decimal, ask: Decimal) -> Self {
+        self.tick.closeout_bid = Some(bid);
+        self.tick.closeout_ask = Some(ask);
+        self
+    }
+
+    /// Set tradeable flag and the matching status
+    pub fn tradeable(mut self, tradeable: bool) -> Self {
+        self.tick.tradeable = tradeable;
+        self.tick.status = if tradeable {
+            PriceStatus::Tradeable
+        } else {
+            PriceStatus::NonTradeable
+        };
+        self
+    }
+
+    /// Build tick
+    pub fn build(self) -> Tick {
+        self.tick
+    }
+}
+
+/// Builder for `AccountSummary` fixtures
+///
+/// NAV and available margin follow the balance unless set explicitly.
+#[derive(Debug, Clone)]
+pub struct AccountSummaryBuilder {
+    summary: AccountSummary,
+    nav: Option<Decimal>,
+    margin_available: Option<Decimal>,
+}
+
+impl Default for AccountSummaryBuilder {
+    fn default() -> Self {
+        Self {
+            summary: AccountSummary {
+                id: "101-001-1234567-001".to_string(),
+                balance: Decimal::from(100_000),
+                nav: Decimal::ZERO,
+                unrealized_pl: Decimal::ZERO,
+                realized_pl: Decimal::ZERO,
+                margin_used: Decimal::ZERO,
+                margin_available: Decimal::ZERO,
+                open_trade_count: 0,
+                open_position_count: 0,
+                currency: "USD".to_string(),
+                hedging_enabled: false,
+            },
+            nav: None,
+            margin_available: None,
+        }
+    }
+}
+
+impl AccountSummaryBuilder {
+    /// Set account ID
+    pub fn id(mut self, id: &str) -> Self {
+        self.summary.id = id.to_string();
+        self
+    }
+
+    /// Set account currency
+    pub fn currency(mut self, currency: &str) -> Self {
+        self.summary.currency = currency.to_string();
+        self
+    }
+
+    /// Set balance
+    pub fn balance(mut self, balance: Decimal) -> Self {
+        self.summary.balance = balance;
+        self
+    }
+
+    /// Set net asset value
+    pub fn nav(mut self, nav: Decimal) -> Self {
+        self.nav = Some(nav);
+        self
+    }
+
+    /// Set unrealized profit/loss
+    pub fn unrealized_pl(mut self, unrealized_pl: Decimal) -> Self {
+        self.summary.unrealized_pl = unrealized_pl;
+        self
+    }
+
+    /// Set realized profit/loss
+    pub fn realized_pl(mut self, realized_pl: Decimal) -> Self {
+        self.summary.realized_pl = realized_pl;
+        self
+    }
+
+    /// Set margin in use
+    pub fn margin_used(mut self, margin_used: Decimal) -> Self {
+        self.summary.margin_used = margin_used;
+        self
+    }
+
+    /// Set margin available
+    pub fn margin_available(mut self, margin_available: Decimal) -> Self {
+        self.margin_available = Some(margin_available);
+        self
+    }
+
+    /// Set open trade and position counts
+    pub fn open_counts(mut self, trades: i32, positions: i32) -> Self {
+        self.summary.open_trade_count = trades;
+        self.summary.open_position_count = positions;
+        self
+    }
+
+    /// Set whether the account is a hedging account
+    pub fn hedging_enabled(mut self, hedging_enabled: bool) -> Self {
+        self.summary.hedging_enabled = hedging_enabled;
+        self
+    }
+
+    /// Build account summary
+    pub fn build(self) -> AccountSummary {
+        let mut summary = self.summary;
+        summary.nav = self.nav.unwrap_or(summary.balance + summary.unrealized_pl);
+        summary.margin_available = self
+            .margin_available
+            .unwrap_or(summary.nav - summary.margin_used);
+        summary
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_builders_defaults() {
+        let tick = Tick::builder().bid(dec!(1.2)).ask(dec!(1.2002)).build();
+        assert_eq!(tick.spread(), dec!(0.0002));
+        assert!(!Tick::builder().tradeable(false).build().tradeable);
+
+        let candle = Candle::builder().high(dec!(1.11)).build();
+        assert_eq!(candle.range(), dec!(0.01));
+
+        let summary = AccountSummary::builder()
+            .unrealized_pl(dec!(-500))
+            .margin_used(dec!(2000))
+            .build();
+        assert_eq!(summary.nav, dec!(99500));
+        assert_eq!(summary.margin_available, dec!(97500));
+    }
+}
@@ -0,0 +1,77 @@
+//! Trading interface shared by the live client and simulators
+//!
+//! Strategies written against [`Trading`] run unchanged on
+//! [`OandaClient`] or on a [`PaperTrader`](crate::paper::PaperTrader), so
+//! they can be validated without sending orders to OANDA.
+//!
+//! ```no_run
+//! use oanda_connector::orders::OrderRequest;
+//! use oanda_connector::trading::Trading;
+//! use oanda_connector::Result;
+//! use rust_decimal_macros::dec;
+//!
+//! async fn buy_the_dip(broker: &impl Trading) -> Result<()> {
+//!     let tick = broker.get_current_price("EUR_USD").await?;
+//!     if tick.mid() < dec!(1.0800) {
+//!         broker.place_order(&OrderRequest::market("EUR_USD", dec!(1000))).await?;
+//!     }
+//!     Ok(())
+//! }
+//! ```
+
+use crate::client::OandaClient;
+use crate::error::{Error, Result};
+use crate::models::{AccountSummary, Position, Tick};
+use crate::orders::{Order, OrderRequest, OrderResponse, OrderSpecifier};
+use async_trait::async_trait;
+
+/// Prices, account state and order placement
+#[async_trait]
+pub trait Trading: Send + Sync {
+    /// Current price for an instrument
+    async fn get_current_price(&self, instrument: &str) -> Result<Tick>;
+
+    /// Balance, NAV, P/L and margin
+    async fn get_account_summary(&self) -> Result<AccountSummary>;
+
+    /// Positions in instruments with units held
+    async fn get_open_positions(&self) -> Result<Vec<Position>>;
+
+    /// Submit an order
+    async fn place_order(&self, order: &OrderRequest) -> Result<OrderResponse>;
+
+    /// Order placed earlier with a client order ID, in any state
+    ///
+    /// Lets callers check whether a request that failed went through after
+    /// all. Brokers that can't look orders up return `None`.
+    async fn find_order(&self, _client_id: &str) -> Result<Option<Order>> {
+        Ok(None)
+    }
+}
+
+#[async_trait]
+impl Trading for OandaClient {
+    async fn get_current_price(&self, instrument: &str) -> Result<Tick> {
+        OandaClient::get_current_price(self, instrument).await
+    }
+
+    async fn get_account_summary(&self) -> Result<AccountSummary> {
+        OandaClient::get_account_summary(self).await
+    }
+
+    async fn get_open_positions(&self) -> Result<Vec<Position>> {
+        OandaClient::get_open_positions(self).await
+    }
+
+    async fn place_order(&self, order: &OrderRequest) -> Result<OrderResponse> {
+        OandaClient::place_order(self, order).await
+    }
+
+    async fn find_order(&self, client_id: &str) -> Result<Option<Order>> {
+        match self.get_order(OrderSpecifier::client_id(client_id)).await {
+            Ok(order) => Ok(Some(order)),
+            Err(e) if matches!(e.inner(), Error::ApiError { code: 404, .. }) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+}
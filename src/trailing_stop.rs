@@ -0,0 +1,582 @@
+//! Client-side trailing stops
+//!
+//! OANDA's own trailing stop follows the price by a fixed distance.
+//! [`TrailingStopManager`] keeps virtual stops instead, which can trail by
+//! a percentage of the price or by a multiple of the instrument's ATR, and
+//! only start trailing once an activation price is reached. Stops follow
+//! the bid for long trades and the ask for short trades, only ever tighten,
+//! and close the trade at market once the price comes back to them.
+//!
+//! Nothing is sent to OANDA until a stop triggers, so the stops only
+//! protect trades while the manager is running.
+//!
+//! ```no_run
+//! use oanda_connector::trailing_stop::{TrailDistance, TrailingStopManager};
+//! use oanda_connector::{Granularity, OandaClient, OandaConfig};
+//! use rust_decimal_macros::dec;
+//! use std::time::Duration;
+//!
+//! # async fn run() -> oanda_connector::Result<()> {
+//! let client = OandaClient::new(OandaConfig::from_env()?)?;
+//! let stops = TrailingStopManager::new(client.clone());
+//!
+//! // Feed ATR from completed candles
+//! for candle in client.get_candles("EUR_USD", Granularity::H1, 50).await? {
+//!     stops.on_candle(&candle);
+//! }
+//! for trade in client.get_open_trades().await? {
+//!     stops.track_trade(&trade, TrailDistance::Atr(dec!(2)));
+//! }
+//!
+//! let mut events = stops.subscribe();
+//! let _feed = stops.spawn_streaming(stops.instruments(), Duration::from_secs(5));
+//! while let Ok(event) = events.recv().await {
+//!     println!("{:?}", event);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::client::OandaClient;
+use crate::error::Result;
+use crate::ids::TradeId;
+use crate::models::{Candle, Tick, Trade};
+use crate::orders::OrderResponse;
+use crate::shutdown::{ShutdownHandle, EVENT_CAPACITY};
+use futures::{Stream, StreamExt};
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::broadcast;
+
+/// ATR period used unless set with [`TrailingStopManager::with_atr_period`]
+pub const DEFAULT_ATR_PERIOD: usize = 14;
+
+/// How far a stop trails behind the best price
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrailDistance {
+    /// Fixed price distance
+    Price(Decimal),
+    /// Percentage of the best price
+    Percent(Decimal),
+    /// Multiple of the instrument's average true range
+    Atr(Decimal),
+}
+
+/// A virtual trailing stop on one trade
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrailingStop {
+    pub trade_id: TradeId,
+    pub instrument: String,
+    /// Whether the trade is long; shorts trail above the ask
+    pub long: bool,
+    pub distance: TrailDistance,
+    /// Price the trade must reach before the stop starts trailing
+    pub activation_price: Option<Decimal>,
+    /// Best price seen since activation (highest bid or lowest ask)
+    pub best_price: Option<Decimal>,
+    /// Current stop level, once known
+    pub stop_price: Option<Decimal>,
+}
+
+impl TrailingStop {
+    pub fn new(trade_id: TradeId, instrument: &str, long: bool, distance: TrailDistance) -> Self {
+        Self {
+            trade_id,
+            instrument: instrument.to_string(),
+            long,
+            distance,
+            activation_price: None,
+            best_price: None,
+            stop_price: None,
+        }
+    }
+
+    /// Stop following an open trade
+    pub fn for_trade(trade: &Trade, distance: TrailDistance) -> Self {
+        Self::new(
+            trade.id.clone(),
+            &trade.instrument,
+            trade.is_long(),
+            distance,
+        )
+    }
+
+    /// Only start trailing once the price reaches `price`
+    pub fn activate_at(mut self, price: Decimal) -> Self {
+        self.activation_price = Some(price);
+        self
+    }
+
+    /// Update the stop with a new price
+    ///
+    /// `atr` is the instrument's current ATR; ATR-based stops don't move
+    /// or trigger until one is known.
+    fn update(&mut self, tick: &Tick, atr: Option<Decimal>) -> Update {
+        let price = if self.long { tick.bid } else { tick.ask };
+        let favourable = |a: Decimal, b: Decimal| if self.long { a >= b } else { a <= b };
+
+        if self.best_price.is_none() {
+            match self.activation_price {
+                Some(activation) if !favourable(price, activation) => return Update::None,
+                _ => {}
+            }
+        }
+        let best = match self.best_price {
+            Some(best) if favourable(best, price) => best,
+            _ => price,
+        };
+        self.best_price = Some(best);
+
+        let distance = match self.distance {
+            TrailDistance::Price(distance) => Some(distance),
+            TrailDistance::Percent(percent) => Some(best * percent / Decimal::ONE_HUNDRED),
+            TrailDistance::Atr(multiple) => atr.map(|atr| atr * multiple),
+        };
+        let mut moved = false;
+        if let Some(distance) = distance {
+            let level = if self.long {
+                best - distance
+            } else {
+                best + distance
+            };
+            // Stops only tighten, even when the ATR widens
+            if self
+                .stop_price
+                .is_none_or(|stop| favourable(level, stop) && level != stop)
+            {
+                self.stop_price = Some(level);
+                moved = true;
+            }
+        }
+
+        match self.stop_price {
+            Some(stop) if !favourable(price, stop) || price == stop => Update::Triggered(price),
+            Some(_) if moved => Update::Moved,
+            _ => Update::None,
+        }
+    }
+}
+
+enum Update {
+    None,
+    Moved,
+    Triggered(Decimal),
+}
+
+/// Something that happened to a trailing stop
+#[derive(Debug, Clone, PartialEq)]
+pub enum TrailingStopEvent {
+    /// The stop tightened to a new level
+    Moved {
+        trade_id: TradeId,
+        instrument: String,
+        stop_price: Decimal,
+    },
+    /// The price came back to the stop; the stop is no longer tracked
+    Triggered {
+        trade_id: TradeId,
+        instrument: String,
+        price: Decimal,
+        stop_price: Decimal,
+    },
+    /// The trade was closed after its stop triggered
+    Closed {
+        trade_id: TradeId,
+        response: Box<OrderResponse>,
+    },
+    /// Closing failed
+    ///
+    /// After a transient error (`retrying`) the stop is tracked again and
+    /// retries on the next price at or through it. Any other error, such as
+    /// the trade having been closed already, drops the stop.
+    CloseFailed {
+        trade_id: TradeId,
+        error: String,
+        retrying: bool,
+    },
+}
+
+/// Wilder-smoothed average true range of one instrument
+#[derive(Default)]
+struct Atr {
+    previous: Option<Candle>,
+    seed: Vec<Decimal>,
+    value: Option<Decimal>,
+}
+
+impl Atr {
+    fn update(&mut self, candle: &Candle, period: usize) {
+        let true_range = candle.true_range(self.previous.as_ref());
+        self.previous = Some(candle.clone());
+        let period_dec = Decimal::from(period);
+        match self.value {
+            Some(atr) => {
+                self.value = Some((atr * (period_dec - Decimal::ONE) + true_range) / period_dec)
+            }
+            None => {
+                self.seed.push(true_range);
+                if self.seed.len() >= period {
+                    self.value = Some(self.seed.iter().sum::<Decimal>() / period_dec);
+                    self.seed.clear();
+                }
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+struct State {
+    stops: Vec<TrailingStop>,
+    atr: HashMap<String, Atr>,
+}
+
+/// Tracks virtual trailing stops and closes trades when they trigger
+///
+/// Cloning is cheap and clones share the same stops and channel.
+#[derive(Clone)]
+pub struct TrailingStopManager {
+    client: OandaClient,
+    atr_period: usize,
+    state: Arc<Mutex<State>>,
+    events: broadcast::Sender<TrailingStopEvent>,
+}
+
+impl TrailingStopManager {
+    pub fn new(client: OandaClient) -> Self {
+        let (events, _) = broadcast::channel(EVENT_CAPACITY);
+        Self {
+            client,
+            atr_period: DEFAULT_ATR_PERIOD,
+            state: Arc::new(Mutex::new(State::default())),
+            events,
+        }
+    }
+
+    /// Number of candles averaged for ATR-based stops
+    pub fn with_atr_period(mut self, period: usize) -> Self {
+        self.atr_period = period.max(1);
+        self
+    }
+
+    /// Start tracking a stop, replacing any existing stop on the same trade
+    pub fn add(&self, stop: TrailingStop) {
+        let mut state = self.state.lock().unwrap();
+        state.stops.retain(|s| s.trade_id != stop.trade_id);
+        state.stops.push(stop);
+    }
+
+    /// Start trailing an open trade
+    pub fn track_trade(&self, trade: &Trade, distance: TrailDistance) {
+        self.add(TrailingStop::for_trade(trade, distance));
+    }
+
+    /// Stop tracking a trade; returns its stop if it was tracked
+    pub fn remove(&self, trade_id: &TradeId) -> Option<TrailingStop> {
+        let mut state = self.state.lock().unwrap();
+        let index = state.stops.iter().position(|s| &s.trade_id == trade_id)?;
+        Some(state.stops.remove(index))
+    }
+
+    /// Stops currently tracked
+    pub fn stops(&self) -> Vec<TrailingStop> {
+        self.state.lock().unwrap().stops.clone()
+    }
+
+    /// Instruments with tracked stops, without duplicates
+    pub fn instruments(&self) -> Vec<String> {
+        let state = self.state.lock().unwrap();
+        let mut instruments: Vec<String> =
+            state.stops.iter().map(|s| s.instrument.clone()).collect();
+        instruments.sort();
+        instruments.dedup();
+        instruments
+    }
+
+    /// Current ATR of an instrument, once enough candles have been seen
+    pub fn atr(&self, instrument: &str) -> Option<Decimal> {
+        let state = self.state.lock().unwrap();
+        state.atr.get(instrument).and_then(|atr| atr.value)
+    }
+
+    /// Feed a candle into the instrument's ATR
+    ///
+    /// Incomplete candles are ignored. Candles must arrive in order and at
+    /// one granularity per instrument.
+    pub fn on_candle(&self, candle: &Candle) {
+        if !candle.complete {
+            return;
+        }
+        let mut state = self.state.lock().unwrap();
+        state
+            .atr
+            .entry(candle.instrument.clone())
+            .or_default()
+            .update(candle, self.atr_period);
+    }
+
+    /// Receiver for events from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<TrailingStopEvent> {
+        self.events.subscribe()
+    }
+
+    /// Move stops with a new price without closing anything
+    ///
+    /// Triggered stops stop being tracked and are returned for the caller
+    /// to close. Non-tradeable prices are skipped.
+    pub fn on_tick(&self, tick: &Tick) -> Vec<TrailingStop> {
+        if !tick.tradeable {
+            return Vec::new();
+        }
+        let mut events = Vec::new();
+        let mut triggered = Vec::new();
+        {
+            let mut state = self.state.lock().unwrap();
+            let atr = state.atr.get(&tick.instrument).and_then(|atr| atr.value);
+            let mut index = 0;
+            while index < state.stops.len() {
+                let stop = &mut state.stops[index];
+                if stop.instrument != tick.instrument {
+                    index += 1;
+                    continue;
+                }
+                match stop.update(tick, atr) {
+                    Update::None => index += 1,
+                    Update::Moved => {
+                        events.push(TrailingStopEvent::Moved {
+                            trade_id: stop.trade_id.clone(),
+                            instrument: stop.instrument.clone(),
+                            stop_price: stop.stop_price.unwrap_or_default(),
+                        });
+                        index += 1;
+                    }
+                    Update::Triggered(price) => {
+                        let stop = state.stops.remove(index);
+                        events.push(TrailingStopEvent::Triggered {
+                            trade_id: stop.trade_id.clone(),
+                            instrument: stop.instrument.clone(),
+                            price,
+                            stop_price: stop.stop_price.unwrap_or_default(),
+                        });
+                        triggered.push(stop);
+                    }
+                }
+            }
+        }
+        for event in events {
+            let _ = self.events.send(event);
+        }
+        triggered
+    }
+
+    /// Move stops with a new price and close the trades that trigger
+    ///
+    /// A stop whose close fails is tracked again, so it retries on a later
+    /// price. Returns the trades closed.
+    pub async fn process_tick(&self, tick: &Tick) -> Vec<TradeId> {
+        let mut closed = Vec::new();
+        for stop in self.on_tick(tick) {
+            match self.client.close_trade(&stop.trade_id, None).await {
+                Ok(response) => {
+                    closed.push(stop.trade_id.clone());
+                    let _ = self.events.send(TrailingStopEvent::Closed {
+                        trade_id: stop.trade_id,
+                        response: Box::new(response),
+                    });
+                }
+                Err(e) => {
+                    let retrying = e.is_retryable();
+                    log::warn!(
+                        "closing trade {} at trailing stop failed{}: {}",
+                        stop.trade_id,
+                        if retrying { ", will retry" } else { "" },
+                        e
+                    );
+                    let _ = self.events.send(TrailingStopEvent::CloseFailed {
+                        trade_id: stop.trade_id.clone(),
+                        error: e.to_string(),
+                        retrying,
+                    });
+                    if retrying {
+                        self.add(stop);
+                    }
+                }
+            }
+        }
+        closed
+    }
+
+    /// Process every tick from a price stream until it ends
+    ///
+    /// Errors in the stream are returned immediately.
+    pub async fn consume<S>(&self, ticks: S) -> Result<()>
+    where
+        S: Stream<Item = Result<Tick>>,
+    {
+        futures::pin_mut!(ticks);
+        while let Some(tick) = ticks.next().await {
+            self.process_tick(&tick?).await;
+        }
+        Ok(())
+    }
+
+    /// Follow the pricing stream in the background
+    ///
+    /// Reopens the stream `reconnect_delay` after it drops. The task runs
+    /// until shut down through the returned handle, or until the handle is
+    /// dropped.
+    pub fn spawn_streaming(
+        &self,
+        instruments: Vec<String>,
+        reconnect_delay: Duration,
+    ) -> ShutdownHandle {
+        let manager = self.clone();
+        ShutdownHandle::spawn(|mut signal| async move {
+            loop {
+                tokio::select! {
+                    result = async {
+                        let ticks = manager.client.stream_prices(&instruments).await?;
+                        manager.consume(ticks).await
+                    } => {
+                        if let Err(e) = result {
+                            log::warn!("trailing stop price stream failed: {}", e);
+                        }
+                    }
+                    _ = signal.requested() => break,
+                }
+                tokio::select! {
+                    _ = tokio::time::sleep(reconnect_delay) => {}
+                    _ = signal.requested() => break,
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::OandaConfig;
+    use crate::models::PriceStatus;
+    use chrono::DateTime;
+    use rust_decimal_macros::dec;
+
+    fn manager() -> TrailingStopManager {
+        let config = OandaConfig::new("token".to_string(), "101-001-1-001".to_string(), false);
+        TrailingStopManager::new(OandaClient::new(config).unwrap())
+    }
+
+    fn tick(bid: Decimal, ask: Decimal) -> Tick {
+        Tick {
+            instrument: "EUR_USD".to_string(),
+            timestamp: DateTime::from_timestamp(1_700_000_000, 0).unwrap(),
+            bid,
+            ask,
+            tradeable: true,
+            status: PriceStatus::Tradeable,
+            closeout_bid: None,
+            closeout_ask: None,
+        }
+    }
+
+    fn candle(high: Decimal, low: Decimal, close: Decimal) -> Candle {
+        Candle {
+            instrument: "EUR_USD".to_string(),
+            timestamp: DateTime::from_timestamp(1_700_000_000, 0).unwrap(),
+            open: close,
+            high,
+            low,
+            close,
+            volume: 100,
+            complete: true,
+        }
+    }
+
+    #[test]
+    fn test_long_stop_ratchets_and_triggers() {
+        let stops = manager();
+        stops.add(TrailingStop::new(
+            TradeId::from(1),
+            "EUR_USD",
+            true,
+            TrailDistance::Price(dec!(0.0020)),
+        ));
+        let mut events = stops.subscribe();
+
+        assert!(stops.on_tick(&tick(dec!(1.1000), dec!(1.1002))).is_empty());
+        assert!(stops.on_tick(&tick(dec!(1.1030), dec!(1.1032))).is_empty());
+        // Pulling back doesn't loosen the stop
+        assert!(stops.on_tick(&tick(dec!(1.1015), dec!(1.1017))).is_empty());
+        assert_eq!(stops.stops()[0].stop_price, Some(dec!(1.1010)));
+
+        let triggered = stops.on_tick(&tick(dec!(1.1010), dec!(1.1012)));
+        assert_eq!(triggered.len(), 1);
+        assert!(stops.stops().is_empty());
+
+        let mut received = Vec::new();
+        while let Ok(event) = events.try_recv() {
+            received.push(event);
+        }
+        assert_eq!(received.len(), 3);
+        assert!(matches!(
+            &received[2],
+            TrailingStopEvent::Triggered { stop_price, .. } if *stop_price == dec!(1.1010)
+        ));
+    }
+
+    #[test]
+    fn test_short_stop_with_activation() {
+        let stops = manager();
+        stops.add(
+            TrailingStop::new(
+                TradeId::from(2),
+                "EUR_USD",
+                false,
+                TrailDistance::Percent(dec!(1)),
+            )
+            .activate_at(dec!(1.0000)),
+        );
+
+        // Not trailing until the ask reaches the activation price
+        assert!(stops.on_tick(&tick(dec!(1.0500), dec!(1.0500))).is_empty());
+        assert_eq!(stops.stops()[0].stop_price, None);
+
+        stops.on_tick(&tick(dec!(1.0000), dec!(1.0000)));
+        assert_eq!(stops.stops()[0].stop_price, Some(dec!(1.0100)));
+        stops.on_tick(&tick(dec!(0.9000), dec!(0.9000)));
+        assert_eq!(stops.stops()[0].stop_price, Some(dec!(0.9090)));
+
+        assert!(stops.on_tick(&tick(dec!(0.9080), dec!(0.9089))).is_empty());
+        assert_eq!(stops.on_tick(&tick(dec!(0.9085), dec!(0.9091))).len(), 1);
+    }
+
+    #[test]
+    fn test_atr_stop_waits_for_atr() {
+        let stops = manager().with_atr_period(2);
+        stops.add(TrailingStop::new(
+            TradeId::from(3),
+            "EUR_USD",
+            true,
+            TrailDistance::Atr(dec!(2)),
+        ));
+
+        assert!(stops.on_tick(&tick(dec!(1.1000), dec!(1.1000))).is_empty());
+        assert_eq!(stops.stops()[0].stop_price, None);
+
+        stops.on_candle(&candle(dec!(1.1010), dec!(1.1000), dec!(1.1005)));
+        let mut incomplete = candle(dec!(1.2000), dec!(1.0000), dec!(1.1000));
+        incomplete.complete = false;
+        stops.on_candle(&incomplete);
+        assert_eq!(stops.atr("EUR_USD"), None);
+
+        stops.on_candle(&candle(dec!(1.1035), dec!(1.1015), dec!(1.1020)));
+        // True ranges 0.0010 and 0.0030
+        assert_eq!(stops.atr("EUR_USD"), Some(dec!(0.0020)));
+        stops.on_candle(&candle(dec!(1.1030), dec!(1.1020), dec!(1.1025)));
+        assert_eq!(stops.atr("EUR_USD"), Some(dec!(0.0015)));
+
+        stops.on_tick(&tick(dec!(1.1050), dec!(1.1050)));
+        assert_eq!(stops.stops()[0].stop_price, Some(dec!(1.1020)));
+    }
+}
@@ -0,0 +1,1040 @@
+//! Typed OANDA transaction models
+//!
+//! Every change to an account is recorded as a transaction. The `type` field
+//! selects the variant; types this crate doesn't know about yet deserialize
+//! into [`Transaction::Unknown`] instead of failing the whole response.
+
+use crate::ids::{AccountId, OrderId, TradeId, TransactionId};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+/// Account transaction, tagged by its OANDA `type`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum Transaction {
+    Create(CreateTransaction),
+    Close(AccountStatusTransaction),
+    Reopen(AccountStatusTransaction),
+    ClientConfigure(ClientConfigureTransaction),
+    ClientConfigureReject(ClientConfigureTransaction),
+    TransferFunds(TransferFundsTransaction),
+    TransferFundsReject(TransferFundsTransaction),
+    MarketOrder(OrderTransaction),
+    MarketOrderReject(OrderTransaction),
+    FixedPriceOrder(OrderTransaction),
+    LimitOrder(OrderTransaction),
+    LimitOrderReject(OrderTransaction),
+    StopOrder(OrderTransaction),
+    StopOrderReject(OrderTransaction),
+    MarketIfTouchedOrder(OrderTransaction),
+    MarketIfTouchedOrderReject(OrderTransaction),
+    TakeProfitOrder(OrderTransaction),
+    TakeProfitOrderReject(OrderTransaction),
+    StopLossOrder(OrderTransaction),
+    StopLossOrderReject(OrderTransaction),
+    GuaranteedStopLossOrder(OrderTransaction),
+    GuaranteedStopLossOrderReject(OrderTransaction),
+    TrailingStopLossOrder(OrderTransaction),
+    TrailingStopLossOrderReject(OrderTransaction),
+    OrderFill(OrderFillTransaction),
+    OrderCancel(OrderCancelTransaction),
+    OrderCancelReject(OrderCancelTransaction),
+    OrderClientExtensionsModify(ClientExtensionsModifyTransaction),
+    OrderClientExtensionsModifyReject(ClientExtensionsModifyTransaction),
+    TradeClientExtensionsModify(ClientExtensionsModifyTransaction),
+    TradeClientExtensionsModifyReject(ClientExtensionsModifyTransaction),
+    MarginCallEnter(MarginCallTransaction),
+    MarginCallExtend(MarginCallTransaction),
+    MarginCallExit(MarginCallTransaction),
+    DelayedTradeClosure(DelayedTradeClosureTransaction),
+    DailyFinancing(DailyFinancingTransaction),
+    DividendAdjustment(DividendAdjustmentTransaction),
+    ResetResettablePl(AccountStatusTransaction),
+    /// Transaction type not modelled by this crate (raw JSON, including `type`)
+    #[serde(untagged)]
+    Unknown(serde_json::Value),
+}
+
+impl Transaction {
+    /// Fields shared by every transaction, if this is a known type
+    pub fn header(&self) -> Option<&TransactionHeader> {
+        match self {
+            Transaction::Create(t) => Some(&t.header),
+            Transaction::Close(t) | Transaction::Reopen(t) | Transaction::ResetResettablePl(t) => {
+                Some(&t.header)
+            }
+            Transaction::ClientConfigure(t) | Transaction::ClientConfigureReject(t) => {
+                Some(&t.header)
+            }
+            Transaction::TransferFunds(t) | Transaction::TransferFundsReject(t) => Some(&t.header),
+            Transaction::MarketOrder(t)
+            | Transaction::MarketOrderReject(t)
+            | Transaction::FixedPriceOrder(t)
+            | Transaction::LimitOrder(t)
+            | Transaction::LimitOrderReject(t)
+            | Transaction::StopOrder(t)
+            | Transaction::StopOrderReject(t)
+            | Transaction::MarketIfTouchedOrder(t)
+            | Transaction::MarketIfTouchedOrderReject(t)
+            | Transaction::TakeProfitOrder(t)
+            | Transaction::TakeProfitOrderReject(t)
+            | Transaction::StopLossOrder(t)
+            | Transaction::StopLossOrderReject(t)
+            | Transaction::GuaranteedStopLossOrder(t)
+            | Transaction::GuaranteedStopLossOrderReject(t)
+            | Transaction::TrailingStopLossOrder(t)
+            | Transaction::TrailingStopLossOrderReject(t) => Some(&t.header),
+            Transaction::OrderFill(t) => Some(&t.header),
+            Transaction::OrderCancel(t) | Transaction::OrderCancelReject(t) => Some(&t.header),
+            Transaction::OrderClientExtensionsModify(t)
+            | Transaction::OrderClientExtensionsModifyReject(t)
+            | Transaction::TradeClientExtensionsModify(t)
+            | Transaction::TradeClientExtensionsModifyReject(t) => Some(&t.header),
+            Transaction::MarginCallEnter(t)
+            | Transaction::MarginCallExtend(t)
+            | Transaction::MarginCallExit(t) => Some(&t.header),
+            Transaction::DelayedTradeClosure(t) => Some(&t.header),
+            Transaction::DailyFinancing(t) => Some(&t.header),
+            Transaction::DividendAdjustment(t) => Some(&t.header),
+            Transaction::Unknown(_) => None,
+        }
+    }
+
+    /// Transaction ID
+    pub fn id(&self) -> Option<&str> {
+        match self {
+            Transaction::Unknown(value) => value.get("id").and_then(|v| v.as_str()),
+            _ => self.header().map(|h| h.id.as_str()),
+        }
+    }
+
+    /// Typed transaction ID, if present and well-formed
+    pub fn transaction_id(&self) -> Option<TransactionId> {
+        match self {
+            Transaction::Unknown(_) => self.id().and_then(|id| id.parse().ok()),
+            _ => self.header().map(|h| h.id.clone()),
+        }
+    }
+
+    /// Time the transaction was executed
+    pub fn time(&self) -> Option<DateTime<Utc>> {
+        match self {
+            Transaction::Unknown(value) => value
+                .get("time")
+                .and_then(|v| v.as_str())
+                .and_then(crate::datetime::parse),
+            _ => self.header().map(|h| h.time),
+        }
+    }
+
+    /// Instrument the transaction concerns, if it is about a single one
+    pub fn instrument(&self) -> Option<&str> {
+        match self {
+            Transaction::MarketOrder(t)
+            | Transaction::MarketOrderReject(t)
+            | Transaction::FixedPriceOrder(t)
+            | Transaction::LimitOrder(t)
+            | Transaction::LimitOrderReject(t)
+            | Transaction::StopOrder(t)
+            | Transaction::StopOrderReject(t)
+            | Transaction::MarketIfTouchedOrder(t)
+            | Transaction::MarketIfTouchedOrderReject(t)
+            | Transaction::TakeProfitOrder(t)
+            | Transaction::TakeProfitOrderReject(t)
+            | Transaction::StopLossOrder(t)
+            | Transaction::StopLossOrderReject(t)
+            | Transaction::GuaranteedStopLossOrder(t)
+            | Transaction::GuaranteedStopLossOrderReject(t)
+            | Transaction::TrailingStopLossOrder(t)
+            | Transaction::TrailingStopLossOrderReject(t) => t.instrument.as_deref(),
+            Transaction::OrderFill(t) => Some(&t.instrument),
+            Transaction::DividendAdjustment(t) => Some(&t.instrument),
+            Transaction::Unknown(value) => value.get("instrument").and_then(|v| v.as_str()),
+            _ => None,
+        }
+    }
+
+    /// OANDA transaction type name (e.g. "ORDER_FILL")
+    pub fn type_name(&self) -> &str {
+        match self {
+            Transaction::Create(_) => "CREATE",
+            Transaction::Close(_) => "CLOSE",
+            Transaction::Reopen(_) => "REOPEN",
+            Transaction::ClientConfigure(_) => "CLIENT_CONFIGURE",
+            Transaction::ClientConfigureReject(_) => "CLIENT_CONFIGURE_REJECT",
+            Transaction::TransferFunds(_) => "TRANSFER_FUNDS",
+            Transaction::TransferFundsReject(_) => "TRANSFER_FUNDS_REJECT",
+            Transaction::MarketOrder(_) => "MARKET_ORDER",
+            Transaction::MarketOrderReject(_) => "MARKET_ORDER_REJECT",
+            Transaction::FixedPriceOrder(_) => "FIXED_PRICE_ORDER",
+            Transaction::LimitOrder(_) => "LIMIT_ORDER",
+            Transaction::LimitOrderReject(_) => "LIMIT_ORDER_REJECT",
+            Transaction::StopOrder(_) => "STOP_ORDER",
+            Transaction::StopOrderReject(_) => "STOP_ORDER_REJECT",
+            Transaction::MarketIfTouchedOrder(_) => "MARKET_IF_TOUCHED_ORDER",
+            Transaction::MarketIfTouchedOrderReject(_) => "MARKET_IF_TOUCHED_ORDER_REJECT",
+            Transaction::TakeProfitOrder(_) => "TAKE_PROFIT_ORDER",
+            Transaction::TakeProfitOrderReject(_) => "TAKE_PROFIT_ORDER_REJECT",
+            Transaction::StopLossOrder(_) => "STOP_LOSS_ORDER",
+            Transaction::StopLossOrderReject(_) => "STOP_LOSS_ORDER_REJECT",
+            Transaction::GuaranteedStopLossOrder(_) => "GUARANTEED_STOP_LOSS_ORDER",
+            Transaction::GuaranteedStopLossOrderReject(_) => "GUARANTEED_STOP_LOSS_ORDER_REJECT",
+            Transaction::TrailingStopLossOrder(_) => "TRAILING_STOP_LOSS_ORDER",
+            Transaction::TrailingStopLossOrderReject(_) => "TRAILING_STOP_LOSS_ORDER_REJECT",
+            Transaction::OrderFill(_) => "ORDER_FILL",
+            Transaction::OrderCancel(_) => "ORDER_CANCEL",
+            Transaction::OrderCancelReject(_) => "ORDER_CANCEL_REJECT",
+            Transaction::OrderClientExtensionsModify(_) => "ORDER_CLIENT_EXTENSIONS_MODIFY",
+            Transaction::OrderClientExtensionsModifyReject(_) => {
+                "ORDER_CLIENT_EXTENSIONS_MODIFY_REJECT"
+            }
+            Transaction::TradeClientExtensionsModify(_) => "TRADE_CLIENT_EXTENSIONS_MODIFY",
+            Transaction::TradeClientExtensionsModifyReject(_) => {
+                "TRADE_CLIENT_EXTENSIONS_MODIFY_REJECT"
+            }
+            Transaction::MarginCallEnter(_) => "MARGIN_CALL_ENTER",
+            Transaction::MarginCallExtend(_) => "MARGIN_CALL_EXTEND",
+            Transaction::MarginCallExit(_) => "MARGIN_CALL_EXIT",
+            Transaction::DelayedTradeClosure(_) => "DELAYED_TRADE_CLOSURE",
+            Transaction::DailyFinancing(_) => "DAILY_FINANCING",
+            Transaction::DividendAdjustment(_) => "DIVIDEND_ADJUSTMENT",
+            Transaction::ResetResettablePl(_) => "RESET_RESETTABLE_PL",
+            Transaction::Unknown(value) => value
+                .get("type")
+                .and_then(|v| v.as_str())
+                .unwrap_or("UNKNOWN"),
+        }
+    }
+}
+
+/// Fields common to all transactions
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TransactionHeader {
+    pub id: TransactionId,
+    #[serde(with = "crate::datetime")]
+    pub time: DateTime<Utc>,
+    #[serde(rename = "userID", default, skip_serializing_if = "Option::is_none")]
+    pub user_id: Option<i64>,
+    #[serde(rename = "accountID")]
+    pub account_id: AccountId,
+    #[serde(rename = "batchID")]
+    pub batch_id: TransactionId,
+    #[serde(rename = "requestID", default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+}
+
+/// Client-provided identifiers attached to orders and trades
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ClientExtensions {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+}
+
+/// CREATE: account was created
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateTransaction {
+    #[serde(flatten)]
+    pub header: TransactionHeader,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub home_currency: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub account_number: Option<i32>,
+}
+
+/// CLOSE, REOPEN and RESET_RESETTABLE_PL: account status changes without a payload
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AccountStatusTransaction {
+    #[serde(flatten)]
+    pub header: TransactionHeader,
+}
+
+/// CLIENT_CONFIGURE and CLIENT_CONFIGURE_REJECT
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ClientConfigureTransaction {
+    #[serde(flatten)]
+    pub header: TransactionHeader,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alias: Option<String>,
+    #[serde(
+        default,
+        with = "crate::decimal_number::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub margin_rate: Option<Decimal>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reject_reason: Option<String>,
+}
+
+/// TRANSFER_FUNDS and TRANSFER_FUNDS_REJECT
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TransferFundsTransaction {
+    #[serde(flatten)]
+    pub header: TransactionHeader,
+    #[serde(with = "crate::decimal_number")]
+    pub amount: Decimal,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub funding_reason: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    #[serde(
+        default,
+        with = "crate::decimal_number::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub account_balance: Option<Decimal>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reject_reason: Option<String>,
+}
+
+/// Order creation (and rejection) transactions for every order type
+///
+/// Fields that only apply to some order types are optional.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct OrderTransaction {
+    #[serde(flatten)]
+    pub header: TransactionHeader,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub instrument: Option<String>,
+    #[serde(
+        default,
+        with = "crate::decimal_number::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub units: Option<Decimal>,
+    #[serde(
+        default,
+        with = "crate::decimal_number::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub price: Option<Decimal>,
+    #[serde(
+        default,
+        with = "crate::decimal_number::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub price_bound: Option<Decimal>,
+    #[serde(
+        default,
+        with = "crate::decimal_number::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub distance: Option<Decimal>,
+    #[serde(rename = "tradeID", default, skip_serializing_if = "Option::is_none")]
+    pub trade_id: Option<TradeId>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time_in_force: Option<String>,
+    #[serde(
+        default,
+        with = "crate::datetime::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub gtd_time: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub position_fill: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trigger_condition: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_extensions: Option<ClientExtensions>,
+    #[serde(
+        rename = "replacesOrderID",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub replaces_order_id: Option<OrderId>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reject_reason: Option<String>,
+}
+
+/// ORDER_FILL: an order was filled, opening and/or closing trades
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct OrderFillTransaction {
+    #[serde(flatten)]
+    pub header: TransactionHeader,
+    #[serde(rename = "orderID")]
+    pub order_id: OrderId,
+    #[serde(
+        rename = "clientOrderID",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub client_order_id: Option<String>,
+    pub instrument: String,
+    #[serde(with = "crate::decimal_number")]
+    pub units: Decimal,
+    #[serde(
+        default,
+        with = "crate::decimal_number::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub price: Option<Decimal>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    #[serde(default, with = "crate::decimal_number")]
+    pub pl: Decimal,
+    #[serde(default, with = "crate::decimal_number")]
+    pub financing: Decimal,
+    #[serde(default, with = "crate::decimal_number")]
+    pub commission: Decimal,
+    #[serde(
+        default,
+        with = "crate::decimal_number::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub guaranteed_execution_fee: Option<Decimal>,
+    #[serde(
+        default,
+        with = "crate::decimal_number::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub half_spread_cost: Option<Decimal>,
+    #[serde(
+        default,
+        with = "crate::decimal_number::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub account_balance: Option<Decimal>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trade_opened: Option<TradeOpen>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub trades_closed: Vec<TradeReduce>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trade_reduced: Option<TradeReduce>,
+}
+
+/// Trade opened by an order fill
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TradeOpen {
+    #[serde(rename = "tradeID")]
+    pub trade_id: TradeId,
+    #[serde(with = "crate::decimal_number")]
+    pub units: Decimal,
+    #[serde(
+        default,
+        with = "crate::decimal_number::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub price: Option<Decimal>,
+    #[serde(
+        default,
+        with = "crate::decimal_number::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub half_spread_cost: Option<Decimal>,
+}
+
+/// Trade closed or reduced by an order fill
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TradeReduce {
+    #[serde(rename = "tradeID")]
+    pub trade_id: TradeId,
+    #[serde(with = "crate::decimal_number")]
+    pub units: Decimal,
+    #[serde(
+        default,
+        with = "crate::decimal_number::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub price: Option<Decimal>,
+    #[serde(rename = "realizedPL", default, with = "crate::decimal_number")]
+    pub realized_pl: Decimal,
+    #[serde(default, with = "crate::decimal_number")]
+    pub financing: Decimal,
+    #[serde(
+        default,
+        with = "crate::decimal_number::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub half_spread_cost: Option<Decimal>,
+}
+
+/// ORDER_CANCEL and ORDER_CANCEL_REJECT
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct OrderCancelTransaction {
+    #[serde(flatten)]
+    pub header: TransactionHeader,
+    #[serde(rename = "orderID")]
+    pub order_id: OrderId,
+    #[serde(
+        rename = "clientOrderID",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub client_order_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    #[serde(
+        rename = "replacedByOrderID",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub replaced_by_order_id: Option<OrderId>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reject_reason: Option<String>,
+}
+
+/// ORDER_/TRADE_CLIENT_EXTENSIONS_MODIFY and their rejections
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ClientExtensionsModifyTransaction {
+    #[serde(flatten)]
+    pub header: TransactionHeader,
+    #[serde(rename = "orderID", default, skip_serializing_if = "Option::is_none")]
+    pub order_id: Option<OrderId>,
+    #[serde(rename = "tradeID", default, skip_serializing_if = "Option::is_none")]
+    pub trade_id: Option<TradeId>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_extensions_modify: Option<ClientExtensions>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trade_client_extensions_modify: Option<ClientExtensions>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reject_reason: Option<String>,
+}
+
+/// MARGIN_CALL_ENTER, MARGIN_CALL_EXTEND and MARGIN_CALL_EXIT
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct MarginCallTransaction {
+    #[serde(flatten)]
+    pub header: TransactionHeader,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extension_number: Option<i32>,
+}
+
+/// DELAYED_TRADE_CLOSURE: trades that will be closed when the market opens
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DelayedTradeClosureTransaction {
+    #[serde(flatten)]
+    pub header: TransactionHeader,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    #[serde(rename = "tradeIDs", default, skip_serializing_if = "Option::is_none")]
+    pub trade_ids: Option<String>,
+}
+
+/// DAILY_FINANCING: overnight financing charged or paid on open positions
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DailyFinancingTransaction {
+    #[serde(flatten)]
+    pub header: TransactionHeader,
+    #[serde(with = "crate::decimal_number")]
+    pub financing: Decimal,
+    #[serde(
+        default,
+        with = "crate::decimal_number::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub account_balance: Option<Decimal>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub account_financing_mode: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub position_financings: Vec<PositionFinancing>,
+}
+
+/// Financing attributed to one instrument's position
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PositionFinancing {
+    pub instrument: String,
+    #[serde(with = "crate::decimal_number")]
+    pub financing: Decimal,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub open_trade_financings: Vec<OpenTradeFinancing>,
+}
+
+/// Financing attributed to one open trade
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct OpenTradeFinancing {
+    #[serde(rename = "tradeID")]
+    pub trade_id: TradeId,
+    #[serde(with = "crate::decimal_number")]
+    pub financing: Decimal,
+}
+
+/// DIVIDEND_ADJUSTMENT: dividend paid or charged on index/equity CFD positions
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DividendAdjustmentTransaction {
+    #[serde(flatten)]
+    pub header: TransactionHeader,
+    pub instrument: String,
+    #[serde(with = "crate::decimal_number")]
+    pub dividend_adjustment: Decimal,
+    #[serde(
+        default,
+        with = "crate::decimal_number::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub account_balance: Option<Decimal>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub open_trade_dividend_adjustments: Vec<OpenTradeDividendAdjustment>,
+}
+
+/// Dividend adjustment attributed to one open trade
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct OpenTradeDividendAdjustment {
+    #[serde(rename = "tradeID")]
+    pub trade_id: TradeId,
+    #[serde(with = "crate::decimal_number")]
+    pub dividend_adjustment: Decimal,
+}
+
+/// Transaction type filter accepted by OANDA's `type` query parameter
+///
+/// Besides individual transaction types, `Order`, `Funding` and `Admin`
+/// select whole groups.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum TransactionType {
+    /// Any order-related transaction (creation, fill, cancel, reject)
+    Order,
+    /// Any funds transfer transaction
+    Funding,
+    /// Any administrative account transaction
+    Admin,
+    Create,
+    Close,
+    Reopen,
+    ClientConfigure,
+    ClientConfigureReject,
+    TransferFunds,
+    TransferFundsReject,
+    MarketOrder,
+    MarketOrderReject,
+    FixedPriceOrder,
+    LimitOrder,
+    LimitOrderReject,
+    StopOrder,
+    StopOrderReject,
+    MarketIfTouchedOrder,
+    MarketIfTouchedOrderReject,
+    TakeProfitOrder,
+    TakeProfitOrderReject,
+    StopLossOrder,
+    StopLossOrderReject,
+    GuaranteedStopLossOrder,
+    GuaranteedStopLossOrderReject,
+    TrailingStopLossOrder,
+    TrailingStopLossOrderReject,
+    OrderFill,
+    OrderCancel,
+    OrderCancelReject,
+    OrderClientExtensionsModify,
+    OrderClientExtensionsModifyReject,
+    TradeClientExtensionsModify,
+    TradeClientExtensionsModifyReject,
+    MarginCallEnter,
+    MarginCallExtend,
+    MarginCallExit,
+    DelayedTradeClosure,
+    DailyFinancing,
+    DividendAdjustment,
+    ResetResettablePl,
+}
+
+impl TransactionType {
+    /// All filter values
+    pub const ALL: [TransactionType; 41] = [
+        TransactionType::Order,
+        TransactionType::Funding,
+        TransactionType::Admin,
+        TransactionType::Create,
+        TransactionType::Close,
+        TransactionType::Reopen,
+        TransactionType::ClientConfigure,
+        TransactionType::ClientConfigureReject,
+        TransactionType::TransferFunds,
+        TransactionType::TransferFundsReject,
+        TransactionType::MarketOrder,
+        TransactionType::MarketOrderReject,
+        TransactionType::FixedPriceOrder,
+        TransactionType::LimitOrder,
+        TransactionType::LimitOrderReject,
+        TransactionType::StopOrder,
+        TransactionType::StopOrderReject,
+        TransactionType::MarketIfTouchedOrder,
+        TransactionType::MarketIfTouchedOrderReject,
+        TransactionType::TakeProfitOrder,
+        TransactionType::TakeProfitOrderReject,
+        TransactionType::StopLossOrder,
+        TransactionType::StopLossOrderReject,
+        TransactionType::GuaranteedStopLossOrder,
+        TransactionType::GuaranteedStopLossOrderReject,
+        TransactionType::TrailingStopLossOrder,
+        TransactionType::TrailingStopLossOrderReject,
+        TransactionType::OrderFill,
+        TransactionType::OrderCancel,
+        TransactionType::OrderCancelReject,
+        TransactionType::OrderClientExtensionsModify,
+        TransactionType::OrderClientExtensionsModifyReject,
+        TransactionType::TradeClientExtensionsModify,
+        TransactionType::TradeClientExtensionsModifyReject,
+        TransactionType::MarginCallEnter,
+        TransactionType::MarginCallExtend,
+        TransactionType::MarginCallExit,
+        TransactionType::DelayedTradeClosure,
+        TransactionType::DailyFinancing,
+        TransactionType::DividendAdjustment,
+        TransactionType::ResetResettablePl,
+    ];
+
+    /// OANDA name of the filter value
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TransactionType::Order => "ORDER",
+            TransactionType::Funding => "FUNDING",
+            TransactionType::Admin => "ADMIN",
+            TransactionType::Create => "CREATE",
+            TransactionType::Close => "CLOSE",
+            TransactionType::Reopen => "REOPEN",
+            TransactionType::ClientConfigure => "CLIENT_CONFIGURE",
+            TransactionType::ClientConfigureReject => "CLIENT_CONFIGURE_REJECT",
+            TransactionType::TransferFunds => "TRANSFER_FUNDS",
+            TransactionType::TransferFundsReject => "TRANSFER_FUNDS_REJECT",
+            TransactionType::MarketOrder => "MARKET_ORDER",
+            TransactionType::MarketOrderReject => "MARKET_ORDER_REJECT",
+            TransactionType::FixedPriceOrder => "FIXED_PRICE_ORDER",
+            TransactionType::LimitOrder => "LIMIT_ORDER",
+            TransactionType::LimitOrderReject => "LIMIT_ORDER_REJECT",
+            TransactionType::StopOrder => "STOP_ORDER",
+            TransactionType::StopOrderReject => "STOP_ORDER_REJECT",
+            TransactionType::MarketIfTouchedOrder => "MARKET_IF_TOUCHED_ORDER",
+            TransactionType::MarketIfTouchedOrderReject => "MARKET_IF_TOUCHED_ORDER_REJECT",
+            TransactionType::TakeProfitOrder => "TAKE_PROFIT_ORDER",
+            TransactionType::TakeProfitOrderReject => "TAKE_PROFIT_ORDER_REJECT",
+            TransactionType::StopLossOrder => "STOP_LOSS_ORDER",
+            TransactionType::StopLossOrderReject => "STOP_LOSS_ORDER_REJECT",
+            TransactionType::GuaranteedStopLossOrder => "GUARANTEED_STOP_LOSS_ORDER",
+            TransactionType::GuaranteedStopLossOrderReject => "GUARANTEED_STOP_LOSS_ORDER_REJECT",
+            TransactionType::TrailingStopLossOrder => "TRAILING_STOP_LOSS_ORDER",
+            TransactionType::TrailingStopLossOrderReject => "TRAILING_STOP_LOSS_ORDER_REJECT",
+            TransactionType::OrderFill => "ORDER_FILL",
+            TransactionType::OrderCancel => "ORDER_CANCEL",
+            TransactionType::OrderCancelReject => "ORDER_CANCEL_REJECT",
+            TransactionType::OrderClientExtensionsModify => "ORDER_CLIENT_EXTENSIONS_MODIFY",
+            TransactionType::OrderClientExtensionsModifyReject => {
+                "ORDER_CLIENT_EXTENSIONS_MODIFY_REJECT"
+            }
+            TransactionType::TradeClientExtensionsModify => "TRADE_CLIENT_EXTENSIONS_MODIFY",
+            TransactionType::TradeClientExtensionsModifyReject => {
+                "TRADE_CLIENT_EXTENSIONS_MODIFY_REJECT"
+            }
+            TransactionType::MarginCallEnter => "MARGIN_CALL_ENTER",
+            TransactionType::MarginCallExtend => "MARGIN_CALL_EXTEND",
+            TransactionType::MarginCallExit => "MARGIN_CALL_EXIT",
+            TransactionType::DelayedTradeClosure => "DELAYED_TRADE_CLOSURE",
+            TransactionType::DailyFinancing => "DAILY_FINANCING",
+            TransactionType::DividendAdjustment => "DIVIDEND_ADJUSTMENT",
+            TransactionType::ResetResettablePl => "RESET_RESETTABLE_PL",
+        }
+    }
+
+    /// Check if a transaction type name is selected by this filter value
+    pub fn matches_type_name(&self, type_name: &str) -> bool {
+        match self {
+            TransactionType::Order => {
+                type_name.starts_with("ORDER_")
+                    || type_name.ends_with("_ORDER")
+                    || type_name.ends_with("_ORDER_REJECT")
+            }
+            TransactionType::Funding => type_name.starts_with("TRANSFER_FUNDS"),
+            TransactionType::Admin => matches!(
+                type_name,
+                "CREATE" | "CLOSE" | "REOPEN" | "CLIENT_CONFIGURE" | "CLIENT_CONFIGURE_REJECT"
+            ),
+            _ => self.as_str() == type_name,
+        }
+    }
+}
+
+impl std::fmt::Display for TransactionType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for TransactionType {
+    type Err = crate::error::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let upper = s.to_uppercase();
+        TransactionType::ALL
+            .iter()
+            .find(|t| t.as_str() == upper)
+            .copied()
+            .ok_or_else(|| {
+                crate::error::Error::ConfigError(format!("Invalid transaction type: {}", s))
+            })
+    }
+}
+
+/// Selects which transaction types a query returns
+///
+/// The types are sent to OANDA as the `type` parameter so unwanted
+/// transactions are never transferred, and [`TransactionFilter::matches`]
+/// applies the same selection locally. An empty filter selects everything.
+///
+/// # Example
+/// ```
+/// use oanda_connector::transactions::{TransactionFilter, TransactionType};
+///
+/// let filter = TransactionFilter::new()
+///     .with_type(TransactionType::OrderFill)
+///     .with_type(TransactionType::DailyFinancing);
+/// assert_eq!(filter.query_value().as_deref(), Some("ORDER_FILL,DAILY_FINANCING"));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TransactionFilter {
+    types: Vec<TransactionType>,
+}
+
+impl TransactionFilter {
+    /// Create an empty filter (selects all transactions)
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a transaction type to the selection
+    pub fn with_type(mut self, transaction_type: TransactionType) -> Self {
+        if !self.types.contains(&transaction_type) {
+            self.types.push(transaction_type);
+        }
+        self
+    }
+
+    /// Selected types (empty means all)
+    pub fn types(&self) -> &[TransactionType] {
+        &self.types
+    }
+
+    /// Check if the filter selects every transaction
+    pub fn is_empty(&self) -> bool {
+        self.types.is_empty()
+    }
+
+    /// Check if a transaction is selected by this filter
+    pub fn matches(&self, transaction: &Transaction) -> bool {
+        self.is_empty()
+            || self
+                .types
+                .iter()
+                .any(|t| t.matches_type_name(transaction.type_name()))
+    }
+
+    /// Value for OANDA's `type` query parameter, if any types are selected
+    pub fn query_value(&self) -> Option<String> {
+        if self.is_empty() {
+            return None;
+        }
+
+        Some(
+            self.types
+                .iter()
+                .map(|t| t.as_str())
+                .collect::<Vec<_>>()
+                .join(","),
+        )
+    }
+}
+
+impl FromIterator<TransactionType> for TransactionFilter {
+    fn from_iter<I: IntoIterator<Item = TransactionType>>(iter: I) -> Self {
+        iter.into_iter()
+            .fold(TransactionFilter::new(), |filter, t| filter.with_type(t))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_order_fill_deserialization() {
+        let transaction: Transaction = serde_json::from_str(
+            r#"{
+                "id": "6410",
+                "time": "2024-01-02T14:30:00.000000000Z",
+                "userID": 1234567,
+                "accountID": "101-004-1234567-001",
+                "batchID": "6409",
+                "requestID": "60997412823470361",
+                "type": "ORDER_FILL",
+                "orderID": "6409",
+                "instrument": "EUR_USD",
+                "units": "-1000",
+                "price": "1.10020",
+                "reason": "MARKET_ORDER",
+                "pl": "12.3456",
+                "financing": "0.0000",
+                "commission": "0.0000",
+                "accountBalance": "100012.3456",
+                "tradesClosed": [{
+                    "tradeID": "6400",
+                    "units": "-1000",
+                    "price": "1.10020",
+                    "realizedPL": "12.3456",
+                    "financing": "0.0000"
+                }]
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(transaction.id(), Some("6410"));
+        assert_eq!(transaction.type_name(), "ORDER_FILL");
+        assert_eq!(transaction.instrument(), Some("EUR_USD"));
+
+        match transaction {
+            Transaction::OrderFill(fill) => {
+                assert_eq!(fill.instrument, "EUR_USD");
+                assert_eq!(fill.units, dec!(-1000));
+                assert_eq!(fill.pl, dec!(12.3456));
+                assert_eq!(fill.account_balance, Some(dec!(100012.3456)));
+                assert_eq!(fill.trades_closed.len(), 1);
+                assert_eq!(fill.trades_closed[0].trade_id, "6400");
+            }
+            other => panic!("Expected OrderFill, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_daily_financing_deserialization() {
+        let transaction: Transaction = serde_json::from_str(
+            r#"{
+                "id": "6500",
+                "time": "2024-01-02T21:00:00.000000000Z",
+                "accountID": "101-004-1234567-001",
+                "batchID": "6500",
+                "type": "DAILY_FINANCING",
+                "financing": "-0.5012",
+                "accountBalance": "100011.8444",
+                "accountFinancingMode": "DAILY",
+                "positionFinancings": [{
+                    "instrument": "EUR_USD",
+                    "financing": "-0.5012",
+                    "openTradeFinancings": [{"tradeID": "6400", "financing": "-0.5012"}]
+                }]
+            }"#,
+        )
+        .unwrap();
+
+        match transaction {
+            Transaction::DailyFinancing(financing) => {
+                assert_eq!(financing.financing, dec!(-0.5012));
+                assert_eq!(financing.position_financings[0].instrument, "EUR_USD");
+            }
+            other => panic!("Expected DailyFinancing, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_unknown_transaction_type() {
+        let transaction: Transaction = serde_json::from_str(
+            r#"{
+                "id": "7000",
+                "time": "2024-01-03T00:00:00.000000000Z",
+                "accountID": "101-004-1234567-001",
+                "batchID": "7000",
+                "type": "SOME_FUTURE_TYPE",
+                "newField": "42"
+            }"#,
+        )
+        .unwrap();
+
+        assert!(matches!(transaction, Transaction::Unknown(_)));
+        assert_eq!(transaction.type_name(), "SOME_FUTURE_TYPE");
+        assert_eq!(transaction.instrument(), None);
+        assert_eq!(transaction.id(), Some("7000"));
+        assert_eq!(
+            transaction.transaction_id(),
+            Some(TransactionId::from(7000))
+        );
+        assert!(transaction.time().is_some());
+        assert!(transaction.header().is_none());
+    }
+
+    #[test]
+    fn test_transaction_round_trip() {
+        let json = r#"{
+            "id": "6501",
+            "time": "2024-01-02T21:00:00Z",
+            "accountID": "101-004-1234567-001",
+            "batchID": "6501",
+            "type": "MARKET_ORDER",
+            "instrument": "USD_JPY",
+            "units": "5000",
+            "timeInForce": "FOK",
+            "reason": "CLIENT_ORDER"
+        }"#;
+
+        let transaction: Transaction = serde_json::from_str(json).unwrap();
+        let serialized = serde_json::to_string(&transaction).unwrap();
+        let round_trip: Transaction = serde_json::from_str(&serialized).unwrap();
+
+        assert_eq!(transaction, round_trip);
+        assert!(
+            matches!(round_trip, Transaction::MarketOrder(ref o) if o.units == Some(dec!(5000)))
+        );
+    }
+
+    #[test]
+    fn test_transaction_type_parsing() {
+        assert_eq!(
+            "order_fill".parse::<TransactionType>().unwrap(),
+            TransactionType::OrderFill
+        );
+        assert_eq!(
+            TransactionType::DailyFinancing.to_string(),
+            "DAILY_FINANCING"
+        );
+        assert!("NOT_A_TYPE".parse::<TransactionType>().is_err());
+    }
+
+    #[test]
+    fn test_transaction_filter_matching() {
+        let fill: Transaction = serde_json::from_str(
+            r#"{
+                "id": "1", "time": "2024-01-02T14:30:00Z", "accountID": "a", "batchID": "1",
+                "type": "ORDER_FILL", "orderID": "1", "instrument": "EUR_USD", "units": "1"
+            }"#,
+        )
+        .unwrap();
+        let transfer: Transaction = serde_json::from_str(
+            r#"{
+                "id": "2", "time": "2024-01-02T14:30:00Z", "accountID": "a", "batchID": "2",
+                "type": "TRANSFER_FUNDS", "amount": "1000"
+            }"#,
+        )
+        .unwrap();
+
+        assert!(TransactionFilter::new().matches(&fill));
+
+        let fills = TransactionFilter::new().with_type(TransactionType::OrderFill);
+        assert!(fills.matches(&fill));
+        assert!(!fills.matches(&transfer));
+
+        let groups: TransactionFilter = [TransactionType::Order, TransactionType::Funding]
+            .into_iter()
+            .collect();
+        assert!(groups.matches(&fill));
+        assert!(groups.matches(&transfer));
+        assert_eq!(groups.query_value().as_deref(), Some("ORDER,FUNDING"));
+    }
+}
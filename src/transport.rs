@@ -0,0 +1,51 @@
+//! Pluggable HTTP transport
+//!
+//! The client hands each finished request to a `Transport`, by default the
+//! reqwest client built from the config. Injecting another implementation
+//! lets tests exercise client logic without a mock server, and lets
+//! embedders send requests through a different HTTP stack.
+//!
+//! Proxy, TLS, User-Agent and default header settings are applied by the
+//! default transport; a custom transport is responsible for its own.
+//!
+//! ```
+//! use async_trait::async_trait;
+//! use oanda_connector::transport::Transport;
+//! use oanda_connector::{OandaClientBuilder, OandaConfig, Result};
+//! use reqwest::{Request, Response};
+//!
+//! /// Answers every request with an empty account list
+//! struct Canned;
+//!
+//! #[async_trait]
+//! impl Transport for Canned {
+//!     async fn execute(&self, _request: Request) -> Result<Response> {
+//!         Ok(Response::from(http::Response::new(r#"{"accounts": []}"#)))
+//!     }
+//! }
+//!
+//! # fn main() -> Result<()> {
+//! let config = OandaConfig::new("key".to_string(), "101-001-1-001".to_string(), true);
+//! let client = OandaClientBuilder::new(config).transport(Canned).build()?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::error::{Error, Result};
+use async_trait::async_trait;
+use reqwest::{Client as HttpClient, Request, Response};
+
+/// Sends requests and returns their responses
+#[async_trait]
+pub trait Transport: Send + Sync + 'static {
+    async fn execute(&self, request: Request) -> Result<Response>;
+}
+
+#[async_trait]
+impl Transport for HttpClient {
+    async fn execute(&self, request: Request) -> Result<Response> {
+        HttpClient::execute(self, request)
+            .await
+            .map_err(Error::HttpError)
+    }
+}
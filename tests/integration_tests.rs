@@ -6,6 +6,7 @@
 //! - OANDA_PRACTICE=true (recommended)
 
 use oanda_connector::{OandaClient, OandaConfig, Granularity};
+use rust_decimal::Decimal;
 use std::time::Duration;
 
 fn get_test_client() -> OandaClient {
@@ -47,10 +48,10 @@ async fn test_get_current_price() {
         .expect("Failed to get current price");
     
     assert_eq!(tick.instrument, "EUR_USD");
-    assert!(tick.bid > 0.0);
-    assert!(tick.ask > 0.0);
+    assert!(tick.bid > Decimal::ZERO);
+    assert!(tick.ask > Decimal::ZERO);
     assert!(tick.ask > tick.bid, "Ask should be greater than bid");
-    assert!(tick.spread() > 0.0);
+    assert!(tick.spread() > Decimal::ZERO);
     
     println!("EUR/USD: bid={}, ask={}, spread={}", tick.bid, tick.ask, tick.spread());
 }
@@ -73,8 +74,8 @@ async fn test_get_multiple_prices() {
     
     for tick in &ticks {
         assert!(instruments.contains(&tick.instrument));
-        assert!(tick.bid > 0.0);
-        assert!(tick.ask > 0.0);
+        assert!(tick.bid > Decimal::ZERO);
+        assert!(tick.ask > Decimal::ZERO);
     }
 }
 
@@ -90,10 +91,10 @@ async fn test_get_candles() {
     
     for candle in &candles {
         assert_eq!(candle.instrument, "EUR_USD");
-        assert!(candle.open > 0.0);
+        assert!(candle.open > Decimal::ZERO);
         assert!(candle.high >= candle.open);
         assert!(candle.low <= candle.open);
-        assert!(candle.close > 0.0);
+        assert!(candle.close > Decimal::ZERO);
         assert!(candle.volume >= 0);
     }
     
@@ -1,7 +1,10 @@
 //! Mock server tests (no real API calls needed)
 
-use oanda_connector::{OandaClient, OandaConfig};
+use oanda_connector::{DatetimeFormat, Granularity, OandaClient, OandaConfig, OrderEvent, OrderManager, OrderRejectReason, OrderRequest, Portfolio, PortfolioSnapshot, Transaction, TransactionId};
+use oanda_connector::order_manager::OrderStatus;
+use rust_decimal_macros::dec;
 use mockito::{Server, Matcher};
+use std::time::Duration;
 
 async fn create_mock_client(server: &Server) -> OandaClient {
     let mut config = OandaConfig::new(
@@ -10,6 +13,7 @@ async fn create_mock_client(server: &Server) -> OandaClient {
         true,
     );
     config.base_url = Some(server.url());
+    config.stream_url = Some(server.url());
     config.enable_retries = false; // Disable retries for faster tests
     
     OandaClient::new(config).unwrap()
@@ -38,8 +42,42 @@ async fn test_mock_current_price() {
     let tick = client.get_current_price("EUR_USD").await.unwrap();
     
     assert_eq!(tick.instrument, "EUR_USD");
-    assert_eq!(tick.bid, 1.10000);
-    assert_eq!(tick.ask, 1.10020);
+    assert_eq!(tick.bid, dec!(1.10000));
+    assert_eq!(tick.ask, dec!(1.10020));
+    
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_mock_stream_prices() {
+    use futures::TryStreamExt;
+    
+    let mut server = Server::new_async().await;
+    
+    let mock = server.mock("GET", "/v3/accounts/test_account_id/pricing/stream")
+        .match_query(Matcher::UrlEncoded("instruments".into(), "EUR_USD,USD_JPY".into()))
+        .with_status(200)
+        .with_body(concat!(
+            r#"{"type":"PRICE","instrument":"EUR_USD","time":"2024-01-01T12:00:00.000000000Z","bids":[{"price":"1.10000"}],"asks":[{"price":"1.10020"}]}"#, "\n",
+            r#"{"type":"HEARTBEAT","time":"2024-01-01T12:00:05.000000000Z"}"#, "\n",
+            r#"{"type":"PRICE","instrument":"USD_JPY","time":"2024-01-01T12:00:06.000000000Z","bids":[{"price":"150.010"}],"asks":[{"price":"150.025"}],"tradeable":false}"#, "\n",
+        ))
+        .create_async()
+        .await;
+    
+    let client = create_mock_client(&server).await;
+    let instruments = vec!["EUR_USD".to_string(), "USD_JPY".to_string()];
+    let ticks: Vec<_> = client.stream_prices(&instruments).await.unwrap()
+        .try_collect()
+        .await
+        .unwrap();
+    
+    assert_eq!(ticks.len(), 2);
+    assert_eq!(ticks[0].ask, dec!(1.10020));
+    assert_eq!(ticks[1].instrument, "USD_JPY");
+    assert!(!ticks[1].tradeable);
+    // The stream slot is released once the stream ends
+    assert!(client.try_acquire_stream_slot().is_some());
     
     mock.assert_async().await;
 }
@@ -58,11 +96,58 @@ async fn test_mock_authentication_error() {
     let result = client.get_current_price("EUR_USD").await;
     
     assert!(result.is_err());
-    assert!(matches!(result.unwrap_err(), oanda_connector::Error::AuthenticationFailed));
+    assert!(matches!(result.unwrap_err().into_inner(), oanda_connector::Error::AuthenticationFailed));
     
     mock.assert_async().await;
 }
 
+#[tokio::test]
+async fn test_mock_server_errors_keep_body_and_request_id() {
+    let mut server = Server::new_async().await;
+
+    let internal = server
+        .mock("GET", "/v3/accounts/test_account_id")
+        .with_status(500)
+        .with_header("RequestID", "42")
+        .with_body(r#"{"errorMessage":"Account cache is rebuilding"}"#)
+        .create_async()
+        .await;
+    let unavailable = server
+        .mock("GET", "/v3/accounts/test_account_id/pricing")
+        .match_query(Matcher::Any)
+        .with_status(503)
+        .with_header("RequestID", "43")
+        .create_async()
+        .await;
+
+    let client = create_mock_client(&server).await;
+
+    let error = client.get_account_summary().await.unwrap_err();
+    assert_eq!(error.request_id(), Some("42"));
+    assert!(error.is_retryable());
+    match error.into_inner() {
+        oanda_connector::Error::ApiError { code, message, .. } => {
+            assert_eq!(code, 500);
+            assert_eq!(message, "Account cache is rebuilding");
+        }
+        other => panic!("expected ApiError, got {:?}", other),
+    }
+
+    // An empty body falls back to the status text
+    let error = client.get_current_price("EUR_USD").await.unwrap_err();
+    assert_eq!(error.request_id(), Some("43"));
+    match error.into_inner() {
+        oanda_connector::Error::ApiError { code, message, .. } => {
+            assert_eq!(code, 503);
+            assert_eq!(message, "Service Unavailable");
+        }
+        other => panic!("expected ApiError, got {:?}", other),
+    }
+
+    internal.assert_async().await;
+    unavailable.assert_async().await;
+}
+
 #[tokio::test]
 async fn test_mock_rate_limit() {
     let mut server = Server::new_async().await;
@@ -77,7 +162,7 @@ async fn test_mock_rate_limit() {
     let result = client.get_current_price("EUR_USD").await;
     
     assert!(result.is_err());
-    match result.unwrap_err() {
+    match result.unwrap_err().into_inner() {
         oanda_connector::Error::RateLimitExceeded { retry_after_seconds } => {
             assert_eq!(retry_after_seconds, 60);
         }
@@ -120,8 +205,2319 @@ async fn test_mock_candles() {
     ).await.unwrap();
     
     assert_eq!(candles.len(), 1);
-    assert_eq!(candles[0].open, 1.10000);
-    assert_eq!(candles[0].close, 1.10020);
+    assert_eq!(candles[0].open, dec!(1.10000));
+    assert_eq!(candles[0].close, dec!(1.10020));
+    
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_file_store_backfills_once() {
+    use chrono::{TimeZone, Utc};
+    use oanda_connector::{DataStore, FileStore};
+    
+    let mut server = Server::new_async().await;
+    
+    let mock = server.mock("GET", "/v3/instruments/EUR_USD/candles")
+        .match_query(Matcher::AllOf(vec![
+            Matcher::UrlEncoded("granularity".into(), "H1".into()),
+            Matcher::UrlEncoded("from".into(), "2024-01-01T00:00:00Z".into()),
+            Matcher::UrlEncoded("to".into(), "2024-01-01T03:00:00Z".into()),
+        ]))
+        .with_status(200)
+        .with_body(r#"{
+            "instrument": "EUR_USD",
+            "granularity": "H1",
+            "candles": [
+                {"time": "2024-01-01T00:00:00.000000000Z", "volume": 10, "complete": true,
+                 "mid": {"o": "1.1000", "h": "1.1010", "l": "1.0990", "c": "1.1005"}},
+                {"time": "2024-01-01T01:00:00.000000000Z", "volume": 12, "complete": true,
+                 "mid": {"o": "1.1005", "h": "1.1020", "l": "1.1000", "c": "1.1015"}},
+                {"time": "2024-01-01T03:00:00.000000000Z", "volume": 9, "complete": true,
+                 "mid": {"o": "1.1015", "h": "1.1030", "l": "1.1010", "c": "1.1025"}}
+            ]
+        }"#)
+        .expect(1)
+        .create_async()
+        .await;
+    
+    let dir = std::env::temp_dir().join(format!("oanda-file-store-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let store = FileStore::new(create_mock_client(&server).await, &dir);
+    let from = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+    let to = Utc.with_ymd_and_hms(2024, 1, 1, 3, 0, 0).unwrap();
+    
+    let fetched = store.query_candles("EUR_USD", Granularity::H1, from..to).await.unwrap();
+    // The 03:00 candle is past the end of the range
+    assert_eq!(fetched.len(), 2);
+    assert_eq!(fetched[1].close, dec!(1.1015));
+    
+    // Served from disk, including a sub-range
+    let again = store.query_candles("EUR_USD", Granularity::H1, from..to).await.unwrap();
+    assert_eq!(again, fetched);
+    let later = store.query_candles("EUR_USD", Granularity::H1, fetched[1].timestamp..to).await.unwrap();
+    assert_eq!(later.len(), 1);
+    
+    mock.assert_async().await;
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_mock_transactions_since() {
+    let mut server = Server::new_async().await;
+    
+    let mock = server.mock("GET", "/v3/accounts/test_account_id/transactions/sinceid")
+        .match_query(Matcher::UrlEncoded("id".into(), "6400".into()))
+        .with_status(200)
+        .with_body(r#"{
+            "transactions": [{
+                "id": "6401",
+                "time": "2024-01-01T12:00:00.000000000Z",
+                "accountID": "test_account_id",
+                "batchID": "6401",
+                "type": "ORDER_FILL",
+                "orderID": "6400",
+                "instrument": "EUR_USD",
+                "units": "1000",
+                "pl": "0.0000"
+            }, {
+                "id": "6402",
+                "time": "2024-01-01T21:00:00.000000000Z",
+                "accountID": "test_account_id",
+                "batchID": "6402",
+                "type": "DAILY_FINANCING",
+                "financing": "-0.1234"
+            }],
+            "lastTransactionID": "6402"
+        }"#)
+        .create_async()
+        .await;
+    
+    let client = create_mock_client(&server).await;
+    let transactions = client.get_transactions_since(&TransactionId::from(6400)).await.unwrap();
+    
+    assert_eq!(transactions.len(), 2);
+    assert_eq!(transactions[0].id(), Some("6401"));
+    match &transactions[0] {
+        Transaction::OrderFill(fill) => assert_eq!(fill.units, dec!(1000)),
+        other => panic!("Expected OrderFill, got {:?}", other),
+    }
+    match &transactions[1] {
+        Transaction::DailyFinancing(financing) => assert_eq!(financing.financing, dec!(-0.1234)),
+        other => panic!("Expected DailyFinancing, got {:?}", other),
+    }
+    
+    mock.assert_async().await;
+}
+
+/// Summary, open trades and open positions for one EUR_USD trade, each
+/// expected `hits` times
+async fn mock_portfolio(server: &mut Server, hits: usize) -> Vec<mockito::Mock> {
+    let summary = server
+        .mock("GET", "/v3/accounts/test_account_id")
+        .with_status(200)
+        .expect(hits)
+        .with_body(
+            r#"{
+            "account": {
+                "id": "test_account_id",
+                "balance": "100000.0000",
+                "NAV": "100012.5000",
+                "unrealizedPL": "12.5000",
+                "pl": "0.0000",
+                "marginUsed": "220.0000",
+                "marginAvailable": "99792.5000",
+                "openTradeCount": 1,
+                "openPositionCount": 1,
+                "currency": "USD"
+            },
+            "lastTransactionID": "7001"
+        }"#)
+        .create_async()
+        .await;
+    let trades = server.mock("GET", "/v3/accounts/test_account_id/openTrades")
+        .with_status(200)
+        .expect(hits)
+        .with_body(
+            r#"{
+            "trades": [{
+                "id": "7000",
+                "instrument": "EUR_USD",
+                "price": "1.10000",
+                "openTime": "2024-01-02T10:00:00.000000000Z",
+                "state": "OPEN",
+                "initialUnits": "10000",
+                "currentUnits": "10000",
+                "realizedPL": "0.0000",
+                "unrealizedPL": "12.5000",
+                "marginUsed": "220.0000"
+            }],
+            "lastTransactionID": "7001"
+        }"#)
+        .create_async()
+        .await;
+    let positions = server.mock("GET", "/v3/accounts/test_account_id/openPositions")
+        .with_status(200)
+        .expect(hits)
+        .with_body(
+            r#"{
+            "positions": [{
+                "instrument": "EUR_USD",
+                "pl": "0.0000",
+                "unrealizedPL": "12.5000",
+                "marginUsed": "220.0000",
+                "long": {
+                    "units": "10000",
+                    "averagePrice": "1.10000",
+                    "tradeIDs": ["7000"],
+                    "pl": "0.0000",
+                    "unrealizedPL": "12.5000"
+                },
+                "short": {
+                    "units": "0",
+                    "pl": "0.0000",
+                    "unrealizedPL": "0.0000"
+                }
+            }],
+            "lastTransactionID": "7001"
+        }"#)
+        .create_async()
+        .await;
+
+    vec![summary, trades, positions]
+}
+
+#[tokio::test]
+async fn test_mock_portfolio_snapshot() {
+    let mut server = Server::new_async().await;
+    let mocks = mock_portfolio(&mut server, 1).await;
+
+    let client = create_mock_client(&server).await;
+    let snapshot = PortfolioSnapshot::fetch(&client).await.unwrap();
+    
+    assert_eq!(snapshot.summary.nav, dec!(100012.5));
+    assert_eq!(snapshot.trades[0].current_units, dec!(10000));
+    let exposure = snapshot.exposure("EUR_USD").unwrap();
+    assert_eq!(exposure.net_units, dec!(10000));
+    assert_eq!(exposure.trade_count, 1);
+    assert_eq!(snapshot.currency_exposure("USD"), dec!(-11000));
+    assert_eq!(
+        snapshot.last_transaction_id,
+        Some(TransactionId::from(7001))
+    );
+
+    for mock in mocks {
+        mock.assert_async().await;
+    }
+}
+
+#[tokio::test]
+async fn test_mock_portfolio_refreshes_on_transactions() {
+    let mut server = Server::new_async().await;
+    let mocks = mock_portfolio(&mut server, 2).await;
+    let fill = server
+        .mock("GET", "/v3/accounts/test_account_id/transactions/sinceid")
+        .match_query(Matcher::UrlEncoded("id".into(), "7001".into()))
+        .with_status(200)
+        .with_body(
+            r#"{
+            "transactions": [{
+                "id": "7002",
+                "time": "2024-01-02T10:05:00.000000000Z",
+                "accountID": "test_account_id",
+                "batchID": "7002",
+                "type": "DAILY_FINANCING",
+                "financing": "-0.1234"
+            }],
+            "lastTransactionID": "7002"
+        }"#,
+        )
+        .expect(1)
+        .create_async()
+        .await;
+    let quiet = server
+        .mock("GET", "/v3/accounts/test_account_id/transactions/sinceid")
+        .match_query(Matcher::UrlEncoded("id".into(), "7002".into()))
+        .with_status(200)
+        .with_body(r#"{"transactions": [], "lastTransactionID": "7002"}"#)
+        .expect_at_least(2)
+        .create_async()
+        .await;
+
+    let client = create_mock_client(&server).await;
+    let portfolio = Portfolio::spawn_on_transactions(client, Duration::from_millis(20));
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    assert!(portfolio.snapshot().is_some());
+    assert_eq!(portfolio.last_error(), None);
+    portfolio.stop().await;
+
+    // Fetched at start and after the financing, then polled from 7002 without
+    // refetching
+    for mock in mocks {
+        mock.assert_async().await;
+    }
+    fill.assert_async().await;
+    quiet.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_mock_transactions_since_last() {
+    let mut server = Server::new_async().await;
+    
+    let summary = server.mock("GET", "/v3/accounts/test_account_id")
+        .with_status(200)
+        .with_body(r#"{
+            "account": {
+                "id": "test_account_id",
+                "balance": "100000.0000",
+                "NAV": "100000.0000",
+                "unrealizedPL": "0.0000",
+                "pl": "0.0000",
+                "marginUsed": "0.0000",
+                "marginAvailable": "100000.0000",
+                "openTradeCount": 0,
+                "openPositionCount": 0,
+                "currency": "USD"
+            },
+            "lastTransactionID": "6400"
+        }"#)
+        .expect(1)
+        .create_async()
+        .await;
+    let since = server.mock("GET", "/v3/accounts/test_account_id/transactions/sinceid")
+        .match_query(Matcher::UrlEncoded("id".into(), "6400".into()))
+        .with_status(200)
+        .with_body(r#"{
+            "transactions": [{
+                "id": "6401",
+                "time": "2024-01-01T21:00:00.000000000Z",
+                "accountID": "test_account_id",
+                "batchID": "6401",
+                "type": "DAILY_FINANCING",
+                "financing": "-0.1234"
+            }],
+            "lastTransactionID": "6401"
+        }"#)
+        .expect(1)
+        .create_async()
+        .await;
+    let caught_up = server.mock("GET", "/v3/accounts/test_account_id/transactions/sinceid")
+        .match_query(Matcher::UrlEncoded("id".into(), "6401".into()))
+        .with_status(200)
+        .with_body(r#"{"transactions": [], "lastTransactionID": "6401"}"#)
+        .expect(1)
+        .create_async()
+        .await;
+    
+    let client = create_mock_client(&server).await;
+    assert_eq!(client.last_transaction_id(), None);
+    
+    assert!(client.get_transactions_since_last().await.unwrap().is_empty());
+    assert_eq!(client.last_transaction_id(), Some(TransactionId::from(6400)));
+    
+    let transactions = client.get_transactions_since_last().await.unwrap();
+    assert_eq!(transactions.len(), 1);
+    assert_eq!(client.last_transaction_id(), Some(TransactionId::from(6401)));
+    
+    assert!(client.get_transactions_since_last().await.unwrap().is_empty());
+    
+    summary.assert_async().await;
+    since.assert_async().await;
+    caught_up.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_mock_stream_transaction_history() {
+    use futures::TryStreamExt;
+    
+    let mut server = Server::new_async().await;
+    
+    let first_page = server.mock("GET", "/v3/accounts/test_account_id/transactions/idrange")
+        .match_query(Matcher::AllOf(vec![
+            Matcher::UrlEncoded("from".into(), "1".into()),
+            Matcher::UrlEncoded("to".into(), "1000".into()),
+        ]))
+        .with_status(200)
+        .with_body(r#"{
+            "transactions": [{
+                "id": "1",
+                "time": "2024-01-01T12:00:00.000000000Z",
+                "accountID": "test_account_id",
+                "batchID": "1",
+                "type": "CREATE"
+            }],
+            "lastTransactionID": "1500"
+        }"#)
+        .create_async()
+        .await;
+    
+    let second_page = server.mock("GET", "/v3/accounts/test_account_id/transactions/idrange")
+        .match_query(Matcher::AllOf(vec![
+            Matcher::UrlEncoded("from".into(), "1001".into()),
+            Matcher::UrlEncoded("to".into(), "1500".into()),
+        ]))
+        .with_status(200)
+        .with_body(r#"{
+            "transactions": [{
+                "id": "1001",
+                "time": "2024-01-02T12:00:00.000000000Z",
+                "accountID": "test_account_id",
+                "batchID": "1001",
+                "type": "ORDER_FILL"
+            }, {
+                "id": "1500",
+                "time": "2024-01-03T12:00:00.000000000Z",
+                "accountID": "test_account_id",
+                "batchID": "1500",
+                "type": "DAILY_FINANCING"
+            }],
+            "lastTransactionID": "1500"
+        }"#)
+        .create_async()
+        .await;
+    
+    let client = create_mock_client(&server).await;
+    let transactions: Vec<_> = client.stream_transaction_history(&TransactionId::from(1), &TransactionId::from(1500))
+        .try_collect()
+        .await
+        .unwrap();
+    
+    let ids: Vec<_> = transactions.iter().filter_map(|t| t.id()).collect();
+    assert_eq!(ids, vec!["1", "1001", "1500"]);
+    
+    first_page.assert_async().await;
+    second_page.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_mock_transactions_type_filter() {
+    use oanda_connector::transactions::{TransactionFilter, TransactionType};
+    
+    let mut server = Server::new_async().await;
+    
+    let mock = server.mock("GET", "/v3/accounts/test_account_id/transactions/sinceid")
+        .match_query(Matcher::AllOf(vec![
+            Matcher::UrlEncoded("id".into(), "6400".into()),
+            Matcher::UrlEncoded("type".into(), "ORDER_FILL,DAILY_FINANCING".into()),
+        ]))
+        .with_status(200)
+        .with_body(r#"{
+            "transactions": [{
+                "id": "6401",
+                "time": "2024-01-01T21:00:00.000000000Z",
+                "accountID": "test_account_id",
+                "batchID": "6401",
+                "type": "DAILY_FINANCING",
+                "financing": "-0.1234"
+            }, {
+                "id": "6402",
+                "time": "2024-01-01T21:00:01.000000000Z",
+                "accountID": "test_account_id",
+                "batchID": "6402",
+                "type": "CLIENT_CONFIGURE",
+                "alias": "ignored by local filter"
+            }],
+            "lastTransactionID": "6402"
+        }"#)
+        .create_async()
+        .await;
+    
+    let filter = TransactionFilter::new()
+        .with_type(TransactionType::OrderFill)
+        .with_type(TransactionType::DailyFinancing);
+    
+    let client = create_mock_client(&server).await;
+    let transactions = client.get_transactions_since_filtered(&TransactionId::from(6400), &filter).await.unwrap();
+    
+    assert_eq!(transactions.len(), 1);
+    assert_eq!(transactions[0].type_name(), "DAILY_FINANCING");
+    
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_mock_stream_transactions_between() {
+    use futures::TryStreamExt;
+    use oanda_connector::transactions::TransactionFilter;
+    
+    let mut server = Server::new_async().await;
+    
+    let pages_body = format!(r#"{{
+        "from": "2024-01-01T00:00:00.000000000Z",
+        "to": "2024-01-31T00:00:00.000000000Z",
+        "pageSize": 1000,
+        "count": 2,
+        "pages": ["{}/v3/accounts/test_account_id/transactions/idrange?from=10&to=11"],
+        "lastTransactionID": "11"
+    }}"#, server.url());
+    
+    let pages = server.mock("GET", "/v3/accounts/test_account_id/transactions")
+        .match_query(Matcher::AllOf(vec![
+            Matcher::UrlEncoded("from".into(), "2024-01-01T00:00:00Z".into()),
+            Matcher::UrlEncoded("to".into(), "2024-01-31T00:00:00Z".into()),
+        ]))
+        .with_status(200)
+        .with_body(pages_body)
+        .create_async()
+        .await;
+    
+    let page = server.mock("GET", "/v3/accounts/test_account_id/transactions/idrange")
+        .match_query(Matcher::AllOf(vec![
+            Matcher::UrlEncoded("from".into(), "10".into()),
+            Matcher::UrlEncoded("to".into(), "11".into()),
+        ]))
+        .with_status(200)
+        .with_body(r#"{
+            "transactions": [{
+                "id": "10",
+                "time": "2024-01-10T21:00:00.000000000Z",
+                "accountID": "test_account_id",
+                "batchID": "10",
+                "type": "DAILY_FINANCING",
+                "financing": "-0.5"
+            }, {
+                "id": "11",
+                "time": "2024-01-11T21:00:00.000000000Z",
+                "accountID": "test_account_id",
+                "batchID": "11",
+                "type": "DAILY_FINANCING",
+                "financing": "-0.5"
+            }],
+            "lastTransactionID": "11"
+        }"#)
+        .create_async()
+        .await;
+    
+    let client = create_mock_client(&server).await;
+    let transactions: Vec<_> = client
+        .stream_transactions_between(
+            "2024-01-01T00:00:00Z".parse().unwrap(),
+            "2024-01-31T00:00:00Z".parse().unwrap(),
+            TransactionFilter::new(),
+        )
+        .try_collect()
+        .await
+        .unwrap();
+    
+    assert_eq!(transactions.len(), 2);
+    assert_eq!(transactions[1].id(), Some("11"));
+    
+    pages.assert_async().await;
+    page.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_mock_malformed_transaction_pages() {
+    use futures::TryStreamExt;
+    use oanda_connector::transactions::TransactionFilter;
+    
+    let mut server = Server::new_async().await;
+    
+    let pages = server.mock("GET", "/v3/accounts/test_account_id/transactions")
+        .match_query(Matcher::Any)
+        .with_status(200)
+        .with_body(r#"{
+            "pageSize": 1000,
+            "count": 2,
+            "pages": ["/v3/accounts/test_account_id/transactions/idrange?from=10"],
+            "lastTransactionID": "11"
+        }"#)
+        .create_async()
+        .await;
+    
+    let client = create_mock_client(&server).await;
+    let result: oanda_connector::Result<Vec<_>> = client
+        .stream_transactions_between(
+            "2024-01-01T00:00:00Z".parse().unwrap(),
+            "2024-01-31T00:00:00Z".parse().unwrap(),
+            TransactionFilter::new(),
+        )
+        .try_collect()
+        .await;
+    
+    assert!(matches!(result.map_err(oanda_connector::Error::into_inner), Err(oanda_connector::Error::SchemaMismatch(_))));
+    
+    pages.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_mock_quote_depth() {
+    let mut server = Server::new_async().await;
+    
+    let mock = server.mock("GET", "/v3/accounts/test_account_id/pricing")
+        .match_query(Matcher::UrlEncoded("instruments".into(), "EUR_USD".into()))
+        .with_status(200)
+        .with_body(r#"{
+            "prices": [{
+                "instrument": "EUR_USD",
+                "time": "2024-01-01T12:00:00.000000000Z",
+                "bids": [
+                    {"price": "1.10000", "liquidity": 1000000},
+                    {"price": "1.09990", "liquidity": 5000000}
+                ],
+                "asks": [
+                    {"price": "1.10020", "liquidity": 1000000},
+                    {"price": "1.10030", "liquidity": 5000000}
+                ]
+            }]
+        }"#)
+        .create_async()
+        .await;
+    
+    let client = create_mock_client(&server).await;
+    let quote = client.get_quote("EUR_USD").await.unwrap();
+    
+    assert_eq!(quote.bids.len(), 2);
+    assert_eq!(quote.asks[1].price, dec!(1.10030));
+    assert_eq!(quote.asks[1].liquidity, Some(5000000));
+    assert_eq!(quote.best_bid().unwrap().price, dec!(1.10000));
+    
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_mock_order_book() {
+    let mut server = Server::new_async().await;
+    
+    let mock = server.mock("GET", "/v3/instruments/EUR_USD/orderBook")
+        .match_query(Matcher::UrlEncoded("time".into(), "2024-01-01T12:00:00Z".into()))
+        .with_status(200)
+        .with_body(r#"{
+            "orderBook": {
+                "instrument": "EUR_USD",
+                "time": "2024-01-01T12:00:00Z",
+                "unixTime": "1704110400",
+                "price": "1.10010",
+                "bucketWidth": "0.00050",
+                "buckets": [
+                    {"price": "1.09950", "longCountPercent": "0.4123", "shortCountPercent": "0.1021"},
+                    {"price": "1.10000", "longCountPercent": "0.2210", "shortCountPercent": "0.3300"}
+                ]
+            }
+        }"#)
+        .create_async()
+        .await;
+    
+    let client = create_mock_client(&server).await;
+    let book = client
+        .get_order_book("EUR_USD", Some("2024-01-01T12:00:00Z".parse().unwrap()))
+        .await
+        .unwrap();
+    
+    assert_eq!(book.instrument, "EUR_USD");
+    assert_eq!(book.price, dec!(1.10010));
+    assert_eq!(book.buckets.len(), 2);
+    assert_eq!(book.buckets[0].long_count_percent, 0.4123);
+    assert_eq!(book.buckets[1].short_count_percent, 0.33);
     
     mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_mock_position_book() {
+    let mut server = Server::new_async().await;
+    
+    let mock = server.mock("GET", "/v3/instruments/USD_JPY/positionBook")
+        .with_status(200)
+        .with_body(r#"{
+            "positionBook": {
+                "instrument": "USD_JPY",
+                "time": "2024-01-01T12:00:00Z",
+                "price": "145.100",
+                "bucketWidth": "0.050",
+                "buckets": [
+                    {"price": "145.000", "longCountPercent": "0.30", "shortCountPercent": "0.10"}
+                ]
+            }
+        }"#)
+        .create_async()
+        .await;
+    
+    let client = create_mock_client(&server).await;
+    let book = client.get_position_book("USD_JPY", None).await.unwrap();
+    
+    assert_eq!(book.instrument, "USD_JPY");
+    assert_eq!(book.bucket_width, dec!(0.05));
+    assert_eq!(book.buckets[0].long_count_percent, 0.30);
+    
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_mock_pricing_since_and_home_conversions() {
+    use oanda_connector::models::PricingOptions;
+    
+    let mut server = Server::new_async().await;
+    
+    let mock = server.mock("GET", "/v3/accounts/test_account_id/pricing")
+        .match_query(Matcher::AllOf(vec![
+            Matcher::UrlEncoded("instruments".into(), "USD_JPY".into()),
+            Matcher::UrlEncoded("since".into(), "2024-01-01T12:00:00.000000000Z".into()),
+            Matcher::UrlEncoded("includeHomeConversions".into(), "true".into()),
+        ]))
+        .with_status(200)
+        .with_body(r#"{
+            "prices": [{
+                "instrument": "USD_JPY",
+                "time": "2024-01-01T12:00:01.000000000Z",
+                "bids": [{"price": "145.100"}],
+                "asks": [{"price": "145.120"}]
+            }],
+            "homeConversions": [{
+                "currency": "JPY",
+                "accountGain": "0.00689",
+                "accountLoss": "0.00690",
+                "positionValue": "0.006895"
+            }],
+            "time": "2024-01-01T12:00:02.000000000Z"
+        }"#)
+        .create_async()
+        .await;
+    
+    let options = PricingOptions {
+        since: Some("2024-01-01T12:00:00Z".parse().unwrap()),
+        include_home_conversions: true,
+    };
+    
+    let client = create_mock_client(&server).await;
+    let snapshot = client.get_pricing(&["USD_JPY".to_string()], &options).await.unwrap();
+    
+    assert_eq!(snapshot.prices.len(), 1);
+    assert_eq!(snapshot.prices[0].bid, dec!(145.100));
+    assert_eq!(snapshot.home_conversion("JPY").unwrap().account_loss, dec!(0.00690));
+    assert_eq!(snapshot.time, Some("2024-01-01T12:00:02Z".parse().unwrap()));
+    
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_mock_measure_latency() {
+    let mut server = Server::new_async().await;
+    
+    let mock = server.mock("GET", "/v3/accounts/test_account_id/pricing")
+        .match_query(Matcher::UrlEncoded("instruments".into(), "EUR_USD".into()))
+        .with_status(200)
+        .with_body(r#"{
+            "prices": [],
+            "time": "2024-01-01T12:00:00.000000000Z"
+        }"#)
+        .expect(3)
+        .create_async()
+        .await;
+    
+    let client = create_mock_client(&server).await;
+    let report = client.measure_latency(3).await.unwrap();
+    
+    assert_eq!(report.probes, 3);
+    assert!(report.min_rtt <= report.median_rtt && report.median_rtt <= report.max_rtt);
+    // The mock's clock is stuck in 2024
+    assert!(report.clock_skew.unwrap() < -chrono::Duration::days(1));
+    
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_mock_price_not_tradeable() {
+    let mut server = Server::new_async().await;
+    
+    let mock = server.mock("GET", "/v3/accounts/test_account_id/pricing")
+        .match_query(Matcher::Any)
+        .with_status(200)
+        .with_body(r#"{
+            "prices": [{
+                "instrument": "EUR_USD",
+                "time": "2024-01-06T12:00:00.000000000Z",
+                "bids": [{"price": "1.10000"}],
+                "asks": [{"price": "1.10020"}],
+                "tradeable": false,
+                "status": "non-tradeable"
+            }]
+        }"#)
+        .create_async()
+        .await;
+    
+    let client = create_mock_client(&server).await;
+    let tick = client.get_current_price("EUR_USD").await.unwrap();
+    
+    assert!(!tick.tradeable);
+    assert_eq!(tick.status, oanda_connector::PriceStatus::NonTradeable);
+    
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_mock_closeout_prices() {
+    let mut server = Server::new_async().await;
+    
+    let mock = server.mock("GET", "/v3/accounts/test_account_id/pricing")
+        .match_query(Matcher::Any)
+        .with_status(200)
+        .with_body(r#"{
+            "prices": [{
+                "instrument": "EUR_USD",
+                "time": "2024-01-06T12:00:00.000000000Z",
+                "bids": [{"price": "1.10000", "liquidity": 1000000}],
+                "asks": [{"price": "1.10020", "liquidity": 1000000}],
+                "closeoutBid": "1.09985",
+                "closeoutAsk": "1.10035"
+            }]
+        }"#)
+        .create_async()
+        .await;
+    
+    let client = create_mock_client(&server).await;
+    let quote = client.get_quote("EUR_USD").await.unwrap();
+    
+    assert_eq!(quote.closeout_bid, Some(dec!(1.09985)));
+    assert_eq!(quote.closeout_ask, Some(dec!(1.10035)));
+    
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_mock_spread_monitor_sample() {
+    let mut server = Server::new_async().await;
+    
+    let mock = server.mock("GET", "/v3/accounts/test_account_id/pricing")
+        .match_query(Matcher::UrlEncoded("instruments".into(), "EUR_USD,USD_JPY".into()))
+        .with_status(200)
+        .with_body(r#"{
+            "prices": [
+                {
+                    "instrument": "EUR_USD",
+                    "time": "2024-01-06T12:00:00.000000000Z",
+                    "bids": [{"price": "1.10000"}],
+                    "asks": [{"price": "1.10020"}]
+                },
+                {
+                    "instrument": "USD_JPY",
+                    "time": "2024-01-06T12:00:00.000000000Z",
+                    "bids": [{"price": "150.000"}],
+                    "asks": [{"price": "150.020"}],
+                    "tradeable": false,
+                    "status": "non-tradeable"
+                }
+            ]
+        }"#)
+        .expect(2)
+        .create_async()
+        .await;
+    
+    let client = create_mock_client(&server).await;
+    let monitor = oanda_connector::SpreadMonitor::new(10);
+    let instruments = vec!["EUR_USD".to_string(), "USD_JPY".to_string()];
+    
+    monitor.sample(&client, &instruments).await.unwrap();
+    monitor.sample(&client, &instruments).await.unwrap();
+    
+    let stats = monitor.stats("EUR_USD").unwrap();
+    assert_eq!(stats.samples, 2);
+    assert_eq!(stats.p50, dec!(0.0002));
+    assert!(monitor.stats("USD_JPY").is_none());
+    
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_mock_cross_rate_conversion() {
+    let mut server = Server::new_async().await;
+    
+    let mock = server.mock("GET", "/v3/accounts/test_account_id/pricing")
+        .match_query(Matcher::UrlEncoded("instruments".into(), "EUR_USD,USD_JPY".into()))
+        .with_status(200)
+        .with_body(r#"{
+            "prices": [
+                {
+                    "instrument": "EUR_USD",
+                    "time": "2024-01-06T12:00:00.000000000Z",
+                    "bids": [{"price": "1.09990"}],
+                    "asks": [{"price": "1.10010"}]
+                },
+                {
+                    "instrument": "USD_JPY",
+                    "time": "2024-01-06T12:00:00.000000000Z",
+                    "bids": [{"price": "149.990"}],
+                    "asks": [{"price": "150.010"}]
+                }
+            ]
+        }"#)
+        .expect(2)
+        .create_async()
+        .await;
+    
+    let client = create_mock_client(&server).await;
+    let instruments: Vec<oanda_connector::Instrument> = ["EUR_USD", "USD_JPY"]
+        .iter()
+        .map(|name| oanda_connector::Instrument {
+            name: name.to_string(),
+            display_name: name.replace('_', "/"),
+            pip_location: -4,
+            trade_units_precision: 0,
+            minimum_trade_size: dec!(1.0),
+            maximum_trade_size: dec!(100000000),
+            margin_rate: dec!(0.02),
+            ..Default::default()
+        })
+        .collect();
+    let converter = oanda_connector::CurrencyConverter::with_instruments(client, &instruments);
+    
+    let jpy = converter.convert(dec!(100.0), "EUR", "JPY").await.unwrap();
+    assert_eq!(jpy, dec!(16500));
+    
+    // Same currency needs no pricing call
+    assert_eq!(converter.convert(dec!(42.0), "EUR", "EUR").await.unwrap(), dec!(42.0));
+    
+    // Every rate comes from one pricing call; CHF has no path and is left out
+    let rates = converter.rates(["EUR", "JPY", "USD", "CHF", "EUR"], "USD").await.unwrap();
+    assert_eq!(rates.len(), 3);
+    assert_eq!(rates["EUR"], dec!(1.1));
+    assert_eq!(rates["JPY"], rust_decimal::Decimal::ONE / dec!(150));
+    assert_eq!(rates["USD"], rust_decimal::Decimal::ONE);
+    
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_mock_account_summary() {
+    let mut server = Server::new_async().await;
+    
+    let mock = server.mock("GET", "/v3/accounts/test_account_id")
+        .with_status(200)
+        .with_body(r#"{
+            "account": {
+                "id": "test_account_id",
+                "balance": "100000.0000",
+                "NAV": "100250.5000",
+                "unrealizedPL": "250.5000",
+                "pl": "-120.2500",
+                "marginUsed": "2000.0000",
+                "marginAvailable": "98250.5000",
+                "openTradeCount": 2,
+                "openPositionCount": 1,
+                "currency": "USD",
+                "hedgingEnabled": true
+            },
+            "lastTransactionID": "6410"
+        }"#)
+        .create_async()
+        .await;
+    
+    let client = create_mock_client(&server).await;
+    let summary = client.get_account_summary().await.unwrap();
+    
+    assert_eq!(summary.balance, dec!(100000));
+    assert_eq!(summary.nav, dec!(100250.5));
+    assert_eq!(summary.realized_pl, dec!(-120.25));
+    assert_eq!(summary.open_trade_count, 2);
+    assert_eq!(summary.position_mode(), oanda_connector::PositionMode::Hedging);
+    
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_mock_reconcile_state() {
+    let mut server = Server::new_async().await;
+    
+    let mock = server.mock("GET", "/v3/accounts/test_account_id/changes")
+        .match_query(Matcher::UrlEncoded("sinceTransactionID".into(), "6400".into()))
+        .with_status(200)
+        .with_body(r#"{
+            "changes": {
+                "ordersCreated": [],
+                "ordersCancelled": [{
+                    "id": "6401", "type": "LIMIT", "state": "CANCELLED",
+                    "instrument": "EUR_USD", "units": "1000", "price": "1.05000",
+                    "cancellingTransactionID": "6403"
+                }],
+                "ordersFilled": [{
+                    "id": "6402", "type": "STOP_LOSS", "state": "FILLED",
+                    "fillingTransactionID": "6404"
+                }],
+                "ordersTriggered": [],
+                "tradesOpened": [],
+                "tradesReduced": [],
+                "tradesClosed": [{
+                    "id": "6300", "instrument": "EUR_USD", "price": "1.10000",
+                    "openTime": "2024-01-01T10:00:00.000000000Z", "state": "CLOSED",
+                    "initialUnits": "1000", "currentUnits": "0", "realizedPL": "-5.0000"
+                }],
+                "positions": [],
+                "transactions": [
+                    {
+                        "id": "6403", "accountID": "test_account_id", "batchID": "6403",
+                        "time": "2024-01-01T12:00:00.000000000Z", "type": "ORDER_CANCEL",
+                        "orderID": "6401", "reason": "CLIENT_REQUEST"
+                    },
+                    {
+                        "id": "6404", "accountID": "test_account_id", "batchID": "6404",
+                        "time": "2024-01-01T12:05:00.000000000Z", "type": "ORDER_FILL",
+                        "orderID": "6402", "instrument": "EUR_USD", "units": "-1000",
+                        "price": "1.09500", "reason": "STOP_LOSS_ORDER", "pl": "-5.0000",
+                        "financing": "0.0000", "commission": "0.0000",
+                        "accountBalance": "99995.0000"
+                    }
+                ]
+            },
+            "state": {},
+            "lastTransactionID": "6404"
+        }"#)
+        .create_async()
+        .await;
+    
+    let client = create_mock_client(&server).await;
+    let diff = client.reconcile_state(&TransactionId::from(6400)).await.unwrap();
+    
+    assert!(!diff.is_empty());
+    assert_eq!(diff.fills().count(), 1);
+    assert_eq!(diff.cancelled_order_ids()[0].as_u64(), 6401);
+    assert_eq!(diff.closed_trade_ids()[0].as_u64(), 6300);
+    assert_eq!(diff.trades_closed[0].realized_pl, Some(dec!(-5)));
+    assert_eq!(diff.last_transaction_id, Some(TransactionId::from(6404)));
+    assert_eq!(client.last_transaction_id(), Some(TransactionId::from(6404)));
+    
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_mock_malformed_balance_is_rejected() {
+    let mut server = Server::new_async().await;
+    
+    let mock = server.mock("GET", "/v3/accounts/test_account_id")
+        .with_status(200)
+        .with_body(r#"{
+            "account": {
+                "id": "test_account_id",
+                "balance": "",
+                "NAV": "100250.5000",
+                "unrealizedPL": "250.5000",
+                "pl": "-120.2500",
+                "marginUsed": "2000.0000",
+                "marginAvailable": "98250.5000",
+                "openTradeCount": 2,
+                "openPositionCount": 1,
+                "currency": "USD"
+            }
+        }"#)
+        .create_async()
+        .await;
+    
+    let client = create_mock_client(&server).await;
+    let result = client.get_account_summary().await;
+    
+    match result.map_err(oanda_connector::Error::into_inner) {
+        Err(oanda_connector::Error::ParseError { field, value }) => {
+            assert_eq!(field, "account.balance");
+            assert_eq!(value, "");
+        }
+        other => panic!("Expected ParseError, got {:?}", other),
+    }
+    
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_mock_instruments_metadata() {
+    let mut server = Server::new_async().await;
+    
+    let mock = server.mock("GET", "/v3/accounts/test_account_id/instruments")
+        .with_status(200)
+        .with_body(r#"{
+            "instruments": [{
+                "name": "USD_JPY",
+                "type": "CURRENCY",
+                "displayName": "USD/JPY",
+                "pipLocation": -2,
+                "displayPrecision": 3,
+                "tradeUnitsPrecision": 0,
+                "minimumTradeSize": "1",
+                "maximumTrailingStopDistance": "100.000",
+                "minimumTrailingStopDistance": "0.050",
+                "maximumPositionSize": "0",
+                "maximumOrderUnits": "100000000",
+                "marginRate": "0.04",
+                "guaranteedStopLossOrderMode": "DISABLED",
+                "tags": [{"type": "ASSET_CLASS", "name": "CURRENCY"}],
+                "financing": {
+                    "longRate": "0.0321",
+                    "shortRate": "-0.0562",
+                    "financingDaysOfWeek": [{"dayOfWeek": "WEDNESDAY", "daysCharged": 3}]
+                }
+            }],
+            "lastTransactionID": "6410"
+        }"#)
+        .create_async()
+        .await;
+    
+    let client = create_mock_client(&server).await;
+    let instruments = client.get_instruments().await.unwrap();
+    
+    assert_eq!(instruments.len(), 1);
+    assert_eq!(instruments[0].pip_location, -2);
+    assert_eq!(instruments[0].maximum_position_size, Some(dec!(0)));
+    assert_eq!(instruments[0].financing.as_ref().unwrap().days_charged("WEDNESDAY"), 3);
+    
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_mock_unix_datetime_format() {
+    let mut server = Server::new_async().await;
+    
+    let mock = server.mock("GET", "/v3/instruments/EUR_USD/candles")
+        .match_query(Matcher::Any)
+        .match_header("Accept-Datetime-Format", "UNIX")
+        .with_status(200)
+        .with_body(r#"{
+            "instrument": "EUR_USD",
+            "granularity": "H1",
+            "candles": [{
+                "time": "1704110400.000000000",
+                "volume": 100,
+                "complete": true,
+                "mid": {"o": "1.10000", "h": "1.10050", "l": "1.09950", "c": "1.10020"}
+            }]
+        }"#)
+        .create_async()
+        .await;
+    
+    let mut config = OandaConfig::new(
+        "test_api_key".to_string(),
+        "test_account_id".to_string(),
+        true,
+    );
+    config.base_url = Some(server.url());
+    config.enable_retries = false;
+    config.datetime_format = DatetimeFormat::Unix;
+    let client = OandaClient::new(config).unwrap();
+    
+    let candles = client.get_candles("EUR_USD", Granularity::H1, 1).await.unwrap();
+    assert_eq!(candles[0].timestamp.to_rfc3339(), "2024-01-01T12:00:00+00:00");
+    
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_mock_strict_mode_rejects_unknown_fields() {
+    let mut server = Server::new_async().await;
+    
+    let mock = server.mock("GET", "/v3/accounts/test_account_id")
+        .with_status(200)
+        .with_body(r#"{
+            "account": {
+                "id": "test_account_id",
+                "balance": "100000.0000",
+                "NAV": "100000.0000",
+                "unrealizedPL": "0.0000",
+                "pl": "0.0000",
+                "marginUsed": "0.0000",
+                "marginAvailable": "100000.0000",
+                "openTradeCount": 0,
+                "openPositionCount": 0,
+                "currency": "USD",
+                "guaranteedExecutionFees": "0.0000"
+            }
+        }"#)
+        .expect(2)
+        .create_async()
+        .await;
+    
+    let lenient = create_mock_client(&server).await;
+    assert!(lenient.get_account_summary().await.is_ok());
+    
+    let mut config = OandaConfig::new(
+        "test_api_key".to_string(),
+        "test_account_id".to_string(),
+        true,
+    );
+    config.base_url = Some(server.url());
+    config.enable_retries = false;
+    config.strict_deserialization = true;
+    let strict = OandaClient::new(config).unwrap();
+    
+    match strict.get_account_summary().await.map_err(oanda_connector::Error::into_inner) {
+        Err(oanda_connector::Error::SchemaMismatch(message)) => {
+            assert!(message.contains("account.guaranteedExecutionFees"), "{}", message);
+        }
+        other => panic!("Expected SchemaMismatch, got {:?}", other),
+    }
+    
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_mock_update_credentials() {
+    let mut server = Server::new_async().await;
+    
+    let body = r#"{
+        "prices": [{
+            "instrument": "EUR_USD",
+            "time": "2024-01-01T12:00:00.000000000Z",
+            "bids": [{"price": "1.10000"}],
+            "asks": [{"price": "1.10020"}]
+        }]
+    }"#;
+    let old_key = server.mock("GET", "/v3/accounts/test_account_id/pricing")
+        .match_query(Matcher::Any)
+        .match_header("Authorization", "Bearer test_api_key")
+        .with_status(200)
+        .with_body(body)
+        .create_async()
+        .await;
+    let new_key = server.mock("GET", "/v3/accounts/test_account_id/pricing")
+        .match_query(Matcher::Any)
+        .match_header("Authorization", "Bearer rotated_key")
+        .with_status(200)
+        .with_body(body)
+        .create_async()
+        .await;
+    
+    let client = create_mock_client(&server).await;
+    let clone = client.clone();
+    client.get_current_price("EUR_USD").await.unwrap();
+    
+    client.update_credentials("rotated_key").unwrap();
+    clone.get_current_price("EUR_USD").await.unwrap();
+    assert!(client.update_credentials("").is_err());
+    
+    old_key.assert_async().await;
+    new_key.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_mock_user_agent_and_headers() {
+    use oanda_connector::client::OandaClientBuilder;
+    
+    let mut server = Server::new_async().await;
+    
+    let mock = server.mock("GET", "/v3/accounts/test_account_id/pricing")
+        .match_query(Matcher::Any)
+        .match_header("User-Agent", "my-desk/1.0")
+        .match_header("X-Desk", "fx-1")
+        .with_status(200)
+        .with_body(r#"{
+            "prices": [{
+                "instrument": "EUR_USD",
+                "time": "2024-01-01T12:00:00.000000000Z",
+                "bids": [{"price": "1.10000"}],
+                "asks": [{"price": "1.10020"}]
+            }]
+        }"#)
+        .create_async()
+        .await;
+    
+    let mut config = OandaConfig::new(
+        "test_api_key".to_string(),
+        "test_account_id".to_string(),
+        true,
+    );
+    config.base_url = Some(server.url());
+    let client = OandaClientBuilder::new(config)
+        .user_agent("my-desk/1.0")
+        .header("X-Desk", "fx-1")
+        .build()
+        .unwrap();
+    
+    client.get_current_price("EUR_USD").await.unwrap();
+    
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_structured_api_error() {
+    let mut server = Server::new_async().await;
+    
+    let mock = server.mock("GET", "/v3/accounts/test_account_id/pricing")
+        .match_query(Matcher::Any)
+        .with_status(400)
+        .with_header("content-type", "application/json")
+        .with_header("RequestID", "24813270528651386")
+        .with_body(r#"{"errorCode": "INVALID_INSTRUMENT", "errorMessage": "Invalid value specified for 'instruments'"}"#)
+        .create_async()
+        .await;
+    
+    let client = create_mock_client(&server).await;
+    
+    let result = client.get_current_price("BAD_PAIR").await;
+    let context = result.as_ref().unwrap_err().context().unwrap();
+    assert_eq!(context.path, "/v3/accounts/test_account_id/pricing");
+    assert_eq!(context.instrument.as_deref(), Some("BAD_PAIR"));
+    
+    match result.map_err(oanda_connector::Error::into_inner) {
+        Err(oanda_connector::Error::ApiError { code, message, oanda_error_code, request_id }) => {
+            assert_eq!(code, 400);
+            assert_eq!(request_id.as_deref(), Some("24813270528651386"));
+            assert_eq!(message, "Invalid value specified for 'instruments'");
+            assert_eq!(oanda_error_code.as_deref(), Some("INVALID_INSTRUMENT"));
+        }
+        other => panic!("expected ApiError, got {:?}", other),
+    }
+    
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_timeout_error() {
+    // Accepts connections but never answers
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let mut connections = Vec::new();
+        while let Ok((socket, _)) = listener.accept().await {
+            connections.push(socket);
+        }
+    });
+    
+    let mut config = OandaConfig::new(
+        "test_api_key".to_string(),
+        "test_account_id".to_string(),
+        true,
+    );
+    config.base_url = Some(format!("http://{}", addr));
+    config.enable_retries = false;
+    config.timeouts.pricing_seconds = Some(1);
+    let client = OandaClient::new(config).unwrap();
+    
+    let result = client.get_current_price("EUR_USD").await.map_err(oanda_connector::Error::into_inner);
+    assert!(matches!(result, Err(oanda_connector::Error::Timeout(1))), "{:?}", result);
+}
+
+#[tokio::test]
+async fn test_retry_on_server_error_and_rate_limit() {
+    let mut server = Server::new_async().await;
+    
+    let unavailable = server.mock("GET", "/v3/accounts/test_account_id/pricing")
+        .match_query(Matcher::Any)
+        .with_status(503)
+        .expect(1)
+        .create_async()
+        .await;
+    let rate_limited = server.mock("GET", "/v3/accounts/test_account_id/pricing")
+        .match_query(Matcher::Any)
+        .with_status(429)
+        .with_header("Retry-After", "0")
+        .expect(1)
+        .create_async()
+        .await;
+    let ok = server.mock("GET", "/v3/accounts/test_account_id/pricing")
+        .match_query(Matcher::Any)
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(r#"{
+            "prices": [{
+                "instrument": "EUR_USD",
+                "time": "2024-01-01T12:00:00.000000000Z",
+                "bids": [{"price": "1.10000"}],
+                "asks": [{"price": "1.10020"}]
+            }]
+        }"#)
+        .create_async()
+        .await;
+    
+    let mut config = OandaConfig::new(
+        "test_api_key".to_string(),
+        "test_account_id".to_string(),
+        true,
+    );
+    config.base_url = Some(server.url());
+    let client = OandaClient::new(config).unwrap();
+    
+    let tick = client.get_current_price("EUR_USD").await.unwrap();
+    assert_eq!(tick.bid, dec!(1.10000));
+    
+    // Every attempt counts towards the endpoint's stats
+    let pricing = client.stats()[&oanda_connector::EndpointGroup::Pricing];
+    assert_eq!(pricing.samples, 3);
+    assert!((pricing.error_rate - 2.0 / 3.0).abs() < 1e-9);
+    
+    unavailable.assert_async().await;
+    rate_limited.assert_async().await;
+    ok.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_raw_requests() {
+    let mut server = Server::new_async().await;
+    
+    let changes = server.mock("GET", "/v3/accounts/test_account_id/changes")
+        .match_query(Matcher::UrlEncoded("sinceTransactionID".into(), "6358".into()))
+        .match_header("Authorization", "Bearer test_api_key")
+        .with_status(200)
+        .with_body(r#"{"changes": {}, "lastTransactionID": "6360"}"#)
+        .create_async()
+        .await;
+    let order = server.mock("POST", "/v3/accounts/test_account_id/orders")
+        .match_body(Matcher::Json(serde_json::json!({"order": {"type": "MARKET"}})))
+        .with_status(503)
+        .expect(1)
+        .create_async()
+        .await;
+    
+    let mut config = OandaConfig::new(
+        "test_api_key".to_string(),
+        "test_account_id".to_string(),
+        true,
+    );
+    config.base_url = Some(server.url());
+    let client = OandaClient::new(config).unwrap();
+    
+    let path = format!("/v3/accounts/{}/changes", client.account_id());
+    let body = client.get_raw(&path, &[("sinceTransactionID", "6358")]).await.unwrap();
+    assert_eq!(body["lastTransactionID"], "6360");
+    
+    // POSTs are not retried even with retries enabled
+    let result = client.post_raw("/v3/accounts/test_account_id/orders", &serde_json::json!({"order": {"type": "MARKET"}})).await;
+    assert!(result.is_err());
+    
+    changes.assert_async().await;
+    order.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_response_metadata() {
+    let mut server = Server::new_async().await;
+    
+    let mock = server.mock("GET", "/v3/instruments/EUR_USD/candles")
+        .match_query(Matcher::Any)
+        .with_status(200)
+        .with_header("RequestID", "24998877665544332")
+        .with_body(r#"{"instrument": "EUR_USD", "granularity": "M5", "candles": []}"#)
+        .create_async()
+        .await;
+    
+    let client = create_mock_client(&server).await;
+    let envelope = client
+        .with_metadata(|client| async move { client.get_candles("EUR_USD", Granularity::M5, 10).await })
+        .await
+        .unwrap();
+    
+    assert!(envelope.body.is_empty());
+    assert_eq!(envelope.request_id(), Some("24998877665544332"));
+    assert_eq!(envelope.metadata.unwrap().status, 200);
+    
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_circuit_breaker_fails_fast() {
+    let mut server = Server::new_async().await;
+    
+    let mock = server.mock("GET", "/v3/accounts/test_account_id")
+        .with_status(500)
+        .expect(2)
+        .create_async()
+        .await;
+    
+    let mut config = OandaConfig::new(
+        "test_api_key".to_string(),
+        "test_account_id".to_string(),
+        true,
+    );
+    config.base_url = Some(server.url());
+    config.enable_retries = false;
+    let client = oanda_connector::client::OandaClientBuilder::new(config)
+        .circuit_breaker(2, 60)
+        .build()
+        .unwrap();
+    
+    for _ in 0..2 {
+        let result = client.get_account_summary().await.map_err(oanda_connector::Error::into_inner);
+        assert!(matches!(result, Err(oanda_connector::Error::ApiError { code: 500, .. })));
+    }
+    
+    let result = client.get_account_summary().await.map_err(oanda_connector::Error::into_inner);
+    assert!(matches!(result, Err(oanda_connector::Error::CircuitOpen { .. })), "{:?}", result);
+    
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_place_order() {
+    let mut server = Server::new_async().await;
+    
+    let mock = server.mock("POST", "/v3/accounts/test_account_id/orders")
+        .match_body(Matcher::PartialJsonString(
+            r#"{"order": {"type": "MARKET", "instrument": "EUR_USD", "units": "1000"}}"#.to_string(),
+        ))
+        .with_status(201)
+        .with_header("content-type", "application/json")
+        .with_body(r#"{
+            "orderCreateTransaction": {
+                "id": "6368", "accountID": "test_account_id", "batchID": "6368",
+                "time": "2024-01-01T12:00:00.000000000Z", "type": "MARKET_ORDER",
+                "instrument": "EUR_USD", "units": "1000", "timeInForce": "FOK"
+            },
+            "relatedTransactionIDs": ["6368", "6369"],
+            "lastTransactionID": "6369"
+        }"#)
+        .create_async()
+        .await;
+    
+    let client = create_mock_client(&server).await;
+    let order = OrderRequest::market("EUR_USD", dec!(1000));
+    let response = client.place_order(&order).await.unwrap();
+    
+    assert!(matches!(response.order_create_transaction, Some(Transaction::MarketOrder(_))));
+    assert_eq!(response.related_transaction_ids.len(), 2);
+    assert_eq!(response.last_transaction_id, Some(TransactionId::from(6369)));
+    
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_order_manager_lifecycle() {
+    let mut server = Server::new_async().await;
+    
+    let place = server.mock("POST", "/v3/accounts/test_account_id/orders")
+        .match_body(Matcher::PartialJsonString(
+            r#"{"order": {"type": "LIMIT", "clientExtensions": {"id": "om-test-1"}}}"#.to_string(),
+        ))
+        .with_status(201)
+        .with_body(r#"{
+            "orderCreateTransaction": {
+                "id": "100", "accountID": "test_account_id", "batchID": "100",
+                "time": "2024-01-01T12:00:00.000000000Z", "type": "LIMIT_ORDER",
+                "instrument": "EUR_USD", "units": "1000", "price": "1.08000",
+                "clientExtensions": {"id": "om-test-1"}
+            },
+            "relatedTransactionIDs": ["100"],
+            "lastTransactionID": "100"
+        }"#)
+        .expect(1)
+        .create_async()
+        .await;
+    let replace = server.mock("PUT", "/v3/accounts/test_account_id/orders/100")
+        .match_body(Matcher::PartialJsonString(
+            r#"{"order": {"price": "1.08200", "clientExtensions": {"id": "om-test-1"}}}"#.to_string(),
+        ))
+        .with_status(201)
+        .with_body(r#"{
+            "orderCancelTransaction": {
+                "id": "101", "accountID": "test_account_id", "batchID": "101",
+                "time": "2024-01-01T12:01:00.000000000Z", "type": "ORDER_CANCEL",
+                "orderID": "100", "clientOrderID": "om-test-1",
+                "reason": "CLIENT_REQUEST_REPLACED", "replacedByOrderID": "102"
+            },
+            "orderCreateTransaction": {
+                "id": "102", "accountID": "test_account_id", "batchID": "101",
+                "time": "2024-01-01T12:01:00.000000000Z", "type": "LIMIT_ORDER",
+                "instrument": "EUR_USD", "units": "1000", "price": "1.08200",
+                "replacesOrderID": "100"
+            },
+            "relatedTransactionIDs": ["101", "102"],
+            "lastTransactionID": "102"
+        }"#)
+        .expect(1)
+        .create_async()
+        .await;
+    let fills = server.mock("GET", "/v3/accounts/test_account_id/transactions/sinceid")
+        .match_query(Matcher::UrlEncoded("id".into(), "99".into()))
+        .with_status(200)
+        .with_body(r#"{
+            "transactions": [{
+                "id": "103", "accountID": "test_account_id", "batchID": "103",
+                "time": "2024-01-01T12:05:00.000000000Z", "type": "ORDER_FILL",
+                "orderID": "102", "clientOrderID": "om-test-1",
+                "instrument": "EUR_USD", "units": "1000", "price": "1.08200",
+                "reason": "LIMIT_ORDER", "pl": "0.0000", "financing": "0.0000",
+                "commission": "0.0000", "accountBalance": "100000.0000"
+            }],
+            "lastTransactionID": "103"
+        }"#)
+        .expect(1)
+        .create_async()
+        .await;
+    
+    let client = create_mock_client(&server).await;
+    let manager = OrderManager::new(client).client_id_prefix("om-test");
+    let mut events = manager.subscribe();
+    
+    let id = manager.submit(OrderRequest::limit("EUR_USD", dec!(1000), dec!(1.08000))).await.unwrap();
+    assert_eq!(id, "om-test-1");
+    manager.amend(&id, OrderRequest::limit("EUR_USD", dec!(1000), dec!(1.08200))).await.unwrap();
+    assert_eq!(manager.order(&id).unwrap().status, OrderStatus::Pending);
+    
+    manager.sync().await.unwrap();
+    assert_eq!(manager.order(&id).unwrap().status, OrderStatus::Filled);
+    assert!(manager.open_orders().is_empty());
+    
+    assert!(matches!(events.recv().await.unwrap(), OrderEvent::Accepted { .. }));
+    match events.recv().await.unwrap() {
+        OrderEvent::Replaced { old_order_id, order_id, .. } => {
+            assert_eq!(old_order_id.as_u64(), 100);
+            assert_eq!(order_id.as_u64(), 102);
+        }
+        other => panic!("Expected Replaced, got {:?}", other),
+    }
+    assert!(matches!(events.recv().await.unwrap(), OrderEvent::Filled { .. }));
+    
+    // Only pending orders can be cancelled
+    assert!(manager.cancel(&id).await.is_err());
+    
+    place.assert_async().await;
+    replace.assert_async().await;
+    fills.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_order_manager_retry_finds_submitted_order() {
+    let mut server = Server::new_async().await;
+    
+    let place = server.mock("POST", "/v3/accounts/test_account_id/orders")
+        .with_status(503)
+        .expect(1)
+        .create_async()
+        .await;
+    let lookup = server.mock("GET", "/v3/accounts/test_account_id/orders/@om-test-1")
+        .with_status(200)
+        .with_body(r#"{
+            "order": {
+                "id": "200", "type": "LIMIT", "state": "PENDING",
+                "instrument": "EUR_USD", "units": "1000", "price": "1.08000",
+                "clientExtensions": {"id": "om-test-1"}
+            },
+            "lastTransactionID": "200"
+        }"#)
+        .expect(1)
+        .create_async()
+        .await;
+    let cancel = server.mock("PUT", "/v3/accounts/test_account_id/orders/200/cancel")
+        .with_status(200)
+        .with_body(r#"{
+            "orderCancelTransaction": {
+                "id": "201", "accountID": "test_account_id", "batchID": "201",
+                "time": "2024-01-01T12:01:00.000000000Z", "type": "ORDER_CANCEL",
+                "orderID": "200", "clientOrderID": "om-test-1", "reason": "CLIENT_REQUEST"
+            },
+            "relatedTransactionIDs": ["201"],
+            "lastTransactionID": "201"
+        }"#)
+        .expect(1)
+        .create_async()
+        .await;
+    
+    let client = create_mock_client(&server).await;
+    let manager = OrderManager::new(client)
+        .client_id_prefix("om-test")
+        .retry_delay(std::time::Duration::ZERO);
+    
+    // The POST failed, but the order exists: found by client ID, not resent
+    let id = manager.submit(OrderRequest::limit("EUR_USD", dec!(1000), dec!(1.08000))).await.unwrap();
+    assert_eq!(manager.order(&id).unwrap().order_id.unwrap().as_u64(), 200);
+    
+    manager.cancel(&id).await.unwrap();
+    assert_eq!(manager.order(&id).unwrap().status, OrderStatus::Cancelled);
+    
+    place.assert_async().await;
+    lookup.assert_async().await;
+    cancel.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_find_order_by_client_id() {
+    use oanda_connector::trading::Trading;
+
+    let mut server = Server::new_async().await;
+
+    let found = server
+        .mock(
+            "GET",
+            "/v3/accounts/test_account_id/orders/@dca-20250707T140000",
+        )
+        .with_status(200)
+        .with_body(
+            r#"{
+            "order": {
+                "id": "310", "type": "MARKET", "state": "FILLED",
+                "instrument": "XAU_USD", "units": "1",
+                "clientExtensions": {"id": "dca-20250707T140000"}
+            },
+            "lastTransactionID": "311"
+        }"#,
+        )
+        .create_async()
+        .await;
+    let missing = server
+        .mock(
+            "GET",
+            "/v3/accounts/test_account_id/orders/@dca-20250708T140000",
+        )
+        .with_status(404)
+        .with_body(
+            r#"{
+            "errorCode": "ORDER_DOESNT_EXIST",
+            "errorMessage": "The order specified does not exist",
+            "lastTransactionID": "311"
+        }"#,
+        )
+        .create_async()
+        .await;
+
+    let client = create_mock_client(&server).await;
+    let order = client.find_order("dca-20250707T140000").await.unwrap();
+    assert_eq!(order.map(|o| o.id.to_string()), Some("310".to_string()));
+    assert!(client
+        .find_order("dca-20250708T140000")
+        .await
+        .unwrap()
+        .is_none());
+
+    found.assert_async().await;
+    missing.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_trailing_stop_closes_trade() {
+    use oanda_connector::trailing_stop::TrailingStopEvent;
+    use oanda_connector::{Tick, TradeId, TrailDistance, TrailingStop, TrailingStopManager};
+    
+    let mut server = Server::new_async().await;
+    
+    let close = server.mock("PUT", "/v3/accounts/test_account_id/trades/42/close")
+        .match_body(Matcher::Json(serde_json::json!({"units": "ALL"})))
+        .with_status(200)
+        .with_body(r#"{
+            "orderFillTransaction": {
+                "id": "301", "accountID": "test_account_id", "batchID": "300",
+                "time": "2024-01-01T12:05:00.000000000Z", "type": "ORDER_FILL",
+                "orderID": "300", "instrument": "EUR_USD", "units": "-1000",
+                "price": "1.10100", "reason": "MARKET_ORDER_TRADE_CLOSE",
+                "pl": "1.0000", "financing": "0.0000", "commission": "0.0000",
+                "accountBalance": "100001.0000"
+            },
+            "relatedTransactionIDs": ["300", "301"],
+            "lastTransactionID": "301"
+        }"#)
+        .expect(1)
+        .create_async()
+        .await;
+    
+    let client = create_mock_client(&server).await;
+    let stops = TrailingStopManager::new(client);
+    stops.add(TrailingStop::new(TradeId::from(42), "EUR_USD", true, TrailDistance::Price(dec!(0.0020))));
+    let mut events = stops.subscribe();
+    
+    let tick = |bid| Tick {
+        instrument: "EUR_USD".to_string(),
+        timestamp: chrono::Utc::now(),
+        bid,
+        ask: bid + dec!(0.0002),
+        tradeable: true,
+        status: oanda_connector::PriceStatus::Tradeable,
+        closeout_bid: None,
+        closeout_ask: None,
+    };
+    assert!(stops.process_tick(&tick(dec!(1.1000))).await.is_empty());
+    assert!(stops.process_tick(&tick(dec!(1.1030))).await.is_empty());
+    let closed = stops.process_tick(&tick(dec!(1.1010))).await;
+    assert_eq!(closed, vec![TradeId::from(42)]);
+    assert!(stops.stops().is_empty());
+    
+    let mut last = None;
+    while let Ok(event) = events.try_recv() {
+        last = Some(event);
+    }
+    match last {
+        Some(TrailingStopEvent::Closed { trade_id, response }) => {
+            assert_eq!(trade_id, TradeId::from(42));
+            assert!(response.order_fill_transaction.is_some());
+        }
+        other => panic!("Expected Closed, got {:?}", other),
+    }
+    
+    close.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_trailing_stop_dropped_when_trade_is_gone() {
+    use oanda_connector::trailing_stop::TrailingStopEvent;
+    use oanda_connector::{Tick, TradeId, TrailDistance, TrailingStop, TrailingStopManager};
+
+    let mut server = Server::new_async().await;
+
+    let close = server
+        .mock("PUT", "/v3/accounts/test_account_id/trades/42/close")
+        .with_status(404)
+        .with_body(
+            r#"{
+            "errorCode": "TRADE_DOESNT_EXIST",
+            "errorMessage": "The Trade specified does not exist",
+            "lastTransactionID": "301"
+        }"#,
+        )
+        .expect(1)
+        .create_async()
+        .await;
+
+    let client = create_mock_client(&server).await;
+    let stops = TrailingStopManager::new(client);
+    stops.add(TrailingStop::new(
+        TradeId::from(42),
+        "EUR_USD",
+        true,
+        TrailDistance::Price(dec!(0.0020)),
+    ));
+    let mut events = stops.subscribe();
+
+    let tick = |bid| Tick {
+        instrument: "EUR_USD".to_string(),
+        timestamp: chrono::Utc::now(),
+        bid,
+        ask: bid + dec!(0.0002),
+        tradeable: true,
+        status: oanda_connector::PriceStatus::Tradeable,
+        closeout_bid: None,
+        closeout_ask: None,
+    };
+    assert!(stops.process_tick(&tick(dec!(1.1000))).await.is_empty());
+    assert!(stops.process_tick(&tick(dec!(1.0970))).await.is_empty());
+    assert!(stops.stops().is_empty());
+
+    // Further prices through the stop don't try to close the trade again
+    assert!(stops.process_tick(&tick(dec!(1.0960))).await.is_empty());
+
+    let mut last = None;
+    while let Ok(event) = events.try_recv() {
+        last = Some(event);
+    }
+    match last {
+        Some(TrailingStopEvent::CloseFailed {
+            trade_id,
+            error,
+            retrying,
+        }) => {
+            assert_eq!(trade_id, TradeId::from(42));
+            assert!(error.contains("404"), "{}", error);
+            assert!(!retrying);
+        }
+        other => panic!("Expected CloseFailed, got {:?}", other),
+    }
+
+    close.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_strategy_runner_wires_streams_and_orders() {
+    use oanda_connector::strategy::{Strategy, StrategyContext, StrategyRunner};
+    use oanda_connector::{Candle, Tick};
+    use std::sync::{Arc, Mutex};
+    
+    #[derive(Default)]
+    struct Recorder {
+        seen: Arc<Mutex<Vec<String>>>,
+        ordered: bool,
+    }
+    
+    impl Strategy for Recorder {
+        fn on_tick(&mut self, tick: &Tick, _ctx: &mut StrategyContext<'_>) {
+            self.seen.lock().unwrap().push(format!("tick {}", tick.bid));
+        }
+        
+        fn on_candle(&mut self, candle: &Candle, ctx: &mut StrategyContext<'_>) {
+            self.seen.lock().unwrap().push(format!("candle {}", candle.close));
+            if !self.ordered {
+                self.ordered = true;
+                ctx.submit(OrderRequest::market(&candle.instrument, dec!(1000)));
+            }
+        }
+        
+        fn on_transaction(&mut self, transaction: &Transaction, _ctx: &mut StrategyContext<'_>) {
+            self.seen.lock().unwrap().push(transaction.type_name().to_string());
+        }
+    }
+    
+    let mut server = Server::new_async().await;
+    
+    let _stream = server.mock("GET", "/v3/accounts/test_account_id/pricing/stream")
+        .match_query(Matcher::Any)
+        .with_status(200)
+        .with_body(concat!(
+            r#"{"type":"PRICE","instrument":"EUR_USD","time":"2024-01-01T12:00:10.000000000Z","bids":[{"price":"1.10000"}],"asks":[{"price":"1.10000"}]}"#, "\n",
+            r#"{"type":"PRICE","instrument":"EUR_USD","time":"2024-01-01T12:01:05.000000000Z","bids":[{"price":"1.10100"}],"asks":[{"price":"1.10100"}]}"#, "\n",
+        ))
+        .create_async()
+        .await;
+    let _summary = server.mock("GET", "/v3/accounts/test_account_id")
+        .with_status(200)
+        .with_body(r#"{
+            "account": {
+                "id": "test_account_id", "balance": "100000.0000", "NAV": "100000.0000",
+                "unrealizedPL": "0.0000", "pl": "0.0000", "marginUsed": "0.0000",
+                "marginAvailable": "100000.0000", "openTradeCount": 0,
+                "openPositionCount": 0, "currency": "USD"
+            },
+            "lastTransactionID": "100"
+        }"#)
+        .create_async()
+        .await;
+    let place = server.mock("POST", "/v3/accounts/test_account_id/orders")
+        .match_body(Matcher::PartialJsonString(
+            r#"{"order": {"type": "MARKET", "instrument": "EUR_USD", "units": "1000"}}"#.to_string(),
+        ))
+        .with_status(201)
+        .with_body(r#"{
+            "orderCreateTransaction": {
+                "id": "101", "accountID": "test_account_id", "batchID": "101",
+                "time": "2024-01-01T12:01:05.000000000Z", "type": "MARKET_ORDER",
+                "instrument": "EUR_USD", "units": "1000", "timeInForce": "FOK"
+            },
+            "relatedTransactionIDs": ["101"],
+            "lastTransactionID": "101"
+        }"#)
+        .expect(1)
+        .create_async()
+        .await;
+    let _transactions = server.mock("GET", "/v3/accounts/test_account_id/transactions/sinceid")
+        .match_query(Matcher::Any)
+        .with_status(200)
+        .with_body(r#"{
+            "transactions": [{
+                "id": "102", "accountID": "test_account_id", "batchID": "101",
+                "time": "2024-01-01T12:01:05.000000000Z", "type": "ORDER_FILL",
+                "orderID": "101", "instrument": "EUR_USD", "units": "1000",
+                "price": "1.10100", "reason": "MARKET_ORDER", "pl": "0.0000",
+                "financing": "0.0000", "commission": "0.0000",
+                "accountBalance": "100000.0000"
+            }],
+            "lastTransactionID": "102"
+        }"#)
+        .create_async()
+        .await;
+    
+    let client = create_mock_client(&server).await;
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let strategy = Recorder { seen: seen.clone(), ordered: false };
+    let task = StrategyRunner::new(client, strategy)
+        .instruments(vec!["EUR_USD".to_string()])
+        .granularity(Granularity::M1)
+        .transaction_interval(std::time::Duration::from_millis(20))
+        .reconnect_delay(std::time::Duration::from_millis(20))
+        .spawn();
+    
+    let deadline = tokio::time::Instant::now() + std::time::Duration::from_secs(5);
+    while !seen.lock().unwrap().iter().any(|s| s == "ORDER_FILL") {
+        assert!(tokio::time::Instant::now() < deadline, "no fill seen: {:?}", seen.lock().unwrap());
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    }
+    task.shutdown().await;
+    
+    let seen = seen.lock().unwrap().clone();
+    assert_eq!(seen[..3], ["tick 1.10000", "candle 1.10000", "tick 1.10100"]);
+    place.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_place_order_rejected() {
+    let mut server = Server::new_async().await;
+    
+    let mock = server.mock("POST", "/v3/accounts/test_account_id/orders")
+        .with_status(400)
+        .with_header("content-type", "application/json")
+        .with_body(r#"{
+            "orderRejectTransaction": {
+                "id": "6370", "accountID": "test_account_id", "batchID": "6370",
+                "time": "2024-01-01T12:00:00.000000000Z", "type": "MARKET_ORDER_REJECT",
+                "instrument": "EUR_USD", "units": "1000",
+                "stopLossOnFill": {"price": "1.2000"},
+                "rejectReason": "STOP_LOSS_ON_FILL_LOSS"
+            },
+            "relatedTransactionIDs": ["6370"],
+            "lastTransactionID": "6370",
+            "errorCode": "STOP_LOSS_ON_FILL_LOSS",
+            "errorMessage": "The Stop Loss on fill specified would cause an immediate loss"
+        }"#)
+        .create_async()
+        .await;
+    
+    let client = create_mock_client(&server).await;
+    let order = OrderRequest::market("EUR_USD", dec!(1000)).stop_loss(dec!(1.2000));
+    
+    match client.place_order(&order).await.map_err(oanda_connector::Error::into_inner) {
+        Err(oanda_connector::Error::OrderRejected { reason, transaction_id, .. }) => {
+            assert_eq!(reason, OrderRejectReason::StopLossOnFillLoss);
+            assert_eq!(transaction_id, Some(TransactionId::from(6370)));
+        }
+        other => panic!("expected OrderRejected, got {:?}", other),
+    }
+    
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_max_in_flight() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    
+    // Answers every request slowly, recording how many were outstanding at once
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let active = Arc::new(AtomicUsize::new(0));
+    let peak = Arc::new(AtomicUsize::new(0));
+    let (active_server, peak_server) = (active.clone(), peak.clone());
+    tokio::spawn(async move {
+        while let Ok((mut socket, _)) = listener.accept().await {
+            let (active, peak) = (active_server.clone(), peak_server.clone());
+            tokio::spawn(async move {
+                let mut buf = [0; 4096];
+                let _ = socket.read(&mut buf).await;
+                peak.fetch_max(active.fetch_add(1, Ordering::SeqCst) + 1, Ordering::SeqCst);
+                tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+                active.fetch_sub(1, Ordering::SeqCst);
+                let _ = socket
+                    .write_all(b"HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")
+                    .await;
+            });
+        }
+    });
+    
+    let client = OandaClient::new(
+        OandaConfig::builder()
+            .api_key("test_api_key")
+            .account_id("test_account_id")
+            .base_url(format!("http://{}", addr))
+            .retries(false)
+            .max_in_flight(2)
+            .build()
+            .unwrap(),
+    ).unwrap();
+    
+    let results = futures::future::join_all(
+        (0..6).map(|_| client.get_account_summary())
+    ).await;
+    
+    assert!(results.iter().all(|r| r.is_err()));
+    assert_eq!(peak.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn test_queue_timeout() {
+    let mut server = Server::new_async().await;
+    
+    let mock = server.mock("GET", "/v3/accounts/test_account_id")
+        .with_status(500)
+        .expect(1)
+        .create_async()
+        .await;
+    
+    let client = OandaClient::new(
+        OandaConfig::builder()
+            .api_key("test_api_key")
+            .account_id("test_account_id")
+            .base_url(server.url())
+            .retries(false)
+            .requests_per_second(1)
+            .burst(1)
+            .queue_timeout(std::time::Duration::from_millis(50))
+            .build()
+            .unwrap(),
+    ).unwrap();
+    
+    let first = client.get_account_summary().await.map_err(oanda_connector::Error::into_inner);
+    assert!(matches!(first, Err(oanda_connector::Error::ApiError { code: 500, .. })));
+    
+    // The next permit is a second away, well past the deadline
+    let started = std::time::Instant::now();
+    let second = client.get_account_summary().await.map_err(oanda_connector::Error::into_inner);
+    assert!(matches!(second, Err(oanda_connector::Error::Timeout(_))), "{:?}", second);
+    assert!(started.elapsed() < std::time::Duration::from_millis(500));
+    
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_middleware() {
+    use oanda_connector::middleware::{Middleware, Next};
+    use oanda_connector::{OandaClientBuilder, RequestContext};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    
+    struct Tag;
+    
+    #[async_trait::async_trait]
+    impl Middleware for Tag {
+        async fn handle(
+            &self,
+            mut request: reqwest::Request,
+            context: &RequestContext,
+            next: Next<'_>,
+        ) -> oanda_connector::Result<reqwest::Response> {
+            request.headers_mut().insert("X-Desk", "fx-1".parse().unwrap());
+            next.run(request, context).await
+        }
+    }
+    
+    struct Count(Arc<AtomicUsize>);
+    
+    #[async_trait::async_trait]
+    impl Middleware for Count {
+        async fn handle(
+            &self,
+            request: reqwest::Request,
+            context: &RequestContext,
+            next: Next<'_>,
+        ) -> oanda_connector::Result<reqwest::Response> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            if context.instrument.as_deref() == Some("BLOCKED") {
+                return Err(oanda_connector::Error::ConfigError("blocked by middleware".to_string()));
+            }
+            next.run(request, context).await
+        }
+    }
+    
+    let mut server = Server::new_async().await;
+    let mock = server.mock("GET", "/v3/accounts/test_account_id/pricing")
+        .match_query(Matcher::Any)
+        .match_header("X-Desk", "fx-1")
+        .with_status(200)
+        .with_body(r#"{
+            "prices": [{
+                "instrument": "EUR_USD",
+                "time": "2024-01-01T12:00:00.000000000Z",
+                "bids": [{"price": "1.10000"}],
+                "asks": [{"price": "1.10020"}]
+            }]
+        }"#)
+        .expect(1)
+        .create_async()
+        .await;
+    
+    let calls = Arc::new(AtomicUsize::new(0));
+    let client = OandaClientBuilder::new(OandaConfig {
+        base_url: Some(server.url()),
+        enable_retries: false,
+        ..OandaConfig::new("test_api_key".to_string(), "test_account_id".to_string(), true)
+    })
+        .middleware(Tag)
+        .middleware(Count(calls.clone()))
+        .build()
+        .unwrap();
+    
+    client.get_current_price("EUR_USD").await.unwrap();
+    let blocked = client.get_current_price("BLOCKED").await.map_err(oanda_connector::Error::into_inner);
+    assert!(matches!(blocked, Err(oanda_connector::Error::ConfigError(_))));
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+    
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_request_logger_passes_streams_through() {
+    use futures::StreamExt;
+    use oanda_connector::middleware::RequestLogger;
+    use oanda_connector::OandaClientBuilder;
+    use std::sync::Mutex;
+    
+    struct Capture(Mutex<Vec<String>>);
+    
+    impl log::Log for Capture {
+        fn enabled(&self, metadata: &log::Metadata) -> bool {
+            metadata.target().starts_with("oanda_connector")
+        }
+        
+        fn log(&self, record: &log::Record) {
+            if self.enabled(record.metadata()) {
+                self.0.lock().unwrap().push(record.args().to_string());
+            }
+        }
+        
+        fn flush(&self) {}
+    }
+    
+    static LOGGER: Capture = Capture(Mutex::new(Vec::new()));
+    log::set_logger(&LOGGER).unwrap();
+    log::set_max_level(log::LevelFilter::Debug);
+    
+    let mut server = Server::new_async().await;
+    // One tick, then the connection stays open like a live stream
+    let mock = server.mock("GET", "/v3/accounts/test_account_id/pricing/stream")
+        .match_query(Matcher::Any)
+        .with_status(200)
+        .with_chunked_body(|w| {
+            w.write_all(br#"{"type":"PRICE","instrument":"EUR_USD","time":"2024-01-01T12:00:00.000000000Z","bids":[{"price":"1.10000"}],"asks":[{"price":"1.10020"}]}"#)?;
+            w.write_all(b"\n")?;
+            w.flush()?;
+            std::thread::sleep(Duration::from_secs(3));
+            Ok(())
+        })
+        .create_async()
+        .await;
+    
+    let client = OandaClientBuilder::new(OandaConfig {
+        base_url: Some(server.url()),
+        stream_url: Some(server.url()),
+        enable_retries: false,
+        ..OandaConfig::new("test_api_key".to_string(), "test_account_id".to_string(), true)
+    })
+        .middleware(RequestLogger::new())
+        .build()
+        .unwrap();
+    
+    let instruments = vec!["EUR_USD".to_string()];
+    let first = tokio::time::timeout(Duration::from_secs(1), async {
+        let mut ticks = Box::pin(client.stream_prices(&instruments).await.unwrap());
+        ticks.next().await
+    })
+        .await
+        .expect("first tick should arrive before the stream ends");
+    assert_eq!(first.unwrap().unwrap().ask, dec!(1.10020));
+    
+    let logged = LOGGER.0.lock().unwrap().clone();
+    assert!(logged.iter().any(|line| line.starts_with("<-- 200 GET") && line.ends_with("(streaming)")), "{:?}", logged);
+    assert!(!logged.iter().any(|line| line.contains("test_api_key")), "{:?}", logged);
+    
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_compression_negotiation() {
+    let mut server = Server::new_async().await;
+    
+    let compressed = server.mock("GET", "/v3/accounts/test_account_id")
+        .match_header("Accept-Encoding", Matcher::Regex("gzip.*br|br.*gzip".to_string()))
+        .with_status(503)
+        .expect(1)
+        .create_async()
+        .await;
+    let client = create_mock_client(&server).await;
+    let _ = client.get_account_summary().await;
+    compressed.assert_async().await;
+    compressed.remove_async().await;
+    
+    let plain = server.mock("GET", "/v3/accounts/test_account_id")
+        .match_header("Accept-Encoding", Matcher::Missing)
+        .with_status(503)
+        .expect(1)
+        .create_async()
+        .await;
+    let client = OandaClient::new(OandaConfig {
+        base_url: Some(server.url()),
+        enable_retries: false,
+        compression: false,
+        ..OandaConfig::new("test_api_key".to_string(), "test_account_id".to_string(), true)
+    }).unwrap();
+    let _ = client.get_account_summary().await;
+    plain.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_flatten_all_cancels_and_closes() {
+    use oanda_connector::FlattenOptions;
+    
+    let mut server = Server::new_async().await;
+    
+    let pending = server.mock("GET", "/v3/accounts/test_account_id/pendingOrders")
+        .with_status(200)
+        .with_body(r#"{
+            "orders": [
+                {"id": "301", "type": "LIMIT", "state": "PENDING",
+                 "instrument": "EUR_USD", "units": "1000", "price": "1.05000"},
+                {"id": "302", "type": "STOP_LOSS", "state": "PENDING", "price": "1.09000"}
+            ],
+            "lastTransactionID": "310"
+        }"#)
+        .expect(1)
+        .create_async()
+        .await;
+    let positions = server.mock("GET", "/v3/accounts/test_account_id/openPositions")
+        .with_status(200)
+        .with_body(r#"{
+            "positions": [
+                {"instrument": "EUR_USD", "pl": "0.0000",
+                 "long": {"units": "10000", "pl": "0.0000"},
+                 "short": {"units": "0", "pl": "0.0000"}},
+                {"instrument": "USD_JPY", "pl": "0.0000",
+                 "long": {"units": "0", "pl": "0.0000"},
+                 "short": {"units": "-5000", "pl": "0.0000"}}
+            ],
+            "lastTransactionID": "310"
+        }"#)
+        .expect(1)
+        .create_async()
+        .await;
+    let cancel_limit = server.mock("PUT", "/v3/accounts/test_account_id/orders/301/cancel")
+        .with_status(200)
+        .with_body(r#"{
+            "orderCancelTransaction": {
+                "id": "311", "accountID": "test_account_id", "batchID": "311",
+                "time": "2024-01-01T12:00:00.000000000Z", "type": "ORDER_CANCEL",
+                "orderID": "301", "reason": "CLIENT_REQUEST"
+            },
+            "relatedTransactionIDs": ["311"],
+            "lastTransactionID": "311"
+        }"#)
+        .expect(1)
+        .create_async()
+        .await;
+    // Rejections are final and not retried
+    let cancel_stop = server.mock("PUT", "/v3/accounts/test_account_id/orders/302/cancel")
+        .with_status(404)
+        .with_body(r#"{
+            "orderCancelRejectTransaction": {
+                "id": "312", "accountID": "test_account_id", "batchID": "312",
+                "time": "2024-01-01T12:00:00.000000000Z", "type": "ORDER_CANCEL_REJECT",
+                "orderID": "302", "rejectReason": "ORDER_DOESNT_EXIST"
+            },
+            "errorMessage": "The order specified does not exist"
+        }"#)
+        .expect(1)
+        .create_async()
+        .await;
+    let close_long = server.mock("PUT", "/v3/accounts/test_account_id/positions/EUR_USD/close")
+        .match_body(Matcher::Json(serde_json::json!({"longUnits": "ALL"})))
+        .with_status(200)
+        .with_body(r#"{
+            "longOrderFillTransaction": {
+                "id": "314", "accountID": "test_account_id", "batchID": "313",
+                "time": "2024-01-01T12:00:01.000000000Z", "type": "ORDER_FILL",
+                "orderID": "313", "instrument": "EUR_USD", "units": "-10000",
+                "price": "1.10100", "reason": "MARKET_ORDER_POSITION_CLOSEOUT",
+                "pl": "10.0000", "financing": "0.0000", "commission": "0.0000",
+                "accountBalance": "100010.0000"
+            },
+            "relatedTransactionIDs": ["313", "314"],
+            "lastTransactionID": "314"
+        }"#)
+        .expect(1)
+        .create_async()
+        .await;
+    // Transient failures are retried up to max_attempts
+    let close_short = server.mock("PUT", "/v3/accounts/test_account_id/positions/USD_JPY/close")
+        .match_body(Matcher::Json(serde_json::json!({"shortUnits": "ALL"})))
+        .with_status(503)
+        .with_body(r#"{"errorMessage": "Service unavailable"}"#)
+        .expect(2)
+        .create_async()
+        .await;
+    
+    let client = create_mock_client(&server).await;
+    let options = FlattenOptions {
+        max_attempts: 2,
+        retry_delay: std::time::Duration::ZERO,
+        ..FlattenOptions::default()
+    };
+    let report = client.flatten_all_with(&options).await.unwrap();
+    
+    assert_eq!(report.cancelled.len(), 2);
+    assert!(report.cancelled[0].1.is_ok());
+    let rejected = report.cancelled[1].1.as_ref().unwrap_err();
+    assert!(matches!(
+        rejected.inner(),
+        oanda_connector::Error::OrderRejected { reason: OrderRejectReason::Other(r), .. } if r == "ORDER_DOESNT_EXIST"
+    ));
+    assert_eq!(report.closed.len(), 2);
+    let closed = report.closed[0].1.as_ref().unwrap();
+    assert!(closed.long_order_fill_transaction.is_some());
+    assert!(closed.short_order_fill_transaction.is_none());
+    
+    assert!(!report.is_complete());
+    let failed: Vec<String> = report.errors().into_iter().map(|(item, _)| item).collect();
+    assert_eq!(failed, vec!["302".to_string(), "USD_JPY".to_string()]);
+    
+    pending.assert_async().await;
+    positions.assert_async().await;
+    cancel_limit.assert_async().await;
+    cancel_stop.assert_async().await;
+    close_long.assert_async().await;
+    close_short.assert_async().await;
 }
\ No newline at end of file